@@ -0,0 +1,308 @@
+use reqwest::{RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use snafu::ResultExt;
+
+use geoengine_datatypes::primitives::{BoundingBox2D, TimeInterval};
+use geoengine_services::users::session::Session;
+use geoengine_services::users::user::{UserCredentials, UserId, UserRegistration};
+use geoengine_services::workflows::workflow::{Workflow, WorkflowId};
+
+use crate::error::{self, Error, Result};
+
+/// A typed client for the HTTP API of a Geo Engine instance.
+///
+/// Requests after `login` are authenticated with the token of the session.
+///
+/// # Examples
+///
+/// ```no_run
+/// use geoengine_client::Client;
+/// use geoengine_services::users::user::UserCredentials;
+///
+/// # async fn example() -> geoengine_client::error::Result<()> {
+/// let mut client = Client::new("http://localhost:3030");
+/// client
+///     .login(&UserCredentials {
+///         email: "foo@bar.de".to_string(),
+///         password: "secret123".to_string(),
+///     })
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    session: Option<Session>,
+}
+
+/// The parameters of a `GetMap` request
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapRequest {
+    /// The bounding box in the axis order of the WMS, i.e., latitude first for `EPSG:4326`
+    pub bbox: BoundingBox2D,
+    pub width: u32,
+    pub height: u32,
+    pub time: Option<TimeInterval>,
+    /// The styles, e.g., `stretch:auto` or `fill:#ff0000`, empty for the default style
+    pub styles: String,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            session: None,
+        }
+    }
+
+    /// The session of the last `login`, if any
+    pub fn current_session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+
+    pub async fn register_user(&self, user: &UserRegistration) -> Result<UserId> {
+        json(self.post("user/register").json(user)).await
+    }
+
+    /// Login and use the session for all further requests
+    pub async fn login(&mut self, credentials: &UserCredentials) -> Result<&Session> {
+        let session: Session = json(self.post("user/login").json(credentials)).await?;
+        Ok(self.session.get_or_insert(session))
+    }
+
+    pub async fn logout(&mut self) -> Result<()> {
+        send(self.authenticated(self.post("user/logout"))?).await?;
+        self.session = None;
+        Ok(())
+    }
+
+    /// Load the current state of the session from the server, e.g., its open project
+    pub async fn session(&self) -> Result<Session> {
+        json(self.authenticated(self.get("session"))?).await
+    }
+
+    /// Extend the validity of the session and use the refreshed session for all further requests
+    pub async fn refresh_session(&mut self) -> Result<&Session> {
+        let session: Session = json(self.authenticated(self.post("session/refresh"))?).await?;
+        self.session = Some(session);
+        self.current_session().ok_or(Error::NotLoggedIn)
+    }
+
+    pub async fn register_workflow(&self, workflow: &Workflow) -> Result<WorkflowId> {
+        json(self.post("workflow/register").json(workflow)).await
+    }
+
+    pub async fn load_workflow(&self, id: &WorkflowId) -> Result<Workflow> {
+        json(self.get(&format!("workflow/{}", id))).await
+    }
+
+    /// Render the workflow as a PNG image
+    pub async fn get_map(&self, workflow: &WorkflowId, request: &MapRequest) -> Result<Vec<u8>> {
+        let mut query = vec![
+            ("request", "GetMap".to_string()),
+            ("service", "WMS".to_string()),
+            ("version", "1.3.0".to_string()),
+            ("layers", workflow.to_string()),
+            ("bbox", bbox_parameter(request.bbox)),
+            ("width", request.width.to_string()),
+            ("height", request.height.to_string()),
+            ("crs", "EPSG:4326".to_string()),
+            ("styles", request.styles.clone()),
+            ("format", "image/png".to_string()),
+        ];
+        if let Some(time) = request.time {
+            query.push(("time", time_parameter(time)));
+        }
+
+        let response = send(self.get("wms").query(&query)).await?;
+        Ok(response.bytes().await.context(error::Http)?.to_vec())
+    }
+
+    /// Query the features of a vector workflow as GeoJSON
+    pub async fn get_feature(
+        &self,
+        workflow: &WorkflowId,
+        bbox: BoundingBox2D,
+        time: Option<TimeInterval>,
+    ) -> Result<serde_json::Value> {
+        let mut query = vec![
+            ("request", "GetFeature".to_string()),
+            ("service", "WFS".to_string()),
+            ("version", "2.0.0".to_string()),
+            ("typeNames", format!("registry:{}", workflow)),
+            ("bbox", bbox_parameter(bbox)),
+        ];
+        if let Some(time) = time {
+            query.push(("time", time_parameter(time)));
+        }
+
+        json(self.get("wfs").query(&query)).await
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.http.get(&format!("{}/{}", self.base_url, path))
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        self.http.post(&format!("{}/{}", self.base_url, path))
+    }
+
+    fn authenticated(&self, request: RequestBuilder) -> Result<RequestBuilder> {
+        let session = self.session.as_ref().ok_or(Error::NotLoggedIn)?;
+        Ok(request.header("authorization", session.token.to_string()))
+    }
+}
+
+/// Send the request and turn error responses into `Error::Server`
+async fn send(request: RequestBuilder) -> Result<Response> {
+    let response = request.send().await.context(error::Http)?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    // errors are serialized as JSON strings
+    let body = response.text().await.context(error::Http)?;
+    let message = serde_json::from_str::<String>(&body).unwrap_or(body);
+
+    Err(Error::Server { status, message })
+}
+
+async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+    send(request).await?.json().await.context(error::Http)
+}
+
+fn bbox_parameter(bbox: BoundingBox2D) -> String {
+    format!(
+        "{},{},{},{}",
+        bbox.lower_left().x,
+        bbox.lower_left().y,
+        bbox.upper_right().x,
+        bbox.upper_right().y
+    )
+}
+
+fn time_parameter(time: TimeInterval) -> String {
+    format!("{}/{}", time.start().as_rfc3339(), time.end().as_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geoengine_datatypes::primitives::Coordinate2D;
+    use geoengine_operators::engine::{TypedOperator, VectorOperator};
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+    use geoengine_services::handlers;
+    use geoengine_services::users::hashmap_userdb::HashMapUserDB;
+    use geoengine_services::workflows::registry::HashMapRegistry;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use warp::Filter;
+
+    /// Serve the user, workflow and WFS handlers on a random port
+    fn server() -> String {
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+
+        let filter = handlers::users::register_user_handler(user_db.clone())
+            .or(handlers::users::login_handler(user_db.clone()))
+            .or(handlers::users::logout_handler(user_db.clone()))
+            .or(handlers::users::session_handler(user_db.clone()))
+            .or(handlers::users::refresh_session_handler(user_db))
+            .or(handlers::workflows::register_workflow_handler(
+                workflow_registry.clone(),
+            ))
+            .or(handlers::workflows::load_workflow_handler(
+                workflow_registry.clone(),
+            ))
+            .or(handlers::wfs::wfs_handler(workflow_registry))
+            .recover(handlers::handle_rejection);
+
+        let (address, server) = warp::serve(filter).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        format!("http://{}/", address)
+    }
+
+    #[tokio::test]
+    async fn session() {
+        let mut client = Client::new(server());
+
+        assert!(matches!(client.session().await, Err(Error::NotLoggedIn)));
+
+        let user = client
+            .register_user(&UserRegistration {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+                real_name: "Foo Bar".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let credentials = UserCredentials {
+            email: "foo@bar.de".to_string(),
+            password: "secret123".to_string(),
+        };
+        assert_eq!(client.login(&credentials).await.unwrap().user, user);
+        assert_eq!(client.session().await.unwrap().user, user);
+        assert_eq!(client.refresh_session().await.unwrap().user, user);
+
+        client.logout().await.unwrap();
+        assert!(client.current_session().is_none());
+    }
+
+    #[tokio::test]
+    async fn invalid_registration() {
+        let client = Client::new(server());
+
+        let error = client
+            .register_user(&UserRegistration {
+                email: "foo".to_string(),
+                password: "secret123".to_string(),
+                real_name: "Foo Bar".to_string(),
+            })
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Server { status, .. } if status == 400));
+    }
+
+    #[tokio::test]
+    async fn workflow_features() {
+        let client = Client::new(server());
+
+        let workflow = Workflow {
+            operator: TypedOperator::Vector(
+                MockPointSource {
+                    params: MockPointSourceParams {
+                        points: vec![Coordinate2D::new(1., 2.)],
+                    },
+                }
+                .boxed(),
+            ),
+        };
+
+        let id = client.register_workflow(&workflow).await.unwrap();
+
+        let loaded = client.load_workflow(&id).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&workflow).unwrap()
+        );
+
+        let features = client
+            .get_feature(
+                &id,
+                BoundingBox2D::new((0., 0.).into(), (10., 10.).into()).unwrap(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(features["features"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,19 @@
+use snafu::Snafu;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub enum Error {
+    #[snafu(display("HttpError: {}", source))]
+    Http { source: reqwest::Error },
+
+    #[snafu(display("The server answered with {}: {}", status, message))]
+    Server {
+        status: reqwest::StatusCode,
+        message: String,
+    },
+
+    #[snafu(display("The client is not logged in"))]
+    NotLoggedIn,
+}
@@ -3,8 +3,9 @@ use geoengine_datatypes::collections::{
     BuilderProvider, GeoFeatureCollectionRowBuilder, MultiPointCollection,
 };
 use geoengine_datatypes::primitives::{
-    Coordinate2D, FeatureDataType, FeatureDataValue, TimeInterval,
+    Coordinate2D, FeatureData, FeatureDataType, FeatureDataValue, MultiPoint, TimeInterval,
 };
+use std::collections::HashMap;
 
 fn multi_point_collection_benchmarks(c: &mut Criterion) {
     let mut group = c.benchmark_group("MultiPointCollection");
@@ -19,7 +20,7 @@ fn multi_point_collection_benchmarks(c: &mut Criterion) {
                 builder
                     .push_time_interval(TimeInterval::new_unchecked(i, i + 1))
                     .unwrap();
-                builder.finish_row();
+                builder.finish_row().unwrap();
             }
             black_box(builder.build())
         })
@@ -42,7 +43,7 @@ fn multi_point_collection_benchmarks(c: &mut Criterion) {
                 builder
                     .push_data("number", FeatureDataValue::Number(i as f64))
                     .unwrap();
-                builder.finish_row();
+                builder.finish_row().unwrap();
             }
             black_box(builder.build())
         })
@@ -51,5 +52,44 @@ fn multi_point_collection_benchmarks(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, multi_point_collection_benchmarks);
+fn multi_point_collection_filter_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("MultiPointCollection Filter");
+
+    let len = 10_000;
+    let mut columns = HashMap::new();
+    columns.insert(
+        "number".to_string(),
+        FeatureData::Number((0..len).map(|i| i as f64).collect()),
+    );
+    let collection = MultiPointCollection::from_data(
+        MultiPoint::many((0..len).map(|i| vec![(i as f64, i as f64)]).collect()).unwrap(),
+        vec![TimeInterval::default(); len],
+        columns,
+    )
+    .unwrap();
+
+    group.bench_function("Mask 10000", |b| {
+        let mask: Vec<bool> = (0..len).map(|i| i % 2 == 0).collect();
+        b.iter(|| black_box(collection.filter(mask.clone()).unwrap()))
+    });
+
+    group.bench_function("Column Range 10000", |b| {
+        let ranges = [FeatureDataValue::Number(1000.)..FeatureDataValue::Number(5000.)];
+        b.iter(|| {
+            black_box(
+                collection
+                    .column_range_filter("number", &ranges, false)
+                    .unwrap(),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    multi_point_collection_benchmarks,
+    multi_point_collection_filter_benchmarks
+);
 criterion_main!(benches);
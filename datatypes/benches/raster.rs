@@ -0,0 +1,69 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use geoengine_datatypes::operations::image::{Colorizer, RgbaColor, ToPng};
+use geoengine_datatypes::primitives::TimeInterval;
+use geoengine_datatypes::raster::{Blit, GeoTransform, Raster2D};
+
+fn raster(size: usize, value: u8, geo_transform: GeoTransform) -> Raster2D<u8> {
+    Raster2D::new(
+        [size, size].into(),
+        vec![value; size * size],
+        None,
+        TimeInterval::default(),
+        geo_transform,
+    )
+    .unwrap()
+}
+
+fn blit_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Blit");
+
+    for &size in &[256, 1024] {
+        let target = raster(size, 0, GeoTransform::new((0., 0.).into(), 1., -1.));
+        // the source overlaps the lower right quarter of the target
+        let half = (size / 2) as f64;
+        let source = raster(size, 7, GeoTransform::new((half, -half).into(), 1., -1.));
+
+        group.bench_function(format!("u8 {0}x{0}", size), |b| {
+            b.iter_batched(
+                || (target.clone(), source.clone()),
+                |(mut target, source)| {
+                    target.blit(source).unwrap();
+                    black_box(target)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn png_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ToPng");
+
+    let colorizer = Colorizer::linear_gradient(
+        vec![
+            (0.0.into(), RgbaColor::new(0, 0, 0, 255)).into(),
+            (255.0.into(), RgbaColor::new(255, 255, 255, 255)).into(),
+        ],
+        RgbaColor::transparent(),
+        RgbaColor::pink(),
+    )
+    .unwrap();
+
+    let mut gradient = raster(600, 0, GeoTransform::default());
+    for (i, pixel) in gradient.data_container.iter_mut().enumerate() {
+        *pixel = (i % 256) as u8;
+    }
+
+    for &size in &[256, 512, 1024] {
+        group.bench_function(format!("linear gradient 600x600 to {0}x{0}", size), |b| {
+            b.iter(|| black_box(gradient.to_png(size, size, &colorizer).unwrap()))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, blit_benchmarks, png_benchmarks);
+criterion_main!(benches);
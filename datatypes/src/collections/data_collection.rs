@@ -28,15 +28,15 @@ mod tests {
         let mut builder = DataCollection::builder().finish_header();
 
         builder.push_time_interval(TimeInterval::default()).unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
         builder
             .push_time_interval(TimeInterval::new(0, 1).unwrap())
             .unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
         builder
             .push_time_interval(TimeInterval::new(2, 3).unwrap())
             .unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
 
         let collection = builder.build().unwrap();
 
@@ -87,7 +87,7 @@ mod tests {
         builder
             .push_data("a", FeatureDataValue::NullableDecimal(Some(42)))
             .unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
         builder
             .push_time_interval(TimeInterval::new(0, 1).unwrap())
             .unwrap();
@@ -97,14 +97,14 @@ mod tests {
         builder
             .push_data("a", FeatureDataValue::NullableDecimal(None))
             .unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
         builder
             .push_time_interval(TimeInterval::new(2, 3).unwrap())
             .unwrap();
         builder
             .push_data("a", FeatureDataValue::NullableDecimal(Some(1337)))
             .unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
 
         let collection = builder.build().unwrap();
 
@@ -138,4 +138,66 @@ mod tests {
             panic!("wrong type");
         }
     }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn missing_values() {
+        let mut builder = DataCollection::builder();
+        builder
+            .add_column("a".into(), FeatureDataType::NullableNumber)
+            .unwrap();
+        builder
+            .add_column("b".into(), FeatureDataType::Number)
+            .unwrap();
+        let mut builder = builder.finish_header();
+
+        builder.push_time_interval(TimeInterval::default()).unwrap();
+        builder
+            .push_data("a", FeatureDataValue::Number(4.2))
+            .unwrap();
+        builder
+            .push_data("b", FeatureDataValue::Number(1.))
+            .unwrap();
+        builder.finish_row().unwrap();
+
+        builder.push_time_interval(TimeInterval::default()).unwrap();
+        builder.push_null("a").unwrap();
+        builder.push_null("b").unwrap_err();
+        builder
+            .push_data("b", FeatureDataValue::Number(2.))
+            .unwrap();
+        builder.finish_row().unwrap();
+
+        // `a` is padded with a null
+        builder.push_time_interval(TimeInterval::default()).unwrap();
+        builder
+            .push_data("b", FeatureDataValue::Number(3.))
+            .unwrap();
+        builder.finish_row().unwrap();
+
+        let collection = builder.build().unwrap();
+
+        assert_eq!(collection.len(), 3);
+
+        if let FeatureDataRef::NullableNumber(a_column) = collection.data("a").unwrap() {
+            assert_eq!(a_column.as_ref()[0], 4.2);
+            assert_eq!(a_column.nulls(), vec![false, true, true]);
+        } else {
+            panic!("wrong type");
+        }
+    }
+
+    #[test]
+    fn missing_non_nullable_value() {
+        let mut builder = DataCollection::builder();
+        builder
+            .add_column("a".into(), FeatureDataType::Number)
+            .unwrap();
+        let mut builder = builder.finish_header();
+
+        builder.push_time_interval(TimeInterval::default()).unwrap();
+        builder.finish_row().unwrap();
+
+        assert!(builder.build().is_err());
+    }
 }
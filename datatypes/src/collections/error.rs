@@ -7,7 +7,7 @@ use crate::primitives::PrimitivesError;
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(crate)")]
 pub enum FeatureCollectionError {
-    #[snafu(display("Arrow internal error: {:?}", source))]
+    #[snafu(display("Arrow internal error"))]
     ArrowInternal {
         source: ArrowError,
     },
@@ -40,6 +40,19 @@ pub enum FeatureCollectionError {
         b: Vec<String>,
     },
 
+    UnmatchedCategoryLabels {
+        column: String,
+    },
+
+    CategoryWithoutLabel {
+        column: String,
+        code: u8,
+    },
+
+    MissingValue {
+        column: String,
+    },
+
     WrongDataType,
 }
 
@@ -6,20 +6,23 @@ use std::sync::Arc;
 
 use arrow::array::{
     as_primitive_array, as_string_array, Array, ArrayData, ArrayRef, BooleanArray, Float64Array,
-    ListArray, StructArray,
+    ListArray, StringArray, StructArray,
 };
-use arrow::datatypes::{DataType, Field, Float64Type, Int64Type};
+use arrow::datatypes::{DataType, Field, Float64Type, Int64Type, UInt8Type};
 use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
 use serde::{Deserialize, Serialize};
 use snafu::ensure;
 
+use crate::collections::spatial_index::SpatialIndexCache;
 use crate::collections::{error, IntoGeometryIterator};
 use crate::collections::{FeatureCollectionError, IntoGeometryOptionsIterator};
 use crate::json_map;
 use crate::primitives::{
-    CategoricalDataRef, DecimalDataRef, FeatureData, FeatureDataRef, FeatureDataType,
-    FeatureDataValue, Geometry, NullableCategoricalDataRef, NullableDecimalDataRef,
-    NullableNumberDataRef, NullableTextDataRef, NumberDataRef, TextDataRef, TimeInterval,
+    CategoricalDataRef, CategoryLookup, DecimalDataRef, FeatureData, FeatureDataRef,
+    FeatureDataType, FeatureDataValue, Geometry, NullableCategoricalDataRef, NullableDataRef,
+    NullableDecimalDataRef, NullableNumberDataRef, NullableTextDataRef, NumberDataRef, TextDataRef,
+    TimeInterval,
 };
 use crate::util::arrow::{downcast_array, ArrowTyped};
 use crate::util::helpers::SomeIter;
@@ -37,6 +40,13 @@ pub struct FeatureCollection<CollectionType> {
 
     #[serde(skip)]
     collection_type: PhantomData<CollectionType>,
+
+    /// labels of dictionary-encoded categorical columns
+    #[serde(default)]
+    categories: HashMap<String, CategoryLookup>,
+
+    #[serde(skip)]
+    pub(super) spatial_index: SpatialIndexCache,
 }
 
 impl<CollectionType> FeatureCollection<CollectionType> {
@@ -56,8 +66,32 @@ impl<CollectionType> FeatureCollection<CollectionType> {
             table,
             types,
             collection_type: Default::default(),
+            categories: Default::default(),
+            spatial_index: Default::default(),
         }
     }
+
+    /// Attach the labels of categorical columns of `other` that also exist in this collection
+    fn with_categories_of(mut self, other: &Self) -> Self {
+        self.categories = other
+            .categories
+            .iter()
+            .filter(|(column, _)| self.types.contains_key(*column))
+            .map(|(column, lookup)| (column.clone(), lookup.clone()))
+            .collect();
+        self
+    }
+
+    /// Returns the labels of the categorical column `column_name` if it has some
+    pub fn category_lookup(&self, column_name: &str) -> Option<&CategoryLookup> {
+        self.categories.get(column_name)
+    }
+
+    /// Returns the columns of this collection, including the geometry and time columns,
+    /// as an Arrow `RecordBatch`, e.g., for sending them to Arrow clients without conversion
+    pub fn to_record_batch(&self) -> RecordBatch {
+        RecordBatch::from(&self.table)
+    }
 }
 
 impl<CollectionType> FeatureCollection<CollectionType>
@@ -446,7 +480,44 @@ where
         Ok(Self::new_from_internals(
             struct_array_from_data(columns, column_values, self.table.len()),
             types,
-        ))
+        )
+        .with_categories_of(self))
+    }
+
+    /// Creates a copy of the collection that labels the categories of the column `column_name`
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the column does not exist, is not categorical or contains codes
+    /// that have no label in the `lookup`
+    ///
+    pub fn with_category_lookup(&self, column_name: &str, lookup: CategoryLookup) -> Result<Self> {
+        let codes: Vec<u8> = match self.data(column_name)? {
+            FeatureDataRef::Categorical(data) => data.as_ref().to_vec(),
+            FeatureDataRef::NullableCategorical(data) => data
+                .as_ref()
+                .iter()
+                .zip(data.nulls())
+                .filter(|(_, is_null)| !is_null)
+                .map(|(&code, _)| code)
+                .collect(),
+            _ => return Err(error::FeatureCollectionError::WrongDataType.into()),
+        };
+
+        if let Some(&code) = codes.iter().find(|&&code| lookup.label(code).is_none()) {
+            return Err(error::FeatureCollectionError::CategoryWithoutLabel {
+                column: column_name.to_string(),
+                code,
+            }
+            .into());
+        }
+
+        let mut collection = self.clone();
+        collection
+            .categories
+            .insert(column_name.to_string(), lookup);
+
+        Ok(collection)
     }
 
     /// Removes a column and returns an updated collection
@@ -547,7 +618,8 @@ where
         Ok(Self::new_from_internals(
             struct_array_from_data(columns, column_values, self.table.len()),
             types,
-        ))
+        )
+        .with_categories_of(self))
     }
 
     /// Filters the feature collection by copying the data into a new feature collection
@@ -598,10 +670,10 @@ where
             ));
         }
 
-        Ok(Self::new_from_internals(
-            filtered_data.into(),
-            self.types.clone(),
-        ))
+        Ok(
+            Self::new_from_internals(filtered_data.into(), self.types.clone())
+                .with_categories_of(self),
+        )
     }
 
     /// Filter a column by one or more ranges.
@@ -615,11 +687,12 @@ where
     where
         R: RangeBounds<FeatureDataValue>,
     {
-        let column_type = self.types.get(column);
+        let column_name = column;
+        let column_type = self.types.get(column_name);
         ensure!(
             column_type.is_some(),
             error::ColumnDoesNotExist {
-                name: column.to_string()
+                name: column_name.to_string()
             }
         );
 
@@ -666,7 +739,32 @@ where
                 )?;
             }
             FeatureDataType::Categorical | FeatureDataType::NullableCategorical => {
-                return Err(error::FeatureCollectionError::WrongDataType.into());
+                // filter on the labels of the categories
+                let lookup = self
+                    .categories
+                    .get(column_name)
+                    .ok_or(error::FeatureCollectionError::WrongDataType)?;
+                let codes = as_primitive_array::<UInt8Type>(column);
+                let labels: StringArray = (0..codes.len())
+                    .map(|i| {
+                        if codes.is_null(i) {
+                            None
+                        } else {
+                            lookup.label(codes.value(i))
+                        }
+                    })
+                    .collect::<Vec<Option<&str>>>()
+                    .into();
+
+                apply_filters(
+                    &labels,
+                    &mut filter_array,
+                    ranges,
+                    arrow::compute::gt_eq_utf8_scalar,
+                    arrow::compute::gt_utf8_scalar,
+                    arrow::compute::lt_eq_utf8_scalar,
+                    arrow::compute::lt_utf8_scalar,
+                )?;
             }
         }
 
@@ -707,6 +805,26 @@ where
             }
         );
 
+        for (column, lookup) in &self.categories {
+            ensure!(
+                other.categories.get(column) == Some(lookup),
+                error::UnmatchedCategoryLabels {
+                    column: column.clone()
+                }
+            );
+        }
+        ensure!(
+            self.categories.len() == other.categories.len(),
+            error::UnmatchedCategoryLabels {
+                column: other
+                    .categories
+                    .keys()
+                    .find(|column| !self.categories.contains_key(*column))
+                    .cloned()
+                    .unwrap_or_default()
+            }
+        );
+
         let table_data = self.table.data();
         let columns = if let DataType::Struct(columns) = table_data.data_type() {
             columns
@@ -739,10 +857,7 @@ where
             ));
         }
 
-        Ok(Self::new_from_internals(
-            new_data.into(),
-            self.types.clone(),
-        ))
+        Ok(Self::new_from_internals(new_data.into(), self.types.clone()).with_categories_of(self))
     }
 
     /// Serialize the feature collection to a geo json string
@@ -755,12 +870,21 @@ where
             .collect::<Vec<_>>();
 
         for column_name in self.types.keys() {
+            let lookup = self.categories.get(column_name);
+
             for (json_value, map) in self
                 .data(column_name)
                 .expect("must exist since it's in `types`")
                 .json_values()
                 .zip(property_maps.as_mut_slice())
             {
+                let json_value = match (lookup, json_value.as_u64()) {
+                    (Some(lookup), Some(code)) => lookup
+                        .label(code as u8)
+                        .map_or(json_value, |label| label.into()),
+                    _ => json_value,
+                };
+
                 map.insert(column_name.clone(), json_value);
             }
         }
@@ -813,6 +937,8 @@ impl<CollectionType> Clone for FeatureCollection<CollectionType> {
             table: StructArray::from(self.table.data()),
             types: self.types.clone(),
             collection_type: Default::default(),
+            categories: self.categories.clone(),
+            spatial_index: self.spatial_index.clone(),
         }
     }
 }
@@ -860,7 +986,7 @@ impl<CollectionType> PartialEq for FeatureCollection<CollectionType> {
             true
         }
 
-        if self.types != other.types {
+        if self.types != other.types || self.categories != other.categories {
             return false;
         }
 
@@ -1036,7 +1162,7 @@ pub fn get_array_memory_size(data: &ArrayData) -> usize {
 mod struct_serde {
     use super::*;
 
-    use arrow::record_batch::{RecordBatch, RecordBatchReader};
+    use arrow::record_batch::RecordBatchReader;
     use serde::de::{SeqAccess, Visitor};
     use serde::ser::Error;
     use serde::{Deserializer, Serializer};
@@ -1177,4 +1303,26 @@ mod tests {
 
         // TODO: rely on numbers once the arrow library provides this feature
     }
+
+    #[test]
+    fn to_record_batch() {
+        let mut data = HashMap::new();
+        data.insert("foo".to_string(), FeatureData::Number(vec![1., 2.]));
+
+        let collection = FeatureCollection::<NoGeometry>::from_data(
+            vec![],
+            vec![TimeInterval::new(0, 1).unwrap(); 2],
+            data,
+        )
+        .unwrap();
+
+        let batch = collection.to_record_batch();
+
+        assert_eq!(batch.num_rows(), 2);
+        assert!(batch.schema().field_with_name("foo").is_ok());
+        assert!(batch
+            .schema()
+            .field_with_name(FeatureCollection::<NoGeometry>::TIME_COLUMN_NAME)
+            .is_ok());
+    }
 }
@@ -132,7 +132,8 @@ where
         Ok(())
     }
 
-    /// Add data to the builder
+    /// Add data to the builder.
+    /// Non-nullable values can be added to the corresponding nullable columns.
     ///
     /// # Errors
     ///
@@ -151,9 +152,13 @@ where
 
         // check that data types match
         // TODO: think of cheaper call for checking data type match
-        let data_type_variant = mem::discriminant(&FeatureDataType::from(&data));
+        let data_type = FeatureDataType::from(&data);
         match self.types.get(column) {
-            Some(data_type) if data_type_variant != mem::discriminant(data_type) => {
+            Some(column_type)
+                if mem::discriminant(&data_type) != mem::discriminant(column_type)
+                    && !(column_type.nullable()
+                        && column_type.arrow_data_type() == data_type.arrow_data_type()) =>
+            {
                 return Err(FeatureCollectionError::WrongDataType.into());
             }
             None => {
@@ -209,9 +214,57 @@ where
         Ok(())
     }
 
-    /// Indicate a finished row
-    pub fn finish_row(&mut self) {
+    /// Add a missing value to a nullable column
+    ///
+    /// # Errors
+    ///
+    /// This call fails if the column does not exist or is not nullable
+    ///
+    pub fn push_null(&mut self, column: &str) -> Result<()> {
+        let data_type =
+            *self
+                .types
+                .get(column)
+                .ok_or_else(|| FeatureCollectionError::ColumnDoesNotExist {
+                    name: column.to_string(),
+                })?;
+
+        ensure!(
+            data_type.nullable(),
+            error::MissingValue {
+                column: column.to_string(),
+            }
+        );
+
+        let data_builder = self
+            .builders
+            .get_mut(column)
+            .expect("builders and types must contain the same columns");
+
+        append_null(data_type, data_builder.as_mut())
+    }
+
+    /// Indicate a finished row.
+    ///
+    /// Nullable columns without a value for this row are filled with nulls, so that missing
+    /// attribute values never turn into zeros.
+    /// Missing values of non-nullable columns let `build()` fail.
+    ///
+    /// # Errors
+    ///
+    /// This call fails on internal errors of the builder
+    ///
+    pub fn finish_row(&mut self) -> Result<()> {
+        for (column, data_builder) in &mut self.builders {
+            let data_type = self.types[column];
+            if data_type.nullable() && data_builder.len() == self.rows {
+                append_null(data_type, data_builder.as_mut())?;
+            }
+        }
+
         self.rows += 1;
+
+        Ok(())
     }
 
     /// Return the number of finished rows
@@ -298,6 +351,30 @@ where
     }
 }
 
+/// Append a null value to a column builder of type `data_type`
+fn append_null(data_type: FeatureDataType, data_builder: &mut dyn ArrayBuilder) -> Result<()> {
+    match data_type {
+        FeatureDataType::Number | FeatureDataType::NullableNumber => {
+            let number_builder: &mut Float64Builder = downcast_mut_array(data_builder);
+            number_builder.append_null()?;
+        }
+        FeatureDataType::Text | FeatureDataType::NullableText => {
+            let string_builder: &mut StringBuilder = downcast_mut_array(data_builder);
+            string_builder.append_null()?;
+        }
+        FeatureDataType::Decimal | FeatureDataType::NullableDecimal => {
+            let decimal_builder: &mut Int64Builder = downcast_mut_array(data_builder);
+            decimal_builder.append_null()?;
+        }
+        FeatureDataType::Categorical | FeatureDataType::NullableCategorical => {
+            let categorical_builder: &mut UInt8Builder = downcast_mut_array(data_builder);
+            categorical_builder.append_null()?;
+        }
+    }
+
+    Ok(())
+}
+
 /// By implementing `Default` ourselves we omit `CollectionType` implementing `Default`
 impl<CollectionType> Default for FeatureCollectionBuilder<CollectionType>
 where
@@ -10,6 +10,7 @@ mod data_collection;
 mod multi_line_string_collection;
 mod multi_point_collection;
 mod multi_polygon_collection;
+mod spatial_index;
 
 pub(crate) use error::FeatureCollectionError;
 pub use feature_collection::FeatureCollection;
@@ -24,3 +25,4 @@ pub use data_types::VectorDataType;
 pub use multi_line_string_collection::MultiLineStringCollection;
 pub use multi_point_collection::MultiPointCollection;
 pub use multi_polygon_collection::MultiPolygonCollection;
+pub use spatial_index::{FeatureSpatialIndex, SpatiallyIndexed};
@@ -147,7 +147,7 @@ mod tests {
         for _ in 0..2 {
             builder.push_time_interval(TimeInterval::default()).unwrap();
 
-            builder.finish_row();
+            builder.finish_row().unwrap();
         }
 
         let collection = builder.build().unwrap();
@@ -194,7 +194,7 @@ mod tests {
         for _ in 0..2 {
             builder.push_time_interval(TimeInterval::default()).unwrap();
 
-            builder.finish_row();
+            builder.finish_row().unwrap();
         }
 
         let collection = builder.build().unwrap();
@@ -257,7 +257,7 @@ mod tests {
         for _ in 0..3 {
             builder.push_time_interval(TimeInterval::default()).unwrap();
 
-            builder.finish_row();
+            builder.finish_row().unwrap();
         }
 
         let collection = builder.build().unwrap();
@@ -297,7 +297,7 @@ mod tests {
             )
             .unwrap();
         builder.push_time_interval(TimeInterval::default()).unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
 
         let collection_a = builder.build().unwrap();
 
@@ -313,7 +313,7 @@ mod tests {
             )
             .unwrap();
         builder.push_time_interval(TimeInterval::default()).unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
 
         let collection_b = builder.build().unwrap();
 
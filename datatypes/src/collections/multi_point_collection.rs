@@ -106,8 +106,8 @@ mod tests {
 
     use crate::collections::BuilderProvider;
     use crate::primitives::{
-        FeatureData, FeatureDataRef, FeatureDataType, FeatureDataValue, MultiPointAccess,
-        NullableDataRef, TimeInterval,
+        CategoryLookup, FeatureData, FeatureDataRef, FeatureDataType, FeatureDataValue,
+        MultiPointAccess, NullableDataRef, TimeInterval,
     };
     use serde_json::{from_str, json};
     use std::collections::HashMap;
@@ -206,7 +206,7 @@ mod tests {
         builder
             .push_data("foo", FeatureDataValue::Number(0.))
             .unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
 
         builder
             .push_geometry(Coordinate2D::new(1., 1.).into())
@@ -217,7 +217,7 @@ mod tests {
         builder
             .push_data("foo", FeatureDataValue::Number(1.))
             .unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
 
         let collection = builder.build().unwrap();
 
@@ -258,7 +258,7 @@ mod tests {
             builder
                 .push_data("foo", FeatureDataValue::Number(0.))
                 .unwrap();
-            builder.finish_row();
+            builder.finish_row().unwrap();
 
             builder
                 .push_geometry(Coordinate2D::new(1., 1.).into())
@@ -269,7 +269,7 @@ mod tests {
             builder
                 .push_data("foo", FeatureDataValue::Number(1.))
                 .unwrap();
-            builder.finish_row();
+            builder.finish_row().unwrap();
 
             builder.build().unwrap()
         };
@@ -416,7 +416,7 @@ mod tests {
                     FeatureDataValue::NullableText(Some("one".to_string())),
                 )
                 .unwrap();
-            builder.finish_row();
+            builder.finish_row().unwrap();
 
             builder
                 .push_geometry(MultiPoint::new(vec![(1., 1.).into(), (2., 2.).into()]).unwrap())
@@ -430,7 +430,7 @@ mod tests {
             builder
                 .push_data("bar", FeatureDataValue::NullableText(None))
                 .unwrap();
-            builder.finish_row();
+            builder.finish_row().unwrap();
 
             builder
                 .push_geometry(Coordinate2D::new(3., 3.).into())
@@ -447,7 +447,7 @@ mod tests {
                     FeatureDataValue::NullableText(Some("three".to_string())),
                 )
                 .unwrap();
-            builder.finish_row();
+            builder.finish_row().unwrap();
 
             builder.build().unwrap()
         };
@@ -580,7 +580,7 @@ mod tests {
             builder
                 .push_data("number", FeatureDataValue::Number(0.))
                 .unwrap();
-            builder.finish_row();
+            builder.finish_row().unwrap();
             builder
                 .push_geometry(Coordinate2D::new(1., 1.).into())
                 .unwrap();
@@ -590,7 +590,7 @@ mod tests {
             builder
                 .push_data("number", FeatureDataValue::Number(1.))
                 .unwrap();
-            builder.finish_row();
+            builder.finish_row().unwrap();
 
             assert_eq!(builder.len(), 2);
 
@@ -621,7 +621,7 @@ mod tests {
             builder
                 .push_data("number", FeatureDataValue::Number(f64::NAN))
                 .unwrap();
-            builder.finish_row();
+            builder.finish_row().unwrap();
 
             assert!(!builder.is_empty());
 
@@ -649,7 +649,7 @@ mod tests {
             builder
                 .push_data("number", FeatureDataValue::NullableNumber(None))
                 .unwrap();
-            builder.finish_row();
+            builder.finish_row().unwrap();
 
             builder.build().unwrap()
         };
@@ -789,6 +789,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn categorical_labels() {
+        let (lookup, codes) =
+            CategoryLookup::encode(&["water", "forest", "urban", "forest"]).unwrap();
+
+        let collection = MultiPointCollection::from_data(
+            MultiPoint::many(vec![(0.0, 0.1), (1.0, 1.1), (2.0, 2.1), (3.0, 3.1)]).unwrap(),
+            vec![TimeInterval::new_unchecked(0, 1); 4],
+            [("class".to_string(), FeatureData::Categorical(codes))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_category_lookup("class", lookup.clone())
+        .unwrap();
+
+        let filtered =
+            collection
+                .column_range_filter(
+                    "class",
+                    &[FeatureDataValue::Text("forest".into())
+                        ..=FeatureDataValue::Text("urban".into())],
+                    false,
+                )
+                .unwrap();
+        assert_eq!(
+            filtered,
+            collection.filter(vec![false, true, true, true]).unwrap()
+        );
+        assert_eq!(filtered.category_lookup("class"), Some(&lookup));
+
+        let geo_json: serde_json::Value = serde_json::from_str(&collection.to_geo_json()).unwrap();
+        assert_eq!(
+            geo_json["features"][0]["properties"]["class"],
+            serde_json::json!("water")
+        );
+
+        assert!(collection.append(&collection).is_ok());
+        assert!(collection
+            .append(
+                &collection
+                    .remove_column("class")
+                    .unwrap()
+                    .add_column("class", FeatureData::Categorical(vec![0, 1, 2, 1]))
+                    .unwrap()
+            )
+            .is_err());
+
+        assert!(collection
+            .with_category_lookup("class", CategoryLookup::new(vec!["water".into()]).unwrap())
+            .is_err());
+    }
+
     #[test]
     fn range_filter_null() {
         let collection = MultiPointCollection::from_data(
@@ -169,7 +169,7 @@ mod tests {
         for _ in 0..2 {
             builder.push_time_interval(TimeInterval::default()).unwrap();
 
-            builder.finish_row();
+            builder.finish_row().unwrap();
         }
 
         let collection = builder.build().unwrap();
@@ -253,7 +253,7 @@ mod tests {
         for _ in 0..2 {
             builder.push_time_interval(TimeInterval::default()).unwrap();
 
-            builder.finish_row();
+            builder.finish_row().unwrap();
         }
 
         let collection = builder.build().unwrap();
@@ -367,7 +367,7 @@ mod tests {
         for _ in 0..3 {
             builder.push_time_interval(TimeInterval::default()).unwrap();
 
-            builder.finish_row();
+            builder.finish_row().unwrap();
         }
 
         let collection = builder.build().unwrap();
@@ -417,7 +417,7 @@ mod tests {
             )
             .unwrap();
         builder.push_time_interval(TimeInterval::default()).unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
 
         let collection_a = builder.build().unwrap();
 
@@ -445,7 +445,7 @@ mod tests {
             )
             .unwrap();
         builder.push_time_interval(TimeInterval::default()).unwrap();
-        builder.finish_row();
+        builder.finish_row().unwrap();
 
         let collection_b = builder.build().unwrap();
 
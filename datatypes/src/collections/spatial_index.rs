@@ -0,0 +1,330 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use crate::collections::{
+    IntoGeometryIterator, MultiLineStringCollection, MultiPointCollection, MultiPolygonCollection,
+};
+use crate::primitives::{
+    BoundingBox2D, Coordinate2D, MultiLineStringAccess, MultiPointAccess, MultiPolygonAccess,
+};
+
+/// The maximum number of entries of an R-tree node
+const NODE_CAPACITY: usize = 16;
+
+/// An R-tree over the bounding boxes of the features of a collection.
+///
+/// The tree is bulk loaded with the sort-tile-recursive algorithm and returns the indices of the
+/// features whose bounding boxes match a query. These are candidates that still have to be
+/// tested against the exact geometries.
+#[derive(Debug)]
+pub struct FeatureSpatialIndex {
+    /// the features' bounding boxes and indices, in the order of the leaf nodes
+    entries: Vec<(BoundingBox2D, usize)>,
+    /// the levels of the tree, from the leaf nodes up to the root level
+    levels: Vec<Vec<Node>>,
+}
+
+#[derive(Debug)]
+struct Node {
+    bounds: BoundingBox2D,
+    /// range of the children on the level below, or of the entries for leaf nodes
+    children: Range<usize>,
+}
+
+impl FeatureSpatialIndex {
+    /// Build an index over the bounding boxes of features.
+    /// Features without a bounding box, e.g., empty geometries, are never returned by queries.
+    pub fn new<I>(feature_bounds: I) -> Self
+    where
+        I: IntoIterator<Item = Option<BoundingBox2D>>,
+    {
+        let mut entries: Vec<(BoundingBox2D, usize)> = feature_bounds
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, bounds)| bounds.map(|bounds| (bounds, index)))
+            .collect();
+
+        sort_tile_recursive(&mut entries);
+
+        let mut levels = Vec::new();
+        let mut level = pack(&entries);
+
+        while level.len() > 1 {
+            let mut nodes: Vec<(BoundingBox2D, Range<usize>)> = level
+                .iter()
+                .map(|node| (node.bounds, node.children.clone()))
+                .collect();
+            sort_tile_recursive(&mut nodes);
+
+            let parents = pack(&nodes);
+            levels.push(
+                nodes
+                    .into_iter()
+                    .map(|(bounds, children)| Node { bounds, children })
+                    .collect(),
+            );
+            level = parents;
+        }
+        levels.push(level);
+
+        Self { entries, levels }
+    }
+
+    /// The number of indexed features
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The indices of all features whose bounding boxes intersect `bbox`, in ascending order
+    pub fn features_intersecting(&self, bbox: &BoundingBox2D) -> Vec<usize> {
+        self.search(|bounds| bounds.intersects_bbox(bbox))
+    }
+
+    /// The indices of all features whose bounding boxes contain `coordinate`, in ascending order
+    pub fn features_containing(&self, coordinate: &Coordinate2D) -> Vec<usize> {
+        self.search(|bounds| bounds.contains_coordinate(coordinate))
+    }
+
+    fn search<F>(&self, matches: F) -> Vec<usize>
+    where
+        F: Fn(&BoundingBox2D) -> bool,
+    {
+        let mut result = Vec::new();
+
+        let root_level = self.levels.len() - 1;
+        let mut stack: Vec<(usize, usize)> = (0..self.levels[root_level].len())
+            .map(|node| (root_level, node))
+            .collect();
+
+        while let Some((level, node)) = stack.pop() {
+            let node = &self.levels[level][node];
+            if !matches(&node.bounds) {
+                continue;
+            }
+
+            if level == 0 {
+                result.extend(
+                    self.entries[node.children.clone()]
+                        .iter()
+                        .filter(|(bounds, _)| matches(bounds))
+                        .map(|&(_, feature)| feature),
+                );
+            } else {
+                stack.extend(node.children.clone().map(|child| (level - 1, child)));
+            }
+        }
+
+        result.sort_unstable();
+        result
+    }
+}
+
+/// Order the items such that consecutive runs of `NODE_CAPACITY` items are spatially close
+fn sort_tile_recursive<T>(items: &mut [(BoundingBox2D, T)]) {
+    let center = |bounds: &BoundingBox2D| {
+        Coordinate2D::new(
+            (bounds.lower_left().x + bounds.upper_right().x) / 2.,
+            (bounds.lower_left().y + bounds.upper_right().y) / 2.,
+        )
+    };
+
+    let number_of_nodes = (items.len() + NODE_CAPACITY - 1) / NODE_CAPACITY;
+    let slices = (number_of_nodes as f64).sqrt().ceil() as usize;
+    let slice_size = (slices * NODE_CAPACITY).max(1);
+
+    items.sort_by(|(a, _), (b, _)| {
+        center(a)
+            .x
+            .partial_cmp(&center(b).x)
+            .unwrap_or(Ordering::Equal)
+    });
+    for slice in items.chunks_mut(slice_size) {
+        slice.sort_by(|(a, _), (b, _)| {
+            center(a)
+                .y
+                .partial_cmp(&center(b).y)
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+}
+
+/// Group consecutive runs of items into nodes
+fn pack<T>(items: &[(BoundingBox2D, T)]) -> Vec<Node> {
+    items
+        .chunks(NODE_CAPACITY)
+        .enumerate()
+        .map(|(chunk, children)| {
+            let start = chunk * NODE_CAPACITY;
+            let bounds = children
+                .iter()
+                .skip(1)
+                .fold(children[0].0, |bounds, (child, _)| bounds.union(child));
+
+            Node {
+                bounds,
+                children: start..start + children.len(),
+            }
+        })
+        .collect()
+}
+
+/// A lazily built spatial index that is shared between clones of a collection
+#[derive(Clone, Default)]
+pub(super) struct SpatialIndexCache(Arc<Mutex<Option<Arc<FeatureSpatialIndex>>>>);
+
+impl SpatialIndexCache {
+    fn get_or_build<F>(&self, build: F) -> Arc<FeatureSpatialIndex>
+    where
+        F: FnOnce() -> FeatureSpatialIndex,
+    {
+        let mut index = self
+            .0
+            .lock()
+            .expect("spatial index lock must not be poisoned");
+
+        index.get_or_insert_with(|| Arc::new(build())).clone()
+    }
+}
+
+impl fmt::Debug for SpatialIndexCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let built = self.0.lock().map_or(false, |index| index.is_some());
+
+        f.debug_struct("SpatialIndexCache")
+            .field("built", &built)
+            .finish()
+    }
+}
+
+/// Collections that provide a spatial index over their features.
+///
+/// The index is built on first use and reused by all subsequent calls, so operators that test
+/// many locations against the same chunk avoid scanning all of its features each time.
+pub trait SpatiallyIndexed {
+    fn spatial_index(&self) -> Arc<FeatureSpatialIndex>;
+}
+
+impl SpatiallyIndexed for MultiPointCollection {
+    fn spatial_index(&self) -> Arc<FeatureSpatialIndex> {
+        self.spatial_index.get_or_build(|| {
+            FeatureSpatialIndex::new(
+                self.geometries()
+                    .map(|multi_point| BoundingBox2D::from_coordinates(multi_point.points())),
+            )
+        })
+    }
+}
+
+impl SpatiallyIndexed for MultiLineStringCollection {
+    fn spatial_index(&self) -> Arc<FeatureSpatialIndex> {
+        self.spatial_index.get_or_build(|| {
+            FeatureSpatialIndex::new(self.geometries().map(|multi_line_string| {
+                BoundingBox2D::from_coordinates(
+                    multi_line_string
+                        .lines()
+                        .iter()
+                        .flat_map(|line| line.as_ref().iter()),
+                )
+            }))
+        })
+    }
+}
+
+impl SpatiallyIndexed for MultiPolygonCollection {
+    fn spatial_index(&self) -> Arc<FeatureSpatialIndex> {
+        self.spatial_index.get_or_build(|| {
+            FeatureSpatialIndex::new(self.geometries().map(|multi_polygon| {
+                // the outer rings bound the polygons
+                BoundingBox2D::from_coordinates(
+                    multi_polygon
+                        .polygons()
+                        .iter()
+                        .filter_map(|polygon| polygon.as_ref().first())
+                        .flat_map(|ring| ring.as_ref().iter()),
+                )
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{FeatureData, MultiPoint, TimeInterval};
+    use std::collections::HashMap;
+
+    #[test]
+    fn query_many_boxes() {
+        let boxes: Vec<Option<BoundingBox2D>> = (0..1000)
+            .map(|i| {
+                let (x, y) = (f64::from(i % 40), f64::from(i / 40));
+                Some(BoundingBox2D::new_unchecked(
+                    (x, y).into(),
+                    (x + 0.5, y + 0.5).into(),
+                ))
+            })
+            .collect();
+
+        let index = FeatureSpatialIndex::new(boxes.clone());
+        assert_eq!(index.len(), 1000);
+
+        let query = BoundingBox2D::new((10.2, 3.2).into(), (12.1, 4.8).into()).unwrap();
+        let expected: Vec<usize> = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, bounds)| bounds.unwrap().intersects_bbox(&query))
+            .map(|(i, _)| i)
+            .collect();
+
+        assert_eq!(index.features_intersecting(&query), expected);
+        assert_eq!(
+            index.features_containing(&(5.25, 2.25).into()),
+            vec![2 * 40 + 5]
+        );
+        assert!(index.features_containing(&(5.75, 2.25).into()).is_empty());
+    }
+
+    #[test]
+    fn skips_features_without_bounds() {
+        let index = FeatureSpatialIndex::new(vec![
+            None,
+            Some(BoundingBox2D::new_unchecked(
+                (0., 0.).into(),
+                (1., 1.).into(),
+            )),
+        ]);
+
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.features_containing(&(0.5, 0.5).into()), vec![1]);
+
+        let empty = FeatureSpatialIndex::new(vec![]);
+        assert!(empty.is_empty());
+        assert!(empty.features_containing(&(0.5, 0.5).into()).is_empty());
+    }
+
+    #[test]
+    fn collection_index_is_reused() {
+        let collection = MultiPointCollection::from_data(
+            MultiPoint::many(vec![vec![(0.0, 0.0), (1.0, 1.0)], vec![(5.0, 5.0)]]).unwrap(),
+            vec![TimeInterval::default(); 2],
+            HashMap::<String, FeatureData>::new(),
+        )
+        .unwrap();
+
+        let index = collection.spatial_index();
+        assert_eq!(index.features_containing(&(0.5, 0.5).into()), vec![0]);
+        assert_eq!(index.features_containing(&(5.0, 5.0).into()), vec![1]);
+
+        assert!(Arc::ptr_eq(&index, &collection.clone().spatial_index()));
+        assert!(!Arc::ptr_eq(
+            &index,
+            &collection.append(&collection).unwrap().spatial_index()
+        ));
+    }
+}
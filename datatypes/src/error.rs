@@ -6,11 +6,12 @@ use crate::{
     raster::RasterDataType,
 };
 use std::convert::Infallible;
+use std::fmt;
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(crate)")]
 pub enum Error {
-    #[snafu(display("Arrow internal error: {:?}", source))]
+    #[snafu(display("Arrow internal error"))]
     ArrowInternal {
         source: arrow::error::ArrowError,
     },
@@ -68,6 +69,16 @@ pub enum Error {
         upper_right_coordinate: Coordinate2D,
     },
 
+    #[snafu(display(
+        "The conditions ul.x <= lr.x && ul.y >= lr.y are not met by ul:{} lr:{}",
+        upper_left_coordinate,
+        lower_right_coordinate
+    ))]
+    InvalidSpatialPartition {
+        upper_left_coordinate: Coordinate2D,
+        lower_right_coordinate: Coordinate2D,
+    },
+
     #[snafu(display(
         "Mask length ≠ collection length ({} ≠ {})",
         mask_length,
@@ -120,7 +131,7 @@ pub enum Error {
         spatial_reference_string: String,
     },
 
-    #[snafu(display("ParseU32: {}", source))]
+    #[snafu(display("ParseU32"))]
     ParseU32 {
         source: <u32 as std::str::FromStr>::Err,
     },
@@ -137,3 +148,48 @@ impl From<Infallible> for Error {
         unreachable!("This function cannot be called on a non-failing type")
     }
 }
+
+/// Displays an error followed by the messages of its chain of sources, e.g.,
+/// `Workflow 42 failed → GdalError → dataset not found`
+///
+/// Errors that wrap a source only describe their own context, so that each message occurs once.
+pub struct ErrorChain<'e>(pub &'e (dyn std::error::Error + 'static));
+
+impl<'e> fmt::Display for ErrorChain<'e> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+
+        let mut source = self.0.source();
+        while let Some(error) = source {
+            write!(f, " → {}", error)?;
+            source = error.source();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_chain() {
+        let error: Error = PrimitivesError::UnallowedEmpty.into();
+
+        assert_eq!(
+            ErrorChain(&error).to_string(),
+            "Primitives → UnallowedEmpty"
+        );
+    }
+
+    #[test]
+    fn error_chain_without_source() {
+        let error = Error::TimeIntervalEndBeforeStart { start: 2, end: 1 };
+
+        assert_eq!(
+            ErrorChain(&error).to_string(),
+            "Start `2` must be before end `1`"
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use crate::collections::{
+    IntoGeometryIterator, MultiLineStringCollection, MultiPointCollection, MultiPolygonCollection,
+};
+use crate::error;
+use crate::operations::image::supersampling::box_filter;
+use crate::operations::image::RgbaColor;
+use crate::primitives::{
+    BoundingBox2D, Coordinate2D, MultiLineStringAccess, MultiPointAccess, MultiPolygonAccess,
+};
+use crate::util::Result;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+/// Styling of rendered vector data. Widths and radii are given in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VectorStyle {
+    pub fill_color: RgbaColor,
+    pub stroke_color: RgbaColor,
+    pub stroke_width: f64,
+    pub point_radius: f64,
+}
+
+impl Default for VectorStyle {
+    fn default() -> Self {
+        Self {
+            fill_color: RgbaColor::new(0, 0, 255, 128),
+            stroke_color: RgbaColor::black(),
+            stroke_width: 1.,
+            point_radius: 3.,
+        }
+    }
+}
+
+impl VectorStyle {
+    /// The style for drawing onto a canvas that is `factor` times larger
+    pub fn scaled(&self, factor: f64) -> Self {
+        Self {
+            stroke_width: self.stroke_width * factor,
+            point_radius: self.point_radius * factor,
+            ..*self
+        }
+    }
+}
+
+/// A transparent image of a `BoundingBox2D` onto which vector data can be drawn
+pub struct Canvas {
+    image: RgbaImage,
+    bbox: BoundingBox2D,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, bbox: BoundingBox2D) -> Self {
+        Self {
+            image: RgbaImage::new(width, height),
+            bbox,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    pub fn bbox(&self) -> BoundingBox2D {
+        self.bbox
+    }
+
+    /// Wrap a png image of the `BoundingBox2D`, e.g., a rendered raster, for drawing onto it
+    pub fn from_png(png: &[u8], bbox: BoundingBox2D) -> Result<Self> {
+        let image = image::load_from_memory_with_format(png, ImageFormat::Png)
+            .map_err(|_| error::Error::Colorizer {
+                details: "decoding PNG failed".into(),
+            })?
+            .to_rgba();
+
+        Ok(Self { image, bbox })
+    }
+
+    /// Map a coordinate to (fractional) pixel space with the origin in the upper left corner
+    fn to_pixel(&self, coordinate: Coordinate2D) -> (f64, f64) {
+        let x = (coordinate.x - self.bbox.lower_left().x) / self.bbox.size_x()
+            * f64::from(self.width());
+        let y = (self.bbox.upper_right().y - coordinate.y) / self.bbox.size_y()
+            * f64::from(self.height());
+        (x, y)
+    }
+
+    /// Draw a disc around the `coordinate` that is filled and outlined according to the `style`
+    pub fn draw_point(&mut self, coordinate: Coordinate2D, style: &VectorStyle) {
+        let (center_x, center_y) = self.to_pixel(coordinate);
+        let half_stroke = style.stroke_width / 2.;
+        let outer_radius = style.point_radius + half_stroke;
+        let inner_radius = style.point_radius - half_stroke;
+
+        self.for_each_pixel_in(
+            (center_x - outer_radius, center_y - outer_radius),
+            (center_x + outer_radius, center_y + outer_radius),
+            |pixel_x, pixel_y| {
+                let distance = (pixel_x - center_x).hypot(pixel_y - center_y);
+                if distance <= inner_radius {
+                    Some(style.fill_color)
+                } else if distance <= outer_radius {
+                    Some(style.stroke_color)
+                } else {
+                    None
+                }
+            },
+        );
+    }
+
+    /// Draw a line string with the stroke of the `style`
+    pub fn draw_line(&mut self, coordinates: &[Coordinate2D], style: &VectorStyle) {
+        if style.stroke_width <= 0. {
+            return;
+        }
+
+        let half_stroke = style.stroke_width / 2.;
+
+        for segment in coordinates.windows(2) {
+            let start = self.to_pixel(segment[0]);
+            let end = self.to_pixel(segment[1]);
+
+            self.for_each_pixel_in(
+                (
+                    start.0.min(end.0) - half_stroke,
+                    start.1.min(end.1) - half_stroke,
+                ),
+                (
+                    start.0.max(end.0) + half_stroke,
+                    start.1.max(end.1) + half_stroke,
+                ),
+                |pixel_x, pixel_y| {
+                    if distance_to_segment((pixel_x, pixel_y), start, end) <= half_stroke {
+                        Some(style.stroke_color)
+                    } else {
+                        None
+                    }
+                },
+            );
+        }
+    }
+
+    /// Fill a polygon with the even-odd rule and outline its rings according to the `style`
+    pub fn draw_polygon<R: AsRef<[Coordinate2D]>>(&mut self, rings: &[R], style: &VectorStyle) {
+        let pixel_rings: Vec<Vec<(f64, f64)>> = rings
+            .iter()
+            .map(|ring| ring.as_ref().iter().map(|&c| self.to_pixel(c)).collect())
+            .collect();
+
+        let mut intersections = Vec::new();
+        for pixel_y in 0..self.height() {
+            let scan_y = f64::from(pixel_y) + 0.5;
+
+            intersections.clear();
+            for ring in &pixel_rings {
+                for edge in ring.windows(2) {
+                    let ((x0, y0), (x1, y1)) = (edge[0], edge[1]);
+                    if (y0 <= scan_y && scan_y < y1) || (y1 <= scan_y && scan_y < y0) {
+                        intersections.push(x0 + (scan_y - y0) / (y1 - y0) * (x1 - x0));
+                    }
+                }
+            }
+
+            intersections.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+            for span in intersections.chunks_exact(2) {
+                let first_pixel = (span[0] - 0.5).ceil().max(0.) as u32;
+                let last_pixel = (span[1] - 0.5).floor().min(f64::from(self.width()) - 1.);
+                if last_pixel < 0. {
+                    continue;
+                }
+
+                for pixel_x in first_pixel..=(last_pixel as u32) {
+                    self.blend_pixel(pixel_x, pixel_y, style.fill_color);
+                }
+            }
+        }
+
+        for ring in rings {
+            self.draw_line(ring.as_ref(), style);
+        }
+    }
+
+    /// Call `color_fn` with the center of every pixel in the given pixel space bounds and blend
+    /// the returned color onto the pixel
+    fn for_each_pixel_in<F>(&mut self, upper_left: (f64, f64), lower_right: (f64, f64), color_fn: F)
+    where
+        F: Fn(f64, f64) -> Option<RgbaColor>,
+    {
+        let x_start = upper_left.0.floor().max(0.) as u32;
+        let y_start = upper_left.1.floor().max(0.) as u32;
+        let x_end = lower_right.0.ceil().min(f64::from(self.width())).max(0.) as u32;
+        let y_end = lower_right.1.ceil().min(f64::from(self.height())).max(0.) as u32;
+
+        for pixel_y in y_start..y_end {
+            for pixel_x in x_start..x_end {
+                if let Some(color) = color_fn(f64::from(pixel_x) + 0.5, f64::from(pixel_y) + 0.5) {
+                    self.blend_pixel(pixel_x, pixel_y, color);
+                }
+            }
+        }
+    }
+
+    /// Compose `color` over the current pixel value
+    pub(super) fn blend_pixel(&mut self, x: u32, y: u32, color: RgbaColor) {
+        let Rgba(source) = color.into();
+        let Rgba(destination) = *self.image.get_pixel(x, y);
+
+        let source_alpha = f64::from(source[3]) / 255.;
+        let destination_alpha = f64::from(destination[3]) / 255.;
+        let alpha = source_alpha + destination_alpha * (1. - source_alpha);
+
+        if alpha <= 0. {
+            return;
+        }
+
+        let mut blended = [0_u8; 4];
+        for ((blended, &source), &destination) in blended.iter_mut().zip(&source).zip(&destination)
+        {
+            let value = (f64::from(source) * source_alpha
+                + f64::from(destination) * destination_alpha * (1. - source_alpha))
+                / alpha;
+            *blended = value.round() as u8;
+        }
+        blended[3] = (alpha * 255.).round() as u8;
+
+        self.image.put_pixel(x, y, Rgba(blended));
+    }
+
+    /// Put a background of the given `color` below the canvas, which fills its transparent parts
+    pub fn fill_background(&mut self, color: RgbaColor) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let Rgba([red, green, blue, alpha]) = *self.image.get_pixel(x, y);
+                self.image.put_pixel(x, y, color.into());
+                self.blend_pixel(x, y, RgbaColor::new(red, green, blue, alpha));
+            }
+        }
+    }
+
+    /// Shrink a canvas that was created at `factor` times the output size, which smooths the
+    /// edges of the drawn shapes
+    pub fn downsampled(&self, factor: u32) -> Self {
+        Self {
+            image: box_filter(&self.image, factor),
+            bbox: self.bbox,
+        }
+    }
+
+    /// Outputs the png bytes of the canvas
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+
+        DynamicImage::ImageRgba8(self.image.clone())
+            .write_to(&mut buffer, ImageFormat::Png)
+            .map_err(|_| error::Error::Colorizer {
+                details: "encoding PNG failed".into(),
+            })?;
+
+        Ok(buffer)
+    }
+}
+
+fn distance_to_segment(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_squared = dx * dx + dy * dy;
+
+    let t = if length_squared > 0. {
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_squared)
+            .max(0.)
+            .min(1.)
+    } else {
+        0.
+    };
+
+    (point.0 - (start.0 + t * dx)).hypot(point.1 - (start.1 + t * dy))
+}
+
+/// Render the geometries of a feature collection onto a `Canvas`
+pub trait DrawOnCanvas {
+    fn draw_on_canvas(&self, canvas: &mut Canvas, style: &VectorStyle);
+}
+
+impl DrawOnCanvas for MultiPointCollection {
+    fn draw_on_canvas(&self, canvas: &mut Canvas, style: &VectorStyle) {
+        for multi_point in self.geometries() {
+            for &point in multi_point.points() {
+                canvas.draw_point(point, style);
+            }
+        }
+    }
+}
+
+impl DrawOnCanvas for MultiLineStringCollection {
+    fn draw_on_canvas(&self, canvas: &mut Canvas, style: &VectorStyle) {
+        for multi_line_string in self.geometries() {
+            for line in multi_line_string.lines() {
+                canvas.draw_line(line, style);
+            }
+        }
+    }
+}
+
+impl DrawOnCanvas for MultiPolygonCollection {
+    fn draw_on_canvas(&self, canvas: &mut Canvas, style: &VectorStyle) {
+        for multi_polygon in self.geometries() {
+            for polygon in multi_polygon.polygons() {
+                canvas.draw_polygon(polygon, style);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{MultiPoint, MultiPolygon, TimeInterval};
+
+    fn pixel(canvas: &Canvas, x: u32, y: u32) -> [u8; 4] {
+        canvas.image.get_pixel(x, y).0
+    }
+
+    #[test]
+    fn point() {
+        let mut canvas = Canvas::new(
+            10,
+            10,
+            BoundingBox2D::new((0., 0.).into(), (10., 10.).into()).unwrap(),
+        );
+
+        let collection = MultiPointCollection::from_data(
+            MultiPoint::many(vec![vec![(5., 5.)]]).unwrap(),
+            vec![TimeInterval::default()],
+            Default::default(),
+        )
+        .unwrap();
+
+        let style = VectorStyle {
+            fill_color: RgbaColor::white(),
+            stroke_color: RgbaColor::black(),
+            stroke_width: 0.,
+            point_radius: 2.,
+        };
+
+        collection.draw_on_canvas(&mut canvas, &style);
+
+        assert_eq!(pixel(&canvas, 5, 4), [255, 255, 255, 255]);
+        assert_eq!(pixel(&canvas, 0, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(&canvas, 9, 9), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn polygon() {
+        let mut canvas = Canvas::new(
+            10,
+            10,
+            BoundingBox2D::new((0., 0.).into(), (10., 10.).into()).unwrap(),
+        );
+
+        let collection = MultiPolygonCollection::from_data(
+            vec![MultiPolygon::new(vec![vec![vec![
+                (2., 2.).into(),
+                (8., 2.).into(),
+                (8., 8.).into(),
+                (2., 8.).into(),
+                (2., 2.).into(),
+            ]]])
+            .unwrap()],
+            vec![TimeInterval::default()],
+            Default::default(),
+        )
+        .unwrap();
+
+        let style = VectorStyle {
+            fill_color: RgbaColor::new(255, 0, 0, 255),
+            stroke_color: RgbaColor::black(),
+            stroke_width: 0.,
+            point_radius: 0.,
+        };
+
+        collection.draw_on_canvas(&mut canvas, &style);
+
+        assert_eq!(pixel(&canvas, 5, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&canvas, 2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&canvas, 1, 5), [0, 0, 0, 0]);
+        assert_eq!(pixel(&canvas, 8, 5), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn supersampled_point() {
+        let style = VectorStyle {
+            fill_color: RgbaColor::white(),
+            stroke_color: RgbaColor::black(),
+            stroke_width: 0.,
+            point_radius: 1.5,
+        };
+        let collection = MultiPointCollection::from_data(
+            MultiPoint::many(vec![vec![(5., 5.)]]).unwrap(),
+            vec![TimeInterval::default()],
+            Default::default(),
+        )
+        .unwrap();
+
+        let mut canvas = Canvas::new(
+            40,
+            40,
+            BoundingBox2D::new((0., 0.).into(), (10., 10.).into()).unwrap(),
+        );
+        collection.draw_on_canvas(&mut canvas, &style.scaled(4.));
+        let canvas = canvas.downsampled(4);
+
+        assert_eq!((canvas.width(), canvas.height()), (10, 10));
+        assert_eq!(pixel(&canvas, 5, 5), [255, 255, 255, 255]);
+        // the edge of the disc is partially covered
+        let [.., alpha] = pixel(&canvas, 6, 5);
+        assert!(alpha > 0 && alpha < 255);
+        assert_eq!(pixel(&canvas, 0, 0), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn blend() {
+        let mut canvas = Canvas::new(
+            1,
+            1,
+            BoundingBox2D::new((0., 0.).into(), (1., 1.).into()).unwrap(),
+        );
+
+        canvas.blend_pixel(0, 0, RgbaColor::white());
+        canvas.blend_pixel(0, 0, RgbaColor::new(0, 0, 0, 128));
+
+        assert_eq!(pixel(&canvas, 0, 0), [127, 127, 127, 255]);
+    }
+
+    #[test]
+    fn background() {
+        let mut canvas = Canvas::new(
+            2,
+            1,
+            BoundingBox2D::new((0., 0.).into(), (2., 1.).into()).unwrap(),
+        );
+
+        canvas.blend_pixel(0, 0, RgbaColor::new(255, 0, 0, 255));
+        canvas.fill_background(RgbaColor::new(0, 0, 255, 255));
+
+        assert_eq!(pixel(&canvas, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&canvas, 1, 0), [0, 0, 255, 255]);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::operations::image::font::{glyph, text_width, GLYPH_ADVANCE, GLYPH_HEIGHT};
+use crate::operations::image::{Canvas, RgbaColor};
+
+/// The distance of the decorations to the edges of the map in pixels
+const MARGIN: u32 = 4;
+/// The space between a text and the border of its background in pixels
+const PADDING: u32 = 2;
+
+/// Overlays that are composited onto a rendered map, e.g., to carry the credits of its data
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapDecorations {
+    /// A text in the lower right corner
+    pub attribution: Option<String>,
+    /// A translucent text across the center of the map
+    pub watermark: Option<String>,
+    /// Draw a scale bar in the lower left corner for maps whose coordinates have the given
+    /// number of meters per unit
+    pub scalebar_meters_per_unit: Option<f64>,
+    /// The factor by which texts, bars and spacings are enlarged, e.g., for high-DPI clients
+    pub scale: u32,
+}
+
+impl Default for MapDecorations {
+    fn default() -> Self {
+        Self {
+            attribution: None,
+            watermark: None,
+            scalebar_meters_per_unit: None,
+            scale: 1,
+        }
+    }
+}
+
+impl Canvas {
+    /// Draw the `decorations` on top of the current content
+    pub fn decorate(&mut self, decorations: &MapDecorations) {
+        if let Some(watermark) = &decorations.watermark {
+            self.draw_watermark(watermark);
+        }
+        let scale = decorations.scale.max(1);
+        if let Some(meters_per_unit) = decorations.scalebar_meters_per_unit {
+            self.draw_scalebar(meters_per_unit, scale);
+        }
+        if let Some(attribution) = &decorations.attribution {
+            self.draw_attribution(attribution, scale);
+        }
+    }
+
+    fn draw_attribution(&mut self, text: &str, scale: u32) {
+        let (margin, padding) = (MARGIN * scale, PADDING * scale);
+        let width = text_width(text, scale) + 2 * padding;
+        let height = GLYPH_HEIGHT * scale + 2 * padding;
+        let x = self.width().saturating_sub(width + margin);
+        let y = self.height().saturating_sub(height + margin);
+
+        self.fill_rectangle(x, y, width, height, RgbaColor::new(255, 255, 255, 192));
+        self.draw_text(text, x + padding, y + padding, scale, RgbaColor::black());
+    }
+
+    fn draw_watermark(&mut self, text: &str) {
+        let unscaled_width = text_width(text, 1);
+        if unscaled_width == 0 {
+            return;
+        }
+
+        // cover about half of the map's width
+        let scale = (self.width() / 2 / unscaled_width).max(1).min(8);
+        let x = self.width().saturating_sub(unscaled_width * scale) / 2;
+        let y = self.height().saturating_sub(GLYPH_HEIGHT * scale) / 2;
+
+        self.draw_text(text, x, y, scale, RgbaColor::new(128, 128, 128, 96));
+    }
+
+    fn draw_scalebar(&mut self, meters_per_unit: f64, scale: u32) {
+        let meters_per_pixel = self.bbox().size_x() / f64::from(self.width()) * meters_per_unit;
+        if !meters_per_pixel.is_finite() || meters_per_pixel <= 0. {
+            return;
+        }
+
+        let meters = nice_length(f64::from(self.width() / 4) * meters_per_pixel);
+        let length = (meters / meters_per_pixel).round() as u32;
+        let label = if meters >= 1000. {
+            format!("{} km", meters / 1000.)
+        } else {
+            format!("{} m", meters)
+        };
+
+        let (margin, padding) = (MARGIN * scale, PADDING * scale);
+        let bar_height = 3 * scale;
+        let width = length.max(text_width(&label, scale)) + 2 * padding;
+        let height = GLYPH_HEIGHT * scale + bar_height + 3 * padding;
+        let x = margin;
+        let y = self.height().saturating_sub(height + margin);
+
+        self.fill_rectangle(x, y, width, height, RgbaColor::new(255, 255, 255, 192));
+        self.draw_text(&label, x + padding, y + padding, scale, RgbaColor::black());
+        self.fill_rectangle(
+            x + padding,
+            y + GLYPH_HEIGHT * scale + 2 * padding,
+            length,
+            bar_height,
+            RgbaColor::black(),
+        );
+    }
+
+    /// Draw a single line of `text` with its upper left corner at the given pixel
+    fn draw_text(&mut self, text: &str, x: u32, y: u32, scale: u32, color: RgbaColor) {
+        for (index, character) in text.chars().enumerate() {
+            let glyph_x = x + index as u32 * GLYPH_ADVANCE * scale;
+
+            for (column, &bits) in glyph(character).iter().enumerate() {
+                for row in 0..GLYPH_HEIGHT {
+                    if bits >> row & 1 == 1 {
+                        self.fill_rectangle(
+                            glyph_x + column as u32 * scale,
+                            y + row * scale,
+                            scale,
+                            scale,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Blend `color` onto the pixels of the rectangle, clipped to the canvas
+    fn fill_rectangle(&mut self, x: u32, y: u32, width: u32, height: u32, color: RgbaColor) {
+        let x_end = x.saturating_add(width).min(self.width());
+        let y_end = y.saturating_add(height).min(self.height());
+
+        for pixel_y in y..y_end {
+            for pixel_x in x..x_end {
+                self.blend_pixel(pixel_x, pixel_y, color);
+            }
+        }
+    }
+}
+
+/// The largest length of the form `1`, `2` or `5` times a power of ten that does not exceed
+/// `max_length`
+fn nice_length(max_length: f64) -> f64 {
+    let power = 10_f64.powf(max_length.log10().floor());
+
+    [5., 2., 1.]
+        .iter()
+        .map(|factor| factor * power)
+        .find(|&length| length <= max_length)
+        .unwrap_or(power)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::BoundingBox2D;
+
+    fn pixel(canvas: &Canvas, x: u32, y: u32) -> [u8; 4] {
+        image::load_from_memory(&canvas.to_png().unwrap())
+            .unwrap()
+            .to_rgba()
+            .get_pixel(x, y)
+            .0
+    }
+
+    #[test]
+    fn nice_lengths() {
+        assert!((nice_length(730.) - 500.).abs() < f64::EPSILON);
+        assert!((nice_length(2500.) - 2000.).abs() < f64::EPSILON);
+        assert!((nice_length(1.5) - 1.).abs() < f64::EPSILON);
+        assert!((nice_length(0.07) - 0.05).abs() < 1e-12);
+    }
+
+    #[test]
+    fn attribution_and_scalebar() {
+        let bbox = BoundingBox2D::new((0., 0.).into(), (10_000., 5_000.).into()).unwrap();
+        let mut canvas = Canvas::new(200, 100, bbox);
+
+        canvas.decorate(&MapDecorations {
+            attribution: Some("(c) geo engine".to_string()),
+            watermark: None,
+            scalebar_meters_per_unit: Some(1.),
+            scale: 1,
+        });
+
+        // the attribution background in the lower right corner
+        assert_eq!(pixel(&canvas, 195, 94), [255, 255, 255, 192]);
+        // the scale bar of 2 km, i.e., 40 pixels
+        assert_eq!(pixel(&canvas, 6, 92), [0, 0, 0, 255]);
+        assert_eq!(pixel(&canvas, 45, 92), [0, 0, 0, 255]);
+        assert_eq!(pixel(&canvas, 46, 92), [255, 255, 255, 192]);
+        // the rest of the map stays transparent
+        assert_eq!(pixel(&canvas, 100, 10), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn scaled_attribution() {
+        let bbox = BoundingBox2D::new((0., 0.).into(), (1., 1.).into()).unwrap();
+        let mut canvas = Canvas::new(200, 100, bbox);
+
+        canvas.decorate(&MapDecorations {
+            attribution: Some("-".to_string()),
+            scale: 2,
+            ..Default::default()
+        });
+
+        // the background of a 10 × 14 pixel glyph with a padding of 4 and a margin of 8 pixels
+        assert_eq!(pixel(&canvas, 191, 91), [255, 255, 255, 192]);
+        assert_eq!(pixel(&canvas, 192, 91), [0, 0, 0, 0]);
+        assert_eq!(pixel(&canvas, 174, 91), [255, 255, 255, 192]);
+        assert_eq!(pixel(&canvas, 173, 91), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn watermark() {
+        let bbox = BoundingBox2D::new((0., 0.).into(), (1., 1.).into()).unwrap();
+        let mut canvas = Canvas::new(100, 100, bbox);
+
+        canvas.decorate(&MapDecorations {
+            watermark: Some("-".to_string()),
+            ..Default::default()
+        });
+
+        // a dash of 40 × 8 pixels across the center
+        assert_eq!(pixel(&canvas, 50, 50), [128, 128, 128, 96]);
+        assert_eq!(pixel(&canvas, 50, 10), [0, 0, 0, 0]);
+    }
+}
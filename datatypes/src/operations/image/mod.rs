@@ -1,9 +1,15 @@
+mod canvas;
 mod colorizer;
+mod decorations;
+mod font;
 mod into_lossy;
 mod rgba_transmutable;
+mod supersampling;
 mod to_png;
 
+pub use canvas::{Canvas, DrawOnCanvas, VectorStyle};
 pub use colorizer::{Breakpoints, Colorizer, RgbaColor};
+pub use decorations::MapDecorations;
 pub use into_lossy::LossyInto;
 pub use rgba_transmutable::RgbaTransmutable;
 pub use to_png::ToPng;
@@ -1,4 +1,5 @@
 use crate::operations::image::RgbaColor;
+use half::f16;
 
 /// This trait allows using raw bytes as RGBA colors
 ///
@@ -41,6 +42,13 @@ rbga_transmutable_impl!(i64);
 rbga_transmutable_impl!(u32);
 rbga_transmutable_impl!(i32);
 
+impl RgbaTransmutable for f16 {
+    fn transmute_to_rgba(self) -> RgbaColor {
+        let [gray, alpha] = self.to_bits().to_be_bytes();
+        RgbaColor::new(gray, gray, gray, alpha)
+    }
+}
+
 impl RgbaTransmutable for u16 {
     fn transmute_to_rgba(self) -> RgbaColor {
         let [gray, alpha] = self.to_be_bytes();
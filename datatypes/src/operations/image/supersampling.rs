@@ -0,0 +1,70 @@
+use image::{Rgba, RgbaImage};
+
+/// Shrink an image that was rendered at `factor` times its size by averaging the blocks of
+/// `factor × factor` pixels. The colors are weighted by their alpha, so that transparent
+/// pixels do not darken the edges of shapes.
+pub(crate) fn box_filter(image: &RgbaImage, factor: u32) -> RgbaImage {
+    if factor <= 1 {
+        return image.clone();
+    }
+
+    let block_size = f64::from(factor * factor);
+
+    RgbaImage::from_fn(image.width() / factor, image.height() / factor, |x, y| {
+        let mut sums = [0_f64; 4];
+
+        for block_y in y * factor..(y + 1) * factor {
+            for block_x in x * factor..(x + 1) * factor {
+                let Rgba(pixel) = *image.get_pixel(block_x, block_y);
+                let alpha = f64::from(pixel[3]);
+
+                for (sum, &channel) in sums.iter_mut().zip(&pixel[..3]) {
+                    *sum += f64::from(channel) * alpha;
+                }
+                sums[3] += alpha;
+            }
+        }
+
+        if sums[3] <= 0. {
+            return Rgba([0, 0, 0, 0]);
+        }
+
+        Rgba([
+            (sums[0] / sums[3]).round() as u8,
+            (sums[1] / sums[3]).round() as u8,
+            (sums[2] / sums[3]).round() as u8,
+            (sums[3] / block_size).round() as u8,
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_blocks() {
+        let image = RgbaImage::from_fn(4, 2, |x, _| {
+            if x == 0 {
+                Rgba([255, 0, 0, 255])
+            } else if x == 1 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        });
+
+        let filtered = box_filter(&image, 2);
+
+        assert_eq!(filtered.dimensions(), (2, 1));
+        assert_eq!(filtered.get_pixel(0, 0).0, [255, 0, 0, 128]);
+        assert_eq!(filtered.get_pixel(1, 0).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn factor_one_keeps_the_image() {
+        let image = RgbaImage::from_pixel(3, 3, Rgba([1, 2, 3, 4]));
+
+        assert_eq!(box_filter(&image, 1), image);
+    }
+}
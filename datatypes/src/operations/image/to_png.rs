@@ -1,12 +1,27 @@
 use crate::error;
+use crate::operations::image::supersampling::box_filter;
 use crate::operations::image::{Colorizer, RgbaTransmutable};
-use crate::raster::{GridDimension, GridPixelAccess, Pixel, Raster, Raster2D, TypedRaster2D};
+use crate::raster::{
+    GridDimension, GridPixelAccess, Pixel, Raster, Raster2D, Raster2DRefFn, SparseRasterTile2D,
+    TypedRaster2D,
+};
 use crate::util::Result;
 use image::{DynamicImage, ImageFormat, RgbaImage};
 
 pub trait ToPng {
     /// Outputs png bytes of an image of size width x height
     fn to_png(&self, width: u32, height: u32, colorizer: &Colorizer) -> Result<Vec<u8>>;
+
+    /// Outputs png bytes of an image of size width x height that is rendered at `factor` times
+    /// the size and downscaled. The raster should have `factor` times the resolution of the
+    /// image, so that the downscaling smooths the edges within the raster
+    fn to_png_supersampled(
+        &self,
+        width: u32,
+        height: u32,
+        colorizer: &Colorizer,
+        factor: u32,
+    ) -> Result<Vec<u8>>;
 }
 
 impl<T> ToPng for Raster2D<T>
@@ -14,50 +29,141 @@ where
     T: Pixel + RgbaTransmutable,
 {
     fn to_png(&self, width: u32, height: u32, colorizer: &Colorizer) -> Result<Vec<u8>> {
-        // TODO: use PNG color palette once it is available
+        image_buffer_to_png_bytes(raster_to_image(self, width, height, colorizer))
+    }
+
+    fn to_png_supersampled(
+        &self,
+        width: u32,
+        height: u32,
+        colorizer: &Colorizer,
+        factor: u32,
+    ) -> Result<Vec<u8>> {
+        let factor = factor.max(1);
+        let image_buffer = raster_to_image(self, width * factor, height * factor, colorizer);
+
+        image_buffer_to_png_bytes(box_filter(&image_buffer, factor))
+    }
+}
+
+fn raster_to_image<T>(
+    raster: &Raster2D<T>,
+    width: u32,
+    height: u32,
+    colorizer: &Colorizer,
+) -> RgbaImage
+where
+    T: Pixel + RgbaTransmutable,
+{
+    // TODO: use PNG color palette once it is available
 
-        let (.., raster_y_size, raster_x_size) = self.dimension().as_pattern();
-        let scale_x = (raster_x_size as f64) / f64::from(width);
-        let scale_y = (raster_y_size as f64) / f64::from(height);
+    let (.., raster_y_size, raster_x_size) = raster.dimension().as_pattern();
+    let scale_x = (raster_x_size as f64) / f64::from(width);
+    let scale_y = (raster_y_size as f64) / f64::from(height);
 
-        let color_mapper = colorizer.create_color_mapper();
+    let color_mapper = colorizer.create_color_mapper();
 
-        let image_buffer: RgbaImage = RgbaImage::from_fn(width, height, |x, y| {
-            let (grid_pixel_x, grid_pixel_y) = image_pixel_to_raster_pixel(x, y, scale_x, scale_y);
-            if let Ok(pixel_value) = self.pixel_value_at_grid_index(&(grid_pixel_y, grid_pixel_x)) {
-                color_mapper.call(pixel_value)
-            } else {
-                colorizer.no_data_color()
+    RgbaImage::from_fn(width, height, |x, y| {
+        let (grid_pixel_x, grid_pixel_y) = image_pixel_to_raster_pixel(x, y, scale_x, scale_y);
+        match raster.pixel_value_at_grid_index(&(grid_pixel_y, grid_pixel_x)) {
+            Ok(pixel_value) if !raster.is_no_data(pixel_value) => color_mapper.call(pixel_value),
+            _ => colorizer.no_data_color(),
+        }
+        .into()
+    })
+}
+
+impl<T> ToPng for SparseRasterTile2D<T>
+where
+    T: Pixel + RgbaTransmutable,
+{
+    fn to_png(&self, width: u32, height: u32, colorizer: &Colorizer) -> Result<Vec<u8>> {
+        let color = match self {
+            SparseRasterTile2D::EmptyTile(_) => colorizer.no_data_color(),
+            SparseRasterTile2D::ConstantTile(tile) => {
+                colorizer.create_color_mapper().call(tile.value)
+            }
+            SparseRasterTile2D::MaterializedTile(tile) => {
+                return tile.data.to_png(width, height, colorizer)
             }
-            .into()
-        });
+        };
 
-        let mut buffer = Vec::new();
+        image_buffer_to_png_bytes(RgbaImage::from_pixel(width, height, color.into()))
+    }
+
+    fn to_png_supersampled(
+        &self,
+        width: u32,
+        height: u32,
+        colorizer: &Colorizer,
+        factor: u32,
+    ) -> Result<Vec<u8>> {
+        match self {
+            SparseRasterTile2D::MaterializedTile(tile) => tile
+                .data
+                .to_png_supersampled(width, height, colorizer, factor),
+            // a single color has no edges to smooth
+            _ => self.to_png(width, height, colorizer),
+        }
+    }
+}
+
+fn image_buffer_to_png_bytes(image_buffer: RgbaImage) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+
+    DynamicImage::ImageRgba8(image_buffer)
+        .write_to(&mut buffer, ImageFormat::Png)
+        .map_err(|_| error::Error::Colorizer {
+            details: "encoding PNG failed".into(),
+        })?;
 
-        DynamicImage::ImageRgba8(image_buffer)
-            .write_to(&mut buffer, ImageFormat::Png)
-            .map_err(|_| error::Error::Colorizer {
-                details: "encoding PNG failed".into(),
-            })?;
+    Ok(buffer)
+}
+
+/// Renders the variant of a `TypedRaster2D`, supersampled if there is a `factor`
+struct ToPngFn<'c> {
+    width: u32,
+    height: u32,
+    colorizer: &'c Colorizer,
+    factor: Option<u32>,
+}
+
+impl<'c> Raster2DRefFn for ToPngFn<'c> {
+    type Output = Result<Vec<u8>>;
 
-        Ok(buffer)
+    fn call<T: Pixel>(self, raster: &Raster2D<T>) -> Result<Vec<u8>> {
+        match self.factor {
+            Some(factor) => {
+                raster.to_png_supersampled(self.width, self.height, self.colorizer, factor)
+            }
+            None => raster.to_png(self.width, self.height, self.colorizer),
+        }
     }
 }
 
 impl ToPng for TypedRaster2D {
     fn to_png(&self, width: u32, height: u32, colorizer: &Colorizer) -> Result<Vec<u8>> {
-        match self {
-            TypedRaster2D::U8(r) => r.to_png(width, height, colorizer),
-            TypedRaster2D::U16(r) => r.to_png(width, height, colorizer),
-            TypedRaster2D::U32(r) => r.to_png(width, height, colorizer),
-            TypedRaster2D::U64(r) => r.to_png(width, height, colorizer),
-            TypedRaster2D::I8(r) => r.to_png(width, height, colorizer),
-            TypedRaster2D::I16(r) => r.to_png(width, height, colorizer),
-            TypedRaster2D::I32(r) => r.to_png(width, height, colorizer),
-            TypedRaster2D::I64(r) => r.to_png(width, height, colorizer),
-            TypedRaster2D::F32(r) => r.to_png(width, height, colorizer),
-            TypedRaster2D::F64(r) => r.to_png(width, height, colorizer),
-        }
+        self.dispatch_ref(ToPngFn {
+            width,
+            height,
+            colorizer,
+            factor: None,
+        })
+    }
+
+    fn to_png_supersampled(
+        &self,
+        width: u32,
+        height: u32,
+        colorizer: &Colorizer,
+        factor: u32,
+    ) -> Result<Vec<u8>> {
+        self.dispatch_ref(ToPngFn {
+            width,
+            height,
+            colorizer,
+            factor: Some(factor),
+        })
     }
 }
 
@@ -215,4 +321,81 @@ mod tests {
             image_bytes.as_slice()
         );
     }
+
+    #[test]
+    fn no_data() {
+        let raster = Raster2D::new(
+            [1, 2].into(),
+            vec![255_u8, 0],
+            Some(0),
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+
+        let colorizer = Colorizer::linear_gradient(
+            vec![
+                (0.0.into(), RgbaColor::new(0, 0, 0, 255)).into(),
+                (255.0.into(), RgbaColor::new(255, 255, 255, 255)).into(),
+            ],
+            RgbaColor::transparent(),
+            RgbaColor::pink(),
+        )
+        .unwrap();
+
+        let image = image::load_from_memory_with_format(
+            &raster.to_png(2, 1, &colorizer).unwrap(),
+            ImageFormat::Png,
+        )
+        .unwrap()
+        .to_rgba();
+
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn sparse_tiles() {
+        let tile = |value: u32| {
+            crate::raster::RasterTile2D::new(
+                Default::default(),
+                crate::raster::TileInformation::new(
+                    [1, 1].into(),
+                    [0, 0].into(),
+                    [0, 0].into(),
+                    [2, 2].into(),
+                    Default::default(),
+                ),
+                Raster2D::new(
+                    [2, 2].into(),
+                    vec![value; 4],
+                    Some(0),
+                    Default::default(),
+                    Default::default(),
+                )
+                .unwrap(),
+            )
+        };
+
+        let colorizer = Colorizer::rgba();
+
+        let constant = SparseRasterTile2D::from_tile(tile(0xFF00_00FF_u32));
+        let materialized = constant.clone().materialize().unwrap();
+        assert_eq!(
+            constant.to_png(10, 10, &colorizer).unwrap(),
+            materialized.data.to_png(10, 10, &colorizer).unwrap()
+        );
+
+        let empty = SparseRasterTile2D::from_tile(tile(0));
+        assert!(empty.is_empty());
+        assert_eq!(
+            empty.to_png(10, 10, &colorizer).unwrap(),
+            image_buffer_to_png_bytes(RgbaImage::from_pixel(
+                10,
+                10,
+                colorizer.no_data_color().into()
+            ))
+            .unwrap()
+        );
+    }
 }
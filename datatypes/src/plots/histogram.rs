@@ -315,8 +315,11 @@ impl Plot for Histogram {
         })
     }
 
-    fn to_png(&self, _width_px: u16, _height_px: u16) -> Vec<u8> {
-        todo!("keep track of https://github.com/procyon-rs/vega_lite_3.rs/issues/18")
+    fn to_png(&self, _width_px: u16, _height_px: u16) -> Result<Vec<u8>> {
+        // TODO: keep track of https://github.com/procyon-rs/vega_lite_3.rs/issues/18
+        Err(error::Error::Plot {
+            details: "Rendering plots as PNG images is not supported yet".to_string(),
+        })
     }
 }
 
@@ -1,9 +1,11 @@
 pub mod histogram;
+mod transition_matrix;
 
 use crate::util::Result;
 pub use histogram::Histogram;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
+pub use transition_matrix::TransitionMatrix;
 
 pub trait Plot {
     type PlotDataMetadataType: Debug + PartialEq + Serialize;
@@ -19,7 +21,13 @@ pub trait Plot {
         allow_interactions: bool,
     ) -> Result<PlotData<Self::PlotDataMetadataType>>;
 
-    fn to_png(&self, width_px: u16, height_px: u16) -> Vec<u8>;
+    /// Renders the plot as a PNG image
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the plot cannot be rendered as an image.
+    ///
+    fn to_png(&self, width_px: u16, height_px: u16) -> Result<Vec<u8>>;
 }
 
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
@@ -0,0 +1,278 @@
+use crate::error;
+use crate::plots::histogram::EmbeddingMetaData;
+use crate::plots::{Plot, PlotData};
+use crate::util::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use snafu::ensure;
+
+const SELECTION_NAME: &str = "transition_selection";
+
+/// Counts how many pixels changed from one class to another between two points in time,
+/// e.g., for reporting land-use change.
+///
+/// The rows of the matrix are the classes before and the columns are the classes after the
+/// change, so the diagonal contains the pixels that kept their class.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TransitionMatrix {
+    classes: Vec<i64>,
+    /// row-major counts of `classes.len() × classes.len()` transitions
+    counts: Vec<u64>,
+}
+
+impl TransitionMatrix {
+    /// Creates an empty matrix of the (distinct) `classes`
+    ///
+    /// # Examples
+    /// ```rust
+    /// use geoengine_datatypes::plots::TransitionMatrix;
+    ///
+    /// let mut matrix = TransitionMatrix::new(vec![2, 1]).unwrap();
+    /// matrix.add(1, 2).unwrap();
+    /// matrix.add(1, 2).unwrap();
+    /// matrix.add(2, 2).unwrap();
+    ///
+    /// assert_eq!(matrix.classes(), &[1, 2]);
+    /// assert_eq!(matrix.count(1, 2), 2);
+    /// assert_eq!(matrix.count(2, 1), 0);
+    /// assert_eq!(matrix.total(), 3);
+    /// ```
+    pub fn new(mut classes: Vec<i64>) -> Result<Self> {
+        classes.sort_unstable();
+        classes.dedup();
+
+        ensure!(
+            !classes.is_empty(),
+            error::Plot {
+                details: "Transition matrices must have at least one class"
+            }
+        );
+
+        Ok(Self {
+            counts: vec![0; classes.len() * classes.len()],
+            classes,
+        })
+    }
+
+    /// Count a pixel that changed from class `before` to class `after`
+    ///
+    /// # Errors
+    ///
+    /// This method fails if one of the classes is not part of the matrix.
+    ///
+    pub fn add(&mut self, before: i64, after: i64) -> Result<()> {
+        self.add_count(before, after, 1)
+    }
+
+    /// Count `count` pixels that changed from class `before` to class `after`
+    ///
+    /// # Errors
+    ///
+    /// This method fails if one of the classes is not part of the matrix.
+    ///
+    pub fn add_count(&mut self, before: i64, after: i64, count: u64) -> Result<()> {
+        let index = match (self.class_index(before), self.class_index(after)) {
+            (Some(row), Some(column)) => row * self.classes.len() + column,
+            _ => {
+                return Err(error::Error::Plot {
+                    details: format!(
+                        "The transition from class {} to {} is not part of the matrix",
+                        before, after
+                    ),
+                })
+            }
+        };
+
+        self.counts[index] += count;
+
+        Ok(())
+    }
+
+    /// The classes of the matrix in ascending order
+    pub fn classes(&self) -> &[i64] {
+        &self.classes
+    }
+
+    /// The number of pixels that changed from class `before` to class `after`
+    pub fn count(&self, before: i64, after: i64) -> u64 {
+        match (self.class_index(before), self.class_index(after)) {
+            (Some(row), Some(column)) => self.counts[row * self.classes.len() + column],
+            _ => 0,
+        }
+    }
+
+    /// The number of all counted pixels
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    fn class_index(&self, class: i64) -> Option<usize> {
+        self.classes.binary_search(&class).ok()
+    }
+
+    /// The transitions of the matrix, row by row
+    fn transitions(&self) -> impl Iterator<Item = (i64, i64, u64)> + '_ {
+        let classes = &self.classes;
+        self.counts.iter().enumerate().map(move |(index, &count)| {
+            (
+                classes[index / classes.len()],
+                classes[index % classes.len()],
+                count,
+            )
+        })
+    }
+}
+
+impl Plot for TransitionMatrix {
+    type PlotDataMetadataType = EmbeddingMetaData;
+
+    /// A heatmap of the transitions with the classes before on the y-axis and the classes
+    /// after on the x-axis
+    fn to_vega_embeddable(
+        &self,
+        allow_interactions: bool,
+    ) -> Result<PlotData<Self::PlotDataMetadataType>> {
+        let total = self.total();
+
+        let values: Vec<serde_json::Value> = self
+            .transitions()
+            .map(|(before, after, count)| {
+                json!({
+                    "before": before.to_string(),
+                    "after": after.to_string(),
+                    "count": count,
+                    "share": if total == 0 { 0. } else { count as f64 / total as f64 },
+                })
+            })
+            .collect();
+        let classes: Vec<String> = self.classes.iter().map(ToString::to_string).collect();
+
+        let mut chart = json!({
+            "$schema": "https://vega.github.io/schema/vega-lite/v3.4.0.json",
+            "data": { "values": values },
+            "mark": "rect",
+            "encoding": {
+                "x": {
+                    "field": "after",
+                    "type": "ordinal",
+                    "title": "After",
+                    "sort": classes,
+                },
+                "y": {
+                    "field": "before",
+                    "type": "ordinal",
+                    "title": "Before",
+                    "sort": classes,
+                },
+                "color": {
+                    "field": "count",
+                    "type": "quantitative",
+                    "title": "Pixels",
+                },
+                "tooltip": [
+                    { "field": "before", "type": "ordinal", "title": "Before" },
+                    { "field": "after", "type": "ordinal", "title": "After" },
+                    { "field": "count", "type": "quantitative", "title": "Pixels" },
+                    { "field": "share", "type": "quantitative", "title": "Share", "format": ".2%" },
+                ],
+            },
+            "padding": 5.0,
+        });
+
+        let selection_name = if allow_interactions {
+            chart["selection"] = json!({
+                SELECTION_NAME: {
+                    "type": "single",
+                    "encodings": ["x", "y"],
+                }
+            });
+
+            Some(SELECTION_NAME.to_string())
+        } else {
+            None
+        };
+
+        Ok(PlotData {
+            vega_string: chart.to_string(),
+            metadata: EmbeddingMetaData { selection_name },
+        })
+    }
+
+    fn to_png(&self, _width_px: u16, _height_px: u16) -> Result<Vec<u8>> {
+        // TODO: keep track of https://github.com/procyon-rs/vega_lite_3.rs/issues/18
+        Err(error::Error::Plot {
+            details: "Rendering plots as PNG images is not supported yet".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_transitions() {
+        let mut matrix = TransitionMatrix::new(vec![3, 1, 2, 1]).unwrap();
+        assert_eq!(matrix.classes(), &[1, 2, 3]);
+
+        matrix.add(1, 1).unwrap();
+        matrix.add(1, 3).unwrap();
+        matrix.add_count(3, 2, 5).unwrap();
+
+        assert_eq!(matrix.count(1, 1), 1);
+        assert_eq!(matrix.count(1, 3), 1);
+        assert_eq!(matrix.count(3, 1), 0);
+        assert_eq!(matrix.count(3, 2), 5);
+        assert_eq!(matrix.count(4, 2), 0);
+        assert_eq!(matrix.total(), 7);
+
+        assert!(matrix.add(4, 1).is_err());
+        assert!(TransitionMatrix::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn vega_heatmap() {
+        let mut matrix = TransitionMatrix::new(vec![1, 2]).unwrap();
+        matrix.add_count(1, 2, 3).unwrap();
+        matrix.add(2, 2).unwrap();
+
+        let plot = matrix.to_vega_embeddable(false).unwrap();
+        assert_eq!(
+            plot.metadata,
+            EmbeddingMetaData {
+                selection_name: None
+            }
+        );
+
+        let chart: serde_json::Value = serde_json::from_str(&plot.vega_string).unwrap();
+        assert_eq!(chart["mark"], "rect");
+        assert_eq!(
+            chart["data"]["values"],
+            json!([
+                {"before": "1", "after": "1", "count": 0, "share": 0.},
+                {"before": "1", "after": "2", "count": 3, "share": 0.75},
+                {"before": "2", "after": "1", "count": 0, "share": 0.},
+                {"before": "2", "after": "2", "count": 1, "share": 0.25},
+            ])
+        );
+        assert!(chart.get("selection").is_none());
+
+        let plot = matrix.to_vega_embeddable(true).unwrap();
+        assert_eq!(
+            plot.metadata.selection_name,
+            Some("transition_selection".to_string())
+        );
+        let chart: serde_json::Value = serde_json::from_str(&plot.vega_string).unwrap();
+        assert_eq!(chart["selection"]["transition_selection"]["type"], "single");
+    }
+
+    #[test]
+    fn png_is_unsupported() {
+        let matrix = TransitionMatrix::new(vec![1, 2]).unwrap();
+
+        assert!(matches!(
+            matrix.to_png(100, 100),
+            Err(error::Error::Plot { .. })
+        ));
+    }
+}
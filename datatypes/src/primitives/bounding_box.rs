@@ -118,6 +118,71 @@ impl BoundingBox2D {
         BoundingBox2D::new_unchecked(lower_left_coordinate, upper_right_coordinate)
     }
 
+    /// Creates the smallest bounding box that contains all `coordinates`.
+    /// Returns `None` if there are no coordinates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::primitives::{Coordinate2D, BoundingBox2D};
+    ///
+    /// let coordinates = [Coordinate2D::new(1.0, 3.0), Coordinate2D::new(2.0, 1.0)];
+    /// let bbox = BoundingBox2D::from_coordinates(&coordinates).unwrap();
+    ///
+    /// assert_eq!(bbox, BoundingBox2D::new((1.0, 1.0).into(), (2.0, 3.0).into()).unwrap());
+    /// ```
+    ///
+    pub fn from_coordinates<'c, I>(coordinates: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'c Coordinate2D>,
+    {
+        let mut coordinates = coordinates.into_iter();
+        let first = *coordinates.next()?;
+
+        Some(
+            coordinates.fold(Self::new_unchecked(first, first), |bbox, coordinate| {
+                bbox.union(&Self::new_unchecked(*coordinate, *coordinate))
+            }),
+        )
+    }
+
+    /// Returns the smallest bounding box that contains both bounding boxes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::primitives::{Coordinate2D, BoundingBox2D};
+    ///
+    /// let bbox1 = BoundingBox2D::new((1.0, 1.0).into(), (2.0, 2.0).into()).unwrap();
+    /// let bbox2 = BoundingBox2D::new((3.0, 0.0).into(), (4.0, 1.0).into()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     bbox1.union(&bbox2),
+    ///     BoundingBox2D::new((1.0, 0.0).into(), (4.0, 2.0).into()).unwrap()
+    /// );
+    /// ```
+    ///
+    pub fn union(&self, other_bbox: &Self) -> Self {
+        Self::new_unchecked(
+            Coordinate2D::new(
+                self.lower_left_coordinate
+                    .x
+                    .min(other_bbox.lower_left_coordinate.x),
+                self.lower_left_coordinate
+                    .y
+                    .min(other_bbox.lower_left_coordinate.y),
+            ),
+            Coordinate2D::new(
+                self.upper_right_coordinate
+                    .x
+                    .max(other_bbox.upper_right_coordinate.x),
+                self.upper_right_coordinate
+                    .y
+                    .max(other_bbox.upper_right_coordinate.y),
+            ),
+        )
+    }
+
     /// Returns the `Coordnate2D` representing the lower left edge of the bounding box
     ///
     /// # Examples
@@ -0,0 +1,179 @@
+use crate::primitives::error;
+use crate::util::Result;
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+use std::collections::HashMap;
+
+/// The labels of a dictionary-encoded categorical column.
+///
+/// Categorical columns store `u8` codes, the lookup maps each code to its label, e.g., the name of
+/// a land cover class.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryLookup {
+    labels: Vec<String>,
+}
+
+impl CategoryLookup {
+    /// Creates a lookup where the code of each label is its position in `labels`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::primitives::CategoryLookup;
+    ///
+    /// let lookup = CategoryLookup::new(vec!["forest".to_string(), "water".to_string()]).unwrap();
+    ///
+    /// assert_eq!(lookup.label(1), Some("water"));
+    /// assert_eq!(lookup.code("forest"), Some(0));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This constructor fails if there are more than 256 labels or if a label occurs twice
+    ///
+    pub fn new(labels: Vec<String>) -> Result<Self> {
+        ensure!(
+            labels.len() <= usize::from(u8::MAX) + 1,
+            error::TooManyCategories {
+                count: labels.len()
+            }
+        );
+
+        for (i, label) in labels.iter().enumerate() {
+            ensure!(
+                !labels[..i].contains(label),
+                error::DuplicateCategoryLabel {
+                    label: label.clone()
+                }
+            );
+        }
+
+        Ok(Self { labels })
+    }
+
+    /// Dictionary-encode `values` into codes and the lookup of their labels.
+    /// Codes are assigned in the order of the first occurrence of each label.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::primitives::CategoryLookup;
+    ///
+    /// let (lookup, codes) = CategoryLookup::encode(&["water", "forest", "water"]).unwrap();
+    ///
+    /// assert_eq!(codes, vec![0, 1, 0]);
+    /// assert_eq!(lookup.labels(), &["water".to_string(), "forest".to_string()]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if there are more than 256 distinct values
+    ///
+    pub fn encode<S: AsRef<str>>(values: &[S]) -> Result<(Self, Vec<u8>)> {
+        let (lookup, codes) = Self::encode_nullable(
+            &values
+                .iter()
+                .map(|value| Some(value.as_ref()))
+                .collect::<Vec<_>>(),
+        )?;
+
+        Ok((lookup, codes.into_iter().flatten().collect()))
+    }
+
+    /// Dictionary-encode `values` that may contain nulls, see [`CategoryLookup::encode`]
+    ///
+    /// # Errors
+    ///
+    /// This method fails if there are more than 256 distinct values
+    ///
+    pub fn encode_nullable<S: AsRef<str>>(values: &[Option<S>]) -> Result<(Self, Vec<Option<u8>>)> {
+        let mut labels = Vec::new();
+        let mut codes_of_labels = HashMap::<&str, u8>::new();
+        let mut codes = Vec::with_capacity(values.len());
+
+        for value in values {
+            let value = match value {
+                Some(value) => value.as_ref(),
+                None => {
+                    codes.push(None);
+                    continue;
+                }
+            };
+
+            let code = match codes_of_labels.get(value) {
+                Some(&code) => code,
+                None => {
+                    ensure!(
+                        labels.len() <= usize::from(u8::MAX),
+                        error::TooManyCategories {
+                            count: labels.len() + 1
+                        }
+                    );
+
+                    let code = labels.len() as u8;
+                    labels.push(value.to_string());
+                    codes_of_labels.insert(value, code);
+                    code
+                }
+            };
+
+            codes.push(Some(code));
+        }
+
+        Ok((Self { labels }, codes))
+    }
+
+    /// The label of the category `code`
+    pub fn label(&self, code: u8) -> Option<&str> {
+        self.labels.get(usize::from(code)).map(String::as_str)
+    }
+
+    /// The code of the category `label`
+    pub fn code(&self, label: &str) -> Option<u8> {
+        self.labels
+            .iter()
+            .position(|l| l == label)
+            .map(|code| code as u8)
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_nullable() {
+        let (lookup, codes) =
+            CategoryLookup::encode_nullable(&[Some("b"), None, Some("a"), Some("b")]).unwrap();
+
+        assert_eq!(codes, vec![Some(0), None, Some(1), Some(0)]);
+        assert_eq!(lookup.label(1), Some("a"));
+        assert_eq!(lookup.label(2), None);
+    }
+
+    #[test]
+    fn too_many_categories() {
+        let values: Vec<String> = (0..257).map(|i| i.to_string()).collect();
+
+        assert!(CategoryLookup::encode(&values[..256]).is_ok());
+        assert!(CategoryLookup::encode(&values).is_err());
+        assert!(CategoryLookup::new(values).is_err());
+    }
+
+    #[test]
+    fn duplicate_labels() {
+        assert!(CategoryLookup::new(vec!["a".to_string(), "a".to_string()]).is_err());
+    }
+}
@@ -10,7 +10,13 @@ pub enum PrimitivesError {
     InvalidSpatialResolution {
         value: f64,
     },
-    #[snafu(display("Arrow internal error: {:?}", source))]
+    TooManyCategories {
+        count: usize,
+    },
+    DuplicateCategoryLabel {
+        label: String,
+    },
+    #[snafu(display("Arrow internal error"))]
     ArrowInternal {
         source: ArrowError,
     },
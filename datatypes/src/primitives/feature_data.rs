@@ -28,7 +28,7 @@ pub enum FeatureData {
     NullableNumber(Vec<Option<f64>>),
     Decimal(Vec<i64>),
     NullableDecimal(Vec<Option<i64>>),
-    Categorical(Vec<u8>), // TODO: add names to categories
+    Categorical(Vec<u8>), // labels are attached to collections as `CategoryLookup`s
     NullableCategorical(Vec<Option<u8>>),
 }
 
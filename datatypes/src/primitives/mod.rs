@@ -1,4 +1,5 @@
 mod bounding_box;
+mod category_lookup;
 mod coordinate;
 pub(self) mod error;
 mod feature_data;
@@ -7,6 +8,7 @@ mod multi_line_string;
 mod multi_point;
 mod multi_polygon;
 mod no_geometry;
+mod spatial_partition;
 mod spatial_resolution;
 mod spatio_temporal_bounded;
 mod time_instance;
@@ -14,6 +16,7 @@ mod time_interval;
 
 use crate::collections::VectorDataType;
 pub use bounding_box::BoundingBox2D;
+pub use category_lookup::CategoryLookup;
 pub use coordinate::Coordinate2D;
 pub(crate) use error::PrimitivesError;
 pub use feature_data::{
@@ -26,6 +29,7 @@ pub use multi_line_string::{MultiLineString, MultiLineStringAccess, MultiLineStr
 pub use multi_point::{MultiPoint, MultiPointAccess, MultiPointRef};
 pub use multi_polygon::{MultiPolygon, MultiPolygonAccess, MultiPolygonRef};
 pub use no_geometry::NoGeometry;
+pub use spatial_partition::{SpatialPartition2D, SpatialPartitioned};
 pub use spatial_resolution::SpatialResolution;
 pub use spatio_temporal_bounded::{SpatialBounded, TemporalBounded};
 use std::fmt::Debug;
@@ -0,0 +1,296 @@
+use super::{BoundingBox2D, Coordinate2D};
+use crate::error;
+use crate::raster::GeoTransform;
+use crate::util::Result;
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+/// An axis-aligned region of pixels, e.g., of a raster query or a tile.
+///
+/// In contrast to the closed `BoundingBox2D` of vector data, the upper left corner belongs to
+/// the partition but the lower right corner does not. Thus, adjacent tiles do not share any
+/// coordinate and a coordinate on a tile edge belongs to exactly one tile.
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SpatialPartition2D {
+    upper_left_coordinate: Coordinate2D,
+    lower_right_coordinate: Coordinate2D,
+}
+
+impl SpatialPartition2D {
+    /// Creates a new spatial partition
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::primitives::{Coordinate2D, SpatialPartition2D};
+    ///
+    /// let ul = Coordinate2D::new(1.0, 2.0);
+    /// let lr = Coordinate2D::new(2.0, 1.0);
+    /// let partition = SpatialPartition2D::new(ul, lr).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This constructor fails if the coordinate's values are not in order
+    ///
+    pub fn new(
+        upper_left_coordinate: Coordinate2D,
+        lower_right_coordinate: Coordinate2D,
+    ) -> Result<Self> {
+        ensure!(
+            upper_left_coordinate.x <= lower_right_coordinate.x
+                && upper_left_coordinate.y >= lower_right_coordinate.y,
+            error::InvalidSpatialPartition {
+                upper_left_coordinate,
+                lower_right_coordinate
+            }
+        );
+        Ok(Self {
+            upper_left_coordinate,
+            lower_right_coordinate,
+        })
+    }
+
+    /// Creates a new spatial partition unchecked
+    pub fn new_unchecked(
+        upper_left_coordinate: Coordinate2D,
+        lower_right_coordinate: Coordinate2D,
+    ) -> Self {
+        Self {
+            upper_left_coordinate,
+            lower_right_coordinate,
+        }
+    }
+
+    /// The partition of the pixels of `geo_transform` that cover `bbox`, i.e., the bbox
+    /// expanded to the pixel edges
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::primitives::{BoundingBox2D, SpatialPartition2D};
+    /// use geoengine_datatypes::raster::GeoTransform;
+    ///
+    /// let geo_transform = GeoTransform::new((0.0, 10.0).into(), 1.0, -1.0);
+    /// let bbox = BoundingBox2D::new((0.5, 0.5).into(), (2.5, 2.5).into()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     SpatialPartition2D::snapped(&bbox, &geo_transform),
+    ///     SpatialPartition2D::new((0.0, 3.0).into(), (3.0, 0.0).into()).unwrap()
+    /// );
+    /// ```
+    pub fn snapped(bbox: &BoundingBox2D, geo_transform: &GeoTransform) -> Self {
+        let origin = geo_transform.upper_left_coordinate;
+        let x_pixel_size = geo_transform.x_pixel_size.abs();
+        let y_pixel_size = geo_transform.y_pixel_size.abs();
+
+        let snap_down =
+            |value: f64, origin: f64, size: f64| origin + ((value - origin) / size).floor() * size;
+        let snap_up =
+            |value: f64, origin: f64, size: f64| origin + ((value - origin) / size).ceil() * size;
+
+        Self {
+            upper_left_coordinate: Coordinate2D::new(
+                snap_down(bbox.upper_left().x, origin.x, x_pixel_size),
+                snap_up(bbox.upper_left().y, origin.y, y_pixel_size),
+            ),
+            lower_right_coordinate: Coordinate2D::new(
+                snap_up(bbox.lower_right().x, origin.x, x_pixel_size),
+                snap_down(bbox.lower_right().y, origin.y, y_pixel_size),
+            ),
+        }
+    }
+
+    pub fn upper_left(&self) -> Coordinate2D {
+        self.upper_left_coordinate
+    }
+
+    pub fn lower_right(&self) -> Coordinate2D {
+        self.lower_right_coordinate
+    }
+
+    pub fn size_x(&self) -> f64 {
+        self.lower_right_coordinate.x - self.upper_left_coordinate.x
+    }
+
+    pub fn size_y(&self) -> f64 {
+        self.upper_left_coordinate.y - self.lower_right_coordinate.y
+    }
+
+    /// Checks if the partition contains the coordinate, which excludes the right and lower
+    /// edges
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::primitives::SpatialPartition2D;
+    ///
+    /// let partition = SpatialPartition2D::new((0.0, 1.0).into(), (1.0, 0.0).into()).unwrap();
+    ///
+    /// assert!(partition.contains_coordinate(&(0.0, 1.0).into()));
+    /// assert!(!partition.contains_coordinate(&(1.0, 0.5).into()));
+    /// assert!(!partition.contains_coordinate(&(0.5, 0.0).into()));
+    /// ```
+    pub fn contains_coordinate(&self, coordinate: &Coordinate2D) -> bool {
+        coordinate.x >= self.upper_left_coordinate.x
+            && coordinate.x < self.lower_right_coordinate.x
+            && coordinate.y <= self.upper_left_coordinate.y
+            && coordinate.y > self.lower_right_coordinate.y
+    }
+
+    /// Checks if the partitions share a region.
+    ///
+    /// Partitions that only touch do not intersect. A partition without extent along an axis
+    /// is treated as the line or point at its upper left corner.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::primitives::SpatialPartition2D;
+    ///
+    /// let left = SpatialPartition2D::new((0.0, 1.0).into(), (1.0, 0.0).into()).unwrap();
+    /// let right = SpatialPartition2D::new((1.0, 1.0).into(), (2.0, 0.0).into()).unwrap();
+    /// let center = SpatialPartition2D::new((0.5, 1.0).into(), (1.5, 0.0).into()).unwrap();
+    ///
+    /// assert!(!left.intersects(&right));
+    /// assert!(left.intersects(&center));
+    /// assert!(right.intersects(&center));
+    /// ```
+    pub fn intersects(&self, other: &Self) -> bool {
+        // the y axis is flipped to keep the lower bound inclusive
+        half_open_ranges_intersect(
+            (self.upper_left_coordinate.x, self.lower_right_coordinate.x),
+            (
+                other.upper_left_coordinate.x,
+                other.lower_right_coordinate.x,
+            ),
+        ) && half_open_ranges_intersect(
+            (
+                -self.upper_left_coordinate.y,
+                -self.lower_right_coordinate.y,
+            ),
+            (
+                -other.upper_left_coordinate.y,
+                -other.lower_right_coordinate.y,
+            ),
+        )
+    }
+
+    /// Returns `Some(intersection)` with `other` or `None` if they do not intersect
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        Some(Self {
+            upper_left_coordinate: Coordinate2D::new(
+                f64::max(self.upper_left_coordinate.x, other.upper_left_coordinate.x),
+                f64::min(self.upper_left_coordinate.y, other.upper_left_coordinate.y),
+            ),
+            lower_right_coordinate: Coordinate2D::new(
+                f64::min(
+                    self.lower_right_coordinate.x,
+                    other.lower_right_coordinate.x,
+                ),
+                f64::max(
+                    self.lower_right_coordinate.y,
+                    other.lower_right_coordinate.y,
+                ),
+            ),
+        })
+    }
+
+    /// The closed bounding box of the partition, e.g., for querying vector data
+    pub fn as_bbox(&self) -> BoundingBox2D {
+        BoundingBox2D::new_upper_left_lower_right_unchecked(
+            self.upper_left_coordinate,
+            self.lower_right_coordinate,
+        )
+    }
+}
+
+/// Checks if `[a_start, a_end)` and `[b_start, b_end)` intersect, where an empty range is
+/// treated as the single value of its start
+fn half_open_ranges_intersect((a_start, a_end): (f64, f64), (b_start, b_end): (f64, f64)) -> bool {
+    #[allow(clippy::float_cmp)]
+    match (a_start == a_end, b_start == b_end) {
+        (true, true) => a_start == b_start,
+        (true, false) => b_start <= a_start && a_start < b_end,
+        (false, true) => a_start <= b_start && b_start < a_end,
+        (false, false) => a_start < b_end && b_start < a_end,
+    }
+}
+
+impl From<BoundingBox2D> for SpatialPartition2D {
+    /// The partition with the corners of the bounding box, i.e., without its right and lower
+    /// edges
+    fn from(bbox: BoundingBox2D) -> Self {
+        Self {
+            upper_left_coordinate: bbox.upper_left(),
+            lower_right_coordinate: bbox.lower_right(),
+        }
+    }
+}
+
+pub trait SpatialPartitioned {
+    fn spatial_partition(&self) -> SpatialPartition2D;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partition(ul: (f64, f64), lr: (f64, f64)) -> SpatialPartition2D {
+        SpatialPartition2D::new(ul.into(), lr.into()).unwrap()
+    }
+
+    #[test]
+    fn invalid_order() {
+        assert!(SpatialPartition2D::new((1.0, 1.0).into(), (0.0, 0.0).into()).is_err());
+        assert!(SpatialPartition2D::new((0.0, 0.0).into(), (1.0, 1.0).into()).is_err());
+    }
+
+    #[test]
+    fn adjacent_partitions_do_not_intersect() {
+        let upper = partition((0., 2.), (1., 1.));
+        let lower = partition((0., 1.), (1., 0.));
+        let diagonal = partition((1., 1.), (2., 0.));
+
+        assert!(!upper.intersects(&lower));
+        assert!(!lower.intersects(&upper));
+        assert!(!upper.intersects(&diagonal));
+        assert!(upper.intersection(&lower).is_none());
+    }
+
+    #[test]
+    fn points_belong_to_one_partition() {
+        let left = partition((0., 1.), (1., 0.));
+        let right = partition((1., 1.), (2., 0.));
+        let edge_point = partition((1., 0.5), (1., 0.5));
+
+        assert!(!left.intersects(&edge_point));
+        assert!(right.intersects(&edge_point));
+        assert!(edge_point.intersects(&right));
+    }
+
+    #[test]
+    fn intersection() {
+        let a = partition((0., 10.), (10., 0.));
+        let b = partition((5., 15.), (15., 5.));
+
+        assert_eq!(a.intersection(&b), Some(partition((5., 10.), (10., 5.))));
+        assert_eq!(a.intersection(&a), Some(a));
+    }
+
+    #[test]
+    fn snapped() {
+        let geo_transform = GeoTransform::new((-180., 90.).into(), 0.5, -0.5);
+
+        let bbox = BoundingBox2D::new((-10.25, 20.1).into(), (10., 30.).into()).unwrap();
+
+        assert_eq!(
+            SpatialPartition2D::snapped(&bbox, &geo_transform),
+            partition((-10.5, 30.), (10., 20.))
+        );
+    }
+}
@@ -5,7 +5,7 @@ use crate::raster::{
 use crate::util::Result;
 use crate::{
     error,
-    primitives::{BoundingBox2D, SpatialBounded, TemporalBounded, TimeInterval},
+    primitives::{BoundingBox2D, Coordinate2D, SpatialBounded, TemporalBounded, TimeInterval},
 };
 use num_traits::AsPrimitive;
 use serde::{Deserialize, Serialize};
@@ -76,6 +76,15 @@ where
         })
     }
 
+    /// Whether `value` is the no-data value of this raster, where a NaN no-data value matches NaN values
+    #[allow(clippy::float_cmp)]
+    pub fn is_no_data(&self, value: T) -> bool {
+        let is_nan = |value: T| AsPrimitive::<f64>::as_(value).is_nan();
+        self.no_data_value.map_or(false, |no_data_value| {
+            value == no_data_value || (is_nan(value) && is_nan(no_data_value))
+        })
+    }
+
     /// Converts the data type of the raster by converting it pixel-wise
     /// TODO: Is is correct to always return a `Vec`?
     pub fn convert<To>(self) -> BaseRaster<D, To, Vec<To>>
@@ -173,6 +182,69 @@ where
 pub type Raster2D<T> = BaseRaster<Dim<[usize; 2]>, T, Vec<T>>;
 pub type Raster3D<T> = BaseRaster<Dim<[usize; 3]>, T, Vec<T>>;
 
+impl<T: Pixel> Raster2D<T> {
+    /// The value of the pixel in column `x` and row `y`
+    ///
+    /// # Panics
+    ///
+    /// If the pixel lies outside of the raster
+    ///
+    pub fn pixel(&self, x: usize, y: usize) -> T {
+        self.pixel_checked(x, y).unwrap_or_else(|| {
+            panic!(
+                "pixel ({}, {}) lies outside of the raster of size {:?}",
+                x,
+                y,
+                self.grid_dimension.dimension_size()
+            )
+        })
+    }
+
+    /// The value of the pixel in column `x` and row `y` or `None` if it lies outside of the raster
+    pub fn pixel_checked(&self, x: usize, y: usize) -> Option<T> {
+        let [rows, columns] = *self.grid_dimension.dimension_size();
+        if x < columns && y < rows {
+            Some(self.data_container[y * columns + x])
+        } else {
+            None
+        }
+    }
+
+    /// All pixels row by row with their column and row, i.e., `((x, y), value)`
+    pub fn pixels(&self) -> impl Iterator<Item = ((usize, usize), T)> + '_ {
+        let columns = self.grid_dimension.size_of_x_axis();
+        self.data_container
+            .iter()
+            .enumerate()
+            .map(move |(index, &value)| ((index % columns, index / columns), value))
+    }
+
+    /// All pixels row by row with the coordinate of their upper left corner
+    pub fn pixels_with_coordinates(&self) -> impl Iterator<Item = (Coordinate2D, T)> + '_ {
+        self.pixels().map(move |((x, y), value)| {
+            (self.geo_transform.grid_2d_to_coordinate_2d((y, x)), value)
+        })
+    }
+
+    /// A raster of the same size and location with `f` applied to every pixel.
+    ///
+    /// The no-data value is mapped by `f` as well, so no-data pixels stay no-data pixels if `f`
+    /// is injective.
+    pub fn map_pixels<To, F>(&self, f: F) -> Raster2D<To>
+    where
+        To: Pixel,
+        F: Fn(T) -> To,
+    {
+        Raster2D {
+            grid_dimension: self.grid_dimension,
+            data_container: self.data_container.iter().map(|&pixel| f(pixel)).collect(),
+            no_data_value: self.no_data_value.map(&f),
+            geo_transform: self.geo_transform,
+            temporal_bounds: self.temporal_bounds,
+        }
+    }
+}
+
 impl<T: Send + Debug> GenericRaster for Raster2D<T>
 where
     T: Pixel,
@@ -185,6 +257,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::{Dim, GridPixelAccess, GridPixelAccessMut, Raster2D, TimeInterval};
+    use crate::primitives::Coordinate2D;
 
     #[test]
     fn simple_raster_2d() {
@@ -265,4 +338,64 @@ mod tests {
         assert_eq!(value, 9);
         assert_eq!(raster2d.data_container, [1, 2, 3, 9, 5, 6]);
     }
+
+    fn raster_3x2() -> Raster2D<u8> {
+        Raster2D::new(
+            [2, 3].into(),
+            vec![1, 2, 3, 4, 5, 6],
+            Some(0),
+            TimeInterval::default(),
+            [0.0, 1.0, 0.0, 10.0, 0.0, -1.0].into(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn pixel_accessors() {
+        let raster = raster_3x2();
+
+        assert_eq!(raster.pixel(0, 0), 1);
+        assert_eq!(raster.pixel(2, 0), 3);
+        assert_eq!(raster.pixel(1, 1), 5);
+
+        assert_eq!(raster.pixel_checked(2, 1), Some(6));
+        assert_eq!(raster.pixel_checked(3, 0), None);
+        assert_eq!(raster.pixel_checked(0, 2), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn pixel_outside() {
+        raster_3x2().pixel(3, 0);
+    }
+
+    #[test]
+    fn pixel_iterators() {
+        let raster = raster_3x2();
+
+        assert_eq!(
+            raster.pixels().collect::<Vec<_>>(),
+            vec![
+                ((0, 0), 1),
+                ((1, 0), 2),
+                ((2, 0), 3),
+                ((0, 1), 4),
+                ((1, 1), 5),
+                ((2, 1), 6)
+            ]
+        );
+
+        let coordinates: Vec<_> = raster.pixels_with_coordinates().collect();
+        assert_eq!(coordinates[0], (Coordinate2D::new(0., 10.), 1));
+        assert_eq!(coordinates[5], (Coordinate2D::new(2., 9.), 6));
+    }
+
+    #[test]
+    fn map_pixels() {
+        let raster = raster_3x2().map_pixels(|pixel| f64::from(pixel) * 0.5);
+
+        assert_eq!(raster.data_container, vec![0.5, 1., 1.5, 2., 2.5, 3.]);
+        assert_eq!(raster.no_data_value, Some(0.));
+        assert_eq!(raster.geo_transform, raster_3x2().geo_transform);
+    }
 }
@@ -1,4 +1,5 @@
 use crate::operations::image::RgbaTransmutable;
+use half::f16;
 use num_traits::{AsPrimitive, Num};
 use serde::{Deserialize, Serialize};
 
@@ -63,6 +64,7 @@ impl Pixel for u32 {}
 impl Pixel for i32 {}
 impl Pixel for u64 {}
 impl Pixel for i64 {}
+impl Pixel for f16 {}
 impl Pixel for f32 {}
 impl Pixel for f64 {}
 
@@ -76,6 +78,7 @@ pub enum RasterDataType {
     I16,
     I32,
     I64,
+    F16,
     F32,
     F64,
 }
@@ -89,6 +92,7 @@ pub enum TypedValue {
     I16(i16),
     I32(i32),
     I64(i64),
+    F16(f16),
     F32(f32),
     F64(f64),
 }
@@ -130,6 +134,10 @@ impl StaticRasterDataType for i64 {
     const TYPE: RasterDataType = RasterDataType::I64;
 }
 
+impl StaticRasterDataType for f16 {
+    const TYPE: RasterDataType = RasterDataType::F16;
+}
+
 impl StaticRasterDataType for f32 {
     const TYPE: RasterDataType = RasterDataType::F32;
 }
@@ -138,6 +146,35 @@ impl StaticRasterDataType for f64 {
     const TYPE: RasterDataType = RasterDataType::F64;
 }
 
+impl RasterDataType {
+    /// The Arrow data type of the pixels of this raster data type
+    pub fn arrow_data_type(self) -> arrow::datatypes::DataType {
+        match self {
+            Self::U8 => arrow::datatypes::DataType::UInt8,
+            Self::U16 => arrow::datatypes::DataType::UInt16,
+            Self::U32 => arrow::datatypes::DataType::UInt32,
+            Self::U64 => arrow::datatypes::DataType::UInt64,
+            Self::I8 => arrow::datatypes::DataType::Int8,
+            Self::I16 => arrow::datatypes::DataType::Int16,
+            Self::I32 => arrow::datatypes::DataType::Int32,
+            Self::I64 => arrow::datatypes::DataType::Int64,
+            Self::F16 => arrow::datatypes::DataType::Float16,
+            Self::F32 => arrow::datatypes::DataType::Float32,
+            Self::F64 => arrow::datatypes::DataType::Float64,
+        }
+    }
+
+    /// The size of a single pixel in bytes
+    pub fn size_in_bytes(self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 | Self::F16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::U64 | Self::I64 | Self::F64 => 8,
+        }
+    }
+}
+
 pub trait DynamicRasterDataType {
     fn raster_data_type(&self) -> RasterDataType;
 }
@@ -150,3 +187,26 @@ where
         R::TYPE
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn f16_conversions() {
+        let pixel = f16::from_f32(1.5);
+
+        assert_eq!(f16::TYPE, RasterDataType::F16);
+        assert_eq!(AsPrimitive::<f64>::as_(pixel), 1.5);
+        assert_eq!(AsPrimitive::<u8>::as_(pixel), 1);
+        assert_eq!(<f16 as FromPrimitive<u8>>::from_(42_u8), f16::from_f32(42.));
+        assert_eq!(<f32 as FromPrimitive<f16>>::from_(pixel), 1.5);
+
+        assert_eq!(
+            RasterDataType::F16.arrow_data_type(),
+            arrow::datatypes::DataType::Float16
+        );
+        assert_eq!(RasterDataType::F16.size_in_bytes(), 2);
+    }
+}
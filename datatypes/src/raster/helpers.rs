@@ -1,45 +1,3 @@
-/// Maps a `TypedRaster2D` to another `TypedRaster2D` by calling a function on its variant.
-/// Call via `map_generic_raster2d!(input, raster => function)`.
-#[macro_export]
-macro_rules! map_generic_raster2d {
-    ($input_raster:expr, $raster:ident => $function_call:expr) => {
-        map_generic_raster2d!(
-            @variants $input_raster, $raster => $function_call,
-            U8, U16, U32, U64, I8, I16, I32, I64, F32, F64
-        )
-    };
-
-    (@variants $input_raster:expr, $raster:ident => $function_call:expr, $($variant:tt),+) => {
-        match $input_raster {
-            $(
-                $crate::raster::TypedRaster2D::$variant($raster) => {
-                    $crate::raster::TypedRaster2D::$variant($function_call)
-                }
-            )+
-        }
-    };
-}
-
-/// Calls a function on a `TypedRaster2D` by calling it on its variant.
-/// Call via `call_generic_raster2d!(input, raster => function)`.
-#[macro_export]
-macro_rules! call_generic_raster2d {
-    ($input_raster:expr, $raster:ident => $function_call:expr) => {
-        call_generic_raster2d!(
-            @variants $input_raster, $raster => $function_call,
-            U8, U16, U32, U64, I8, I16, I32, I64, F32, F64
-        )
-    };
-
-    (@variants $input_raster:expr, $raster:ident => $function_call:expr, $($variant:tt),+) => {
-        match $input_raster {
-            $(
-                $crate::raster::TypedRaster2D::$variant($raster) => $function_call,
-            )+
-        }
-    };
-}
-
 /// Calls a function on two `TypedRaster2D`s by calling it on their variant combination.
 /// Call via `call_bi_generic_raster2d!(input, (raster_a, raster_b) => function)`.
 #[macro_export]
@@ -53,16 +11,17 @@ macro_rules! call_bi_generic_raster2d {
             @variants
             $input_a, $input_b,
             ( $raster_a, $raster_b ) => $function_call,
-            (U8, U8), (U8, U16), (U8, U32), (U8, U64), (U8, I8), (U8, I16), (U8, I32), (U8, I64), (U8, F32), (U8, F64),
-            (U16, U8), (U16, U16), (U16, U32), (U16, U64), (U16, I8), (U16, I16), (U16, I32), (U16, I64), (U16, F32), (U16, F64),
-            (U32, U8), (U32, U16), (U32, U32), (U32, U64), (U32, I8), (U32, I16), (U32, I32), (U32, I64), (U32, F32), (U32, F64),
-            (U64, U8), (U64, U16), (U64, U32), (U64, U64), (U64, I8), (U64, I16), (U64, I32), (U64, I64), (U64, F32), (U64, F64),
-            (I8, U8), (I8, U16), (I8, U32), (I8, U64), (I8, I8), (I8, I16), (I8, I32), (I8, I64), (I8, F32), (I8, F64),
-            (I16, U8), (I16, U16), (I16, U32), (I16, U64), (I16, I8), (I16, I16), (I16, I32), (I16, I64), (I16, F32), (I16, F64),
-            (I32, U8), (I32, U16), (I32, U32), (I32, U64), (I32, I8), (I32, I16), (I32, I32), (I32, I64), (I32, F32), (I32, F64),
-            (I64, U8), (I64, U16), (I64, U32), (I64, U64), (I64, I8), (I64, I16), (I64, I32), (I64, I64), (I64, F32), (I64, F64),
-            (F32, U8), (F32, U16), (F32, U32), (F32, U64), (F32, I8), (F32, I16), (F32, I32), (F32, I64), (F32, F32), (F32, F64),
-            (F64, U8), (F64, U16), (F64, U32), (F64, U64), (F64, I8), (F64, I16), (F64, I32), (F64, I64), (F64, F32), (F64, F64)
+            (U8, U8), (U8, U16), (U8, U32), (U8, U64), (U8, I8), (U8, I16), (U8, I32), (U8, I64), (U8, F16), (U8, F32), (U8, F64),
+            (U16, U8), (U16, U16), (U16, U32), (U16, U64), (U16, I8), (U16, I16), (U16, I32), (U16, I64), (U16, F16), (U16, F32), (U16, F64),
+            (U32, U8), (U32, U16), (U32, U32), (U32, U64), (U32, I8), (U32, I16), (U32, I32), (U32, I64), (U32, F16), (U32, F32), (U32, F64),
+            (U64, U8), (U64, U16), (U64, U32), (U64, U64), (U64, I8), (U64, I16), (U64, I32), (U64, I64), (U64, F16), (U64, F32), (U64, F64),
+            (I8, U8), (I8, U16), (I8, U32), (I8, U64), (I8, I8), (I8, I16), (I8, I32), (I8, I64), (I8, F16), (I8, F32), (I8, F64),
+            (I16, U8), (I16, U16), (I16, U32), (I16, U64), (I16, I8), (I16, I16), (I16, I32), (I16, I64), (I16, F16), (I16, F32), (I16, F64),
+            (I32, U8), (I32, U16), (I32, U32), (I32, U64), (I32, I8), (I32, I16), (I32, I32), (I32, I64), (I32, F16), (I32, F32), (I32, F64),
+            (I64, U8), (I64, U16), (I64, U32), (I64, U64), (I64, I8), (I64, I16), (I64, I32), (I64, I64), (I64, F16), (I64, F32), (I64, F64),
+            (F16, U8), (F16, U16), (F16, U32), (F16, U64), (F16, I8), (F16, I16), (F16, I32), (F16, I64), (F16, F16), (F16, F32), (F16, F64),
+            (F32, U8), (F32, U16), (F32, U32), (F32, U64), (F32, I8), (F32, I16), (F32, I32), (F32, I64), (F32, F16), (F32, F32), (F32, F64),
+            (F64, U8), (F64, U16), (F64, U32), (F64, U64), (F64, I8), (F64, I16), (F64, I32), (F64, I64), (F64, F16), (F64, F32), (F64, F64)
         )
     };
 
@@ -110,7 +69,7 @@ macro_rules! call_bi_generic_raster2d_same {
             $input_a, $input_b,
             ( $raster_a, $raster_b ) => $function_call,
             $on_error,
-            U8, U16, U32, U64, I8, I16, I32, I64, F32, F64
+            U8, U16, U32, U64, I8, I16, I32, I64, F16, F32, F64
         )
     };
 
@@ -168,8 +127,9 @@ macro_rules! call_bi_generic_raster2d_staircase {
             (I16, U8), (I16, I8), (I16, I16),
             (I32, U8), (I32, U16), (I32, I8), (I32, I16), (I32, I32),
             (I64, U8), (I64, U16), (I64, U32), (I64, I8), (I64, I16), (I64, I32), (I64, I64),
-            (F32, U8), (F32, U16), (F32, I8), (F32, I16), (F32, F32),
-            (F64, U8), (F64, U16), (F64, U32), (F64, I8), (F64, I16), (F64, I32), (F64, F32), (F64, F64)
+            (F16, U8), (F16, I8), (F16, F16),
+            (F32, U8), (F32, U16), (F32, I8), (F32, I16), (F32, F16), (F32, F32),
+            (F64, U8), (F64, U16), (F64, U32), (F64, I8), (F64, I16), (F64, I32), (F64, F16), (F64, F32), (F64, F64)
         )
     };
 
@@ -199,7 +159,7 @@ macro_rules! generate_generic_raster2d {
     ($type_enum:expr, $function_call:expr) => {
         generate_generic_raster2d!(
             @variants $type_enum, $function_call,
-            U8, U16, U32, U64, I8, I16, I32, I64, F32, F64
+            U8, U16, U32, U64, I8, I16, I32, I64, F16, F32, F64
         )
     };
 
@@ -219,57 +179,6 @@ mod tests {
     use crate::raster::{GridPixelAccess, Pixel, Raster2D, RasterDataType, TypedRaster2D};
     use crate::util::test::catch_unwind_silent;
 
-    #[test]
-    fn map_generic_raster2d() {
-        fn map<T: Pixel>(raster: Raster2D<T>) -> Raster2D<T> {
-            raster
-        }
-
-        let typed_raster = TypedRaster2D::U32(
-            Raster2D::new(
-                [3, 2].into(),
-                vec![1, 2, 3, 4, 5, 6],
-                None,
-                Default::default(),
-                [1.0, 1.0, 0.0, 1.0, 0.0, 1.0].into(),
-            )
-            .unwrap(),
-        );
-
-        assert_eq!(
-            typed_raster,
-            map_generic_raster2d!(typed_raster.clone(), raster => map(raster))
-        );
-    }
-
-    #[test]
-    fn call_generic_raster2d() {
-        fn first_pixel<T: Pixel>(raster: &Raster2D<T>) -> i64 {
-            raster.pixel_value_at_grid_index(&(0, 0)).unwrap().as_()
-        }
-
-        let typed_raster = TypedRaster2D::U32(
-            Raster2D::new(
-                [3, 2].into(),
-                vec![1, 2, 3, 4, 5, 6],
-                None,
-                Default::default(),
-                [1.0, 1.0, 0.0, 1.0, 0.0, 1.0].into(),
-            )
-            .unwrap(),
-        );
-
-        assert_eq!(
-            call_generic_raster2d!(typed_raster.clone(), _raster => 2),
-            2
-        );
-
-        assert_eq!(
-            call_generic_raster2d!(typed_raster, raster => first_pixel(&raster)),
-            1
-        );
-    }
-
     #[test]
     fn generate_generic_raster2d() {
         fn generate<T: Pixel>() -> Raster2D<T> {
@@ -5,6 +5,8 @@ mod grid_dimension;
 mod helpers;
 mod operations;
 mod raster_tile;
+mod sparse_tile;
+mod tile_statistics;
 mod typed_raster;
 
 pub use self::base_raster::{BaseRaster, Raster2D, Raster3D};
@@ -14,9 +16,16 @@ pub use self::data_type::{
 pub use self::geo_transform::{GdalGeoTransform, GeoTransform};
 pub use self::grid_dimension::{Dim, Dim1D, Dim2D, Dim3D, GridDimension, GridIndex, Ix};
 pub use self::operations::blit::Blit;
-pub use self::typed_raster::{TypedRaster2D, TypedRaster3D};
+pub use self::operations::distance_transform::euclidean_distance_transform;
+pub use self::operations::rasterize::{FeatureMask, PolygonMask};
+pub use self::sparse_tile::{ConstantTile, EmptyTile, SparseRasterTile2D};
+pub use self::tile_statistics::TileStatistics;
+pub use self::typed_raster::{
+    Raster2DFn, Raster2DMapFn, Raster2DRefFn, TypedRaster2D, TypedRaster3D,
+};
 use super::primitives::{SpatialBounded, TemporalBounded};
 use crate::util::Result;
+pub use half::f16;
 pub use raster_tile::*;
 use std::fmt::Debug;
 
@@ -8,7 +8,8 @@ pub trait Blit<R> {
 }
 
 impl<T: Pixel> Blit<Raster2D<T>> for Raster2D<T> {
-    /// Copy `source` raster pixels into this raster, fails if the rasters do not overlap
+    /// Copy `source` raster pixels into this raster, fails if the rasters do not overlap.
+    /// No-data pixels of the `source` are skipped, so they keep the pixels of this raster.
     #[allow(clippy::float_cmp)]
     fn blit(&mut self, source: Raster2D<T>) -> Result<()> {
         // TODO: same crs
@@ -50,9 +51,19 @@ impl<T: Pixel> Blit<Raster2D<T>> for Raster2D<T> {
             let index_source = (start_source_y + y, start_source_x)
                 .grid_index_to_1d_index_unchecked(&source.dimension());
 
-            self.data_container.as_mut_slice()[index..index + width].copy_from_slice(
-                &source.data_container().as_slice()[index_source..index_source + width],
-            );
+            let target_row = &mut self.data_container.as_mut_slice()[index..index + width];
+            let source_row =
+                &source.data_container().as_slice()[index_source..index_source + width];
+
+            if source.no_data_value.is_some() {
+                for (target, &value) in target_row.iter_mut().zip(source_row) {
+                    if !source.is_no_data(value) {
+                        *target = value;
+                    }
+                }
+            } else {
+                target_row.copy_from_slice(source_row);
+            }
         }
 
         Ok(())
@@ -93,6 +104,9 @@ impl Blit<TypedRaster2D> for TypedRaster2D {
             crate::raster::typed_raster::TypedRasterNDim::I64(r) => {
                 r.blit(source.get_i64().expect("Must not fail!"))
             }
+            crate::raster::typed_raster::TypedRasterNDim::F16(r) => {
+                r.blit(source.get_f16().expect("Must not fail!"))
+            }
             crate::raster::typed_raster::TypedRasterNDim::F32(r) => {
                 r.blit(source.get_f32().expect("Must not fail!"))
             }
@@ -131,4 +145,56 @@ mod tests {
             vec![0, 0, 7, 7, 0, 0, 7, 7, 0, 0, 0, 0, 0, 0, 0, 0]
         );
     }
+
+    #[test]
+    fn blit_skips_no_data() {
+        let dim = [2, 2];
+        let geo_transform = GeoTransform::new((0.0, 10.0).into(), 5.0, -5.0);
+        let temporal_bounds: TimeInterval = TimeInterval::default();
+
+        let mut target = Raster2D::new(
+            dim.into(),
+            vec![1, 1, 1, 1],
+            Some(0),
+            temporal_bounds,
+            geo_transform,
+        )
+        .unwrap();
+        let source = Raster2D::new(
+            dim.into(),
+            vec![7, 255, 255, 8],
+            Some(255),
+            temporal_bounds,
+            geo_transform,
+        )
+        .unwrap();
+
+        target.blit(source).unwrap();
+        assert_eq!(*target.data_container(), vec![7, 1, 1, 8]);
+
+        let mut target = Raster2D::new(
+            dim.into(),
+            vec![f64::NAN; 4],
+            Some(f64::NAN),
+            temporal_bounds,
+            geo_transform,
+        )
+        .unwrap();
+        let source = Raster2D::new(
+            dim.into(),
+            vec![1., f64::NAN, 2., f64::NAN],
+            Some(f64::NAN),
+            temporal_bounds,
+            geo_transform,
+        )
+        .unwrap();
+        target.blit(source.clone()).unwrap();
+        target.blit(source).unwrap();
+
+        let data = target.data_container();
+        assert_eq!(data[0], 1.);
+        assert!(data[1].is_nan());
+        assert_eq!(data[2], 2.);
+        assert!(data[3].is_nan());
+    }
 }
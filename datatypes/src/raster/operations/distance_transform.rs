@@ -0,0 +1,128 @@
+use crate::raster::{Dim2D, GridDimension};
+
+/// Compute the euclidean distance of every pixel center to the nearest pixel marked in `seeds`.
+/// Distances are scaled by the pixel sizes, pixels of grids without any seed are infinitely far away.
+///
+/// This is the separable exact transform of Felzenszwalb and Huttenlocher that first processes
+/// the columns and then the rows of the grid.
+///
+/// # Panics
+///
+/// If the number of `seeds` does not match the `dimension`
+///
+pub fn euclidean_distance_transform(
+    seeds: &[bool],
+    dimension: Dim2D,
+    x_pixel_size: f64,
+    y_pixel_size: f64,
+) -> Vec<f64> {
+    assert_eq!(seeds.len(), dimension.number_of_elements());
+
+    let rows = dimension.size_of_y_axis();
+    let columns = dimension.size_of_x_axis();
+
+    let mut squared_distances: Vec<f64> = seeds
+        .iter()
+        .map(|&seed| if seed { 0. } else { f64::INFINITY })
+        .collect();
+
+    let mut line = Vec::with_capacity(rows.max(columns));
+
+    for column in 0..columns {
+        line.clear();
+        line.extend((0..rows).map(|row| squared_distances[row * columns + column]));
+
+        for (row, distance) in squared_distance_1d(&line, y_pixel_size.abs())
+            .into_iter()
+            .enumerate()
+        {
+            squared_distances[row * columns + column] = distance;
+        }
+    }
+
+    for row in squared_distances.chunks_exact_mut(columns.max(1)) {
+        let distances = squared_distance_1d(row, x_pixel_size.abs());
+        row.copy_from_slice(&distances);
+    }
+
+    squared_distances.into_iter().map(f64::sqrt).collect()
+}
+
+/// One-dimensional squared distance transform of the sampled function `f` as the lower envelope
+/// of the parabolas rooted at the finite samples
+fn squared_distance_1d(f: &[f64], spacing: f64) -> Vec<f64> {
+    let position = |index: usize| index as f64 * spacing;
+    let intersection = |q: usize, v: usize| {
+        ((f[q] + position(q).powi(2)) - (f[v] + position(v).powi(2)))
+            / (2. * (position(q) - position(v)))
+    };
+
+    let mut vertices: Vec<usize> = Vec::with_capacity(f.len());
+    let mut boundaries: Vec<f64> = Vec::with_capacity(f.len());
+
+    for q in (0..f.len()).filter(|&q| f[q].is_finite()) {
+        while let (Some(&v), Some(&boundary)) = (vertices.last(), boundaries.last()) {
+            if intersection(q, v) <= boundary {
+                vertices.pop();
+                boundaries.pop();
+            } else {
+                break;
+            }
+        }
+
+        boundaries.push(match vertices.last() {
+            Some(&v) => intersection(q, v),
+            None => f64::NEG_INFINITY,
+        });
+        vertices.push(q);
+    }
+
+    if vertices.is_empty() {
+        return vec![f64::INFINITY; f.len()];
+    }
+
+    let mut k = 0;
+    (0..f.len())
+        .map(|p| {
+            while k + 1 < vertices.len() && boundaries[k + 1] < position(p) {
+                k += 1;
+            }
+            let v = vertices[k];
+            (position(p) - position(v)).powi(2) + f[v]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn single_seed() {
+        let distances = euclidean_distance_transform(
+            &[true, false, false, false, false, false],
+            [3, 2].into(),
+            1.,
+            -1.,
+        );
+
+        assert_eq!(distances, vec![0., 1., 1., 2_f64.sqrt(), 2., 5_f64.sqrt()]);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn anisotropic_pixels() {
+        let distances =
+            euclidean_distance_transform(&[false, false, false, true], [2, 2].into(), 2., -3.);
+
+        assert_eq!(distances, vec![13_f64.sqrt(), 3., 2., 0.]);
+    }
+
+    #[test]
+    fn no_seeds() {
+        let distances = euclidean_distance_transform(&[false; 4], [2, 2].into(), 1., -1.);
+
+        assert!(distances.iter().all(|d| d.is_infinite()));
+    }
+}
@@ -1 +1,3 @@
 pub mod blit;
+pub mod distance_transform;
+pub mod rasterize;
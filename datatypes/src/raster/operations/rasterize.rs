@@ -0,0 +1,388 @@
+use crate::collections::{
+    IntoGeometryIterator, MultiLineStringCollection, MultiPointCollection, MultiPolygonCollection,
+};
+use crate::primitives::{
+    BoundingBox2D, Coordinate2D, MultiLineStringAccess, MultiPointAccess, MultiPolygonAccess,
+    TimeInterval,
+};
+use crate::raster::{Dim2D, GeoTransform, GridDimension};
+
+pub trait PolygonMask {
+    /// Create a mask for a grid of `dimension` that is `true` for every pixel whose center lies
+    /// inside a polygon that is valid at `time`.
+    /// The mask is in row-major order like the data container of a `Raster2D`.
+    fn polygon_mask(
+        &self,
+        geo_transform: &GeoTransform,
+        dimension: Dim2D,
+        time: &TimeInterval,
+    ) -> Vec<bool>;
+}
+
+impl PolygonMask for MultiPolygonCollection {
+    fn polygon_mask(
+        &self,
+        geo_transform: &GeoTransform,
+        dimension: Dim2D,
+        time: &TimeInterval,
+    ) -> Vec<bool> {
+        let mut mask = vec![false; dimension.number_of_elements()];
+
+        for (multi_polygon, feature_time) in self.geometries().zip(self.time_intervals()) {
+            if !feature_time.intersects(time) {
+                continue;
+            }
+
+            for polygon in multi_polygon.polygons() {
+                rasterize_polygon(polygon, geo_transform, dimension, &mut mask);
+            }
+        }
+
+        mask
+    }
+}
+
+pub trait FeatureMask {
+    /// Create a mask for a grid of `dimension` that is `true` for every pixel that is touched by
+    /// a feature that is valid at `time`.
+    /// Points mark the pixel they lie in, lines mark every pixel they cross and polygons mark
+    /// their interior and their outline.
+    fn feature_mask(
+        &self,
+        geo_transform: &GeoTransform,
+        dimension: Dim2D,
+        time: &TimeInterval,
+    ) -> Vec<bool>;
+}
+
+impl FeatureMask for MultiPointCollection {
+    fn feature_mask(
+        &self,
+        geo_transform: &GeoTransform,
+        dimension: Dim2D,
+        time: &TimeInterval,
+    ) -> Vec<bool> {
+        let mut mask = vec![false; dimension.number_of_elements()];
+
+        for (multi_point, feature_time) in self.geometries().zip(self.time_intervals()) {
+            if !feature_time.intersects(time) {
+                continue;
+            }
+
+            for &point in multi_point.points() {
+                mark_coordinate(point, geo_transform, dimension, &mut mask);
+            }
+        }
+
+        mask
+    }
+}
+
+impl FeatureMask for MultiLineStringCollection {
+    fn feature_mask(
+        &self,
+        geo_transform: &GeoTransform,
+        dimension: Dim2D,
+        time: &TimeInterval,
+    ) -> Vec<bool> {
+        let mut mask = vec![false; dimension.number_of_elements()];
+
+        for (multi_line_string, feature_time) in self.geometries().zip(self.time_intervals()) {
+            if !feature_time.intersects(time) {
+                continue;
+            }
+
+            for line in multi_line_string.lines() {
+                rasterize_line(line, geo_transform, dimension, &mut mask);
+            }
+        }
+
+        mask
+    }
+}
+
+impl FeatureMask for MultiPolygonCollection {
+    fn feature_mask(
+        &self,
+        geo_transform: &GeoTransform,
+        dimension: Dim2D,
+        time: &TimeInterval,
+    ) -> Vec<bool> {
+        let mut mask = self.polygon_mask(geo_transform, dimension, time);
+
+        for (multi_polygon, feature_time) in self.geometries().zip(self.time_intervals()) {
+            if !feature_time.intersects(time) {
+                continue;
+            }
+
+            for polygon in multi_polygon.polygons() {
+                for ring in polygon {
+                    rasterize_line(ring, geo_transform, dimension, &mut mask);
+                }
+            }
+        }
+
+        mask
+    }
+}
+
+/// Mark the pixel that contains the `coordinate` if it lies inside the grid
+fn mark_coordinate(
+    coordinate: Coordinate2D,
+    geo_transform: &GeoTransform,
+    dimension: Dim2D,
+    mask: &mut [bool],
+) {
+    let column = ((coordinate.x - geo_transform.upper_left_coordinate.x)
+        / geo_transform.x_pixel_size)
+        .floor();
+    let row = ((coordinate.y - geo_transform.upper_left_coordinate.y) / geo_transform.y_pixel_size)
+        .floor();
+
+    let columns = dimension.size_of_x_axis();
+    let rows = dimension.size_of_y_axis();
+
+    if column < 0. || row < 0. || column >= columns as f64 || row >= rows as f64 {
+        return;
+    }
+
+    mask[row as usize * columns + column as usize] = true;
+}
+
+/// Mark every pixel a line crosses by sampling it in steps of half a pixel
+fn rasterize_line(
+    coordinates: &[Coordinate2D],
+    geo_transform: &GeoTransform,
+    dimension: Dim2D,
+    mask: &mut [bool],
+) {
+    let step = geo_transform
+        .x_pixel_size
+        .abs()
+        .min(geo_transform.y_pixel_size.abs())
+        / 2.;
+
+    for coordinate in coordinates {
+        mark_coordinate(*coordinate, geo_transform, dimension, mask);
+    }
+
+    for segment in coordinates.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        let length = (end.x - start.x).hypot(end.y - start.y);
+        let samples = (length / step).ceil() as usize;
+
+        for sample in 1..samples {
+            let fraction = sample as f64 / samples as f64;
+            let coordinate = Coordinate2D::new(
+                start.x + fraction * (end.x - start.x),
+                start.y + fraction * (end.y - start.y),
+            );
+            mark_coordinate(coordinate, geo_transform, dimension, mask);
+        }
+    }
+}
+
+/// Mark all pixel centers inside the `rings` of a polygon using an even-odd scanline fill.
+/// Only the rows that are covered by the polygon's bounding box are scanned.
+fn rasterize_polygon<R: AsRef<[Coordinate2D]>>(
+    rings: &[R],
+    geo_transform: &GeoTransform,
+    dimension: Dim2D,
+    mask: &mut [bool],
+) {
+    let bbox = match polygon_bounds(rings) {
+        Some(bbox) => bbox,
+        None => return,
+    };
+
+    let rows = dimension.size_of_y_axis();
+    let columns = dimension.size_of_x_axis();
+
+    let (first_row, last_row) = pixel_range(
+        bbox.upper_left().y,
+        bbox.lower_right().y,
+        geo_transform.upper_left_coordinate.y,
+        geo_transform.y_pixel_size,
+        rows,
+    );
+
+    let mut intersections = Vec::new();
+    for row in first_row..last_row {
+        let scan_y =
+            geo_transform.upper_left_coordinate.y + (row as f64 + 0.5) * geo_transform.y_pixel_size;
+
+        intersections.clear();
+        for ring in rings {
+            for edge in ring.as_ref().windows(2) {
+                let (start, end) = (edge[0], edge[1]);
+                if (start.y <= scan_y && scan_y < end.y) || (end.y <= scan_y && scan_y < start.y) {
+                    intersections
+                        .push(start.x + (scan_y - start.y) / (end.y - start.y) * (end.x - start.x));
+                }
+            }
+        }
+
+        intersections.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        for span in intersections.chunks_exact(2) {
+            let (first_column, last_column) = pixel_range(
+                span[0],
+                span[1],
+                geo_transform.upper_left_coordinate.x,
+                geo_transform.x_pixel_size,
+                columns,
+            );
+
+            let row_offset = row * columns;
+            for pixel in &mut mask[row_offset + first_column..row_offset + last_column] {
+                *pixel = true;
+            }
+        }
+    }
+}
+
+/// Compute the half-open range of pixels along an axis whose centers lie between `from` and `to`
+fn pixel_range(from: f64, to: f64, origin: f64, pixel_size: f64, size: usize) -> (usize, usize) {
+    let from_pixel = (from - origin) / pixel_size - 0.5;
+    let to_pixel = (to - origin) / pixel_size - 0.5;
+
+    let (from_pixel, to_pixel) = if from_pixel <= to_pixel {
+        (from_pixel, to_pixel)
+    } else {
+        (to_pixel, from_pixel)
+    };
+
+    let clamp = |pixel: f64| pixel.max(0.).min(size as f64) as usize;
+
+    (clamp(from_pixel.ceil()), clamp(to_pixel.ceil()))
+}
+
+fn polygon_bounds<R: AsRef<[Coordinate2D]>>(rings: &[R]) -> Option<BoundingBox2D> {
+    let mut coordinates = rings.iter().flat_map(|ring| ring.as_ref().iter());
+
+    let first = *coordinates.next()?;
+    let (lower_left, upper_right) =
+        coordinates.fold((first, first), |(lower_left, upper_right), c| {
+            (
+                Coordinate2D::new(lower_left.x.min(c.x), lower_left.y.min(c.y)),
+                Coordinate2D::new(upper_right.x.max(c.x), upper_right.y.max(c.y)),
+            )
+        });
+
+    Some(BoundingBox2D::new_unchecked(lower_left, upper_right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{MultiLineString, MultiPoint, MultiPolygon};
+
+    fn square(from: f64, to: f64) -> Vec<Coordinate2D> {
+        vec![
+            (from, from).into(),
+            (to, from).into(),
+            (to, to).into(),
+            (from, to).into(),
+            (from, from).into(),
+        ]
+    }
+
+    #[test]
+    fn mask_with_hole() {
+        let collection = MultiPolygonCollection::from_data(
+            vec![MultiPolygon::new(vec![vec![square(0., 4.), square(1., 3.)]]).unwrap()],
+            vec![TimeInterval::default()],
+            Default::default(),
+        )
+        .unwrap();
+
+        let mask = collection.polygon_mask(
+            &GeoTransform::new((0., 5.).into(), 1., -1.),
+            [5, 5].into(),
+            &TimeInterval::default(),
+        );
+
+        #[rustfmt::skip]
+        let expected = vec![
+            false, false, false, false, false,
+            true,  true,  true,  true,  false,
+            true,  false, false, true,  false,
+            true,  false, false, true,  false,
+            true,  true,  true,  true,  false,
+        ];
+
+        assert_eq!(mask, expected);
+    }
+
+    #[test]
+    fn mask_respects_time() {
+        let collection = MultiPolygonCollection::from_data(
+            vec![MultiPolygon::new(vec![vec![square(0., 2.)]]).unwrap()],
+            vec![TimeInterval::new(0, 10).unwrap()],
+            Default::default(),
+        )
+        .unwrap();
+
+        let geo_transform = GeoTransform::new((0., 2.).into(), 1., -1.);
+
+        assert_eq!(
+            collection.polygon_mask(
+                &geo_transform,
+                [2, 2].into(),
+                &TimeInterval::new(5, 15).unwrap()
+            ),
+            vec![true; 4]
+        );
+        assert_eq!(
+            collection.polygon_mask(
+                &geo_transform,
+                [2, 2].into(),
+                &TimeInterval::new(10, 15).unwrap()
+            ),
+            vec![false; 4]
+        );
+    }
+
+    #[test]
+    fn point_mask() {
+        let collection = MultiPointCollection::from_data(
+            MultiPoint::many(vec![vec![(0.5, 1.5), (1.5, 0.5)], vec![(5., 5.)]]).unwrap(),
+            vec![TimeInterval::default(); 2],
+            Default::default(),
+        )
+        .unwrap();
+
+        let mask = collection.feature_mask(
+            &GeoTransform::new((0., 2.).into(), 1., -1.),
+            [2, 2].into(),
+            &TimeInterval::default(),
+        );
+
+        assert_eq!(mask, vec![true, false, false, true]);
+    }
+
+    #[test]
+    fn line_mask() {
+        let collection = MultiLineStringCollection::from_data(
+            vec![MultiLineString::new(vec![vec![(0.5, 2.5).into(), (2.5, 2.5).into()]]).unwrap()],
+            vec![TimeInterval::default()],
+            Default::default(),
+        )
+        .unwrap();
+
+        let mask = collection.feature_mask(
+            &GeoTransform::new((0., 3.).into(), 1., -1.),
+            [3, 3].into(),
+            &TimeInterval::default(),
+        );
+
+        #[rustfmt::skip]
+        let expected = vec![
+            true,  true,  true,
+            false, false, false,
+            false, false, false,
+        ];
+
+        assert_eq!(mask, expected);
+    }
+}
@@ -1,5 +1,8 @@
-use super::{BaseRaster, Dim2D, Dim3D, GeoTransform, GridDimension, Raster};
-use crate::primitives::{BoundingBox2D, SpatialBounded, TemporalBounded, TimeInterval};
+use super::{BaseRaster, Dim2D, Dim3D, GeoTransform, GridDimension, Raster, TileStatistics};
+use crate::primitives::{
+    BoundingBox2D, SpatialBounded, SpatialPartition2D, SpatialPartitioned, TemporalBounded,
+    TimeInterval,
+};
 use crate::raster::data_type::FromPrimitive;
 use crate::raster::Pixel;
 use num_traits::AsPrimitive;
@@ -17,6 +20,10 @@ where
     pub time: TimeInterval,
     pub tile: TileInformation,
     pub data: BaseRaster<D, T, Vec<T>>,
+    /// Statistics over the valid pixels, if they were already computed.
+    /// They must be reset whenever the pixels change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<TileStatistics>,
 }
 
 impl<D, T> RasterTile<D, T>
@@ -25,7 +32,26 @@ where
 {
     /// create a new `RasterTile2D`
     pub fn new(time: TimeInterval, tile: TileInformation, data: BaseRaster<D, T, Vec<T>>) -> Self {
-        Self { time, tile, data }
+        Self {
+            time,
+            tile,
+            data,
+            statistics: None,
+        }
+    }
+
+    /// The statistics of the tile's valid pixels, computed unless they are already attached
+    pub fn statistics(&self) -> TileStatistics {
+        self.statistics.unwrap_or_else(|| {
+            TileStatistics::from_pixels(&self.data.data_container, self.data.no_data_value)
+        })
+    }
+
+    /// Attach the statistics of the tile's valid pixels, so that consumers of the tile need not
+    /// compute them again
+    pub fn with_statistics(mut self) -> Self {
+        self.statistics = Some(self.statistics());
+        self
     }
 
     /// Converts the data type of the raster tile by converting its inner raster
@@ -112,6 +138,17 @@ impl SpatialBounded for TileInformation {
     }
 }
 
+impl SpatialPartitioned for TileInformation {
+    fn spatial_partition(&self) -> SpatialPartition2D {
+        SpatialPartition2D::new_unchecked(
+            self.global_geo_transform
+                .grid_2d_to_coordinate_2d(self.global_pixel_position_upper_left().as_pattern()),
+            self.global_geo_transform
+                .grid_2d_to_coordinate_2d(self.global_pixel_position_lower_right().as_pattern()),
+        )
+    }
+}
+
 impl<D, T> TemporalBounded for RasterTile<D, T>
 where
     T: Pixel,
@@ -0,0 +1,261 @@
+use super::{Pixel, Raster2D, RasterTile2D, TileInformation, TileStatistics};
+use crate::primitives::TimeInterval;
+use crate::util::Result;
+use serde::{Deserialize, Serialize};
+
+/// A tile that consists of no-data pixels only
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct EmptyTile<T>
+where
+    T: Pixel,
+{
+    pub time: TimeInterval,
+    pub tile: TileInformation,
+    pub no_data_value: T,
+}
+
+/// A tile whose pixels all have the same valid value
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ConstantTile<T>
+where
+    T: Pixel,
+{
+    pub time: TimeInterval,
+    pub tile: TileInformation,
+    pub value: T,
+    pub no_data_value: Option<T>,
+}
+
+/// A raster tile that only stores its pixels if they differ.
+///
+/// Operators and outputs can skip the work for empty and constant tiles instead of iterating
+/// over a full grid of identical pixels.
+#[allow(clippy::pub_enum_variant_names)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SparseRasterTile2D<T>
+where
+    T: Pixel,
+{
+    EmptyTile(EmptyTile<T>),
+    ConstantTile(ConstantTile<T>),
+    MaterializedTile(RasterTile2D<T>),
+}
+
+impl<T> SparseRasterTile2D<T>
+where
+    T: Pixel,
+{
+    /// Store a tile sparsely if all of its pixels are no-data or have the same value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::primitives::TimeInterval;
+    /// use geoengine_datatypes::raster::{Raster2D, RasterTile2D, SparseRasterTile2D, TileInformation};
+    ///
+    /// let tile = RasterTile2D::new(
+    ///     TimeInterval::default(),
+    ///     TileInformation::new(
+    ///         [1, 1].into(),
+    ///         [0, 0].into(),
+    ///         [0, 0].into(),
+    ///         [2, 2].into(),
+    ///         Default::default(),
+    ///     ),
+    ///     Raster2D::new(
+    ///         [2, 2].into(),
+    ///         vec![0_u8; 4],
+    ///         Some(0),
+    ///         TimeInterval::default(),
+    ///         Default::default(),
+    ///     )
+    ///     .unwrap(),
+    /// );
+    ///
+    /// assert!(SparseRasterTile2D::from_tile(tile).is_empty());
+    /// ```
+    pub fn from_tile(tile: RasterTile2D<T>) -> Self {
+        let pixels = &tile.data.data_container;
+        let no_data_value = tile.data.no_data_value;
+
+        let first = match pixels.first() {
+            Some(&first) if pixels.iter().all(|&pixel| pixel == first) => first,
+            _ => return Self::MaterializedTile(tile),
+        };
+
+        if Some(first) == no_data_value {
+            Self::EmptyTile(EmptyTile {
+                time: tile.time,
+                tile: tile.tile,
+                no_data_value: first,
+            })
+        } else {
+            Self::ConstantTile(ConstantTile {
+                time: tile.time,
+                tile: tile.tile,
+                value: first,
+                no_data_value,
+            })
+        }
+    }
+
+    pub fn time(&self) -> TimeInterval {
+        match self {
+            Self::EmptyTile(tile) => tile.time,
+            Self::ConstantTile(tile) => tile.time,
+            Self::MaterializedTile(tile) => tile.time,
+        }
+    }
+
+    pub fn tile_information(&self) -> TileInformation {
+        match self {
+            Self::EmptyTile(tile) => tile.tile,
+            Self::ConstantTile(tile) => tile.tile,
+            Self::MaterializedTile(tile) => tile.tile,
+        }
+    }
+
+    pub fn no_data_value(&self) -> Option<T> {
+        match self {
+            Self::EmptyTile(tile) => Some(tile.no_data_value),
+            Self::ConstantTile(tile) => tile.no_data_value,
+            Self::MaterializedTile(tile) => tile.data.no_data_value,
+        }
+    }
+
+    /// Checks whether the tile contains no-data pixels only
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::EmptyTile(_))
+    }
+
+    /// The value of all pixels, if they are all the same
+    pub fn constant_value(&self) -> Option<T> {
+        match self {
+            Self::EmptyTile(tile) => Some(tile.no_data_value),
+            Self::ConstantTile(tile) => Some(tile.value),
+            Self::MaterializedTile(_) => None,
+        }
+    }
+
+    /// The statistics of the tile's valid pixels, which are free for empty and constant tiles
+    pub fn statistics(&self) -> TileStatistics {
+        match self {
+            Self::EmptyTile(_) => TileStatistics::empty(),
+            Self::ConstantTile(tile) => {
+                let [rows, columns] = *tile.tile.tile_size_in_pixels().dimension_size();
+                TileStatistics::constant(tile.value, rows * columns)
+            }
+            Self::MaterializedTile(tile) => tile.statistics(),
+        }
+    }
+
+    /// Expand the tile into a full grid of pixels.
+    /// Empty and constant tiles keep their statistics.
+    ///
+    /// # Errors
+    ///
+    /// This call fails if the raster of an empty or constant tile cannot be created
+    ///
+    pub fn materialize(self) -> Result<RasterTile2D<T>> {
+        let statistics = self.statistics();
+        let (time, tile, value, no_data_value) = match self {
+            Self::EmptyTile(tile) => (
+                tile.time,
+                tile.tile,
+                tile.no_data_value,
+                Some(tile.no_data_value),
+            ),
+            Self::ConstantTile(tile) => (tile.time, tile.tile, tile.value, tile.no_data_value),
+            Self::MaterializedTile(tile) => return Ok(tile),
+        };
+
+        let dimension = tile.tile_size_in_pixels();
+        let [rows, columns] = *dimension.dimension_size();
+
+        let mut materialized = RasterTile2D::new(
+            time,
+            tile,
+            Raster2D::new(
+                dimension,
+                vec![value; rows * columns],
+                no_data_value,
+                time,
+                tile.tile_geo_transform(),
+            )?,
+        );
+        materialized.statistics = Some(statistics);
+
+        Ok(materialized)
+    }
+}
+
+impl<T> From<RasterTile2D<T>> for SparseRasterTile2D<T>
+where
+    T: Pixel,
+{
+    fn from(tile: RasterTile2D<T>) -> Self {
+        Self::from_tile(tile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(data: Vec<u8>, no_data_value: Option<u8>) -> RasterTile2D<u8> {
+        let tile = TileInformation::new(
+            [1, 1].into(),
+            [0, 0].into(),
+            [0, 0].into(),
+            [2, 2].into(),
+            Default::default(),
+        );
+
+        RasterTile2D::new(
+            TimeInterval::default(),
+            tile,
+            Raster2D::new(
+                [2, 2].into(),
+                data,
+                no_data_value,
+                TimeInterval::default(),
+                tile.tile_geo_transform(),
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn compacts_tiles() {
+        let empty = SparseRasterTile2D::from_tile(tile(vec![0; 4], Some(0)));
+        assert!(empty.is_empty());
+        assert_eq!(empty.constant_value(), Some(0));
+
+        let constant = SparseRasterTile2D::from_tile(tile(vec![7; 4], Some(0)));
+        assert!(!constant.is_empty());
+        assert_eq!(constant.constant_value(), Some(7));
+
+        let without_no_data = SparseRasterTile2D::from_tile(tile(vec![0; 4], None));
+        assert_eq!(without_no_data.constant_value(), Some(0));
+        assert!(!without_no_data.is_empty());
+
+        let materialized = SparseRasterTile2D::from_tile(tile(vec![1, 2, 3, 4], Some(0)));
+        assert_eq!(materialized.constant_value(), None);
+    }
+
+    #[test]
+    fn materializes_tiles() {
+        for original in vec![
+            tile(vec![0; 4], Some(0)),
+            tile(vec![7; 4], Some(0)),
+            tile(vec![1, 2, 3, 4], None),
+        ] {
+            let materialized = SparseRasterTile2D::from_tile(original.clone())
+                .materialize()
+                .unwrap();
+
+            assert_eq!(materialized.data, original.data);
+            assert_eq!(materialized.statistics(), original.statistics());
+        }
+    }
+}
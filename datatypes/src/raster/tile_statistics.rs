@@ -0,0 +1,143 @@
+use super::Pixel;
+use num_traits::AsPrimitive;
+use serde::{Deserialize, Serialize};
+
+/// Summary statistics over the valid pixels of a raster tile.
+///
+/// Pixels are valid if they are neither the no-data value nor `NaN`.
+/// Without valid pixels, `min` is positive and `max` is negative infinity, so that statistics
+/// of several tiles can be merged without special cases.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TileStatistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub valid_count: usize,
+}
+
+impl TileStatistics {
+    /// Compute the statistics of `pixels` in a single pass
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::raster::TileStatistics;
+    ///
+    /// let statistics = TileStatistics::from_pixels(&[0_u8, 2, 4, 6], Some(0));
+    ///
+    /// assert_eq!(statistics.valid_count, 3);
+    /// assert_eq!((statistics.min, statistics.max, statistics.mean), (2., 6., 4.));
+    /// ```
+    pub fn from_pixels<T>(pixels: &[T], no_data_value: Option<T>) -> Self
+    where
+        T: Pixel,
+    {
+        let mut statistics = Self::empty();
+        let mut sum = 0.;
+
+        for &pixel in pixels {
+            if Some(pixel) == no_data_value {
+                continue;
+            }
+
+            let value: f64 = pixel.as_();
+            if value.is_nan() {
+                continue;
+            }
+
+            statistics.min = statistics.min.min(value);
+            statistics.max = statistics.max.max(value);
+            statistics.valid_count += 1;
+            sum += value;
+        }
+
+        if statistics.valid_count > 0 {
+            statistics.mean = sum / statistics.valid_count as f64;
+        }
+
+        statistics
+    }
+
+    /// The statistics of `count` pixels that all have the same valid `value`
+    pub fn constant<T>(value: T, count: usize) -> Self
+    where
+        T: Pixel,
+    {
+        if count == 0 {
+            return Self::empty();
+        }
+
+        let value: f64 = value.as_();
+        Self {
+            min: value,
+            max: value,
+            mean: value,
+            valid_count: count,
+        }
+    }
+
+    /// The statistics of a tile without valid pixels
+    pub fn empty() -> Self {
+        Self {
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.,
+            valid_count: 0,
+        }
+    }
+
+    /// Combine the statistics of two disjoint sets of pixels, e.g., of two tiles
+    pub fn merge(&self, other: &Self) -> Self {
+        let valid_count = self.valid_count + other.valid_count;
+
+        let mean = if valid_count == 0 {
+            0.
+        } else {
+            (self.mean * self.valid_count as f64 + other.mean * other.valid_count as f64)
+                / valid_count as f64
+        };
+
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            mean,
+            valid_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn skips_invalid_pixels() {
+        let statistics = TileStatistics::from_pixels(&[f64::NAN, 1., -1., 3., 42.], Some(42.));
+
+        assert_eq!(statistics.valid_count, 3);
+        assert_eq!(statistics.min, -1.);
+        assert_eq!(statistics.max, 3.);
+        assert_eq!(statistics.mean, 1.);
+
+        assert_eq!(
+            TileStatistics::from_pixels(&[42_u8; 4], Some(42)),
+            TileStatistics::empty()
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn merges_statistics() {
+        let a = TileStatistics::from_pixels(&[1_u8, 2, 3], None);
+        let b = TileStatistics::constant(10_u8, 2);
+
+        let merged = a.merge(&b);
+        assert_eq!(merged.valid_count, 5);
+        assert_eq!(merged.min, 1.);
+        assert_eq!(merged.max, 10.);
+        assert_eq!(merged.mean, 26. / 5.);
+
+        assert_eq!(a.merge(&TileStatistics::empty()), a);
+    }
+}
@@ -1,4 +1,9 @@
-use super::{BaseRaster, Dim, DynamicRasterDataType, GridDimension, RasterDataType};
+use half::f16;
+use num_traits::AsPrimitive;
+
+use super::{
+    BaseRaster, Dim, DynamicRasterDataType, GridDimension, Pixel, Raster2D, RasterDataType,
+};
 
 pub type TypedRaster2D = TypedRasterNDim<Dim<[usize; 2]>>;
 pub type TypedRaster3D = TypedRasterNDim<Dim<[usize; 3]>>;
@@ -13,6 +18,7 @@ pub enum TypedRasterNDim<D: GridDimension> {
     I16(BaseRaster<D, i16, Vec<i16>>),
     I32(BaseRaster<D, i32, Vec<i32>>),
     I64(BaseRaster<D, i64, Vec<i64>>),
+    F16(BaseRaster<D, f16, Vec<f16>>),
     F32(BaseRaster<D, f32, Vec<f32>>),
     F64(BaseRaster<D, f64, Vec<f64>>),
 }
@@ -78,6 +84,13 @@ where
         None
     }
 
+    pub fn get_f16(self) -> Option<BaseRaster<D, f16, Vec<f16>>> {
+        if let TypedRasterNDim::F16(r) = self {
+            return Some(r);
+        }
+        None
+    }
+
     pub fn get_f32(self) -> Option<BaseRaster<D, f32, Vec<f32>>> {
         if let TypedRasterNDim::F32(r) = self {
             return Some(r);
@@ -166,6 +179,15 @@ where
     }
 }
 
+impl<D> Into<TypedRasterNDim<D>> for BaseRaster<D, f16, Vec<f16>>
+where
+    D: GridDimension,
+{
+    fn into(self) -> TypedRasterNDim<D> {
+        TypedRasterNDim::F16(self)
+    }
+}
+
 impl<D> Into<TypedRasterNDim<D>> for BaseRaster<D, f32, Vec<f32>>
 where
     D: GridDimension,
@@ -198,8 +220,259 @@ where
             TypedRasterNDim::I16(_) => RasterDataType::I16,
             TypedRasterNDim::I32(_) => RasterDataType::I32,
             TypedRasterNDim::I64(_) => RasterDataType::I64,
+            TypedRasterNDim::F16(_) => RasterDataType::F16,
             TypedRasterNDim::F32(_) => RasterDataType::F32,
             TypedRasterNDim::F64(_) => RasterDataType::F64,
         }
     }
 }
+
+/// A function that is generic over the pixel type, to be called on the variant of a
+/// `TypedRaster2D` via `TypedRaster2D::dispatch`
+pub trait Raster2DFn {
+    type Output;
+
+    fn call<T: Pixel>(self, raster: Raster2D<T>) -> Self::Output;
+}
+
+/// A function that is generic over the pixel type, to be called on a reference to the variant
+/// of a `TypedRaster2D` via `TypedRaster2D::dispatch_ref`
+pub trait Raster2DRefFn {
+    type Output;
+
+    fn call<T: Pixel>(self, raster: &Raster2D<T>) -> Self::Output;
+}
+
+/// A function that maps a raster to a raster of the same pixel type, to be called on the
+/// variant of a `TypedRaster2D` via `TypedRaster2D::map`
+pub trait Raster2DMapFn {
+    fn call<T: Pixel>(self, raster: Raster2D<T>) -> Raster2D<T>;
+}
+
+impl TypedRaster2D {
+    /// Call `f` on the raster of the variant
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geoengine_datatypes::raster::{Pixel, Raster2D, Raster2DFn, TypedRaster2D};
+    ///
+    /// struct Len;
+    ///
+    /// impl Raster2DFn for Len {
+    ///     type Output = usize;
+    ///
+    ///     fn call<T: Pixel>(self, raster: Raster2D<T>) -> usize {
+    ///         raster.data_container.len()
+    ///     }
+    /// }
+    ///
+    /// let raster: TypedRaster2D = Raster2D::new(
+    ///     [3, 2].into(),
+    ///     vec![1_u8, 2, 3, 4, 5, 6],
+    ///     None,
+    ///     Default::default(),
+    ///     Default::default(),
+    /// )
+    /// .unwrap()
+    /// .into();
+    ///
+    /// assert_eq!(raster.dispatch(Len), 6);
+    /// ```
+    pub fn dispatch<F: Raster2DFn>(self, f: F) -> F::Output {
+        match self {
+            TypedRasterNDim::U8(r) => f.call(r),
+            TypedRasterNDim::U16(r) => f.call(r),
+            TypedRasterNDim::U32(r) => f.call(r),
+            TypedRasterNDim::U64(r) => f.call(r),
+            TypedRasterNDim::I8(r) => f.call(r),
+            TypedRasterNDim::I16(r) => f.call(r),
+            TypedRasterNDim::I32(r) => f.call(r),
+            TypedRasterNDim::I64(r) => f.call(r),
+            TypedRasterNDim::F16(r) => f.call(r),
+            TypedRasterNDim::F32(r) => f.call(r),
+            TypedRasterNDim::F64(r) => f.call(r),
+        }
+    }
+
+    /// Call `f` on a reference to the raster of the variant
+    pub fn dispatch_ref<F: Raster2DRefFn>(&self, f: F) -> F::Output {
+        match self {
+            TypedRasterNDim::U8(r) => f.call(r),
+            TypedRasterNDim::U16(r) => f.call(r),
+            TypedRasterNDim::U32(r) => f.call(r),
+            TypedRasterNDim::U64(r) => f.call(r),
+            TypedRasterNDim::I8(r) => f.call(r),
+            TypedRasterNDim::I16(r) => f.call(r),
+            TypedRasterNDim::I32(r) => f.call(r),
+            TypedRasterNDim::I64(r) => f.call(r),
+            TypedRasterNDim::F16(r) => f.call(r),
+            TypedRasterNDim::F32(r) => f.call(r),
+            TypedRasterNDim::F64(r) => f.call(r),
+        }
+    }
+
+    /// Map the raster of the variant, keeping the pixel type
+    pub fn map<F: Raster2DMapFn>(self, f: F) -> Self {
+        match self {
+            TypedRasterNDim::U8(r) => TypedRasterNDim::U8(f.call(r)),
+            TypedRasterNDim::U16(r) => TypedRasterNDim::U16(f.call(r)),
+            TypedRasterNDim::U32(r) => TypedRasterNDim::U32(f.call(r)),
+            TypedRasterNDim::U64(r) => TypedRasterNDim::U64(f.call(r)),
+            TypedRasterNDim::I8(r) => TypedRasterNDim::I8(f.call(r)),
+            TypedRasterNDim::I16(r) => TypedRasterNDim::I16(f.call(r)),
+            TypedRasterNDim::I32(r) => TypedRasterNDim::I32(f.call(r)),
+            TypedRasterNDim::I64(r) => TypedRasterNDim::I64(f.call(r)),
+            TypedRasterNDim::F16(r) => TypedRasterNDim::F16(f.call(r)),
+            TypedRasterNDim::F32(r) => TypedRasterNDim::F32(f.call(r)),
+            TypedRasterNDim::F64(r) => TypedRasterNDim::F64(f.call(r)),
+        }
+    }
+
+    /// Map every pixel that is not no-data as `f64`, keeping the pixel type
+    pub fn map_f64<F: Fn(f64) -> f64>(self, f: F) -> Self {
+        struct MapF64<F>(F);
+
+        impl<F: Fn(f64) -> f64> Raster2DMapFn for MapF64<F> {
+            fn call<T: Pixel>(self, raster: Raster2D<T>) -> Raster2D<T> {
+                let no_data_value = raster.no_data_value;
+                raster.map_pixels(|pixel| {
+                    if Some(pixel) == no_data_value {
+                        pixel
+                    } else {
+                        T::from_((self.0)(pixel.as_()))
+                    }
+                })
+            }
+        }
+
+        self.map(MapF64(f))
+    }
+
+    /// Fold all pixels that are not no-data as `f64`
+    pub fn fold_f64<A, F: FnMut(A, f64) -> A>(&self, init: A, f: F) -> A {
+        struct FoldF64<A, F>(A, F);
+
+        impl<A, F: FnMut(A, f64) -> A> Raster2DRefFn for FoldF64<A, F> {
+            type Output = A;
+
+            fn call<T: Pixel>(self, raster: &Raster2D<T>) -> A {
+                let FoldF64(init, f) = self;
+                let no_data_value = raster.no_data_value;
+                raster
+                    .data_container
+                    .iter()
+                    .filter(|&&pixel| Some(pixel) != no_data_value)
+                    .map(|&pixel| pixel.as_())
+                    .fold(init, f)
+            }
+        }
+
+        self.dispatch_ref(FoldF64(init, f))
+    }
+
+    /// Convert the raster to the pixel type `data_type`, e.g., for combining rasters of
+    /// different types
+    pub fn convert(self, data_type: RasterDataType) -> Self {
+        struct Convert(RasterDataType);
+
+        impl Raster2DFn for Convert {
+            type Output = TypedRaster2D;
+
+            fn call<T: Pixel>(self, raster: Raster2D<T>) -> TypedRaster2D {
+                match self.0 {
+                    RasterDataType::U8 => TypedRasterNDim::U8(raster.convert()),
+                    RasterDataType::U16 => TypedRasterNDim::U16(raster.convert()),
+                    RasterDataType::U32 => TypedRasterNDim::U32(raster.convert()),
+                    RasterDataType::U64 => TypedRasterNDim::U64(raster.convert()),
+                    RasterDataType::I8 => TypedRasterNDim::I8(raster.convert()),
+                    RasterDataType::I16 => TypedRasterNDim::I16(raster.convert()),
+                    RasterDataType::I32 => TypedRasterNDim::I32(raster.convert()),
+                    RasterDataType::I64 => TypedRasterNDim::I64(raster.convert()),
+                    // `Pixel` does not require `AsPrimitive<f16>`, so convert via `f32`
+                    RasterDataType::F16 => {
+                        TypedRasterNDim::F16(raster.map_pixels(|pixel| f16::from_f32(pixel.as_())))
+                    }
+                    RasterDataType::F32 => TypedRasterNDim::F32(raster.convert()),
+                    RasterDataType::F64 => TypedRasterNDim::F64(raster.convert()),
+                }
+            }
+        }
+
+        if self.raster_data_type() == data_type {
+            return self;
+        }
+
+        self.dispatch(Convert(data_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raster::GridPixelAccess;
+
+    fn typed_raster() -> TypedRaster2D {
+        TypedRaster2D::U32(
+            Raster2D::new(
+                [3, 2].into(),
+                vec![1, 2, 3, 4, 5, 0],
+                Some(0),
+                Default::default(),
+                [1.0, 1.0, 0.0, 1.0, 0.0, 1.0].into(),
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn dispatch() {
+        struct FirstPixel;
+
+        impl Raster2DRefFn for FirstPixel {
+            type Output = i64;
+
+            fn call<T: Pixel>(self, raster: &Raster2D<T>) -> i64 {
+                raster.pixel_value_at_grid_index(&(0, 0)).unwrap().as_()
+            }
+        }
+
+        struct Identity;
+
+        impl Raster2DMapFn for Identity {
+            fn call<T: Pixel>(self, raster: Raster2D<T>) -> Raster2D<T> {
+                raster
+            }
+        }
+
+        assert_eq!(typed_raster().dispatch_ref(FirstPixel), 1);
+        assert_eq!(typed_raster().map(Identity), typed_raster());
+    }
+
+    #[test]
+    fn map_and_fold() {
+        let raster = typed_raster().map_f64(|value| value * 2.);
+
+        assert_eq!(
+            raster.clone().get_u32().unwrap().data_container,
+            vec![2, 4, 6, 8, 10, 0]
+        );
+        assert_eq!(raster.fold_f64(0., |sum, value| sum + value), 30.);
+    }
+
+    #[test]
+    fn convert() {
+        let raster = typed_raster().convert(RasterDataType::F16);
+        assert_eq!(raster.raster_data_type(), RasterDataType::F16);
+        assert_eq!(
+            raster.clone().get_f16().unwrap().no_data_value,
+            Some(f16::from_f32(0.))
+        );
+
+        let raster = raster.convert(RasterDataType::I8);
+        assert_eq!(
+            raster.get_i8().unwrap().data_container,
+            vec![1, 2, 3, 4, 5, 0]
+        );
+    }
+}
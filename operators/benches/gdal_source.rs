@@ -0,0 +1,75 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use futures::StreamExt;
+use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution};
+use geoengine_operators::source::gdal_source::{
+    GdalSourceProcessor, JsonDatasetInformationProvider,
+};
+use geoengine_operators::source::{GdalDatasetPool, GdalSourceParameters};
+use tokio::runtime::{Builder, Runtime};
+
+fn processor(
+    dataset_pool: Option<Arc<GdalDatasetPool>>,
+) -> GdalSourceProcessor<JsonDatasetInformationProvider, u8> {
+    GdalSourceProcessor::from_params_with_json_provider(
+        GdalSourceParameters {
+            dataset_id: "test".to_owned(),
+            channel: None,
+            band: None,
+        },
+        Path::new("test-data/raster"),
+    )
+    .unwrap()
+    .with_dataset_pool(dataset_pool)
+}
+
+/// Load all tiles of the query and return their number
+fn load_tiles(
+    runtime: &mut Runtime,
+    processor: &GdalSourceProcessor<JsonDatasetInformationProvider, u8>,
+    bbox: BoundingBox2D,
+) -> usize {
+    runtime.block_on(
+        processor
+            .tile_stream(bbox, SpatialResolution::zero_point_one())
+            .map(|tile| black_box(tile.unwrap()))
+            .count(),
+    )
+}
+
+fn gdal_source_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("GdalSource");
+    // every iteration reads the whole test dataset from disk
+    group.sample_size(20);
+
+    let mut runtime = Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let world = BoundingBox2D::new((-180., -90.).into(), (180., 90.).into()).unwrap();
+    let europe = BoundingBox2D::new((-10., 35.).into(), (30., 70.).into()).unwrap();
+
+    let unpooled = processor(None);
+    let pooled = processor(Some(Arc::new(GdalDatasetPool::new(4, 4))));
+
+    group.bench_function("world 18 tiles", |b| {
+        b.iter(|| load_tiles(&mut runtime, &unpooled, world))
+    });
+
+    group.bench_function("world 18 tiles with dataset pool", |b| {
+        b.iter(|| load_tiles(&mut runtime, &pooled, world))
+    });
+
+    group.bench_function("europe", |b| {
+        b.iter(|| load_tiles(&mut runtime, &unpooled, europe))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, gdal_source_benchmarks);
+criterion_main!(benches);
@@ -175,6 +175,8 @@ mod tests {
         };
         let cx = QueryContext {
             chunk_byte_size: std::mem::size_of::<Coordinate2D>() * 2,
+            timeout: None,
+            seed: 0,
         };
 
         let number_of_source_chunks = processor
@@ -248,7 +250,11 @@ mod tests {
             time_interval: Default::default(),
             spatial_resolution: SpatialResolution::zero_point_one(),
         };
-        let cx = QueryContext { chunk_byte_size: 0 };
+        let cx = QueryContext {
+            chunk_byte_size: 0,
+            timeout: None,
+            seed: 0,
+        };
 
         let collections = FeatureCollectionChunkMerger::new(processor.query(qrect, cx).fuse(), 0)
             .collect::<Vec<Result<DataCollection>>>()
@@ -1,3 +1,5 @@
 mod feature_collection_merger;
+mod raster_time;
 
 pub use feature_collection_merger::FeatureCollectionChunkMerger;
+pub use raster_time::RasterTimeAdapter;
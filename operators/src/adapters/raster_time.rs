@@ -0,0 +1,256 @@
+use crate::engine::{QueryContext, QueryRectangle, RasterQueryProcessor};
+use crate::error::Error;
+use crate::util::tile_grid::{collect_time_steps, flatten_result};
+use crate::util::Result;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use geoengine_datatypes::primitives::{TimeInstance, TimeInterval};
+use geoengine_datatypes::raster::{Pixel, RasterTile2D};
+
+/// Aligns the time steps of two raster inputs, so that binary temporal operators can combine
+/// tiles without caring about differing time steps of their inputs.
+///
+/// Every pair of intersecting time steps yields a common time slice, i.e., the intersection of
+/// both intervals. Thus, a monthly and a yearly input result in monthly slices and the yearly
+/// tiles are repeated for every month. Time ranges that are covered by only one input are
+/// dropped.
+///
+/// The inputs must be spatially aligned.
+pub struct RasterTimeAdapter<T1, T2>
+where
+    T1: Pixel,
+    T2: Pixel,
+{
+    source_a: Box<dyn RasterQueryProcessor<RasterType = T1>>,
+    source_b: Box<dyn RasterQueryProcessor<RasterType = T2>>,
+}
+
+impl<T1, T2> RasterTimeAdapter<T1, T2>
+where
+    T1: Pixel,
+    T2: Pixel,
+{
+    pub fn new(
+        source_a: Box<dyn RasterQueryProcessor<RasterType = T1>>,
+        source_b: Box<dyn RasterQueryProcessor<RasterType = T2>>,
+    ) -> Self {
+        Self { source_a, source_b }
+    }
+
+    /// Query both inputs and emit the pairs of tiles at the same position for every common
+    /// time slice, ordered by time. Both tiles of a pair carry the time interval of the slice.
+    pub fn query(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> BoxStream<Result<(RasterTile2D<T1>, RasterTile2D<T2>)>> {
+        stream::once(self.tile_pairs(query, ctx))
+            .flat_map(|result| stream::iter(flatten_result(result)))
+            .boxed()
+    }
+
+    async fn tile_pairs(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<(RasterTile2D<T1>, RasterTile2D<T2>)>> {
+        let time_steps_a = collect_time_steps(self.source_a.raster_query(query, ctx)).await?;
+        let time_steps_b = collect_time_steps(self.source_b.raster_query(query, ctx)).await?;
+
+        let mut slices = Vec::new();
+        for (time_a, tiles_a) in &time_steps_a {
+            for (time_b, tiles_b) in &time_steps_b {
+                if let Some(slice) = common_time_slice(*time_a, *time_b) {
+                    slices.push((slice, tiles_a, tiles_b));
+                }
+            }
+        }
+        slices.sort_by_key(|(slice, _, _)| slice.start());
+
+        let mut pairs = Vec::new();
+        for (slice, tiles_a, tiles_b) in slices {
+            for tile_a in tiles_a {
+                let tile_b = tiles_b
+                    .iter()
+                    .find(|tile_b| tile_b.tile == tile_a.tile)
+                    .ok_or(Error::UnalignedRasterTiles)?;
+
+                let mut tile_a = tile_a.clone();
+                let mut tile_b = tile_b.clone();
+                tile_a.time = slice;
+                tile_b.time = slice;
+
+                pairs.push((tile_a, tile_b));
+            }
+        }
+
+        Ok(pairs)
+    }
+}
+
+/// The intersection of two time steps, if any. Equal instants intersect as well.
+fn common_time_slice(a: TimeInterval, b: TimeInterval) -> Option<TimeInterval> {
+    if a == b {
+        return Some(a);
+    }
+
+    if !a.intersects(&b) {
+        return None;
+    }
+
+    Some(TimeInterval::new_unchecked(
+        TimeInstance::max(a.start(), b.start()),
+        TimeInstance::min(a.end(), b.end()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{ExecutionContext, RasterOperator, RasterResultDescriptor};
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution};
+    use geoengine_datatypes::raster::{Raster2D, RasterDataType, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    fn tile(time: TimeInterval, position: usize, value: u8) -> RasterTile2D<u8> {
+        RasterTile2D::new(
+            time,
+            TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, position * 2].into(),
+                global_size_in_tiles: [1, 2].into(),
+                global_tile_position: [0, position].into(),
+                tile_size_in_pixels: [2, 2].into(),
+            },
+            Raster2D::new(
+                [2, 2].into(),
+                vec![value; 4],
+                None,
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+        )
+    }
+
+    fn source(tiles: Vec<RasterTile2D<u8>>) -> Box<dyn RasterQueryProcessor<RasterType = u8>> {
+        MockRasterSource {
+            params: MockRasterSourceParams {
+                data: tiles,
+                result_descriptor: RasterResultDescriptor {
+                    data_type: RasterDataType::U8,
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: None,
+                    bands: vec![],
+                },
+            },
+        }
+        .boxed()
+        .initialize(&ExecutionContext::mock_empty())
+        .unwrap()
+        .query_processor()
+        .unwrap()
+        .get_u8()
+        .unwrap()
+    }
+
+    async fn pairs(
+        tiles_a: Vec<RasterTile2D<u8>>,
+        tiles_b: Vec<RasterTile2D<u8>>,
+    ) -> Result<Vec<(RasterTile2D<u8>, RasterTile2D<u8>)>> {
+        let query = QueryRectangle {
+            bbox: BoundingBox2D::new((0., -2.).into(), (4., 0.).into()).unwrap(),
+            time_interval: TimeInterval::new_unchecked(0, 100),
+            spatial_resolution: SpatialResolution::one(),
+        };
+        let ctx = QueryContext {
+            chunk_byte_size: 1024 * 1024,
+            timeout: None,
+            seed: 0,
+        };
+
+        RasterTimeAdapter::new(source(tiles_a), source(tiles_b))
+            .query(query, ctx)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn time_slices() {
+        let interval = TimeInterval::new_unchecked;
+
+        assert_eq!(
+            common_time_slice(interval(0, 10), interval(5, 20)),
+            Some(interval(5, 10))
+        );
+        assert_eq!(
+            common_time_slice(interval(0, 30), interval(10, 20)),
+            Some(interval(10, 20))
+        );
+        assert_eq!(
+            common_time_slice(interval(5, 5), interval(5, 5)),
+            Some(interval(5, 5))
+        );
+        assert_eq!(common_time_slice(interval(0, 10), interval(10, 20)), None);
+    }
+
+    #[tokio::test]
+    async fn splits_time_steps() {
+        let interval = TimeInterval::new_unchecked;
+
+        let pairs = pairs(
+            vec![
+                tile(interval(0, 20), 0, 1),
+                tile(interval(0, 20), 1, 2),
+                tile(interval(20, 40), 0, 3),
+                tile(interval(20, 40), 1, 4),
+            ],
+            vec![
+                tile(interval(10, 30), 0, 5),
+                tile(interval(10, 30), 1, 6),
+                tile(interval(30, 50), 0, 7),
+                tile(interval(30, 50), 1, 8),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let summary: Vec<_> = pairs
+            .iter()
+            .map(|(a, b)| {
+                assert_eq!(a.time, b.time);
+                assert_eq!(a.tile, b.tile);
+                (a.time, a.data.data_container[0], b.data.data_container[0])
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (interval(10, 20), 1, 5),
+                (interval(10, 20), 2, 6),
+                (interval(20, 30), 3, 5),
+                (interval(20, 30), 4, 6),
+                (interval(30, 40), 3, 7),
+                (interval(30, 40), 4, 8),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_unaligned_tiles() {
+        let interval = TimeInterval::new_unchecked;
+
+        assert!(pairs(
+            vec![tile(interval(0, 10), 0, 1), tile(interval(0, 10), 1, 2)],
+            vec![tile(interval(0, 10), 0, 3)],
+        )
+        .await
+        .is_err());
+    }
+}
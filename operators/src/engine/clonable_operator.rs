@@ -1,6 +1,6 @@
 use super::{
-    InitializedOperatorBase, InitializedRasterOperator, InitializedVectorOperator, RasterOperator,
-    ResultDescriptor, VectorOperator,
+    InitializedOperatorBase, InitializedRasterOperator, InitializedVectorOperator, PlotOperator,
+    RasterOperator, ResultDescriptor, VectorOperator,
 };
 
 /// Helper trait for making boxed `RasterOperator`s cloneable
@@ -13,6 +13,11 @@ pub trait CloneableVectorOperator {
     fn clone_boxed_vector(&self) -> Box<dyn VectorOperator>;
 }
 
+/// Helper trait for making boxed `PlotOperator`s cloneable
+pub trait CloneablePlotOperator {
+    fn clone_boxed_plot(&self) -> Box<dyn PlotOperator>;
+}
+
 impl<T> CloneableRasterOperator for T
 where
     T: 'static + RasterOperator + Clone,
@@ -31,6 +36,15 @@ where
     }
 }
 
+impl<T> CloneablePlotOperator for T
+where
+    T: 'static + PlotOperator + Clone,
+{
+    fn clone_boxed_plot(&self) -> Box<dyn PlotOperator> {
+        Box::new(self.clone())
+    }
+}
+
 impl Clone for Box<dyn RasterOperator> {
     fn clone(&self) -> Box<dyn RasterOperator> {
         self.clone_boxed_raster()
@@ -43,6 +57,12 @@ impl Clone for Box<dyn VectorOperator> {
     }
 }
 
+impl Clone for Box<dyn PlotOperator> {
+    fn clone(&self) -> Box<dyn PlotOperator> {
+        self.clone_boxed_plot()
+    }
+}
+
 /// Helper trait for making boxed `InitializedOperator`s cloneable
 pub trait CloneableInitializedOperator {
     type Descriptor: ResultDescriptor;
@@ -1,26 +1,39 @@
 mod clonable_operator;
 mod operator;
 mod operator_impl;
+mod operator_registry;
 mod query;
 mod query_processor;
 mod result_descriptor;
 
 pub use operator::{
-    ExecutionContext, InitializedOperator, InitializedOperatorBase, InitializedRasterOperator,
-    InitializedVectorOperator, RasterOperator, TypedOperator, VectorOperator,
+    ExecutionContext, InitializedOperator, InitializedOperatorBase, InitializedPlotOperator,
+    InitializedRasterOperator, InitializedVectorOperator, PlotOperator, Principal, RasterOperator,
+    TypedOperator, VectorOperator, WorkflowResolver,
 };
 
 pub use clonable_operator::{
     CloneableInitializedOperator, CloneableInitializedRasterOperator,
-    CloneableInitializedVectorOperator, CloneableRasterOperator, CloneableVectorOperator,
+    CloneableInitializedVectorOperator, CloneablePlotOperator, CloneableRasterOperator,
+    CloneableVectorOperator,
 };
 pub use operator_impl::{InitializedOperatorImpl, Operator, SourceOperator};
+pub use operator_registry::{
+    OperatorKind, OperatorRegistration, OperatorRegistry, WorkflowProblem,
+};
 pub use query::{QueryContext, QueryRectangle};
 pub use query_processor::{
-    QueryProcessor, RasterQueryProcessor, TypedRasterQueryProcessor, TypedVectorQueryProcessor,
-    VectorQueryProcessor,
+    PlotQueryProcessor, QueryProcessor, RasterQueryProcessor, TypedPlotQueryProcessor,
+    TypedRasterQueryProcessor, TypedVectorQueryProcessor, VectorQueryProcessor,
+};
+pub use result_descriptor::{
+    enclosing_time_interval, PlotOutputFormat, PlotResultDescriptor, RasterBandDescriptor,
+    RasterResultDescriptor, ResultDescriptor, VectorResultDescriptor,
 };
-pub use result_descriptor::{RasterResultDescriptor, ResultDescriptor, VectorResultDescriptor};
+
+// used by `register_operator!`
+#[doc(hidden)]
+pub use inventory;
 
 #[macro_export]
 macro_rules! call_generic_raster_processor {
@@ -50,6 +63,9 @@ macro_rules! call_generic_raster_processor {
             geoengine_datatypes::raster::RasterDataType::I64 => {
                 crate::engine::TypedRasterQueryProcessor::I64($function_call)
             }
+            geoengine_datatypes::raster::RasterDataType::F16 => {
+                crate::engine::TypedRasterQueryProcessor::F16($function_call)
+            }
             geoengine_datatypes::raster::RasterDataType::F32 => {
                 crate::engine::TypedRasterQueryProcessor::F32($function_call)
             }
@@ -72,6 +88,7 @@ macro_rules! call_on_generic_raster_processor {
             $crate::engine::TypedRasterQueryProcessor::I16($processor_var) => $function_call,
             $crate::engine::TypedRasterQueryProcessor::I32($processor_var) => $function_call,
             $crate::engine::TypedRasterQueryProcessor::I64($processor_var) => $function_call,
+            $crate::engine::TypedRasterQueryProcessor::F16($processor_var) => $function_call,
             $crate::engine::TypedRasterQueryProcessor::F32($processor_var) => $function_call,
             $crate::engine::TypedRasterQueryProcessor::F64($processor_var) => $function_call,
         }
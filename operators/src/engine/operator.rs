@@ -1,12 +1,17 @@
 use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
 use super::{
-    query_processor::{TypedRasterQueryProcessor, TypedVectorQueryProcessor},
-    CloneableRasterOperator, CloneableVectorOperator, RasterResultDescriptor, ResultDescriptor,
-    VectorResultDescriptor,
+    query_processor::{
+        TypedPlotQueryProcessor, TypedRasterQueryProcessor, TypedVectorQueryProcessor,
+    },
+    CloneablePlotOperator, CloneableRasterOperator, CloneableVectorOperator, PlotResultDescriptor,
+    RasterResultDescriptor, ResultDescriptor, VectorResultDescriptor,
 };
 use crate::engine::query_processor::QueryProcessor;
 use crate::error;
+use crate::source::{DatasetDefinitions, DatasetStatistics, GdalDatasetPool, PinnedDatasets};
+use crate::util::r_runtime::RRuntime;
 use crate::util::Result;
 
 use serde::{Deserialize, Serialize};
@@ -45,19 +50,83 @@ pub trait VectorOperator: CloneableVectorOperator + Send + Sync + std::fmt::Debu
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Common methods for `PlotOperator`s
+#[typetag::serde(tag = "type")]
+pub trait PlotOperator: CloneablePlotOperator + Send + Sync + std::fmt::Debug {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedPlotOperator>>;
+
+    /// Wrap a box around a `PlotOperator`
+    fn boxed(self) -> Box<dyn PlotOperator>
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(self)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ExecutionContext {
     pub raster_data_root: PathBuf,
+    /// Validated dataset definitions, if `None` they are read from the `raster_data_root`
+    pub dataset_definitions: Option<Arc<RwLock<DatasetDefinitions>>>,
+    /// Open GDAL datasets that are shared between queries, if `None` every tile opens its file
+    pub gdal_dataset_pool: Option<Arc<GdalDatasetPool>>,
+    /// Precomputed statistics of raster datasets, if `None` there are none
+    pub dataset_statistics: Option<Arc<RwLock<DatasetStatistics>>>,
+    /// Vector datasets that are kept in memory, if `None` no datasets are pinned
+    pub pinned_datasets: Option<Arc<RwLock<PinnedDatasets>>>,
+    /// The R installation for `RScript` operators, if `None` they cannot be executed
+    pub r_runtime: Option<Arc<RRuntime>>,
+    /// Registered workflows for `WorkflowSource` operators, if `None` they cannot be resolved
+    pub workflow_resolver: Option<Arc<dyn WorkflowResolver>>,
+    /// The workflows that are being resolved, i.e., the path of `WorkflowSource` operators to
+    /// the current operator, for detecting cyclic references
+    pub resolving_workflows: Vec<String>,
+    /// On whose behalf the operators are initialized, for checking the access to datasets
+    pub principal: Principal,
+}
+
+/// The originator of a workflow execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Principal {
+    /// The server itself, which may access all datasets
+    System,
+    /// A client without a session
+    Anonymous,
+    /// A user with the given id
+    User(String),
 }
 
 impl ExecutionContext {
     pub fn mock_empty() -> Self {
         ExecutionContext {
             raster_data_root: "".into(),
+            dataset_definitions: None,
+            gdal_dataset_pool: None,
+            dataset_statistics: None,
+            pinned_datasets: None,
+            r_runtime: None,
+            workflow_resolver: None,
+            resolving_workflows: vec![],
+            principal: Principal::System,
         }
     }
 }
 
+/// Looks up the workflows that `WorkflowSource` operators refer to
+pub trait WorkflowResolver: Send + Sync + std::fmt::Debug {
+    /// The operator of the workflow with the given `id`
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::UnresolvableWorkflow` if there is no such workflow
+    ///
+    fn resolve(&self, id: &str) -> Result<TypedOperator>;
+}
+
 pub trait InitializedOperatorBase {
     type Descriptor: ResultDescriptor + Clone;
 
@@ -83,6 +152,9 @@ pub type InitializedVectorOperator =
 pub type InitializedRasterOperator =
     dyn InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>;
 
+pub type InitializedPlotOperator =
+    dyn InitializedOperator<PlotResultDescriptor, TypedPlotQueryProcessor>;
+
 pub trait InitializedOperator<R, Q>: InitializedOperatorBase<Descriptor = R> + Send + Sync
 where
     R: ResultDescriptor,
@@ -161,6 +233,7 @@ where
 pub enum TypedOperator {
     Vector(Box<dyn VectorOperator>),
     Raster(Box<dyn RasterOperator>),
+    Plot(Box<dyn PlotOperator>),
 }
 
 impl TypedOperator {
@@ -177,6 +250,13 @@ impl TypedOperator {
         }
         Err(error::Error::InvalidOperatorType)
     }
+
+    pub fn get_plot(self) -> Result<Box<dyn PlotOperator>> {
+        if let TypedOperator::Plot(o) = self {
+            return Ok(o);
+        }
+        Err(error::Error::InvalidOperatorType)
+    }
 }
 
 impl Into<TypedOperator> for Box<dyn VectorOperator> {
@@ -191,10 +271,17 @@ impl Into<TypedOperator> for Box<dyn RasterOperator> {
     }
 }
 
+impl Into<TypedOperator> for Box<dyn PlotOperator> {
+    fn into(self) -> TypedOperator {
+        TypedOperator::Plot(self)
+    }
+}
+
 /// An enum to differentiate between `InitializedOperator` variants
 pub enum TypedInitializedOperator {
     Vector(Box<InitializedVectorOperator>),
     Raster(Box<InitializedRasterOperator>),
+    Plot(Box<InitializedPlotOperator>),
 }
 
 impl Into<TypedInitializedOperator> for Box<InitializedVectorOperator> {
@@ -208,3 +295,9 @@ impl Into<TypedInitializedOperator> for Box<InitializedRasterOperator> {
         TypedInitializedOperator::Raster(self)
     }
 }
+
+impl Into<TypedInitializedOperator> for Box<InitializedPlotOperator> {
+    fn into(self) -> TypedInitializedOperator {
+        TypedInitializedOperator::Plot(self)
+    }
+}
@@ -107,7 +107,7 @@ where
     type Descriptor = R;
 
     fn result_descriptor(&self) -> Self::Descriptor {
-        self.result_descriptor
+        self.result_descriptor.clone()
     }
     fn raster_sources(&self) -> &[Box<InitializedRasterOperator>] {
         self.raster_sources.as_slice()
@@ -0,0 +1,436 @@
+use super::{ExecutionContext, PlotOperator, RasterOperator, TypedOperator, VectorOperator};
+use crate::error::Error;
+use crate::util::Result;
+use geoengine_datatypes::error::ErrorChain;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+/// Whether an operator produces rasters, vectors or plots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OperatorKind {
+    Raster,
+    Vector,
+    Plot,
+}
+
+/// A problem of a workflow document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowProblem {
+    /// A JSON pointer (RFC 6901) to the faulty part of the document
+    pub pointer: String,
+    pub message: String,
+}
+
+impl WorkflowProblem {
+    fn new(pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            pointer: pointer.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// An operator type that workflows can refer to by its `name`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OperatorRegistration {
+    pub name: &'static str,
+    pub kind: OperatorKind,
+}
+
+inventory::collect!(OperatorRegistration);
+
+/// Register an operator, so that it is listed by the `OperatorRegistry`.
+///
+/// Operators deserialize from workflow JSON by their `#[typetag::serde]` implementation, which
+/// also works for operators of downstream crates. Registering makes them discoverable and lets
+/// workflows with unknown operator types fail with a list of the available ones.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[typetag::serde]
+/// impl RasterOperator for MyOperator {
+///     // ...
+/// }
+///
+/// geoengine_operators::register_operator!(Raster, MyOperator);
+/// ```
+#[macro_export]
+macro_rules! register_operator {
+    ($kind:ident, $operator:ident) => {
+        $crate::engine::inventory::submit! {
+            $crate::engine::OperatorRegistration {
+                name: stringify!($operator),
+                kind: $crate::engine::OperatorKind::$kind,
+            }
+        }
+    };
+}
+
+/// All operators that are registered in the crates linked into the binary
+#[derive(Debug, Clone)]
+pub struct OperatorRegistry {
+    operators: Vec<OperatorRegistration>,
+}
+
+impl OperatorRegistry {
+    pub fn collect() -> Self {
+        let mut operators: Vec<OperatorRegistration> = inventory::iter::<OperatorRegistration>
+            .into_iter()
+            .copied()
+            .collect();
+        operators.sort_by_key(|operator| operator.name);
+        operators.dedup();
+
+        Self { operators }
+    }
+
+    /// The registered operators, sorted by name
+    pub fn operators(&self) -> &[OperatorRegistration] {
+        &self.operators
+    }
+
+    pub fn get(&self, name: &str) -> Option<&OperatorRegistration> {
+        self.operators
+            .binary_search_by_key(&name, |operator| operator.name)
+            .ok()
+            .map(|index| &self.operators[index])
+    }
+
+    /// Deserialize a workflow operator from JSON.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::UnknownOperator` if the JSON refers to an operator type that is not
+    /// registered, or if it cannot be deserialized otherwise
+    ///
+    pub fn deserialize(&self, json: &str) -> Result<TypedOperator> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).context(crate::error::SerdeJson)?;
+
+        if let Some(name) = self.find_unknown_operator(&value) {
+            return Err(Error::UnknownOperator {
+                name,
+                available: self
+                    .operators
+                    .iter()
+                    .map(|operator| operator.name.to_string())
+                    .collect(),
+            });
+        }
+
+        serde_json::from_value(value).context(crate::error::SerdeJson)
+    }
+
+    /// Check a workflow document and return all its problems, which are empty if it is valid.
+    ///
+    /// Besides syntax errors, this finds unknown operators, operators of the wrong kind and
+    /// parameters that cannot be deserialized, and initializes the operators with the `context`
+    /// for semantic errors, like missing datasets or mismatching sources. Problems are attributed
+    /// to the innermost faulty operator, so operators whose sources have problems are not checked.
+    pub fn validate(&self, json: &str, context: &ExecutionContext) -> Vec<WorkflowProblem> {
+        let mut problems = Vec::new();
+
+        let value: serde_json::Value = match serde_json::from_str(json) {
+            Ok(value) => value,
+            Err(error) => {
+                problems.push(WorkflowProblem::new("", error.to_string()));
+                return problems;
+            }
+        };
+
+        let kind = match value.get("type").and_then(serde_json::Value::as_str) {
+            Some("Raster") => OperatorKind::Raster,
+            Some("Vector") => OperatorKind::Vector,
+            Some("Plot") => OperatorKind::Plot,
+            _ => {
+                problems.push(WorkflowProblem::new(
+                    "/type",
+                    "expected a workflow type of `Raster`, `Vector` or `Plot`",
+                ));
+                return problems;
+            }
+        };
+
+        match value.get("operator") {
+            Some(operator) => {
+                self.validate_operator(operator, kind, "/operator", context, &mut problems);
+            }
+            None => problems.push(WorkflowProblem::new("", "missing field `operator`")),
+        }
+
+        problems
+    }
+
+    /// Check the operator at `pointer` and its sources, returns whether they are valid
+    fn validate_operator(
+        &self,
+        value: &serde_json::Value,
+        kind: OperatorKind,
+        pointer: &str,
+        context: &ExecutionContext,
+        problems: &mut Vec<WorkflowProblem>,
+    ) -> bool {
+        let object = match value.as_object() {
+            Some(object) => object,
+            None => {
+                problems.push(WorkflowProblem::new(pointer, "expected an operator object"));
+                return false;
+            }
+        };
+
+        let mut valid = true;
+
+        match object.get("type").and_then(serde_json::Value::as_str) {
+            Some(name) => {
+                let kinds: Vec<OperatorKind> = self
+                    .operators
+                    .iter()
+                    .filter(|operator| operator.name == name)
+                    .map(|operator| operator.kind)
+                    .collect();
+
+                if kinds.is_empty() {
+                    problems.push(WorkflowProblem::new(
+                        format!("{}/type", pointer),
+                        format!("unknown operator `{}`", name),
+                    ));
+                    valid = false;
+                } else if !kinds.contains(&kind) {
+                    problems.push(WorkflowProblem::new(
+                        format!("{}/type", pointer),
+                        format!("`{}` is not a {:?} operator", name, kind),
+                    ));
+                    valid = false;
+                }
+            }
+            None => {
+                problems.push(WorkflowProblem::new(pointer, "missing operator `type`"));
+                valid = false;
+            }
+        }
+
+        for (field, source_kind) in &[
+            ("raster_sources", OperatorKind::Raster),
+            ("vector_sources", OperatorKind::Vector),
+        ] {
+            if let Some(serde_json::Value::Array(sources)) = object.get(*field) {
+                for (index, source) in sources.iter().enumerate() {
+                    let source_pointer = format!("{}/{}/{}", pointer, field, index);
+                    valid &= self.validate_operator(
+                        source,
+                        *source_kind,
+                        &source_pointer,
+                        context,
+                        problems,
+                    );
+                }
+            }
+        }
+
+        if !valid {
+            return false;
+        }
+
+        // the sources are valid, so the remaining problems belong to this operator
+        let operator = match deserialize_operator(value, kind) {
+            Ok(operator) => operator,
+            Err(error) => {
+                problems.push(WorkflowProblem::new(pointer, error.to_string()));
+                return false;
+            }
+        };
+
+        if let Err(error) = initialize_operator(operator, context) {
+            problems.push(WorkflowProblem::new(
+                pointer,
+                ErrorChain(&error).to_string(),
+            ));
+            return false;
+        }
+
+        true
+    }
+
+    /// The type of the first operator in the workflow tree that is not registered
+    fn find_unknown_operator(&self, value: &serde_json::Value) -> Option<String> {
+        match value {
+            serde_json::Value::Object(object) => {
+                // operators are objects with a type and parameters
+                if let (Some(serde_json::Value::String(name)), true) =
+                    (object.get("type"), object.contains_key("params"))
+                {
+                    if self.get(name).is_none() {
+                        return Some(name.clone());
+                    }
+                }
+
+                object
+                    .values()
+                    .find_map(|value| self.find_unknown_operator(value))
+            }
+            serde_json::Value::Array(values) => values
+                .iter()
+                .find_map(|value| self.find_unknown_operator(value)),
+            _ => None,
+        }
+    }
+}
+
+fn deserialize_operator(
+    value: &serde_json::Value,
+    kind: OperatorKind,
+) -> serde_json::Result<TypedOperator> {
+    Ok(match kind {
+        OperatorKind::Raster => TypedOperator::Raster(serde_json::from_value::<
+            Box<dyn RasterOperator>,
+        >(value.clone())?),
+        OperatorKind::Vector => TypedOperator::Vector(serde_json::from_value::<
+            Box<dyn VectorOperator>,
+        >(value.clone())?),
+        OperatorKind::Plot => TypedOperator::Plot(serde_json::from_value::<Box<dyn PlotOperator>>(
+            value.clone(),
+        )?),
+    })
+}
+
+fn initialize_operator(operator: TypedOperator, context: &ExecutionContext) -> Result<()> {
+    match operator {
+        TypedOperator::Raster(operator) => operator.initialize(context).map(|_| ()),
+        TypedOperator::Vector(operator) => operator.initialize(context).map(|_| ()),
+        TypedOperator::Plot(operator) => operator.initialize(context).map(|_| ()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn lists_builtin_operators() {
+        let registry = OperatorRegistry::collect();
+
+        assert_eq!(
+            registry.get("GdalSource"),
+            Some(&OperatorRegistration {
+                name: "GdalSource",
+                kind: OperatorKind::Raster,
+            })
+        );
+        assert_eq!(
+            registry
+                .get("MockPointSource")
+                .map(|operator| operator.kind),
+            Some(OperatorKind::Vector)
+        );
+        assert!(registry.get("DoesNotExist").is_none());
+    }
+
+    #[test]
+    fn deserialize() {
+        let registry = OperatorRegistry::collect();
+
+        let workflow = json!({
+            "type": "Vector",
+            "operator": {
+                "type": "MockPointSource",
+                "params": {
+                    "points": [{"x": 1.0, "y": 2.0}]
+                }
+            }
+        });
+        assert!(registry.deserialize(&workflow.to_string()).is_ok());
+
+        let unknown = json!({
+            "type": "Vector",
+            "operator": {
+                "type": "MockRasterPointJoinOperator",
+                "params": {},
+                "raster_sources": [{
+                    "type": "CustomSource",
+                    "params": {}
+                }],
+                "vector_sources": []
+            }
+        });
+        match registry.deserialize(&unknown.to_string()) {
+            Err(Error::UnknownOperator { name, available }) => {
+                assert_eq!(name, "CustomSource");
+                assert!(available.contains(&"GdalSource".to_string()));
+            }
+            _ => panic!("the operator must be unknown"),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let registry = OperatorRegistry::collect();
+        let context = ExecutionContext::mock_empty();
+
+        let valid = json!({
+            "type": "Vector",
+            "operator": {
+                "type": "MockPointSource",
+                "params": {
+                    "points": [{"x": 1.0, "y": 2.0}]
+                }
+            }
+        });
+        assert!(registry.validate(&valid.to_string(), &context).is_empty());
+
+        let problems = registry.validate("{\"type\": ", &context);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].pointer, "");
+        assert!(problems[0].message.contains("line 1"));
+
+        let faulty = json!({
+            "type": "Vector",
+            "operator": {
+                "type": "MockRasterPointJoinOperator",
+                "params": {
+                    "feature_name": "raster"
+                },
+                "raster_sources": [{
+                    "type": "MockPointSource",
+                    "params": {
+                        "points": []
+                    }
+                }, {
+                    "type": "WorkflowSource",
+                    "params": {
+                        "workflow": "unregistered"
+                    }
+                }],
+                "vector_sources": [{
+                    "type": "MockPointSource",
+                    "params": {
+                        "points": "none"
+                    }
+                }, {
+                    "type": "CustomSource",
+                    "params": {}
+                }]
+            }
+        });
+        let problems = registry.validate(&faulty.to_string(), &context);
+        let pointers: Vec<&str> = problems
+            .iter()
+            .map(|problem| problem.pointer.as_str())
+            .collect();
+        assert_eq!(
+            pointers,
+            vec![
+                "/operator/raster_sources/0/type",
+                "/operator/raster_sources/1",
+                "/operator/vector_sources/0",
+                "/operator/vector_sources/1/type",
+            ]
+        );
+        assert!(problems[0].message.contains("not a Raster operator"));
+        assert!(problems[1].message.contains("unregistered"));
+        assert!(problems[3]
+            .message
+            .contains("unknown operator `CustomSource`"));
+    }
+}
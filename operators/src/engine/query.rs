@@ -1,4 +1,7 @@
 use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution, TimeInterval};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::time::Duration;
 
 /// A spatio-temporal rectangle for querying data
 #[derive(Copy, Clone, Debug)]
@@ -14,4 +17,53 @@ pub struct QueryContext {
     // TODO: resolution, profiler, user session, ...
     // TODO: determine chunk size globally or dynamically from workload? Or global Engine Manager instance that gives that info
     pub chunk_byte_size: usize,
+    /// The wall-clock time after which the query is cancelled, if any
+    pub timeout: Option<Duration>,
+    /// The seed of all random numbers of the query, queries with the same seed yield the
+    /// same results
+    pub seed: u64,
+}
+
+impl QueryContext {
+    /// A random number generator for the `stream`-th independent part of the query, e.g., a
+    /// tile or a chunk.
+    ///
+    /// The numbers only depend on the seed and the stream, so the results do not change with
+    /// the order in which the parts are processed.
+    pub fn rng(&self, stream: u64) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        rng.set_stream(stream);
+        rng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn context(seed: u64) -> QueryContext {
+        QueryContext {
+            chunk_byte_size: 1024,
+            timeout: None,
+            seed,
+        }
+    }
+
+    fn sample(ctx: &QueryContext, stream: u64) -> Vec<u32> {
+        let mut rng = ctx.rng(stream);
+        (0..8).map(|_| rng.gen()).collect()
+    }
+
+    #[test]
+    fn same_seed_is_reproducible() {
+        assert_eq!(sample(&context(42), 0), sample(&context(42), 0));
+        assert_eq!(sample(&context(42), 7), sample(&context(42), 7));
+    }
+
+    #[test]
+    fn seeds_and_streams_differ() {
+        assert_ne!(sample(&context(42), 0), sample(&context(43), 0));
+        assert_ne!(sample(&context(42), 0), sample(&context(42), 1));
+    }
 }
@@ -1,10 +1,12 @@
 use super::query::{QueryContext, QueryRectangle};
 use crate::util::Result;
+use futures::future::BoxFuture;
 use futures::stream::BoxStream;
 use geoengine_datatypes::collections::{
     DataCollection, MultiLineStringCollection, MultiPolygonCollection,
 };
-use geoengine_datatypes::raster::Pixel;
+use geoengine_datatypes::plots::PlotData;
+use geoengine_datatypes::raster::{f16, Pixel};
 use geoengine_datatypes::{collections::MultiPointCollection, raster::RasterTile2D};
 
 /// An instantiation of an operator that produces a stream of results for a query
@@ -78,6 +80,24 @@ where
     }
 }
 
+/// An instantiation of a plot operator that computes one plot for a query
+pub trait PlotQueryProcessor: Sync + Send {
+    type OutputFormat;
+
+    fn plot_query(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> BoxFuture<Result<Self::OutputFormat>>;
+
+    fn boxed(self) -> Box<dyn PlotQueryProcessor<OutputFormat = Self::OutputFormat>>
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(self)
+    }
+}
+
 impl<T> QueryProcessor for Box<dyn QueryProcessor<Output = T>> {
     type Output = T;
     fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
@@ -116,6 +136,7 @@ pub enum TypedRasterQueryProcessor {
     I16(Box<dyn RasterQueryProcessor<RasterType = i16>>),
     I32(Box<dyn RasterQueryProcessor<RasterType = i32>>),
     I64(Box<dyn RasterQueryProcessor<RasterType = i64>>),
+    F16(Box<dyn RasterQueryProcessor<RasterType = f16>>),
     F32(Box<dyn RasterQueryProcessor<RasterType = f32>>),
     F64(Box<dyn RasterQueryProcessor<RasterType = f64>>),
 }
@@ -163,6 +184,12 @@ impl TypedRasterQueryProcessor {
             _ => None,
         }
     }
+    pub fn get_f16(self) -> Option<Box<dyn RasterQueryProcessor<RasterType = f16>>> {
+        match self {
+            Self::F16(r) => Some(r),
+            _ => None,
+        }
+    }
     pub fn get_f32(self) -> Option<Box<dyn RasterQueryProcessor<RasterType = f32>>> {
         match self {
             Self::F32(r) => Some(r),
@@ -177,6 +204,30 @@ impl TypedRasterQueryProcessor {
     }
 }
 
+macro_rules! impl_from_raster_query_processor {
+    ($pixel:ty, $variant:ident) => {
+        impl From<Box<dyn RasterQueryProcessor<RasterType = $pixel>>>
+            for TypedRasterQueryProcessor
+        {
+            fn from(processor: Box<dyn RasterQueryProcessor<RasterType = $pixel>>) -> Self {
+                Self::$variant(processor)
+            }
+        }
+    };
+}
+
+impl_from_raster_query_processor!(u8, U8);
+impl_from_raster_query_processor!(u16, U16);
+impl_from_raster_query_processor!(u32, U32);
+impl_from_raster_query_processor!(u64, U64);
+impl_from_raster_query_processor!(i8, I8);
+impl_from_raster_query_processor!(i16, I16);
+impl_from_raster_query_processor!(i32, I32);
+impl_from_raster_query_processor!(i64, I64);
+impl_from_raster_query_processor!(f16, F16);
+impl_from_raster_query_processor!(f32, F32);
+impl_from_raster_query_processor!(f64, F64);
+
 /// An enum that contains all possible query processor variants
 #[allow(clippy::pub_enum_variant_names)]
 pub enum TypedVectorQueryProcessor {
@@ -185,3 +236,29 @@ pub enum TypedVectorQueryProcessor {
     MultiLineString(Box<dyn VectorQueryProcessor<VectorType = MultiLineStringCollection>>),
     MultiPolygon(Box<dyn VectorQueryProcessor<VectorType = MultiPolygonCollection>>),
 }
+
+/// An enum that contains all possible plot query processor variants
+pub enum TypedPlotQueryProcessor {
+    JsonPlain(Box<dyn PlotQueryProcessor<OutputFormat = serde_json::Value>>),
+    JsonVega(Box<dyn PlotQueryProcessor<OutputFormat = PlotData<serde_json::Value>>>),
+}
+
+impl TypedPlotQueryProcessor {
+    pub fn get_json_plain(
+        self,
+    ) -> Option<Box<dyn PlotQueryProcessor<OutputFormat = serde_json::Value>>> {
+        match self {
+            Self::JsonPlain(p) => Some(p),
+            Self::JsonVega(_) => None,
+        }
+    }
+
+    pub fn get_json_vega(
+        self,
+    ) -> Option<Box<dyn PlotQueryProcessor<OutputFormat = PlotData<serde_json::Value>>>> {
+        match self {
+            Self::JsonVega(p) => Some(p),
+            Self::JsonPlain(_) => None,
+        }
+    }
+}
@@ -1,11 +1,15 @@
+use crate::engine::QueryRectangle;
 use geoengine_datatypes::{
-    collections::VectorDataType, raster::RasterDataType, spatial_reference::SpatialReferenceOption,
+    collections::VectorDataType,
+    primitives::{BoundingBox2D, Measurement, TimeInterval},
+    raster::RasterDataType,
+    spatial_reference::SpatialReferenceOption,
 };
 use serde::{Deserialize, Serialize};
 
 /// A descriptor that contains information about the query result, for instance, the data type
 /// and spatial reference.
-pub trait ResultDescriptor: Copy {
+pub trait ResultDescriptor: Clone {
     type DataType;
 
     /// Return the type-specific result data type
@@ -14,6 +18,14 @@ pub trait ResultDescriptor: Copy {
     /// Return the spatial reference of the result
     fn spatial_reference(&self) -> SpatialReferenceOption;
 
+    /// Return whether the result may contain data for the `query`.
+    ///
+    /// This is `false` if the `query` lies outside of the known bounds of the result, so that
+    /// the query can be answered with an empty result without querying the operators.
+    fn has_data_for(&self, _query: &QueryRectangle) -> bool {
+        true
+    }
+
     /// Map one descriptor to another one
     fn map<F>(self, f: F) -> Self
     where
@@ -34,10 +46,42 @@ pub trait ResultDescriptor: Copy {
 }
 
 /// A `ResultDescriptor` for raster queries
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RasterResultDescriptor {
     pub data_type: RasterDataType,
     pub spatial_reference: SpatialReferenceOption,
+    /// The spatial extent of the data, `None` if unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<BoundingBox2D>,
+    /// The time span of the data, `None` if unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_interval: Option<TimeInterval>,
+    /// The value of pixels without data, `None` if there is none or it is unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_data_value: Option<f64>,
+    /// The bands of the data in the order of their channels, empty if unknown
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bands: Vec<RasterBandDescriptor>,
+}
+
+impl RasterResultDescriptor {
+    /// The band with the `name` and its index in `bands`
+    pub fn band(&self, name: &str) -> Option<(usize, &RasterBandDescriptor)> {
+        self.bands
+            .iter()
+            .enumerate()
+            .find(|(_, band)| band.name == name)
+    }
+}
+
+/// The description of a single band of a raster, so that workflows can reference it by name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RasterBandDescriptor {
+    pub name: String,
+    pub measurement: Measurement,
+    /// The value of pixels without data in this band, `None` if there is none or it is unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_data_value: Option<f64>,
 }
 
 impl ResultDescriptor for RasterResultDescriptor {
@@ -51,6 +95,10 @@ impl ResultDescriptor for RasterResultDescriptor {
         self.spatial_reference
     }
 
+    fn has_data_for(&self, query: &QueryRectangle) -> bool {
+        bounds_intersect(self.bbox, self.time_interval, query)
+    }
+
     fn map_spatial_reference<F>(mut self, f: F) -> Self
     where
         F: Fn(Self::DataType) -> Self::DataType,
@@ -69,10 +117,16 @@ impl ResultDescriptor for RasterResultDescriptor {
 }
 
 /// A `ResultDescriptor` for vector queries
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VectorResultDescriptor {
     pub data_type: VectorDataType,
     pub spatial_reference: SpatialReferenceOption,
+    /// The spatial extent of the data, `None` if unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<BoundingBox2D>,
+    /// The time span of the data, `None` if unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_interval: Option<TimeInterval>,
 }
 
 impl ResultDescriptor for VectorResultDescriptor {
@@ -86,6 +140,10 @@ impl ResultDescriptor for VectorResultDescriptor {
         self.spatial_reference
     }
 
+    fn has_data_for(&self, query: &QueryRectangle) -> bool {
+        bounds_intersect(self.bbox, self.time_interval, query)
+    }
+
     fn map_spatial_reference<F>(mut self, f: F) -> Self
     where
         F: Fn(Self::DataType) -> Self::DataType,
@@ -102,3 +160,161 @@ impl ResultDescriptor for VectorResultDescriptor {
         self
     }
 }
+
+/// The smallest time interval that encloses all `time_intervals`, `None` if there are none
+pub fn enclosing_time_interval(time_intervals: &[TimeInterval]) -> Option<TimeInterval> {
+    let start = time_intervals.iter().map(TimeInterval::start).min()?;
+    let end = time_intervals.iter().map(TimeInterval::end).max()?;
+
+    Some(TimeInterval::new_unchecked(start, end))
+}
+
+/// Whether the known bounds of a result intersect the `query`, unknown bounds always do
+fn bounds_intersect(
+    bbox: Option<BoundingBox2D>,
+    time_interval: Option<TimeInterval>,
+    query: &QueryRectangle,
+) -> bool {
+    let spatially = bbox.map_or(true, |bbox| bbox.intersects_bbox(&query.bbox));
+    let temporally = time_interval.map_or(true, |time_interval| {
+        time_intervals_intersect(time_interval, query.time_interval)
+    });
+
+    spatially && temporally
+}
+
+/// Whether two time intervals share an instant.
+/// Intervals are half-open, but intervals with equal start and end are the instant itself.
+fn time_intervals_intersect(a: TimeInterval, b: TimeInterval) -> bool {
+    match (a.start() == a.end(), b.start() == b.end()) {
+        (true, true) => a == b,
+        (true, false) => b.start() <= a.start() && a.start() < b.end(),
+        (false, true) => a.start() <= b.start() && b.start() < a.end(),
+        (false, false) => a.intersects(&b),
+    }
+}
+
+/// The formats in which plot operators return their results
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PlotOutputFormat {
+    /// the plot's data as plain JSON
+    JsonPlain,
+    /// a Vega specification of the plot for embedding it into a Html page
+    JsonVega,
+}
+
+/// A `ResultDescriptor` for plot queries
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PlotResultDescriptor {
+    pub output_format: PlotOutputFormat,
+    pub spatial_reference: SpatialReferenceOption,
+}
+
+impl ResultDescriptor for PlotResultDescriptor {
+    type DataType = PlotOutputFormat;
+
+    fn data_type(&self) -> Self::DataType {
+        self.output_format
+    }
+
+    fn spatial_reference(&self) -> SpatialReferenceOption {
+        self.spatial_reference
+    }
+
+    fn map_spatial_reference<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Self::DataType) -> Self::DataType,
+    {
+        self.output_format = f(self.output_format);
+        self
+    }
+
+    fn map_data_type<F>(mut self, f: F) -> Self
+    where
+        F: Fn(SpatialReferenceOption) -> SpatialReferenceOption,
+    {
+        self.spatial_reference = f(self.spatial_reference);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geoengine_datatypes::primitives::SpatialResolution;
+
+    fn query(bbox: BoundingBox2D, time_interval: TimeInterval) -> QueryRectangle {
+        QueryRectangle {
+            bbox,
+            time_interval,
+            spatial_resolution: SpatialResolution::one(),
+        }
+    }
+
+    #[test]
+    fn has_data_for() {
+        let descriptor = RasterResultDescriptor {
+            data_type: RasterDataType::U8,
+            spatial_reference: SpatialReferenceOption::None,
+            bbox: Some(BoundingBox2D::new((0., 0.).into(), (10., 10.).into()).unwrap()),
+            time_interval: Some(TimeInterval::new_unchecked(10, 20)),
+            no_data_value: None,
+            bands: vec![],
+        };
+
+        let inside = BoundingBox2D::new((5., 5.).into(), (15., 15.).into()).unwrap();
+        let outside = BoundingBox2D::new((20., 20.).into(), (30., 30.).into()).unwrap();
+
+        assert!(descriptor.has_data_for(&query(inside, TimeInterval::new_unchecked(0, 11))));
+        assert!(descriptor.has_data_for(&query(inside, TimeInterval::new_unchecked(10, 10))));
+        assert!(!descriptor.has_data_for(&query(inside, TimeInterval::new_unchecked(20, 20))));
+        assert!(!descriptor.has_data_for(&query(inside, TimeInterval::new_unchecked(0, 10))));
+        assert!(!descriptor.has_data_for(&query(outside, TimeInterval::new_unchecked(10, 20))));
+
+        let unbounded = RasterResultDescriptor {
+            bbox: None,
+            time_interval: None,
+            ..descriptor
+        };
+
+        assert!(unbounded.has_data_for(&query(outside, TimeInterval::new_unchecked(0, 10))));
+    }
+
+    #[test]
+    fn bands() {
+        let descriptor: RasterResultDescriptor = serde_json::from_str(
+            r#"{
+                "data_type": "U16",
+                "spatial_reference": "EPSG:4326",
+                "bands": [
+                    {"name": "red", "measurement": "Unitless"},
+                    {
+                        "name": "nir",
+                        "measurement": {
+                            "Continuous": {"measurement": "reflectance", "unit": null}
+                        },
+                        "no_data_value": 0.0
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let (index, nir) = descriptor.band("nir").unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(
+            nir.measurement,
+            Measurement::continuous("reflectance".to_string(), None)
+        );
+        assert_eq!(nir.no_data_value, Some(0.));
+        assert!(descriptor.band("blue").is_none());
+
+        // descriptors without bands stay as they were
+        let serialized = serde_json::to_value(RasterResultDescriptor {
+            bands: vec![],
+            ..descriptor
+        })
+        .unwrap();
+        assert!(serialized.get("bands").is_none());
+    }
+}
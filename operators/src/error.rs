@@ -5,7 +5,7 @@ use std::ops::Range;
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(crate)")]
 pub enum Error {
-    #[snafu(display("CsvSource Error: {}", source))]
+    #[snafu(display("CsvSource Error"))]
     CsvSourceReader {
         source: csv::Error,
     },
@@ -13,7 +13,27 @@ pub enum Error {
     CsvSource {
         details: String,
     },
-    #[snafu(display("DataTypeError: {}", source))]
+    #[snafu(display("GpsSource Error: {}", details))]
+    GpsSource {
+        details: String,
+    },
+    #[snafu(display("ZarrSource Error: {}", details))]
+    ZarrSource {
+        details: String,
+    },
+    #[snafu(display("GribSource Error: {}", details))]
+    GribSource {
+        details: String,
+    },
+    #[snafu(display("AttributeJoin Error: {}", details))]
+    AttributeJoin {
+        details: String,
+    },
+    #[snafu(display("HttpError"))]
+    Http {
+        source: reqwest::Error,
+    },
+    #[snafu(display("DataTypeError"))]
     DataType {
         source: geoengine_datatypes::error::Error,
     },
@@ -47,16 +67,16 @@ pub enum Error {
         expected: Range<usize>,
         found: usize,
     },
-    #[snafu(display("GdalError: {}", source))]
+    #[snafu(display("GdalError"))]
     Gdal {
         #[snafu(source(from(gdal::errors::Error, failure::Fail::compat)))]
         source: failure::Compat<gdal::errors::Error>,
     },
-    #[snafu(display("IOError: {}", source))]
+    #[snafu(display("IOError"))]
     IO {
         source: std::io::Error,
     },
-    #[snafu(display("SerdeJsonError: {}", source))]
+    #[snafu(display("SerdeJsonError"))]
     SerdeJson {
         source: serde_json::Error,
     },
@@ -66,6 +86,118 @@ pub enum Error {
         found: String,
     },
     InvalidOperatorType,
+
+    #[snafu(display("InvalidDatasetDefinition: {}", reason))]
+    InvalidDatasetDefinition {
+        reason: String,
+    },
+    #[snafu(display("UnknownDataset: \"{}\"", id))]
+    UnknownDataset {
+        id: String,
+    },
+    DatasetDefinitionsLockFailed,
+    #[snafu(display("DatasetAccessDenied: \"{}\"", id))]
+    DatasetAccessDenied {
+        id: String,
+    },
+
+    #[snafu(display("UnknownPinnedDataset: \"{}\"", name))]
+    UnknownPinnedDataset {
+        name: String,
+    },
+    PinnedDatasetsLockFailed,
+    #[snafu(display(
+        "PinnedDatasetsFull: \"{}\" occupies {} bytes, but only {} bytes are available",
+        name,
+        bytes,
+        available
+    ))]
+    PinnedDatasetsFull {
+        name: String,
+        bytes: usize,
+        available: usize,
+    },
+
+    #[snafu(display("InvalidOperatorParameter: `{}` {}", parameter, reason))]
+    InvalidOperatorParameter {
+        parameter: String,
+        reason: String,
+    },
+
+    #[snafu(display("A no-data value is required because the raster has none"))]
+    NoDataValueRequired,
+
+    #[snafu(display("The tiles of the raster inputs are not spatially aligned"))]
+    UnalignedRasterTiles,
+
+    #[snafu(display(
+        "RemoteSourceUnavailable: `{}` is considered down, retry in {} seconds",
+        remote,
+        retry_after_seconds
+    ))]
+    RemoteSourceUnavailable {
+        remote: String,
+        retry_after_seconds: u64,
+    },
+    RemoteSourcePolicyLockFailed,
+
+    #[snafu(display(
+        "UnknownOperator: \"{}\", available operators are {}",
+        name,
+        available.join(", ")
+    ))]
+    UnknownOperator {
+        name: String,
+        available: Vec<String>,
+    },
+
+    #[snafu(display(
+        "UnresolvableWorkflow: \"{}\" is not registered or workflows cannot be referenced here",
+        workflow
+    ))]
+    UnresolvableWorkflow {
+        workflow: String,
+    },
+    #[snafu(display("CyclicWorkflowReference: \"{}\" references itself", workflow))]
+    CyclicWorkflowReference {
+        workflow: String,
+    },
+    #[snafu(display(
+        "InvalidWorkflowType: \"{}\" is a {} workflow, expected a {} workflow",
+        workflow,
+        found,
+        expected
+    ))]
+    InvalidWorkflowType {
+        workflow: String,
+        expected: String,
+        found: String,
+    },
+
+    #[snafu(display("PythonScriptError: {}", details))]
+    PythonScript {
+        details: String,
+    },
+
+    #[snafu(display("WasmModuleError: {}", details))]
+    WasmModule {
+        details: String,
+    },
+
+    #[snafu(display("RScriptError: {}", details))]
+    RScript {
+        details: String,
+    },
+
+    #[snafu(display("ArrowError"))]
+    Arrow {
+        source: arrow::error::ArrowError,
+    },
+
+    #[snafu(display("TokioJoinError"))]
+    TokioJoin {
+        source: tokio::task::JoinError,
+    },
 }
 
 impl From<geoengine_datatypes::error::Error> for Error {
@@ -24,6 +24,7 @@ pub mod adapters;
 pub mod engine;
 pub mod error;
 pub mod mock;
+#[cfg(feature = "processing")]
 pub mod processing;
 pub mod source;
 pub mod util;
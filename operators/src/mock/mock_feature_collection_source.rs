@@ -79,6 +79,8 @@ macro_rules! impl_mock_feature_collection_source {
                         Ok(VectorResultDescriptor {
                             data_type: <$geometry>::DATA_TYPE,
                             spatial_reference: SpatialReference::wgs84().into(), // TODO: get from `FeatureCollection`
+                            bbox: None,
+                            time_interval: None,
                         })
                     },
                     vec![],
@@ -104,6 +106,8 @@ macro_rules! impl_mock_feature_collection_source {
                 ))
             }
         }
+
+        $crate::register_operator!(Vector, $newtype);
     };
 }
 
@@ -253,6 +257,8 @@ mod tests {
         };
         let ctx = QueryContext {
             chunk_byte_size: 2 * std::mem::size_of::<Coordinate2D>(),
+            timeout: None,
+            seed: 0,
         };
 
         let stream = processor.vector_query(query_rectangle, ctx);
@@ -61,6 +61,8 @@ impl VectorOperator for MockPointSource {
                 Ok(VectorResultDescriptor {
                     data_type: VectorDataType::MultiPoint,
                     spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
                 })
             },
             vec![],
@@ -70,6 +72,8 @@ impl VectorOperator for MockPointSource {
     }
 }
 
+crate::register_operator!(Vector, MockPointSource);
+
 impl InitializedOperator<VectorResultDescriptor, TypedVectorQueryProcessor>
     for InitializedOperatorImpl<MockPointSourceParams, VectorResultDescriptor, ()>
 {
@@ -128,6 +132,8 @@ mod tests {
         };
         let ctx = QueryContext {
             chunk_byte_size: 2 * std::mem::size_of::<Coordinate2D>(),
+            timeout: None,
+            seed: 0,
         };
         let stream = point_processor.vector_query(query_rectangle, ctx);
 
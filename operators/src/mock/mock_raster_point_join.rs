@@ -12,6 +12,7 @@ use geoengine_datatypes::{
     raster::{GridPixelAccess, RasterTile2D},
     spatial_reference::SpatialReferenceOption,
 };
+use num_traits::AsPrimitive;
 use serde::{Deserialize, Serialize};
 
 pub struct MockRasterPointJoinProcessor<R, V> {
@@ -51,11 +52,18 @@ where
                 let raster_tile: RasterTile2D<T> =
                     raster_future.ok_or(crate::error::Error::QueryProcessor)??;
                 let pixel: T = raster_tile.data.pixel_value_at_grid_index(&(0, 0))?;
-                let pixel: f64 = pixel.as_();
-                let collection = collection.add_column(
-                    &self.feature_name,
-                    FeatureData::Number(vec![pixel; collection.len()]),
-                )?;
+
+                // no-data pixels are propagated as missing values instead of numbers
+                let data = if raster_tile.data.no_data_value.is_some() {
+                    let pixel: Option<f64> = Some(pixel)
+                        .filter(|&pixel| Some(pixel) != raster_tile.data.no_data_value)
+                        .map(AsPrimitive::as_);
+                    FeatureData::NullableNumber(vec![pixel; collection.len()])
+                } else {
+                    FeatureData::Number(vec![pixel.as_(); collection.len()])
+                };
+
+                let collection = collection.add_column(&self.feature_name, data)?;
                 Ok(collection)
             })
             .boxed()
@@ -100,6 +108,8 @@ impl VectorOperator for MockRasterPointJoinOperator {
                         |o| o.result_descriptor().spatial_reference,
                     ),
                     data_type: VectorDataType::MultiPoint,
+                    bbox: None,
+                    time_interval: None,
                 })
             },
             self.raster_sources,
@@ -109,6 +119,8 @@ impl VectorOperator for MockRasterPointJoinOperator {
     }
 }
 
+crate::register_operator!(Vector, MockRasterPointJoinOperator);
+
 impl InitializedOperator<VectorResultDescriptor, TypedVectorQueryProcessor>
     for InitializedOperatorImpl<MockRasterPointJoinParams, VectorResultDescriptor, ()>
 {
@@ -143,7 +155,7 @@ mod tests {
     use futures::executor::block_on_stream;
     use geoengine_datatypes::{
         primitives::SpatialResolution,
-        primitives::{BoundingBox2D, Coordinate2D, FeatureDataRef, TimeInterval},
+        primitives::{BoundingBox2D, Coordinate2D, FeatureDataRef, NullableDataRef, TimeInterval},
         raster::{Raster2D, RasterDataType, TileInformation},
         spatial_reference::SpatialReference,
     };
@@ -176,6 +188,7 @@ mod tests {
                 tile_size_in_pixels: [3, 2].into(),
             },
             data: raster,
+            statistics: None,
         };
 
         let mrs = MockRasterSource {
@@ -184,6 +197,10 @@ mod tests {
                 result_descriptor: RasterResultDescriptor {
                     data_type: RasterDataType::U8,
                     spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: None,
+                    bands: vec![],
                 },
             },
         }
@@ -310,6 +327,7 @@ mod tests {
                 tile_size_in_pixels: [3, 2].into(),
             },
             data: raster,
+            statistics: None,
         };
 
         let mrs = MockRasterSource {
@@ -318,6 +336,10 @@ mod tests {
                 result_descriptor: RasterResultDescriptor {
                     data_type: RasterDataType::U8,
                     spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: None,
+                    bands: vec![],
                 },
             },
         }
@@ -349,6 +371,8 @@ mod tests {
         };
         let ctx = QueryContext {
             chunk_byte_size: 2 * std::mem::size_of::<Coordinate2D>(),
+            timeout: None,
+            seed: 0,
         };
         let stream = point_processor.vector_query(query_rectangle, ctx);
 
@@ -376,4 +400,93 @@ mod tests {
 
         assert_eq!(numbers.as_ref(), &[1.0]);
     }
+
+    #[test]
+    fn propagates_no_data() {
+        let mps = MockPointSource {
+            params: MockPointSourceParams {
+                points: vec![Coordinate2D::new(1., 2.); 2],
+            },
+        }
+        .boxed();
+
+        let raster_tile = RasterTile2D {
+            time: TimeInterval::default(),
+            tile: TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, 0].into(),
+                global_size_in_tiles: [1, 1].into(),
+                global_tile_position: [0, 0].into(),
+                tile_size_in_pixels: [1, 2].into(),
+            },
+            data: Raster2D::new(
+                [1, 2].into(),
+                vec![0, 2],
+                Some(0),
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+            statistics: None,
+        };
+
+        let mrs = MockRasterSource {
+            params: MockRasterSourceParams {
+                data: vec![raster_tile],
+                result_descriptor: RasterResultDescriptor {
+                    data_type: RasterDataType::U8,
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: None,
+                    bands: vec![],
+                },
+            },
+        }
+        .boxed();
+
+        let op = MockRasterPointJoinOperator {
+            params: MockRasterPointJoinParams {
+                feature_name: "raster_values".to_string(),
+            },
+            raster_sources: vec![mrs],
+            vector_sources: vec![mps],
+        }
+        .boxed();
+
+        let point_processor = match op
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+        {
+            Ok(TypedVectorQueryProcessor::MultiPoint(processor)) => processor,
+            _ => panic!(),
+        };
+
+        let query_rectangle = QueryRectangle {
+            bbox: BoundingBox2D::new((0., 0.).into(), (4., 4.).into()).unwrap(),
+            time_interval: TimeInterval::default(),
+            spatial_resolution: SpatialResolution::zero_point_one(),
+        };
+        let ctx = QueryContext {
+            chunk_byte_size: 1024 * 1024,
+            timeout: None,
+            seed: 0,
+        };
+
+        let collections: Vec<MultiPointCollection> =
+            block_on_stream(point_processor.vector_query(query_rectangle, ctx))
+                .map(Result::unwrap)
+                .collect();
+        assert_eq!(collections.len(), 1);
+
+        let column = collections[0].data("raster_values").unwrap();
+        let numbers = if let FeatureDataRef::NullableNumber(numbers) = column {
+            numbers
+        } else {
+            panic!()
+        };
+
+        assert_eq!(numbers.nulls(), vec![true, true]);
+    }
 }
@@ -59,7 +59,7 @@ impl RasterOperator for MockRasterSource {
             self.params,
             context,
             |_, _, _, _| Ok(()),
-            |params, _, _, _, _| Ok(params.result_descriptor),
+            |params, _, _, _, _| Ok(params.result_descriptor.clone()),
             vec![],
             vec![],
         )
@@ -67,6 +67,8 @@ impl RasterOperator for MockRasterSource {
     }
 }
 
+crate::register_operator!(Raster, MockRasterSource);
+
 impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
     for InitializedOperatorImpl<MockRasterSourceParams, RasterResultDescriptor, ()>
 {
@@ -125,6 +127,7 @@ mod tests {
                 tile_size_in_pixels: [3, 2].into(),
             },
             data: raster,
+            statistics: None,
         };
 
         let mrs = MockRasterSource {
@@ -133,6 +136,10 @@ mod tests {
                 result_descriptor: RasterResultDescriptor {
                     data_type: RasterDataType::U8,
                     spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: None,
+                    bands: vec![],
                 },
             },
         }
@@ -0,0 +1,505 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedVectorOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, TypedVectorQueryProcessor,
+    VectorOperator, VectorQueryProcessor, VectorResultDescriptor,
+};
+use crate::error;
+use crate::util::Result;
+use futures::stream::{self, BoxStream};
+use futures::{FutureExt, StreamExt};
+use geoengine_datatypes::collections::{DataCollection, FeatureCollection, VectorDataType};
+use geoengine_datatypes::primitives::{FeatureData, FeatureDataType, Geometry};
+use geoengine_datatypes::util::arrow::ArrowTyped;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use snafu::ensure;
+use std::collections::HashMap;
+
+/// Parameters of the `AttributeJoin` operator.
+///
+/// Features are matched by the textual value of their `left_column` and the `right_column`
+/// of the table, so text keys like `"06534"` only match text keys. The `right_columns` of
+/// the table are added to the features.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttributeJoinParams {
+    pub left_column: String,
+    pub right_column: String,
+    pub right_columns: Vec<String>,
+}
+
+/// Joins the columns of a table, i.e., a `Data` collection, onto the features of a vector
+/// source by a key column.
+///
+/// The first vector source provides the features, the second one the table. This is a left
+/// join: features without a matching row get nulls and for duplicate keys the first row wins.
+/// The time of the table rows is ignored.
+pub type AttributeJoin = Operator<AttributeJoinParams>;
+
+#[typetag::serde]
+impl VectorOperator for AttributeJoin {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedVectorOperator>> {
+        ensure!(
+            self.vector_sources.len() == 2,
+            error::InvalidNumberOfVectorInputs {
+                expected: 2..3,
+                found: self.vector_sources.len()
+            }
+        );
+        ensure!(
+            self.raster_sources.is_empty(),
+            error::InvalidNumberOfRasterInputs {
+                expected: 0..1,
+                found: self.raster_sources.len()
+            }
+        );
+
+        InitializedAttributeJoin::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, _, vector_sources| {
+                let table_descriptor = vector_sources[1].result_descriptor();
+
+                ensure!(
+                    table_descriptor.data_type == VectorDataType::Data,
+                    error::InvalidType {
+                        expected: format!("{:?}", VectorDataType::Data),
+                        found: format!("{:?}", table_descriptor.data_type),
+                    }
+                );
+
+                Ok(vector_sources[0].result_descriptor())
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedAttributeJoin::boxed)
+    }
+}
+
+crate::register_operator!(Vector, AttributeJoin);
+
+pub type InitializedAttributeJoin =
+    InitializedOperatorImpl<AttributeJoinParams, VectorResultDescriptor, ()>;
+
+impl InitializedOperator<VectorResultDescriptor, TypedVectorQueryProcessor>
+    for InitializedAttributeJoin
+{
+    fn query_processor(&self) -> Result<TypedVectorQueryProcessor> {
+        let table = match self.vector_sources[1].query_processor()? {
+            TypedVectorQueryProcessor::Data(table) => table,
+            _ => return Err(error::Error::InvalidOperatorType),
+        };
+        let params = self.params.clone();
+
+        Ok(match self.vector_sources[0].query_processor()? {
+            TypedVectorQueryProcessor::Data(source) => TypedVectorQueryProcessor::Data(
+                AttributeJoinProcessor::new(source, table, params).boxed(),
+            ),
+            TypedVectorQueryProcessor::MultiPoint(source) => TypedVectorQueryProcessor::MultiPoint(
+                AttributeJoinProcessor::new(source, table, params).boxed(),
+            ),
+            TypedVectorQueryProcessor::MultiLineString(source) => {
+                TypedVectorQueryProcessor::MultiLineString(
+                    AttributeJoinProcessor::new(source, table, params).boxed(),
+                )
+            }
+            TypedVectorQueryProcessor::MultiPolygon(source) => {
+                TypedVectorQueryProcessor::MultiPolygon(
+                    AttributeJoinProcessor::new(source, table, params).boxed(),
+                )
+            }
+        })
+    }
+}
+
+pub struct AttributeJoinProcessor<G> {
+    source: Box<dyn VectorQueryProcessor<VectorType = FeatureCollection<G>>>,
+    table: Box<dyn VectorQueryProcessor<VectorType = DataCollection>>,
+    params: AttributeJoinParams,
+}
+
+impl<G> AttributeJoinProcessor<G>
+where
+    G: Geometry + ArrowTyped + Sync + Send + 'static,
+{
+    pub fn new(
+        source: Box<dyn VectorQueryProcessor<VectorType = FeatureCollection<G>>>,
+        table: Box<dyn VectorQueryProcessor<VectorType = DataCollection>>,
+        params: AttributeJoinParams,
+    ) -> Self {
+        Self {
+            source,
+            table,
+            params,
+        }
+    }
+}
+
+impl<G> QueryProcessor for AttributeJoinProcessor<G>
+where
+    G: Geometry + ArrowTyped + Sync + Send + 'static,
+{
+    type Output = FeatureCollection<G>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        // the table is collected and indexed once and then joined onto every collection
+        self.table
+            .vector_query(query, ctx)
+            .collect::<Vec<_>>()
+            .map(move |tables| {
+                match tables
+                    .into_iter()
+                    .collect::<Result<Vec<_>>>()
+                    .and_then(|tables| JoinTable::new(&tables, &self.params))
+                {
+                    Ok(table) => {
+                        let left_column = self.params.left_column.clone();
+                        self.source
+                            .vector_query(query, ctx)
+                            .map(move |collection| table.join(&collection?, &left_column))
+                            .boxed()
+                    }
+                    Err(error) => stream::once(async move { Err(error) }).boxed(),
+                }
+            })
+            .flatten_stream()
+            .boxed()
+    }
+}
+
+/// The joined columns of a table, indexed by their key
+struct JoinTable {
+    rows: HashMap<String, usize>,
+    columns: Vec<(String, FeatureDataType, Vec<Value>)>,
+}
+
+impl JoinTable {
+    fn new(tables: &[DataCollection], params: &AttributeJoinParams) -> Result<Self> {
+        let first_table = tables.first().ok_or_else(|| error::Error::AttributeJoin {
+            details: "The table source returned no data".to_string(),
+        })?;
+
+        let mut columns = params
+            .right_columns
+            .iter()
+            .map(|name| Ok((name.clone(), first_table.column_type(name)?, Vec::new())))
+            .collect::<Result<Vec<_>>>()?;
+        let mut rows = HashMap::new();
+        let mut offset = 0;
+
+        for table in tables {
+            for (row, key) in table.data(&params.right_column)?.json_values().enumerate() {
+                if let Some(key) = key_string(&key) {
+                    rows.entry(key).or_insert(offset + row);
+                }
+            }
+
+            for (name, _, values) in &mut columns {
+                values.extend(table.data(name)?.json_values());
+            }
+
+            offset += table.len();
+        }
+
+        Ok(Self { rows, columns })
+    }
+
+    fn join<G>(
+        &self,
+        collection: &FeatureCollection<G>,
+        left_column: &str,
+    ) -> Result<FeatureCollection<G>>
+    where
+        G: Geometry + ArrowTyped,
+    {
+        let rows: Vec<Option<usize>> = collection
+            .data(left_column)?
+            .json_values()
+            .map(|key| key_string(&key).and_then(|key| self.rows.get(&key).copied()))
+            .collect();
+
+        let joined: Vec<(&str, FeatureData)> = self
+            .columns
+            .iter()
+            .map(|(name, data_type, values)| {
+                let values = rows.iter().map(|row| row.map(|row| &values[row]));
+
+                let data = match data_type {
+                    FeatureDataType::Text | FeatureDataType::NullableText => {
+                        FeatureData::NullableText(
+                            values
+                                .map(|v| v.and_then(Value::as_str).map(ToString::to_string))
+                                .collect(),
+                        )
+                    }
+                    FeatureDataType::Number | FeatureDataType::NullableNumber => {
+                        FeatureData::NullableNumber(
+                            values.map(|v| v.and_then(Value::as_f64)).collect(),
+                        )
+                    }
+                    FeatureDataType::Decimal | FeatureDataType::NullableDecimal => {
+                        FeatureData::NullableDecimal(
+                            values.map(|v| v.and_then(Value::as_i64)).collect(),
+                        )
+                    }
+                    FeatureDataType::Categorical | FeatureDataType::NullableCategorical => {
+                        FeatureData::NullableCategorical(
+                            values
+                                .map(|v| v.and_then(Value::as_u64).map(|c| c as u8))
+                                .collect(),
+                        )
+                    }
+                };
+
+                (name.as_str(), data)
+            })
+            .collect();
+
+        Ok(collection.add_columns(&joined)?)
+    }
+}
+
+/// The textual representation of a key, numbers without fraction are written as integers
+/// so that decimal and number keys match
+fn key_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(match number.as_f64() {
+            Some(float) if number.is_f64() && float.fract() == 0. => format!("{}", float as i64),
+            _ => number.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockFeatureCollectionSource, MockFeatureCollectionSourceParams};
+    use geoengine_datatypes::collections::MultiPointCollection;
+    use geoengine_datatypes::primitives::{
+        BoundingBox2D, MultiPoint, SpatialResolution, TimeInterval,
+    };
+
+    fn params() -> AttributeJoinParams {
+        AttributeJoinParams {
+            left_column: "id".to_string(),
+            right_column: "key".to_string(),
+            right_columns: vec!["population".to_string(), "name".to_string()],
+        }
+    }
+
+    fn points(ids: FeatureData) -> MultiPointCollection {
+        MultiPointCollection::from_data(
+            MultiPoint::many(vec![(0.0, 0.1), (1.0, 1.1), (2.0, 2.1)]).unwrap(),
+            vec![TimeInterval::default(); 3],
+            [("id".to_string(), ids)].iter().cloned().collect(),
+        )
+        .unwrap()
+    }
+
+    fn table(keys: FeatureData) -> DataCollection {
+        DataCollection::from_data(
+            vec![],
+            vec![TimeInterval::default(); 3],
+            [
+                ("key".to_string(), keys),
+                (
+                    "population".to_string(),
+                    FeatureData::NullableDecimal(vec![Some(100), None, Some(300)]),
+                ),
+                (
+                    "name".to_string(),
+                    FeatureData::Text(vec!["a".into(), "b".into(), "c".into()]),
+                ),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+        )
+        .unwrap()
+    }
+
+    fn operator(points: MultiPointCollection, table: DataCollection) -> Box<dyn VectorOperator> {
+        AttributeJoin {
+            params: params(),
+            raster_sources: vec![],
+            vector_sources: vec![
+                MockFeatureCollectionSource {
+                    params: MockFeatureCollectionSourceParams { collection: points },
+                }
+                .boxed(),
+                MockFeatureCollectionSource {
+                    params: MockFeatureCollectionSourceParams { collection: table },
+                }
+                .boxed(),
+            ],
+        }
+        .boxed()
+    }
+
+    async fn joined(operator: Box<dyn VectorOperator>) -> Result<Vec<MultiPointCollection>> {
+        let processor = match operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+        {
+            TypedVectorQueryProcessor::MultiPoint(processor) => processor,
+            _ => panic!("expected a point processor"),
+        };
+
+        let query = QueryRectangle {
+            bbox: BoundingBox2D::new((0., 0.).into(), (4., 4.).into()).unwrap(),
+            time_interval: TimeInterval::default(),
+            spatial_resolution: SpatialResolution::zero_point_one(),
+        };
+        let ctx = QueryContext {
+            chunk_byte_size: 1024 * 1024,
+            timeout: None,
+            seed: 0,
+        };
+
+        processor
+            .vector_query(query, ctx)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn serde() {
+        let operator = AttributeJoin {
+            params: params(),
+            raster_sources: vec![],
+            vector_sources: vec![],
+        }
+        .boxed();
+
+        let serialized = serde_json::to_string(&operator).unwrap();
+
+        assert_eq!(
+            serialized,
+            serde_json::json!({
+                "type": "AttributeJoin",
+                "params": {
+                    "left_column": "id",
+                    "right_column": "key",
+                    "right_columns": ["population", "name"]
+                },
+                "raster_sources": [],
+                "vector_sources": []
+            })
+            .to_string()
+        );
+
+        let _: Box<dyn VectorOperator> = serde_json::from_str(&serialized).unwrap();
+    }
+
+    #[tokio::test]
+    async fn join_text_keys() {
+        let points = points(FeatureData::Text(vec![
+            "03".into(),
+            "01".into(),
+            "99".into(),
+        ]));
+        let table = table(FeatureData::Text(vec![
+            "01".into(),
+            "02".into(),
+            "03".into(),
+        ]));
+
+        let collections = joined(operator(points.clone(), table)).await.unwrap();
+
+        assert_eq!(collections.len(), 1);
+        assert_eq!(
+            collections[0],
+            points
+                .add_columns(&[
+                    (
+                        "population",
+                        FeatureData::NullableDecimal(vec![Some(300), Some(100), None])
+                    ),
+                    (
+                        "name",
+                        FeatureData::NullableText(vec![Some("c".into()), Some("a".into()), None])
+                    ),
+                ])
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn join_number_onto_decimal_keys() {
+        let points = points(FeatureData::Decimal(vec![2, 3, 1]));
+        let table = table(FeatureData::Number(vec![1., 2., 2.]));
+
+        let collections = joined(operator(points.clone(), table)).await.unwrap();
+
+        assert_eq!(
+            collections[0],
+            points
+                .add_columns(&[
+                    (
+                        "population",
+                        FeatureData::NullableDecimal(vec![None, None, Some(100)])
+                    ),
+                    (
+                        "name",
+                        FeatureData::NullableText(vec![Some("b".into()), None, Some("a".into())])
+                    ),
+                ])
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn conflicting_column() {
+        let points = points(FeatureData::Text(vec![
+            "01".into(),
+            "02".into(),
+            "03".into(),
+        ]))
+        .add_column("name", FeatureData::Number(vec![0., 1., 2.]))
+        .unwrap();
+        let table = table(FeatureData::Text(vec![
+            "01".into(),
+            "02".into(),
+            "03".into(),
+        ]));
+
+        assert!(joined(operator(points, table)).await.is_err());
+    }
+
+    #[test]
+    fn requires_table_source() {
+        let points = points(FeatureData::Decimal(vec![1, 2, 3]));
+
+        let operator = AttributeJoin {
+            params: params(),
+            raster_sources: vec![],
+            vector_sources: vec![
+                MockFeatureCollectionSource {
+                    params: MockFeatureCollectionSourceParams {
+                        collection: points.clone(),
+                    },
+                }
+                .boxed(),
+                MockFeatureCollectionSource {
+                    params: MockFeatureCollectionSourceParams { collection: points },
+                }
+                .boxed(),
+            ],
+        }
+        .boxed();
+
+        assert!(operator
+            .initialize(&ExecutionContext::mock_empty())
+            .is_err());
+    }
+}
@@ -0,0 +1,454 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::tile_grid::{collect_time_steps, flatten_result};
+use crate::util::Result;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use geoengine_datatypes::primitives::{TimeInstance, TimeInterval};
+use geoengine_datatypes::raster::{Pixel, Raster2D, RasterDataType, RasterTile2D};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+pub const NO_CHANGE: u8 = 0;
+pub const INCREASE: u8 = 1;
+pub const DECREASE: u8 = 2;
+pub const CHANGE_NO_DATA: u8 = 255;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChangeDetectionParams {
+    pub time_steps: ChangeDetectionTimeSteps,
+    pub method: ChangeDetectionMethod,
+    /// changes with a magnitude up to the threshold are classified as no change
+    pub threshold: f64,
+}
+
+/// The time steps of the raster series that are compared
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ChangeDetectionTimeSteps {
+    /// Compare the time steps that contain the `before` and `after` instants
+    Instants {
+        before: TimeInstance,
+        after: TimeInstance,
+    },
+    /// Compare every time step of the query with its predecessor
+    Consecutive,
+}
+
+/// How the change between the two values of a pixel is measured
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeDetectionMethod {
+    /// `after - before`
+    Difference,
+    /// `(after - before) / before`
+    Ratio,
+}
+
+/// Detects the per-pixel change between two time steps of a raster series.
+///
+/// The output classifies every pixel as [`NO_CHANGE`], [`INCREASE`] or [`DECREASE`] and has
+/// the time interval of the later time step.
+pub type ChangeDetection = Operator<ChangeDetectionParams>;
+
+#[typetag::serde]
+impl RasterOperator for ChangeDetection {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.is_empty(),
+            error::InvalidNumberOfVectorInputs {
+                expected: 0..1,
+                found: self.vector_sources.len()
+            }
+        );
+        ensure!(
+            self.params.threshold >= 0.,
+            error::InvalidOperatorParameter {
+                parameter: "threshold".to_string(),
+                reason: "must not be negative".to_string(),
+            }
+        );
+        if let ChangeDetectionTimeSteps::Instants { before, after } = self.params.time_steps {
+            ensure!(
+                before < after,
+                error::InvalidOperatorParameter {
+                    parameter: "time_steps".to_string(),
+                    reason: "before must precede after".to_string(),
+                }
+            );
+        }
+
+        InitializedChangeDetection::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, _| {
+                Ok(RasterResultDescriptor {
+                    data_type: RasterDataType::U8,
+                    no_data_value: Some(f64::from(CHANGE_NO_DATA)),
+                    bands: vec![],
+                    ..raster_sources[0].result_descriptor()
+                })
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedChangeDetection::boxed)
+    }
+}
+
+crate::register_operator!(Raster, ChangeDetection);
+
+pub type InitializedChangeDetection =
+    InitializedOperatorImpl<ChangeDetectionParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedChangeDetection
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let params = self.params.clone();
+
+        Ok(TypedRasterQueryProcessor::U8(
+            crate::call_on_generic_raster_processor!(
+                self.raster_sources[0].query_processor()?,
+                source => RasterQueryProcessor::boxed(ChangeDetectionProcessor { source, params })
+            ),
+        ))
+    }
+}
+
+pub struct ChangeDetectionProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    params: ChangeDetectionParams,
+}
+
+impl<T> ChangeDetectionProcessor<T>
+where
+    T: Pixel,
+{
+    async fn change_tiles(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<RasterTile2D<u8>>> {
+        let mut tiles = Vec::new();
+
+        match self.params.time_steps {
+            ChangeDetectionTimeSteps::Instants { before, after } => {
+                let before_tiles = self.time_step_at(before, query, ctx).await?;
+                let after_tiles = self.time_step_at(after, query, ctx).await?;
+
+                tiles.extend(self.compare(&before_tiles, &after_tiles)?);
+            }
+            ChangeDetectionTimeSteps::Consecutive => {
+                let mut time_steps =
+                    collect_time_steps(self.source.raster_query(query, ctx)).await?;
+                time_steps.sort_by_key(|(time, _)| time.start());
+
+                for pair in time_steps.windows(2) {
+                    tiles.extend(self.compare(&pair[0].1, &pair[1].1)?);
+                }
+            }
+        }
+
+        Ok(tiles)
+    }
+
+    /// The tiles of the time step that contains `instant`
+    async fn time_step_at(
+        &self,
+        instant: TimeInstance,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<RasterTile2D<T>>> {
+        let query = QueryRectangle {
+            time_interval: TimeInterval::new_unchecked(instant, instant),
+            ..query
+        };
+
+        let time_steps = collect_time_steps(self.source.raster_query(query, ctx)).await?;
+
+        Ok(time_steps
+            .into_iter()
+            .find(|(time, _)| time.start() <= instant && instant < time.end())
+            .map(|(_, tiles)| tiles)
+            .unwrap_or_default())
+    }
+
+    /// Compare the tiles of two time steps at the same positions
+    fn compare(
+        &self,
+        before_tiles: &[RasterTile2D<T>],
+        after_tiles: &[RasterTile2D<T>],
+    ) -> Result<Vec<RasterTile2D<u8>>> {
+        after_tiles
+            .iter()
+            .filter_map(|after| {
+                before_tiles
+                    .iter()
+                    .find(|before| before.tile == after.tile)
+                    .map(|before| self.compare_tiles(before, after))
+            })
+            .collect()
+    }
+
+    fn compare_tiles(
+        &self,
+        before: &RasterTile2D<T>,
+        after: &RasterTile2D<T>,
+    ) -> Result<RasterTile2D<u8>> {
+        let value = |tile: &RasterTile2D<T>, pixel: T| -> Option<f64> {
+            if Some(pixel) == tile.data.no_data_value {
+                return None;
+            }
+            let value: f64 = pixel.as_();
+            if value.is_nan() {
+                None
+            } else {
+                Some(value)
+            }
+        };
+
+        let data = before
+            .data
+            .data_container
+            .iter()
+            .zip(&after.data.data_container)
+            .map(|(&before_pixel, &after_pixel)| {
+                match (value(before, before_pixel), value(after, after_pixel)) {
+                    (Some(before_value), Some(after_value)) => classify(
+                        self.change(before_value, after_value),
+                        self.params.threshold,
+                    ),
+                    _ => CHANGE_NO_DATA,
+                }
+            })
+            .collect();
+
+        Ok(RasterTile2D::new(
+            after.time,
+            after.tile,
+            Raster2D::new(
+                after.tile.tile_size_in_pixels(),
+                data,
+                Some(CHANGE_NO_DATA),
+                after.data.temporal_bounds,
+                after.data.geo_transform,
+            )?,
+        ))
+    }
+
+    #[allow(clippy::float_cmp)]
+    fn change(&self, before: f64, after: f64) -> f64 {
+        match self.params.method {
+            ChangeDetectionMethod::Difference => after - before,
+            ChangeDetectionMethod::Ratio if before == 0. => {
+                if after == 0. {
+                    0.
+                } else {
+                    after.signum() * f64::INFINITY
+                }
+            }
+            ChangeDetectionMethod::Ratio => (after - before) / before.abs(),
+        }
+    }
+}
+
+fn classify(change: f64, threshold: f64) -> u8 {
+    if change > threshold {
+        INCREASE
+    } else if change < -threshold {
+        DECREASE
+    } else {
+        NO_CHANGE
+    }
+}
+
+impl<T> QueryProcessor for ChangeDetectionProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<u8>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        stream::once(self.change_tiles(query, ctx))
+            .flat_map(|result| stream::iter(flatten_result(result)))
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution};
+    use geoengine_datatypes::raster::TileInformation;
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    fn series(
+        method: ChangeDetectionMethod,
+        time_steps: ChangeDetectionTimeSteps,
+    ) -> Box<dyn RasterOperator> {
+        let tile = |start: i64, data: Vec<u8>| RasterTile2D {
+            time: TimeInterval::new_unchecked(start, start + 10),
+            tile: TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, 0].into(),
+                global_size_in_tiles: [1, 1].into(),
+                global_tile_position: [0, 0].into(),
+                tile_size_in_pixels: [2, 2].into(),
+            },
+            data: Raster2D::new(
+                [2, 2].into(),
+                data,
+                Some(0),
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+            statistics: None,
+        };
+
+        ChangeDetection {
+            params: ChangeDetectionParams {
+                time_steps,
+                method,
+                threshold: 1.,
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![
+                        tile(0, vec![10, 10, 10, 10]),
+                        tile(10, vec![12, 9, 5, 0]),
+                        tile(20, vec![12, 12, 12, 12]),
+                    ],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed()
+    }
+
+    async fn run(operator: Box<dyn RasterOperator>) -> Vec<RasterTile2D<u8>> {
+        let processor = operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_u8()
+            .unwrap();
+
+        processor
+            .raster_query(
+                QueryRectangle {
+                    bbox: BoundingBox2D::new((0., -2.).into(), (2., 0.).into()).unwrap(),
+                    time_interval: TimeInterval::new_unchecked(0, 30),
+                    spatial_resolution: SpatialResolution::one(),
+                },
+                QueryContext {
+                    chunk_byte_size: 1024 * 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .map(Result::unwrap)
+            .collect()
+            .await
+    }
+
+    #[test]
+    fn serde() {
+        let operator = series(
+            ChangeDetectionMethod::Ratio,
+            ChangeDetectionTimeSteps::Instants {
+                before: TimeInstance::from_millis(0),
+                after: TimeInstance::from_millis(10),
+            },
+        );
+
+        let serialized = serde_json::to_string(&operator).unwrap();
+        let deserialized: Box<dyn RasterOperator> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+    }
+
+    #[tokio::test]
+    async fn difference_between_instants() {
+        let tiles = run(series(
+            ChangeDetectionMethod::Difference,
+            ChangeDetectionTimeSteps::Instants {
+                before: TimeInstance::from_millis(5),
+                after: TimeInstance::from_millis(15),
+            },
+        ))
+        .await;
+
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].time, TimeInterval::new_unchecked(10, 20));
+        assert_eq!(
+            tiles[0].data.data_container,
+            vec![INCREASE, NO_CHANGE, DECREASE, CHANGE_NO_DATA]
+        );
+    }
+
+    #[tokio::test]
+    async fn ratio_of_consecutive_steps() {
+        let tiles = run(series(
+            ChangeDetectionMethod::Ratio,
+            ChangeDetectionTimeSteps::Consecutive,
+        ))
+        .await;
+
+        assert_eq!(tiles.len(), 2);
+        assert_eq!(
+            tiles[0].data.data_container,
+            vec![NO_CHANGE, NO_CHANGE, NO_CHANGE, CHANGE_NO_DATA]
+        );
+        assert_eq!(tiles[1].time, TimeInterval::new_unchecked(20, 30));
+        assert_eq!(
+            tiles[1].data.data_container,
+            vec![NO_CHANGE, NO_CHANGE, INCREASE, CHANGE_NO_DATA]
+        );
+    }
+
+    #[test]
+    fn instants_must_be_ordered() {
+        let operator = series(
+            ChangeDetectionMethod::Difference,
+            ChangeDetectionTimeSteps::Instants {
+                before: TimeInstance::from_millis(10),
+                after: TimeInstance::from_millis(10),
+            },
+        );
+
+        assert!(operator
+            .initialize(&ExecutionContext::mock_empty())
+            .is_err());
+    }
+}
@@ -0,0 +1,759 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedPlotOperator,
+    Operator, PlotOperator, PlotOutputFormat, PlotQueryProcessor, PlotResultDescriptor,
+    QueryContext, QueryRectangle, RasterQueryProcessor, ResultDescriptor, TypedPlotQueryProcessor,
+    TypedVectorQueryProcessor, VectorQueryProcessor,
+};
+use crate::error;
+use crate::source::{statistics_dataset, RasterDatasetStatistics};
+use crate::util::Result;
+use futures::future::{self, BoxFuture};
+use futures::{FutureExt, TryStreamExt};
+use geoengine_datatypes::collections::FeatureCollection;
+use geoengine_datatypes::primitives::{FeatureDataRef, Geometry, NullableDataRef};
+use geoengine_datatypes::raster::Pixel;
+use geoengine_datatypes::util::arrow::ArrowTyped;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt};
+
+/// Natural breaks are computed on an evenly spaced sample of at most this many sorted values,
+/// because the optimization is quadratic in the number of values
+const MAX_NATURAL_BREAKS_VALUES: usize = 4096;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClassBreaksParams {
+    pub method: ClassBreaksMethod,
+    pub number_of_classes: usize,
+    /// the numeric column of a vector source, must be `None` for raster sources
+    #[serde(default)]
+    pub column_name: Option<String>,
+    /// compute the breaks of a raster `GdalSource` from the precomputed statistics of its whole
+    /// dataset instead of the values within the query
+    #[serde(default)]
+    pub dataset_statistics: bool,
+}
+
+/// How the range of values is divided into classes
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassBreaksMethod {
+    /// classes of equal width between the minimum and the maximum
+    EqualInterval,
+    /// classes with an equal number of values
+    Quantile,
+    /// classes that minimize the variance within and maximize it between classes (Jenks)
+    NaturalBreaks,
+}
+
+/// The result of a `ClassBreaks` query
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClassBreaksResult {
+    /// ascending bounds of the classes, starting with the minimum and ending with the maximum
+    pub breaks: Vec<f64>,
+    /// the number of valid values in the query
+    pub value_count: usize,
+}
+
+/// Computes class breaks of the values of a raster or of a numeric column of a vector source
+/// within the query, e.g., for building the legend of a choropleth map or a colorizer.
+///
+/// No-data pixels, null values and non-finite values are ignored. Breaks that coincide, e.g.,
+/// if there are fewer distinct values than classes, are merged, so there may be fewer classes
+/// than requested.
+pub type ClassBreaks = Operator<ClassBreaksParams>;
+
+#[typetag::serde]
+impl PlotOperator for ClassBreaks {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedPlotOperator>> {
+        let vector_input = !self.vector_sources.is_empty();
+
+        if vector_input {
+            ensure!(
+                self.raster_sources.is_empty(),
+                error::InvalidNumberOfRasterInputs {
+                    expected: 0..1,
+                    found: self.raster_sources.len()
+                }
+            );
+            ensure!(
+                self.vector_sources.len() == 1,
+                error::InvalidNumberOfVectorInputs {
+                    expected: 1..2,
+                    found: self.vector_sources.len()
+                }
+            );
+        } else {
+            ensure!(
+                self.raster_sources.len() == 1,
+                error::InvalidNumberOfRasterInputs {
+                    expected: 1..2,
+                    found: self.raster_sources.len()
+                }
+            );
+        }
+        ensure!(
+            self.params.column_name.is_some() == vector_input,
+            error::InvalidOperatorParameter {
+                parameter: "column_name".to_string(),
+                reason: "is required for vector sources and not allowed for raster sources"
+                    .to_string(),
+            }
+        );
+        ensure!(
+            self.params.number_of_classes > 0,
+            error::InvalidOperatorParameter {
+                parameter: "number_of_classes".to_string(),
+                reason: "must be positive".to_string(),
+            }
+        );
+
+        let dataset_id = if self.params.dataset_statistics {
+            let dataset = self
+                .raster_sources
+                .first()
+                .and_then(|raster| statistics_dataset(raster.as_ref()));
+            Some(
+                dataset.context(error::InvalidOperatorParameter {
+                    parameter: "dataset_statistics".to_string(),
+                    reason: "requires a `GdalSource` that reads the first channel of its dataset"
+                        .to_string(),
+                })?,
+            )
+        } else {
+            None
+        };
+
+        InitializedClassBreaks::create(
+            self.params,
+            context,
+            |_, context, _, _| {
+                let dataset = match &dataset_id {
+                    Some(dataset) => dataset,
+                    None => return Ok(None),
+                };
+
+                let statistics = context
+                    .dataset_statistics
+                    .as_ref()
+                    .and_then(|statistics| {
+                        statistics
+                            .read()
+                            .ok()
+                            .and_then(|statistics| statistics.get(dataset).cloned())
+                    })
+                    .context(error::InvalidOperatorParameter {
+                        parameter: "dataset_statistics".to_string(),
+                        reason: format!("there are no statistics of dataset `{}` yet", dataset),
+                    })?;
+
+                Ok(Some(statistics))
+            },
+            |_, _, _, raster_sources, vector_sources| {
+                let spatial_reference = match (raster_sources.first(), vector_sources.first()) {
+                    (Some(raster), _) => raster.result_descriptor().spatial_reference(),
+                    (None, Some(vector)) => vector.result_descriptor().spatial_reference(),
+                    (None, None) => unreachable!("the number of sources was checked"),
+                };
+
+                Ok(PlotResultDescriptor {
+                    output_format: PlotOutputFormat::JsonPlain,
+                    spatial_reference,
+                })
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedClassBreaks::boxed)
+    }
+}
+
+crate::register_operator!(Plot, ClassBreaks);
+
+pub type InitializedClassBreaks = InitializedOperatorImpl<
+    ClassBreaksParams,
+    PlotResultDescriptor,
+    Option<RasterDatasetStatistics>,
+>;
+
+impl InitializedOperator<PlotResultDescriptor, TypedPlotQueryProcessor> for InitializedClassBreaks {
+    fn query_processor(&self) -> Result<TypedPlotQueryProcessor> {
+        let params = self.params.clone();
+
+        if let Some(raster_source) = self.raster_sources.first() {
+            let dataset_statistics = self.state.clone();
+            return Ok(crate::call_on_generic_raster_processor!(
+                raster_source.query_processor()?,
+                source => TypedPlotQueryProcessor::JsonPlain(
+                    RasterClassBreaksProcessor { source, params, dataset_statistics }.boxed()
+                )
+            ));
+        }
+
+        let column_name = params.column_name.clone().unwrap_or_default();
+
+        Ok(TypedPlotQueryProcessor::JsonPlain(
+            match self.vector_sources[0].query_processor()? {
+                TypedVectorQueryProcessor::Data(source) => VectorClassBreaksProcessor {
+                    source,
+                    column_name,
+                    params,
+                }
+                .boxed(),
+                TypedVectorQueryProcessor::MultiPoint(source) => VectorClassBreaksProcessor {
+                    source,
+                    column_name,
+                    params,
+                }
+                .boxed(),
+                TypedVectorQueryProcessor::MultiLineString(source) => VectorClassBreaksProcessor {
+                    source,
+                    column_name,
+                    params,
+                }
+                .boxed(),
+                TypedVectorQueryProcessor::MultiPolygon(source) => VectorClassBreaksProcessor {
+                    source,
+                    column_name,
+                    params,
+                }
+                .boxed(),
+            },
+        ))
+    }
+}
+
+pub struct RasterClassBreaksProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    params: ClassBreaksParams,
+    /// the statistics of the whole dataset that replace the values of the query, if any
+    dataset_statistics: Option<RasterDatasetStatistics>,
+}
+
+impl<T> PlotQueryProcessor for RasterClassBreaksProcessor<T>
+where
+    T: Pixel,
+{
+    type OutputFormat = serde_json::Value;
+
+    fn plot_query(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> BoxFuture<Result<Self::OutputFormat>> {
+        async move {
+            if let Some(statistics) = &self.dataset_statistics {
+                return dataset_class_breaks_json(&self.params, statistics);
+            }
+
+            let values = self
+                .source
+                .raster_query(query, ctx)
+                .try_fold(Vec::new(), |mut values, tile| {
+                    let no_data_value = tile.data.no_data_value;
+                    values.extend(
+                        tile.data
+                            .data_container
+                            .iter()
+                            .filter(|&&pixel| Some(pixel) != no_data_value)
+                            .map(|&pixel| -> f64 { pixel.as_() }),
+                    );
+                    future::ok(values)
+                })
+                .await?;
+
+            class_breaks_json(&self.params, values)
+        }
+        .boxed()
+    }
+}
+
+pub struct VectorClassBreaksProcessor<G> {
+    source: Box<dyn VectorQueryProcessor<VectorType = FeatureCollection<G>>>,
+    column_name: String,
+    params: ClassBreaksParams,
+}
+
+impl<G> PlotQueryProcessor for VectorClassBreaksProcessor<G>
+where
+    G: Geometry + ArrowTyped + Sync + Send + 'static,
+{
+    type OutputFormat = serde_json::Value;
+
+    fn plot_query(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> BoxFuture<Result<Self::OutputFormat>> {
+        async move {
+            let values = self
+                .source
+                .vector_query(query, ctx)
+                .try_fold(Vec::new(), |mut values, collection| {
+                    future::ready(
+                        collection
+                            .data(&self.column_name)
+                            .context(error::DataType)
+                            .and_then(|data| extend_with_numbers(&mut values, data))
+                            .map(|_| values),
+                    )
+                })
+                .await?;
+
+            class_breaks_json(&self.params, values)
+        }
+        .boxed()
+    }
+}
+
+/// Add the non-null values of a numeric column to `values`
+fn extend_with_numbers(values: &mut Vec<f64>, data: FeatureDataRef) -> Result<()> {
+    match data {
+        FeatureDataRef::Number(number_ref) => values.extend(number_ref.as_ref()),
+        FeatureDataRef::NullableNumber(number_ref) => values.extend(
+            number_ref
+                .as_ref()
+                .iter()
+                .zip(number_ref.nulls())
+                .filter(|(_, is_null)| !is_null)
+                .map(|(&value, _)| value),
+        ),
+        FeatureDataRef::Decimal(decimal_ref) => {
+            values.extend(decimal_ref.as_ref().iter().map(|&value| value as f64))
+        }
+        FeatureDataRef::NullableDecimal(decimal_ref) => values.extend(
+            decimal_ref
+                .as_ref()
+                .iter()
+                .zip(decimal_ref.nulls())
+                .filter(|(_, is_null)| !is_null)
+                .map(|(&value, _)| value as f64),
+        ),
+        _ => {
+            return Err(error::Error::InvalidType {
+                expected: "a numeric column".to_string(),
+                found: "a text or categorical column".to_string(),
+            })
+        }
+    }
+
+    Ok(())
+}
+
+fn class_breaks_json(
+    params: &ClassBreaksParams,
+    mut values: Vec<f64>,
+) -> Result<serde_json::Value> {
+    values.retain(|value| value.is_finite());
+    values.sort_by(|a, b| a.partial_cmp(b).expect("values are finite"));
+
+    let result = ClassBreaksResult {
+        breaks: class_breaks(params.method, params.number_of_classes, &values),
+        value_count: values.len(),
+    };
+
+    serde_json::to_value(result).context(error::SerdeJson)
+}
+
+/// The class breaks of a dataset from its histogram, where the values are approximated by
+/// evenly spaced quantiles
+fn dataset_class_breaks_json(
+    params: &ClassBreaksParams,
+    statistics: &RasterDatasetStatistics,
+) -> Result<serde_json::Value> {
+    let values = statistics.representative_values(MAX_NATURAL_BREAKS_VALUES);
+
+    let result = ClassBreaksResult {
+        breaks: class_breaks(params.method, params.number_of_classes, &values),
+        value_count: statistics.valid_count,
+    };
+
+    serde_json::to_value(result).context(error::SerdeJson)
+}
+
+/// The class breaks of the sorted, finite `values`
+fn class_breaks(method: ClassBreaksMethod, classes: usize, values: &[f64]) -> Vec<f64> {
+    if values.is_empty() {
+        return Vec::new();
+    }
+
+    let mut breaks = match method {
+        ClassBreaksMethod::EqualInterval => {
+            let (min, max) = (values[0], values[values.len() - 1]);
+            let width = (max - min) / classes as f64;
+            (0..=classes)
+                .map(|class| {
+                    if class == classes {
+                        max
+                    } else {
+                        min + width * class as f64
+                    }
+                })
+                .collect()
+        }
+        ClassBreaksMethod::Quantile => (0..=classes)
+            .map(|class| quantile(values, class as f64 / classes as f64))
+            .collect(),
+        ClassBreaksMethod::NaturalBreaks => {
+            if values.len() > MAX_NATURAL_BREAKS_VALUES {
+                let step = (values.len() - 1) as f64 / (MAX_NATURAL_BREAKS_VALUES - 1) as f64;
+                let sample: Vec<f64> = (0..MAX_NATURAL_BREAKS_VALUES)
+                    .map(|index| values[(index as f64 * step).round() as usize])
+                    .collect();
+                natural_breaks(&sample, classes)
+            } else {
+                natural_breaks(values, classes)
+            }
+        }
+    };
+
+    breaks.dedup();
+
+    breaks
+}
+
+/// The `q`-quantile of the sorted `values` with linear interpolation
+fn quantile(values: &[f64], q: f64) -> f64 {
+    let position = q * (values.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+
+    values[lower] + (values[upper] - values[lower]) * (position - lower as f64)
+}
+
+/// Jenks natural breaks of the sorted `values` by dynamic programming over the lower class
+/// limits with the minimal sum of squared deviations
+fn natural_breaks(values: &[f64], classes: usize) -> Vec<f64> {
+    let n = values.len();
+    let classes = usize::min(classes, n);
+
+    // 1-based indices as in the original formulation
+    let mut lower_class_limits = vec![vec![0_usize; classes + 1]; n + 1];
+    let mut variance_combinations = vec![vec![0_f64; classes + 1]; n + 1];
+
+    for limit in lower_class_limits[1].iter_mut().skip(1) {
+        *limit = 1;
+    }
+    for row in variance_combinations.iter_mut().skip(2) {
+        for combination in row.iter_mut().skip(1) {
+            *combination = f64::INFINITY;
+        }
+    }
+
+    for l in 2..=n {
+        let (mut sum, mut sum_of_squares, mut variance) = (0., 0., 0.);
+
+        for m in 1..=l {
+            let lower_limit = l - m + 1;
+            let value = values[lower_limit - 1];
+
+            sum += value;
+            sum_of_squares += value * value;
+            variance = sum_of_squares - (sum * sum) / m as f64;
+
+            let previous = lower_limit - 1;
+            if previous != 0 {
+                for class in 2..=classes {
+                    let combined = variance + variance_combinations[previous][class - 1];
+                    if variance_combinations[l][class] >= combined {
+                        lower_class_limits[l][class] = lower_limit;
+                        variance_combinations[l][class] = combined;
+                    }
+                }
+            }
+        }
+
+        lower_class_limits[l][1] = 1;
+        variance_combinations[l][1] = variance;
+    }
+
+    let mut breaks = vec![0.; classes + 1];
+    breaks[0] = values[0];
+    breaks[classes] = values[n - 1];
+
+    let mut upper = n;
+    for class in (2..=classes).rev() {
+        let lower_limit = lower_class_limits[upper][class];
+        breaks[class - 1] = values[lower_limit - 2];
+        upper = lower_limit - 1;
+    }
+
+    breaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{RasterOperator, RasterResultDescriptor, VectorOperator};
+    use crate::mock::{
+        MockFeatureCollectionSource, MockFeatureCollectionSourceParams, MockRasterSource,
+        MockRasterSourceParams,
+    };
+    use crate::source::{DatasetStatistics, GdalSource, GdalSourceParameters};
+    use geoengine_datatypes::collections::MultiPointCollection;
+    use geoengine_datatypes::primitives::{
+        BoundingBox2D, FeatureData, MultiPoint, SpatialResolution, TimeInterval,
+    };
+    use geoengine_datatypes::raster::{Raster2D, RasterDataType, RasterTile2D, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+    use std::sync::{Arc, RwLock};
+
+    fn query() -> QueryRectangle {
+        QueryRectangle {
+            bbox: BoundingBox2D::new((0., -4.).into(), (4., 4.).into()).unwrap(),
+            time_interval: TimeInterval::default(),
+            spatial_resolution: SpatialResolution::one(),
+        }
+    }
+
+    fn query_context() -> QueryContext {
+        QueryContext {
+            chunk_byte_size: 1024 * 1024,
+            timeout: None,
+            seed: 0,
+        }
+    }
+
+    async fn run(operator: Box<dyn PlotOperator>) -> ClassBreaksResult {
+        run_in(operator, &ExecutionContext::mock_empty()).await
+    }
+
+    async fn run_in(
+        operator: Box<dyn PlotOperator>,
+        context: &ExecutionContext,
+    ) -> ClassBreaksResult {
+        let processor = operator
+            .initialize(context)
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_json_plain()
+            .unwrap();
+
+        serde_json::from_value(
+            processor
+                .plot_query(query(), query_context())
+                .await
+                .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn json() {
+        let params = ClassBreaksParams {
+            method: ClassBreaksMethod::NaturalBreaks,
+            number_of_classes: 3,
+            column_name: Some("population".to_string()),
+            dataset_statistics: false,
+        };
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            serde_json::json!({
+                "method": "natural_breaks",
+                "number_of_classes": 3,
+                "column_name": "population",
+                "dataset_statistics": false,
+            })
+        );
+
+        let result = ClassBreaksResult {
+            breaks: vec![0., 1.],
+            value_count: 2,
+        };
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            serde_json::json!({
+                "breaks": [0., 1.],
+                "value_count": 2,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn raster_equal_interval() {
+        let operator = ClassBreaks {
+            params: ClassBreaksParams {
+                method: ClassBreaksMethod::EqualInterval,
+                number_of_classes: 4,
+                column_name: None,
+                dataset_statistics: false,
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![RasterTile2D::new(
+                        TimeInterval::default(),
+                        TileInformation {
+                            global_geo_transform: Default::default(),
+                            global_pixel_position: [0, 0].into(),
+                            global_size_in_tiles: [1, 1].into(),
+                            global_tile_position: [0, 0].into(),
+                            tile_size_in_pixels: [2, 3].into(),
+                        },
+                        Raster2D::new(
+                            [2, 3].into(),
+                            vec![2_u8, 4, 0, 6, 10, 0],
+                            Some(0),
+                            Default::default(),
+                            Default::default(),
+                        )
+                        .unwrap(),
+                    )],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed();
+
+        assert_eq!(
+            run(operator).await,
+            ClassBreaksResult {
+                breaks: vec![2., 4., 6., 8., 10.],
+                value_count: 4,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn raster_dataset_statistics() {
+        let operator = || {
+            ClassBreaks {
+                params: ClassBreaksParams {
+                    method: ClassBreaksMethod::Quantile,
+                    number_of_classes: 2,
+                    column_name: None,
+                    dataset_statistics: true,
+                },
+                raster_sources: vec![GdalSource {
+                    params: GdalSourceParameters {
+                        dataset_id: "test".to_string(),
+                        channel: None,
+                        band: None,
+                    },
+                }
+                .boxed()],
+                vector_sources: vec![],
+            }
+            .boxed()
+        };
+        let context = ExecutionContext {
+            raster_data_root: "../operators/test-data/raster".into(),
+            ..ExecutionContext::mock_empty()
+        };
+
+        // the statistics have not been computed yet
+        assert!(operator().initialize(&context).is_err());
+
+        let mut statistics = DatasetStatistics::default();
+        statistics
+            .insert(
+                "test",
+                RasterDatasetStatistics {
+                    min: 0.,
+                    max: 100.,
+                    mean: 37.5,
+                    valid_count: 1000,
+                    histogram: vec![500, 250, 0, 250],
+                },
+            )
+            .unwrap();
+        let context = ExecutionContext {
+            dataset_statistics: Some(Arc::new(RwLock::new(statistics))),
+            ..context
+        };
+
+        let result = run_in(operator(), &context).await;
+        assert_eq!(result.value_count, 1000);
+        assert_eq!(result.breaks.len(), 3);
+        assert!((result.breaks[0] - 0.).abs() < 1e-9);
+        assert!((result.breaks[1] - 25.).abs() < 0.1);
+        assert!((result.breaks[2] - 100.).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn vector_quantiles() {
+        let collection = MultiPointCollection::from_data(
+            MultiPoint::many(vec![(0., 0.), (1., 1.), (2., 2.), (3., 3.), (0., 1.)]).unwrap(),
+            vec![TimeInterval::default(); 5],
+            [(
+                "population".to_string(),
+                FeatureData::NullableNumber(vec![Some(1.), Some(2.), None, Some(3.), Some(5.)]),
+            )]
+            .iter()
+            .cloned()
+            .collect(),
+        )
+        .unwrap();
+
+        let operator = ClassBreaks {
+            params: ClassBreaksParams {
+                method: ClassBreaksMethod::Quantile,
+                number_of_classes: 2,
+                column_name: Some("population".to_string()),
+                dataset_statistics: false,
+            },
+            raster_sources: vec![],
+            vector_sources: vec![MockFeatureCollectionSource {
+                params: MockFeatureCollectionSourceParams { collection },
+            }
+            .boxed()],
+        }
+        .boxed();
+
+        assert_eq!(
+            run(operator).await,
+            ClassBreaksResult {
+                breaks: vec![1., 2.5, 5.],
+                value_count: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn natural_breaks_of_clusters() {
+        let values = [1., 2., 3., 10., 11., 12., 20., 21., 22.];
+
+        assert_eq!(
+            class_breaks(ClassBreaksMethod::NaturalBreaks, 3, &values),
+            vec![1., 3., 12., 22.]
+        );
+        assert_eq!(
+            class_breaks(ClassBreaksMethod::NaturalBreaks, 5, &[4., 4.]),
+            vec![4.]
+        );
+        assert!(class_breaks(ClassBreaksMethod::Quantile, 3, &[]).is_empty());
+    }
+
+    #[test]
+    fn column_name_must_match_sources() {
+        let operator = ClassBreaks {
+            params: ClassBreaksParams {
+                method: ClassBreaksMethod::Quantile,
+                number_of_classes: 2,
+                column_name: Some("population".to_string()),
+                dataset_statistics: false,
+            },
+            raster_sources: vec![],
+            vector_sources: vec![],
+        }
+        .boxed();
+
+        assert!(operator
+            .initialize(&ExecutionContext::mock_empty())
+            .is_err());
+    }
+}
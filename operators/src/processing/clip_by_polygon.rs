@@ -0,0 +1,363 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor, TypedVectorQueryProcessor,
+    VectorQueryProcessor,
+};
+use crate::error;
+use crate::util::Result;
+use futures::stream::{self, BoxStream};
+use futures::{FutureExt, StreamExt};
+use geoengine_datatypes::collections::{MultiPolygonCollection, VectorDataType};
+use geoengine_datatypes::raster::{FromPrimitive, Pixel, PolygonMask, RasterTile2D};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+/// Parameters of the `ClipByPolygon` operator.
+///
+/// The `no_data_value` is used for pixels outside the polygons if the raster has none.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipByPolygonParams {
+    pub no_data_value: Option<f64>,
+}
+
+/// Masks a raster to the interior of the polygons of a vector source.
+/// Pixels whose centers lie outside of all polygons become no-data.
+pub type ClipByPolygon = Operator<ClipByPolygonParams>;
+
+#[typetag::serde]
+impl RasterOperator for ClipByPolygon {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.len() == 1,
+            error::InvalidNumberOfVectorInputs {
+                expected: 1..2,
+                found: self.vector_sources.len()
+            }
+        );
+
+        InitializedClipByPolygon::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, vector_sources| {
+                let raster_descriptor = raster_sources[0].result_descriptor();
+                let vector_descriptor = vector_sources[0].result_descriptor();
+
+                ensure!(
+                    vector_descriptor.data_type == VectorDataType::MultiPolygon,
+                    error::InvalidType {
+                        expected: format!("{:?}", VectorDataType::MultiPolygon),
+                        found: format!("{:?}", vector_descriptor.data_type),
+                    }
+                );
+                ensure!(
+                    raster_descriptor.spatial_reference == vector_descriptor.spatial_reference,
+                    error::InvalidSpatialReference {
+                        expected: raster_descriptor.spatial_reference,
+                        found: vector_descriptor.spatial_reference,
+                    }
+                );
+
+                Ok(raster_descriptor)
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedClipByPolygon::boxed)
+    }
+}
+
+crate::register_operator!(Raster, ClipByPolygon);
+
+pub type InitializedClipByPolygon =
+    InitializedOperatorImpl<ClipByPolygonParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedClipByPolygon
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let polygon_source = match self.vector_sources[0].query_processor()? {
+            TypedVectorQueryProcessor::MultiPolygon(source) => source,
+            _ => return Err(error::Error::InvalidOperatorType),
+        };
+
+        let no_data_value = self.params.no_data_value;
+
+        Ok(crate::call_on_generic_raster_processor!(
+            self.raster_sources[0].query_processor()?,
+            raster_source => RasterQueryProcessor::boxed(ClipByPolygonProcessor::new(
+                raster_source,
+                polygon_source,
+                no_data_value,
+            )).into()
+        ))
+    }
+}
+
+pub struct ClipByPolygonProcessor<T>
+where
+    T: Pixel,
+{
+    raster_source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    polygon_source: Box<dyn VectorQueryProcessor<VectorType = MultiPolygonCollection>>,
+    no_data_value: Option<T>,
+}
+
+impl<T> ClipByPolygonProcessor<T>
+where
+    T: Pixel,
+{
+    pub fn new(
+        raster_source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+        polygon_source: Box<dyn VectorQueryProcessor<VectorType = MultiPolygonCollection>>,
+        no_data_value: Option<f64>,
+    ) -> Self {
+        Self {
+            raster_source,
+            polygon_source,
+            no_data_value: no_data_value.map(<T as FromPrimitive<f64>>::from_),
+        }
+    }
+
+    fn clip(
+        &self,
+        mut tile: RasterTile2D<T>,
+        polygons: &[MultiPolygonCollection],
+    ) -> Result<RasterTile2D<T>> {
+        let no_data_value = tile
+            .data
+            .no_data_value
+            .or(self.no_data_value)
+            .ok_or(error::Error::NoDataValueRequired)?;
+        tile.data.no_data_value = Some(no_data_value);
+
+        let geo_transform = tile.tile.tile_geo_transform();
+        let dimension = tile.tile.tile_size_in_pixels();
+
+        let mut inside = vec![false; tile.data.data_container.len()];
+        for collection in polygons {
+            let mask = collection.polygon_mask(&geo_transform, dimension, &tile.time);
+            for (inside, masked) in inside.iter_mut().zip(mask) {
+                *inside |= masked;
+            }
+        }
+
+        for (pixel, inside) in tile.data.data_container.iter_mut().zip(inside) {
+            if !inside {
+                *pixel = no_data_value;
+            }
+        }
+
+        Ok(tile)
+    }
+}
+
+impl<T> QueryProcessor for ClipByPolygonProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<T>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        // the polygons of the query are collected once and then applied to every tile
+        self.polygon_source
+            .vector_query(query, ctx)
+            .collect::<Vec<_>>()
+            .map(
+                move |collections| match collections.into_iter().collect::<Result<Vec<_>>>() {
+                    Ok(polygons) => self
+                        .raster_source
+                        .raster_query(query, ctx)
+                        .map(move |tile| self.clip(tile?, &polygons))
+                        .boxed(),
+                    Err(error) => stream::once(async move { Err(error) }).boxed(),
+                },
+            )
+            .flatten_stream()
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::VectorOperator;
+    use crate::mock::{
+        MockFeatureCollectionSource, MockFeatureCollectionSourceParams, MockRasterSource,
+        MockRasterSourceParams,
+    };
+    use geoengine_datatypes::primitives::{
+        BoundingBox2D, MultiPolygon, SpatialResolution, TimeInterval,
+    };
+    use geoengine_datatypes::raster::{Raster2D, RasterDataType, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    fn operator(no_data_value: Option<u8>) -> Box<dyn RasterOperator> {
+        let raster = Raster2D::new(
+            [3, 2].into(),
+            vec![1, 2, 3, 4, 5, 6],
+            no_data_value,
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+
+        let raster_source = MockRasterSource {
+            params: MockRasterSourceParams {
+                data: vec![RasterTile2D {
+                    time: TimeInterval::default(),
+                    tile: TileInformation {
+                        global_geo_transform: Default::default(),
+                        global_pixel_position: [0, 0].into(),
+                        global_size_in_tiles: [1, 1].into(),
+                        global_tile_position: [0, 0].into(),
+                        tile_size_in_pixels: [3, 2].into(),
+                    },
+                    data: raster,
+                    statistics: None,
+                }],
+                result_descriptor: RasterResultDescriptor {
+                    data_type: RasterDataType::U8,
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: None,
+                    bands: vec![],
+                },
+            },
+        }
+        .boxed();
+
+        // covers the upper right pixel and the complete second row
+        let polygons = MultiPolygonCollection::from_data(
+            vec![MultiPolygon::new(vec![vec![vec![
+                (0., -2.).into(),
+                (2., -2.).into(),
+                (2., 0.).into(),
+                (1., 0.).into(),
+                (1., -1.).into(),
+                (0., -1.).into(),
+                (0., -2.).into(),
+            ]]])
+            .unwrap()],
+            vec![TimeInterval::default()],
+            Default::default(),
+        )
+        .unwrap();
+
+        let polygon_source = MockFeatureCollectionSource {
+            params: MockFeatureCollectionSourceParams {
+                collection: polygons,
+            },
+        }
+        .boxed();
+
+        ClipByPolygon {
+            params: ClipByPolygonParams {
+                no_data_value: Some(0.),
+            },
+            raster_sources: vec![raster_source],
+            vector_sources: vec![polygon_source],
+        }
+        .boxed()
+    }
+
+    async fn clipped_tiles(operator: Box<dyn RasterOperator>) -> Vec<Result<RasterTile2D<u8>>> {
+        let processor = operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_u8()
+            .unwrap();
+
+        let query = QueryRectangle {
+            bbox: BoundingBox2D::new((0., -3.).into(), (2., 0.).into()).unwrap(),
+            time_interval: TimeInterval::default(),
+            spatial_resolution: SpatialResolution::one(),
+        };
+        let ctx = QueryContext {
+            chunk_byte_size: 1024 * 1024,
+            timeout: None,
+            seed: 0,
+        };
+
+        processor.raster_query(query, ctx).collect().await
+    }
+
+    #[test]
+    fn serde() {
+        let operator = ClipByPolygon {
+            params: ClipByPolygonParams {
+                no_data_value: Some(0.),
+            },
+            raster_sources: vec![],
+            vector_sources: vec![],
+        }
+        .boxed();
+
+        let serialized = serde_json::to_string(&operator).unwrap();
+
+        assert_eq!(
+            serialized,
+            serde_json::json!({
+                "type": "ClipByPolygon",
+                "params": {
+                    "no_data_value": 0.0
+                },
+                "raster_sources": [],
+                "vector_sources": []
+            })
+            .to_string()
+        );
+
+        let _: Box<dyn RasterOperator> = serde_json::from_str(&serialized).unwrap();
+    }
+
+    #[tokio::test]
+    async fn execute() {
+        let tiles = clipped_tiles(operator(None)).await;
+
+        assert_eq!(tiles.len(), 1);
+
+        let tile = tiles.into_iter().next().unwrap().unwrap();
+        assert_eq!(tile.data.data_container, vec![0, 2, 3, 4, 0, 0]);
+        assert_eq!(tile.data.no_data_value, Some(0));
+    }
+
+    #[tokio::test]
+    async fn keeps_no_data_value_of_raster() {
+        let tiles = clipped_tiles(operator(Some(42))).await;
+
+        let tile = tiles.into_iter().next().unwrap().unwrap();
+        assert_eq!(tile.data.data_container, vec![42, 2, 3, 4, 42, 42]);
+        assert_eq!(tile.data.no_data_value, Some(42));
+    }
+
+    #[test]
+    fn requires_raster_and_vector_source() {
+        let operator = ClipByPolygon {
+            params: ClipByPolygonParams {
+                no_data_value: None,
+            },
+            raster_sources: vec![],
+            vector_sources: vec![],
+        }
+        .boxed();
+
+        assert!(operator
+            .initialize(&ExecutionContext::mock_empty())
+            .is_err());
+    }
+}
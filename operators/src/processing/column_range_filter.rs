@@ -60,6 +60,8 @@ impl VectorOperator for ColumnRangeFilter {
     }
 }
 
+crate::register_operator!(Vector, ColumnRangeFilter);
+
 pub type InitializedColumnRangeFilter =
     InitializedOperatorImpl<ColumnRangeFilterParams, VectorResultDescriptor, ()>;
 
@@ -138,7 +140,10 @@ where
             // TODO: do transformation work only once
             let ranges: Result<Vec<RangeInclusive<FeatureDataValue>>> =
                 match collection.column_type(&column_name)? {
-                    FeatureDataType::Text | FeatureDataType::NullableText => ranges
+                    FeatureDataType::Text
+                    | FeatureDataType::NullableText
+                    | FeatureDataType::Categorical
+                    | FeatureDataType::NullableCategorical => ranges
                         .iter()
                         .cloned()
                         .map(|range| range.into_string_range().map(Into::into))
@@ -153,12 +158,6 @@ where
                         .cloned()
                         .map(|range| range.into_decimal_range().map(Into::into))
                         .collect(),
-                    FeatureDataType::Categorical | FeatureDataType::NullableCategorical => {
-                        Err(error::Error::InvalidType {
-                            expected: "text, number, or decimal".to_string(),
-                            found: "categorical".to_string(),
-                        })
-                    }
                 };
 
             collection
@@ -266,6 +265,8 @@ mod tests {
         };
         let ctx = QueryContext {
             chunk_byte_size: 2 * std::mem::size_of::<Coordinate2D>(),
+            timeout: None,
+            seed: 0,
         };
         let stream = point_processor.vector_query(query_rectangle, ctx);
 
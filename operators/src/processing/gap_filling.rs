@@ -0,0 +1,390 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::tile_grid::{collect_time_steps, flatten_result};
+use crate::util::Result;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use geoengine_datatypes::primitives::{TimeInstance, TimeInterval};
+use geoengine_datatypes::raster::{Pixel, Raster2D, RasterTile2D};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GapFillingParams {
+    pub method: GapFillingMethod,
+    /// observations that start more than `max_gap_millis` before or after the time step
+    /// of a missing pixel are not used to fill it
+    pub max_gap_millis: i64,
+}
+
+/// Which observations fill a missing pixel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GapFillingMethod {
+    /// the latest previous valid observation
+    Forward,
+    /// the earliest following valid observation
+    Backward,
+    /// the temporally nearest valid observation, preferring previous ones on ties
+    Nearest,
+}
+
+/// Fills no-data pixels of a raster time series with the values of other time steps, e.g.,
+/// to close gaps due to clouds in optical data.
+///
+/// The source is queried up to `max_gap_millis` before and after the query, so that also
+/// observations outside the query can fill gaps. The output contains only the time steps of
+/// the query and pixels without a valid observation within the gap remain no-data.
+pub type GapFilling = Operator<GapFillingParams>;
+
+#[typetag::serde]
+impl RasterOperator for GapFilling {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.is_empty(),
+            error::InvalidNumberOfVectorInputs {
+                expected: 0..1,
+                found: self.vector_sources.len()
+            }
+        );
+        ensure!(
+            self.params.max_gap_millis > 0,
+            error::InvalidOperatorParameter {
+                parameter: "max_gap_millis".to_string(),
+                reason: "must be positive".to_string(),
+            }
+        );
+
+        InitializedGapFilling::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, _| Ok(raster_sources[0].result_descriptor()),
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedGapFilling::boxed)
+    }
+}
+
+crate::register_operator!(Raster, GapFilling);
+
+pub type InitializedGapFilling =
+    InitializedOperatorImpl<GapFillingParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedGapFilling
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let params = self.params.clone();
+
+        Ok(crate::call_on_generic_raster_processor!(
+            self.raster_sources[0].query_processor()?,
+            source => RasterQueryProcessor::boxed(GapFillingProcessor { source, params }).into()
+        ))
+    }
+}
+
+pub struct GapFillingProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    params: GapFillingParams,
+}
+
+impl<T> GapFillingProcessor<T>
+where
+    T: Pixel,
+{
+    async fn filled_tiles(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<RasterTile2D<T>>> {
+        let max_gap = self.params.max_gap_millis;
+        let extended_query = QueryRectangle {
+            time_interval: TimeInterval::new(
+                query.time_interval.start().inner().saturating_sub(max_gap),
+                query.time_interval.end().inner().saturating_add(max_gap),
+            )?,
+            ..query
+        };
+
+        let mut time_steps =
+            collect_time_steps(self.source.raster_query(extended_query, ctx)).await?;
+        time_steps.sort_by_key(|(time, _)| time.start());
+
+        let mut tiles = Vec::new();
+        for (index, (time, time_step)) in time_steps.iter().enumerate() {
+            if !time.intersects(&query.time_interval) {
+                continue;
+            }
+
+            for tile in time_step {
+                tiles.push(self.fill_tile(tile, &time_steps[..index], &time_steps[index + 1..])?);
+            }
+        }
+
+        Ok(tiles)
+    }
+
+    /// Fill the missing pixels of `tile` from the tiles at the same position of the `previous`
+    /// and `following` time steps
+    fn fill_tile(
+        &self,
+        tile: &RasterTile2D<T>,
+        previous: &[(TimeInterval, Vec<RasterTile2D<T>>)],
+        following: &[(TimeInterval, Vec<RasterTile2D<T>>)],
+    ) -> Result<RasterTile2D<T>> {
+        let no_data_value = match tile.data.no_data_value {
+            Some(no_data_value) => no_data_value,
+            None => return Ok(tile.clone()),
+        };
+
+        let start = tile.time.start();
+        let candidates = |time_steps: &[(TimeInterval, Vec<RasterTile2D<T>>)]| {
+            time_steps
+                .iter()
+                .filter(|(time, _)| gap(start, time.start()) <= self.params.max_gap_millis)
+                .filter_map(|(time, time_step)| {
+                    time_step
+                        .iter()
+                        .find(|other| other.tile == tile.tile)
+                        .map(|other| (time.start(), other))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // ordered by their distance to the tile
+        let previous: Vec<_> = if self.params.method == GapFillingMethod::Backward {
+            Vec::new()
+        } else {
+            candidates(previous).into_iter().rev().collect()
+        };
+        let following = if self.params.method == GapFillingMethod::Forward {
+            Vec::new()
+        } else {
+            candidates(following)
+        };
+
+        let valid_pixel = |candidates: &[(TimeInstance, &RasterTile2D<T>)], index: usize| {
+            candidates.iter().find_map(|(time, other)| {
+                let pixel = other.data.data_container[index];
+                if is_no_data(pixel, other.data.no_data_value) {
+                    None
+                } else {
+                    Some((gap(start, *time), pixel))
+                }
+            })
+        };
+
+        let data = tile
+            .data
+            .data_container
+            .iter()
+            .enumerate()
+            .map(|(index, &pixel)| {
+                if !is_no_data(pixel, Some(no_data_value)) {
+                    return pixel;
+                }
+
+                match (
+                    valid_pixel(&previous, index),
+                    valid_pixel(&following, index),
+                ) {
+                    (Some((previous_gap, previous)), Some((following_gap, following))) => {
+                        if previous_gap <= following_gap {
+                            previous
+                        } else {
+                            following
+                        }
+                    }
+                    (Some((_, pixel)), None) | (None, Some((_, pixel))) => pixel,
+                    (None, None) => no_data_value,
+                }
+            })
+            .collect();
+
+        Ok(RasterTile2D::new(
+            tile.time,
+            tile.tile,
+            Raster2D::new(
+                tile.data.grid_dimension,
+                data,
+                tile.data.no_data_value,
+                tile.data.temporal_bounds,
+                tile.data.geo_transform,
+            )?,
+        ))
+    }
+}
+
+fn gap(a: TimeInstance, b: TimeInstance) -> i64 {
+    (a.inner() - b.inner()).abs()
+}
+
+fn is_no_data<T: Pixel>(pixel: T, no_data_value: Option<T>) -> bool {
+    if Some(pixel) == no_data_value {
+        return true;
+    }
+    let value: f64 = pixel.as_();
+    value.is_nan()
+}
+
+impl<T> QueryProcessor for GapFillingProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<T>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        stream::once(self.filled_tiles(query, ctx))
+            .flat_map(|result| stream::iter(flatten_result(result)))
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution};
+    use geoengine_datatypes::raster::{RasterDataType, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    fn series(method: GapFillingMethod, max_gap_millis: i64) -> Box<dyn RasterOperator> {
+        let tile = |start: i64, data: Vec<u8>| RasterTile2D {
+            time: TimeInterval::new_unchecked(start, start + 10),
+            tile: TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, 0].into(),
+                global_size_in_tiles: [1, 1].into(),
+                global_tile_position: [0, 0].into(),
+                tile_size_in_pixels: [2, 2].into(),
+            },
+            data: Raster2D::new(
+                [2, 2].into(),
+                data,
+                Some(0),
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+            statistics: None,
+        };
+
+        GapFilling {
+            params: GapFillingParams {
+                method,
+                max_gap_millis,
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![
+                        tile(0, vec![1, 1, 0, 1]),
+                        tile(10, vec![2, 0, 0, 0]),
+                        tile(20, vec![0, 0, 0, 3]),
+                        tile(30, vec![4, 4, 0, 4]),
+                    ],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed()
+    }
+
+    async fn run(operator: Box<dyn RasterOperator>, time_interval: TimeInterval) -> Vec<Vec<u8>> {
+        let processor = operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_u8()
+            .unwrap();
+
+        processor
+            .raster_query(
+                QueryRectangle {
+                    bbox: BoundingBox2D::new((0., -2.).into(), (2., 0.).into()).unwrap(),
+                    time_interval,
+                    spatial_resolution: SpatialResolution::one(),
+                },
+                QueryContext {
+                    chunk_byte_size: 1024 * 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .map(|tile| tile.unwrap().data.data_container)
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn forward_fill() {
+        let tiles = run(
+            series(GapFillingMethod::Forward, 10),
+            TimeInterval::new_unchecked(10, 30),
+        )
+        .await;
+
+        assert_eq!(tiles, vec![vec![2, 1, 0, 1], vec![2, 0, 0, 3]]);
+    }
+
+    #[tokio::test]
+    async fn backward_fill() {
+        let tiles = run(
+            series(GapFillingMethod::Backward, 20),
+            TimeInterval::new_unchecked(10, 20),
+        )
+        .await;
+
+        assert_eq!(tiles, vec![vec![2, 4, 0, 3]]);
+    }
+
+    #[tokio::test]
+    async fn nearest_fill() {
+        let tiles = run(
+            series(GapFillingMethod::Nearest, 20),
+            TimeInterval::new_unchecked(20, 30),
+        )
+        .await;
+
+        // the gap to the first pixel is equal, so the previous observation wins
+        assert_eq!(tiles, vec![vec![2, 4, 0, 3]]);
+    }
+
+    #[test]
+    fn serde() {
+        let operator = series(GapFillingMethod::Nearest, 10);
+
+        let serialized = serde_json::to_string(&operator).unwrap();
+        let deserialized: Box<dyn RasterOperator> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+    }
+}
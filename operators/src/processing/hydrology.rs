@@ -0,0 +1,537 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::tile_grid::{collect_time_steps, flatten_result, TileGrid};
+use crate::util::Result;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use geoengine_datatypes::primitives::{BoundingBox2D, Coordinate2D, SpatialBounded};
+use geoengine_datatypes::raster::{Pixel, RasterDataType, RasterTile2D};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+/// The D8 directions as (row, column) offsets together with their codes
+const D8_DIRECTIONS: [((isize, isize), u8); 8] = [
+    ((0, 1), 1),
+    ((1, 1), 2),
+    ((1, 0), 4),
+    ((1, -1), 8),
+    ((0, -1), 16),
+    ((-1, -1), 32),
+    ((-1, 0), 64),
+    ((-1, 1), 128),
+];
+
+/// Flow direction of pixels without a lower neighbor, i.e., pits and flats
+pub const NO_FLOW_DIRECTION: u8 = 0;
+pub const FLOW_DIRECTION_NO_DATA: u8 = 255;
+pub const FLOW_ACCUMULATION_NO_DATA: u32 = u32::MAX;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlowDirectionParams {}
+
+/// Computes the D8 flow direction of a digital elevation model, i.e., the direction of the
+/// steepest descent to one of the eight neighbors of every pixel.
+///
+/// The directions are encoded as `1` (east), `2` (south-east), `4` (south), `8` (south-west),
+/// `16` (west), `32` (north-west), `64` (north) and `128` (north-east).
+/// Pits and flats have no direction (`0`). The input is queried with a border of one pixel, so
+/// that pixels at tile borders see their neighbors.
+pub type FlowDirection = Operator<FlowDirectionParams>;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlowAccumulationParams {}
+
+/// Computes the number of upstream pixels that drain through every pixel of a D8 flow direction
+/// raster. Only the pixels of the queried area contribute.
+pub type FlowAccumulation = Operator<FlowAccumulationParams>;
+
+fn ensure_single_raster_source(operator: &Operator<impl Sized>) -> Result<()> {
+    ensure!(
+        operator.raster_sources.len() == 1,
+        error::InvalidNumberOfRasterInputs {
+            expected: 1..2,
+            found: operator.raster_sources.len()
+        }
+    );
+    ensure!(
+        operator.vector_sources.is_empty(),
+        error::InvalidNumberOfVectorInputs {
+            expected: 0..1,
+            found: operator.vector_sources.len()
+        }
+    );
+
+    Ok(())
+}
+
+#[typetag::serde]
+impl RasterOperator for FlowDirection {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure_single_raster_source(&self)?;
+
+        InitializedFlowDirection::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, _| {
+                Ok(RasterResultDescriptor {
+                    data_type: RasterDataType::U8,
+                    no_data_value: Some(f64::from(FLOW_DIRECTION_NO_DATA)),
+                    bands: vec![],
+                    ..raster_sources[0].result_descriptor()
+                })
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedFlowDirection::boxed)
+    }
+}
+
+crate::register_operator!(Raster, FlowDirection);
+
+#[typetag::serde]
+impl RasterOperator for FlowAccumulation {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure_single_raster_source(&self)?;
+
+        InitializedFlowAccumulation::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, _| {
+                let input = raster_sources[0].result_descriptor();
+
+                ensure!(
+                    input.data_type == RasterDataType::U8,
+                    error::InvalidType {
+                        expected: "U8 flow directions".to_string(),
+                        found: format!("{:?}", input.data_type),
+                    }
+                );
+
+                Ok(RasterResultDescriptor {
+                    data_type: RasterDataType::U32,
+                    no_data_value: Some(f64::from(FLOW_ACCUMULATION_NO_DATA)),
+                    bands: vec![],
+                    ..input
+                })
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedFlowAccumulation::boxed)
+    }
+}
+
+crate::register_operator!(Raster, FlowAccumulation);
+
+pub type InitializedFlowDirection =
+    InitializedOperatorImpl<FlowDirectionParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedFlowDirection
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        Ok(TypedRasterQueryProcessor::U8(
+            crate::call_on_generic_raster_processor!(
+                self.raster_sources[0].query_processor()?,
+                elevation => RasterQueryProcessor::boxed(FlowDirectionProcessor { elevation })
+            ),
+        ))
+    }
+}
+
+pub type InitializedFlowAccumulation =
+    InitializedOperatorImpl<FlowAccumulationParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedFlowAccumulation
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let flow_directions = self.raster_sources[0]
+            .query_processor()?
+            .get_u8()
+            .ok_or(error::Error::InvalidOperatorType)?;
+
+        Ok(TypedRasterQueryProcessor::U32(RasterQueryProcessor::boxed(
+            FlowAccumulationProcessor { flow_directions },
+        )))
+    }
+}
+
+pub struct FlowDirectionProcessor<T>
+where
+    T: Pixel,
+{
+    elevation: Box<dyn RasterQueryProcessor<RasterType = T>>,
+}
+
+impl<T> FlowDirectionProcessor<T>
+where
+    T: Pixel,
+{
+    async fn flow_direction_tiles(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<RasterTile2D<u8>>> {
+        let time_steps = collect_time_steps(
+            self.elevation
+                .raster_query(enlarged_by_one_pixel(query), ctx),
+        )
+        .await?;
+
+        let mut tiles = Vec::new();
+
+        for (_, elevation_tiles) in time_steps {
+            let elevation = TileGrid::from_tiles(&elevation_tiles, |pixel: Option<T>| {
+                pixel.map(|pixel| -> f64 { pixel.as_() })
+            });
+            let directions = elevation.with_values(flow_directions(&elevation));
+
+            for tile in elevation_tiles
+                .iter()
+                .filter(|tile| intersects_with_area(&tile.spatial_bounds(), &query.bbox))
+            {
+                tiles.push(directions.tile(tile, Some(FLOW_DIRECTION_NO_DATA), |&d| d)?);
+            }
+        }
+
+        Ok(tiles)
+    }
+}
+
+impl<T> QueryProcessor for FlowDirectionProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<u8>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        stream::once(self.flow_direction_tiles(query, ctx))
+            .flat_map(|result| stream::iter(flatten_result(result)))
+            .boxed()
+    }
+}
+
+pub struct FlowAccumulationProcessor {
+    flow_directions: Box<dyn RasterQueryProcessor<RasterType = u8>>,
+}
+
+impl FlowAccumulationProcessor {
+    async fn flow_accumulation_tiles(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<RasterTile2D<u32>>> {
+        let time_steps = collect_time_steps(self.flow_directions.raster_query(query, ctx)).await?;
+
+        let mut tiles = Vec::new();
+
+        for (_, direction_tiles) in time_steps {
+            let directions = TileGrid::from_tiles(&direction_tiles, |pixel: Option<u8>| {
+                pixel.unwrap_or(FLOW_DIRECTION_NO_DATA)
+            });
+            let accumulation = directions.with_values(flow_accumulation(&directions));
+
+            for tile in &direction_tiles {
+                tiles.push(accumulation.tile(tile, Some(FLOW_ACCUMULATION_NO_DATA), |&a| a)?);
+            }
+        }
+
+        Ok(tiles)
+    }
+}
+
+impl QueryProcessor for FlowAccumulationProcessor {
+    type Output = RasterTile2D<u32>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        stream::once(self.flow_accumulation_tiles(query, ctx))
+            .flat_map(|result| stream::iter(flatten_result(result)))
+            .boxed()
+    }
+}
+
+/// The query extended by one pixel of the query's resolution in every direction
+fn enlarged_by_one_pixel(query: QueryRectangle) -> QueryRectangle {
+    let (lower_left, upper_right) = (query.bbox.lower_left(), query.bbox.upper_right());
+    let (x, y) = (query.spatial_resolution.x, query.spatial_resolution.y);
+
+    QueryRectangle {
+        bbox: BoundingBox2D::new_unchecked(
+            Coordinate2D::new(lower_left.x - x, lower_left.y - y),
+            Coordinate2D::new(upper_right.x + x, upper_right.y + y),
+        ),
+        ..query
+    }
+}
+
+fn intersects_with_area(a: &BoundingBox2D, b: &BoundingBox2D) -> bool {
+    a.intersection(b).map_or(false, |intersection| {
+        intersection.size_x() > 0. && intersection.size_y() > 0.
+    })
+}
+
+/// The D8 direction of the steepest descent of every pixel of the `elevation` grid
+fn flow_directions(elevation: &TileGrid<Option<f64>>) -> Vec<u8> {
+    let x_size = elevation.geo_transform.x_pixel_size.abs();
+    let y_size = elevation.geo_transform.y_pixel_size.abs();
+
+    (0..elevation.values.len())
+        .map(|index| {
+            let height = match elevation.values[index] {
+                Some(height) => height,
+                None => return FLOW_DIRECTION_NO_DATA,
+            };
+
+            let mut direction = NO_FLOW_DIRECTION;
+            let mut steepest_slope = 0.;
+
+            for &(offset, code) in &D8_DIRECTIONS {
+                let neighbor_height = match elevation
+                    .neighbor(index, offset)
+                    .and_then(|neighbor| elevation.values[neighbor])
+                {
+                    Some(neighbor_height) => neighbor_height,
+                    None => continue,
+                };
+
+                let distance = (offset.1 as f64 * x_size).hypot(offset.0 as f64 * y_size);
+                let slope = (height - neighbor_height) / distance;
+
+                if slope > steepest_slope {
+                    steepest_slope = slope;
+                    direction = code;
+                }
+            }
+
+            direction
+        })
+        .collect()
+}
+
+/// The number of upstream pixels of every pixel of the D8 `directions` grid.
+/// Pixels are processed in topological order, so every pixel is visited only once.
+fn flow_accumulation(directions: &TileGrid<u8>) -> Vec<u32> {
+    let downstream: Vec<Option<usize>> = directions
+        .values
+        .iter()
+        .enumerate()
+        .map(|(index, &code)| {
+            D8_DIRECTIONS
+                .iter()
+                .find(|(_, direction_code)| *direction_code == code)
+                .and_then(|&(offset, _)| directions.neighbor(index, offset))
+                .filter(|&neighbor| directions.values[neighbor] != FLOW_DIRECTION_NO_DATA)
+        })
+        .collect();
+
+    let mut upstream_count = vec![0_usize; downstream.len()];
+    for &neighbor in downstream.iter().flatten() {
+        upstream_count[neighbor] += 1;
+    }
+
+    let mut accumulation = vec![0_u32; downstream.len()];
+    let mut ready: Vec<usize> = (0..downstream.len())
+        .filter(|&index| upstream_count[index] == 0)
+        .collect();
+
+    while let Some(index) = ready.pop() {
+        if let Some(neighbor) = downstream[index] {
+            accumulation[neighbor] += accumulation[index] + 1;
+            upstream_count[neighbor] -= 1;
+            if upstream_count[neighbor] == 0 {
+                ready.push(neighbor);
+            }
+        }
+    }
+
+    accumulation
+        .into_iter()
+        .zip(&directions.values)
+        .map(|(accumulation, &code)| {
+            if code == FLOW_DIRECTION_NO_DATA {
+                FLOW_ACCUMULATION_NO_DATA
+            } else {
+                accumulation
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{SpatialResolution, TimeInterval};
+    use geoengine_datatypes::raster::{Raster2D, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    /// A 3x4 elevation model in two tiles that drains to its lower right pixel
+    fn elevation_source() -> Box<dyn RasterOperator> {
+        let tile = |position: usize, data: Vec<u8>| RasterTile2D {
+            time: TimeInterval::default(),
+            tile: TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, position * 2].into(),
+                global_size_in_tiles: [1, 2].into(),
+                global_tile_position: [0, position].into(),
+                tile_size_in_pixels: [3, 2].into(),
+            },
+            data: Raster2D::new(
+                [3, 2].into(),
+                data,
+                None,
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+            statistics: None,
+        };
+
+        MockRasterSource {
+            params: MockRasterSourceParams {
+                data: vec![
+                    tile(0, vec![9, 8, 8, 7, 7, 6]),
+                    tile(1, vec![7, 6, 6, 5, 5, 1]),
+                ],
+                result_descriptor: RasterResultDescriptor {
+                    data_type: RasterDataType::U8,
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: None,
+                    bands: vec![],
+                },
+            },
+        }
+        .boxed()
+    }
+
+    fn query() -> (QueryRectangle, QueryContext) {
+        (
+            QueryRectangle {
+                bbox: BoundingBox2D::new((0., -3.).into(), (4., 0.).into()).unwrap(),
+                time_interval: TimeInterval::default(),
+                spatial_resolution: SpatialResolution::one(),
+            },
+            QueryContext {
+                chunk_byte_size: 1024 * 1024,
+                timeout: None,
+                seed: 0,
+            },
+        )
+    }
+
+    fn flow_direction() -> Box<dyn RasterOperator> {
+        FlowDirection {
+            params: FlowDirectionParams {},
+            raster_sources: vec![elevation_source()],
+            vector_sources: vec![],
+        }
+        .boxed()
+    }
+
+    #[test]
+    fn serde() {
+        let serialized = serde_json::to_string(&flow_direction()).unwrap();
+        let deserialized: Box<dyn RasterOperator> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+    }
+
+    #[test]
+    fn result_descriptors() {
+        let directions = flow_direction()
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap();
+        assert_eq!(
+            directions.result_descriptor().no_data_value,
+            Some(f64::from(FLOW_DIRECTION_NO_DATA))
+        );
+
+        let accumulation = FlowAccumulation {
+            params: FlowAccumulationParams {},
+            raster_sources: vec![flow_direction()],
+            vector_sources: vec![],
+        }
+        .boxed()
+        .initialize(&ExecutionContext::mock_empty())
+        .unwrap();
+        assert_eq!(
+            accumulation.result_descriptor().data_type,
+            RasterDataType::U32
+        );
+        assert_eq!(
+            accumulation.result_descriptor().no_data_value,
+            Some(f64::from(FLOW_ACCUMULATION_NO_DATA))
+        );
+    }
+
+    #[tokio::test]
+    async fn directions_across_tiles() {
+        let processor = flow_direction()
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_u8()
+            .unwrap();
+
+        let (query, ctx) = query();
+        let tiles: Vec<RasterTile2D<u8>> = processor
+            .raster_query(query, ctx)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(tiles.len(), 2);
+        // the right column of the first tile drains into the second tile
+        assert_eq!(tiles[0].data.data_container, vec![2, 2, 2, 2, 1, 1]);
+        assert_eq!(
+            tiles[1].data.data_container,
+            vec![2, 4, 2, 4, 1, NO_FLOW_DIRECTION]
+        );
+    }
+
+    #[tokio::test]
+    async fn accumulation() {
+        let operator = FlowAccumulation {
+            params: FlowAccumulationParams {},
+            raster_sources: vec![flow_direction()],
+            vector_sources: vec![],
+        }
+        .boxed();
+
+        let processor = operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_u32()
+            .unwrap();
+
+        let (query, ctx) = query();
+        let tiles: Vec<RasterTile2D<u32>> = processor
+            .raster_query(query, ctx)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(tiles.len(), 2);
+        // every pixel of the grid drains into the lower right pixel
+        assert_eq!(tiles[1].data.data_container[5], 11);
+    }
+}
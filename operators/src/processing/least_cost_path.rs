@@ -0,0 +1,718 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    InitializedVectorOperator, Operator, QueryContext, QueryProcessor, QueryRectangle,
+    RasterOperator, RasterQueryProcessor, RasterResultDescriptor, TypedRasterQueryProcessor,
+    TypedVectorQueryProcessor, VectorOperator, VectorQueryProcessor, VectorResultDescriptor,
+};
+use crate::error;
+use crate::util::tile_grid::{collect_time_steps, flatten_result, TileGrid};
+use crate::util::Result;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use geoengine_datatypes::collections::{
+    IntoGeometryIterator, MultiLineStringCollection, MultiPointCollection, VectorDataType,
+};
+use geoengine_datatypes::primitives::{
+    Coordinate2D, FeatureData, MultiLineString, MultiPointAccess, TimeInterval,
+};
+use geoengine_datatypes::raster::{Pixel, RasterDataType, RasterTile2D};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Parameters of the `LeastCostPath` operator
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LeastCostPathParams {
+    /// Allow moves to the diagonal neighbors of a pixel in addition to the direct ones
+    pub diagonal_moves: bool,
+}
+
+/// Computes the least-cost path through a cost raster from the nearest start point of the first
+/// vector source to every end point of the second vector source.
+///
+/// The result contains one line per reachable end point with its accumulated cost in the
+/// `cost` column. Pixels with no-data or negative costs are impassable.
+pub type LeastCostPath = Operator<LeastCostPathParams>;
+
+/// Parameters of the `AccumulatedCost` operator
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccumulatedCostParams {
+    /// Allow moves to the diagonal neighbors of a pixel in addition to the direct ones
+    pub diagonal_moves: bool,
+}
+
+/// Computes the accumulated cost of reaching every pixel of a cost raster from the nearest start
+/// point of the vector source. This is the cost surface the `LeastCostPath` operator traces its
+/// paths on. Unreachable pixels are `NaN`.
+pub type AccumulatedCost = Operator<AccumulatedCostParams>;
+
+#[typetag::serde]
+impl VectorOperator for LeastCostPath {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedVectorOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.len() == 2,
+            error::InvalidNumberOfVectorInputs {
+                expected: 2..3,
+                found: self.vector_sources.len()
+            }
+        );
+
+        InitializedLeastCostPath::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, vector_sources| {
+                let raster_descriptor = raster_sources[0].result_descriptor();
+
+                for vector_source in vector_sources {
+                    ensure_points_in_raster_reference(
+                        &raster_descriptor,
+                        &vector_source.result_descriptor(),
+                    )?;
+                }
+
+                Ok(VectorResultDescriptor {
+                    data_type: VectorDataType::MultiLineString,
+                    spatial_reference: raster_descriptor.spatial_reference,
+                    bbox: None,
+                    time_interval: None,
+                })
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedLeastCostPath::boxed)
+    }
+}
+
+crate::register_operator!(Vector, LeastCostPath);
+
+#[typetag::serde]
+impl RasterOperator for AccumulatedCost {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.len() == 1,
+            error::InvalidNumberOfVectorInputs {
+                expected: 1..2,
+                found: self.vector_sources.len()
+            }
+        );
+
+        InitializedAccumulatedCost::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, vector_sources| {
+                let raster_descriptor = raster_sources[0].result_descriptor();
+
+                ensure_points_in_raster_reference(
+                    &raster_descriptor,
+                    &vector_sources[0].result_descriptor(),
+                )?;
+
+                Ok(RasterResultDescriptor {
+                    data_type: RasterDataType::F64,
+                    no_data_value: Some(f64::NAN),
+                    bands: vec![],
+                    ..raster_descriptor
+                })
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedAccumulatedCost::boxed)
+    }
+}
+
+crate::register_operator!(Raster, AccumulatedCost);
+
+fn ensure_points_in_raster_reference(
+    raster_descriptor: &RasterResultDescriptor,
+    vector_descriptor: &VectorResultDescriptor,
+) -> Result<()> {
+    ensure!(
+        vector_descriptor.data_type == VectorDataType::MultiPoint,
+        error::InvalidType {
+            expected: format!("{:?}", VectorDataType::MultiPoint),
+            found: format!("{:?}", vector_descriptor.data_type),
+        }
+    );
+    ensure!(
+        raster_descriptor.spatial_reference == vector_descriptor.spatial_reference,
+        error::InvalidSpatialReference {
+            expected: raster_descriptor.spatial_reference,
+            found: vector_descriptor.spatial_reference,
+        }
+    );
+
+    Ok(())
+}
+
+fn point_processor(
+    processor: TypedVectorQueryProcessor,
+) -> Result<Box<dyn VectorQueryProcessor<VectorType = MultiPointCollection>>> {
+    match processor {
+        TypedVectorQueryProcessor::MultiPoint(processor) => Ok(processor),
+        _ => Err(error::Error::InvalidOperatorType),
+    }
+}
+
+pub type InitializedLeastCostPath =
+    InitializedOperatorImpl<LeastCostPathParams, VectorResultDescriptor, ()>;
+
+impl InitializedOperator<VectorResultDescriptor, TypedVectorQueryProcessor>
+    for InitializedLeastCostPath
+{
+    fn query_processor(&self) -> Result<TypedVectorQueryProcessor> {
+        let start_points = point_processor(self.vector_sources[0].query_processor()?)?;
+        let end_points = point_processor(self.vector_sources[1].query_processor()?)?;
+        let diagonal_moves = self.params.diagonal_moves;
+
+        Ok(TypedVectorQueryProcessor::MultiLineString(
+            crate::call_on_generic_raster_processor!(
+                self.raster_sources[0].query_processor()?,
+                costs => VectorQueryProcessor::boxed(LeastCostPathProcessor {
+                    costs,
+                    start_points,
+                    end_points,
+                    diagonal_moves,
+                })
+            ),
+        ))
+    }
+}
+
+pub type InitializedAccumulatedCost =
+    InitializedOperatorImpl<AccumulatedCostParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedAccumulatedCost
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let start_points = point_processor(self.vector_sources[0].query_processor()?)?;
+        let diagonal_moves = self.params.diagonal_moves;
+
+        Ok(TypedRasterQueryProcessor::F64(
+            crate::call_on_generic_raster_processor!(
+                self.raster_sources[0].query_processor()?,
+                costs => RasterQueryProcessor::boxed(AccumulatedCostProcessor {
+                    costs,
+                    start_points,
+                    diagonal_moves,
+                })
+            ),
+        ))
+    }
+}
+
+pub struct LeastCostPathProcessor<T>
+where
+    T: Pixel,
+{
+    costs: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    start_points: Box<dyn VectorQueryProcessor<VectorType = MultiPointCollection>>,
+    end_points: Box<dyn VectorQueryProcessor<VectorType = MultiPointCollection>>,
+    diagonal_moves: bool,
+}
+
+impl<T> LeastCostPathProcessor<T>
+where
+    T: Pixel,
+{
+    /// Trace the paths for every time step of the cost raster
+    async fn paths(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<MultiLineStringCollection>> {
+        let time_steps = collect_time_steps(self.costs.raster_query(query, ctx)).await?;
+        let start_points = collect_points(self.start_points.vector_query(query, ctx)).await?;
+        let end_points = collect_points(self.end_points.vector_query(query, ctx)).await?;
+
+        let mut collections = Vec::with_capacity(time_steps.len());
+
+        for (time, tiles) in time_steps {
+            let grid = CostGrid::from_tiles(&tiles, self.diagonal_moves);
+            let surface = grid.accumulate(&points_at(&start_points, &time));
+
+            let mut lines = Vec::new();
+            let mut costs = Vec::new();
+
+            for end in points_at(&end_points, &time) {
+                if let Some((path, cost)) = surface.path_to(end) {
+                    lines.push(MultiLineString::new(vec![path])?);
+                    costs.push(cost);
+                }
+            }
+
+            let mut data = HashMap::new();
+            data.insert("cost".to_string(), FeatureData::Number(costs));
+
+            let number_of_lines = lines.len();
+            collections.push(MultiLineStringCollection::from_data(
+                lines,
+                vec![time; number_of_lines],
+                data,
+            )?);
+        }
+
+        Ok(collections)
+    }
+}
+
+impl<T> QueryProcessor for LeastCostPathProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = MultiLineStringCollection;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        stream::once(self.paths(query, ctx))
+            .flat_map(|result| stream::iter(flatten_result(result)))
+            .boxed()
+    }
+}
+
+pub struct AccumulatedCostProcessor<T>
+where
+    T: Pixel,
+{
+    costs: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    start_points: Box<dyn VectorQueryProcessor<VectorType = MultiPointCollection>>,
+    diagonal_moves: bool,
+}
+
+impl<T> AccumulatedCostProcessor<T>
+where
+    T: Pixel,
+{
+    /// Compute the cost surface for every time step and cut it into the tiles of the cost raster
+    async fn accumulated_tiles(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<RasterTile2D<f64>>> {
+        let time_steps = collect_time_steps(self.costs.raster_query(query, ctx)).await?;
+        let start_points = collect_points(self.start_points.vector_query(query, ctx)).await?;
+
+        let mut accumulated_tiles = Vec::new();
+
+        for (time, tiles) in time_steps {
+            let grid = CostGrid::from_tiles(&tiles, self.diagonal_moves);
+            let surface = grid.accumulate(&points_at(&start_points, &time));
+
+            for tile in &tiles {
+                accumulated_tiles.push(surface.tile(tile)?);
+            }
+        }
+
+        Ok(accumulated_tiles)
+    }
+}
+
+impl<T> QueryProcessor for AccumulatedCostProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<f64>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        stream::once(self.accumulated_tiles(query, ctx))
+            .flat_map(|result| stream::iter(flatten_result(result)))
+            .boxed()
+    }
+}
+
+async fn collect_points(
+    collections: BoxStream<'_, Result<MultiPointCollection>>,
+) -> Result<Vec<MultiPointCollection>> {
+    collections.collect::<Vec<_>>().await.into_iter().collect()
+}
+
+/// All points of the `collections` that are valid at `time`
+fn points_at(collections: &[MultiPointCollection], time: &TimeInterval) -> Vec<Coordinate2D> {
+    let mut points = Vec::new();
+
+    for collection in collections {
+        for (multi_point, feature_time) in collection.geometries().zip(collection.time_intervals())
+        {
+            if feature_time.intersects(time) {
+                points.extend_from_slice(multi_point.points());
+            }
+        }
+    }
+
+    points
+}
+
+/// The costs of all tiles of a time step on one grid
+struct CostGrid {
+    grid: TileGrid<Option<f64>>,
+    diagonal_moves: bool,
+}
+
+impl CostGrid {
+    fn from_tiles<T: Pixel>(tiles: &[RasterTile2D<T>], diagonal_moves: bool) -> Self {
+        let grid = TileGrid::from_tiles(tiles, |pixel: Option<T>| {
+            pixel
+                .map(|pixel| -> f64 { pixel.as_() })
+                .filter(|cost| cost.is_finite() && *cost >= 0.)
+        });
+
+        Self {
+            grid,
+            diagonal_moves,
+        }
+    }
+
+    /// The neighbors of a pixel and the distance to their centers
+    fn neighbors(&self, index: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        static DIRECT: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+        static DIAGONAL: [(isize, isize); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+        let x_size = self.grid.geo_transform.x_pixel_size.abs();
+        let y_size = self.grid.geo_transform.y_pixel_size.abs();
+
+        let diagonal: &[(isize, isize)] = if self.diagonal_moves { &DIAGONAL } else { &[] };
+
+        DIRECT
+            .iter()
+            .chain(diagonal.iter())
+            .filter_map(move |&(d_row, d_column)| {
+                let neighbor = self.grid.neighbor(index, (d_row, d_column))?;
+                let distance = (d_column as f64 * x_size).hypot(d_row as f64 * y_size);
+                Some((neighbor, distance))
+            })
+    }
+
+    /// Dijkstra's algorithm from all `starts`. Moving between two pixels costs the mean of their
+    /// costs times the distance of their centers.
+    fn accumulate(&self, starts: &[Coordinate2D]) -> CostSurface {
+        let costs = &self.grid.values;
+        let mut accumulated = vec![f64::INFINITY; costs.len()];
+        let mut predecessors = vec![None; costs.len()];
+        let mut queue = BinaryHeap::new();
+
+        for &start in starts {
+            if let Some(index) = self.grid.index_of(start) {
+                if costs[index].is_some() {
+                    accumulated[index] = 0.;
+                    queue.push(QueueEntry { cost: 0., index });
+                }
+            }
+        }
+
+        while let Some(QueueEntry { cost, index }) = queue.pop() {
+            if cost > accumulated[index] {
+                continue;
+            }
+
+            let pixel_cost = match costs[index] {
+                Some(pixel_cost) => pixel_cost,
+                None => continue,
+            };
+
+            for (neighbor, distance) in self.neighbors(index) {
+                let neighbor_cost = match costs[neighbor] {
+                    Some(neighbor_cost) => neighbor_cost,
+                    None => continue,
+                };
+
+                let cost = cost + (pixel_cost + neighbor_cost) / 2. * distance;
+                if cost < accumulated[neighbor] {
+                    accumulated[neighbor] = cost;
+                    predecessors[neighbor] = Some(index);
+                    queue.push(QueueEntry {
+                        cost,
+                        index: neighbor,
+                    });
+                }
+            }
+        }
+
+        CostSurface {
+            accumulated: self.grid.with_values(accumulated),
+            predecessors,
+        }
+    }
+}
+
+/// The accumulated costs from the start points together with the moves that led to them
+struct CostSurface {
+    accumulated: TileGrid<f64>,
+    predecessors: Vec<Option<usize>>,
+}
+
+impl CostSurface {
+    /// Trace the path from the nearest start to the pixel of `end` along the pixel centers
+    fn path_to(&self, end: Coordinate2D) -> Option<(Vec<Coordinate2D>, f64)> {
+        let end_index = self.accumulated.index_of(end)?;
+        let cost = self.accumulated.values[end_index];
+        if !cost.is_finite() {
+            return None;
+        }
+
+        let mut path = vec![self.accumulated.center_of(end_index)];
+        let mut index = end_index;
+        while let Some(predecessor) = self.predecessors[index] {
+            path.push(self.accumulated.center_of(predecessor));
+            index = predecessor;
+        }
+
+        // a line needs at least two coordinates
+        if path.len() == 1 {
+            path.push(path[0]);
+        }
+
+        path.reverse();
+
+        Some((path, cost))
+    }
+
+    /// The accumulated costs of the area of the `tile`
+    fn tile<T: Pixel>(&self, tile: &RasterTile2D<T>) -> Result<RasterTile2D<f64>> {
+        self.accumulated.tile(tile, Some(f64::NAN), |&cost| {
+            if cost.is_finite() {
+                cost
+            } else {
+                f64::NAN
+            }
+        })
+    }
+}
+
+/// A pixel in the queue of Dijkstra's algorithm, ordered such that the `BinaryHeap` pops the
+/// lowest cost first
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct QueueEntry {
+    cost: f64,
+    index: usize,
+}
+
+impl Eq for QueueEntry {}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{
+        MockFeatureCollectionSource, MockFeatureCollectionSourceParams, MockRasterSource,
+        MockRasterSourceParams,
+    };
+    use geoengine_datatypes::primitives::{
+        BoundingBox2D, FeatureDataRef, MultiLineStringAccess, MultiPoint, SpatialResolution,
+    };
+    use geoengine_datatypes::raster::{Raster2D, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    /// A 3x3 cost raster whose center pixel is expensive
+    fn cost_source() -> Box<dyn RasterOperator> {
+        let raster = Raster2D::new(
+            [3, 3].into(),
+            vec![1, 1, 1, 1, 9, 1, 1, 1, 1],
+            None,
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+
+        MockRasterSource {
+            params: MockRasterSourceParams {
+                data: vec![RasterTile2D {
+                    time: TimeInterval::default(),
+                    tile: TileInformation {
+                        global_geo_transform: Default::default(),
+                        global_pixel_position: [0, 0].into(),
+                        global_size_in_tiles: [1, 1].into(),
+                        global_tile_position: [0, 0].into(),
+                        tile_size_in_pixels: [3, 3].into(),
+                    },
+                    data: raster,
+                    statistics: None,
+                }],
+                result_descriptor: RasterResultDescriptor {
+                    data_type: RasterDataType::U8,
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: None,
+                    bands: vec![],
+                },
+            },
+        }
+        .boxed()
+    }
+
+    fn point_source(point: (f64, f64)) -> Box<dyn VectorOperator> {
+        MockFeatureCollectionSource {
+            params: MockFeatureCollectionSourceParams {
+                collection: MultiPointCollection::from_data(
+                    MultiPoint::many(vec![vec![point]]).unwrap(),
+                    vec![TimeInterval::default()],
+                    Default::default(),
+                )
+                .unwrap(),
+            },
+        }
+        .boxed()
+    }
+
+    fn query() -> (QueryRectangle, QueryContext) {
+        (
+            QueryRectangle {
+                bbox: BoundingBox2D::new((0., -3.).into(), (3., 0.).into()).unwrap(),
+                time_interval: TimeInterval::default(),
+                spatial_resolution: SpatialResolution::one(),
+            },
+            QueryContext {
+                chunk_byte_size: 1024 * 1024,
+                timeout: None,
+                seed: 0,
+            },
+        )
+    }
+
+    #[test]
+    fn serde() {
+        let operator = LeastCostPath {
+            params: LeastCostPathParams {
+                diagonal_moves: true,
+            },
+            raster_sources: vec![],
+            vector_sources: vec![],
+        }
+        .boxed();
+
+        let serialized = serde_json::to_string(&operator).unwrap();
+
+        assert_eq!(
+            serialized,
+            serde_json::json!({
+                "type": "LeastCostPath",
+                "params": {
+                    "diagonal_moves": true
+                },
+                "raster_sources": [],
+                "vector_sources": []
+            })
+            .to_string()
+        );
+
+        let _: Box<dyn VectorOperator> = serde_json::from_str(&serialized).unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::float_cmp)]
+    async fn path_avoids_expensive_pixel() {
+        let operator = LeastCostPath {
+            params: LeastCostPathParams {
+                diagonal_moves: false,
+            },
+            raster_sources: vec![cost_source()],
+            vector_sources: vec![point_source((0.5, -1.5)), point_source((2.5, -1.5))],
+        }
+        .boxed();
+
+        let processor = match operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+        {
+            TypedVectorQueryProcessor::MultiLineString(processor) => processor,
+            _ => panic!("expected a line processor"),
+        };
+
+        let (query, ctx) = query();
+        let collections: Vec<MultiLineStringCollection> = processor
+            .vector_query(query, ctx)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(collections.len(), 1);
+        assert_eq!(collections[0].len(), 1);
+
+        let line = collections[0].geometries().next().unwrap();
+        assert_eq!(line.lines()[0].len(), 5);
+        assert!(!line.lines()[0].contains(&Coordinate2D::new(1.5, -1.5)));
+
+        match collections[0].data("cost").unwrap() {
+            FeatureDataRef::Number(costs) => assert_eq!(costs.as_ref(), &[4.]),
+            _ => panic!("expected a number column"),
+        }
+    }
+
+    #[tokio::test]
+    #[allow(clippy::float_cmp)]
+    async fn accumulated_cost() {
+        let operator = AccumulatedCost {
+            params: AccumulatedCostParams {
+                diagonal_moves: false,
+            },
+            raster_sources: vec![cost_source()],
+            vector_sources: vec![point_source((0.5, -0.5))],
+        }
+        .boxed();
+
+        let initialized = operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap();
+        // unreachable pixels are NaN
+        assert!(initialized
+            .result_descriptor()
+            .no_data_value
+            .map_or(false, f64::is_nan));
+
+        let processor = initialized.query_processor().unwrap().get_f64().unwrap();
+
+        let (query, ctx) = query();
+        let tiles: Vec<RasterTile2D<f64>> = processor
+            .raster_query(query, ctx)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(
+            tiles[0].data.data_container,
+            vec![0., 1., 2., 1., 6., 3., 2., 3., 4.]
+        );
+    }
+}
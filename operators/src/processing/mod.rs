@@ -1 +1,18 @@
+mod attribute_join;
+mod change_detection;
+mod class_breaks;
+mod clip_by_polygon;
 mod column_range_filter;
+mod gap_filling;
+mod hydrology;
+mod least_cost_path;
+mod phenology;
+mod proximity;
+#[cfg(feature = "python")]
+mod python_script;
+mod r_script;
+mod raster_pyramid;
+mod temporal_smoothing;
+mod transition_matrix;
+#[cfg(feature = "wasm")]
+mod wasm_module;
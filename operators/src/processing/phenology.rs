@@ -0,0 +1,397 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::tile_grid::{collect_time_steps, flatten_result};
+use crate::util::Result;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use geoengine_datatypes::primitives::TimeInterval;
+use geoengine_datatypes::raster::{Pixel, Raster2D, RasterDataType, RasterTile2D};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+const MILLIS_PER_DAY: f64 = 24. * 60. * 60. * 1000.;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PhenologyParams {
+    pub metric: PhenologyMetric,
+    /// the season lasts while the values exceed `minimum + season_threshold * amplitude`
+    pub season_threshold: f64,
+}
+
+/// The seasonal metric that is derived per pixel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PhenologyMetric {
+    /// the time at which the values first exceed the season threshold in milliseconds
+    StartOfSeason,
+    /// the time at which the values last exceed the season threshold in milliseconds
+    EndOfSeason,
+    /// the time of the maximum value in milliseconds
+    PeakOfSeason,
+    /// the difference between the maximum and the minimum value
+    Amplitude,
+    /// the integral of the values over time in value-days
+    Integral,
+}
+
+/// Derives a seasonal metric per pixel from a time series of a vegetation index like NDVI.
+///
+/// The operator takes all time steps of the query into account and outputs one `F64` raster
+/// that is valid for the whole query period. Times of the start and end of the season are
+/// linearly interpolated between the acquisitions. Pixels with fewer than two valid
+/// observations are NaN.
+///
+/// Rasters have a single band, so every metric is a separate operator that shares the source.
+pub type Phenology = Operator<PhenologyParams>;
+
+#[typetag::serde]
+impl RasterOperator for Phenology {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.is_empty(),
+            error::InvalidNumberOfVectorInputs {
+                expected: 0..1,
+                found: self.vector_sources.len()
+            }
+        );
+        ensure!(
+            (0. ..=1.).contains(&self.params.season_threshold),
+            error::InvalidOperatorParameter {
+                parameter: "season_threshold".to_string(),
+                reason: "must be between zero and one".to_string(),
+            }
+        );
+
+        InitializedPhenology::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, _| {
+                Ok(RasterResultDescriptor {
+                    data_type: RasterDataType::F64,
+                    no_data_value: Some(f64::NAN),
+                    bands: vec![],
+                    ..raster_sources[0].result_descriptor()
+                })
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedPhenology::boxed)
+    }
+}
+
+crate::register_operator!(Raster, Phenology);
+
+pub type InitializedPhenology =
+    InitializedOperatorImpl<PhenologyParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedPhenology
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let params = self.params.clone();
+
+        Ok(TypedRasterQueryProcessor::F64(
+            crate::call_on_generic_raster_processor!(
+                self.raster_sources[0].query_processor()?,
+                source => RasterQueryProcessor::boxed(PhenologyProcessor { source, params })
+            ),
+        ))
+    }
+}
+
+pub struct PhenologyProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    params: PhenologyParams,
+}
+
+impl<T> PhenologyProcessor<T>
+where
+    T: Pixel,
+{
+    async fn metric_tiles(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<RasterTile2D<f64>>> {
+        let mut time_steps = collect_time_steps(self.source.raster_query(query, ctx)).await?;
+        time_steps.sort_by_key(|(time, _)| time.start());
+
+        let period = match (time_steps.first(), time_steps.last()) {
+            (Some((first, _)), Some((last, _))) => TimeInterval::new(first.start(), last.end())?,
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut tiles = Vec::new();
+        for tile in &time_steps[0].1 {
+            // the series of the tile position as (time in milliseconds, tile)
+            let series: Vec<(f64, &RasterTile2D<T>)> = time_steps
+                .iter()
+                .filter_map(|(time, time_step)| {
+                    time_step
+                        .iter()
+                        .find(|other| other.tile == tile.tile)
+                        .map(|other| (time.start().inner() as f64, other))
+                })
+                .collect();
+
+            let mut observations = Vec::with_capacity(series.len());
+            let data = (0..tile.data.data_container.len())
+                .map(|index| {
+                    observations.clear();
+                    observations.extend(series.iter().filter_map(|(time, tile)| {
+                        let pixel = tile.data.data_container[index];
+                        let value: f64 = pixel.as_();
+                        if Some(pixel) == tile.data.no_data_value || value.is_nan() {
+                            None
+                        } else {
+                            Some((*time, value))
+                        }
+                    }));
+
+                    metric(&observations, &self.params).unwrap_or(f64::NAN)
+                })
+                .collect();
+
+            tiles.push(RasterTile2D::new(
+                period,
+                tile.tile,
+                Raster2D::new(
+                    tile.data.grid_dimension,
+                    data,
+                    Some(f64::NAN),
+                    period,
+                    tile.data.geo_transform,
+                )?,
+            ));
+        }
+
+        Ok(tiles)
+    }
+}
+
+/// Compute the metric of the `observations` (time, value) in temporal order
+fn metric(observations: &[(f64, f64)], params: &PhenologyParams) -> Option<f64> {
+    if observations.len() < 2 {
+        return None;
+    }
+
+    let (peak_time, maximum) =
+        observations
+            .iter()
+            .copied()
+            .fold((f64::NAN, f64::NEG_INFINITY), |peak, (time, value)| {
+                if value > peak.1 {
+                    (time, value)
+                } else {
+                    peak
+                }
+            });
+    let minimum = observations
+        .iter()
+        .map(|(_, value)| *value)
+        .fold(f64::INFINITY, f64::min);
+    let amplitude = maximum - minimum;
+    let threshold = minimum + params.season_threshold * amplitude;
+
+    match params.metric {
+        PhenologyMetric::PeakOfSeason => Some(peak_time),
+        PhenologyMetric::Amplitude => Some(amplitude),
+        PhenologyMetric::Integral => Some(
+            observations
+                .windows(2)
+                .map(|pair| {
+                    let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+                    (t1 - t0) / MILLIS_PER_DAY * (v0 + v1) / 2.
+                })
+                .sum(),
+        ),
+        PhenologyMetric::StartOfSeason => observations
+            .windows(2)
+            .find(|pair| pair[0].1 < threshold && pair[1].1 >= threshold)
+            .map(|pair| crossing(pair[0], pair[1], threshold))
+            .or_else(|| {
+                // the season started before the first observation
+                observations
+                    .first()
+                    .filter(|(_, value)| *value >= threshold)
+                    .map(|(time, _)| *time)
+            }),
+        PhenologyMetric::EndOfSeason => observations
+            .windows(2)
+            .rev()
+            .find(|pair| pair[0].1 >= threshold && pair[1].1 < threshold)
+            .map(|pair| crossing(pair[0], pair[1], threshold))
+            .or_else(|| {
+                // the season lasts beyond the last observation
+                observations
+                    .last()
+                    .filter(|(_, value)| *value >= threshold)
+                    .map(|(time, _)| *time)
+            }),
+    }
+}
+
+/// The time at which the line between two observations crosses the `threshold`
+fn crossing((t0, v0): (f64, f64), (t1, v1): (f64, f64), threshold: f64) -> f64 {
+    t0 + (threshold - v0) / (v1 - v0) * (t1 - t0)
+}
+
+impl<T> QueryProcessor for PhenologyProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<f64>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        stream::once(self.metric_tiles(query, ctx))
+            .flat_map(|result| stream::iter(flatten_result(result)))
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution};
+    use geoengine_datatypes::raster::TileInformation;
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+
+    fn series(metric: PhenologyMetric) -> Box<dyn RasterOperator> {
+        let tile = |day: i64, data: Vec<u8>| RasterTile2D {
+            time: TimeInterval::new_unchecked(day * DAY, (day + 10) * DAY),
+            tile: TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, 0].into(),
+                global_size_in_tiles: [1, 1].into(),
+                global_tile_position: [0, 0].into(),
+                tile_size_in_pixels: [1, 2].into(),
+            },
+            data: Raster2D::new(
+                [1, 2].into(),
+                data,
+                Some(0),
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+            statistics: None,
+        };
+
+        Phenology {
+            params: PhenologyParams {
+                metric,
+                season_threshold: 0.5,
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![
+                        tile(0, vec![10, 5]),
+                        tile(10, vec![30, 0]),
+                        tile(20, vec![50, 0]),
+                        tile(30, vec![20, 0]),
+                    ],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed()
+    }
+
+    async fn run(metric: PhenologyMetric) -> Vec<RasterTile2D<f64>> {
+        let processor = series(metric)
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_f64()
+            .unwrap();
+
+        processor
+            .raster_query(
+                QueryRectangle {
+                    bbox: BoundingBox2D::new((0., -1.).into(), (2., 0.).into()).unwrap(),
+                    time_interval: TimeInterval::new_unchecked(0, 40 * DAY),
+                    spatial_resolution: SpatialResolution::one(),
+                },
+                QueryContext {
+                    chunk_byte_size: 1024 * 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .map(Result::unwrap)
+            .collect()
+            .await
+    }
+
+    #[test]
+    fn result_descriptor() {
+        let initialized = series(PhenologyMetric::Amplitude)
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap();
+
+        // pixels without enough observations are NaN
+        assert!(initialized
+            .result_descriptor()
+            .no_data_value
+            .map_or(false, f64::is_nan));
+    }
+
+    #[tokio::test]
+    async fn seasonal_metrics() {
+        let days = |metric| async move {
+            let tiles = run(metric).await;
+            assert_eq!(tiles.len(), 1);
+            assert_eq!(tiles[0].time, TimeInterval::new_unchecked(0, 40 * DAY));
+            tiles[0].data.data_container[0] / DAY as f64
+        };
+
+        // the threshold is 10 + 0.5 * 40 = 30
+        assert!((days(PhenologyMetric::StartOfSeason).await - 10.).abs() < 1e-9);
+        assert!((days(PhenologyMetric::EndOfSeason).await - 26.666_666_666).abs() < 1e-6);
+        assert!((days(PhenologyMetric::PeakOfSeason).await - 20.).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn amplitude_and_integral() {
+        let tiles = run(PhenologyMetric::Amplitude).await;
+        assert!((tiles[0].data.data_container[0] - 40.).abs() < 1e-9);
+        // a single valid observation
+        assert!(tiles[0].data.data_container[1].is_nan());
+
+        let tiles = run(PhenologyMetric::Integral).await;
+        // 10 * (20 + 40 + 35)
+        assert!((tiles[0].data.data_container[0] - 950.).abs() < 1e-9);
+    }
+}
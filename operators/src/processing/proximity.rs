@@ -0,0 +1,498 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor, TypedVectorQueryProcessor,
+    VectorQueryProcessor,
+};
+use crate::error;
+use crate::util::Result;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use geoengine_datatypes::primitives::{BoundingBox2D, Coordinate2D, SpatialBounded};
+use geoengine_datatypes::raster::{
+    euclidean_distance_transform, FeatureMask, GeoTransform, Pixel, Raster2D, RasterDataType,
+    RasterTile2D,
+};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+/// Parameters of the `Proximity` operator.
+///
+/// Features up to `max_distance` outside of a tile are considered, which must not exceed
+/// `MAX_BORDER_TILES` tiles at the resolution of a query.
+/// Pixels that are further away from any feature become no-data.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProximityParams {
+    pub max_distance: f64,
+}
+
+/// The border around a tile within which features are considered must not be wider than this
+/// many tiles, which bounds the memory of the extended grid
+const MAX_BORDER_TILES: usize = 4;
+
+/// Computes the distance of every pixel center of the raster source's grid to the nearest feature
+/// of the vector source. The output is a `F64` raster with `NaN` as no-data value.
+pub type Proximity = Operator<ProximityParams>;
+
+#[typetag::serde]
+impl RasterOperator for Proximity {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.len() == 1,
+            error::InvalidNumberOfVectorInputs {
+                expected: 1..2,
+                found: self.vector_sources.len()
+            }
+        );
+        ensure!(
+            self.params.max_distance.is_finite() && self.params.max_distance > 0.,
+            error::InvalidOperatorParameter {
+                parameter: "max_distance",
+                reason: "must be a positive number",
+            }
+        );
+
+        InitializedProximity::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, vector_sources| {
+                let raster_descriptor = raster_sources[0].result_descriptor();
+                let vector_descriptor = vector_sources[0].result_descriptor();
+
+                ensure!(
+                    raster_descriptor.spatial_reference == vector_descriptor.spatial_reference,
+                    error::InvalidSpatialReference {
+                        expected: raster_descriptor.spatial_reference,
+                        found: vector_descriptor.spatial_reference,
+                    }
+                );
+
+                Ok(RasterResultDescriptor {
+                    data_type: RasterDataType::F64,
+                    no_data_value: Some(f64::NAN),
+                    bands: vec![],
+                    ..raster_descriptor
+                })
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedProximity::boxed)
+    }
+}
+
+crate::register_operator!(Raster, Proximity);
+
+pub type InitializedProximity =
+    InitializedOperatorImpl<ProximityParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedProximity
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let raster_source = self.raster_sources[0].query_processor()?;
+        let max_distance = self.params.max_distance;
+
+        match self.vector_sources[0].query_processor()? {
+            TypedVectorQueryProcessor::MultiPoint(features) => {
+                Ok(proximity_processor(raster_source, features, max_distance))
+            }
+            TypedVectorQueryProcessor::MultiLineString(features) => {
+                Ok(proximity_processor(raster_source, features, max_distance))
+            }
+            TypedVectorQueryProcessor::MultiPolygon(features) => {
+                Ok(proximity_processor(raster_source, features, max_distance))
+            }
+            TypedVectorQueryProcessor::Data(_) => Err(error::Error::InvalidType {
+                expected: "features with geometries".to_string(),
+                found: "data collection".to_string(),
+            }),
+        }
+    }
+}
+
+fn proximity_processor<C>(
+    raster_source: TypedRasterQueryProcessor,
+    features: Box<dyn VectorQueryProcessor<VectorType = C>>,
+    max_distance: f64,
+) -> TypedRasterQueryProcessor
+where
+    C: FeatureMask + Send + 'static,
+{
+    crate::call_on_generic_raster_processor!(
+        raster_source,
+        raster_source => TypedRasterQueryProcessor::F64(RasterQueryProcessor::boxed(
+            ProximityProcessor::new(raster_source, features, max_distance)
+        ))
+    )
+}
+
+pub struct ProximityProcessor<T, C>
+where
+    T: Pixel,
+{
+    raster_source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    features: Box<dyn VectorQueryProcessor<VectorType = C>>,
+    max_distance: f64,
+}
+
+impl<T, C> ProximityProcessor<T, C>
+where
+    T: Pixel,
+    C: FeatureMask,
+{
+    pub fn new(
+        raster_source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+        features: Box<dyn VectorQueryProcessor<VectorType = C>>,
+        max_distance: f64,
+    ) -> Self {
+        Self {
+            raster_source,
+            features,
+            max_distance,
+        }
+    }
+
+    /// The number of pixels by which a tile's grid is extended in x and y direction to cover
+    /// `max_distance`
+    fn border(&self, tile: &RasterTile2D<T>) -> Result<(usize, usize)> {
+        let tile_geo_transform = tile.tile.tile_geo_transform();
+        let [rows, columns] = *tile.tile.tile_size_in_pixels().dimension_size();
+
+        let border_x = (self.max_distance / tile_geo_transform.x_pixel_size.abs()).ceil();
+        let border_y = (self.max_distance / tile_geo_transform.y_pixel_size.abs()).ceil();
+        ensure!(
+            border_x <= (MAX_BORDER_TILES * columns) as f64
+                && border_y <= (MAX_BORDER_TILES * rows) as f64,
+            error::InvalidOperatorParameter {
+                parameter: "max_distance",
+                reason: format!(
+                    "must not exceed {} tiles at the resolution of the query",
+                    MAX_BORDER_TILES
+                ),
+            }
+        );
+
+        Ok((border_x as usize, border_y as usize))
+    }
+
+    /// Compute the distances of a tile on a grid that is extended by `max_distance`
+    /// in every direction, so that features close to the tile's border are considered
+    fn distance_tile(
+        &self,
+        tile: &RasterTile2D<T>,
+        features: &[C],
+        (border_x, border_y): (usize, usize),
+    ) -> Result<RasterTile2D<f64>> {
+        let tile_geo_transform = tile.tile.tile_geo_transform();
+        let [rows, columns] = *tile.tile.tile_size_in_pixels().dimension_size();
+
+        let extended_columns = columns + 2 * border_x;
+        let extended_rows = rows + 2 * border_y;
+
+        let extended_geo_transform = GeoTransform::new_with_coordinate_x_y(
+            tile_geo_transform.upper_left_coordinate.x
+                - border_x as f64 * tile_geo_transform.x_pixel_size,
+            tile_geo_transform.x_pixel_size,
+            tile_geo_transform.upper_left_coordinate.y
+                - border_y as f64 * tile_geo_transform.y_pixel_size,
+            tile_geo_transform.y_pixel_size,
+        );
+
+        let mut seeds = vec![false; extended_rows * extended_columns];
+        for collection in features {
+            let mask = collection.feature_mask(
+                &extended_geo_transform,
+                [extended_rows, extended_columns].into(),
+                &tile.time,
+            );
+            for (seed, masked) in seeds.iter_mut().zip(mask) {
+                *seed |= masked;
+            }
+        }
+
+        let extended_distances = euclidean_distance_transform(
+            &seeds,
+            [extended_rows, extended_columns].into(),
+            tile_geo_transform.x_pixel_size,
+            tile_geo_transform.y_pixel_size,
+        );
+
+        let distances: Vec<f64> = extended_distances
+            .chunks_exact(extended_columns)
+            .skip(border_y)
+            .take(rows)
+            .flat_map(|row| row[border_x..border_x + columns].iter())
+            .map(|&distance| {
+                if distance <= self.max_distance {
+                    distance
+                } else {
+                    f64::NAN
+                }
+            })
+            .collect();
+
+        Ok(RasterTile2D::new(
+            tile.time,
+            tile.tile,
+            Raster2D::new(
+                tile.tile.tile_size_in_pixels(),
+                distances,
+                Some(f64::NAN),
+                tile.data.temporal_bounds,
+                tile.data.geo_transform,
+            )?,
+        ))
+    }
+
+    /// The query rectangle of the features that may be closer than `max_distance` to the tile
+    fn feature_query(&self, tile: &RasterTile2D<T>, query: QueryRectangle) -> QueryRectangle {
+        let tile_bounds = tile.spatial_bounds();
+        let (lower_left, upper_right) = (tile_bounds.lower_left(), tile_bounds.upper_right());
+
+        QueryRectangle {
+            bbox: BoundingBox2D::new_unchecked(
+                Coordinate2D::new(
+                    lower_left.x - self.max_distance,
+                    lower_left.y - self.max_distance,
+                ),
+                Coordinate2D::new(
+                    upper_right.x + self.max_distance,
+                    upper_right.y + self.max_distance,
+                ),
+            ),
+            time_interval: tile.time,
+            spatial_resolution: query.spatial_resolution,
+        }
+    }
+}
+
+impl<T, C> QueryProcessor for ProximityProcessor<T, C>
+where
+    T: Pixel,
+    C: FeatureMask + Send + 'static,
+{
+    type Output = RasterTile2D<f64>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        self.raster_source
+            .raster_query(query, ctx)
+            .then(async move |tile| {
+                let tile = tile?;
+                let border = self.border(&tile)?;
+
+                let features = self
+                    .features
+                    .vector_query(self.feature_query(&tile, query), ctx)
+                    .collect::<Vec<_>>()
+                    .await
+                    .into_iter()
+                    .collect::<Result<Vec<C>>>()?;
+
+                self.distance_tile(&tile, &features, border)
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::VectorOperator;
+    use crate::mock::{
+        MockFeatureCollectionSource, MockFeatureCollectionSourceParams, MockRasterSource,
+        MockRasterSourceParams,
+    };
+    use geoengine_datatypes::collections::MultiPointCollection;
+    use geoengine_datatypes::primitives::{MultiPoint, SpatialResolution, TimeInterval};
+    use geoengine_datatypes::raster::TileInformation;
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    fn operator(point: (f64, f64), max_distance: f64) -> Box<dyn RasterOperator> {
+        let raster = Raster2D::new(
+            [3, 2].into(),
+            vec![1_u8, 2, 3, 4, 5, 6],
+            None,
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+
+        let raster_source = MockRasterSource {
+            params: MockRasterSourceParams {
+                data: vec![RasterTile2D {
+                    time: TimeInterval::default(),
+                    tile: TileInformation {
+                        global_geo_transform: Default::default(),
+                        global_pixel_position: [0, 0].into(),
+                        global_size_in_tiles: [1, 1].into(),
+                        global_tile_position: [0, 0].into(),
+                        tile_size_in_pixels: [3, 2].into(),
+                    },
+                    data: raster,
+                    statistics: None,
+                }],
+                result_descriptor: RasterResultDescriptor {
+                    data_type: RasterDataType::U8,
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: None,
+                    bands: vec![],
+                },
+            },
+        }
+        .boxed();
+
+        let points = MultiPointCollection::from_data(
+            MultiPoint::many(vec![vec![point]]).unwrap(),
+            vec![TimeInterval::default()],
+            Default::default(),
+        )
+        .unwrap();
+
+        let point_source = MockFeatureCollectionSource {
+            params: MockFeatureCollectionSourceParams { collection: points },
+        }
+        .boxed();
+
+        Proximity {
+            params: ProximityParams { max_distance },
+            raster_sources: vec![raster_source],
+            vector_sources: vec![point_source],
+        }
+        .boxed()
+    }
+
+    async fn distances(operator: Box<dyn RasterOperator>) -> Vec<f64> {
+        let processor = operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_f64()
+            .unwrap();
+
+        let query = QueryRectangle {
+            bbox: BoundingBox2D::new((0., -3.).into(), (2., 0.).into()).unwrap(),
+            time_interval: TimeInterval::default(),
+            spatial_resolution: SpatialResolution::one(),
+        };
+        let ctx = QueryContext {
+            chunk_byte_size: 1024 * 1024,
+            timeout: None,
+            seed: 0,
+        };
+
+        let tiles: Vec<RasterTile2D<f64>> = processor
+            .raster_query(query, ctx)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(tiles.len(), 1);
+
+        tiles[0].data.data_container.clone()
+    }
+
+    #[test]
+    fn serde() {
+        let operator = Proximity {
+            params: ProximityParams { max_distance: 10. },
+            raster_sources: vec![],
+            vector_sources: vec![],
+        };
+
+        let serialized = serde_json::to_string(&operator.boxed()).unwrap();
+
+        assert_eq!(
+            serialized,
+            serde_json::json!({
+                "type": "Proximity",
+                "params": {
+                    "max_distance": 10.0
+                },
+                "raster_sources": [],
+                "vector_sources": []
+            })
+            .to_string()
+        );
+
+        let _: Box<dyn RasterOperator> = serde_json::from_str(&serialized).unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::float_cmp)]
+    async fn execute() {
+        let distances = distances(operator((0.5, -0.5), 2.)).await;
+
+        assert_eq!(distances[..5], [0., 1., 1., 2_f64.sqrt(), 2.]);
+        assert!(distances[5].is_nan());
+    }
+
+    #[tokio::test]
+    #[allow(clippy::float_cmp)]
+    async fn feature_outside_of_tile() {
+        let distances = distances(operator((-0.5, -0.5), 3.)).await;
+
+        assert_eq!(
+            distances,
+            vec![
+                1.,
+                2.,
+                2_f64.sqrt(),
+                5_f64.sqrt(),
+                5_f64.sqrt(),
+                8_f64.sqrt()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn max_distance_beyond_border_limit() {
+        let processor = operator((0.5, -0.5), 1e12)
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_f64()
+            .unwrap();
+
+        let query = QueryRectangle {
+            bbox: BoundingBox2D::new((0., -3.).into(), (2., 0.).into()).unwrap(),
+            time_interval: TimeInterval::default(),
+            spatial_resolution: SpatialResolution::one(),
+        };
+        let ctx = QueryContext {
+            chunk_byte_size: 1024 * 1024,
+            timeout: None,
+            seed: 0,
+        };
+
+        let tiles: Vec<Result<RasterTile2D<f64>>> =
+            processor.raster_query(query, ctx).collect().await;
+
+        assert!(matches!(
+            tiles[0],
+            Err(error::Error::InvalidOperatorParameter { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_max_distance() {
+        assert!(operator((0., 0.), -1.)
+            .initialize(&ExecutionContext::mock_empty())
+            .is_err());
+    }
+}
@@ -0,0 +1,380 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::Result;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use geoengine_datatypes::raster::{Pixel, Raster2D, RasterTile2D};
+use num_traits::AsPrimitive;
+use numpy::{Element, PyArray1, PyArray2};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+use std::sync::Arc;
+
+/// The builtins that scripts may use, everything else, e.g., `open` or `__import__`, is unavailable
+const ALLOWED_BUILTINS: [&str; 22] = [
+    "abs",
+    "all",
+    "any",
+    "bool",
+    "dict",
+    "enumerate",
+    "filter",
+    "float",
+    "int",
+    "isinstance",
+    "len",
+    "list",
+    "map",
+    "max",
+    "min",
+    "pow",
+    "range",
+    "round",
+    "sum",
+    "tuple",
+    "zip",
+    "ValueError",
+];
+
+/// Parameters of the `PythonScript` operator.
+///
+/// The `script` must define a function `process(tile, no_data_value)` that receives the pixels of a
+/// tile as a two-dimensional numpy array and the no-data value as a float or `None`. It returns an
+/// array of the same shape, which is converted to the data type of the input.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PythonScriptParams {
+    pub script: String,
+}
+
+/// Applies a user-defined Python function to every tile of its raster source.
+///
+/// Scripts run without imports and with a reduced set of builtins; `numpy` is available as `np`.
+/// This restricts what a script can do by accident, but it is no security boundary,
+/// so only trusted users should be able to run scripts.
+pub type PythonScript = Operator<PythonScriptParams>;
+
+#[typetag::serde]
+impl RasterOperator for PythonScript {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.is_empty(),
+            error::InvalidNumberOfVectorInputs {
+                expected: 0..1,
+                found: self.vector_sources.len()
+            }
+        );
+
+        InitializedPythonScript::create(
+            self.params,
+            context,
+            |params, _, _, _| compile_process_function(&params.script).map(Arc::new),
+            |_, _, _, raster_sources, _| Ok(raster_sources[0].result_descriptor()),
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedPythonScript::boxed)
+    }
+}
+
+crate::register_operator!(Raster, PythonScript);
+
+pub type InitializedPythonScript =
+    InitializedOperatorImpl<PythonScriptParams, RasterResultDescriptor, Arc<PyObject>>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedPythonScript
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let function = self.state.clone();
+
+        Ok(match self.raster_sources[0].query_processor()? {
+            TypedRasterQueryProcessor::U8(source) => {
+                TypedRasterQueryProcessor::U8(PythonScriptProcessor::new(source, function).boxed())
+            }
+            TypedRasterQueryProcessor::U16(source) => {
+                TypedRasterQueryProcessor::U16(PythonScriptProcessor::new(source, function).boxed())
+            }
+            TypedRasterQueryProcessor::U32(source) => {
+                TypedRasterQueryProcessor::U32(PythonScriptProcessor::new(source, function).boxed())
+            }
+            TypedRasterQueryProcessor::U64(source) => {
+                TypedRasterQueryProcessor::U64(PythonScriptProcessor::new(source, function).boxed())
+            }
+            TypedRasterQueryProcessor::I8(source) => {
+                TypedRasterQueryProcessor::I8(PythonScriptProcessor::new(source, function).boxed())
+            }
+            TypedRasterQueryProcessor::I16(source) => {
+                TypedRasterQueryProcessor::I16(PythonScriptProcessor::new(source, function).boxed())
+            }
+            TypedRasterQueryProcessor::I32(source) => {
+                TypedRasterQueryProcessor::I32(PythonScriptProcessor::new(source, function).boxed())
+            }
+            TypedRasterQueryProcessor::I64(source) => {
+                TypedRasterQueryProcessor::I64(PythonScriptProcessor::new(source, function).boxed())
+            }
+            TypedRasterQueryProcessor::F16(_) => {
+                return Err(error::Error::InvalidType {
+                    expected: "a raster type with a numpy equivalent".to_string(),
+                    found: "F16".to_string(),
+                })
+            }
+            TypedRasterQueryProcessor::F32(source) => {
+                TypedRasterQueryProcessor::F32(PythonScriptProcessor::new(source, function).boxed())
+            }
+            TypedRasterQueryProcessor::F64(source) => {
+                TypedRasterQueryProcessor::F64(PythonScriptProcessor::new(source, function).boxed())
+            }
+        })
+    }
+}
+
+/// Run the `script` in a restricted namespace and return its `process` function
+fn compile_process_function(script: &str) -> Result<PyObject> {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let compile = || -> PyResult<PyObject> {
+        let builtins = py.import("builtins")?;
+        let allowed_builtins = PyDict::new(py);
+        for name in &ALLOWED_BUILTINS {
+            allowed_builtins.set_item(name, builtins.getattr(name)?)?;
+        }
+
+        let globals = PyDict::new(py);
+        globals.set_item("__builtins__", allowed_builtins)?;
+        globals.set_item("np", py.import("numpy")?)?;
+
+        py.run(script, Some(globals), None)?;
+
+        match globals.get_item("process") {
+            Some(function) if function.is_callable() => Ok(function.into()),
+            _ => Err(pyo3::exceptions::PyValueError::new_err(
+                "the script must define a function `process(tile, no_data_value)`",
+            )),
+        }
+    };
+
+    compile().map_err(|error| python_error(py, &error))
+}
+
+fn python_error(py: Python, error: &PyErr) -> error::Error {
+    error::Error::PythonScript {
+        details: error
+            .pvalue(py)
+            .str()
+            .map_or_else(|_| "unknown error".to_string(), ToString::to_string),
+    }
+}
+
+pub struct PythonScriptProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    function: Arc<PyObject>,
+}
+
+impl<T> PythonScriptProcessor<T>
+where
+    T: Pixel + Element,
+{
+    pub fn new(
+        source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+        function: Arc<PyObject>,
+    ) -> Self {
+        Self { source, function }
+    }
+
+    fn process_tile(function: &PyObject, tile: RasterTile2D<T>) -> Result<RasterTile2D<T>> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let [rows, columns] = *tile.tile.tile_size_in_pixels().dimension_size();
+        let no_data_value: Option<f64> = tile.data.no_data_value.map(AsPrimitive::as_);
+
+        let process = || -> PyResult<Vec<T>> {
+            let pixels =
+                PyArray1::from_slice(py, &tile.data.data_container).reshape([rows, columns])?;
+
+            let result = function.call1(py, (pixels, no_data_value))?;
+            let result = py
+                .import("numpy")?
+                .call1("ascontiguousarray", (result, pixels.dtype()))?;
+            let result: &PyArray2<T> = result.extract()?;
+
+            if result.shape() != [rows, columns].as_ref() {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "`process` must return an array of shape ({}, {}), found {:?}",
+                    rows,
+                    columns,
+                    result.shape()
+                )));
+            }
+
+            result
+                .to_vec()
+                .map_err(|_| pyo3::exceptions::PyValueError::new_err("array is not contiguous"))
+        };
+
+        let data = process().map_err(|error| python_error(py, &error))?;
+
+        Ok(RasterTile2D::new(
+            tile.time,
+            tile.tile,
+            Raster2D::new(
+                tile.data.grid_dimension,
+                data,
+                tile.data.no_data_value,
+                tile.data.temporal_bounds,
+                tile.data.geo_transform,
+            )?,
+        ))
+    }
+}
+
+impl<T> QueryProcessor for PythonScriptProcessor<T>
+where
+    T: Pixel + Element,
+{
+    type Output = RasterTile2D<T>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        let function = self.function.clone();
+
+        self.source
+            .raster_query(query, ctx)
+            .and_then(move |tile| {
+                let function = function.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || Self::process_tile(&function, tile))
+                        .await
+                        .context(error::TokioJoin)?
+                }
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution, TimeInterval};
+    use geoengine_datatypes::raster::{RasterDataType, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    fn script_operator(script: &str) -> Box<dyn RasterOperator> {
+        let tile = RasterTile2D::new(
+            TimeInterval::default(),
+            TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, 0].into(),
+                global_size_in_tiles: [1, 1].into(),
+                global_tile_position: [0, 0].into(),
+                tile_size_in_pixels: [2, 2].into(),
+            },
+            Raster2D::new(
+                [2, 2].into(),
+                vec![0_u8, 1, 2, 3],
+                Some(0),
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+        );
+
+        PythonScript {
+            params: PythonScriptParams {
+                script: script.to_string(),
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![tile],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed()
+    }
+
+    #[tokio::test]
+    async fn process_tiles() {
+        let processor = script_operator(
+            "def process(tile, no_data_value):\n    return np.where(tile == no_data_value, tile, tile * 2)\n",
+        )
+        .initialize(&ExecutionContext::mock_empty())
+        .unwrap()
+        .query_processor()
+        .unwrap()
+        .get_u8()
+        .unwrap();
+
+        let tiles: Vec<RasterTile2D<u8>> = processor
+            .raster_query(
+                QueryRectangle {
+                    bbox: BoundingBox2D::new((0., -2.).into(), (2., 0.).into()).unwrap(),
+                    time_interval: TimeInterval::default(),
+                    spatial_resolution: SpatialResolution::one(),
+                },
+                QueryContext {
+                    chunk_byte_size: 1024 * 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].data.data_container, vec![0, 2, 4, 6]);
+        assert_eq!(tiles[0].data.no_data_value, Some(0));
+    }
+
+    #[test]
+    fn restricted_scripts() {
+        let initialize = |script: &str| {
+            script_operator(script)
+                .initialize(&ExecutionContext::mock_empty())
+                .map(|_| ())
+        };
+
+        assert!(matches!(
+            initialize("x = 1"),
+            Err(error::Error::PythonScript { .. })
+        ));
+        assert!(matches!(
+            initialize("import os\ndef process(tile, no_data_value):\n    return tile\n"),
+            Err(error::Error::PythonScript { .. })
+        ));
+        assert!(matches!(
+            initialize("open('/etc/passwd')\n"),
+            Err(error::Error::PythonScript { .. })
+        ));
+    }
+}
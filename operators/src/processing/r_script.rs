@@ -0,0 +1,318 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor,
+};
+use crate::error::{self, Error};
+use crate::util::r_runtime::RRuntime;
+use crate::util::Result;
+use arrow::array::{Array, Float64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use geoengine_datatypes::raster::{FromPrimitive, Pixel, Raster2D, RasterTile2D};
+use num_traits::AsPrimitive;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Calls the `process` function of the user script with the tile as a matrix and writes the
+/// result. The arguments are the input file, the user script, the number of rows and columns,
+/// the no-data value and the output file.
+const WRAPPER: &str = r#"
+args <- commandArgs(trailingOnly = TRUE)
+rows <- as.integer(args[[3]])
+columns <- as.integer(args[[4]])
+no_data_value <- if (args[[5]] == "NA") NA else as.numeric(args[[5]])
+
+input <- arrow::read_feather(args[[1]])
+tile <- matrix(input$value, nrow = rows, ncol = columns, byrow = TRUE)
+
+source(args[[2]], local = TRUE)
+result <- process(tile, no_data_value)
+
+if (!is.matrix(result) || any(dim(result) != c(rows, columns))) {
+    stop(sprintf("`process` must return a %d x %d matrix", rows, columns))
+}
+
+arrow::write_feather(
+    data.frame(value = as.numeric(t(result))),
+    args[[6]],
+    compression = "uncompressed"
+)
+"#;
+
+/// Parameters of the `RScript` operator.
+///
+/// The `script` must define a function `process(tile, no_data_value)` that receives the pixels of
+/// a tile as a numeric matrix and the no-data value or `NA`. It returns a matrix of the same
+/// dimensions, whose `NA` values become no-data.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RScriptParams {
+    pub script: String,
+}
+
+/// Applies an R script to every tile of its raster source.
+///
+/// The script runs in a separate process of the `RRuntime` of the execution context.
+/// Tiles are exchanged as Feather files, so the runtime needs the `arrow` R package.
+pub type RScript = Operator<RScriptParams>;
+
+#[typetag::serde]
+impl RasterOperator for RScript {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.is_empty(),
+            error::InvalidNumberOfVectorInputs {
+                expected: 0..1,
+                found: self.vector_sources.len()
+            }
+        );
+
+        InitializedRScript::create(
+            self.params,
+            context,
+            |_, context, _, _| {
+                context.r_runtime.clone().ok_or_else(|| Error::RScript {
+                    details: "there is no R runtime to run the script".to_string(),
+                })
+            },
+            |_, _, _, raster_sources, _| Ok(raster_sources[0].result_descriptor()),
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedRScript::boxed)
+    }
+}
+
+crate::register_operator!(Raster, RScript);
+
+pub type InitializedRScript =
+    InitializedOperatorImpl<RScriptParams, RasterResultDescriptor, Arc<RRuntime>>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor> for InitializedRScript {
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let runtime = self.state.clone();
+        let script = Arc::new(self.params.script.clone());
+
+        Ok(crate::call_on_generic_raster_processor!(
+            self.raster_sources[0].query_processor()?,
+            source => RasterQueryProcessor::boxed(RScriptProcessor::new(
+                source,
+                runtime,
+                script,
+            )).into()
+        ))
+    }
+}
+
+pub struct RScriptProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    runtime: Arc<RRuntime>,
+    script: Arc<String>,
+}
+
+impl<T> RScriptProcessor<T>
+where
+    T: Pixel,
+{
+    pub fn new(
+        source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+        runtime: Arc<RRuntime>,
+        script: Arc<String>,
+    ) -> Self {
+        Self {
+            source,
+            runtime,
+            script,
+        }
+    }
+
+    fn process_tile(
+        runtime: &RRuntime,
+        script: &str,
+        tile: RasterTile2D<T>,
+    ) -> Result<RasterTile2D<T>> {
+        let directory = tempfile::tempdir()?;
+        let input = directory.path().join("input.feather");
+        let output = directory.path().join("output.feather");
+        let wrapper = directory.path().join("wrapper.R");
+        let user_script = directory.path().join("script.R");
+
+        std::fs::write(&wrapper, WRAPPER)?;
+        std::fs::write(&user_script, script)?;
+        write_pixels(&input, &tile.data.data_container)?;
+
+        let [rows, columns] = *tile.tile.tile_size_in_pixels().dimension_size();
+        let no_data_value = tile.data.no_data_value.map_or_else(
+            || "NA".to_string(),
+            |no_data_value| AsPrimitive::<f64>::as_(no_data_value).to_string(),
+        );
+
+        let (rows, columns) = (rows.to_string(), columns.to_string());
+
+        runtime.run(&[
+            wrapper.as_os_str(),
+            input.as_os_str(),
+            user_script.as_os_str(),
+            OsStr::new(&rows),
+            OsStr::new(&columns),
+            OsStr::new(&no_data_value),
+            output.as_os_str(),
+        ])?;
+
+        let data = read_pixels(&output, tile.data.no_data_value)?;
+        ensure!(
+            data.len() == tile.data.data_container.len(),
+            error::RScript {
+                details: "the result has a different number of pixels than the tile"
+            }
+        );
+
+        Ok(RasterTile2D::new(
+            tile.time,
+            tile.tile,
+            Raster2D::new(
+                tile.data.grid_dimension,
+                data,
+                tile.data.no_data_value,
+                tile.data.temporal_bounds,
+                tile.data.geo_transform,
+            )?,
+        ))
+    }
+}
+
+impl<T> QueryProcessor for RScriptProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<T>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        let runtime = self.runtime.clone();
+        let script = self.script.clone();
+
+        self.source
+            .raster_query(query, ctx)
+            .and_then(move |tile| {
+                let runtime = runtime.clone();
+                let script = script.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || Self::process_tile(&runtime, &script, tile))
+                        .await
+                        .context(error::TokioJoin)?
+                }
+            })
+            .boxed()
+    }
+}
+
+/// Write the pixels as the `value` column of a Feather file
+fn write_pixels<T: Pixel>(path: &Path, pixels: &[T]) -> Result<()> {
+    let schema = Schema::new(vec![Field::new("value", DataType::Float64, false)]);
+    let values: Vec<f64> = pixels.iter().map(|&pixel| pixel.as_()).collect();
+    let batch = RecordBatch::try_new(
+        Arc::new(schema.clone()),
+        vec![Arc::new(Float64Array::from(values))],
+    )
+    .context(error::Arrow)?;
+
+    let mut writer = FileWriter::try_new(File::create(path)?, &schema).context(error::Arrow)?;
+    writer.write(&batch).context(error::Arrow)?;
+    writer.finish().context(error::Arrow)
+}
+
+/// Read the `value` column of a Feather file, missing values become the `no_data_value`
+fn read_pixels<T: Pixel>(path: &Path, no_data_value: Option<T>) -> Result<Vec<T>> {
+    let reader = FileReader::try_new(File::open(path)?).context(error::Arrow)?;
+
+    let mut pixels = Vec::new();
+    for batch in reader {
+        let batch = batch.context(error::Arrow)?;
+        let values = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .ok_or_else(|| Error::RScript {
+                details: "the result must be a numeric column `value`".to_string(),
+            })?;
+
+        for i in 0..values.len() {
+            if values.is_null(i) {
+                pixels.push(no_data_value.ok_or(Error::NoDataValueRequired)?);
+            } else {
+                pixels.push(T::from_(values.value(i)));
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::raster::RasterDataType;
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    #[test]
+    fn feather_round_trip() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("pixels.feather");
+
+        write_pixels(&path, &[1_u8, 2, 3, 4]).unwrap();
+
+        assert_eq!(read_pixels::<u8>(&path, Some(0)).unwrap(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn requires_runtime() {
+        let operator = RScript {
+            params: RScriptParams {
+                script: "process <- function(tile, no_data_value) tile * 2".to_string(),
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed();
+
+        assert!(matches!(
+            operator.initialize(&ExecutionContext::mock_empty()),
+            Err(Error::RScript { .. })
+        ));
+    }
+}
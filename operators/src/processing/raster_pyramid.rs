@@ -0,0 +1,333 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::Result;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use geoengine_datatypes::primitives::SpatialResolution;
+use geoengine_datatypes::raster::{
+    FromPrimitive, GeoTransform, Pixel, Raster2D, RasterTile2D, TileInformation,
+};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+/// Blocks of more than `2^16 × 2^16` pixels exceed any tile size
+const MAX_LEVEL: u32 = 16;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RasterPyramidParams {
+    pub aggregation: PyramidAggregation,
+    /// the coarsest level, i.e., tiles are aggregated by a factor of at most `2^max_level`
+    pub max_level: u32,
+}
+
+/// How the valid pixels of a block are combined into one pixel of a coarser level
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PyramidAggregation {
+    Mean,
+    Min,
+    Max,
+}
+
+/// Aggregates the tiles of its source to the pyramid level that fits the resolution of
+/// the query.
+///
+/// Sources like files read their data at the query resolution, but computed rasters often
+/// have a fixed resolution. For zoomed out queries, this operator combines blocks of
+/// `2^level × 2^level` pixels, so that the operators above it, e.g., a WMS rendering, process
+/// only as many pixels as the query needs. The level is the coarsest one whose resolution does
+/// not exceed the query resolution and whose blocks fit the tiles of the source.
+pub type RasterPyramid = Operator<RasterPyramidParams>;
+
+#[typetag::serde]
+impl RasterOperator for RasterPyramid {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.is_empty(),
+            error::InvalidNumberOfVectorInputs {
+                expected: 0..1,
+                found: self.vector_sources.len()
+            }
+        );
+        ensure!(
+            self.params.max_level <= MAX_LEVEL,
+            error::InvalidOperatorParameter {
+                parameter: "max_level".to_string(),
+                reason: format!("must not exceed {}", MAX_LEVEL),
+            }
+        );
+
+        InitializedRasterPyramid::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, _| Ok(raster_sources[0].result_descriptor()),
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedRasterPyramid::boxed)
+    }
+}
+
+crate::register_operator!(Raster, RasterPyramid);
+
+pub type InitializedRasterPyramid =
+    InitializedOperatorImpl<RasterPyramidParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedRasterPyramid
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let params = self.params.clone();
+
+        Ok(crate::call_on_generic_raster_processor!(
+            self.raster_sources[0].query_processor()?,
+            source => RasterQueryProcessor::boxed(RasterPyramidProcessor { source, params }).into()
+        ))
+    }
+}
+
+pub struct RasterPyramidProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    params: RasterPyramidParams,
+}
+
+impl<T> RasterPyramidProcessor<T>
+where
+    T: Pixel,
+{
+    /// The aggregation factor for `tile` and the query `resolution`
+    fn factor(&self, tile: &TileInformation, resolution: SpatialResolution) -> usize {
+        let pixel_size = f64::min(
+            tile.global_geo_transform.x_pixel_size.abs(),
+            tile.global_geo_transform.y_pixel_size.abs(),
+        );
+        let query_size = f64::min(resolution.x, resolution.y);
+
+        let [y, x] = *tile.global_pixel_position.dimension_size();
+        let [rows, columns] = *tile.tile_size_in_pixels.dimension_size();
+
+        let mut level = 0;
+        while level < self.params.max_level {
+            let factor = 1_usize << (level + 1);
+            let fits = [y, x, rows, columns].iter().all(|&size| size % factor == 0);
+
+            if !fits || pixel_size * factor as f64 > query_size {
+                break;
+            }
+            level += 1;
+        }
+
+        1 << level
+    }
+
+    fn aggregate_tile(&self, tile: RasterTile2D<T>, factor: usize) -> Result<RasterTile2D<T>> {
+        if factor == 1 {
+            return Ok(tile);
+        }
+
+        let [rows, columns] = *tile.tile.tile_size_in_pixels.dimension_size();
+        let [y, x] = *tile.tile.global_pixel_position.dimension_size();
+        let (coarse_rows, coarse_columns) = (rows / factor, columns / factor);
+        let no_data_value = tile.data.no_data_value;
+
+        let mut values = Vec::with_capacity(factor * factor);
+        let mut data = Vec::with_capacity(coarse_rows * coarse_columns);
+        for coarse_row in 0..coarse_rows {
+            for coarse_column in 0..coarse_columns {
+                values.clear();
+                for row in coarse_row * factor..(coarse_row + 1) * factor {
+                    for column in coarse_column * factor..(coarse_column + 1) * factor {
+                        let pixel = tile.data.pixel(column, row);
+                        let value: f64 = pixel.as_();
+                        if Some(pixel) != no_data_value && !value.is_nan() {
+                            values.push(value);
+                        }
+                    }
+                }
+
+                data.push(match aggregate(self.params.aggregation, &values) {
+                    Some(value) => T::from_(value),
+                    None => no_data_value.unwrap_or_else(|| T::from_(f64::NAN)),
+                });
+            }
+        }
+
+        let global_geo_transform = GeoTransform::new(
+            tile.tile.global_geo_transform.upper_left_coordinate,
+            tile.tile.global_geo_transform.x_pixel_size * factor as f64,
+            tile.tile.global_geo_transform.y_pixel_size * factor as f64,
+        );
+        let tile_information = TileInformation::new(
+            tile.tile.global_size_in_tiles,
+            tile.tile.global_tile_position,
+            [y / factor, x / factor].into(),
+            [coarse_rows, coarse_columns].into(),
+            global_geo_transform,
+        );
+
+        Ok(RasterTile2D::new(
+            tile.time,
+            tile_information,
+            Raster2D::new(
+                tile_information.tile_size_in_pixels,
+                data,
+                no_data_value,
+                tile.data.temporal_bounds,
+                tile_information.tile_geo_transform(),
+            )?,
+        ))
+    }
+}
+
+fn aggregate(aggregation: PyramidAggregation, values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(match aggregation {
+        PyramidAggregation::Mean => values.iter().sum::<f64>() / values.len() as f64,
+        PyramidAggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+        PyramidAggregation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+    })
+}
+
+impl<T> QueryProcessor for RasterPyramidProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<T>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        self.source
+            .raster_query(query, ctx)
+            .map(move |tile| {
+                let tile = tile?;
+                let factor = self.factor(&tile.tile, query.spatial_resolution);
+                self.aggregate_tile(tile, factor)
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, TimeInterval};
+    use geoengine_datatypes::raster::RasterDataType;
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    fn pyramid(aggregation: PyramidAggregation) -> Box<dyn RasterOperator> {
+        let tile = RasterTile2D::new(
+            TimeInterval::default(),
+            TileInformation {
+                global_geo_transform: GeoTransform::new((0., 4.).into(), 1., -1.),
+                global_pixel_position: [0, 0].into(),
+                global_size_in_tiles: [1, 1].into(),
+                global_tile_position: [0, 0].into(),
+                tile_size_in_pixels: [4, 4].into(),
+            },
+            Raster2D::new(
+                [4, 4].into(),
+                vec![1_u8, 2, 3, 0, 5, 6, 0, 0, 9, 10, 11, 12, 13, 14, 15, 16],
+                Some(0),
+                Default::default(),
+                GeoTransform::new((0., 4.).into(), 1., -1.),
+            )
+            .unwrap(),
+        );
+
+        RasterPyramid {
+            params: RasterPyramidParams {
+                aggregation,
+                max_level: 4,
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![tile],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed()
+    }
+
+    async fn run(aggregation: PyramidAggregation, resolution: f64) -> RasterTile2D<u8> {
+        let processor = pyramid(aggregation)
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_u8()
+            .unwrap();
+
+        let mut tiles: Vec<RasterTile2D<u8>> = processor
+            .raster_query(
+                QueryRectangle {
+                    bbox: BoundingBox2D::new((0., 0.).into(), (4., 4.).into()).unwrap(),
+                    time_interval: TimeInterval::default(),
+                    spatial_resolution: SpatialResolution::new_unchecked(resolution, resolution),
+                },
+                QueryContext {
+                    chunk_byte_size: 1024 * 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(tiles.len(), 1);
+        tiles.remove(0)
+    }
+
+    #[tokio::test]
+    async fn aggregates_to_the_query_resolution() {
+        let tile = run(PyramidAggregation::Mean, 2.5).await;
+        assert_eq!(tile.tile.tile_size_in_pixels, [2, 2].into());
+        assert!((tile.tile.global_geo_transform.x_pixel_size - 2.).abs() < f64::EPSILON);
+        assert_eq!(tile.data.data_container, vec![3, 3, 11, 13]);
+
+        let tile = run(PyramidAggregation::Max, 4.).await;
+        assert_eq!(tile.tile.tile_size_in_pixels, [1, 1].into());
+        assert_eq!(tile.data.data_container, vec![16]);
+
+        let tile = run(PyramidAggregation::Min, 2.).await;
+        assert_eq!(tile.data.data_container, vec![1, 3, 9, 11]);
+    }
+
+    #[tokio::test]
+    async fn passes_fine_queries_through() {
+        let tile = run(PyramidAggregation::Mean, 1.).await;
+        assert_eq!(tile.tile.tile_size_in_pixels, [4, 4].into());
+        assert_eq!(tile.data.data_container[..4], [1, 2, 3, 0]);
+    }
+}
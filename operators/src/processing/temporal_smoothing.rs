@@ -0,0 +1,327 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::Result;
+use futures::future;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use geoengine_datatypes::raster::{Dim2D, FromPrimitive, Pixel, Raster2D, RasterTile2D};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemporalSmoothingParams {
+    pub method: TemporalSmoothingMethod,
+    /// the number of acquisitions in the window, including the current one
+    pub window_size: usize,
+}
+
+/// How the valid values of a pixel in the window are aggregated
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TemporalSmoothingMethod {
+    Mean,
+    Median,
+}
+
+/// Smooths a raster time series with a running mean or median over the current and the
+/// previous `window_size - 1` acquisitions.
+///
+/// The operator keeps only the window of tiles per tile position, so the memory is bounded
+/// by the window size regardless of the length of the series. It expects the source to deliver
+/// the time steps in temporal order. The first time steps of a query use the shorter windows
+/// that are available and pixels without any valid value in the window remain no-data.
+pub type TemporalSmoothing = Operator<TemporalSmoothingParams>;
+
+#[typetag::serde]
+impl RasterOperator for TemporalSmoothing {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.is_empty(),
+            error::InvalidNumberOfVectorInputs {
+                expected: 0..1,
+                found: self.vector_sources.len()
+            }
+        );
+        ensure!(
+            self.params.window_size > 0,
+            error::InvalidOperatorParameter {
+                parameter: "window_size".to_string(),
+                reason: "must be positive".to_string(),
+            }
+        );
+
+        InitializedTemporalSmoothing::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, raster_sources, _| Ok(raster_sources[0].result_descriptor()),
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedTemporalSmoothing::boxed)
+    }
+}
+
+crate::register_operator!(Raster, TemporalSmoothing);
+
+pub type InitializedTemporalSmoothing =
+    InitializedOperatorImpl<TemporalSmoothingParams, RasterResultDescriptor, ()>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedTemporalSmoothing
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let params = self.params.clone();
+
+        Ok(crate::call_on_generic_raster_processor!(
+            self.raster_sources[0].query_processor()?,
+            source => RasterQueryProcessor::boxed(TemporalSmoothingProcessor { source, params }).into()
+        ))
+    }
+}
+
+pub struct TemporalSmoothingProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    params: TemporalSmoothingParams,
+}
+
+/// The latest tiles of every tile position
+type Windows<T> = HashMap<Dim2D, VecDeque<RasterTile2D<T>>>;
+
+impl<T> TemporalSmoothingProcessor<T>
+where
+    T: Pixel,
+{
+    fn smooth(
+        params: &TemporalSmoothingParams,
+        windows: &mut Windows<T>,
+        tile: RasterTile2D<T>,
+    ) -> Result<RasterTile2D<T>> {
+        let window = windows.entry(tile.tile.global_tile_position).or_default();
+        if window.len() == params.window_size {
+            window.pop_front();
+        }
+        window.push_back(tile);
+
+        let current = window.back().expect("the window contains the current tile");
+        let no_data_value = current.data.no_data_value;
+
+        let mut values = Vec::with_capacity(window.len());
+        let data = (0..current.data.data_container.len())
+            .map(|index| {
+                values.clear();
+                values.extend(window.iter().filter_map(|tile| {
+                    let pixel = tile.data.data_container[index];
+                    let value: f64 = pixel.as_();
+                    if Some(pixel) == tile.data.no_data_value || value.is_nan() {
+                        None
+                    } else {
+                        Some(value)
+                    }
+                }));
+
+                match aggregate(params.method, &mut values) {
+                    Some(value) => T::from_(value),
+                    None => no_data_value.unwrap_or_else(|| T::from_(f64::NAN)),
+                }
+            })
+            .collect();
+
+        Ok(RasterTile2D::new(
+            current.time,
+            current.tile,
+            Raster2D::new(
+                current.data.grid_dimension,
+                data,
+                no_data_value,
+                current.data.temporal_bounds,
+                current.data.geo_transform,
+            )?,
+        ))
+    }
+}
+
+/// Aggregate the `values`, which must not contain NaN, or return `None` if there are none
+fn aggregate(method: TemporalSmoothingMethod, values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(match method {
+        TemporalSmoothingMethod::Mean => values.iter().sum::<f64>() / values.len() as f64,
+        TemporalSmoothingMethod::Median => {
+            values.sort_by(|a, b| a.partial_cmp(b).expect("values are not NaN"));
+            let middle = values.len() / 2;
+            if values.len() % 2 == 0 {
+                (values[middle - 1] + values[middle]) / 2.
+            } else {
+                values[middle]
+            }
+        }
+    })
+}
+
+impl<T> QueryProcessor for TemporalSmoothingProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<T>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        let params = self.params.clone();
+
+        self.source
+            .raster_query(query, ctx)
+            .scan(Windows::new(), move |windows, tile| {
+                future::ready(Some(
+                    tile.and_then(|tile| Self::smooth(&params, windows, tile)),
+                ))
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution, TimeInterval};
+    use geoengine_datatypes::raster::{RasterDataType, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    fn series(method: TemporalSmoothingMethod) -> Box<dyn RasterOperator> {
+        let tile = |start: i64, position: usize, data: Vec<u8>| RasterTile2D {
+            time: TimeInterval::new_unchecked(start, start + 10),
+            tile: TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, position * 2].into(),
+                global_size_in_tiles: [1, 2].into(),
+                global_tile_position: [0, position].into(),
+                tile_size_in_pixels: [2, 2].into(),
+            },
+            data: Raster2D::new(
+                [2, 2].into(),
+                data,
+                Some(0),
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+            statistics: None,
+        };
+
+        TemporalSmoothing {
+            params: TemporalSmoothingParams {
+                method,
+                window_size: 3,
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![
+                        tile(0, 0, vec![1, 10, 0, 5]),
+                        tile(0, 1, vec![7, 7, 7, 7]),
+                        tile(10, 0, vec![3, 20, 0, 0]),
+                        tile(10, 1, vec![9, 9, 9, 9]),
+                        tile(20, 0, vec![8, 90, 0, 7]),
+                        tile(30, 0, vec![10, 30, 6, 0]),
+                    ],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed()
+    }
+
+    async fn run(operator: Box<dyn RasterOperator>) -> Vec<Vec<u8>> {
+        let processor = operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_u8()
+            .unwrap();
+
+        processor
+            .raster_query(
+                QueryRectangle {
+                    bbox: BoundingBox2D::new((0., -2.).into(), (4., 0.).into()).unwrap(),
+                    time_interval: TimeInterval::new_unchecked(0, 40),
+                    spatial_resolution: SpatialResolution::one(),
+                },
+                QueryContext {
+                    chunk_byte_size: 1024 * 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .map(|tile| tile.unwrap().data.data_container)
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn running_mean() {
+        assert_eq!(
+            run(series(TemporalSmoothingMethod::Mean)).await,
+            vec![
+                vec![1, 10, 0, 5],
+                vec![7, 7, 7, 7],
+                vec![2, 15, 0, 5],
+                vec![8, 8, 8, 8],
+                vec![4, 40, 0, 6],
+                vec![7, 46, 6, 7],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn running_median() {
+        assert_eq!(
+            run(series(TemporalSmoothingMethod::Median)).await,
+            vec![
+                vec![1, 10, 0, 5],
+                vec![7, 7, 7, 7],
+                vec![2, 15, 0, 5],
+                vec![8, 8, 8, 8],
+                vec![3, 20, 0, 6],
+                vec![8, 30, 6, 7],
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregate_values() {
+        assert_eq!(
+            aggregate(TemporalSmoothingMethod::Median, &mut [3., 1., 2.]),
+            Some(2.)
+        );
+        assert_eq!(aggregate(TemporalSmoothingMethod::Mean, &mut []), None);
+    }
+}
@@ -0,0 +1,432 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedPlotOperator,
+    Operator, PlotOperator, PlotOutputFormat, PlotQueryProcessor, PlotResultDescriptor,
+    QueryContext, QueryRectangle, RasterQueryProcessor, ResultDescriptor, TypedPlotQueryProcessor,
+};
+use crate::error;
+use crate::util::tile_grid::collect_time_steps;
+use crate::util::Result;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use geoengine_datatypes::plots::{self, Plot, PlotData};
+use geoengine_datatypes::primitives::{TimeInstance, TimeInterval};
+use geoengine_datatypes::raster::{Pixel, RasterDataType, RasterTile2D};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransitionMatrixParams {
+    /// an instant of the time step with the classes before the change
+    pub before: TimeInstance,
+    /// an instant of the time step with the classes after the change
+    pub after: TimeInstance,
+    /// the classes of the matrix, if `None` it contains the classes that occur in the data
+    #[serde(default)]
+    pub classes: Option<Vec<i64>>,
+    pub output_format: PlotOutputFormat,
+}
+
+/// Compares two time steps of a categorical raster, e.g., a land cover classification, and
+/// counts how many pixels changed from one class to another.
+///
+/// Pixels that are no-data in one of the time steps are not counted. If the `classes` are
+/// given, the query fails for pixels of other classes, so that no change is silently dropped
+/// from a report.
+pub type TransitionMatrix = Operator<TransitionMatrixParams>;
+
+#[typetag::serde]
+impl PlotOperator for TransitionMatrix {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedPlotOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.is_empty(),
+            error::InvalidNumberOfVectorInputs {
+                expected: 0..1,
+                found: self.vector_sources.len()
+            }
+        );
+        ensure!(
+            self.params.before < self.params.after,
+            error::InvalidOperatorParameter {
+                parameter: "before".to_string(),
+                reason: "must be earlier than `after`".to_string(),
+            }
+        );
+        ensure!(
+            self.params
+                .classes
+                .as_ref()
+                .map_or(true, |classes| !classes.is_empty()),
+            error::InvalidOperatorParameter {
+                parameter: "classes".to_string(),
+                reason: "must not be empty".to_string(),
+            }
+        );
+
+        InitializedTransitionMatrix::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |params, _, _, raster_sources, _| {
+                let source = raster_sources[0].result_descriptor();
+
+                match source.data_type {
+                    RasterDataType::F16 | RasterDataType::F32 | RasterDataType::F64 => {
+                        Err(error::Error::InvalidType {
+                            expected: "a raster of integer classes".to_string(),
+                            found: format!("{:?}", source.data_type),
+                        })
+                    }
+                    _ => Ok(PlotResultDescriptor {
+                        output_format: params.output_format,
+                        spatial_reference: source.spatial_reference(),
+                    }),
+                }
+            },
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedTransitionMatrix::boxed)
+    }
+}
+
+crate::register_operator!(Plot, TransitionMatrix);
+
+pub type InitializedTransitionMatrix =
+    InitializedOperatorImpl<TransitionMatrixParams, PlotResultDescriptor, ()>;
+
+impl InitializedOperator<PlotResultDescriptor, TypedPlotQueryProcessor>
+    for InitializedTransitionMatrix
+{
+    fn query_processor(&self) -> Result<TypedPlotQueryProcessor> {
+        let params = self.params.clone();
+
+        Ok(crate::call_on_generic_raster_processor!(
+            self.raster_sources[0].query_processor()?,
+            source => {
+                let processor = TransitionMatrixProcessor { source, params };
+                match processor.params.output_format {
+                    PlotOutputFormat::JsonPlain => {
+                        TypedPlotQueryProcessor::JsonPlain(processor.boxed())
+                    }
+                    PlotOutputFormat::JsonVega => {
+                        TypedPlotQueryProcessor::JsonVega(VegaProcessor(processor).boxed())
+                    }
+                }
+            }
+        ))
+    }
+}
+
+pub struct TransitionMatrixProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    params: TransitionMatrixParams,
+}
+
+impl<T> TransitionMatrixProcessor<T>
+where
+    T: Pixel,
+{
+    async fn matrix(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<plots::TransitionMatrix> {
+        let before_tiles = self.time_step_at(self.params.before, query, ctx).await?;
+        let after_tiles = self.time_step_at(self.params.after, query, ctx).await?;
+
+        let mut transitions = HashMap::<(i64, i64), u64>::new();
+        for after in &after_tiles {
+            if let Some(before) = before_tiles.iter().find(|before| before.tile == after.tile) {
+                for (&before_pixel, &after_pixel) in before
+                    .data
+                    .data_container
+                    .iter()
+                    .zip(&after.data.data_container)
+                {
+                    if let (Some(before_class), Some(after_class)) =
+                        (class(before, before_pixel), class(after, after_pixel))
+                    {
+                        *transitions.entry((before_class, after_class)).or_default() += 1;
+                    }
+                }
+            }
+        }
+
+        let classes = match &self.params.classes {
+            Some(classes) => classes.clone(),
+            None => {
+                let mut classes = BTreeSet::new();
+                for &(before, after) in transitions.keys() {
+                    classes.insert(before);
+                    classes.insert(after);
+                }
+                classes.into_iter().collect()
+            }
+        };
+
+        // without explicit classes, a query without valid pixels has no classes at all
+        if classes.is_empty() {
+            return Err(error::Error::InvalidOperatorParameter {
+                parameter: "classes".to_string(),
+                reason: "the query contains no classes, specify them explicitly".to_string(),
+            });
+        }
+
+        let mut matrix = plots::TransitionMatrix::new(classes).context(error::DataType)?;
+        for ((before, after), count) in transitions {
+            matrix
+                .add_count(before, after, count)
+                .context(error::DataType)?;
+        }
+
+        Ok(matrix)
+    }
+
+    /// The tiles of the time step that contains `instant`
+    async fn time_step_at(
+        &self,
+        instant: TimeInstance,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Vec<RasterTile2D<T>>> {
+        let query = QueryRectangle {
+            time_interval: TimeInterval::new_unchecked(instant, instant),
+            ..query
+        };
+
+        let time_steps = collect_time_steps(self.source.raster_query(query, ctx)).await?;
+
+        Ok(time_steps
+            .into_iter()
+            .find(|(time, _)| time.start() <= instant && instant < time.end())
+            .map(|(_, tiles)| tiles)
+            .unwrap_or_default())
+    }
+}
+
+/// The class of a pixel or `None` if it is no-data
+fn class<T: Pixel>(tile: &RasterTile2D<T>, pixel: T) -> Option<i64> {
+    if Some(pixel) == tile.data.no_data_value {
+        None
+    } else {
+        Some(pixel.as_())
+    }
+}
+
+impl<T> PlotQueryProcessor for TransitionMatrixProcessor<T>
+where
+    T: Pixel,
+{
+    type OutputFormat = serde_json::Value;
+
+    fn plot_query(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> BoxFuture<Result<Self::OutputFormat>> {
+        async move {
+            let matrix = self.matrix(query, ctx).await?;
+            serde_json::to_value(matrix).context(error::SerdeJson)
+        }
+        .boxed()
+    }
+}
+
+/// Outputs the matrix of a `TransitionMatrixProcessor` as a Vega heatmap
+pub struct VegaProcessor<T>(TransitionMatrixProcessor<T>)
+where
+    T: Pixel;
+
+impl<T> PlotQueryProcessor for VegaProcessor<T>
+where
+    T: Pixel,
+{
+    type OutputFormat = PlotData<serde_json::Value>;
+
+    fn plot_query(
+        &self,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> BoxFuture<Result<Self::OutputFormat>> {
+        async move {
+            let plot = self
+                .0
+                .matrix(query, ctx)
+                .await?
+                .to_vega_embeddable(false)
+                .context(error::DataType)?;
+
+            Ok(PlotData {
+                vega_string: plot.vega_string,
+                metadata: serde_json::to_value(plot.metadata).context(error::SerdeJson)?,
+            })
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{RasterOperator, RasterResultDescriptor};
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution};
+    use geoengine_datatypes::raster::{Raster2D, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    fn land_cover(
+        classes: Option<Vec<i64>>,
+        output_format: PlotOutputFormat,
+    ) -> Box<dyn PlotOperator> {
+        let tile = |start: i64, data: Vec<u8>| RasterTile2D {
+            time: TimeInterval::new_unchecked(start, start + 10),
+            tile: TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, 0].into(),
+                global_size_in_tiles: [1, 1].into(),
+                global_tile_position: [0, 0].into(),
+                tile_size_in_pixels: [2, 3].into(),
+            },
+            data: Raster2D::new(
+                [2, 3].into(),
+                data,
+                Some(0),
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+            statistics: None,
+        };
+
+        TransitionMatrix {
+            params: TransitionMatrixParams {
+                before: TimeInstance::from_millis(5),
+                after: TimeInstance::from_millis(25),
+                classes,
+                output_format,
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![
+                        tile(0, vec![1, 1, 2, 2, 3, 0]),
+                        tile(10, vec![3, 3, 3, 3, 3, 3]),
+                        tile(20, vec![1, 2, 2, 3, 3, 3]),
+                    ],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed()
+    }
+
+    fn query() -> QueryRectangle {
+        QueryRectangle {
+            bbox: BoundingBox2D::new((0., -2.).into(), (3., 0.).into()).unwrap(),
+            time_interval: TimeInterval::new_unchecked(0, 30),
+            spatial_resolution: SpatialResolution::one(),
+        }
+    }
+
+    fn query_context() -> QueryContext {
+        QueryContext {
+            chunk_byte_size: 1024 * 1024,
+            timeout: None,
+            seed: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_transitions() {
+        let processor = land_cover(None, PlotOutputFormat::JsonPlain)
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_json_plain()
+            .unwrap();
+
+        let json = processor
+            .plot_query(query(), query_context())
+            .await
+            .unwrap();
+        let matrix: plots::TransitionMatrix = serde_json::from_value(json).unwrap();
+
+        let mut expected = plots::TransitionMatrix::new(vec![1, 2, 3]).unwrap();
+        expected.add(1, 1).unwrap();
+        expected.add(1, 2).unwrap();
+        expected.add(2, 2).unwrap();
+        expected.add(2, 3).unwrap();
+        expected.add(3, 3).unwrap();
+
+        assert_eq!(matrix, expected);
+    }
+
+    #[tokio::test]
+    async fn vega_heatmap() {
+        let processor = land_cover(Some(vec![1, 2, 3, 4]), PlotOutputFormat::JsonVega)
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_json_vega()
+            .unwrap();
+
+        let plot = processor
+            .plot_query(query(), query_context())
+            .await
+            .unwrap();
+        let chart: serde_json::Value = serde_json::from_str(&plot.vega_string).unwrap();
+
+        assert_eq!(chart["mark"], "rect");
+        assert_eq!(chart["data"]["values"].as_array().unwrap().len(), 16);
+    }
+
+    #[tokio::test]
+    async fn unknown_classes() {
+        let processor = land_cover(Some(vec![1, 2]), PlotOutputFormat::JsonPlain)
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+            .get_json_plain()
+            .unwrap();
+
+        assert!(processor
+            .plot_query(query(), query_context())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn serde() {
+        let operator = land_cover(None, PlotOutputFormat::JsonVega);
+
+        let serialized = serde_json::to_string(&operator).unwrap();
+        let deserialized: Box<dyn PlotOperator> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+    }
+}
@@ -0,0 +1,462 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    Operator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::Result;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use geoengine_datatypes::raster::{FromPrimitive, Pixel, Raster2D, RasterTile2D};
+use num_traits::AsPrimitive;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+use std::convert::TryFrom;
+use std::mem::size_of;
+use wasmtime::{Config, Engine, ExternType, Instance, Module, Store};
+
+/// Parameters of the `WasmModule` operator.
+///
+/// The `module` is given in the WebAssembly text format. It must not import anything and must
+/// export
+///  - a `memory` with a declared maximum of at most `max_memory_pages` pages of 64 KiB,
+///  - a function `alloc(bytes: i32) -> i32` that returns the offset of a buffer of `bytes` bytes,
+///  - a function `process(offset: i32, pixels: i32, no_data_value: f64, has_no_data_value: i32) -> i32`
+///    that processes the pixels of a tile in place and returns zero on success.
+///
+/// The pixels are passed as little-endian `f64` values in row-major order and are converted
+/// back to the data type of the input.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WasmModuleParams {
+    pub module: String,
+    /// The fuel, i.e., roughly the number of instructions, a module may use per tile
+    #[serde(default = "default_fuel_per_tile")]
+    pub fuel_per_tile: u64,
+    #[serde(default = "default_max_memory_pages")]
+    pub max_memory_pages: u32,
+}
+
+fn default_fuel_per_tile() -> u64 {
+    100_000_000
+}
+
+fn default_max_memory_pages() -> u32 {
+    // 64 MiB
+    1024
+}
+
+/// Applies a user-defined WebAssembly module to every tile of its raster source.
+///
+/// Modules run without access to the host, so they can neither read files nor use the network.
+/// Their memory and the instructions they execute per tile are limited and floating point
+/// results are deterministic, so that also untrusted users can upload processing steps.
+pub type WasmModule = Operator<WasmModuleParams>;
+
+#[typetag::serde]
+impl RasterOperator for WasmModule {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        ensure!(
+            self.raster_sources.len() == 1,
+            error::InvalidNumberOfRasterInputs {
+                expected: 1..2,
+                found: self.raster_sources.len()
+            }
+        );
+        ensure!(
+            self.vector_sources.is_empty(),
+            error::InvalidNumberOfVectorInputs {
+                expected: 0..1,
+                found: self.vector_sources.len()
+            }
+        );
+
+        InitializedWasmModule::create(
+            self.params,
+            context,
+            |params, _, _, _| compile_module(params),
+            |_, _, _, raster_sources, _| Ok(raster_sources[0].result_descriptor()),
+            self.raster_sources,
+            self.vector_sources,
+        )
+        .map(InitializedWasmModule::boxed)
+    }
+}
+
+crate::register_operator!(Raster, WasmModule);
+
+pub type InitializedWasmModule =
+    InitializedOperatorImpl<WasmModuleParams, RasterResultDescriptor, Module>;
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedWasmModule
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let module = self.state.clone();
+        let fuel_per_tile = self.params.fuel_per_tile;
+
+        Ok(crate::call_on_generic_raster_processor!(
+            self.raster_sources[0].query_processor()?,
+            source => RasterQueryProcessor::boxed(WasmModuleProcessor::new(
+                source,
+                module,
+                fuel_per_tile,
+            )).into()
+        ))
+    }
+}
+
+fn wasm_error<E: ToString>(error: E) -> error::Error {
+    error::Error::WasmModule {
+        details: error.to_string(),
+    }
+}
+
+/// Compile the module and check that it adheres to the ABI and the limits
+fn compile_module(params: &WasmModuleParams) -> Result<Module> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    config.cranelift_nan_canonicalization(true);
+    config.wasm_threads(false);
+
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, &params.module).map_err(wasm_error)?;
+
+    ensure!(
+        module.imports().len() == 0,
+        error::WasmModule {
+            details: "modules must not import anything"
+        }
+    );
+
+    let memory = module.exports().find_map(|export| match export.ty() {
+        ExternType::Memory(memory) if export.name() == "memory" => Some(memory),
+        _ => None,
+    });
+    match memory.and_then(|memory| memory.limits().max()) {
+        Some(pages) if pages <= params.max_memory_pages => {}
+        Some(pages) => {
+            return Err(error::Error::WasmModule {
+                details: format!(
+                    "the memory may grow to {} pages, but at most {} are allowed",
+                    pages, params.max_memory_pages
+                ),
+            })
+        }
+        None => {
+            return Err(error::Error::WasmModule {
+                details: "modules must export a `memory` with a maximum size".to_string(),
+            })
+        }
+    }
+
+    for function in &["alloc", "process"] {
+        ensure!(
+            module
+                .exports()
+                .any(|export| export.name() == *function
+                    && matches!(export.ty(), ExternType::Func(_))),
+            error::WasmModule {
+                details: format!("modules must export a function `{}`", function)
+            }
+        );
+    }
+
+    Ok(module)
+}
+
+pub struct WasmModuleProcessor<T>
+where
+    T: Pixel,
+{
+    source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+    module: Module,
+    fuel_per_tile: u64,
+}
+
+impl<T> WasmModuleProcessor<T>
+where
+    T: Pixel,
+{
+    pub fn new(
+        source: Box<dyn RasterQueryProcessor<RasterType = T>>,
+        module: Module,
+        fuel_per_tile: u64,
+    ) -> Self {
+        Self {
+            source,
+            module,
+            fuel_per_tile,
+        }
+    }
+
+    /// Run the module on the pixels of `tile` in a fresh instance, so that tiles cannot
+    /// influence each other
+    fn process_tile(
+        module: &Module,
+        fuel_per_tile: u64,
+        tile: RasterTile2D<T>,
+    ) -> Result<RasterTile2D<T>> {
+        let store = Store::new(module.engine());
+        store.add_fuel(fuel_per_tile).map_err(wasm_error)?;
+
+        let instance = Instance::new(&store, module, &[]).map_err(wasm_error)?;
+        let memory = instance
+            .get_memory("memory")
+            .ok_or_else(|| wasm_error("modules must export a `memory` with a maximum size"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>("alloc")
+            .map_err(wasm_error)?;
+        let process = instance
+            .get_typed_func::<(i32, i32, f64, i32), i32>("process")
+            .map_err(wasm_error)?;
+
+        let pixels = &tile.data.data_container;
+        let number_of_pixels = i32::try_from(pixels.len()).map_err(wasm_error)?;
+        let bytes: Vec<u8> = pixels
+            .iter()
+            .flat_map(|&pixel| AsPrimitive::<f64>::as_(pixel).to_le_bytes().to_vec())
+            .collect();
+        let number_of_bytes = i32::try_from(bytes.len()).map_err(wasm_error)?;
+
+        let pointer = alloc.call(number_of_bytes).map_err(wasm_error)?;
+        let offset = usize::try_from(pointer).map_err(wasm_error)?;
+        memory.write(offset, &bytes).map_err(wasm_error)?;
+
+        let (no_data_value, has_no_data_value) = match tile.data.no_data_value {
+            Some(no_data_value) => (no_data_value.as_(), 1),
+            None => (0., 0),
+        };
+
+        let status = process
+            .call((pointer, number_of_pixels, no_data_value, has_no_data_value))
+            .map_err(wasm_error)?;
+        ensure!(
+            status == 0,
+            error::WasmModule {
+                details: format!("`process` failed with status {}", status)
+            }
+        );
+
+        let mut result = vec![0; bytes.len()];
+        memory.read(offset, &mut result).map_err(wasm_error)?;
+
+        let data = result
+            .chunks_exact(size_of::<f64>())
+            .map(|chunk| {
+                let mut pixel = [0; size_of::<f64>()];
+                pixel.copy_from_slice(chunk);
+                T::from_(f64::from_le_bytes(pixel))
+            })
+            .collect();
+
+        Ok(RasterTile2D::new(
+            tile.time,
+            tile.tile,
+            Raster2D::new(
+                tile.data.grid_dimension,
+                data,
+                tile.data.no_data_value,
+                tile.data.temporal_bounds,
+                tile.data.geo_transform,
+            )?,
+        ))
+    }
+}
+
+impl<T> QueryProcessor for WasmModuleProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<T>;
+
+    fn query(&self, query: QueryRectangle, ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        let module = self.module.clone();
+        let fuel_per_tile = self.fuel_per_tile;
+
+        self.source
+            .raster_query(query, ctx)
+            .and_then(move |tile| {
+                let module = module.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || {
+                        Self::process_tile(&module, fuel_per_tile, tile)
+                    })
+                    .await
+                    .context(error::TokioJoin)?
+                }
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution, TimeInterval};
+    use geoengine_datatypes::raster::{RasterDataType, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    /// Doubles all valid pixels
+    const DOUBLE: &str = r#"
+        (module
+            (memory (export "memory") 1 1)
+            (func (export "alloc") (param $bytes i32) (result i32)
+                i32.const 0)
+            (func (export "process")
+                (param $offset i32) (param $pixels i32)
+                (param $no_data f64) (param $has_no_data i32)
+                (result i32)
+                (local $end i32)
+                (local $value f64)
+                (local.set $end
+                    (i32.add (local.get $offset) (i32.mul (local.get $pixels) (i32.const 8))))
+                (block $done
+                    (loop $next
+                        (br_if $done (i32.ge_u (local.get $offset) (local.get $end)))
+                        (local.set $value (f64.load (local.get $offset)))
+                        (if (i32.eqz (i32.and
+                                (local.get $has_no_data)
+                                (f64.eq (local.get $value) (local.get $no_data))))
+                            (then (f64.store
+                                (local.get $offset)
+                                (f64.mul (local.get $value) (f64.const 2)))))
+                        (local.set $offset (i32.add (local.get $offset) (i32.const 8)))
+                        (br $next)))
+                i32.const 0))
+    "#;
+
+    /// Never terminates
+    const ENDLESS: &str = r#"
+        (module
+            (memory (export "memory") 1 1)
+            (func (export "alloc") (param $bytes i32) (result i32)
+                i32.const 0)
+            (func (export "process")
+                (param $offset i32) (param $pixels i32)
+                (param $no_data f64) (param $has_no_data i32)
+                (result i32)
+                (loop $forever (br $forever))
+                i32.const 0))
+    "#;
+
+    fn wasm_operator(module: &str) -> Box<dyn RasterOperator> {
+        let tile = RasterTile2D::new(
+            TimeInterval::default(),
+            TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [0, 0].into(),
+                global_size_in_tiles: [1, 1].into(),
+                global_tile_position: [0, 0].into(),
+                tile_size_in_pixels: [2, 2].into(),
+            },
+            Raster2D::new(
+                [2, 2].into(),
+                vec![0_u8, 1, 2, 3],
+                Some(0),
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+        );
+
+        WasmModule {
+            params: WasmModuleParams {
+                module: module.to_string(),
+                fuel_per_tile: 10_000,
+                max_memory_pages: 1,
+            },
+            raster_sources: vec![MockRasterSource {
+                params: MockRasterSourceParams {
+                    data: vec![tile],
+                    result_descriptor: RasterResultDescriptor {
+                        data_type: RasterDataType::U8,
+                        spatial_reference: SpatialReference::wgs84().into(),
+                        bbox: None,
+                        time_interval: None,
+                        no_data_value: None,
+                        bands: vec![],
+                    },
+                },
+            }
+            .boxed()],
+            vector_sources: vec![],
+        }
+        .boxed()
+    }
+
+    async fn run(module: &str) -> Result<Vec<RasterTile2D<u8>>> {
+        let processor = wasm_operator(module)
+            .initialize(&ExecutionContext::mock_empty())?
+            .query_processor()?
+            .get_u8()
+            .unwrap();
+
+        processor
+            .raster_query(
+                QueryRectangle {
+                    bbox: BoundingBox2D::new((0., -2.).into(), (2., 0.).into()).unwrap(),
+                    time_interval: TimeInterval::default(),
+                    spatial_resolution: SpatialResolution::one(),
+                },
+                QueryContext {
+                    chunk_byte_size: 1024 * 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .try_collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn process_tiles() {
+        let tiles = run(DOUBLE).await.unwrap();
+
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].data.data_container, vec![0, 2, 4, 6]);
+        assert_eq!(tiles[0].data.no_data_value, Some(0));
+    }
+
+    #[tokio::test]
+    async fn limits_instructions() {
+        assert!(matches!(
+            run(ENDLESS).await,
+            Err(error::Error::WasmModule { .. })
+        ));
+    }
+
+    #[test]
+    fn checks_abi() {
+        let initialize = |module: &str| {
+            wasm_operator(module)
+                .initialize(&ExecutionContext::mock_empty())
+                .map(|_| ())
+        };
+
+        assert!(initialize(DOUBLE).is_ok());
+
+        // unbounded memory
+        assert!(matches!(
+            initialize(&DOUBLE.replace("1 1)", "1)")),
+            Err(error::Error::WasmModule { .. })
+        ));
+
+        // access to the host
+        assert!(matches!(
+            initialize(&DOUBLE.replace(
+                "(memory",
+                "(import \"env\" \"log\" (func $log (param i32)))\n(memory"
+            )),
+            Err(error::Error::WasmModule { .. })
+        ));
+
+        assert!(matches!(
+            initialize("(module (memory (export \"memory\") 1 1))"),
+            Err(error::Error::WasmModule { .. })
+        ));
+    }
+}
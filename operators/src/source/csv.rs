@@ -156,6 +156,8 @@ impl VectorOperator for CsvSource {
                 Ok(VectorResultDescriptor {
                     data_type: VectorDataType::MultiPoint, // TODO: get as user input
                     spatial_reference: SpatialReference::wgs84().into(), // TODO: get as user input
+                    bbox: None,
+                    time_interval: None,
                 })
             },
             vec![],
@@ -165,6 +167,8 @@ impl VectorOperator for CsvSource {
     }
 }
 
+crate::register_operator!(Vector, CsvSource);
+
 impl InitializedOperator<VectorResultDescriptor, TypedVectorQueryProcessor>
     for InitializedOperatorImpl<CsvSourceParameters, VectorResultDescriptor, ()>
 {
@@ -327,7 +331,7 @@ impl Stream for CsvSourceStream {
                     if bbox.contains_coordinate(&parsed_row.coordinate) {
                         builder.push_geometry(parsed_row.coordinate.into())?;
                         builder.push_time_interval(parsed_row.time_interval)?;
-                        builder.finish_row();
+                        builder.finish_row()?;
 
                         number_of_entries += 1;
                     }
@@ -540,6 +544,8 @@ x,y
         };
         let ctx = QueryContext {
             chunk_byte_size: 10 * 8 * 2,
+            timeout: None,
+            seed: 0,
         };
 
         let r: Vec<Result<MultiPointCollection>> = p.query(query, ctx).collect().await;
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+use geoengine_datatypes::collections::{DataCollection, VectorDataType};
+use geoengine_datatypes::primitives::{FeatureData, TimeInterval};
+use geoengine_datatypes::spatial_reference::SpatialReferenceOption;
+
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedVectorOperator,
+    QueryContext, QueryProcessor, QueryRectangle, SourceOperator, TypedVectorQueryProcessor,
+    VectorOperator, VectorQueryProcessor, VectorResultDescriptor,
+};
+use crate::error;
+use crate::util::Result;
+
+/// Parameters for the CSV Data Source Operator
+///
+/// # Examples
+///
+/// ```rust
+/// use geoengine_operators::source::{CsvDataSource, CsvDataSourceParameters};
+/// use geoengine_operators::source::csv_data::{CsvDataColumn, CsvDataColumnType};
+///
+/// let json_string = r#"
+///     {
+///         "type": "CsvDataSource",
+///         "params": {
+///             "file_path": "/foo/census.csv",
+///             "field_separator": ";",
+///             "columns": [
+///                 { "name": "ags", "data_type": "text" },
+///                 { "name": "population", "data_type": "decimal" }
+///             ]
+///         }
+///     }"#;
+///
+/// let operator: CsvDataSource = serde_json::from_str(json_string).unwrap();
+///
+/// assert_eq!(operator, CsvDataSource {
+///     params: CsvDataSourceParameters {
+///         file_path: "/foo/census.csv".into(),
+///         field_separator: ';',
+///         columns: vec![
+///             CsvDataColumn { name: "ags".into(), data_type: CsvDataColumnType::Text },
+///             CsvDataColumn { name: "population".into(), data_type: CsvDataColumnType::Decimal },
+///         ],
+///     },
+/// });
+/// ```
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct CsvDataSourceParameters {
+    pub file_path: PathBuf,
+    pub field_separator: char,
+    /// The columns to read, other columns of the file are ignored
+    pub columns: Vec<CsvDataColumn>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct CsvDataColumn {
+    pub name: String,
+    pub data_type: CsvDataColumnType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CsvDataColumnType {
+    Text,
+    Number,
+    Decimal,
+}
+
+/// Reads a plain CSV table without geometries, e.g., statistics that are joined onto
+/// features with the `AttributeJoin` operator.
+///
+/// The file must have a header. All columns are nullable, empty fields become nulls.
+/// The rows are valid for all time.
+pub type CsvDataSource = SourceOperator<CsvDataSourceParameters>;
+
+#[typetag::serde]
+impl VectorOperator for CsvDataSource {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedVectorOperator>> {
+        InitializedOperatorImpl::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, _, _| {
+                Ok(VectorResultDescriptor {
+                    data_type: VectorDataType::Data,
+                    spatial_reference: SpatialReferenceOption::None,
+                    bbox: None,
+                    time_interval: None,
+                })
+            },
+            vec![],
+            vec![],
+        )
+        .map(InitializedOperatorImpl::boxed)
+    }
+}
+
+crate::register_operator!(Vector, CsvDataSource);
+
+impl InitializedOperator<VectorResultDescriptor, TypedVectorQueryProcessor>
+    for InitializedOperatorImpl<CsvDataSourceParameters, VectorResultDescriptor, ()>
+{
+    fn query_processor(&self) -> Result<TypedVectorQueryProcessor> {
+        Ok(TypedVectorQueryProcessor::Data(
+            CsvDataSourceProcessor {
+                params: self.params.clone(),
+            }
+            .boxed(),
+        ))
+    }
+}
+
+#[derive(Debug)]
+struct CsvDataSourceProcessor {
+    params: CsvDataSourceParameters,
+}
+
+impl QueryProcessor for CsvDataSourceProcessor {
+    type Output = DataCollection;
+
+    fn query(&self, _query: QueryRectangle, _ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        // tables are small, so the whole file is emitted as one collection regardless of the query
+        let params = self.params.clone();
+
+        stream::once(async move {
+            tokio::task::spawn_blocking(move || read_table(&params))
+                .await
+                .context(error::TokioJoin)?
+        })
+        .boxed()
+    }
+}
+
+fn read_table(params: &CsvDataSourceParameters) -> Result<DataCollection> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(params.field_separator as u8)
+        .has_headers(true)
+        .from_path(&params.file_path)
+        .context(error::CsvSourceReader)?;
+
+    let header = reader.headers().context(error::CsvSourceReader)?.clone();
+    let indices = params
+        .columns
+        .iter()
+        .map(|column| {
+            header
+                .iter()
+                .position(|name| name == column.name)
+                .ok_or_else(|| error::Error::CsvSource {
+                    details: format!("Column `{}` is missing in the header", column.name),
+                })
+        })
+        .collect::<Result<Vec<usize>>>()?;
+
+    let mut columns: Vec<FeatureData> = params
+        .columns
+        .iter()
+        .map(|column| match column.data_type {
+            CsvDataColumnType::Text => FeatureData::NullableText(Vec::new()),
+            CsvDataColumnType::Number => FeatureData::NullableNumber(Vec::new()),
+            CsvDataColumnType::Decimal => FeatureData::NullableDecimal(Vec::new()),
+        })
+        .collect();
+
+    let mut number_of_rows = 0;
+    for record in reader.records() {
+        let record = record.context(error::CsvSourceReader)?;
+
+        for ((&index, column), spec) in indices.iter().zip(&mut columns).zip(&params.columns) {
+            let field = record.get(index).map(str::trim).unwrap_or_default();
+            push_field(column, field).map_err(|_| error::Error::CsvSource {
+                details: format!(
+                    "Cannot parse `{}` of column `{}` as {:?}",
+                    field, spec.name, spec.data_type
+                ),
+            })?;
+        }
+
+        number_of_rows += 1;
+    }
+
+    let data: HashMap<String, FeatureData> = params
+        .columns
+        .iter()
+        .map(|column| column.name.clone())
+        .zip(columns)
+        .collect();
+
+    Ok(DataCollection::from_data(
+        vec![],
+        vec![TimeInterval::default(); number_of_rows],
+        data,
+    )?)
+}
+
+/// Appends a field to the column, empty fields are nulls
+fn push_field(column: &mut FeatureData, field: &str) -> std::result::Result<(), ()> {
+    match column {
+        FeatureData::NullableText(values) => {
+            values.push(if field.is_empty() {
+                None
+            } else {
+                Some(field.to_string())
+            });
+        }
+        FeatureData::NullableNumber(values) => values.push(if field.is_empty() {
+            None
+        } else {
+            Some(field.parse().map_err(|_| ())?)
+        }),
+        FeatureData::NullableDecimal(values) => values.push(if field.is_empty() {
+            None
+        } else {
+            Some(field.parse().map_err(|_| ())?)
+        }),
+        _ => unreachable!("columns are created as nullable text, number or decimal"),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geoengine_datatypes::primitives::{
+        BoundingBox2D, FeatureDataRef, NullableDataRef, SpatialResolution,
+    };
+    use std::io::Write;
+
+    fn file(content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "{}", content).unwrap();
+        file
+    }
+
+    fn source(file_path: PathBuf, columns: Vec<CsvDataColumn>) -> Box<dyn VectorOperator> {
+        CsvDataSource {
+            params: CsvDataSourceParameters {
+                file_path,
+                field_separator: ';',
+                columns,
+            },
+        }
+        .boxed()
+    }
+
+    async fn query(operator: Box<dyn VectorOperator>) -> Result<DataCollection> {
+        let processor = match operator
+            .initialize(&ExecutionContext::mock_empty())
+            .unwrap()
+            .query_processor()
+            .unwrap()
+        {
+            TypedVectorQueryProcessor::Data(processor) => processor,
+            _ => panic!("expected a data processor"),
+        };
+
+        let query = QueryRectangle {
+            bbox: BoundingBox2D::new((0., 0.).into(), (1., 1.).into()).unwrap(),
+            time_interval: TimeInterval::default(),
+            spatial_resolution: SpatialResolution::one(),
+        };
+        let ctx = QueryContext {
+            chunk_byte_size: 1024,
+            timeout: None,
+            seed: 0,
+        };
+
+        let mut collections: Vec<_> = processor.vector_query(query, ctx).collect().await;
+        assert_eq!(collections.len(), 1);
+        collections.remove(0)
+    }
+
+    #[tokio::test]
+    async fn read_typed_columns() {
+        let file = file("ags;name;population;density\n06534;Marburg;76000;608.5\n06531;Gießen;;\n");
+
+        let collection = query(source(
+            file.path().into(),
+            vec![
+                CsvDataColumn {
+                    name: "ags".into(),
+                    data_type: CsvDataColumnType::Text,
+                },
+                CsvDataColumn {
+                    name: "population".into(),
+                    data_type: CsvDataColumnType::Decimal,
+                },
+                CsvDataColumn {
+                    name: "density".into(),
+                    data_type: CsvDataColumnType::Number,
+                },
+            ],
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(collection.len(), 2);
+        assert!(collection.data("name").is_err());
+
+        if let FeatureDataRef::NullableText(ags) = collection.data("ags").unwrap() {
+            assert_eq!(ags.text_at(0).unwrap(), Some("06534"));
+            assert_eq!(ags.text_at(1).unwrap(), Some("06531"));
+        } else {
+            panic!("wrong type");
+        }
+
+        if let FeatureDataRef::NullableDecimal(population) = collection.data("population").unwrap()
+        {
+            assert_eq!(population.as_ref()[0], 76000);
+            assert_eq!(population.nulls(), vec![false, true]);
+        } else {
+            panic!("wrong type");
+        }
+
+        if let FeatureDataRef::NullableNumber(density) = collection.data("density").unwrap() {
+            assert_eq!(density.as_ref()[0], 608.5);
+            assert_eq!(density.nulls(), vec![false, true]);
+        } else {
+            panic!("wrong type");
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_column() {
+        let file = file("ags;population\n06534;76000\n");
+
+        assert!(query(source(
+            file.path().into(),
+            vec![CsvDataColumn {
+                name: "area".into(),
+                data_type: CsvDataColumnType::Number,
+            }],
+        ))
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn unparsable_number() {
+        let file = file("ags;population\n06534;many\n");
+
+        assert!(query(source(
+            file.path().into(),
+            vec![CsvDataColumn {
+                name: "population".into(),
+                data_type: CsvDataColumnType::Decimal,
+            }],
+        ))
+        .await
+        .is_err());
+    }
+}
@@ -0,0 +1,471 @@
+use crate::engine::Principal;
+use crate::error;
+use crate::source::gdal_source::JsonDatasetInformation;
+use crate::util::Result;
+use geoengine_datatypes::raster::RasterDataType;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Validated dataset definitions of a raster data root that can be reloaded at runtime.
+///
+/// A definition that became invalid on disk does not replace its previous valid version.
+#[derive(Debug, Clone)]
+pub struct DatasetDefinitions {
+    raster_data_root: PathBuf,
+    definitions: HashMap<String, DatasetDefinition>,
+}
+
+#[derive(Debug, Clone)]
+struct DatasetDefinition {
+    information: JsonDatasetInformation,
+    modified: SystemTime,
+}
+
+/// Who may build workflows on a dataset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DatasetAccess {
+    /// Everyone, including anonymous clients
+    Public,
+    /// Only its owner
+    Private { owner: String },
+    /// Its owner and the listed users
+    Shared { owner: String, users: Vec<String> },
+}
+
+impl Default for DatasetAccess {
+    fn default() -> Self {
+        DatasetAccess::Public
+    }
+}
+
+impl DatasetAccess {
+    /// The user who controls a private or shared dataset
+    pub fn owner(&self) -> Option<&str> {
+        match self {
+            DatasetAccess::Public => None,
+            DatasetAccess::Private { owner } | DatasetAccess::Shared { owner, .. } => Some(owner),
+        }
+    }
+
+    /// Check whether the `principal` may use the dataset
+    pub fn permits(&self, principal: &Principal) -> bool {
+        match (self, principal) {
+            (DatasetAccess::Public, _) | (_, Principal::System) => true,
+            (_, Principal::Anonymous) => false,
+            (DatasetAccess::Private { owner }, Principal::User(user)) => owner == user,
+            (DatasetAccess::Shared { owner, users }, Principal::User(user)) => {
+                owner == user || users.contains(user)
+            }
+        }
+    }
+}
+
+/// The outcome of (re)loading the dataset definitions
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReloadReport {
+    pub loaded: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub removed: Vec<String>,
+    pub failed: Vec<FailedDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedDefinition {
+    pub id: String,
+    pub reason: String,
+}
+
+impl DatasetDefinitions {
+    const DEFINITION_SUBPATH: &'static str = "./dataset_defs/";
+
+    /// Load all definitions of the `raster_data_root`
+    pub fn load(raster_data_root: &Path) -> (Self, ReloadReport) {
+        let mut definitions = Self {
+            raster_data_root: raster_data_root.to_path_buf(),
+            definitions: HashMap::new(),
+        };
+
+        let report = definitions.reload();
+
+        (definitions, report)
+    }
+
+    pub fn raster_data_root(&self) -> &Path {
+        &self.raster_data_root
+    }
+
+    pub fn get(&self, id: &str) -> Option<&JsonDatasetInformation> {
+        self.definitions.get(id).map(|d| &d.information)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &String> {
+        self.definitions.keys()
+    }
+
+    /// Check whether a definition file was added, modified or removed since the last reload
+    pub fn is_outdated(&self) -> bool {
+        let files = match self.definition_files() {
+            Ok(files) => files,
+            Err(_) => return !self.definitions.is_empty(),
+        };
+
+        files.len() != self.definitions.len()
+            || files
+                .iter()
+                .any(|(id, (_, modified))| match self.definitions.get(id) {
+                    Some(definition) => definition.modified != *modified,
+                    None => true,
+                })
+    }
+
+    /// Reload all definitions whose files changed since the last reload
+    pub fn reload(&mut self) -> ReloadReport {
+        let mut report = ReloadReport::default();
+
+        let files = match self.definition_files() {
+            Ok(files) => files,
+            Err(error) => {
+                report.failed.push(FailedDefinition {
+                    id: String::new(),
+                    reason: error.to_string(),
+                });
+                return report;
+            }
+        };
+
+        let removed: Vec<String> = self
+            .definitions
+            .keys()
+            .filter(|id| !files.contains_key(*id))
+            .cloned()
+            .collect();
+        for id in removed {
+            self.definitions.remove(&id);
+            report.removed.push(id);
+        }
+
+        for (id, (path, modified)) in files {
+            let unchanged = self
+                .definitions
+                .get(&id)
+                .map_or(false, |definition| definition.modified == modified);
+
+            if unchanged {
+                report.unchanged.push(id);
+                continue;
+            }
+
+            match self.read_definition(&path) {
+                Ok(information) => {
+                    self.definitions.insert(
+                        id.clone(),
+                        DatasetDefinition {
+                            information,
+                            modified,
+                        },
+                    );
+                    report.loaded.push(id);
+                }
+                Err(error) => report.failed.push(FailedDefinition {
+                    id,
+                    reason: error.to_string(),
+                }),
+            }
+        }
+
+        report.loaded.sort();
+        report.unchanged.sort();
+        report.removed.sort();
+        report.failed.sort_by(|a, b| a.id.cmp(&b.id));
+
+        report
+    }
+
+    /// Remove the definition `id` from disk and reclaim its data directory, unless another
+    /// definition uses it or it is outside of the raster data root
+    pub fn remove(&mut self, id: &str) -> Result<JsonDatasetInformation> {
+        let information = self
+            .get(id)
+            .cloned()
+            .context(error::UnknownDataset { id })?;
+
+        let definition_file = self.definition_directory().join(format!("{}.json", id));
+        std::fs::remove_file(definition_file).context(error::IO)?;
+        self.definitions.remove(id);
+
+        let shared = self
+            .definitions
+            .values()
+            .any(|definition| definition.information.base_path == information.base_path);
+        let data_path = self.definition_directory().join(&information.base_path);
+
+        if !shared && self.is_data_directory(&data_path)? {
+            std::fs::remove_dir_all(data_path).context(error::IO)?;
+        }
+
+        Ok(information)
+    }
+
+    /// Check whether `path` is a data directory below the raster data root, which neither is nor
+    /// contains the definition directory
+    fn is_data_directory(&self, path: &Path) -> Result<bool> {
+        if !path.is_dir() {
+            return Ok(false);
+        }
+
+        let root = self.raster_data_root.canonicalize().context(error::IO)?;
+        let definitions = self
+            .definition_directory()
+            .canonicalize()
+            .context(error::IO)?;
+        let path = path.canonicalize().context(error::IO)?;
+
+        Ok(path != root && path.starts_with(&root) && !definitions.starts_with(&path))
+    }
+
+    fn definition_directory(&self) -> PathBuf {
+        self.raster_data_root.join(Self::DEFINITION_SUBPATH)
+    }
+
+    fn definition_files(&self) -> Result<HashMap<String, (PathBuf, SystemTime)>> {
+        let mut files = HashMap::new();
+
+        for entry in std::fs::read_dir(self.definition_directory()).context(error::IO)? {
+            let path = entry.context(error::IO)?.path();
+
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "json")
+            {
+                continue;
+            }
+
+            let id = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(id) => id.to_owned(),
+                None => continue,
+            };
+
+            let modified = path
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .context(error::IO)?;
+
+            files.insert(id, (path, modified));
+        }
+
+        Ok(files)
+    }
+
+    fn read_definition(&self, path: &Path) -> Result<JsonDatasetInformation> {
+        let file = File::open(path).context(error::IO)?;
+        let information: JsonDatasetInformation =
+            serde_json::from_reader(BufReader::new(file)).context(error::SerdeJson)?;
+
+        self.validate(&information)?;
+
+        Ok(information)
+    }
+
+    fn validate(&self, information: &JsonDatasetInformation) -> Result<()> {
+        ensure!(
+            !information.time.time_intervals.is_empty(),
+            error::InvalidDatasetDefinition {
+                reason: "no time intervals"
+            }
+        );
+
+        ensure!(
+            information.data_type != RasterDataType::F16,
+            error::InvalidDatasetDefinition {
+                reason: "the data type F16 is not supported"
+            }
+        );
+
+        let &[.., tile_y, tile_x] = information.tile.tile_pixel_size.dimension_size();
+        ensure!(
+            tile_y > 0 && tile_x > 0,
+            error::InvalidDatasetDefinition {
+                reason: "empty tile size"
+            }
+        );
+
+        let data_path = self.definition_directory().join(&information.base_path);
+        ensure!(
+            data_path.is_dir(),
+            error::InvalidDatasetDefinition {
+                reason: format!("data directory `{}` does not exist", data_path.display())
+            }
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ExecutionContext;
+    use crate::source::gdal_source::JsonDatasetInformationProvider;
+    use std::io::Write;
+    use std::sync::{Arc, RwLock};
+
+    fn copy_test_definition(raster_data_root: &Path) {
+        let definitions = raster_data_root.join("dataset_defs");
+        std::fs::create_dir_all(&definitions).unwrap();
+        std::fs::create_dir_all(raster_data_root.join("modis_ndvi")).unwrap();
+        std::fs::copy(
+            "../operators/test-data/raster/dataset_defs/test.json",
+            definitions.join("test.json"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn load() {
+        let (definitions, report) =
+            DatasetDefinitions::load(Path::new("../operators/test-data/raster"));
+
+        assert_eq!(report.loaded, vec!["test".to_string()]);
+        assert!(report.failed.is_empty());
+        assert!(definitions.get("test").is_some());
+        assert!(!definitions.is_outdated());
+    }
+
+    #[test]
+    fn invalid_definition_keeps_previous_version() {
+        let dir = tempfile::tempdir().unwrap();
+        copy_test_definition(dir.path());
+
+        let (mut definitions, _) = DatasetDefinitions::load(dir.path());
+        assert!(definitions.get("test").is_some());
+
+        // make sure the modification time changes
+        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        let mut file = File::create(dir.path().join("dataset_defs/test.json")).unwrap();
+        file.write_all(b"{ \"invalid\": true }").unwrap();
+        drop(file);
+
+        let report = definitions.reload();
+
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].id, "test");
+        assert!(definitions.get("test").is_some());
+    }
+
+    #[test]
+    fn unsupported_data_type() {
+        let dir = tempfile::tempdir().unwrap();
+        copy_test_definition(dir.path());
+
+        let path = dir.path().join("dataset_defs/test.json");
+        let mut information: serde_json::Value =
+            serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        information["data_type"] = serde_json::json!("F16");
+        serde_json::to_writer(File::create(&path).unwrap(), &information).unwrap();
+
+        let (definitions, report) = DatasetDefinitions::load(dir.path());
+        assert_eq!(report.failed.len(), 1);
+        assert!(definitions.get("test").is_none());
+    }
+
+    #[test]
+    fn removed_definition() {
+        let dir = tempfile::tempdir().unwrap();
+        copy_test_definition(dir.path());
+
+        let (mut definitions, _) = DatasetDefinitions::load(dir.path());
+
+        std::fs::remove_file(dir.path().join("dataset_defs/test.json")).unwrap();
+        assert!(definitions.is_outdated());
+
+        let report = definitions.reload();
+
+        assert_eq!(report.removed, vec!["test".to_string()]);
+        assert!(definitions.get("test").is_none());
+    }
+
+    #[test]
+    fn access() {
+        let user = Principal::User("alice".to_string());
+        let other = Principal::User("bob".to_string());
+
+        assert!(DatasetAccess::Public.permits(&Principal::Anonymous));
+
+        let private = DatasetAccess::Private {
+            owner: "alice".to_string(),
+        };
+        assert!(private.permits(&user));
+        assert!(private.permits(&Principal::System));
+        assert!(!private.permits(&other));
+        assert!(!private.permits(&Principal::Anonymous));
+
+        let shared = DatasetAccess::Shared {
+            owner: "carol".to_string(),
+            users: vec!["alice".to_string()],
+        };
+        assert!(shared.permits(&user));
+        assert!(!shared.permits(&other));
+        assert!(!shared.permits(&Principal::Anonymous));
+    }
+
+    #[test]
+    fn private_dataset_requires_its_owner() {
+        let dir = tempfile::tempdir().unwrap();
+        copy_test_definition(dir.path());
+
+        let path = dir.path().join("dataset_defs/test.json");
+        let mut information: serde_json::Value =
+            serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        information["access"] = serde_json::json!({ "type": "Private", "owner": "alice" });
+        serde_json::to_writer(File::create(&path).unwrap(), &information).unwrap();
+
+        let (definitions, report) = DatasetDefinitions::load(dir.path());
+        assert!(report.failed.is_empty());
+
+        let context = |principal| ExecutionContext {
+            dataset_definitions: Some(Arc::new(RwLock::new(definitions.clone()))),
+            principal,
+            ..ExecutionContext::mock_empty()
+        };
+
+        assert!(JsonDatasetInformationProvider::from_execution_context(
+            "test",
+            &context(Principal::User("alice".to_string()))
+        )
+        .is_ok());
+        assert!(matches!(
+            JsonDatasetInformationProvider::from_execution_context(
+                "test",
+                &context(Principal::Anonymous)
+            ),
+            Err(error::Error::DatasetAccessDenied { .. })
+        ));
+    }
+
+    #[test]
+    fn remove() {
+        let dir = tempfile::tempdir().unwrap();
+        copy_test_definition(dir.path());
+        std::fs::write(dir.path().join("modis_ndvi/data.tiff"), b"").unwrap();
+
+        let (mut definitions, _) = DatasetDefinitions::load(dir.path());
+
+        assert!(definitions.remove("test").is_ok());
+        assert!(definitions.get("test").is_none());
+        assert!(!dir.path().join("dataset_defs/test.json").exists());
+        assert!(!dir.path().join("modis_ndvi").exists());
+        assert!(!definitions.is_outdated());
+
+        assert!(matches!(
+            definitions.remove("test"),
+            Err(error::Error::UnknownDataset { .. })
+        ));
+    }
+}
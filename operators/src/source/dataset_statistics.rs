@@ -0,0 +1,338 @@
+use crate::engine::{QueryContext, QueryRectangle, RasterQueryProcessor};
+use crate::error;
+use crate::util::Result;
+use futures::{future, TryStreamExt};
+use geoengine_datatypes::raster::{Pixel, TileStatistics};
+use num_traits::AsPrimitive;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Statistics of the valid pixels of a raster dataset over its extent and all of its time steps.
+///
+/// They are computed on a sample of the pixels, e.g., a query at a coarse resolution, so that
+/// consumers need not read the whole dataset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RasterDatasetStatistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// The number of valid pixels in the sample
+    pub valid_count: usize,
+    /// The number of valid pixels in equally wide bins between `min` and `max`
+    pub histogram: Vec<usize>,
+}
+
+impl RasterDatasetStatistics {
+    /// Compute the statistics of the tiles of the `query` with a histogram of `bins` bins,
+    /// `None` if there are no valid pixels.
+    ///
+    /// The tiles are queried twice, first for the range of the histogram and then for its counts.
+    pub async fn compute<T>(
+        processor: &dyn RasterQueryProcessor<RasterType = T>,
+        query: QueryRectangle,
+        ctx: QueryContext,
+        bins: usize,
+    ) -> Result<Option<Self>>
+    where
+        T: Pixel,
+    {
+        let summary = processor
+            .raster_query(query, ctx)
+            .try_fold(TileStatistics::empty(), |summary, tile| {
+                future::ok(summary.merge(&tile.statistics()))
+            })
+            .await?;
+
+        if summary.valid_count == 0 {
+            return Ok(None);
+        }
+
+        let statistics = Self {
+            min: summary.min,
+            max: summary.max,
+            mean: summary.mean,
+            valid_count: summary.valid_count,
+            histogram: vec![0; bins.max(1)],
+        };
+
+        let histogram = processor
+            .raster_query(query, ctx)
+            .try_fold(statistics.histogram.clone(), |mut histogram, tile| {
+                let no_data_value = tile.data.no_data_value;
+                for &pixel in &tile.data.data_container {
+                    let value: f64 = pixel.as_();
+                    if Some(pixel) != no_data_value && !value.is_nan() {
+                        histogram[statistics.bin(value)] += 1;
+                    }
+                }
+                future::ok(histogram)
+            })
+            .await?;
+
+        Ok(Some(Self {
+            histogram,
+            ..statistics
+        }))
+    }
+
+    /// The index of the histogram bin of a `value` within the range
+    fn bin(&self, value: f64) -> usize {
+        let bins = self.histogram.len();
+        if self.max <= self.min {
+            return 0;
+        }
+
+        let bin = ((value - self.min) / (self.max - self.min) * bins as f64) as usize;
+        bin.min(bins - 1)
+    }
+
+    /// The value below which the `fraction` of the valid pixels lie, interpolated linearly
+    /// within the bins of the histogram
+    pub fn quantile(&self, fraction: f64) -> f64 {
+        let target = fraction.max(0.).min(1.) * self.histogram.iter().sum::<usize>() as f64;
+        let width = (self.max - self.min) / self.histogram.len() as f64;
+
+        let mut below = 0.;
+        for (bin, &count) in self.histogram.iter().enumerate() {
+            let count = count as f64;
+            if count > 0. && below + count >= target {
+                return self.min + width * (bin as f64 + (target - below) / count);
+            }
+            below += count;
+        }
+
+        self.max
+    }
+
+    /// `count` ascending values that are distributed like the valid pixels, i.e., the evenly
+    /// spaced quantiles from the minimum to the maximum
+    pub fn representative_values(&self, count: usize) -> Vec<f64> {
+        match count {
+            0 => vec![],
+            1 => vec![self.quantile(0.5)],
+            _ => (0..count)
+                .map(|index| self.quantile(index as f64 / (count - 1) as f64))
+                .collect(),
+        }
+    }
+}
+
+/// Precomputed statistics of raster datasets by their id.
+///
+/// If there is a directory, every dataset's statistics are kept in its file `<id>.json`, so
+/// that they survive restarts.
+#[derive(Debug, Default)]
+pub struct DatasetStatistics {
+    directory: Option<PathBuf>,
+    statistics: HashMap<String, RasterDatasetStatistics>,
+}
+
+impl DatasetStatistics {
+    /// Load the statistics that are stored in the `directory`, which is created if it is missing.
+    /// Unreadable files are skipped and computed again.
+    pub fn load(directory: &Path) -> Result<Self> {
+        std::fs::create_dir_all(directory)?;
+
+        let mut statistics = HashMap::new();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "json")
+            {
+                continue;
+            }
+
+            let id = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(id) => id.to_string(),
+                None => continue,
+            };
+            let file = File::open(&path)?;
+            if let Ok(dataset_statistics) = serde_json::from_reader(BufReader::new(file)) {
+                statistics.insert(id, dataset_statistics);
+            }
+        }
+
+        Ok(Self {
+            directory: Some(directory.to_path_buf()),
+            statistics,
+        })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&RasterDatasetStatistics> {
+        self.statistics.get(id)
+    }
+
+    /// The ids of the datasets that have statistics
+    pub fn ids(&self) -> impl Iterator<Item = &String> {
+        self.statistics.keys()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.statistics.contains_key(id)
+    }
+
+    /// Store the statistics of the dataset `id`, replacing its previous statistics
+    pub fn insert(&mut self, id: &str, statistics: RasterDatasetStatistics) -> Result<()> {
+        if let Some(directory) = &self.directory {
+            let file = File::create(directory.join(id).with_extension("json"))?;
+            serde_json::to_writer(BufWriter::new(file), &statistics).context(error::SerdeJson)?;
+        }
+
+        self.statistics.insert(id.to_string(), statistics);
+        Ok(())
+    }
+
+    /// Forget the statistics of the dataset `id`, e.g., because it was deleted
+    pub fn remove(&mut self, id: &str) -> Result<()> {
+        if let Some(directory) = &self.directory {
+            let path = directory.join(id).with_extension("json");
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        self.statistics.remove(id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{ExecutionContext, QueryProcessor};
+    use crate::engine::{RasterOperator, RasterResultDescriptor};
+    use crate::mock::{MockRasterSource, MockRasterSourceParams};
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution, TimeInterval};
+    use geoengine_datatypes::raster::{Raster2D, RasterDataType, RasterTile2D, TileInformation};
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+
+    #[tokio::test]
+    async fn compute() {
+        let raster = Raster2D::new(
+            [2, 3].into(),
+            vec![1_u8, 2, 3, 4, 0, 10],
+            Some(0),
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+        let tile = RasterTile2D::new(
+            TimeInterval::default(),
+            TileInformation {
+                global_size_in_tiles: [1, 1].into(),
+                global_tile_position: [0, 0].into(),
+                global_pixel_position: [0, 0].into(),
+                tile_size_in_pixels: [2, 3].into(),
+                global_geo_transform: Default::default(),
+            },
+            raster,
+        );
+
+        let processor = MockRasterSource {
+            params: MockRasterSourceParams {
+                data: vec![tile],
+                result_descriptor: RasterResultDescriptor {
+                    data_type: RasterDataType::U8,
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: Some(0.),
+                    bands: vec![],
+                },
+            },
+        }
+        .boxed()
+        .initialize(&ExecutionContext::mock_empty())
+        .unwrap()
+        .query_processor()
+        .unwrap()
+        .get_u8()
+        .unwrap();
+
+        let statistics = RasterDatasetStatistics::compute(
+            processor.as_ref(),
+            QueryRectangle {
+                bbox: BoundingBox2D::new((0., 0.).into(), (3., 2.).into()).unwrap(),
+                time_interval: TimeInterval::default(),
+                spatial_resolution: SpatialResolution::one(),
+            },
+            QueryContext {
+                chunk_byte_size: 1024,
+                timeout: None,
+                seed: 0,
+            },
+            3,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            statistics,
+            RasterDatasetStatistics {
+                min: 1.,
+                max: 10.,
+                mean: 4.,
+                valid_count: 5,
+                histogram: vec![3, 1, 1],
+            }
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn quantiles() {
+        let statistics = RasterDatasetStatistics {
+            min: 0.,
+            max: 4.,
+            mean: 2.,
+            valid_count: 8,
+            histogram: vec![2, 2, 2, 2],
+        };
+
+        assert_eq!(statistics.quantile(0.), 0.);
+        assert_eq!(statistics.quantile(0.5), 2.);
+        assert_eq!(statistics.quantile(1.), 4.);
+        assert_eq!(
+            statistics.representative_values(5),
+            vec![0., 1., 2., 3., 4.]
+        );
+
+        let skewed = RasterDatasetStatistics {
+            histogram: vec![6, 0, 0, 2],
+            ..statistics
+        };
+        assert_eq!(skewed.quantile(0.5), 4. / 6.);
+        assert_eq!(skewed.quantile(1.), 4.);
+    }
+
+    #[test]
+    fn store() {
+        let directory = tempfile::tempdir().unwrap();
+        let statistics = RasterDatasetStatistics {
+            min: 0.,
+            max: 1.,
+            mean: 0.5,
+            valid_count: 2,
+            histogram: vec![1, 1],
+        };
+
+        let mut store = DatasetStatistics::load(directory.path()).unwrap();
+        store.insert("ndvi", statistics.clone()).unwrap();
+        assert_eq!(store.get("ndvi"), Some(&statistics));
+
+        let mut store = DatasetStatistics::load(directory.path()).unwrap();
+        assert_eq!(store.get("ndvi"), Some(&statistics));
+
+        store.remove("ndvi").unwrap();
+        assert!(!store.contains("ndvi"));
+        assert!(!DatasetStatistics::load(directory.path())
+            .unwrap()
+            .contains("ndvi"));
+    }
+}
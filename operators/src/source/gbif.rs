@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+use geoengine_datatypes::collections::{MultiPointCollection, VectorDataType};
+use geoengine_datatypes::primitives::{
+    Coordinate2D, FeatureData, MultiPoint, TimeInstance, TimeInterval,
+};
+use geoengine_datatypes::spatial_reference::SpatialReference;
+
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedVectorOperator,
+    QueryContext, QueryProcessor, QueryRectangle, SourceOperator, TypedVectorQueryProcessor,
+    VectorOperator, VectorQueryProcessor, VectorResultDescriptor,
+};
+use crate::error;
+use crate::util::Result;
+
+/// The occurrence search of the public GBIF API
+const GBIF_OCCURRENCE_SEARCH: &str = "https://api.gbif.org/v1/occurrence/search";
+/// The maximum page size of the occurrence search
+const GBIF_PAGE_SIZE: usize = 300;
+
+/// Parameters for the GBIF Source Operator
+///
+/// # Examples
+///
+/// ```rust
+/// use geoengine_operators::source::{GbifSource, GbifSourceParameters};
+///
+/// let json_string = r#"
+///     {
+///         "type": "GbifSource",
+///         "params": {
+///             "taxon_key": 212
+///         }
+///     }"#;
+///
+/// let operator: GbifSource = serde_json::from_str(json_string).unwrap();
+///
+/// assert_eq!(operator, GbifSource {
+///     params: GbifSourceParameters {
+///         taxon_key: Some(212),
+///         limit: 3000,
+///     },
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GbifSourceParameters {
+    /// Restricts the occurrences to a taxon of the GBIF backbone and its subtaxa, e.g., `212`
+    /// for birds
+    #[serde(default)]
+    pub taxon_key: Option<u64>,
+    /// The maximum number of occurrences per query
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+fn default_limit() -> usize {
+    3000
+}
+
+/// Queries species occurrences of the query rectangle from GBIF.
+///
+/// Only occurrences with coordinates and without geospatial issues are returned.
+/// The event date becomes the time instant of an occurrence, occurrences without one are valid for
+/// all time. Each page of the search results is emitted as one collection with the columns
+/// `gbifId`, `scientificName` and `basisOfRecord`.
+pub type GbifSource = SourceOperator<GbifSourceParameters>;
+
+#[typetag::serde]
+impl VectorOperator for GbifSource {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedVectorOperator>> {
+        InitializedOperatorImpl::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |_, _, _, _, _| {
+                Ok(VectorResultDescriptor {
+                    data_type: VectorDataType::MultiPoint,
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                })
+            },
+            vec![],
+            vec![],
+        )
+        .map(InitializedOperatorImpl::boxed)
+    }
+}
+
+crate::register_operator!(Vector, GbifSource);
+
+impl InitializedOperator<VectorResultDescriptor, TypedVectorQueryProcessor>
+    for InitializedOperatorImpl<GbifSourceParameters, VectorResultDescriptor, ()>
+{
+    fn query_processor(&self) -> Result<TypedVectorQueryProcessor> {
+        Ok(TypedVectorQueryProcessor::MultiPoint(
+            GbifSourceProcessor::new(GBIF_OCCURRENCE_SEARCH.to_string(), self.params.clone())
+                .boxed(),
+        ))
+    }
+}
+
+/// A page of the occurrence search
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OccurrencePage {
+    end_of_records: bool,
+    results: Vec<Occurrence>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Occurrence {
+    key: i64,
+    decimal_longitude: Option<f64>,
+    decimal_latitude: Option<f64>,
+    event_date: Option<String>,
+    scientific_name: Option<String>,
+    basis_of_record: Option<String>,
+}
+
+#[derive(Debug)]
+struct GbifSourceProcessor {
+    endpoint: String,
+    params: GbifSourceParameters,
+    client: reqwest::Client,
+}
+
+impl GbifSourceProcessor {
+    fn new(endpoint: String, params: GbifSourceParameters) -> Self {
+        Self {
+            endpoint,
+            params,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// The search parameters of the `query` without paging
+    fn search_parameters(&self, query: &QueryRectangle) -> Vec<(&'static str, String)> {
+        let lower_left = query.bbox.lower_left();
+        let upper_right = query.bbox.upper_right();
+
+        let mut parameters = vec![
+            ("hasCoordinate", "true".to_string()),
+            ("hasGeospatialIssue", "false".to_string()),
+            (
+                "decimalLongitude",
+                format!("{},{}", lower_left.x, upper_right.x),
+            ),
+            (
+                "decimalLatitude",
+                format!("{},{}", lower_left.y, upper_right.y),
+            ),
+        ];
+
+        if let (Some(start), Some(end)) = (
+            query.time_interval.start().as_utc_date_time(),
+            query.time_interval.end().as_utc_date_time(),
+        ) {
+            parameters.push((
+                "eventDate",
+                format!("{},{}", start.format("%Y-%m-%d"), end.format("%Y-%m-%d")),
+            ));
+        }
+
+        if let Some(taxon_key) = self.params.taxon_key {
+            parameters.push(("taxonKey", taxon_key.to_string()));
+        }
+
+        parameters
+    }
+
+    async fn fetch_page(
+        client: reqwest::Client,
+        endpoint: String,
+        parameters: Vec<(&'static str, String)>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<OccurrencePage> {
+        let bytes = client
+            .get(&endpoint)
+            .query(&parameters)
+            .query(&[("offset", offset), ("limit", limit)])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(error::Http)?
+            .bytes()
+            .await
+            .context(error::Http)?;
+
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+impl QueryProcessor for GbifSourceProcessor {
+    type Output = MultiPointCollection;
+
+    fn query(&self, query: QueryRectangle, _ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        let client = self.client.clone();
+        let endpoint = self.endpoint.clone();
+        let parameters = self.search_parameters(&query);
+        let limit = self.params.limit;
+
+        // the offset of the next page or `None` after the last page
+        stream::unfold(Some(0), move |offset| {
+            let client = client.clone();
+            let endpoint = endpoint.clone();
+            let parameters = parameters.clone();
+
+            async move {
+                let offset = offset.filter(|&offset| offset < limit)?;
+                let page_size = GBIF_PAGE_SIZE.min(limit - offset);
+
+                let page =
+                    match Self::fetch_page(client, endpoint, parameters, offset, page_size).await {
+                        Ok(page) => page,
+                        Err(error) => return Some((Err(error), None)),
+                    };
+
+                let next_offset = if page.end_of_records || page.results.is_empty() {
+                    None
+                } else {
+                    Some(offset + page.results.len())
+                };
+
+                Some((occurrences_to_collection(page.results, &query), next_offset))
+            }
+        })
+        .boxed()
+    }
+}
+
+fn occurrences_to_collection(
+    occurrences: Vec<Occurrence>,
+    query: &QueryRectangle,
+) -> Result<MultiPointCollection> {
+    let mut points = Vec::with_capacity(occurrences.len());
+    let mut time_intervals = Vec::with_capacity(occurrences.len());
+    let mut keys = Vec::with_capacity(occurrences.len());
+    let mut scientific_names = Vec::with_capacity(occurrences.len());
+    let mut basis_of_records = Vec::with_capacity(occurrences.len());
+
+    for occurrence in occurrences {
+        let coordinate = match (occurrence.decimal_longitude, occurrence.decimal_latitude) {
+            (Some(x), Some(y)) => Coordinate2D::new(x, y),
+            _ => continue,
+        };
+        let time_interval = occurrence
+            .event_date
+            .as_deref()
+            .and_then(parse_event_date)
+            .map_or_else(TimeInterval::default, |instant| {
+                TimeInterval::new_unchecked(instant, instant)
+            });
+
+        // the search is by date, so remove occurrences of the first and last day outside the query
+        if !(query.time_interval.intersects(&time_interval)
+            || time_interval.contains(&query.time_interval)
+            || query.time_interval.contains(&time_interval))
+        {
+            continue;
+        }
+
+        points.push(MultiPoint::new(vec![coordinate])?);
+        time_intervals.push(time_interval);
+        keys.push(occurrence.key);
+        scientific_names.push(occurrence.scientific_name);
+        basis_of_records.push(occurrence.basis_of_record);
+    }
+
+    let mut data = HashMap::with_capacity(3);
+    data.insert("gbifId".to_string(), FeatureData::Decimal(keys));
+    data.insert(
+        "scientificName".to_string(),
+        FeatureData::NullableText(scientific_names),
+    );
+    data.insert(
+        "basisOfRecord".to_string(),
+        FeatureData::NullableText(basis_of_records),
+    );
+
+    Ok(MultiPointCollection::from_data(
+        points,
+        time_intervals,
+        data,
+    )?)
+}
+
+/// The start of an ISO 8601 event date, which may be a date time, a date or a range of these
+fn parse_event_date(event_date: &str) -> Option<TimeInstance> {
+    let start = event_date.split('/').next()?.trim();
+
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(start) {
+        return Some(date_time.with_timezone(&Utc).into());
+    }
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(start, "%Y-%m-%dT%H:%M:%S") {
+        return Some(date_time.into());
+    }
+
+    NaiveDate::parse_from_str(start, "%Y-%m-%d")
+        .ok()
+        .map(|date| date.and_hms(0, 0, 0).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution};
+    use warp::Filter;
+
+    const PAGE: &str = r#"{
+        "offset": 0,
+        "limit": 2,
+        "endOfRecords": false,
+        "count": 3,
+        "results": [
+            {
+                "key": 1,
+                "decimalLongitude": 8.77,
+                "decimalLatitude": 50.81,
+                "eventDate": "2019-05-03T10:00:00",
+                "scientificName": "Parus major Linnaeus, 1758",
+                "basisOfRecord": "HUMAN_OBSERVATION"
+            },
+            {
+                "key": 2,
+                "decimalLongitude": 8.78,
+                "decimalLatitude": 50.82,
+                "scientificName": "Parus major Linnaeus, 1758"
+            }
+        ]
+    }"#;
+
+    const LAST_PAGE: &str = r#"{
+        "offset": 2,
+        "limit": 2,
+        "endOfRecords": true,
+        "count": 3,
+        "results": [
+            {
+                "key": 3,
+                "decimalLongitude": 8.79,
+                "decimalLatitude": 50.83,
+                "eventDate": "2019-05-04/2019-05-05"
+            }
+        ]
+    }"#;
+
+    fn query() -> QueryRectangle {
+        QueryRectangle {
+            bbox: BoundingBox2D::new((8., 50.).into(), (9., 51.).into()).unwrap(),
+            time_interval: TimeInterval::default(),
+            spatial_resolution: SpatialResolution::zero_point_one(),
+        }
+    }
+
+    #[tokio::test]
+    async fn pages() {
+        let search = warp::path!("occurrence" / "search")
+            .and(warp::query::<HashMap<String, String>>())
+            .map(|parameters: HashMap<String, String>| {
+                assert_eq!(parameters["decimalLongitude"], "8,9");
+                assert_eq!(parameters["taxonKey"], "212");
+                if parameters["offset"] == "0" {
+                    PAGE
+                } else {
+                    LAST_PAGE
+                }
+            });
+        let (address, server) = warp::serve(search).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let processor = GbifSourceProcessor::new(
+            format!("http://{}/occurrence/search", address),
+            GbifSourceParameters {
+                taxon_key: Some(212),
+                limit: 10,
+            },
+        );
+
+        let collections: Vec<MultiPointCollection> = processor
+            .query(
+                query(),
+                QueryContext {
+                    chunk_byte_size: 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(collections.len(), 2);
+        assert_eq!(collections[0].len(), 2);
+        assert_eq!(collections[1].len(), 1);
+        assert_eq!(
+            collections[0].time_intervals()[0],
+            TimeInterval::new_unchecked(
+                parse_event_date("2019-05-03T10:00:00Z").unwrap(),
+                parse_event_date("2019-05-03T10:00:00Z").unwrap()
+            )
+        );
+        assert_eq!(collections[0].time_intervals()[1], TimeInterval::default());
+    }
+
+    #[test]
+    fn event_dates() {
+        assert_eq!(
+            parse_event_date("2019-05-04/2019-05-05"),
+            parse_event_date("2019-05-04T00:00:00Z")
+        );
+        assert!(parse_event_date("2019-05-04").is_some());
+        assert!(parse_event_date("spring").is_none());
+    }
+
+    #[test]
+    fn search_parameters() {
+        let processor = GbifSourceProcessor::new(
+            GBIF_OCCURRENCE_SEARCH.to_string(),
+            GbifSourceParameters {
+                taxon_key: None,
+                limit: 10,
+            },
+        );
+
+        let parameters = processor.search_parameters(&QueryRectangle {
+            time_interval: TimeInterval::new_unchecked(
+                parse_event_date("2019-01-01").unwrap(),
+                parse_event_date("2020-01-01").unwrap(),
+            ),
+            ..query()
+        });
+
+        assert!(parameters.contains(&("eventDate", "2019-01-01,2020-01-01".to_string())));
+        assert!(!parameters.iter().any(|(name, _)| *name == "taxonKey"));
+    }
+}
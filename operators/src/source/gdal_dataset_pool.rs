@@ -0,0 +1,222 @@
+use super::remote_policy::RemoteSourcePolicy;
+use crate::util::Result;
+use gdal::raster::dataset::Dataset as GdalDataset;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A pool of open GDAL datasets, so that repeated tile requests to the same file reuse
+/// their handles instead of opening the file again.
+///
+/// GDAL handles are not thread-safe, so every handle is used by only one tile request at a
+/// time. A request takes an idle handle out of the pool and returns it once it is done.
+/// The pool keeps at most `max_handles_per_file` idle handles for at most `max_files` files and
+/// closes the handles of the least recently used file if there are more.
+pub struct GdalDatasetPool {
+    max_files: usize,
+    max_handles_per_file: usize,
+    remote_policy: RemoteSourcePolicy,
+    idle: Mutex<IdleDatasets>,
+}
+
+#[derive(Default)]
+struct IdleDatasets {
+    files: HashMap<PathBuf, IdleFile>,
+    /// a counter that orders the accesses to the pool
+    clock: u64,
+}
+
+struct IdleFile {
+    handles: Vec<SendableDataset>,
+    last_used: u64,
+}
+
+/// A dataset handle that can be moved to another thread
+struct SendableDataset(GdalDataset);
+
+// GDAL handles must not be used concurrently, but they can be moved between threads.
+// The pool hands out every handle exclusively, so a handle is never shared.
+unsafe impl Send for SendableDataset {}
+
+impl GdalDatasetPool {
+    pub fn new(max_files: usize, max_handles_per_file: usize) -> Self {
+        Self {
+            max_files,
+            max_handles_per_file,
+            remote_policy: RemoteSourcePolicy::default(),
+            idle: Mutex::new(IdleDatasets::default()),
+        }
+    }
+
+    /// Access datasets that are read over the network according to `remote_policy`
+    pub fn with_remote_policy(mut self, remote_policy: RemoteSourcePolicy) -> Self {
+        self.remote_policy = remote_policy;
+        self
+    }
+
+    pub fn remote_policy(&self) -> &RemoteSourcePolicy {
+        &self.remote_policy
+    }
+
+    /// The number of idle handles over all files
+    pub fn idle_handles(&self) -> usize {
+        self.idle.lock().map_or(0, |idle| {
+            idle.files.values().map(|file| file.handles.len()).sum()
+        })
+    }
+
+    fn take(&self, path: &Path) -> Option<GdalDataset> {
+        let mut idle = self.idle.lock().ok()?;
+        idle.clock += 1;
+        let clock = idle.clock;
+
+        let file = idle.files.get_mut(path)?;
+        file.last_used = clock;
+        file.handles.pop().map(|dataset| dataset.0)
+    }
+
+    fn give_back(&self, path: &Path, dataset: GdalDataset) {
+        // a poisoned pool just closes the dataset
+        let mut idle = match self.idle.lock() {
+            Ok(idle) => idle,
+            Err(_) => return,
+        };
+        idle.clock += 1;
+        let clock = idle.clock;
+
+        let file = idle
+            .files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| IdleFile {
+                handles: Vec::new(),
+                last_used: clock,
+            });
+        file.last_used = clock;
+
+        if file.handles.len() < self.max_handles_per_file {
+            file.handles.push(SendableDataset(dataset));
+        }
+
+        while idle.files.len() > self.max_files {
+            let least_recently_used = idle
+                .files
+                .iter()
+                .min_by_key(|(_, file)| file.last_used)
+                .map(|(path, _)| path.clone());
+
+            match least_recently_used {
+                Some(path) => idle.files.remove(&path),
+                None => break,
+            };
+        }
+    }
+}
+
+impl fmt::Debug for GdalDatasetPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GdalDatasetPool")
+            .field("max_files", &self.max_files)
+            .field("max_handles_per_file", &self.max_handles_per_file)
+            .field("remote_policy", &self.remote_policy)
+            .field("idle_handles", &self.idle_handles())
+            .finish()
+    }
+}
+
+impl Default for GdalDatasetPool {
+    fn default() -> Self {
+        Self::new(64, 4)
+    }
+}
+
+/// An open GDAL dataset that returns to its pool when it is dropped
+pub struct GdalDatasetHandle<'p> {
+    dataset: Option<GdalDataset>,
+    path: PathBuf,
+    pool: Option<&'p GdalDatasetPool>,
+}
+
+impl<'p> GdalDatasetHandle<'p> {
+    /// Reuse an idle handle of the `pool` or open the dataset at `path`.
+    /// Without a `pool`, the dataset is closed when the handle is dropped.
+    pub fn open(path: &Path, pool: Option<&'p GdalDatasetPool>) -> Result<Self> {
+        let dataset = match pool.and_then(|pool| pool.take(path)) {
+            Some(dataset) => dataset,
+            None => GdalDataset::open(path)?,
+        };
+
+        Ok(Self {
+            dataset: Some(dataset),
+            path: path.to_path_buf(),
+            pool,
+        })
+    }
+}
+
+impl<'p> Deref for GdalDatasetHandle<'p> {
+    type Target = GdalDataset;
+
+    fn deref(&self) -> &Self::Target {
+        self.dataset
+            .as_ref()
+            .expect("the dataset is only removed on drop")
+    }
+}
+
+impl<'p> Drop for GdalDatasetHandle<'p> {
+    fn drop(&mut self) {
+        if let (Some(pool), Some(dataset)) = (self.pool, self.dataset.take()) {
+            pool.give_back(&self.path, dataset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE_A: &str = "test-data/raster/modis_ndvi/MOD13A2_M_NDVI_2014-01-01.TIFF";
+    const FILE_B: &str = "test-data/raster/modis_ndvi/MOD13A2_M_NDVI_2014-02-01.TIFF";
+
+    #[test]
+    fn reuses_handles() {
+        let pool = GdalDatasetPool::new(2, 1);
+
+        {
+            let first = GdalDatasetHandle::open(Path::new(FILE_A), Some(&pool)).unwrap();
+            let second = GdalDatasetHandle::open(Path::new(FILE_A), Some(&pool)).unwrap();
+            assert!(first.rasterband(1).is_ok());
+            assert!(second.rasterband(1).is_ok());
+            assert_eq!(pool.idle_handles(), 0);
+        }
+
+        // only one idle handle per file is kept
+        assert_eq!(pool.idle_handles(), 1);
+
+        let reused = GdalDatasetHandle::open(Path::new(FILE_A), Some(&pool)).unwrap();
+        assert_eq!(pool.idle_handles(), 0);
+        drop(reused);
+        assert_eq!(pool.idle_handles(), 1);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_files() {
+        let pool = GdalDatasetPool::new(1, 4);
+
+        drop(GdalDatasetHandle::open(Path::new(FILE_A), Some(&pool)).unwrap());
+        drop(GdalDatasetHandle::open(Path::new(FILE_B), Some(&pool)).unwrap());
+
+        assert_eq!(pool.idle_handles(), 1);
+        assert!(pool.take(Path::new(FILE_A)).is_none());
+        assert!(pool.take(Path::new(FILE_B)).is_some());
+    }
+
+    #[test]
+    fn without_pool() {
+        let handle = GdalDatasetHandle::open(Path::new(FILE_A), None).unwrap();
+        assert!(handle.rasterband(1).is_ok());
+        assert!(GdalDatasetHandle::open(Path::new("does/not/exist.tiff"), None).is_err());
+    }
+}
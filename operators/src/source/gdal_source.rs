@@ -1,13 +1,18 @@
+use super::dataset_definitions::DatasetAccess;
+use super::gdal_dataset_pool::{GdalDatasetHandle, GdalDatasetPool};
+use super::workflow_source::referenced_parameters;
 use crate::{
     engine::{
-        InitializedOperator, InitializedOperatorBase, InitializedOperatorImpl,
-        InitializedRasterOperator, QueryProcessor, RasterOperator, RasterQueryProcessor,
-        RasterResultDescriptor, SourceOperator, TypedRasterQueryProcessor,
+        enclosing_time_interval, ExecutionContext, InitializedOperator, InitializedOperatorBase,
+        InitializedOperatorImpl, InitializedRasterOperator, QueryProcessor, RasterBandDescriptor,
+        RasterOperator, RasterQueryProcessor, RasterResultDescriptor, SourceOperator,
+        TypedOperator, TypedRasterQueryProcessor,
     },
+    error,
     util::Result,
 };
+use snafu::{ensure, OptionExt, ResultExt};
 
-use gdal::raster::dataset::Dataset as GdalDataset;
 use gdal::raster::rasterband::RasterBand as GdalRasterBand;
 use std::{
     cmp::min,
@@ -15,6 +20,7 @@ use std::{
     marker::PhantomData,
     path::Path,
     path::PathBuf,
+    sync::Arc,
 };
 //use gdal::metadata::Metadata; // TODO: handle metadata
 
@@ -23,7 +29,10 @@ use serde::{Deserialize, Serialize};
 use futures::stream::{self, BoxStream, StreamExt};
 
 use geoengine_datatypes::{
-    primitives::{BoundingBox2D, Coordinate2D, SpatialBounded, SpatialResolution, TimeInterval},
+    primitives::{
+        BoundingBox2D, Coordinate2D, SpatialBounded, SpatialPartition2D, SpatialPartitioned,
+        SpatialResolution, TimeInterval,
+    },
     raster::Dim2D,
 };
 use geoengine_datatypes::{
@@ -56,6 +65,7 @@ use geoengine_datatypes::{
 ///     params: GdalSourceParameters {
 ///         dataset_id: "test".to_owned(),
 ///         channel: Some(1),
+///         band: None,
 ///     },
 /// });
 /// ```
@@ -63,6 +73,9 @@ use geoengine_datatypes::{
 pub struct GdalSourceParameters {
     pub dataset_id: String,
     pub channel: Option<u32>,
+    /// The name of a band of the dataset to read instead of a `channel`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub band: Option<String>,
     // TODO: add some kind of tick interval
 }
 
@@ -91,6 +104,12 @@ pub struct JsonDatasetInformation {
     pub time_format: String,
     pub base_path: PathBuf,
     pub data_type: RasterDataType,
+    /// Who may build workflows on the dataset, public if omitted
+    #[serde(default)]
+    pub access: DatasetAccess,
+    /// The bands of the dataset in the order of their channels, empty if unknown
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bands: Vec<RasterBandDescriptor>,
 }
 
 impl JsonDatasetInformationProvider {
@@ -101,6 +120,40 @@ impl JsonDatasetInformationProvider {
         &self.raster_data_root
     }
 
+    /// Look up the dataset information in the validated definitions of the `context` or
+    /// read it from the raster data root if there are none.
+    /// Fails if the dataset's access does not permit the principal of the `context`.
+    pub fn from_execution_context(id: &str, context: &ExecutionContext) -> Result<Self> {
+        let provider = match &context.dataset_definitions {
+            Some(definitions) => {
+                let definitions = definitions
+                    .read()
+                    .map_err(|_| error::Error::DatasetDefinitionsLockFailed)?;
+
+                let dataset_information = definitions
+                    .get(id)
+                    .cloned()
+                    .context(error::UnknownDataset { id })?;
+
+                JsonDatasetInformationProvider {
+                    dataset_information,
+                    raster_data_root: definitions.raster_data_root().to_path_buf(),
+                }
+            }
+            None => Self::with_dataset_id(id, &context.raster_data_root)?,
+        };
+
+        ensure!(
+            provider
+                .dataset_information
+                .access
+                .permits(&context.principal),
+            error::DatasetAccessDenied { id }
+        );
+
+        Ok(provider)
+    }
+
     pub fn write_to_file(&self, id: &str, raster_data_root: &Path) -> Result<()> {
         let mut dataset_information_path: PathBuf = PathBuf::from(raster_data_root)
             .join(Self::DEFINTION_SUBPATH)
@@ -186,8 +239,83 @@ impl TilingInformation {
             .grid_2d_to_coordinate_2d(self.global_pixel_size.as_pattern())
     }
 
+    /// The spatial extent of the raster
+    pub(crate) fn bbox(&self) -> Option<BoundingBox2D> {
+        BoundingBox2D::new_upper_left_lower_right(
+            self.upper_left_coordinate(),
+            self.lower_right_coordinate(),
+        )
+        .ok()
+    }
+
+    /// The tiling of the same extent with the pixel size of a query
+    pub(crate) fn with_query_resolution(&self, spatial_resolution: SpatialResolution) -> Self {
+        // generate a geotransform matching the query request
+        let query_scale_geo_transform = GeoTransform::new(
+            self.geo_transform.upper_left_coordinate,
+            spatial_resolution.x,
+            -spatial_resolution.y, // TODO: negative, s.t. geo transform fits...
+        );
+
+        // transform the native dataset size to the virtual size of the queried dataset
+        let query_scale_global_lower_right_pixel =
+            query_scale_geo_transform.coordinate_2d_to_grid_2d(self.lower_right_coordinate());
+
+        // build a new tiling information for the dataset with the pixel size of the query
+        TilingInformation {
+            geo_transform: query_scale_geo_transform,
+            tile_pixel_size: (600, 600).into(),
+            global_pixel_size: query_scale_global_lower_right_pixel.into(),
+        }
+    }
+
+    /// The pixel window of this (native) tiling that covers the `tile_information`, as
+    /// pixel origin, native pixel size and requested pixel size in the order of GDAL
+    pub(crate) fn read_window(
+        &self,
+        tile_information: &TileInformation,
+    ) -> ((isize, isize), (usize, usize), (usize, usize)) {
+        // transform the tile bounds to coordinates
+        let tile_geo_transform = tile_information.global_geo_transform;
+
+        let tile_upper_left_coord = tile_geo_transform.grid_2d_to_coordinate_2d(
+            tile_information
+                .global_pixel_position_upper_left()
+                .as_pattern(),
+        );
+
+        let tile_lower_right_coord = tile_geo_transform.grid_2d_to_coordinate_2d(
+            tile_information
+                .global_pixel_position_lower_right()
+                .as_pattern(),
+        );
+
+        // transform the tile bound into original pixels
+        let (.., y_pixel_position_ul, x_pixel_position_ul) = self
+            .geo_transform
+            .coordinate_2d_to_grid_2d(tile_upper_left_coord);
+
+        let (.., y_pixel_position_lr, x_pixel_position_lr) = self
+            .geo_transform
+            .coordinate_2d_to_grid_2d(tile_lower_right_coord);
+
+        let native_pixel_origin = (x_pixel_position_ul as isize, y_pixel_position_ul as isize);
+
+        let native_pixel_size = (
+            x_pixel_position_lr - x_pixel_position_ul,
+            y_pixel_position_lr - y_pixel_position_ul,
+        );
+
+        let (query_tile_size_y, query_tile_size_x) =
+            tile_information.tile_size_in_pixels().as_pattern();
+
+        let query_pixel_size = (query_tile_size_x, query_tile_size_y);
+
+        (native_pixel_origin, native_pixel_size, query_pixel_size)
+    }
+
     /// generates a vec with `TileInformation` for each tile
-    fn tile_informations(&self) -> Vec<TileInformation> {
+    pub(crate) fn tile_informations(&self) -> Vec<TileInformation> {
         let &[.., y_pixels_global, x_pixels_global] = self.global_pixel_size.dimension_size();
         let &[.., y_pixels_tile, x_pixels_tile] = self.tile_pixel_size.dimension_size();
         let x_tiles = (x_pixels_global as f32 / x_pixels_tile as f32).ceil() as usize;
@@ -230,6 +358,8 @@ where
 {
     pub dataset_information: P,
     pub gdal_params: GdalSourceParameters,
+    /// Open datasets that are shared with other queries, if `None` every tile opens its file
+    pub dataset_pool: Option<Arc<GdalDatasetPool>>,
     pub phantom_data: PhantomData<T>,
 }
 
@@ -271,10 +401,17 @@ where
         Ok(GdalSourceProcessor {
             dataset_information,
             gdal_params: params,
+            dataset_pool: None,
             phantom_data: PhantomData,
         })
     }
 
+    /// Reuse the open datasets of `dataset_pool` when loading tiles
+    pub fn with_dataset_pool(mut self, dataset_pool: Option<Arc<GdalDatasetPool>>) -> Self {
+        self.dataset_pool = dataset_pool;
+        self
+    }
+
     ///
     /// An iterator which will produce one element per time step and grid tile
     ///
@@ -283,30 +420,11 @@ where
         bbox: BoundingBox2D,
         spatial_resolution: SpatialResolution,
     ) -> impl Iterator<Item = (TimeInterval, TileInformation)> + '_ {
-        // generate a geotransform matching the query request
-        let query_scale_geo_transform = GeoTransform::new(
-            self.dataset_information
-                .native_tiling_information()
-                .geo_transform
-                .upper_left_coordinate,
-            spatial_resolution.x,
-            -spatial_resolution.y, // TODO: negative, s.t. geo transform fits...
-        );
-
-        // transform the native dataset size to the virtual size of the queried dataset
-        let query_scale_global_lower_right_pixel = query_scale_geo_transform
-            .coordinate_2d_to_grid_2d(
-                self.dataset_information
-                    .native_tiling_information()
-                    .lower_right_coordinate(),
-            );
-
-        // build a new tiling information for the dataset with the pixel size of the query
-        let query_tile_information = TilingInformation {
-            geo_transform: query_scale_geo_transform,
-            tile_pixel_size: (600, 600).into(),
-            global_pixel_size: query_scale_global_lower_right_pixel.into(),
-        };
+        let query_tile_information = self
+            .dataset_information
+            .native_tiling_information()
+            .with_query_resolution(spatial_resolution);
+        let query_partition = SpatialPartition2D::from(bbox);
 
         let time_interval_iterator = self
             .dataset_information
@@ -318,7 +436,8 @@ where
                 .tile_informations()
                 .into_iter()
                 .map(move |tile| (*time, tile))
-                .filter(move |(_, tile)| bbox.intersects_bbox(&tile.spatial_bounds()))
+                // tiles that only touch the query at their edges are not part of the result
+                .filter(move |(_, tile)| query_partition.intersects(&tile.spatial_partition()))
         })
     }
 
@@ -327,6 +446,7 @@ where
         gdal_dataset_information: P,
         time_interval: TimeInterval,
         tile_information: TileInformation,
+        dataset_pool: Option<Arc<GdalDatasetPool>>,
     ) -> Result<RasterTile2D<T>> {
         tokio::task::spawn_blocking(move || {
             Self::load_tile_data_impl(
@@ -334,6 +454,7 @@ where
                 &gdal_dataset_information,
                 time_interval,
                 tile_information,
+                dataset_pool.as_deref(),
             )
         })
         .await
@@ -350,6 +471,7 @@ where
             &self.dataset_information,
             time_interval,
             tile_information,
+            self.dataset_pool.as_deref(),
         )
     }
 
@@ -361,6 +483,7 @@ where
         gdal_dataset_information: &P,
         time_interval: TimeInterval,
         tile_information: TileInformation,
+        dataset_pool: Option<&GdalDatasetPool>,
     ) -> Result<RasterTile2D<T>> {
         // format the time interval
         let time_string = time_interval.start().as_naive_date_time().map(|t| {
@@ -379,63 +502,42 @@ where
         let path = gdal_dataset_information.dataset_path(); // TODO: add the path of the definition file for relative paths
         let data_file = path.join(file_name);
 
-        // open the dataset at path (or 'throw' an error)
-        let dataset = GdalDataset::open(&data_file)?; // TODO: investigate if we need a dataset cache
-                                                      // get the geo transform (pixel size ...) of the dataset (or 'throw' an error)
-                                                      // let gdal_geo_transform = dataset.geo_transform()?;
-                                                      // let geo_transform = GeoTransform::from(gdal_geo_transform); //TODO: clip the geotransform information / is this required at all?
-
-        // get the requested raster band of the dataset …
-        let rasterband_index = gdal_params.channel.unwrap_or(1) as isize; // TODO: investigate if this should be isize in gdal
-        let rasterband: GdalRasterBand = dataset.rasterband(rasterband_index)?;
-
-        // transform the tile bounds to coordinates
-        let tile_geo_transform = tile_information.global_geo_transform;
-
-        let tile_upper_left_coord = tile_geo_transform.grid_2d_to_coordinate_2d(
-            tile_information
-                .global_pixel_position_upper_left()
-                .as_pattern(),
-        );
-
-        let tile_lower_right_coord = tile_geo_transform.grid_2d_to_coordinate_2d(
-            tile_information
-                .global_pixel_position_lower_right()
-                .as_pattern(),
-        );
-
-        // transform the tile bound into original pixels
-        let native_geo_transform = gdal_dataset_information
+        let (native_pixel_origin, native_pixel_size, query_pixel_size) = gdal_dataset_information
             .native_tiling_information()
-            .geo_transform;
+            .read_window(&tile_information);
 
-        let (.., y_pixel_position_ul, x_pixel_position_ul) =
-            native_geo_transform.coordinate_2d_to_grid_2d(tile_upper_left_coord);
+        // get the geo transform (pixel size ...) of the dataset (or 'throw' an error)
+        // let gdal_geo_transform = dataset.geo_transform()?;
+        // let geo_transform = GeoTransform::from(gdal_geo_transform); //TODO: clip the geotransform information / is this required at all?
 
-        let (.., y_pixel_position_lr, x_pixel_position_lr) =
-            native_geo_transform.coordinate_2d_to_grid_2d(tile_lower_right_coord);
+        let rasterband_index = gdal_params.channel.unwrap_or(1) as isize; // TODO: investigate if this should be isize in gdal
 
-        let native_pixel_origin = (x_pixel_position_ul as isize, y_pixel_position_ul as isize);
+        let read_tile = || -> Result<Vec<T>> {
+            // open the dataset at path or reuse an idle handle of the pool (or 'throw' an error)
+            let dataset = GdalDatasetHandle::open(&data_file, dataset_pool)?;
 
-        let native_pixel_size = (
-            x_pixel_position_lr - x_pixel_position_ul,
-            y_pixel_position_lr - y_pixel_position_ul,
-        );
+            // get the requested raster band of the dataset …
+            let rasterband: GdalRasterBand = dataset.rasterband(rasterband_index)?;
 
-        let (query_tile_size_y, query_tile_size_x) =
-            tile_information.tile_size_in_pixels().as_pattern();
+            // read the data from the rasterband
+            let buffer = rasterband.read_as::<T>(
+                native_pixel_origin, // pixelspace origin
+                native_pixel_size,   // pixelspace size
+                query_pixel_size,    /* requested raster size */
+            )?;
 
-        let query_pixel_size = (query_tile_size_x, query_tile_size_y);
+            Ok(buffer.data)
+        };
+
+        // datasets that are read over the network are retried according to the pool's policy
+        let data = match dataset_pool {
+            Some(pool) => pool.remote_policy().read(&data_file, read_tile)?,
+            None => read_tile()?,
+        };
 
-        // read the data from the rasterband
-        let buffer = rasterband.read_as::<T>(
-            native_pixel_origin, // pixelspace origin
-            native_pixel_size,   // pixelspace size
-            query_pixel_size,    /* requested raster size */
-        )?;
         let raster_result = Raster2D::new(
             tile_information.tile_size_in_pixels,
-            buffer.data,
+            data,
             None,
             time_interval,
             tile_information.tile_geo_transform(),
@@ -463,11 +565,20 @@ where
                     self.dataset_information.clone(),
                     time,
                     tile,
+                    self.dataset_pool.clone(),
                 )
             })
-            .then(|(gdal_params, dataset_information, time, tile)| {
-                Self::load_tile_data_async(gdal_params, dataset_information, time, tile)
-            })
+            .then(
+                |(gdal_params, dataset_information, time, tile, dataset_pool)| {
+                    Self::load_tile_data_async(
+                        gdal_params,
+                        dataset_information,
+                        time,
+                        tile,
+                        dataset_pool,
+                    )
+                },
+            )
             .boxed()
     }
 }
@@ -490,6 +601,59 @@ where
 
 pub type GdalSource = SourceOperator<GdalSourceParameters>;
 
+/// The ids of the datasets that the `GdalSource` operators of the `operator` tree read,
+/// without resolving `WorkflowSource` operators
+pub fn referenced_datasets(operator: &TypedOperator) -> Vec<String> {
+    referenced_parameters(operator, "GdalSource", "dataset_id")
+}
+
+/// The `operator` tree with all of its `GdalSource` operators reading the band with the `name`
+/// instead of their channel
+pub fn select_band(operator: TypedOperator, name: &str) -> Result<TypedOperator> {
+    let mut value = serde_json::to_value(operator).context(error::SerdeJson)?;
+    select_band_of_value(&mut value, name);
+    serde_json::from_value(value).context(error::SerdeJson)
+}
+
+fn select_band_of_value(value: &mut serde_json::Value, name: &str) {
+    match value {
+        serde_json::Value::Object(object) => {
+            if object.get("type").and_then(serde_json::Value::as_str) == Some("GdalSource") {
+                if let Some(serde_json::Value::Object(params)) = object.get_mut("params") {
+                    params.remove("channel");
+                    params.insert("band".to_string(), name.into());
+                }
+            }
+
+            for value in object.values_mut() {
+                select_band_of_value(value, name);
+            }
+        }
+        serde_json::Value::Array(array) => {
+            for value in array {
+                select_band_of_value(value, name);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The id of the dataset if the `operator` is a `GdalSource` that reads its first channel,
+/// i.e., the dataset whose precomputed statistics describe the values of the operator
+pub fn statistics_dataset(operator: &dyn RasterOperator) -> Option<String> {
+    let value = serde_json::to_value(operator).ok()?;
+    if value.get("type").and_then(serde_json::Value::as_str) != Some("GdalSource") {
+        return None;
+    }
+
+    let params: GdalSourceParameters = serde_json::from_value(value.get("params")?.clone()).ok()?;
+    if params.band.is_some() || params.channel.unwrap_or(1) != 1 {
+        return None;
+    }
+
+    Some(params.dataset_id)
+}
+
 #[typetag::serde]
 impl RasterOperator for GdalSource {
     fn initialize(
@@ -500,15 +664,44 @@ impl RasterOperator for GdalSource {
             self.params.clone(),
             context,
             |params, exe_context, _, _| {
-                JsonDatasetInformationProvider::with_dataset_id(
+                let dataset_information = JsonDatasetInformationProvider::from_execution_context(
                     &params.dataset_id,
-                    &exe_context.raster_data_root,
-                )
+                    exe_context,
+                )?;
+
+                Ok(GdalSourceState {
+                    channel: selected_channel(
+                        params,
+                        &dataset_information.dataset_information.bands,
+                    )?,
+                    dataset_information,
+                    dataset_pool: exe_context.gdal_dataset_pool.clone(),
+                })
             },
             |_, _, state, _, _| {
+                // the tiles consist of the selected band only
+                let band = (state.channel.unwrap_or(1) as usize)
+                    .checked_sub(1)
+                    .and_then(|index| {
+                        state
+                            .dataset_information
+                            .dataset_information
+                            .bands
+                            .get(index)
+                    });
+
                 Ok(RasterResultDescriptor {
-                    data_type: state.data_type(),
+                    data_type: state.dataset_information.data_type(),
                     spatial_reference: SpatialReference::wgs84().into(), // TODO: lookup from dataset
+                    bbox: state.dataset_information.native_tiling_information().bbox(),
+                    time_interval: enclosing_time_interval(
+                        state
+                            .dataset_information
+                            .native_time_information()
+                            .time_intervals(),
+                    ),
+                    no_data_value: band.and_then(|band| band.no_data_value),
+                    bands: band.cloned().into_iter().collect(),
                 })
             },
             vec![],
@@ -518,65 +711,122 @@ impl RasterOperator for GdalSource {
     }
 }
 
+crate::register_operator!(Raster, GdalSource);
+
+/// The channel that the `params` select, either directly or by the name of one of the `bands`
+fn selected_channel(
+    params: &GdalSourceParameters,
+    bands: &[RasterBandDescriptor],
+) -> Result<Option<u32>> {
+    let name = match &params.band {
+        Some(name) => name,
+        None => return Ok(params.channel),
+    };
+
+    ensure!(
+        params.channel.is_none(),
+        error::InvalidOperatorParameter {
+            parameter: "band".to_string(),
+            reason: "must not be combined with `channel`".to_string(),
+        }
+    );
+
+    let index = bands
+        .iter()
+        .position(|band| &band.name == name)
+        .with_context(|| error::InvalidOperatorParameter {
+            parameter: "band".to_string(),
+            reason: format!(
+                "`{}` is not a band of dataset `{}`",
+                name, params.dataset_id
+            ),
+        })?;
+
+    Ok(Some(index as u32 + 1))
+}
+
+/// The state of an initialized `GdalSource`
+#[derive(Debug, Clone)]
+pub struct GdalSourceState {
+    /// The channel to read, which is resolved from the band name of the parameters
+    channel: Option<u32>,
+    dataset_information: JsonDatasetInformationProvider,
+    dataset_pool: Option<Arc<GdalDatasetPool>>,
+}
+
 impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
-    for InitializedOperatorImpl<
-        GdalSourceParameters,
-        RasterResultDescriptor,
-        JsonDatasetInformationProvider,
-    >
+    for InitializedOperatorImpl<GdalSourceParameters, RasterResultDescriptor, GdalSourceState>
 {
     fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        let params = GdalSourceParameters {
+            channel: self.state.channel,
+            band: None,
+            ..self.params.clone()
+        };
+
         Ok(match self.result_descriptor().data_type {
             RasterDataType::U8 => TypedRasterQueryProcessor::U8(
                 GdalSourceProcessor::from_params_with_provider(
-                    self.params.clone(),
-                    self.state.clone(),
+                    params.clone(),
+                    self.state.dataset_information.clone(),
                 )?
+                .with_dataset_pool(self.state.dataset_pool.clone())
                 .boxed(),
             ),
             RasterDataType::U16 => TypedRasterQueryProcessor::U16(
                 GdalSourceProcessor::from_params_with_provider(
-                    self.params.clone(),
-                    self.state.clone(),
+                    params.clone(),
+                    self.state.dataset_information.clone(),
                 )?
+                .with_dataset_pool(self.state.dataset_pool.clone())
                 .boxed(),
             ),
             RasterDataType::U32 => TypedRasterQueryProcessor::U32(
                 GdalSourceProcessor::from_params_with_provider(
-                    self.params.clone(),
-                    self.state.clone(),
+                    params.clone(),
+                    self.state.dataset_information.clone(),
                 )?
+                .with_dataset_pool(self.state.dataset_pool.clone())
                 .boxed(),
             ),
             RasterDataType::U64 => unimplemented!("implement U64 type"), // TypedRasterQueryProcessor::U64(self.create_processor()),
             RasterDataType::I8 => unimplemented!("I8 type is not supported"),
             RasterDataType::I16 => TypedRasterQueryProcessor::I16(
                 GdalSourceProcessor::from_params_with_provider(
-                    self.params.clone(),
-                    self.state.clone(),
+                    params.clone(),
+                    self.state.dataset_information.clone(),
                 )?
+                .with_dataset_pool(self.state.dataset_pool.clone())
                 .boxed(),
             ),
             RasterDataType::I32 => TypedRasterQueryProcessor::I32(
                 GdalSourceProcessor::from_params_with_provider(
-                    self.params.clone(),
-                    self.state.clone(),
+                    params.clone(),
+                    self.state.dataset_information.clone(),
                 )?
+                .with_dataset_pool(self.state.dataset_pool.clone())
                 .boxed(),
             ),
             RasterDataType::I64 => unimplemented!("implement I64 type"), // TypedRasterQueryProcessor::I64(self.create_processor()),
+            RasterDataType::F16 => {
+                return Err(error::Error::InvalidDatasetDefinition {
+                    reason: "the data type F16 is not supported".to_string(),
+                })
+            }
             RasterDataType::F32 => TypedRasterQueryProcessor::F32(
                 GdalSourceProcessor::from_params_with_provider(
-                    self.params.clone(),
-                    self.state.clone(),
+                    params.clone(),
+                    self.state.dataset_information.clone(),
                 )?
+                .with_dataset_pool(self.state.dataset_pool.clone())
                 .boxed(),
             ),
             RasterDataType::F64 => TypedRasterQueryProcessor::F64(
                 GdalSourceProcessor::from_params_with_provider(
-                    self.params.clone(),
-                    self.state.clone(),
+                    params.clone(),
+                    self.state.dataset_information.clone(),
                 )?
+                .with_dataset_pool(self.state.dataset_pool.clone())
                 .boxed(),
             ),
         })
@@ -706,6 +956,7 @@ mod tests {
         let gdal_params = GdalSourceParameters {
             dataset_id: "test".to_owned(),
             channel: None,
+            band: None,
         };
 
         let dataset_information = JsonDatasetInformation {
@@ -715,6 +966,8 @@ mod tests {
             tile: grid_tile_provider,
             base_path: "../modis_ndvi".into(),
             data_type: RasterDataType::U8,
+            access: DatasetAccess::Public,
+            bands: vec![],
         };
 
         let dataset_information_provider = JsonDatasetInformationProvider {
@@ -725,6 +978,7 @@ mod tests {
         let gdal_source = GdalSourceProcessor::<_, u8> {
             dataset_information: dataset_information_provider,
             gdal_params,
+            dataset_pool: None,
             phantom_data: PhantomData,
         };
 
@@ -846,6 +1100,7 @@ mod tests {
         let gdal_params = GdalSourceParameters {
             dataset_id: "test".to_owned(),
             channel: None,
+            band: None,
         };
 
         let dataset_information = JsonDatasetInformation {
@@ -855,6 +1110,8 @@ mod tests {
             tile: grid_tile_provider,
             base_path: "../modis_ndvi".into(),
             data_type: RasterDataType::U8,
+            access: DatasetAccess::Public,
+            bands: vec![],
         };
 
         let dataset_information_provider = JsonDatasetInformationProvider {
@@ -865,6 +1122,7 @@ mod tests {
         let gdal_source = GdalSourceProcessor::<_, u8> {
             dataset_information: dataset_information_provider,
             gdal_params,
+            dataset_pool: None,
             phantom_data: PhantomData,
         };
 
@@ -932,6 +1190,7 @@ mod tests {
         let gdal_params = GdalSourceParameters {
             dataset_id: "test".to_owned(),
             channel: None,
+            band: None,
         };
 
         let dataset_information = JsonDatasetInformation {
@@ -941,6 +1200,8 @@ mod tests {
             tile: grid_tile_provider,
             base_path: "../modis_ndvi".into(),
             data_type: RasterDataType::U8,
+            access: DatasetAccess::Public,
+            bands: vec![],
         };
 
         let dataset_information_provider = JsonDatasetInformationProvider {
@@ -951,6 +1212,7 @@ mod tests {
         let gdal_source = GdalSourceProcessor::<_, u8> {
             dataset_information: dataset_information_provider,
             gdal_params,
+            dataset_pool: None,
             phantom_data: PhantomData,
         };
 
@@ -997,6 +1259,7 @@ mod tests {
         let gdal_params = GdalSourceParameters {
             dataset_id: "test".to_owned(),
             channel: None,
+            band: None,
         };
 
         let dataset_information = JsonDatasetInformation {
@@ -1006,6 +1269,8 @@ mod tests {
             tile: grid_tile_provider,
             base_path: "../modis_ndvi".into(),
             data_type: RasterDataType::U8,
+            access: DatasetAccess::Public,
+            bands: vec![],
         };
 
         let dataset_information_provider = JsonDatasetInformationProvider {
@@ -1016,6 +1281,7 @@ mod tests {
         let gdal_source = GdalSourceProcessor {
             dataset_information: dataset_information_provider,
             gdal_params,
+            dataset_pool: None,
             phantom_data: PhantomData,
         };
 
@@ -1076,6 +1342,7 @@ mod tests {
         let gdal_params = GdalSourceParameters {
             dataset_id: "test".to_owned(),
             channel: None,
+            band: None,
         };
 
         let dataset_information = JsonDatasetInformation {
@@ -1085,6 +1352,8 @@ mod tests {
             tile: grid_tile_provider,
             base_path: "../modis_ndvi".into(),
             data_type: RasterDataType::U8,
+            access: DatasetAccess::Public,
+            bands: vec![],
         };
 
         let dataset_information_provider = JsonDatasetInformationProvider {
@@ -1095,6 +1364,7 @@ mod tests {
         let gdal_source = GdalSourceProcessor {
             dataset_information: dataset_information_provider,
             gdal_params,
+            dataset_pool: None,
             phantom_data: PhantomData,
         };
 
@@ -1153,6 +1423,7 @@ mod tests {
         let gdal_params = GdalSourceParameters {
             dataset_id: "test".to_owned(),
             channel: None,
+            band: None,
         };
 
         let dataset_information = JsonDatasetInformation {
@@ -1162,6 +1433,8 @@ mod tests {
             tile: grid_tile_provider,
             base_path: "../modis_ndvi".into(),
             data_type: RasterDataType::U8,
+            access: DatasetAccess::Public,
+            bands: vec![],
         };
 
         let dataset_information_provider = JsonDatasetInformationProvider {
@@ -1172,6 +1445,7 @@ mod tests {
         let gdal_source = GdalSourceProcessor::<_, u8> {
             dataset_information: dataset_information_provider,
             gdal_params,
+            dataset_pool: None,
             phantom_data: PhantomData,
         };
 
@@ -1234,6 +1508,7 @@ mod tests {
         let gdal_params = GdalSourceParameters {
             dataset_id: "test".to_owned(),
             channel: None,
+            band: None,
         };
 
         let dataset_information = JsonDatasetInformation {
@@ -1243,6 +1518,8 @@ mod tests {
             tile: grid_tile_provider,
             base_path: "../modis_ndvi".into(),
             data_type: RasterDataType::U8,
+            access: DatasetAccess::Public,
+            bands: vec![],
         };
 
         let dataset_information_provider = JsonDatasetInformationProvider {
@@ -1255,6 +1532,7 @@ mod tests {
             dataset_information_provider,
             time_interval,
             tile_information,
+            None,
         )
         .await;
         let x = x_r.expect("GDAL Error");
@@ -1267,4 +1545,95 @@ mod tests {
             .unwrap();
         assert_eq!(center_pixel, 19);
     }
+
+    #[test]
+    fn band_selection() {
+        let bands = vec![
+            RasterBandDescriptor {
+                name: "red".to_string(),
+                measurement: geoengine_datatypes::primitives::Measurement::Unitless,
+                no_data_value: None,
+            },
+            RasterBandDescriptor {
+                name: "nir".to_string(),
+                measurement: geoengine_datatypes::primitives::Measurement::Unitless,
+                no_data_value: Some(0.),
+            },
+        ];
+        let params = |channel: Option<u32>, band: Option<&str>| GdalSourceParameters {
+            dataset_id: "test".to_string(),
+            channel,
+            band: band.map(ToString::to_string),
+        };
+
+        assert_eq!(selected_channel(&params(None, None), &bands).unwrap(), None);
+        assert_eq!(
+            selected_channel(&params(Some(1), None), &bands).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            selected_channel(&params(None, Some("nir")), &bands).unwrap(),
+            Some(2)
+        );
+        assert!(selected_channel(&params(None, Some("blue")), &bands).is_err());
+        assert!(selected_channel(&params(Some(1), Some("nir")), &bands).is_err());
+
+        let operator = TypedOperator::Raster(
+            GdalSource {
+                params: params(Some(1), None),
+            }
+            .boxed(),
+        );
+        let operator = select_band(operator, "nir").unwrap();
+        assert_eq!(
+            referenced_parameters(&operator, "GdalSource", "band"),
+            vec!["nir".to_string()]
+        );
+        assert!(
+            serde_json::to_value(&operator).unwrap()["operator"]["params"]
+                .get("channel")
+                .is_none()
+        );
+
+        assert_eq!(
+            statistics_dataset(&GdalSource {
+                params: params(Some(1), None),
+            }),
+            Some("test".to_string())
+        );
+        assert_eq!(
+            statistics_dataset(&GdalSource {
+                params: params(Some(2), None)
+            }),
+            None
+        );
+        assert_eq!(
+            statistics_dataset(&GdalSource {
+                params: params(None, Some("red")),
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn datasets_of_operator() {
+        let source = |dataset_id: &str| GdalSource {
+            params: GdalSourceParameters {
+                dataset_id: dataset_id.to_string(),
+                channel: None,
+                band: None,
+            },
+        };
+
+        let operator = TypedOperator::Raster(source("test").boxed());
+        assert_eq!(referenced_datasets(&operator), vec!["test".to_string()]);
+
+        let operator =
+            TypedOperator::Raster(RasterOperator::boxed(crate::source::WorkflowSource {
+                params: crate::source::WorkflowSourceParameters {
+                    workflow: "other".to_string(),
+                },
+            }));
+        assert!(referenced_datasets(&operator).is_empty());
+    }
 }
@@ -0,0 +1,683 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+use geoengine_datatypes::collections::{
+    MultiLineStringCollection, MultiPointCollection, VectorDataType,
+};
+use geoengine_datatypes::primitives::{
+    BoundingBox2D, Coordinate2D, FeatureData, MultiLineString, MultiPoint, TimeInstance,
+    TimeInterval,
+};
+use geoengine_datatypes::spatial_reference::SpatialReference;
+
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedVectorOperator,
+    QueryContext, QueryProcessor, QueryRectangle, SourceOperator, TypedVectorQueryProcessor,
+    VectorOperator, VectorQueryProcessor, VectorResultDescriptor,
+};
+use crate::error;
+use crate::util::Result;
+
+/// Parameters for the GPS Source Operator
+///
+/// # Examples
+///
+/// ```rust
+/// use geoengine_operators::source::{GpsSource, GpsSourceParameters};
+/// use geoengine_operators::source::gps::{GpsFeatures, GpsFormat};
+///
+/// let json_string = r#"
+///     {
+///         "type": "GpsSource",
+///         "params": {
+///             "file_path": "/foo/bar.gpx",
+///             "features": "tracks"
+///         }
+///     }"#;
+///
+/// let operator: GpsSource = serde_json::from_str(json_string).unwrap();
+///
+/// assert_eq!(operator, GpsSource {
+///     params: GpsSourceParameters {
+///         file_path: "/foo/bar.gpx".into(),
+///         format: None,
+///         features: GpsFeatures::Tracks,
+///     },
+/// });
+/// ```
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct GpsSourceParameters {
+    pub file_path: PathBuf,
+    /// The format of the file, derived from its extension if unspecified
+    #[serde(default)]
+    pub format: Option<GpsFormat>,
+    pub features: GpsFeatures,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpsFormat {
+    Gpx,
+    Kml,
+}
+
+/// The features to read, since a collection has a single geometry type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpsFeatures {
+    /// GPX waypoints or KML placemarks with points as `MultiPoint`s
+    Waypoints,
+    /// GPX tracks and routes or KML line strings and tracks as `MultiLineString`s
+    Tracks,
+}
+
+/// Reads GPS field data from GPX and KML files.
+///
+/// Each feature has a `name` column. Timestamps become the time intervals of the features,
+/// an instant for waypoints and the span from the first to the last recorded position for
+/// tracks. Features without timestamps are valid for all time.
+pub type GpsSource = SourceOperator<GpsSourceParameters>;
+
+#[typetag::serde]
+impl VectorOperator for GpsSource {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedVectorOperator>> {
+        InitializedOperatorImpl::create(
+            self.params,
+            context,
+            |_, _, _, _| Ok(()),
+            |params, _, _, _, _| {
+                Ok(VectorResultDescriptor {
+                    data_type: match params.features {
+                        GpsFeatures::Waypoints => VectorDataType::MultiPoint,
+                        GpsFeatures::Tracks => VectorDataType::MultiLineString,
+                    },
+                    // GPX and KML coordinates are always WGS 84
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                })
+            },
+            vec![],
+            vec![],
+        )
+        .map(InitializedOperatorImpl::boxed)
+    }
+}
+
+crate::register_operator!(Vector, GpsSource);
+
+impl InitializedOperator<VectorResultDescriptor, TypedVectorQueryProcessor>
+    for InitializedOperatorImpl<GpsSourceParameters, VectorResultDescriptor, ()>
+{
+    fn query_processor(&self) -> Result<TypedVectorQueryProcessor> {
+        let params = self.params.clone();
+
+        Ok(match self.params.features {
+            GpsFeatures::Waypoints => {
+                TypedVectorQueryProcessor::MultiPoint(GpsWaypointProcessor { params }.boxed())
+            }
+            GpsFeatures::Tracks => {
+                TypedVectorQueryProcessor::MultiLineString(GpsTrackProcessor { params }.boxed())
+            }
+        })
+    }
+}
+
+#[derive(Debug)]
+struct GpsWaypointProcessor {
+    params: GpsSourceParameters,
+}
+
+impl QueryProcessor for GpsWaypointProcessor {
+    type Output = MultiPointCollection;
+
+    fn query(&self, query: QueryRectangle, _ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        read_matching_features(self.params.clone(), query)
+            .map(|features| {
+                let features = features?;
+                let names = features.iter().map(|f| f.name.clone()).collect();
+                let time_intervals = features.iter().map(|f| f.time_interval).collect();
+                let points = features
+                    .into_iter()
+                    .map(|f| MultiPoint::new(f.lines.into_iter().flatten().collect()))
+                    .collect::<geoengine_datatypes::util::Result<_>>()?;
+
+                Ok(MultiPointCollection::from_data(
+                    points,
+                    time_intervals,
+                    name_column(names),
+                )?)
+            })
+            .boxed()
+    }
+}
+
+#[derive(Debug)]
+struct GpsTrackProcessor {
+    params: GpsSourceParameters,
+}
+
+impl QueryProcessor for GpsTrackProcessor {
+    type Output = MultiLineStringCollection;
+
+    fn query(&self, query: QueryRectangle, _ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        read_matching_features(self.params.clone(), query)
+            .map(|features| {
+                let features = features?;
+                let names = features.iter().map(|f| f.name.clone()).collect();
+                let time_intervals = features.iter().map(|f| f.time_interval).collect();
+                let lines = features
+                    .into_iter()
+                    .map(|f| MultiLineString::new(f.lines))
+                    .collect::<geoengine_datatypes::util::Result<_>>()?;
+
+                Ok(MultiLineStringCollection::from_data(
+                    lines,
+                    time_intervals,
+                    name_column(names),
+                )?)
+            })
+            .boxed()
+    }
+}
+
+/// Read the file on a blocking thread and keep the features that match the `query`.
+/// GPS files are small, so all features are emitted as one collection.
+fn read_matching_features(
+    params: GpsSourceParameters,
+    query: QueryRectangle,
+) -> BoxStream<'static, Result<Vec<GpsFeature>>> {
+    stream::once(async move {
+        let features = tokio::task::spawn_blocking(move || read_features(&params))
+            .await
+            .context(error::TokioJoin)??;
+
+        Ok(features
+            .into_iter()
+            .filter(|feature| feature.matches(&query))
+            .collect())
+    })
+    .boxed()
+}
+
+fn name_column(names: Vec<String>) -> HashMap<String, FeatureData> {
+    let mut data = HashMap::with_capacity(1);
+    data.insert("name".to_string(), FeatureData::Text(names));
+    data
+}
+
+/// A waypoint (with a single line of one coordinate) or a track (with a line per segment)
+#[derive(Debug, Clone, PartialEq)]
+struct GpsFeature {
+    name: String,
+    lines: Vec<Vec<Coordinate2D>>,
+    time_interval: TimeInterval,
+}
+
+impl GpsFeature {
+    fn new(name: String, lines: Vec<Vec<Coordinate2D>>, times: &[TimeInstance]) -> Self {
+        let time_interval = match (times.iter().min(), times.iter().max()) {
+            (Some(&start), Some(&end)) => TimeInterval::new_unchecked(start, end),
+            _ => TimeInterval::default(),
+        };
+
+        Self {
+            name,
+            lines,
+            time_interval,
+        }
+    }
+
+    fn matches(&self, query: &QueryRectangle) -> bool {
+        let time_matches = query.time_interval.intersects(&self.time_interval)
+            || query.time_interval.contains(&self.time_interval);
+
+        time_matches
+            && BoundingBox2D::from_coordinates(self.lines.iter().flatten())
+                .map_or(false, |bbox| query.bbox.intersects_bbox(&bbox))
+    }
+}
+
+fn read_features(params: &GpsSourceParameters) -> Result<Vec<GpsFeature>> {
+    let format = match params.format {
+        Some(format) => format,
+        None => match params
+            .file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("gpx") => GpsFormat::Gpx,
+            Some("kml") => GpsFormat::Kml,
+            _ => {
+                return Err(error::Error::GpsSource {
+                    details: "Cannot derive the format from the file extension".to_string(),
+                })
+            }
+        },
+    };
+
+    let text = std::fs::read_to_string(&params.file_path).context(error::IO)?;
+    let document = Document::parse(&text).map_err(|e| error::Error::GpsSource {
+        details: e.to_string(),
+    })?;
+
+    match format {
+        GpsFormat::Gpx => gpx_features(&document, params.features),
+        GpsFormat::Kml => kml_features(&document, params.features),
+    }
+}
+
+fn gpx_features(document: &Document, features: GpsFeatures) -> Result<Vec<GpsFeature>> {
+    let mut result = Vec::new();
+
+    for node in document.root_element().children() {
+        match (features, node.tag_name().name()) {
+            (GpsFeatures::Waypoints, "wpt") => {
+                let (coordinate, time) = gpx_point(node)?;
+                result.push(GpsFeature::new(
+                    child_text(node, "name"),
+                    vec![vec![coordinate]],
+                    &time.into_iter().collect::<Vec<_>>(),
+                ));
+            }
+            (GpsFeatures::Tracks, "trk") | (GpsFeatures::Tracks, "rte") => {
+                let mut lines = Vec::new();
+                let mut times = Vec::new();
+
+                // routes have their points directly, tracks in segments
+                let segments: Vec<Node> = if node.has_tag_name("rte") {
+                    vec![node]
+                } else {
+                    elements(node, "trkseg").collect()
+                };
+
+                for segment in segments {
+                    let mut line = Vec::new();
+                    for point in segment
+                        .children()
+                        .filter(|n| n.has_tag_name("trkpt") || n.has_tag_name("rtept"))
+                    {
+                        let (coordinate, time) = gpx_point(point)?;
+                        line.push(coordinate);
+                        times.extend(time);
+                    }
+                    if line.len() >= 2 {
+                        lines.push(line);
+                    }
+                }
+
+                if !lines.is_empty() {
+                    result.push(GpsFeature::new(child_text(node, "name"), lines, &times));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(result)
+}
+
+/// The coordinate and timestamp of a `wpt`, `trkpt` or `rtept`
+fn gpx_point(node: Node) -> Result<(Coordinate2D, Option<TimeInstance>)> {
+    let attribute = |name: &str| -> Result<f64> {
+        node.attribute(name)
+            .and_then(|value| value.trim().parse().ok())
+            .ok_or_else(|| error::Error::GpsSource {
+                details: format!("Missing or invalid `{}` of a GPX point", name),
+            })
+    };
+
+    let coordinate = Coordinate2D::new(attribute("lon")?, attribute("lat")?);
+    let time = elements(node, "time")
+        .next()
+        .and_then(|time| time.text())
+        .map(parse_time)
+        .transpose()?;
+
+    Ok((coordinate, time))
+}
+
+fn kml_features(document: &Document, features: GpsFeatures) -> Result<Vec<GpsFeature>> {
+    let mut result = Vec::new();
+
+    for placemark in document
+        .descendants()
+        .filter(|n| n.has_tag_name("Placemark"))
+    {
+        let mut lines = Vec::new();
+        let mut times = kml_times(placemark)?;
+
+        for geometry in placemark.descendants() {
+            match (features, geometry.tag_name().name()) {
+                (GpsFeatures::Waypoints, "Point") => {
+                    lines.push(kml_coordinates(geometry)?);
+                }
+                (GpsFeatures::Tracks, "LineString") => {
+                    lines.push(kml_coordinates(geometry)?);
+                }
+                (GpsFeatures::Tracks, "Track") => {
+                    let mut line = Vec::new();
+                    for coord in elements(geometry, "coord") {
+                        line.push(parse_coordinate(
+                            coord.text().unwrap_or_default().split_whitespace(),
+                        )?);
+                    }
+                    for when in elements(geometry, "when") {
+                        times.push(parse_time(when.text().unwrap_or_default())?);
+                    }
+                    lines.push(line);
+                }
+                _ => {}
+            }
+        }
+
+        match features {
+            GpsFeatures::Waypoints => lines.retain(|line| !line.is_empty()),
+            GpsFeatures::Tracks => lines.retain(|line| line.len() >= 2),
+        }
+
+        if !lines.is_empty() {
+            result.push(GpsFeature::new(
+                child_text(placemark, "name"),
+                lines,
+                &times,
+            ));
+        }
+    }
+
+    Ok(result)
+}
+
+/// The `TimeStamp` or `TimeSpan` of a placemark
+fn kml_times(placemark: Node) -> Result<Vec<TimeInstance>> {
+    let mut times = Vec::new();
+
+    for primitive in placemark
+        .children()
+        .filter(|n| n.has_tag_name("TimeStamp") || n.has_tag_name("TimeSpan"))
+    {
+        for instant in primitive
+            .children()
+            .filter(|n| n.has_tag_name("when") || n.has_tag_name("begin") || n.has_tag_name("end"))
+        {
+            times.push(parse_time(instant.text().unwrap_or_default())?);
+        }
+    }
+
+    Ok(times)
+}
+
+/// The whitespace-separated `lon,lat[,alt]` tuples of a `coordinates` element
+fn kml_coordinates(geometry: Node) -> Result<Vec<Coordinate2D>> {
+    elements(geometry, "coordinates")
+        .next()
+        .and_then(|coordinates| coordinates.text())
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|tuple| parse_coordinate(tuple.split(',')))
+        .collect()
+}
+
+fn parse_coordinate<'s>(mut values: impl Iterator<Item = &'s str>) -> Result<Coordinate2D> {
+    let mut next = || -> Result<f64> {
+        values
+            .next()
+            .and_then(|value| value.trim().parse().ok())
+            .ok_or_else(|| error::Error::GpsSource {
+                details: "Invalid KML coordinate".to_string(),
+            })
+    };
+
+    Ok(Coordinate2D::new(next()?, next()?))
+}
+
+/// Parse an RFC 3339 timestamp or a date, which KML allows as well
+fn parse_time(text: &str) -> Result<TimeInstance> {
+    let text = text.trim();
+
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(date_time.with_timezone(&Utc).into());
+    }
+
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map(|date| date.and_hms(0, 0, 0).into())
+        .map_err(|_| error::Error::GpsSource {
+            details: format!("Invalid timestamp `{}`", text),
+        })
+}
+
+fn elements<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |n| n.has_tag_name(name))
+}
+
+fn child_text(node: Node, name: &str) -> String {
+    elements(node, name)
+        .next()
+        .and_then(|child| child.text())
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geoengine_datatypes::collections::IntoGeometryIterator;
+    use geoengine_datatypes::primitives::{MultiLineStringAccess, SpatialResolution};
+    use std::io::Write;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <wpt lat="50.81" lon="8.77">
+    <name>Plot A</name>
+    <time>2020-06-01T08:00:00Z</time>
+  </wpt>
+  <wpt lat="50.82" lon="8.78">
+    <name>Plot B</name>
+  </wpt>
+  <trk>
+    <name>Transect</name>
+    <trkseg>
+      <trkpt lat="50.80" lon="8.76"><time>2020-06-01T09:00:00Z</time></trkpt>
+      <trkpt lat="50.81" lon="8.77"><time>2020-06-01T09:10:00Z</time></trkpt>
+      <trkpt lat="50.82" lon="8.78"><time>2020-06-01T09:20:00Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    const KML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">
+  <Document>
+    <Placemark>
+      <name>Plot A</name>
+      <TimeStamp><when>2020-06-01</when></TimeStamp>
+      <Point><coordinates>8.77,50.81,200</coordinates></Point>
+    </Placemark>
+    <Placemark>
+      <name>Transect</name>
+      <gx:Track>
+        <when>2020-06-01T09:00:00Z</when>
+        <when>2020-06-01T09:20:00Z</when>
+        <gx:coord>8.76 50.80 200</gx:coord>
+        <gx:coord>8.78 50.82 210</gx:coord>
+      </gx:Track>
+    </Placemark>
+  </Document>
+</kml>"#;
+
+    fn file(content: &str, extension: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(extension)
+            .tempfile()
+            .unwrap();
+        write!(file, "{}", content).unwrap();
+        file
+    }
+
+    fn query(time_interval: TimeInterval) -> QueryRectangle {
+        QueryRectangle {
+            bbox: BoundingBox2D::new((8., 50.).into(), (9., 51.).into()).unwrap(),
+            time_interval,
+            spatial_resolution: SpatialResolution::zero_point_one(),
+        }
+    }
+
+    fn context() -> QueryContext {
+        QueryContext {
+            chunk_byte_size: 1024,
+            timeout: None,
+            seed: 0,
+        }
+    }
+
+    fn instant(text: &str) -> TimeInstance {
+        parse_time(text).unwrap()
+    }
+
+    async fn query_collection<P, C>(processor: P, time_interval: TimeInterval) -> C
+    where
+        P: QueryProcessor<Output = C>,
+    {
+        let mut collections: Vec<_> =
+            QueryProcessor::query(&processor, query(time_interval), context())
+                .map(Result::unwrap)
+                .collect()
+                .await;
+
+        assert_eq!(collections.len(), 1);
+        collections.remove(0)
+    }
+
+    #[tokio::test]
+    async fn gpx_waypoints() {
+        let file = file(GPX, ".gpx");
+
+        let collection = query_collection(
+            GpsWaypointProcessor {
+                params: GpsSourceParameters {
+                    file_path: file.path().into(),
+                    format: None,
+                    features: GpsFeatures::Waypoints,
+                },
+            },
+            TimeInterval::default(),
+        )
+        .await;
+
+        assert_eq!(collection.len(), 2);
+        assert_eq!(
+            collection.time_intervals()[0],
+            TimeInterval::new_unchecked(
+                instant("2020-06-01T08:00:00Z"),
+                instant("2020-06-01T08:00:00Z")
+            )
+        );
+        assert_eq!(collection.time_intervals()[1], TimeInterval::default());
+    }
+
+    #[tokio::test]
+    async fn gpx_tracks_filtered_by_time() {
+        let file = file(GPX, ".gpx");
+        let params = GpsSourceParameters {
+            file_path: file.path().into(),
+            format: Some(GpsFormat::Gpx),
+            features: GpsFeatures::Tracks,
+        };
+
+        let collection = query_collection(
+            GpsTrackProcessor {
+                params: params.clone(),
+            },
+            TimeInterval::default(),
+        )
+        .await;
+
+        assert_eq!(collection.len(), 1);
+        assert_eq!(
+            collection.time_intervals()[0],
+            TimeInterval::new_unchecked(
+                instant("2020-06-01T09:00:00Z"),
+                instant("2020-06-01T09:20:00Z")
+            )
+        );
+        assert_eq!(collection.geometries().next().unwrap().lines()[0].len(), 3);
+
+        let collection = query_collection(
+            GpsTrackProcessor { params },
+            TimeInterval::new_unchecked(instant("2020-06-02"), instant("2020-06-03")),
+        )
+        .await;
+
+        assert_eq!(collection.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn kml_placemarks() {
+        let file = file(KML, ".kml");
+
+        let points = query_collection(
+            GpsWaypointProcessor {
+                params: GpsSourceParameters {
+                    file_path: file.path().into(),
+                    format: None,
+                    features: GpsFeatures::Waypoints,
+                },
+            },
+            TimeInterval::default(),
+        )
+        .await;
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(
+            points.time_intervals()[0],
+            TimeInterval::new_unchecked(instant("2020-06-01"), instant("2020-06-01"))
+        );
+
+        let tracks = query_collection(
+            GpsTrackProcessor {
+                params: GpsSourceParameters {
+                    file_path: file.path().into(),
+                    format: None,
+                    features: GpsFeatures::Tracks,
+                },
+            },
+            TimeInterval::default(),
+        )
+        .await;
+
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(
+            tracks.geometries().next().unwrap().lines()[0],
+            &[
+                Coordinate2D::new(8.76, 50.80),
+                Coordinate2D::new(8.78, 50.82)
+            ][..]
+        );
+    }
+
+    #[test]
+    fn unknown_extension() {
+        let file = file(GPX, ".xml");
+
+        assert!(read_features(&GpsSourceParameters {
+            file_path: file.path().into(),
+            format: None,
+            features: GpsFeatures::Waypoints,
+        })
+        .is_err());
+    }
+}
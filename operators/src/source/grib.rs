@@ -0,0 +1,440 @@
+use std::path::{Component, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeZone, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
+use gdal::metadata::Metadata;
+use gdal::raster::dataset::Dataset as GdalDataset;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+
+use geoengine_datatypes::primitives::{SpatialBounded, TimeInstance, TimeInterval};
+use geoengine_datatypes::raster::{GeoTransform, Raster2D, RasterDataType, RasterTile2D};
+use geoengine_datatypes::spatial_reference::SpatialReference;
+
+use super::gdal_dataset_pool::{GdalDatasetHandle, GdalDatasetPool};
+use super::gdal_source::TilingInformation;
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedRasterOperator,
+    QueryContext, QueryProcessor, QueryRectangle, RasterOperator, RasterQueryProcessor,
+    RasterResultDescriptor, SourceOperator, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::Result;
+
+/// Parameters for the GRIB Source Operator
+///
+/// # Examples
+///
+/// ```rust
+/// use geoengine_operators::source::{GribSource, GribSourceParameters};
+///
+/// let json_string = r#"
+///     {
+///         "type": "GribSource",
+///         "params": {
+///             "file": "weather/icon-eu.grib2",
+///             "parameter": "TMP",
+///             "level": "2-HTGL"
+///         }
+///     }"#;
+///
+/// let operator: GribSource = serde_json::from_str(json_string).unwrap();
+///
+/// assert_eq!(operator, GribSource {
+///     params: GribSourceParameters {
+///         file: "weather/icon-eu.grib2".into(),
+///         parameter: "TMP".into(),
+///         level: Some("2-HTGL".into()),
+///         reference_time: None,
+///     },
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GribSourceParameters {
+    /// The GRIB file, relative to the raster data root
+    pub file: PathBuf,
+    /// The parameter of the messages, i.e., their `GRIB_ELEMENT`, e.g., `TMP`
+    pub parameter: String,
+    /// The level of the messages, i.e., their `GRIB_SHORT_NAME`, e.g., `2-HTGL`,
+    /// if there is more than one
+    #[serde(default)]
+    pub level: Option<String>,
+    /// The forecast reference time of the model run, the latest run of the file if unspecified
+    #[serde(default)]
+    pub reference_time: Option<TimeInstance>,
+}
+
+/// Reads the forecast steps of one parameter and level of a GRIB file, e.g., DWD or NOAA model
+/// output, with GDAL's GRIB driver.
+///
+/// The messages are selected by their band metadata. Each forecast step is valid from its valid
+/// time until the valid time of the next step, and the last step for as long as the step before.
+/// The grid must be a regular latitude/longitude grid.
+pub type GribSource = SourceOperator<GribSourceParameters>;
+
+#[typetag::serde]
+impl RasterOperator for GribSource {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        InitializedOperatorImpl::create(
+            self.params,
+            context,
+            |params, context, _, _| GribFile::open(params, context).map(Arc::new),
+            |_, _, file, _, _| {
+                Ok(RasterResultDescriptor {
+                    // GDAL decodes all GRIB messages to doubles
+                    data_type: RasterDataType::F64,
+                    spatial_reference: SpatialReference::wgs84().into(),
+                    bbox: None,
+                    time_interval: None,
+                    no_data_value: file.no_data_value,
+                    bands: vec![],
+                })
+            },
+            vec![],
+            vec![],
+        )
+        .map(InitializedOperatorImpl::boxed)
+    }
+}
+
+crate::register_operator!(Raster, GribSource);
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedOperatorImpl<GribSourceParameters, RasterResultDescriptor, Arc<GribFile>>
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        Ok(TypedRasterQueryProcessor::F64(
+            GribSourceProcessor {
+                file: self.state.clone(),
+            }
+            .boxed(),
+        ))
+    }
+}
+
+/// The metadata of a GRIB message, i.e., a band of the GDAL dataset
+#[derive(Debug, Clone, PartialEq)]
+struct GribMessage {
+    band: isize,
+    parameter: String,
+    level: String,
+    reference_time: TimeInstance,
+    valid_time: TimeInstance,
+}
+
+/// An opened GRIB file with the selected messages
+#[derive(Debug)]
+pub struct GribFile {
+    path: PathBuf,
+    tiling: TilingInformation,
+    /// the band of each forecast step
+    steps: Vec<(isize, TimeInterval)>,
+    no_data_value: Option<f64>,
+    dataset_pool: Option<Arc<GdalDatasetPool>>,
+}
+
+impl GribFile {
+    fn open(params: &GribSourceParameters, context: &ExecutionContext) -> Result<Self> {
+        ensure!(
+            params
+                .file
+                .components()
+                .all(|component| matches!(component, Component::Normal(_))),
+            error::InvalidOperatorParameter {
+                parameter: "file",
+                reason: "must be a path inside the raster data root",
+            }
+        );
+
+        let path = context.raster_data_root.join(&params.file);
+        let dataset = GdalDataset::open(&path)?;
+
+        let mut messages = Vec::new();
+        for band in 1..=dataset.count() {
+            messages.push(grib_message(&dataset, band)?);
+        }
+
+        let steps = select_steps(messages, params)?;
+
+        let (width, height) = dataset.size();
+        let tiling = TilingInformation {
+            global_pixel_size: (height, width).into(),
+            tile_pixel_size: (600, 600).into(),
+            geo_transform: GeoTransform::from(dataset.geo_transform()?),
+        };
+        let no_data_value = dataset.rasterband(steps[0].0)?.no_data_value();
+
+        Ok(Self {
+            path,
+            tiling,
+            steps,
+            no_data_value,
+            dataset_pool: context.gdal_dataset_pool.clone(),
+        })
+    }
+
+    fn read_tile(
+        &self,
+        band: isize,
+        time_interval: TimeInterval,
+        tile_information: geoengine_datatypes::raster::TileInformation,
+    ) -> Result<RasterTile2D<f64>> {
+        let (native_pixel_origin, native_pixel_size, query_pixel_size) =
+            self.tiling.read_window(&tile_information);
+
+        let dataset = GdalDatasetHandle::open(&self.path, self.dataset_pool.as_deref())?;
+        let buffer = dataset.rasterband(band)?.read_as::<f64>(
+            native_pixel_origin,
+            native_pixel_size,
+            query_pixel_size,
+        )?;
+
+        let raster = Raster2D::new(
+            tile_information.tile_size_in_pixels,
+            buffer.data,
+            self.no_data_value,
+            time_interval,
+            tile_information.tile_geo_transform(),
+        )?;
+
+        Ok(RasterTile2D::new(time_interval, tile_information, raster))
+    }
+}
+
+fn grib_message(dataset: &GdalDataset, band: isize) -> Result<GribMessage> {
+    let rasterband = dataset.rasterband(band)?;
+    let item = |key: &str| -> Result<String> {
+        rasterband
+            .metadata_item(key, "")
+            .ok_or_else(|| error::Error::GribSource {
+                details: format!("Band {} has no `{}`", band, key),
+            })
+    };
+
+    Ok(GribMessage {
+        band,
+        parameter: item("GRIB_ELEMENT")?,
+        level: item("GRIB_SHORT_NAME")?,
+        reference_time: parse_grib_time(&item("GRIB_REF_TIME")?)?,
+        valid_time: parse_grib_time(&item("GRIB_VALID_TIME")?)?,
+    })
+}
+
+/// Parse a GRIB time of GDAL, which is either `1589371200 sec UTC` or, for newer GDAL versions,
+/// an ISO 8601 date time
+fn parse_grib_time(text: &str) -> Result<TimeInstance> {
+    let text = text.trim();
+
+    if let Some(seconds) = text
+        .split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.parse::<i64>().ok())
+    {
+        return Ok(Utc.timestamp(seconds, 0).into());
+    }
+
+    DateTime::parse_from_rfc3339(text)
+        .map(|date_time| date_time.with_timezone(&Utc).into())
+        .map_err(|_| error::Error::GribSource {
+            details: format!("Invalid GRIB time `{}`", text),
+        })
+}
+
+/// Select the messages of the parameter, level and model run and derive the validity of each
+/// forecast step
+fn select_steps(
+    messages: Vec<GribMessage>,
+    params: &GribSourceParameters,
+) -> Result<Vec<(isize, TimeInterval)>> {
+    let mut messages: Vec<GribMessage> = messages
+        .into_iter()
+        .filter(|message| message.parameter == params.parameter)
+        .collect();
+
+    let mut levels: Vec<&str> = messages.iter().map(|m| m.level.as_str()).collect();
+    levels.sort_unstable();
+    levels.dedup();
+
+    let level = match (&params.level, levels.as_slice()) {
+        (Some(level), _) => level.clone(),
+        (None, [level]) => (*level).to_string(),
+        (None, []) => {
+            return Err(error::Error::GribSource {
+                details: format!("The file has no messages of `{}`", params.parameter),
+            })
+        }
+        (None, levels) => {
+            return Err(error::Error::InvalidOperatorParameter {
+                parameter: "level".to_string(),
+                reason: format!("must be one of {}", levels.join(", ")),
+            })
+        }
+    };
+    messages.retain(|message| message.level == level);
+
+    let reference_time = match params.reference_time {
+        Some(reference_time) => reference_time,
+        None => messages
+            .iter()
+            .map(|message| message.reference_time)
+            .max()
+            .ok_or_else(|| error::Error::GribSource {
+                details: format!(
+                    "The file has no messages of `{}` at `{}`",
+                    params.parameter, level
+                ),
+            })?,
+    };
+    messages.retain(|message| message.reference_time == reference_time);
+
+    ensure!(
+        !messages.is_empty(),
+        error::GribSource {
+            details: format!("The file has no run at {}", reference_time.as_rfc3339())
+        }
+    );
+
+    messages.sort_by_key(|message| message.valid_time);
+    messages.dedup_by_key(|message| message.valid_time);
+
+    let valid_times: Vec<i64> = messages.iter().map(|m| m.valid_time.inner()).collect();
+
+    Ok(messages
+        .iter()
+        .enumerate()
+        .map(|(i, message)| {
+            let start = valid_times[i];
+            let end = match (valid_times.get(i + 1), i.checked_sub(1)) {
+                (Some(&next), _) => next,
+                (None, Some(previous)) => start + (start - valid_times[previous]),
+                (None, None) => start,
+            };
+            (message.band, TimeInterval::new_unchecked(start, end))
+        })
+        .collect())
+}
+
+#[derive(Debug)]
+struct GribSourceProcessor {
+    file: Arc<GribFile>,
+}
+
+impl QueryProcessor for GribSourceProcessor {
+    type Output = RasterTile2D<f64>;
+
+    fn query(&self, query: QueryRectangle, _ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        let tiles: Vec<_> = self
+            .file
+            .tiling
+            .with_query_resolution(query.spatial_resolution)
+            .tile_informations()
+            .into_iter()
+            .filter(|tile| query.bbox.intersects_bbox(&tile.spatial_bounds()))
+            .collect();
+
+        let requests: Vec<_> = self
+            .file
+            .steps
+            .iter()
+            .filter(|(_, time)| {
+                query.time_interval.intersects(time) || time.contains(&query.time_interval)
+            })
+            .flat_map(|&(band, time)| tiles.iter().map(move |&tile| (band, time, tile)))
+            .collect();
+
+        let file = self.file.clone();
+        stream::iter(requests)
+            .then(move |(band, time, tile)| {
+                let file = file.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || file.read_tile(band, time, tile))
+                        .await
+                        .context(error::TokioJoin)?
+                }
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(band: isize, level: &str, reference_hour: i64, valid_hour: i64) -> GribMessage {
+        GribMessage {
+            band,
+            parameter: "TMP".to_string(),
+            level: level.to_string(),
+            reference_time: TimeInstance::from_millis(reference_hour * 3_600_000),
+            valid_time: TimeInstance::from_millis(valid_hour * 3_600_000),
+        }
+    }
+
+    fn params(level: Option<&str>, reference_hour: Option<i64>) -> GribSourceParameters {
+        GribSourceParameters {
+            file: "forecast.grib2".into(),
+            parameter: "TMP".to_string(),
+            level: level.map(ToString::to_string),
+            reference_time: reference_hour.map(|hour| TimeInstance::from_millis(hour * 3_600_000)),
+        }
+    }
+
+    fn hours(start: i64, end: i64) -> TimeInterval {
+        TimeInterval::new_unchecked(start * 3_600_000, end * 3_600_000)
+    }
+
+    #[test]
+    fn latest_run_by_valid_time() {
+        let messages = vec![
+            message(1, "2-HTGL", 0, 0),
+            message(2, "2-HTGL", 0, 3),
+            message(3, "2-HTGL", 6, 9),
+            message(4, "2-HTGL", 6, 6),
+            message(5, "2-HTGL", 6, 12),
+        ];
+
+        assert_eq!(
+            select_steps(messages.clone(), &params(None, None)).unwrap(),
+            vec![(4, hours(6, 9)), (3, hours(9, 12)), (5, hours(12, 15))]
+        );
+        assert_eq!(
+            select_steps(messages, &params(None, Some(0))).unwrap(),
+            vec![(1, hours(0, 3)), (2, hours(3, 6))]
+        );
+    }
+
+    #[test]
+    fn level_selection() {
+        let messages = vec![message(1, "2-HTGL", 0, 0), message(2, "850-ISBL", 0, 0)];
+
+        assert!(select_steps(messages.clone(), &params(None, None)).is_err());
+        assert_eq!(
+            select_steps(messages, &params(Some("850-ISBL"), None)).unwrap(),
+            vec![(2, hours(0, 0))]
+        );
+    }
+
+    #[test]
+    fn unknown_run() {
+        let messages = vec![message(1, "2-HTGL", 0, 0)];
+
+        assert!(select_steps(messages, &params(None, Some(6))).is_err());
+    }
+
+    #[test]
+    fn grib_times() {
+        assert_eq!(
+            parse_grib_time("  1589371200 sec UTC").unwrap(),
+            TimeInstance::from_millis(1_589_371_200_000)
+        );
+        assert_eq!(
+            parse_grib_time("2020-05-13T12:00:00Z").unwrap(),
+            TimeInstance::from_millis(1_589_371_200_000)
+        );
+        assert!(parse_grib_time("yesterday").is_err());
+    }
+}
@@ -1,5 +1,33 @@
 pub mod csv;
+pub mod csv_data;
+pub mod dataset_definitions;
+pub mod dataset_statistics;
+pub mod gbif;
+pub mod gdal_dataset_pool;
 pub mod gdal_source;
+pub mod gps;
+pub mod grib;
+pub mod pinned;
+pub mod remote_policy;
+pub mod workflow_source;
+pub mod zarr;
 
 pub use self::csv::{CsvSource, CsvSourceParameters, CsvSourceStream};
-pub use self::gdal_source::{GdalSource, GdalSourceParameters};
+pub use self::csv_data::{CsvDataSource, CsvDataSourceParameters};
+pub use self::dataset_definitions::{DatasetAccess, DatasetDefinitions, ReloadReport};
+pub use self::dataset_statistics::{DatasetStatistics, RasterDatasetStatistics};
+pub use self::gbif::{GbifSource, GbifSourceParameters};
+pub use self::gdal_dataset_pool::GdalDatasetPool;
+pub use self::gdal_source::{
+    referenced_datasets, select_band, statistics_dataset, GdalSource, GdalSourceParameters,
+};
+pub use self::gps::{GpsSource, GpsSourceParameters};
+pub use self::grib::{GribSource, GribSourceParameters};
+pub use self::pinned::{
+    PinnedCollection, PinnedDataset, PinnedDatasets, PinnedSource, PinnedSourceParameters,
+};
+pub use self::remote_policy::RemoteSourcePolicy;
+pub use self::workflow_source::{
+    referenced_workflows, referenced_workflows_of_json, WorkflowSource, WorkflowSourceParameters,
+};
+pub use self::zarr::{ZarrSource, ZarrSourceParameters};
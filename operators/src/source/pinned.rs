@@ -0,0 +1,478 @@
+use crate::engine::{
+    ExecutionContext, InitializedOperator, InitializedOperatorImpl, InitializedVectorOperator,
+    QueryContext, QueryProcessor, QueryRectangle, SourceOperator, TypedVectorQueryProcessor,
+    VectorOperator, VectorQueryProcessor, VectorResultDescriptor,
+};
+use crate::error;
+use crate::util::Result;
+use futures::stream::{self, BoxStream, StreamExt};
+use futures::{future, TryStreamExt};
+use geoengine_datatypes::collections::{
+    DataCollection, FeatureCollection, MultiLineStringCollection, MultiPointCollection,
+    MultiPolygonCollection, SpatiallyIndexed, VectorDataType,
+};
+use geoengine_datatypes::primitives::{BoundingBox2D, Geometry};
+use geoengine_datatypes::util::arrow::ArrowTyped;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, OptionExt};
+use std::collections::HashMap;
+
+/// The features of a vector dataset, kept in memory as Arrow arrays
+#[derive(Debug, Clone)]
+pub enum PinnedCollection {
+    Data(DataCollection),
+    MultiPoint(MultiPointCollection),
+    MultiLineString(MultiLineStringCollection),
+    MultiPolygon(MultiPolygonCollection),
+}
+
+impl PinnedCollection {
+    /// Collect all features that the `processor` outputs for the `query` into one collection
+    pub async fn load(
+        processor: TypedVectorQueryProcessor,
+        query: QueryRectangle,
+        ctx: QueryContext,
+    ) -> Result<Self> {
+        Ok(match processor {
+            TypedVectorQueryProcessor::Data(p) => {
+                PinnedCollection::Data(collect(p.as_ref(), query, ctx).await?)
+            }
+            TypedVectorQueryProcessor::MultiPoint(p) => {
+                PinnedCollection::MultiPoint(collect(p.as_ref(), query, ctx).await?)
+            }
+            TypedVectorQueryProcessor::MultiLineString(p) => {
+                PinnedCollection::MultiLineString(collect(p.as_ref(), query, ctx).await?)
+            }
+            TypedVectorQueryProcessor::MultiPolygon(p) => {
+                PinnedCollection::MultiPolygon(collect(p.as_ref(), query, ctx).await?)
+            }
+        })
+    }
+
+    pub fn data_type(&self) -> VectorDataType {
+        match self {
+            PinnedCollection::Data(_) => VectorDataType::Data,
+            PinnedCollection::MultiPoint(_) => VectorDataType::MultiPoint,
+            PinnedCollection::MultiLineString(_) => VectorDataType::MultiLineString,
+            PinnedCollection::MultiPolygon(_) => VectorDataType::MultiPolygon,
+        }
+    }
+
+    /// The number of features
+    pub fn len(&self) -> usize {
+        match self {
+            PinnedCollection::Data(collection) => collection.len(),
+            PinnedCollection::MultiPoint(collection) => collection.len(),
+            PinnedCollection::MultiLineString(collection) => collection.len(),
+            PinnedCollection::MultiPolygon(collection) => collection.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The memory that the Arrow arrays of the features occupy
+    pub fn byte_size(&self) -> usize {
+        match self {
+            PinnedCollection::Data(collection) => collection.byte_size(),
+            PinnedCollection::MultiPoint(collection) => collection.byte_size(),
+            PinnedCollection::MultiLineString(collection) => collection.byte_size(),
+            PinnedCollection::MultiPolygon(collection) => collection.byte_size(),
+        }
+    }
+}
+
+/// Append the chunks of a vector query into one collection
+async fn collect<G>(
+    processor: &dyn VectorQueryProcessor<VectorType = FeatureCollection<G>>,
+    query: QueryRectangle,
+    ctx: QueryContext,
+) -> Result<FeatureCollection<G>>
+where
+    G: Geometry + ArrowTyped,
+{
+    let collected = processor
+        .vector_query(query, ctx)
+        .try_fold(None, |collected: Option<FeatureCollection<G>>, chunk| {
+            future::ready(match collected {
+                Some(collected) => collected.append(&chunk).map(Some).map_err(Into::into),
+                None => Ok(Some(chunk)),
+            })
+        })
+        .await?;
+
+    Ok(collected.unwrap_or_else(FeatureCollection::empty))
+}
+
+#[derive(Debug, Clone)]
+pub struct PinnedDataset {
+    pub collection: PinnedCollection,
+    pub result_descriptor: VectorResultDescriptor,
+}
+
+/// Vector datasets that are kept in memory by their name, up to a total of `max_bytes`
+#[derive(Debug)]
+pub struct PinnedDatasets {
+    max_bytes: usize,
+    datasets: HashMap<String, PinnedDataset>,
+}
+
+impl PinnedDatasets {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            datasets: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PinnedDataset> {
+        self.datasets.get(name)
+    }
+
+    /// The pinned datasets and their names, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &PinnedDataset)> {
+        self.datasets.iter()
+    }
+
+    /// The memory that all pinned datasets occupy
+    pub fn byte_size(&self) -> usize {
+        self.datasets
+            .values()
+            .map(|dataset| dataset.collection.byte_size())
+            .sum()
+    }
+
+    /// Pin the dataset `name`, replacing a previously pinned dataset of the same name
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::PinnedDatasetsFull` if the datasets would occupy more than `max_bytes`
+    ///
+    pub fn insert(&mut self, name: &str, dataset: PinnedDataset) -> Result<()> {
+        let replaced = self
+            .datasets
+            .get(name)
+            .map_or(0, |dataset| dataset.collection.byte_size());
+        let available = self.max_bytes.saturating_sub(self.byte_size() - replaced);
+        let bytes = dataset.collection.byte_size();
+
+        ensure!(
+            bytes <= available,
+            error::PinnedDatasetsFull {
+                name,
+                bytes,
+                available,
+            }
+        );
+
+        self.datasets.insert(name.to_string(), dataset);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PinnedDataset> {
+        self.datasets.remove(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PinnedSourceParameters {
+    /// The name of a pinned dataset
+    pub name: String,
+}
+
+/// Outputs the features of a pinned dataset whose bounding boxes intersect the query.
+///
+/// Pinned datasets are looked up in the `PinnedDatasets` of the `ExecutionContext`. They are
+/// kept in memory, so small reference layers that many workflows use, e.g., country borders,
+/// are not read from their files for every query.
+pub type PinnedSource = SourceOperator<PinnedSourceParameters>;
+
+#[typetag::serde]
+impl VectorOperator for PinnedSource {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedVectorOperator>> {
+        InitializedOperatorImpl::create(
+            self.params,
+            context,
+            |params, context, _, _| {
+                let name = params.name.as_str();
+                let pinned_datasets = context
+                    .pinned_datasets
+                    .as_ref()
+                    .context(error::UnknownPinnedDataset { name })?
+                    .read()
+                    .map_err(|_| error::Error::PinnedDatasetsLockFailed)?;
+
+                pinned_datasets
+                    .get(name)
+                    .cloned()
+                    .context(error::UnknownPinnedDataset { name })
+            },
+            |_, _, dataset: &PinnedDataset, _, _| Ok(dataset.result_descriptor),
+            vec![],
+            vec![],
+        )
+        .map(InitializedOperatorImpl::boxed)
+    }
+}
+
+impl InitializedOperator<VectorResultDescriptor, TypedVectorQueryProcessor>
+    for InitializedOperatorImpl<PinnedSourceParameters, VectorResultDescriptor, PinnedDataset>
+{
+    fn query_processor(&self) -> Result<TypedVectorQueryProcessor> {
+        // the collections share their Arrow arrays and spatial index with the pinned dataset
+        Ok(match self.state.collection.clone() {
+            PinnedCollection::Data(collection) => {
+                TypedVectorQueryProcessor::Data(PinnedSourceProcessor { collection }.boxed())
+            }
+            PinnedCollection::MultiPoint(collection) => {
+                TypedVectorQueryProcessor::MultiPoint(PinnedSourceProcessor { collection }.boxed())
+            }
+            PinnedCollection::MultiLineString(collection) => {
+                TypedVectorQueryProcessor::MultiLineString(
+                    PinnedSourceProcessor { collection }.boxed(),
+                )
+            }
+            PinnedCollection::MultiPolygon(collection) => TypedVectorQueryProcessor::MultiPolygon(
+                PinnedSourceProcessor { collection }.boxed(),
+            ),
+        })
+    }
+}
+
+crate::register_operator!(Vector, PinnedSource);
+
+pub struct PinnedSourceProcessor<G>
+where
+    G: Geometry + ArrowTyped,
+{
+    collection: FeatureCollection<G>,
+}
+
+impl<G> QueryProcessor for PinnedSourceProcessor<G>
+where
+    G: Geometry + ArrowTyped + Send + Sync,
+    FeatureCollection<G>: Intersecting,
+{
+    type Output = FeatureCollection<G>;
+
+    fn query(&self, query: QueryRectangle, _ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        let mask: Vec<bool> = self
+            .collection
+            .intersecting(&query.bbox)
+            .into_iter()
+            .zip(self.collection.time_intervals())
+            .map(|(intersects, time)| intersects && time.intersects(&query.time_interval))
+            .collect();
+
+        let result = self.collection.filter(mask).map_err(Into::into);
+        stream::once(future::ready(result)).boxed()
+    }
+}
+
+/// Collections whose features can be selected by the bounding box of a query
+pub trait Intersecting {
+    /// A mask of the features whose bounding boxes intersect `bbox`
+    fn intersecting(&self, bbox: &BoundingBox2D) -> Vec<bool>;
+}
+
+impl Intersecting for DataCollection {
+    /// Features without geometries lie everywhere
+    fn intersecting(&self, _bbox: &BoundingBox2D) -> Vec<bool> {
+        vec![true; self.len()]
+    }
+}
+
+macro_rules! impl_intersecting_with_spatial_index {
+    ($collection:ty) => {
+        impl Intersecting for $collection {
+            fn intersecting(&self, bbox: &BoundingBox2D) -> Vec<bool> {
+                let mut mask = vec![false; self.len()];
+                for feature in self.spatial_index().features_intersecting(bbox) {
+                    mask[feature] = true;
+                }
+                mask
+            }
+        }
+    };
+}
+
+impl_intersecting_with_spatial_index!(MultiPointCollection);
+impl_intersecting_with_spatial_index!(MultiLineStringCollection);
+impl_intersecting_with_spatial_index!(MultiPolygonCollection);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockFeatureCollectionSource, MockFeatureCollectionSourceParams};
+    use geoengine_datatypes::primitives::{
+        FeatureData, MultiPoint, SpatialResolution, TimeInterval,
+    };
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+    use std::sync::{Arc, RwLock};
+
+    fn points() -> MultiPointCollection {
+        MultiPointCollection::from_data(
+            MultiPoint::many(vec![(0.0, 0.0), (5.0, 5.0), (10.0, 10.0)]).unwrap(),
+            vec![
+                TimeInterval::new_unchecked(0, 10),
+                TimeInterval::new_unchecked(0, 10),
+                TimeInterval::new_unchecked(10, 20),
+            ],
+            [(
+                "name".to_string(),
+                FeatureData::Text(vec!["a".to_string(), "b".to_string(), "c".to_string()]),
+            )]
+            .iter()
+            .cloned()
+            .collect(),
+        )
+        .unwrap()
+    }
+
+    fn query_ctx() -> QueryContext {
+        QueryContext {
+            chunk_byte_size: 1024,
+            timeout: None,
+            seed: 0,
+        }
+    }
+
+    fn pinned(collection: MultiPointCollection) -> PinnedDataset {
+        PinnedDataset {
+            collection: PinnedCollection::MultiPoint(collection),
+            result_descriptor: VectorResultDescriptor {
+                data_type: VectorDataType::MultiPoint,
+                spatial_reference: SpatialReference::wgs84().into(),
+                bbox: None,
+                time_interval: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn loads_query_results() {
+        let processor = MockFeatureCollectionSource {
+            params: MockFeatureCollectionSourceParams {
+                collection: points(),
+            },
+        }
+        .boxed()
+        .initialize(&ExecutionContext::mock_empty())
+        .unwrap()
+        .query_processor()
+        .unwrap();
+
+        let collection = PinnedCollection::load(
+            processor,
+            QueryRectangle {
+                bbox: BoundingBox2D::new((0., 0.).into(), (10., 10.).into()).unwrap(),
+                time_interval: TimeInterval::default(),
+                spatial_resolution: SpatialResolution::zero_point_one(),
+            },
+            query_ctx(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(collection.data_type(), VectorDataType::MultiPoint);
+        assert_eq!(collection.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn serves_intersecting_features() {
+        let mut pinned_datasets = PinnedDatasets::new(1024 * 1024);
+        pinned_datasets.insert("places", pinned(points())).unwrap();
+
+        let context = ExecutionContext {
+            pinned_datasets: Some(Arc::new(RwLock::new(pinned_datasets))),
+            ..ExecutionContext::mock_empty()
+        };
+        let initialized = PinnedSource {
+            params: PinnedSourceParameters {
+                name: "places".to_string(),
+            },
+        }
+        .boxed()
+        .initialize(&context)
+        .unwrap();
+        assert_eq!(
+            initialized.result_descriptor().data_type,
+            VectorDataType::MultiPoint
+        );
+
+        let processor = match initialized.query_processor().unwrap() {
+            TypedVectorQueryProcessor::MultiPoint(processor) => processor,
+            _ => panic!("the pinned dataset contains points"),
+        };
+
+        let collections: Vec<MultiPointCollection> = processor
+            .vector_query(
+                QueryRectangle {
+                    bbox: BoundingBox2D::new((4., 4.).into(), (11., 11.).into()).unwrap(),
+                    time_interval: TimeInterval::new_unchecked(0, 5),
+                    spatial_resolution: SpatialResolution::zero_point_one(),
+                },
+                query_ctx(),
+            )
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(collections.len(), 1);
+        assert_eq!(
+            collections[0],
+            points().filter(vec![false, true, false]).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_dataset() {
+        let source = || {
+            PinnedSource {
+                params: PinnedSourceParameters {
+                    name: "places".to_string(),
+                },
+            }
+            .boxed()
+        };
+
+        assert!(matches!(
+            source().initialize(&ExecutionContext::mock_empty()),
+            Err(error::Error::UnknownPinnedDataset { .. })
+        ));
+
+        let context = ExecutionContext {
+            pinned_datasets: Some(Arc::new(RwLock::new(PinnedDatasets::new(1024)))),
+            ..ExecutionContext::mock_empty()
+        };
+        assert!(matches!(
+            source().initialize(&context),
+            Err(error::Error::UnknownPinnedDataset { .. })
+        ));
+    }
+
+    #[test]
+    fn limits_memory() {
+        let bytes = points().byte_size();
+        let mut pinned_datasets = PinnedDatasets::new(bytes);
+
+        pinned_datasets.insert("places", pinned(points())).unwrap();
+        assert!(matches!(
+            pinned_datasets.insert("more places", pinned(points())),
+            Err(error::Error::PinnedDatasetsFull { .. })
+        ));
+
+        // replacing a dataset frees its memory
+        pinned_datasets.insert("places", pinned(points())).unwrap();
+        assert_eq!(pinned_datasets.byte_size(), bytes);
+
+        pinned_datasets.remove("places");
+        pinned_datasets
+            .insert("more places", pinned(points()))
+            .unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+use crate::error::Error;
+use crate::util::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// GDAL virtual file systems that read over the network
+const REMOTE_FILE_SYSTEMS: [&str; 7] = [
+    "/vsicurl/",
+    "/vsicurl_streaming/",
+    "/vsis3/",
+    "/vsigs/",
+    "/vsiaz/",
+    "/vsiadls/",
+    "/vsiswift/",
+];
+
+/// How to access datasets that are read over the network.
+///
+/// Failed reads are retried with an exponential backoff. If a remote fails `failure_threshold`
+/// times in a row, it is considered down for `cool_down` and reads fail immediately with
+/// `Error::RemoteSourceUnavailable` instead of waiting for the network again.
+#[derive(Debug)]
+pub struct RemoteSourcePolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub failure_threshold: u32,
+    pub cool_down: Duration,
+    remotes: Mutex<HashMap<String, RemoteState>>,
+}
+
+#[derive(Debug, Default)]
+struct RemoteState {
+    consecutive_failures: u32,
+    down_until: Option<Instant>,
+}
+
+impl RemoteSourcePolicy {
+    pub fn new(
+        max_retries: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+        failure_threshold: u32,
+        cool_down: Duration,
+    ) -> Self {
+        Self {
+            max_retries,
+            initial_backoff,
+            max_backoff,
+            failure_threshold,
+            cool_down,
+            remotes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The time to wait before the `retry`th retry
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    /// Run `read` for the dataset at `path` according to the policy.
+    /// Local datasets are read only once.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the error of the last attempt or with `Error::RemoteSourceUnavailable`
+    /// if the remote is considered down
+    ///
+    pub fn read<T, F>(&self, path: &Path, mut read: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let remote = match remote_of(path) {
+            Some(remote) => remote,
+            None => return read(),
+        };
+
+        self.check_available(&remote)?;
+
+        let mut retry = 0;
+        loop {
+            match read() {
+                Ok(result) => {
+                    self.record_success(&remote);
+                    return Ok(result);
+                }
+                Err(error) if retry >= self.max_retries => {
+                    self.record_failure(&remote);
+                    return Err(error);
+                }
+                Err(_) => {
+                    retry += 1;
+                    std::thread::sleep(self.backoff(retry));
+                }
+            }
+        }
+    }
+
+    fn check_available(&self, remote: &str) -> Result<()> {
+        let mut remotes = self
+            .remotes
+            .lock()
+            .map_err(|_| Error::RemoteSourcePolicyLockFailed)?;
+
+        let state = match remotes.get_mut(remote) {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+
+        match state.down_until {
+            Some(down_until) if down_until > Instant::now() => {
+                Err(Error::RemoteSourceUnavailable {
+                    remote: remote.to_string(),
+                    retry_after_seconds: (down_until - Instant::now()).as_secs() + 1,
+                })
+            }
+            Some(_) => {
+                // the cool down is over, so let the next read probe the remote
+                state.down_until = None;
+                state.consecutive_failures = self.failure_threshold.saturating_sub(1);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn record_success(&self, remote: &str) {
+        if let Ok(mut remotes) = self.remotes.lock() {
+            remotes.remove(remote);
+        }
+    }
+
+    fn record_failure(&self, remote: &str) {
+        if let Ok(mut remotes) = self.remotes.lock() {
+            let state = remotes.entry(remote.to_string()).or_default();
+            state.consecutive_failures += 1;
+
+            if state.consecutive_failures >= self.failure_threshold {
+                state.down_until = Some(Instant::now() + self.cool_down);
+            }
+        }
+    }
+}
+
+impl Default for RemoteSourcePolicy {
+    fn default() -> Self {
+        Self::new(
+            3,
+            Duration::from_millis(100),
+            Duration::from_secs(2),
+            5,
+            Duration::from_secs(30),
+        )
+    }
+}
+
+/// The remote of a GDAL path that is read over the network, i.e., the host of a URL or
+/// the bucket of a cloud storage
+///
+/// # Examples
+///
+/// ```
+/// use geoengine_operators::source::remote_policy::remote_of;
+/// use std::path::Path;
+///
+/// assert_eq!(
+///     remote_of(Path::new("/vsicurl/https://example.com/data/ndvi.tiff")),
+///     Some("/vsicurl/https://example.com".to_string())
+/// );
+/// assert_eq!(
+///     remote_of(Path::new("/vsis3/bucket/ndvi.tiff")),
+///     Some("/vsis3/bucket".to_string())
+/// );
+/// assert_eq!(remote_of(Path::new("data/ndvi.tiff")), None);
+/// ```
+pub fn remote_of(path: &Path) -> Option<String> {
+    let path = path.to_str()?;
+
+    let file_system = REMOTE_FILE_SYSTEMS
+        .iter()
+        .find(|file_system| path.starts_with(*file_system))?;
+    let location = &path[file_system.len()..];
+
+    let (scheme, rest) = match location.find("://") {
+        Some(index) => location.split_at(index + 3),
+        None => ("", location),
+    };
+    let host = rest.split('/').next().unwrap_or_default();
+
+    Some(format!("{}{}{}", file_system, scheme, host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REMOTE: &str = "/vsicurl/https://example.com/ndvi.tiff";
+
+    fn policy(failure_threshold: u32, cool_down: Duration) -> RemoteSourcePolicy {
+        RemoteSourcePolicy::new(
+            2,
+            Duration::from_millis(0),
+            Duration::from_millis(0),
+            failure_threshold,
+            cool_down,
+        )
+    }
+
+    #[test]
+    fn backoff() {
+        let policy = RemoteSourcePolicy::new(
+            10,
+            Duration::from_millis(100),
+            Duration::from_millis(500),
+            1,
+            Duration::from_secs(1),
+        );
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn retries_remote_reads() {
+        let policy = policy(5, Duration::from_secs(60));
+
+        let mut attempts = 0;
+        let result = policy.read(Path::new(REMOTE), || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(Error::QueryProcessor)
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let mut attempts = 0;
+        let result: Result<()> = policy.read(Path::new("local.tiff"), || {
+            attempts += 1;
+            Err(Error::QueryProcessor)
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn circuit_breaker() {
+        let policy = policy(2, Duration::from_secs(60));
+        let fail = || -> Result<()> { Err(Error::QueryProcessor) };
+
+        assert!(matches!(
+            policy.read(Path::new(REMOTE), fail),
+            Err(Error::QueryProcessor)
+        ));
+        assert!(matches!(
+            policy.read(Path::new(REMOTE), fail),
+            Err(Error::QueryProcessor)
+        ));
+
+        let mut attempts = 0;
+        let result = policy.read(Path::new(REMOTE), || {
+            attempts += 1;
+            Ok(())
+        });
+        assert!(matches!(result, Err(Error::RemoteSourceUnavailable { .. })));
+        assert_eq!(attempts, 0);
+
+        // other remotes are not affected
+        assert!(policy
+            .read(Path::new("/vsicurl/https://other.org/ndvi.tiff"), || Ok(()))
+            .is_ok());
+    }
+
+    #[test]
+    fn circuit_breaker_recovers() {
+        let policy = policy(1, Duration::from_millis(0));
+
+        assert!(policy
+            .read(Path::new(REMOTE), || -> Result<()> {
+                Err(Error::QueryProcessor)
+            })
+            .is_err());
+
+        assert!(policy.read(Path::new(REMOTE), || Ok(())).is_ok());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::engine::{
+    ExecutionContext, InitializedPlotOperator, InitializedRasterOperator,
+    InitializedVectorOperator, PlotOperator, RasterOperator, SourceOperator, TypedOperator,
+    VectorOperator,
+};
+use crate::error;
+use crate::util::Result;
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowSourceParameters {
+    /// The id of a registered workflow
+    pub workflow: String,
+}
+
+/// Outputs the result of another registered workflow.
+///
+/// The referenced workflow is looked up with the `WorkflowResolver` of the `ExecutionContext`
+/// and initialized in place of this operator, so building-block workflows can be reused without
+/// copying their operator graph.
+pub type WorkflowSource = SourceOperator<WorkflowSourceParameters>;
+
+impl WorkflowSource {
+    /// Look up the referenced workflow and derive the context for initializing it
+    fn resolve(&self, context: &ExecutionContext) -> Result<(TypedOperator, ExecutionContext)> {
+        let workflow = &self.params.workflow;
+
+        ensure!(
+            !context.resolving_workflows.contains(workflow),
+            error::CyclicWorkflowReference {
+                workflow: workflow.clone()
+            }
+        );
+
+        let operator = context
+            .workflow_resolver
+            .as_ref()
+            .ok_or_else(|| error::Error::UnresolvableWorkflow {
+                workflow: workflow.clone(),
+            })?
+            .resolve(workflow)?;
+
+        let mut context = context.clone();
+        context.resolving_workflows.push(workflow.clone());
+
+        Ok((operator, context))
+    }
+
+    fn invalid_type(&self, expected: &str, operator: &TypedOperator) -> error::Error {
+        let found = match operator {
+            TypedOperator::Raster(_) => "raster",
+            TypedOperator::Vector(_) => "vector",
+            TypedOperator::Plot(_) => "plot",
+        };
+
+        error::Error::InvalidWorkflowType {
+            workflow: self.params.workflow.clone(),
+            expected: expected.to_string(),
+            found: found.to_string(),
+        }
+    }
+}
+
+#[typetag::serde]
+impl RasterOperator for WorkflowSource {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        match self.resolve(context)? {
+            (TypedOperator::Raster(operator), context) => operator.initialize(&context),
+            (operator, _) => Err(self.invalid_type("raster", &operator)),
+        }
+    }
+}
+
+#[typetag::serde]
+impl VectorOperator for WorkflowSource {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedVectorOperator>> {
+        match self.resolve(context)? {
+            (TypedOperator::Vector(operator), context) => operator.initialize(&context),
+            (operator, _) => Err(self.invalid_type("vector", &operator)),
+        }
+    }
+}
+
+#[typetag::serde]
+impl PlotOperator for WorkflowSource {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedPlotOperator>> {
+        match self.resolve(context)? {
+            (TypedOperator::Plot(operator), context) => operator.initialize(&context),
+            (operator, _) => Err(self.invalid_type("plot", &operator)),
+        }
+    }
+}
+
+crate::register_operator!(Raster, WorkflowSource);
+crate::register_operator!(Vector, WorkflowSource);
+crate::register_operator!(Plot, WorkflowSource);
+
+/// The ids of the workflows that the `WorkflowSource` operators of the `operator` tree refer to,
+/// without resolving them
+pub fn referenced_workflows(operator: &TypedOperator) -> Vec<String> {
+    referenced_parameters(operator, "WorkflowSource", "workflow")
+}
+
+/// The ids of the workflows that the `WorkflowSource` operators of a workflow document refer to,
+/// e.g., for resolving the references of workflows that are not deserialized yet
+pub fn referenced_workflows_of_json(value: &serde_json::Value) -> Vec<String> {
+    let mut values = Vec::new();
+    collect_parameters(value, "WorkflowSource", "workflow", &mut values);
+    values
+}
+
+/// The distinct values of the string `parameter` of all operators of type `operator_type` in the
+/// `operator` tree
+pub(crate) fn referenced_parameters(
+    operator: &TypedOperator,
+    operator_type: &str,
+    parameter: &str,
+) -> Vec<String> {
+    let mut values = Vec::new();
+    if let Ok(value) = serde_json::to_value(operator) {
+        collect_parameters(&value, operator_type, parameter, &mut values);
+    }
+    values
+}
+
+fn collect_parameters(
+    value: &serde_json::Value,
+    operator_type: &str,
+    parameter: &str,
+    values: &mut Vec<String>,
+) {
+    match value {
+        serde_json::Value::Object(object) => {
+            if object.get("type").and_then(serde_json::Value::as_str) == Some(operator_type) {
+                if let Some(value) = object
+                    .get("params")
+                    .and_then(|params| params.get(parameter))
+                    .and_then(serde_json::Value::as_str)
+                {
+                    if !values.iter().any(|known| known == value) {
+                        values.push(value.to_string());
+                    }
+                }
+            }
+
+            for value in object.values() {
+                collect_parameters(value, operator_type, parameter, values);
+            }
+        }
+        serde_json::Value::Array(array) => {
+            for value in array {
+                collect_parameters(value, operator_type, parameter, values);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{
+        QueryContext, QueryRectangle, TypedVectorQueryProcessor, VectorQueryProcessor,
+    };
+    use crate::mock::{MockPointSource, MockPointSourceParams};
+    use futures::StreamExt;
+    use geoengine_datatypes::primitives::{
+        BoundingBox2D, Coordinate2D, SpatialResolution, TimeInterval,
+    };
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct MapResolver(HashMap<String, TypedOperator>);
+
+    impl crate::engine::WorkflowResolver for MapResolver {
+        fn resolve(&self, id: &str) -> Result<TypedOperator> {
+            self.0
+                .get(id)
+                .cloned()
+                .ok_or_else(|| error::Error::UnresolvableWorkflow {
+                    workflow: id.to_string(),
+                })
+        }
+    }
+
+    fn reference(workflow: &str) -> WorkflowSource {
+        WorkflowSource {
+            params: WorkflowSourceParameters {
+                workflow: workflow.to_string(),
+            },
+        }
+    }
+
+    fn context() -> ExecutionContext {
+        let mut workflows = HashMap::new();
+        workflows.insert(
+            "points".to_string(),
+            TypedOperator::Vector(
+                MockPointSource {
+                    params: MockPointSourceParams {
+                        points: vec![Coordinate2D::new(1., 2.); 3],
+                    },
+                }
+                .boxed(),
+            ),
+        );
+        workflows.insert(
+            "alias".to_string(),
+            TypedOperator::Vector(VectorOperator::boxed(reference("points"))),
+        );
+        workflows.insert(
+            "cycle".to_string(),
+            TypedOperator::Vector(VectorOperator::boxed(reference("cycle"))),
+        );
+
+        ExecutionContext {
+            workflow_resolver: Some(Arc::new(MapResolver(workflows))),
+            ..ExecutionContext::mock_empty()
+        }
+    }
+
+    #[tokio::test]
+    async fn splices_referenced_workflows() {
+        let initialized = VectorOperator::boxed(reference("alias"))
+            .initialize(&context())
+            .unwrap();
+
+        let processor = match initialized.query_processor().unwrap() {
+            TypedVectorQueryProcessor::MultiPoint(processor) => processor,
+            _ => panic!("the referenced workflow outputs points"),
+        };
+
+        let collections: Vec<_> = processor
+            .vector_query(
+                QueryRectangle {
+                    bbox: BoundingBox2D::new((0., 0.).into(), (4., 4.).into()).unwrap(),
+                    time_interval: TimeInterval::default(),
+                    spatial_resolution: SpatialResolution::zero_point_one(),
+                },
+                QueryContext {
+                    chunk_byte_size: 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(collections.len(), 1);
+        assert_eq!(collections[0].len(), 3);
+    }
+
+    #[test]
+    fn fails_for_unresolvable_references() {
+        let context = context();
+
+        assert!(matches!(
+            VectorOperator::boxed(reference("cycle")).initialize(&context),
+            Err(error::Error::CyclicWorkflowReference { .. })
+        ));
+        assert!(matches!(
+            VectorOperator::boxed(reference("unknown")).initialize(&context),
+            Err(error::Error::UnresolvableWorkflow { .. })
+        ));
+        assert!(matches!(
+            RasterOperator::boxed(reference("points")).initialize(&context),
+            Err(error::Error::InvalidWorkflowType { .. })
+        ));
+        assert!(matches!(
+            VectorOperator::boxed(reference("points")).initialize(&ExecutionContext::mock_empty()),
+            Err(error::Error::UnresolvableWorkflow { .. })
+        ));
+    }
+
+    #[test]
+    fn lists_references() {
+        let operator = TypedOperator::Vector(VectorOperator::boxed(reference("alias")));
+
+        assert_eq!(referenced_workflows(&operator), vec!["alias".to_string()]);
+    }
+}
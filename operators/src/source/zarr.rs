@@ -0,0 +1,690 @@
+use std::fs::File;
+use std::io::Read;
+use std::marker::PhantomData;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+
+use geoengine_datatypes::primitives::{BoundingBox2D, SpatialBounded, TimeInterval};
+use geoengine_datatypes::raster::{
+    GeoTransform, Pixel, Raster2D, RasterDataType, RasterTile2D, TileInformation,
+};
+use geoengine_datatypes::spatial_reference::SpatialReference;
+
+use crate::engine::{
+    enclosing_time_interval, ExecutionContext, InitializedOperator, InitializedOperatorImpl,
+    InitializedRasterOperator, QueryContext, QueryProcessor, QueryRectangle, RasterOperator,
+    RasterQueryProcessor, RasterResultDescriptor, SourceOperator, TypedRasterQueryProcessor,
+};
+use crate::error;
+use crate::util::Result;
+
+/// Parameters for the Zarr Source Operator
+///
+/// # Examples
+///
+/// ```rust
+/// use geoengine_operators::source::{ZarrSource, ZarrSourceParameters};
+/// use geoengine_datatypes::primitives::TimeInterval;
+/// use geoengine_datatypes::raster::GeoTransform;
+/// use geoengine_datatypes::spatial_reference::SpatialReference;
+///
+/// let json_string = r#"
+///     {
+///         "type": "ZarrSource",
+///         "params": {
+///             "store": "cubes/ndvi.zarr",
+///             "array": "ndvi",
+///             "geo_transform": {
+///                 "upper_left_coordinate": { "x": -180.0, "y": 90.0 },
+///                 "x_pixel_size": 0.1,
+///                 "y_pixel_size": -0.1
+///             },
+///             "time_intervals": [{ "start": 0, "end": 1 }],
+///             "spatial_reference": "EPSG:4326"
+///         }
+///     }"#;
+///
+/// let operator: ZarrSource = serde_json::from_str(json_string).unwrap();
+///
+/// assert_eq!(operator, ZarrSource {
+///     params: ZarrSourceParameters {
+///         store: "cubes/ndvi.zarr".into(),
+///         array: "ndvi".into(),
+///         geo_transform: GeoTransform::new((-180., 90.).into(), 0.1, -0.1),
+///         time_intervals: vec![TimeInterval::new_unchecked(0, 1)],
+///         spatial_reference: SpatialReference::wgs84(),
+///     },
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZarrSourceParameters {
+    /// The Zarr store, relative to the raster data root
+    pub store: PathBuf,
+    /// The array of the store, e.g., an xarray data variable, with `(time, y, x)` dimensions
+    pub array: String,
+    /// The geo transform of the first pixel of the array
+    pub geo_transform: GeoTransform,
+    /// The validity of each index of the time dimension
+    pub time_intervals: Vec<TimeInterval>,
+    pub spatial_reference: SpatialReference,
+}
+
+/// Reads a chunked Zarr (version 2) array from a local store.
+///
+/// The array must have the dimensions `(time, y, x)` in C order and be uncompressed or compressed
+/// with zlib or gzip. Each spatial chunk becomes a tile at the native resolution of the array,
+/// so chunks are read once per query and never re-sliced. Missing chunks are filled with the
+/// `fill_value`, which is the no-data value of the tiles.
+pub type ZarrSource = SourceOperator<ZarrSourceParameters>;
+
+#[typetag::serde]
+impl RasterOperator for ZarrSource {
+    fn initialize(
+        self: Box<Self>,
+        context: &ExecutionContext,
+    ) -> Result<Box<InitializedRasterOperator>> {
+        InitializedOperatorImpl::create(
+            self.params,
+            context,
+            |params, context, _, _| {
+                ZarrArray::open(params, &context.raster_data_root).map(Arc::new)
+            },
+            |params, _, array, _, _| {
+                Ok(RasterResultDescriptor {
+                    data_type: array.data_type,
+                    spatial_reference: params.spatial_reference.into(),
+                    bbox: array.bbox(),
+                    time_interval: enclosing_time_interval(&array.time_intervals),
+                    no_data_value: array.fill_value,
+                    bands: vec![],
+                })
+            },
+            vec![],
+            vec![],
+        )
+        .map(InitializedOperatorImpl::boxed)
+    }
+}
+
+crate::register_operator!(Raster, ZarrSource);
+
+impl InitializedOperator<RasterResultDescriptor, TypedRasterQueryProcessor>
+    for InitializedOperatorImpl<ZarrSourceParameters, RasterResultDescriptor, Arc<ZarrArray>>
+{
+    fn query_processor(&self) -> Result<TypedRasterQueryProcessor> {
+        Ok(call_generic_raster_processor!(
+            self.state.data_type,
+            ZarrSourceProcessor::new(self.state.clone()).boxed()
+        ))
+    }
+}
+
+/// The `.zarray` metadata of a Zarr array
+#[derive(Debug, Clone, Deserialize)]
+struct ZarrArrayMetadata {
+    zarr_format: u8,
+    shape: Vec<usize>,
+    chunks: Vec<usize>,
+    dtype: String,
+    compressor: Option<ZarrCompressor>,
+    fill_value: Option<serde_json::Value>,
+    order: String,
+    filters: Option<Vec<serde_json::Value>>,
+    #[serde(default = "default_dimension_separator")]
+    dimension_separator: String,
+}
+
+fn default_dimension_separator() -> String {
+    ".".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ZarrCompressor {
+    id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compression {
+    None,
+    Zlib,
+    Gzip,
+}
+
+/// An opened Zarr array with validated metadata
+#[derive(Debug)]
+pub struct ZarrArray {
+    path: PathBuf,
+    data_type: RasterDataType,
+    big_endian: bool,
+    /// `[time, y, x]`
+    shape: [usize; 3],
+    /// `[time, y, x]`
+    chunks: [usize; 3],
+    compression: Compression,
+    fill_value: Option<f64>,
+    dimension_separator: String,
+    geo_transform: GeoTransform,
+    time_intervals: Vec<TimeInterval>,
+}
+
+impl ZarrArray {
+    fn open(params: &ZarrSourceParameters, raster_data_root: &Path) -> Result<Self> {
+        let is_relative = |path: &Path| {
+            path.components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        };
+        ensure!(
+            is_relative(&params.store),
+            error::InvalidOperatorParameter {
+                parameter: "store",
+                reason: "must be a path inside the raster data root",
+            }
+        );
+        ensure!(
+            is_relative(Path::new(&params.array)),
+            error::InvalidOperatorParameter {
+                parameter: "array",
+                reason: "must be a path inside the store",
+            }
+        );
+
+        let path = raster_data_root.join(&params.store).join(&params.array);
+        let file = File::open(path.join(".zarray")).context(error::IO)?;
+        let metadata: ZarrArrayMetadata =
+            serde_json::from_reader(file).context(error::SerdeJson)?;
+
+        ensure!(
+            metadata.zarr_format == 2,
+            error::ZarrSource {
+                details: format!("Unsupported Zarr format {}", metadata.zarr_format)
+            }
+        );
+        ensure!(
+            metadata.shape.len() == 3 && metadata.chunks.len() == 3,
+            error::ZarrSource {
+                details: "The array must have the dimensions (time, y, x)"
+            }
+        );
+        ensure!(
+            metadata.order == "C",
+            error::ZarrSource {
+                details: "Only arrays in C order are supported"
+            }
+        );
+        ensure!(
+            metadata.filters.as_ref().map_or(true, Vec::is_empty),
+            error::ZarrSource {
+                details: "Filters are not supported"
+            }
+        );
+        ensure!(
+            metadata.chunks.iter().all(|&size| size > 0),
+            error::ZarrSource {
+                details: "Chunk sizes must be positive"
+            }
+        );
+        ensure!(
+            params.time_intervals.len() == metadata.shape[0],
+            error::InvalidOperatorParameter {
+                parameter: "time_intervals",
+                reason: format!(
+                    "must have an entry for each of the {} time steps",
+                    metadata.shape[0]
+                ),
+            }
+        );
+
+        let compression = match metadata.compressor.as_ref().map(|c| c.id.as_str()) {
+            None => Compression::None,
+            Some("zlib") => Compression::Zlib,
+            Some("gzip") => Compression::Gzip,
+            Some(id) => {
+                return Err(error::Error::ZarrSource {
+                    details: format!("Unsupported compressor `{}`", id),
+                })
+            }
+        };
+
+        let (data_type, big_endian) = parse_dtype(&metadata.dtype)?;
+
+        let fill_value = match metadata.fill_value {
+            Some(serde_json::Value::Number(number)) => number.as_f64(),
+            Some(serde_json::Value::String(value)) if value == "NaN" => Some(f64::NAN),
+            _ => None,
+        };
+
+        Ok(Self {
+            path,
+            data_type,
+            big_endian,
+            shape: [metadata.shape[0], metadata.shape[1], metadata.shape[2]],
+            chunks: [metadata.chunks[0], metadata.chunks[1], metadata.chunks[2]],
+            compression,
+            fill_value,
+            dimension_separator: metadata.dimension_separator,
+            geo_transform: params.geo_transform,
+            time_intervals: params.time_intervals.clone(),
+        })
+    }
+
+    /// The spatial extent of the array
+    fn bbox(&self) -> Option<BoundingBox2D> {
+        BoundingBox2D::new_upper_left_lower_right(
+            self.geo_transform.upper_left_coordinate,
+            self.geo_transform
+                .grid_2d_to_coordinate_2d((self.shape[1], self.shape[2])),
+        )
+        .ok()
+    }
+
+    fn number_of_chunks(&self, dimension: usize) -> usize {
+        (self.shape[dimension] + self.chunks[dimension] - 1) / self.chunks[dimension]
+    }
+
+    /// The tile of the spatial chunk at `(y, x)`, clipped to the shape of the array
+    fn tile_information(&self, y: usize, x: usize) -> TileInformation {
+        let pixel_y = y * self.chunks[1];
+        let pixel_x = x * self.chunks[2];
+
+        TileInformation::new(
+            (self.number_of_chunks(1), self.number_of_chunks(2)).into(),
+            (y, x).into(),
+            (pixel_y, pixel_x).into(),
+            (
+                self.chunks[1].min(self.shape[1] - pixel_y),
+                self.chunks[2].min(self.shape[2] - pixel_x),
+            )
+                .into(),
+            self.geo_transform,
+        )
+    }
+
+    /// The decompressed bytes of a chunk or `None` if it was never written
+    fn read_chunk(&self, time: usize, y: usize, x: usize) -> Result<Option<Vec<u8>>> {
+        let key = [time, y, x]
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(&self.dimension_separator);
+
+        let file = match File::open(self.path.join(key)) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error::Error::IO { source: error }),
+        };
+
+        let mut bytes = Vec::new();
+        match self.compression {
+            Compression::None => Box::new(file) as Box<dyn Read>,
+            Compression::Zlib => Box::new(ZlibDecoder::new(file)),
+            Compression::Gzip => Box::new(GzDecoder::new(file)),
+        }
+        .read_to_end(&mut bytes)
+        .context(error::IO)?;
+
+        let expected = self.chunks.iter().product::<usize>() * self.data_type_size();
+        ensure!(
+            bytes.len() == expected,
+            error::ZarrSource {
+                details: format!(
+                    "Chunk {}.{}.{} has {} bytes instead of {}",
+                    time,
+                    y,
+                    x,
+                    bytes.len(),
+                    expected
+                )
+            }
+        );
+
+        Ok(Some(bytes))
+    }
+
+    fn data_type_size(&self) -> usize {
+        match self.data_type {
+            RasterDataType::U8 | RasterDataType::I8 => 1,
+            RasterDataType::U16 | RasterDataType::I16 | RasterDataType::F16 => 2,
+            RasterDataType::U32 | RasterDataType::I32 | RasterDataType::F32 => 4,
+            RasterDataType::U64 | RasterDataType::I64 | RasterDataType::F64 => 8,
+        }
+    }
+
+    /// The pixels of `bytes` as `T`, which is the data type of the array
+    fn decode<T: Pixel>(&self, bytes: &[u8]) -> Vec<T> {
+        macro_rules! decode {
+            ($type:ty) => {{
+                const SIZE: usize = std::mem::size_of::<$type>();
+                bytes
+                    .chunks_exact(SIZE)
+                    .map(|value| {
+                        let mut array = [0; SIZE];
+                        array.copy_from_slice(value);
+                        let value = if self.big_endian {
+                            <$type>::from_be_bytes(array)
+                        } else {
+                            <$type>::from_le_bytes(array)
+                        };
+                        T::from_(value)
+                    })
+                    .collect()
+            }};
+        }
+
+        match self.data_type {
+            RasterDataType::U8 => decode!(u8),
+            RasterDataType::U16 => decode!(u16),
+            RasterDataType::U32 => decode!(u32),
+            RasterDataType::U64 => decode!(u64),
+            RasterDataType::I8 => decode!(i8),
+            RasterDataType::I16 => decode!(i16),
+            RasterDataType::I32 => decode!(i32),
+            RasterDataType::I64 => decode!(i64),
+            RasterDataType::F32 => decode!(f32),
+            RasterDataType::F64 => decode!(f64),
+            RasterDataType::F16 => unreachable!("half-precision arrays are rejected when opened"),
+        }
+    }
+}
+
+/// The data type and byte order of a NumPy type string, e.g., `<f4`
+fn parse_dtype(dtype: &str) -> Result<(RasterDataType, bool)> {
+    let unsupported = || error::Error::ZarrSource {
+        details: format!("Unsupported data type `{}`", dtype),
+    };
+
+    let mut chars = dtype.chars();
+    let big_endian = match chars.next() {
+        Some('>') => true,
+        Some('<') | Some('|') => false,
+        _ => return Err(unsupported()),
+    };
+
+    let data_type = match chars.as_str() {
+        "u1" | "b1" => RasterDataType::U8,
+        "u2" => RasterDataType::U16,
+        "u4" => RasterDataType::U32,
+        "u8" => RasterDataType::U64,
+        "i1" => RasterDataType::I8,
+        "i2" => RasterDataType::I16,
+        "i4" => RasterDataType::I32,
+        "i8" => RasterDataType::I64,
+        "f4" => RasterDataType::F32,
+        "f8" => RasterDataType::F64,
+        _ => return Err(unsupported()),
+    };
+
+    Ok((data_type, big_endian))
+}
+
+pub struct ZarrSourceProcessor<T> {
+    array: Arc<ZarrArray>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> ZarrSourceProcessor<T>
+where
+    T: Pixel,
+{
+    pub fn new(array: Arc<ZarrArray>) -> Self {
+        Self {
+            array,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// The tiles of all time steps of the time chunk `time` that match the `query`, time step by
+    /// time step. Each spatial chunk of the time chunk is read once.
+    fn time_chunk_tiles(
+        array: &ZarrArray,
+        time: usize,
+        query: QueryRectangle,
+    ) -> Result<Vec<RasterTile2D<T>>> {
+        let time_steps: Vec<usize> = (time * array.chunks[0]
+            ..((time + 1) * array.chunks[0]).min(array.shape[0]))
+            .filter(|&step| {
+                let interval = array.time_intervals[step];
+                query.time_interval.intersects(&interval) || interval.contains(&query.time_interval)
+            })
+            .collect();
+
+        if time_steps.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut chunks = Vec::new();
+        for y in 0..array.number_of_chunks(1) {
+            for x in 0..array.number_of_chunks(2) {
+                let tile_information = array.tile_information(y, x);
+                if query
+                    .bbox
+                    .intersects_bbox(&tile_information.spatial_bounds())
+                {
+                    let pixels = array
+                        .read_chunk(time, y, x)?
+                        .map(|bytes| array.decode::<T>(&bytes));
+                    chunks.push((tile_information, pixels));
+                }
+            }
+        }
+
+        let no_data_value = array.fill_value.map(|value: f64| T::from_(value));
+        let [_, chunk_height, chunk_width] = array.chunks;
+        let mut tiles = Vec::with_capacity(time_steps.len() * chunks.len());
+
+        for step in time_steps {
+            let offset = (step % array.chunks[0]) * chunk_height * chunk_width;
+            let time_interval = array.time_intervals[step];
+
+            for (tile_information, pixels) in &chunks {
+                let (height, width) = tile_information.tile_size_in_pixels().as_pattern();
+
+                // edge chunks are stored with the full chunk size
+                let data = match pixels {
+                    Some(pixels) => (0..height)
+                        .flat_map(|row| {
+                            let start = offset + row * chunk_width;
+                            pixels[start..start + width].iter().copied()
+                        })
+                        .collect(),
+                    None => vec![no_data_value.unwrap_or_else(T::zero); height * width],
+                };
+
+                let raster = Raster2D::new(
+                    tile_information.tile_size_in_pixels,
+                    data,
+                    no_data_value,
+                    time_interval,
+                    tile_information.tile_geo_transform(),
+                )?;
+
+                tiles.push(RasterTile2D::new(time_interval, *tile_information, raster));
+            }
+        }
+
+        Ok(tiles)
+    }
+}
+
+impl<T> QueryProcessor for ZarrSourceProcessor<T>
+where
+    T: Pixel,
+{
+    type Output = RasterTile2D<T>;
+
+    fn query(&self, query: QueryRectangle, _ctx: QueryContext) -> BoxStream<Result<Self::Output>> {
+        let array = self.array.clone();
+
+        stream::iter(0..self.array.number_of_chunks(0))
+            .then(move |time| {
+                let array = array.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || Self::time_chunk_tiles(&array, time, query))
+                        .await
+                        .context(error::TokioJoin)?
+                }
+            })
+            .map(|tiles| {
+                stream::iter(match tiles {
+                    Ok(tiles) => tiles.into_iter().map(Ok).collect(),
+                    Err(error) => vec![Err(error)],
+                })
+            })
+            .flatten()
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression as ZlibCompression;
+    use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution};
+    use geoengine_datatypes::raster::GridPixelAccess;
+    use std::io::Write;
+
+    /// A `(time: 2, y: 3, x: 3)` array of `u16` in chunks of `(2, 2, 2)`, with a missing chunk
+    fn store(compressor: &str) -> tempfile::TempDir {
+        let directory = tempfile::tempdir().unwrap();
+        let array = directory.path().join("cube.zarr").join("values");
+        std::fs::create_dir_all(&array).unwrap();
+
+        std::fs::write(
+            array.join(".zarray"),
+            format!(
+                r#"{{
+                    "zarr_format": 2,
+                    "shape": [2, 3, 3],
+                    "chunks": [2, 2, 2],
+                    "dtype": "<u2",
+                    "compressor": {},
+                    "fill_value": 0,
+                    "order": "C",
+                    "filters": null
+                }}"#,
+                compressor
+            ),
+        )
+        .unwrap();
+
+        // value = 100 * time + 10 * y + x, padded to the full chunk size
+        for (y, x) in &[(0, 0), (0, 1), (1, 0)] {
+            let mut bytes = Vec::new();
+            for time in 0..2 {
+                for row in 0..2 {
+                    for column in 0..2 {
+                        let value = 100 * time + 10 * (y * 2 + row) + (x * 2 + column);
+                        bytes.extend_from_slice(&(value as u16).to_le_bytes());
+                    }
+                }
+            }
+
+            if compressor != "null" {
+                let mut encoder = ZlibEncoder::new(Vec::new(), ZlibCompression::default());
+                encoder.write_all(&bytes).unwrap();
+                bytes = encoder.finish().unwrap();
+            }
+
+            std::fs::write(array.join(format!("0.{}.{}", y, x)), bytes).unwrap();
+        }
+
+        directory
+    }
+
+    fn params() -> ZarrSourceParameters {
+        ZarrSourceParameters {
+            store: "cube.zarr".into(),
+            array: "values".into(),
+            geo_transform: GeoTransform::new((0., 3.).into(), 1., -1.),
+            time_intervals: vec![
+                TimeInterval::new_unchecked(0, 10),
+                TimeInterval::new_unchecked(10, 20),
+            ],
+            spatial_reference: SpatialReference::wgs84(),
+        }
+    }
+
+    fn query(time_interval: TimeInterval) -> QueryRectangle {
+        QueryRectangle {
+            bbox: BoundingBox2D::new((0., 0.).into(), (3., 3.).into()).unwrap(),
+            time_interval,
+            spatial_resolution: SpatialResolution::one(),
+        }
+    }
+
+    async fn tiles(directory: &Path, time_interval: TimeInterval) -> Vec<RasterTile2D<u16>> {
+        let array = ZarrArray::open(&params(), directory).unwrap();
+        assert_eq!(array.data_type, RasterDataType::U16);
+
+        ZarrSourceProcessor::<u16>::new(Arc::new(array))
+            .query(
+                query(time_interval),
+                QueryContext {
+                    chunk_byte_size: 1024,
+                    timeout: None,
+                    seed: 0,
+                },
+            )
+            .map(Result::unwrap)
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn chunks_as_tiles() {
+        let directory = store(r#"{"id": "zlib", "level": 1}"#);
+
+        let tiles = tiles(directory.path(), TimeInterval::default()).await;
+
+        // two time steps of four tiles, time step by time step
+        assert_eq!(tiles.len(), 8);
+        assert!(tiles[..4]
+            .iter()
+            .all(|tile| tile.time == TimeInterval::new_unchecked(0, 10)));
+
+        // the edge tile is clipped to the shape of the array
+        assert_eq!(tiles[5].tile.tile_size_in_pixels, [2, 1].into());
+        assert_eq!(tiles[5].data.data_container, vec![102, 112]);
+
+        // the missing chunk is filled with the fill value
+        assert_eq!(tiles[7].data.data_container, vec![0]);
+        assert_eq!(tiles[7].data.pixel_value_at_grid_index(&(0, 0)).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn filters_time_steps() {
+        let directory = store("null");
+
+        let tiles = tiles(directory.path(), TimeInterval::new_unchecked(12, 12)).await;
+
+        assert_eq!(tiles.len(), 4);
+        assert_eq!(tiles[0].data.data_container, vec![100, 101, 110, 111]);
+    }
+
+    #[test]
+    fn rejects_paths_outside_of_the_data_root() {
+        let directory = store("null");
+
+        assert!(ZarrArray::open(
+            &ZarrSourceParameters {
+                store: "../cube.zarr".into(),
+                ..params()
+            },
+            directory.path()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn dtypes() {
+        assert_eq!(parse_dtype("<f4").unwrap(), (RasterDataType::F32, false));
+        assert_eq!(parse_dtype(">i2").unwrap(), (RasterDataType::I16, true));
+        assert!(parse_dtype("<f2").is_err());
+        assert!(parse_dtype("<U8").is_err());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod input;
+pub mod r_runtime;
+pub mod tile_grid;
 
 use crate::error::Error;
 
@@ -0,0 +1,112 @@
+use crate::error::Error;
+use crate::util::Result;
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// An R installation that runs the scripts of `RScript` operators.
+///
+/// Every script runs in a separate `Rscript` process that is killed if it does not finish
+/// within the `timeout`.
+#[derive(Debug, Clone)]
+pub struct RRuntime {
+    executable: PathBuf,
+    timeout: Duration,
+}
+
+impl RRuntime {
+    pub fn new(executable: PathBuf, timeout: Duration) -> Self {
+        Self {
+            executable,
+            timeout,
+        }
+    }
+
+    /// Run the runtime's executable with `args` and wait for it to finish
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::RScript` if the process cannot be started, exits unsuccessfully or
+    /// exceeds the timeout
+    ///
+    pub fn run<I, S>(&self, args: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut child = Command::new(&self.executable)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| Error::RScript {
+                details: format!("cannot start `{}`: {}", self.executable.display(), error),
+            })?;
+
+        let start = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+
+            if start.elapsed() > self.timeout {
+                // the process may have exited in the meantime
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(Error::RScript {
+                    details: format!("the script did not finish within {:?}", self.timeout),
+                });
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        };
+
+        if status.success() {
+            return Ok(());
+        }
+
+        let mut stderr = String::new();
+        if let Some(mut output) = child.stderr.take() {
+            output.read_to_string(&mut stderr)?;
+        }
+
+        Err(Error::RScript {
+            details: format!("the script failed with {}: {}", status, stderr.trim()),
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_failures() {
+        let runtime = RRuntime::new("sh".into(), Duration::from_secs(10));
+
+        assert!(runtime.run(&["-c", "exit 0"]).is_ok());
+
+        match runtime.run(&["-c", "echo 'object not found' >&2; exit 1"]) {
+            Err(Error::RScript { details }) => assert!(details.contains("object not found")),
+            _ => panic!("the script must fail"),
+        }
+
+        assert!(
+            RRuntime::new("does-not-exist".into(), Duration::from_secs(1))
+                .run(&["script.R"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn kills_scripts_after_the_timeout() {
+        let runtime = RRuntime::new("sleep".into(), Duration::from_millis(50));
+
+        let start = Instant::now();
+        assert!(matches!(runtime.run(&["10"]), Err(Error::RScript { .. })));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
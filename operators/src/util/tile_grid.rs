@@ -0,0 +1,279 @@
+use crate::util::Result;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use geoengine_datatypes::primitives::{Coordinate2D, TimeInterval};
+use geoengine_datatypes::raster::{GeoTransform, Pixel, Raster2D, RasterTile2D};
+
+/// The values of a set of tiles assembled on one grid.
+///
+/// This is used by operators that need the whole query area at once instead of single tiles,
+/// e.g., for global passes like cost accumulation or flow routing.
+#[derive(Debug, Clone)]
+pub struct TileGrid<V> {
+    pub geo_transform: GeoTransform,
+    /// global pixel position (y, x) of the upper left pixel of the grid
+    pub origin: (usize, usize),
+    pub rows: usize,
+    pub columns: usize,
+    pub values: Vec<V>,
+}
+
+impl<V> TileGrid<V>
+where
+    V: Clone,
+{
+    /// Assemble the `tiles` on one grid. The `value` function maps every pixel, `None` denotes
+    /// no-data and pixels that are not covered by any tile.
+    pub fn from_tiles<T, F>(tiles: &[RasterTile2D<T>], value: F) -> Self
+    where
+        T: Pixel,
+        F: Fn(Option<T>) -> V,
+    {
+        let bounds = tiles.iter().fold(None, |bounds, tile| {
+            let [y, x] = *tile
+                .tile
+                .global_pixel_position_upper_left()
+                .dimension_size();
+            let [end_y, end_x] = *tile
+                .tile
+                .global_pixel_position_lower_right()
+                .dimension_size();
+
+            Some(match bounds {
+                Some((min_y, min_x, max_y, max_x)) => (
+                    usize::min(min_y, y),
+                    usize::min(min_x, x),
+                    usize::max(max_y, end_y),
+                    usize::max(max_x, end_x),
+                ),
+                None => (y, x, end_y, end_x),
+            })
+        });
+
+        let (min_y, min_x, max_y, max_x) = bounds.unwrap_or((0, 0, 0, 0));
+        let (rows, columns) = (max_y - min_y, max_x - min_x);
+
+        let mut values = vec![value(None); rows * columns];
+
+        for tile in tiles {
+            let [y, x] = *tile
+                .tile
+                .global_pixel_position_upper_left()
+                .dimension_size();
+            let [tile_rows, _] = *tile.tile.tile_size_in_pixels().dimension_size();
+
+            for ((column, row), pixel) in tile.data.pixels() {
+                if row >= tile_rows {
+                    break;
+                }
+
+                let pixel = if Some(pixel) == tile.data.no_data_value {
+                    None
+                } else {
+                    Some(pixel)
+                };
+
+                values[(y - min_y + row) * columns + (x - min_x + column)] = value(pixel);
+            }
+        }
+
+        Self {
+            geo_transform: tiles
+                .first()
+                .map_or_else(Default::default, |tile| tile.tile.global_geo_transform),
+            origin: (min_y, min_x),
+            rows,
+            columns,
+            values,
+        }
+    }
+}
+
+impl<V> TileGrid<V> {
+    /// Replace the values of the grid, e.g., by the results of a computation on it
+    ///
+    /// # Panics
+    ///
+    /// If the number of `values` differs from the number of pixels
+    ///
+    pub fn with_values<W>(&self, values: Vec<W>) -> TileGrid<W> {
+        assert_eq!(values.len(), self.rows * self.columns);
+
+        TileGrid {
+            geo_transform: self.geo_transform,
+            origin: self.origin,
+            rows: self.rows,
+            columns: self.columns,
+            values,
+        }
+    }
+
+    /// The grid index of the pixel that contains the `coordinate`
+    pub fn index_of(&self, coordinate: Coordinate2D) -> Option<usize> {
+        let column = ((coordinate.x - self.geo_transform.upper_left_coordinate.x)
+            / self.geo_transform.x_pixel_size)
+            .floor()
+            - self.origin.1 as f64;
+        let row = ((coordinate.y - self.geo_transform.upper_left_coordinate.y)
+            / self.geo_transform.y_pixel_size)
+            .floor()
+            - self.origin.0 as f64;
+
+        if column < 0. || row < 0. || column >= self.columns as f64 || row >= self.rows as f64 {
+            return None;
+        }
+
+        Some(row as usize * self.columns + column as usize)
+    }
+
+    /// The coordinate of the center of the pixel at grid `index`
+    pub fn center_of(&self, index: usize) -> Coordinate2D {
+        let row = self.origin.0 + index / self.columns;
+        let column = self.origin.1 + index % self.columns;
+        let corner = self.geo_transform.grid_2d_to_coordinate_2d((row, column));
+
+        Coordinate2D::new(
+            corner.x + self.geo_transform.x_pixel_size / 2.,
+            corner.y + self.geo_transform.y_pixel_size / 2.,
+        )
+    }
+
+    /// The grid index of the pixel that is `offset` (rows, columns) away from `index`
+    pub fn neighbor(&self, index: usize, offset: (isize, isize)) -> Option<usize> {
+        let row = (index / self.columns) as isize + offset.0;
+        let column = (index % self.columns) as isize + offset.1;
+
+        if row < 0 || column < 0 || row >= self.rows as isize || column >= self.columns as isize {
+            return None;
+        }
+
+        Some(row as usize * self.columns + column as usize)
+    }
+
+    /// Cut the area of `tile` out of the grid and map its values to pixels
+    pub fn tile<T, O, F>(
+        &self,
+        tile: &RasterTile2D<T>,
+        no_data_value: Option<O>,
+        pixel: F,
+    ) -> Result<RasterTile2D<O>>
+    where
+        T: Pixel,
+        O: Pixel,
+        F: Fn(&V) -> O,
+    {
+        let [y, x] = *tile
+            .tile
+            .global_pixel_position_upper_left()
+            .dimension_size();
+        let [rows, columns] = *tile.tile.tile_size_in_pixels().dimension_size();
+        let (offset_y, offset_x) = (y - self.origin.0, x - self.origin.1);
+
+        let mut data = Vec::with_capacity(rows * columns);
+        for row in 0..rows {
+            let start = (offset_y + row) * self.columns + offset_x;
+            data.extend(self.values[start..start + columns].iter().map(&pixel));
+        }
+
+        Ok(RasterTile2D::new(
+            tile.time,
+            tile.tile,
+            Raster2D::new(
+                tile.tile.tile_size_in_pixels(),
+                data,
+                no_data_value,
+                tile.data.temporal_bounds,
+                tile.data.geo_transform,
+            )?,
+        ))
+    }
+}
+
+/// Collect all tiles of the stream grouped by their time interval
+pub async fn collect_time_steps<T: Pixel>(
+    tiles: BoxStream<'_, Result<RasterTile2D<T>>>,
+) -> Result<Vec<(TimeInterval, Vec<RasterTile2D<T>>)>> {
+    let tiles: Vec<Result<RasterTile2D<T>>> = tiles.collect().await;
+
+    let mut time_steps: Vec<(TimeInterval, Vec<RasterTile2D<T>>)> = Vec::new();
+    for tile in tiles {
+        let tile = tile?;
+        match time_steps.iter_mut().find(|(time, _)| *time == tile.time) {
+            Some((_, time_step)) => time_step.push(tile),
+            None => time_steps.push((tile.time, vec![tile])),
+        }
+    }
+
+    Ok(time_steps)
+}
+
+/// Turn the result of a computation over a whole query into the items of a result stream
+pub fn flatten_result<T>(result: Result<Vec<T>>) -> Vec<Result<T>> {
+    match result {
+        Ok(values) => values.into_iter().map(Ok).collect(),
+        Err(error) => vec![Err(error)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geoengine_datatypes::raster::TileInformation;
+
+    fn tile(position: [usize; 2], data: Vec<u8>) -> RasterTile2D<u8> {
+        RasterTile2D::new(
+            TimeInterval::default(),
+            TileInformation {
+                global_geo_transform: Default::default(),
+                global_pixel_position: [position[0] * 2, position[1] * 2].into(),
+                global_size_in_tiles: [1, 2].into(),
+                global_tile_position: position.into(),
+                tile_size_in_pixels: [2, 2].into(),
+            },
+            Raster2D::new(
+                [2, 2].into(),
+                data,
+                Some(0),
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn assemble_and_cut() {
+        let tiles = vec![
+            tile([0, 1], vec![5, 6, 7, 8]),
+            tile([0, 0], vec![1, 0, 3, 4]),
+        ];
+
+        let grid = TileGrid::from_tiles(&tiles, |pixel: Option<u8>| pixel);
+
+        assert_eq!((grid.rows, grid.columns), (2, 4));
+        assert_eq!(
+            grid.values,
+            vec![
+                Some(1),
+                None,
+                Some(5),
+                Some(6),
+                Some(3),
+                Some(4),
+                Some(7),
+                Some(8)
+            ]
+        );
+
+        assert_eq!(grid.index_of((2.5, -1.5).into()), Some(6));
+        assert_eq!(grid.index_of((4.5, -1.5).into()), None);
+        assert_eq!(grid.center_of(6), (2.5, -1.5).into());
+        assert_eq!(grid.neighbor(3, (0, 1)), None);
+        assert_eq!(grid.neighbor(3, (1, -1)), Some(6));
+
+        let cut = grid
+            .tile(&tiles[0], Some(0), |pixel| pixel.unwrap_or(0) * 2)
+            .unwrap();
+        assert_eq!(cut.data.data_container, vec![10, 12, 14, 16]);
+    }
+}
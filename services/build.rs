@@ -0,0 +1,5 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/control.proto")
+        .expect("the protobuf definitions must compile");
+}
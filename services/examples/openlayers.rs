@@ -1,6 +1,7 @@
 use clap::Clap;
 use geoengine_services::error::Error;
 use geoengine_services::server;
+use geoengine_services::util::config;
 use std::path::Path;
 use std::{thread, time};
 use tokio::sync::oneshot;
@@ -22,8 +23,12 @@ enum Protocol {
 async fn main() -> Result<(), Error> {
     let opts: Opts = Opts::parse();
 
-    // TODO: use special config for port etc. for starting the server and connecting to it
-    let base_url = "http://localhost:3030/".to_string();
+    let base_url = format!(
+        "http://{}/",
+        config::get_config_element::<config::Web>()
+            .expect("web config")
+            .bind_address
+    );
 
     let static_files_directory = Path::new(file!()).with_file_name(match opts.protocol {
         Protocol::WMS => "openlayers-wms-static/",
@@ -1,4 +1,5 @@
 use geoengine_services::server;
+use geoengine_services::util::config;
 use std::{thread, time};
 use tokio::sync::oneshot;
 use tokio::sync::oneshot::Sender;
@@ -23,8 +24,12 @@ async fn main() {
 }
 
 async fn queries(shutdown_tx: Sender<()>) -> bool {
-    // TODO: use special config for port etc. for starting the server and connecting to it
-    let base_url = "http://localhost:3030/".to_string();
+    let base_url = format!(
+        "http://{}/",
+        config::get_config_element::<config::Web>()
+            .expect("web config")
+            .bind_address
+    );
 
     let mut success = false;
     if wait_for_server(&base_url).await {
@@ -1,20 +1,11 @@
-use geoengine_services::error::Error;
-use geoengine_services::server;
-use tokio::sync::oneshot;
+use clap::Clap;
+use geoengine_datatypes::error::ErrorChain;
+use geoengine_services::cli;
 
 #[tokio::main]
-async fn main() -> Result<(), Error> {
-    // TODO: use special config for port etc. for starting the server and connecting to it
-    let base_url = "http://localhost:3030/".to_string();
-
-    eprintln!("Starting server… {}", base_url);
-
-    let (shutdown_tx, shutdown_rx) = oneshot::channel();
-
-    let (server, interrupt_success) = tokio::join!(
-        server::start_server(Some(shutdown_rx), None),
-        server::interrupt_handler(shutdown_tx, Some(|| eprintln!("Shutting down server…"))),
-    );
-
-    server.and(interrupt_success)
+async fn main() {
+    if let Err(error) = cli::run(cli::Opts::parse()).await {
+        eprintln!("{}", ErrorChain(&error));
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,178 @@
+use crate::error;
+use crate::error::Result;
+use crate::server;
+use crate::users::user::UserRegistration;
+use crate::util::config;
+use crate::util::user_input::{UserInput, Validated};
+use clap::Clap;
+use geoengine_operators::source::gdal_source::{
+    JsonDatasetInformation, JsonDatasetInformationProvider,
+};
+use snafu::ResultExt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use tokio::sync::oneshot;
+
+/// Geo Engine server and administration tool
+#[derive(Clap, Debug)]
+pub struct Opts {
+    /// Settings profile, i.e., a `Settings-<profile>.toml` that overrides the default settings.
+    /// Defaults to the `GEOENGINE_PROFILE` environment variable
+    #[clap(long)]
+    pub profile: Option<String>,
+    /// Settings file that overrides the default and profile settings
+    #[clap(long)]
+    pub settings: Option<PathBuf>,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Clap, Debug)]
+pub enum Command {
+    /// Start the server (default)
+    Serve,
+    /// Migrate the schema of the Postgres database to the current version
+    Migrate,
+    /// Register a new user in the Postgres database.
+    /// The password is read from the `GEOENGINE_USER_PASSWORD` environment variable
+    AddUser(AddUser),
+    /// Copy a dataset definition into the raster data directory
+    ImportDataset(ImportDataset),
+    /// Validate the configuration and print the effective settings
+    CheckConfig,
+}
+
+/// Environment variable holding the password of the user to register, so that it does not show
+/// up in the shell history or the process list
+const PASSWORD_VARIABLE: &str = "GEOENGINE_USER_PASSWORD";
+
+#[derive(Clap, Debug)]
+pub struct AddUser {
+    #[clap(long)]
+    pub email: String,
+    #[clap(long)]
+    pub real_name: String,
+}
+
+#[derive(Clap, Debug)]
+pub struct ImportDataset {
+    /// Id under which the dataset is available to the `GdalSource`
+    #[clap(long)]
+    pub id: String,
+    /// JSON file containing the dataset definition
+    #[clap(long)]
+    pub definition: PathBuf,
+}
+
+/// Execute the command given in the `opts`
+pub async fn run(opts: Opts) -> Result<()> {
+    config::load_settings(opts.profile.as_deref(), opts.settings.as_deref())?;
+
+    match opts.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve().await,
+        Command::Migrate => migrate(),
+        Command::AddUser(add_user) => register_user(
+            UserRegistration {
+                email: add_user.email,
+                password: std::env::var(PASSWORD_VARIABLE).map_err(|_| {
+                    error::Error::MissingPassword {
+                        variable: PASSWORD_VARIABLE.to_string(),
+                    }
+                })?,
+                real_name: add_user.real_name,
+            }
+            .validated()?,
+        ),
+        Command::ImportDataset(import) => import_dataset(import),
+        Command::CheckConfig => {
+            eprintln!("{}", config::validate()?);
+            Ok(())
+        }
+    }
+}
+
+async fn serve() -> Result<()> {
+    let report = config::validate()?;
+
+    eprintln!("Effective configuration:\n{}", report);
+
+    let bind_address = config::get_config_element::<config::Web>()?.bind_address;
+
+    eprintln!("Starting server… http://{}/", bind_address);
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+    let (server, interrupt_success) = tokio::join!(
+        server::start_server(Some(shutdown_rx), None),
+        server::interrupt_handler(shutdown_tx, Some(|| eprintln!("Shutting down server…"))),
+    );
+
+    server.and(interrupt_success)
+}
+
+/// Bring the schema of the Postgres database up to date, if it is enabled
+fn migrate() -> Result<()> {
+    #[cfg(feature = "postgres")]
+    {
+        let postgres = config::get_config_element::<config::Postgres>()?;
+        if postgres.enabled {
+            // connecting applies the missing migrations
+            crate::util::postgres::connect(&postgres)?;
+            eprintln!("Migrated schema `{}`", postgres.schema);
+            return Ok(());
+        }
+    }
+
+    eprintln!("The in-memory backend has no schema to migrate");
+    Ok(())
+}
+
+/// Register the `user` in the Postgres database.
+/// The users of the in-memory backend only exist while the server is running.
+#[cfg(feature = "postgres")]
+fn register_user(user: Validated<UserRegistration>) -> Result<()> {
+    use crate::users::postgres_userdb::PostgresUserDB;
+    use crate::users::userdb::UserDB;
+    use snafu::ensure;
+
+    let postgres = config::get_config_element::<config::Postgres>()?;
+    ensure!(
+        postgres.enabled,
+        error::UnsupportedByBackend {
+            command: "add-user"
+        }
+    );
+
+    let email = user.user_input.email.clone();
+    let id = PostgresUserDB::new(crate::util::postgres::connect(&postgres)?).register(user)?;
+    eprintln!("Registered user `{}` with id {}", email, id);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "postgres"))]
+fn register_user(_user: Validated<UserRegistration>) -> Result<()> {
+    Err(error::Error::UnsupportedByBackend {
+        command: "add-user".to_string(),
+    })
+}
+
+fn import_dataset(import: ImportDataset) -> Result<()> {
+    let file = File::open(&import.definition).context(error::IO)?;
+    let dataset_information: JsonDatasetInformation =
+        serde_json::from_reader(BufReader::new(file)).context(error::SerdeJson)?;
+
+    let raster_data_root = config::get_config_element::<config::Raster>()?.data_root;
+
+    JsonDatasetInformationProvider {
+        dataset_information,
+        raster_data_root: raster_data_root.clone(),
+    }
+    .write_to_file(&import.id, &raster_data_root)
+    .context(error::Operator)?;
+
+    eprintln!("Imported dataset `{}`", import.id);
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::error::Result;
+use crate::projects::project::ProjectId;
+use crate::projects::projectdb::ProjectDB;
+use crate::users::user::UserId;
+use crate::util::identifiers::Identifier;
+use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::workflow::WorkflowId;
+use geoengine_operators::source::{referenced_datasets, referenced_workflows};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// The registered workflows and projects of a user that use a dataset
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatasetDependencies {
+    pub workflows: Vec<WorkflowId>,
+    pub projects: Vec<ProjectId>,
+    /// Whether public workflows or the workflows or projects of other users use the dataset,
+    /// which are not listed
+    #[serde(skip)]
+    pub used_by_others: bool,
+}
+
+impl DatasetDependencies {
+    /// Scan the operator graphs of all registered workflows for the dataset `id`, including
+    /// workflows that use it through `WorkflowSource` operators, and the projects of all users
+    /// for layers of these workflows.
+    /// Only the private workflows and the projects that the `user` owns are listed.
+    pub fn scan<W, P>(id: &str, user: UserId, workflow_registry: &W, project_db: &P) -> Result<Self>
+    where
+        W: WorkflowRegistry + ?Sized,
+        P: ProjectDB + ?Sized,
+    {
+        let mut dependent = HashSet::new();
+        let mut references = HashMap::new();
+
+        for workflow_id in workflow_registry.list()? {
+            let operator = workflow_registry.load(&workflow_id)?.operator;
+
+            if referenced_datasets(&operator)
+                .iter()
+                .any(|dataset| dataset == id)
+            {
+                dependent.insert(workflow_id);
+            }
+
+            let referenced: Vec<WorkflowId> = referenced_workflows(&operator)
+                .iter()
+                .filter_map(|reference| Uuid::parse_str(reference).ok())
+                .map(WorkflowId::from_uuid)
+                .collect();
+            references.insert(workflow_id, referenced);
+        }
+
+        // add the workflows that reference dependent workflows until there are no new ones
+        loop {
+            let indirect: Vec<WorkflowId> = references
+                .iter()
+                .filter(|(workflow, referenced)| {
+                    !dependent.contains(*workflow)
+                        && referenced
+                            .iter()
+                            .any(|reference| dependent.contains(reference))
+                })
+                .map(|(workflow, _)| *workflow)
+                .collect();
+
+            if indirect.is_empty() {
+                break;
+            }
+
+            dependent.extend(indirect);
+        }
+
+        let mut used_by_others = false;
+
+        let mut projects = vec![];
+        for project in project_db.referencing_projects(&dependent) {
+            if project_db.is_owner(user, project) {
+                projects.push(project);
+            } else {
+                used_by_others = true;
+            }
+        }
+        projects.sort_by_key(ToString::to_string);
+
+        let mut workflows = vec![];
+        for workflow in dependent {
+            if workflow_registry.owner(&workflow)? == Some(user) {
+                workflows.push(workflow);
+            } else {
+                used_by_others = true;
+            }
+        }
+        workflows.sort_by_key(ToString::to_string);
+
+        Ok(Self {
+            workflows,
+            projects,
+            used_by_others,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.workflows.is_empty() && self.projects.is_empty()
+    }
+}
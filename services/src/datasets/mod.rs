@@ -0,0 +1,112 @@
+use crate::error::{Error, Result};
+use crate::util::config;
+use geoengine_operators::source::{
+    DatasetDefinitions, DatasetStatistics, GdalDatasetPool, PinnedDatasets, RemoteSourcePolicy,
+};
+use lazy_static::lazy_static;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+pub mod dependencies;
+pub mod pinned;
+pub mod statistics;
+pub mod storage;
+pub mod upload;
+pub mod watcher;
+
+/// Dataset definitions that are shared between the handlers and the reload watcher
+pub type SharedDatasetDefinitions = Arc<RwLock<DatasetDefinitions>>;
+
+/// Dataset statistics that are shared between the queries and the task that computes them
+pub type SharedDatasetStatistics = Arc<RwLock<DatasetStatistics>>;
+
+/// Pinned vector datasets that are shared between the queries and the handlers that pin them
+pub type SharedPinnedDatasets = Arc<RwLock<PinnedDatasets>>;
+
+/// Load the dataset definitions of the configured raster data root.
+/// Invalid definitions are skipped and reported on stderr.
+pub fn load_dataset_definitions() -> Result<SharedDatasetDefinitions> {
+    let raster_data_root = config::get_config_element::<config::Raster>()?.data_root;
+
+    let (definitions, report) = DatasetDefinitions::load(&raster_data_root);
+
+    for failed in report.failed {
+        eprintln!(
+            "Skipping dataset definition `{}`: {}",
+            failed.id, failed.reason
+        );
+    }
+
+    Ok(Arc::new(RwLock::new(definitions)))
+}
+
+lazy_static! {
+    static ref GDAL_DATASET_POOL: Mutex<Option<Arc<GdalDatasetPool>>> = Mutex::new(None);
+    static ref DATASET_STATISTICS: Mutex<Option<SharedDatasetStatistics>> = Mutex::new(None);
+    static ref PINNED_DATASETS: Mutex<Option<SharedPinnedDatasets>> = Mutex::new(None);
+}
+
+/// The pool of open GDAL datasets that is shared by all queries.
+/// It is created from the configuration on first use.
+pub fn gdal_dataset_pool() -> Result<Arc<GdalDatasetPool>> {
+    let mut pool = GDAL_DATASET_POOL
+        .lock()
+        .map_err(|_| Error::GdalDatasetPoolLockFailed)?;
+
+    if let Some(pool) = pool.as_ref() {
+        return Ok(pool.clone());
+    }
+
+    let settings = config::get_config_element::<config::GdalDatasetPool>()?;
+    let remote_sources = config::get_config_element::<config::RemoteSources>()?;
+    let created = Arc::new(
+        GdalDatasetPool::new(settings.max_files, settings.handles_per_file).with_remote_policy(
+            RemoteSourcePolicy::new(
+                remote_sources.max_retries,
+                Duration::from_millis(remote_sources.initial_backoff_milliseconds),
+                Duration::from_millis(remote_sources.max_backoff_milliseconds),
+                remote_sources.failure_threshold,
+                Duration::from_secs(remote_sources.cool_down_seconds),
+            ),
+        ),
+    );
+    *pool = Some(created.clone());
+
+    Ok(created)
+}
+
+/// The precomputed statistics of the raster datasets that are shared by all queries.
+/// They are loaded from the configured directory on first use.
+pub fn dataset_statistics() -> Result<SharedDatasetStatistics> {
+    let mut statistics = DATASET_STATISTICS
+        .lock()
+        .map_err(|_| Error::DatasetStatisticsLockFailed)?;
+
+    if let Some(statistics) = statistics.as_ref() {
+        return Ok(statistics.clone());
+    }
+
+    let directory = config::get_config_element::<config::DatasetStatistics>()?.directory;
+    let loaded = Arc::new(RwLock::new(DatasetStatistics::load(&directory)?));
+    *statistics = Some(loaded.clone());
+
+    Ok(loaded)
+}
+
+/// The pinned vector datasets that are shared by all queries.
+/// They are created empty with the configured memory limit on first use.
+pub fn pinned_datasets() -> Result<SharedPinnedDatasets> {
+    let mut pinned = PINNED_DATASETS
+        .lock()
+        .map_err(|_| Error::PinnedDatasetsLockFailed)?;
+
+    if let Some(pinned) = pinned.as_ref() {
+        return Ok(pinned.clone());
+    }
+
+    let max_bytes = config::get_config_element::<config::PinnedDatasets>()?.max_bytes;
+    let created = Arc::new(RwLock::new(PinnedDatasets::new(max_bytes)));
+    *pinned = Some(created.clone());
+
+    Ok(created)
+}
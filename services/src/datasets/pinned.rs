@@ -0,0 +1,332 @@
+use crate::datasets::SharedPinnedDatasets;
+use crate::error;
+use crate::error::{Error, Result};
+use crate::handlers::Requester;
+use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::resolver::WorkflowSnapshot;
+use crate::workflows::workflow::Workflow;
+use geoengine_datatypes::collections::VectorDataType;
+use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution, TimeInterval};
+use geoengine_operators::engine::{
+    ExecutionContext, InitializedOperatorBase, Principal, QueryContext, QueryRectangle,
+    TypedOperator, VectorResultDescriptor, WorkflowResolver,
+};
+use geoengine_operators::source::{PinnedCollection, PinnedDataset};
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// How a pinned dataset is loaded, which is stored for pinning it again after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedDatasetDefinition {
+    /// A vector workflow whose features are kept in memory
+    pub workflow: Workflow,
+    /// The extent of the features to load, defaults to the extent of the workflow's result
+    #[serde(default)]
+    pub bbox: Option<BoundingBox2D>,
+    /// The time of the features to load, defaults to the time of the workflow's result
+    #[serde(default)]
+    pub time_interval: Option<TimeInterval>,
+}
+
+/// A summary of a pinned dataset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedDatasetInfo {
+    pub name: String,
+    pub data_type: VectorDataType,
+    pub features: usize,
+    pub bytes: usize,
+}
+
+impl PinnedDatasetInfo {
+    fn of(name: &str, dataset: &PinnedDataset) -> Self {
+        Self {
+            name: name.to_string(),
+            data_type: dataset.collection.data_type(),
+            features: dataset.collection.len(),
+            bytes: dataset.collection.byte_size(),
+        }
+    }
+}
+
+/// Load the features of the `definition`'s workflow into memory and pin them as `name`.
+/// The definition is stored in the `file`, so that the dataset is pinned again on startup.
+pub async fn pin_dataset(
+    name: &str,
+    definition: PinnedDatasetDefinition,
+    resolver: Arc<dyn WorkflowResolver>,
+    pinned_datasets: &SharedPinnedDatasets,
+    file: &Path,
+) -> Result<PinnedDatasetInfo> {
+    let dataset = load_pinned_dataset(&definition, resolver, pinned_datasets).await?;
+    let info = PinnedDatasetInfo::of(name, &dataset);
+
+    // the definitions are only changed with the write lock, so they match the pinned datasets
+    let mut pinned_datasets = pinned_datasets
+        .write()
+        .map_err(|_| Error::PinnedDatasetsLockFailed)?;
+    pinned_datasets.insert(name, dataset)?;
+
+    let mut definitions = read_definitions(file)?;
+    definitions.insert(name.to_string(), definition);
+    write_definitions(file, &definitions)?;
+
+    Ok(info)
+}
+
+/// Release the memory of the pinned dataset `name` and forget its definition, `false` if it was
+/// not pinned
+pub fn unpin_dataset(
+    name: &str,
+    pinned_datasets: &SharedPinnedDatasets,
+    file: &Path,
+) -> Result<bool> {
+    let mut pinned_datasets = pinned_datasets
+        .write()
+        .map_err(|_| Error::PinnedDatasetsLockFailed)?;
+    let removed = pinned_datasets.remove(name).is_some();
+
+    let mut definitions = read_definitions(file)?;
+    if definitions.remove(name).is_some() {
+        write_definitions(file, &definitions)?;
+    }
+
+    Ok(removed)
+}
+
+/// The summaries of all pinned datasets, ordered by their names
+pub fn pinned_dataset_infos(
+    pinned_datasets: &SharedPinnedDatasets,
+) -> Result<Vec<PinnedDatasetInfo>> {
+    let pinned_datasets = pinned_datasets
+        .read()
+        .map_err(|_| Error::PinnedDatasetsLockFailed)?;
+
+    let mut infos: Vec<PinnedDatasetInfo> = pinned_datasets
+        .iter()
+        .map(|(name, dataset)| PinnedDatasetInfo::of(name, dataset))
+        .collect();
+    infos.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(infos)
+}
+
+/// Pin the datasets whose definitions are stored in the `file`.
+/// Datasets that cannot be loaded are reported on stderr and stay stored for the next startup.
+/// Like when pinning them, only public workflows may be referenced.
+pub async fn pin_stored_datasets<W>(
+    workflow_registry: Arc<RwLock<W>>,
+    pinned_datasets: SharedPinnedDatasets,
+    file: PathBuf,
+) where
+    W: WorkflowRegistry,
+{
+    let definitions = match read_definitions(&file) {
+        Ok(definitions) => definitions,
+        Err(error) => {
+            eprintln!("Unable to read the pinned datasets: {}", error);
+            return;
+        }
+    };
+
+    for (name, definition) in definitions {
+        let resolver = Arc::new(WorkflowSnapshot::collect(
+            &*workflow_registry.read().await,
+            &Requester::Anonymous,
+            &definition.workflow.operator,
+        ));
+
+        let pinned = match load_pinned_dataset(&definition, resolver, &pinned_datasets).await {
+            Ok(dataset) => pinned_datasets
+                .write()
+                .map_err(|_| Error::PinnedDatasetsLockFailed)
+                .and_then(|mut pinned_datasets| {
+                    pinned_datasets.insert(&name, dataset).map_err(Into::into)
+                }),
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = pinned {
+            eprintln!("Unable to pin dataset `{}`: {}", name, error);
+        }
+    }
+}
+
+/// Query all features of the `definition`'s workflow within its extent
+async fn load_pinned_dataset(
+    definition: &PinnedDatasetDefinition,
+    resolver: Arc<dyn WorkflowResolver>,
+    pinned_datasets: &SharedPinnedDatasets,
+) -> Result<PinnedDataset> {
+    let operator = match definition.workflow.operator.clone() {
+        TypedOperator::Vector(operator) => operator,
+        TypedOperator::Raster(_) => {
+            return Err(Error::InvalidWorkflowResultType {
+                expected: "Vector".to_string(),
+                found: "Raster".to_string(),
+                hint: "Only the features of vector workflows can be pinned".to_string(),
+            })
+        }
+        TypedOperator::Plot(_) => {
+            return Err(Error::InvalidWorkflowResultType {
+                expected: "Vector".to_string(),
+                found: "Plot".to_string(),
+                hint: "Only the features of vector workflows can be pinned".to_string(),
+            })
+        }
+    };
+
+    let execution_context = ExecutionContext {
+        pinned_datasets: Some(pinned_datasets.clone()),
+        workflow_resolver: Some(resolver),
+        principal: Principal::System,
+        ..ExecutionContext::mock_empty()
+    };
+    let initialized = operator
+        .initialize(&execution_context)
+        .context(error::Operator)?;
+
+    let result_descriptor = initialized.result_descriptor();
+    let query = QueryRectangle {
+        bbox: definition
+            .bbox
+            .or(result_descriptor.bbox)
+            .context(error::UnknownPinnedDatasetExtent)?,
+        time_interval: definition
+            .time_interval
+            .or(result_descriptor.time_interval)
+            .unwrap_or_else(TimeInterval::default),
+        spatial_resolution: SpatialResolution::zero_point_one(),
+    };
+    let query_ctx = QueryContext {
+        chunk_byte_size: 1024 * 1024,
+        timeout: None,
+        seed: 0,
+    };
+
+    let processor = initialized.query_processor().context(error::Operator)?;
+    let collection = PinnedCollection::load(processor, query, query_ctx)
+        .await
+        .context(error::Operator)?;
+
+    Ok(PinnedDataset {
+        collection,
+        // the features outside of the query were not loaded
+        result_descriptor: VectorResultDescriptor {
+            bbox: Some(query.bbox),
+            time_interval: Some(query.time_interval),
+            ..result_descriptor
+        },
+    })
+}
+
+fn read_definitions(file: &Path) -> Result<HashMap<String, PinnedDatasetDefinition>> {
+    if !file.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let reader = BufReader::new(File::open(file).context(error::IO)?);
+    serde_json::from_reader(reader).context(error::SerdeJson)
+}
+
+fn write_definitions(
+    file: &Path,
+    definitions: &HashMap<String, PinnedDatasetDefinition>,
+) -> Result<()> {
+    let writer = BufWriter::new(File::create(file).context(error::IO)?);
+    serde_json::to_writer_pretty(writer, definitions).context(error::SerdeJson)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflows::registry::HashMapRegistry;
+    use geoengine_datatypes::primitives::Coordinate2D;
+    use geoengine_operators::engine::VectorOperator;
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+    use geoengine_operators::source::PinnedDatasets;
+
+    fn definition() -> PinnedDatasetDefinition {
+        PinnedDatasetDefinition {
+            workflow: Workflow {
+                operator: TypedOperator::Vector(
+                    MockPointSource {
+                        params: MockPointSourceParams {
+                            points: vec![Coordinate2D::new(1., 2.); 3],
+                        },
+                    }
+                    .boxed(),
+                ),
+            },
+            bbox: Some(BoundingBox2D::new((0., 0.).into(), (10., 10.).into()).unwrap()),
+            time_interval: None,
+        }
+    }
+
+    fn shared(pinned_datasets: PinnedDatasets) -> SharedPinnedDatasets {
+        Arc::new(std::sync::RwLock::new(pinned_datasets))
+    }
+
+    #[tokio::test]
+    async fn pins_and_unpins_datasets() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("pinned_datasets.json");
+        let pinned_datasets = shared(PinnedDatasets::new(1024 * 1024));
+
+        let info = pin_dataset(
+            "points",
+            definition(),
+            Arc::new(WorkflowSnapshot::default()),
+            &pinned_datasets,
+            &file,
+        )
+        .await
+        .unwrap();
+        assert_eq!(info.name, "points");
+        assert_eq!(info.data_type, VectorDataType::MultiPoint);
+        assert_eq!(info.features, 3);
+        assert_eq!(pinned_dataset_infos(&pinned_datasets).unwrap(), vec![info]);
+
+        // the stored definitions are pinned again after a restart
+        let restarted = shared(PinnedDatasets::new(1024 * 1024));
+        pin_stored_datasets(
+            Arc::new(RwLock::new(HashMapRegistry::default())),
+            restarted.clone(),
+            file.clone(),
+        )
+        .await;
+        assert!(restarted.read().unwrap().get("points").is_some());
+
+        assert!(unpin_dataset("points", &pinned_datasets, &file).unwrap());
+        assert!(!unpin_dataset("points", &pinned_datasets, &file).unwrap());
+        assert!(pinned_dataset_infos(&pinned_datasets).unwrap().is_empty());
+        assert!(read_definitions(&file).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn does_not_exceed_memory_limit() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("pinned_datasets.json");
+        let pinned_datasets = shared(PinnedDatasets::new(0));
+
+        assert!(pin_dataset(
+            "points",
+            definition(),
+            Arc::new(WorkflowSnapshot::default()),
+            &pinned_datasets,
+            &file,
+        )
+        .await
+        .is_err());
+
+        // the definition of a dataset that was not pinned is not stored
+        assert!(read_definitions(&file).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::datasets::{gdal_dataset_pool, SharedDatasetDefinitions, SharedDatasetStatistics};
+use crate::error;
+use crate::error::{Error, Result};
+use geoengine_datatypes::primitives::{SpatialResolution, TimeInterval};
+use geoengine_operators::call_on_generic_raster_processor;
+use geoengine_operators::engine::{
+    ExecutionContext, InitializedOperatorBase, Principal, QueryContext, QueryRectangle,
+    RasterOperator,
+};
+use geoengine_operators::source::{GdalSource, GdalSourceParameters, RasterDatasetStatistics};
+use snafu::ResultExt;
+use std::time::Duration;
+
+/// Periodically compute the statistics of the raster datasets that have none yet and forget the
+/// statistics of removed datasets
+pub async fn compute_statistics_periodically(
+    definitions: SharedDatasetDefinitions,
+    statistics: SharedDatasetStatistics,
+    sample_size: usize,
+    bins: usize,
+    period: Duration,
+) {
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(error) =
+            compute_missing_statistics(&definitions, &statistics, sample_size, bins).await
+        {
+            eprintln!("Unable to compute dataset statistics: {}", error);
+        }
+    }
+}
+
+/// Compute the statistics of the raster datasets that have none yet and return their ids.
+/// Datasets that cannot be read are reported on stderr and tried again next time.
+pub async fn compute_missing_statistics(
+    definitions: &SharedDatasetDefinitions,
+    statistics: &SharedDatasetStatistics,
+    sample_size: usize,
+    bins: usize,
+) -> Result<Vec<String>> {
+    let ids: Vec<String> = definitions
+        .read()
+        .map_err(|_| Error::DatasetDefinitionsLockFailed)?
+        .ids()
+        .cloned()
+        .collect();
+
+    let missing: Vec<String> = {
+        let mut statistics = statistics
+            .write()
+            .map_err(|_| Error::DatasetStatisticsLockFailed)?;
+
+        let removed: Vec<String> = statistics
+            .ids()
+            .filter(|id| !ids.contains(id))
+            .cloned()
+            .collect();
+        for id in removed {
+            statistics.remove(&id)?;
+        }
+
+        ids.into_iter()
+            .filter(|id| !statistics.contains(id))
+            .collect()
+    };
+
+    let mut computed = Vec::new();
+    for id in missing {
+        match compute_statistics(&id, definitions, sample_size, bins).await {
+            Ok(Some(dataset_statistics)) => {
+                statistics
+                    .write()
+                    .map_err(|_| Error::DatasetStatisticsLockFailed)?
+                    .insert(&id, dataset_statistics)?;
+                computed.push(id);
+            }
+            Ok(None) => {}
+            Err(error) => eprintln!(
+                "Unable to compute statistics of dataset `{}`: {}",
+                id, error
+            ),
+        }
+    }
+
+    Ok(computed)
+}
+
+/// Compute the statistics of a raster dataset from a sample of at most `sample_size` pixels
+/// along each axis of its extent, `None` if its extent is unknown or it has no valid pixels
+async fn compute_statistics(
+    id: &str,
+    definitions: &SharedDatasetDefinitions,
+    sample_size: usize,
+    bins: usize,
+) -> Result<Option<RasterDatasetStatistics>> {
+    let raster_data_root = definitions
+        .read()
+        .map_err(|_| Error::DatasetDefinitionsLockFailed)?
+        .raster_data_root()
+        .to_path_buf();
+
+    let execution_context = ExecutionContext {
+        raster_data_root,
+        dataset_definitions: Some(definitions.clone()),
+        gdal_dataset_pool: Some(gdal_dataset_pool()?),
+        principal: Principal::System,
+        ..ExecutionContext::mock_empty()
+    };
+
+    let initialized = GdalSource {
+        params: GdalSourceParameters {
+            dataset_id: id.to_string(),
+            channel: None,
+            band: None,
+        },
+    }
+    .boxed()
+    .initialize(&execution_context)
+    .context(error::Operator)?;
+
+    let result_descriptor = initialized.result_descriptor();
+    let bbox = match result_descriptor.bbox {
+        Some(bbox) => bbox,
+        None => return Ok(None),
+    };
+
+    // GDAL reads the overviews of the dataset at coarse resolutions
+    let resolution = bbox.size_x().max(bbox.size_y()) / sample_size as f64;
+    let query = QueryRectangle {
+        bbox,
+        time_interval: result_descriptor
+            .time_interval
+            .unwrap_or_else(TimeInterval::default),
+        spatial_resolution: SpatialResolution::new(resolution, resolution)
+            .context(error::DataType)?,
+    };
+    let query_ctx = QueryContext {
+        chunk_byte_size: 1024,
+        timeout: None,
+        seed: 0,
+    };
+
+    let processor = initialized.query_processor().context(error::Operator)?;
+    let dataset_statistics = call_on_generic_raster_processor!(
+        processor,
+        p => RasterDatasetStatistics::compute(p.as_ref(), query, query_ctx, bins).await
+    )
+    .context(error::Operator)?;
+
+    Ok(dataset_statistics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geoengine_operators::source::{DatasetDefinitions, DatasetStatistics};
+    use std::path::Path;
+    use std::sync::{Arc, RwLock};
+
+    #[tokio::test]
+    async fn computes_missing_statistics() {
+        let definitions = Arc::new(RwLock::new(
+            DatasetDefinitions::load(Path::new("../operators/test-data/raster")).0,
+        ));
+        let directory = tempfile::tempdir().unwrap();
+        let statistics = Arc::new(RwLock::new(
+            DatasetStatistics::load(directory.path()).unwrap(),
+        ));
+        statistics
+            .write()
+            .unwrap()
+            .insert(
+                "removed",
+                RasterDatasetStatistics {
+                    min: 0.,
+                    max: 1.,
+                    mean: 0.5,
+                    valid_count: 2,
+                    histogram: vec![1, 1],
+                },
+            )
+            .unwrap();
+
+        let computed = compute_missing_statistics(&definitions, &statistics, 64, 16)
+            .await
+            .unwrap();
+        assert_eq!(computed, vec!["test".to_string()]);
+
+        let statistics = statistics.read().unwrap();
+        assert!(!statistics.contains("removed"));
+
+        let test = statistics.get("test").unwrap();
+        assert!(test.valid_count > 0);
+        assert!(test.min <= test.mean && test.mean <= test.max);
+        assert_eq!(test.histogram.len(), 16);
+        assert_eq!(test.histogram.iter().sum::<usize>(), test.valid_count);
+
+        // the statistics are stored, so they are not computed again after a restart
+        assert!(DatasetStatistics::load(directory.path())
+            .unwrap()
+            .contains("test"));
+    }
+}
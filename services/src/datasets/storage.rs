@@ -0,0 +1,229 @@
+use crate::datasets::upload::{list_uploads, used_storage, Upload, UploadId};
+use crate::error;
+use crate::error::Result;
+use crate::users::user::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The storage that the uploads of a user occupy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    pub used_bytes: u64,
+    /// The storage that the uploads may occupy, if it is limited
+    pub quota_bytes: Option<u64>,
+    pub uploads: usize,
+}
+
+impl StorageUsage {
+    pub fn of_user(directory: &Path, user: UserId, quota: Option<u64>) -> Result<Self> {
+        Ok(Self {
+            used_bytes: used_storage(directory, user)?,
+            quota_bytes: quota,
+            uploads: list_uploads(directory, user)?.len(),
+        })
+    }
+}
+
+/// The outcome of a garbage collection
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GarbageReport {
+    pub expired_uploads: Vec<UploadId>,
+    /// Leftovers of interrupted uploads, i.e., files without metadata and unreadable metadata
+    pub orphans: Vec<PathBuf>,
+    pub reclaimed_bytes: u64,
+}
+
+/// Remove the uploads that are older than the `ttl`, if any, and the leftovers of interrupted
+/// uploads that are older than the `orphan_ttl` from the `directory` of the uploads
+pub fn collect_garbage(
+    directory: &Path,
+    now: DateTime<Utc>,
+    ttl: Option<Duration>,
+    orphan_ttl: Duration,
+) -> Result<GarbageReport> {
+    let mut report = GarbageReport::default();
+    if !directory.is_dir() {
+        return Ok(report);
+    }
+
+    let expired = |time: DateTime<Utc>, ttl: Duration| {
+        chrono::Duration::from_std(ttl)
+            .ok()
+            .and_then(|ttl| time.checked_add_signed(ttl))
+            .map_or(false, |expiry| expiry <= now)
+    };
+
+    for user_directory in std::fs::read_dir(directory).context(error::IO)? {
+        let user_directory = user_directory.context(error::IO)?.path();
+        if !user_directory.is_dir() {
+            continue;
+        }
+
+        for entry in std::fs::read_dir(&user_directory).context(error::IO)? {
+            let path = entry.context(error::IO)?.path();
+            let modified: DateTime<Utc> = path
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .context(error::IO)?
+                .into();
+
+            if path.is_dir() {
+                if !path.with_extension("json").exists() && expired(modified, orphan_ttl) {
+                    report.reclaimed_bytes += directory_size(&path)?;
+                    std::fs::remove_dir_all(&path).context(error::IO)?;
+                    report.orphans.push(path);
+                }
+                continue;
+            }
+
+            match read_upload(&path) {
+                Some(upload) if path.with_extension("").is_dir() => {
+                    if ttl.map_or(false, |ttl| expired(upload.created, ttl)) {
+                        std::fs::remove_dir_all(path.with_extension("")).context(error::IO)?;
+                        std::fs::remove_file(&path).context(error::IO)?;
+                        report.reclaimed_bytes += upload.size;
+                        report.expired_uploads.push(upload.id);
+                    }
+                }
+                _ => {
+                    if expired(modified, orphan_ttl) {
+                        report.reclaimed_bytes += path.metadata().context(error::IO)?.len();
+                        std::fs::remove_file(&path).context(error::IO)?;
+                        report.orphans.push(path);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn read_upload(path: &Path) -> Option<Upload> {
+    if path
+        .extension()
+        .map_or(true, |extension| extension != "json")
+    {
+        return None;
+    }
+
+    let file = File::open(path).ok()?;
+    serde_json::from_reader(BufReader::new(file)).ok()
+}
+
+fn directory_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path).context(error::IO)? {
+        let path = entry.context(error::IO)?.path();
+        size += if path.is_dir() {
+            directory_size(&path)?
+        } else {
+            path.metadata().context(error::IO)?.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Periodically remove expired uploads and the leftovers of interrupted uploads
+pub async fn collect_garbage_periodically(
+    directory: PathBuf,
+    period: Duration,
+    ttl: Option<Duration>,
+    orphan_ttl: Duration,
+) {
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        let directory = directory.clone();
+        let collection = tokio::task::spawn_blocking(move || {
+            collect_garbage(&directory, Utc::now(), ttl, orphan_ttl)
+        })
+        .await;
+
+        match collection {
+            Ok(Ok(report)) => {
+                if report.reclaimed_bytes > 0 {
+                    eprintln!(
+                        "Removed {} expired uploads and {} orphans, reclaimed {} bytes",
+                        report.expired_uploads.len(),
+                        report.orphans.len(),
+                        report.reclaimed_bytes
+                    );
+                }
+            }
+            Ok(Err(error)) => eprintln!("Unable to collect garbage of uploads: {}", error),
+            Err(error) => eprintln!("Unable to collect garbage of uploads: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::upload::{store_upload, UploadFormat, UploadLimits};
+    use crate::util::identifiers::Identifier;
+
+    const GEOJSON: &[u8] = br#"{ "type": "FeatureCollection", "features": [] }"#;
+
+    #[tokio::test]
+    async fn garbage_collection() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = UserId::new();
+
+        let upload = store_upload(
+            dir.path().to_path_buf(),
+            user,
+            "points.geojson".to_string(),
+            UploadFormat::GeoJson,
+            GEOJSON.to_vec(),
+            UploadLimits {
+                max_file_size: 1024,
+                quota: None,
+            },
+        )
+        .await
+        .unwrap()
+        .upload
+        .unwrap();
+
+        // the leftover of an upload that was interrupted before its metadata was written
+        let orphan = dir.path().join(user.to_string()).join("orphan");
+        std::fs::create_dir_all(&orphan).unwrap();
+        std::fs::write(orphan.join("points.geojson"), GEOJSON).unwrap();
+
+        let hour = Duration::from_secs(3600);
+        let now = Utc::now();
+
+        let report = collect_garbage(dir.path(), now, None, hour).unwrap();
+        assert_eq!(report, GarbageReport::default());
+
+        let later = now + chrono::Duration::hours(2);
+        let report = collect_garbage(dir.path(), later, None, hour).unwrap();
+        assert!(report.expired_uploads.is_empty());
+        assert_eq!(report.orphans, vec![orphan.clone()]);
+        assert_eq!(report.reclaimed_bytes, GEOJSON.len() as u64);
+        assert!(!orphan.exists());
+
+        let usage = StorageUsage::of_user(dir.path(), user, Some(1024)).unwrap();
+        assert_eq!(usage.uploads, 1);
+        assert_eq!(usage.used_bytes, upload.size);
+
+        let report = collect_garbage(dir.path(), later, Some(hour), hour).unwrap();
+        assert_eq!(report.expired_uploads, vec![upload.id]);
+        assert_eq!(
+            StorageUsage::of_user(dir.path(), user, Some(1024))
+                .unwrap()
+                .uploads,
+            0
+        );
+    }
+}
@@ -0,0 +1,591 @@
+use crate::error;
+use crate::error::Result;
+use crate::users::user::UserId;
+use crate::util::identifiers::Identifier;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+identifier!(UploadId);
+
+lazy_static! {
+    /// The bytes of the uploads that are being stored, per user.
+    /// They count against the quota until the metadata of the upload is written.
+    static ref UPLOAD_RESERVATIONS: Mutex<HashMap<UserId, u64>> = Mutex::new(HashMap::new());
+}
+
+/// The formats of files that users may upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadFormat {
+    GeoTiff,
+    GeoJson,
+    Csv,
+}
+
+impl UploadFormat {
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            UploadFormat::GeoTiff => &["tif", "tiff"],
+            UploadFormat::GeoJson => &["geojson", "json"],
+            UploadFormat::Csv => &["csv"],
+        }
+    }
+
+    fn is_raster(self) -> bool {
+        self == UploadFormat::GeoTiff
+    }
+}
+
+/// The checks of an upload, in the order they are applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValidationCheck {
+    Name,
+    Size,
+    Extension,
+    Content,
+    Quota,
+    Open,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationProblem {
+    pub check: ValidationCheck,
+    pub message: String,
+}
+
+/// The problems of an upload, which is valid if there are none
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub problems: Vec<ValidationProblem>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn push(&mut self, check: ValidationCheck, message: impl Into<String>) {
+        self.problems.push(ValidationProblem {
+            check,
+            message: message.into(),
+        });
+    }
+}
+
+/// A validated file of a user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Upload {
+    pub id: UploadId,
+    pub user: UserId,
+    pub name: String,
+    pub format: UploadFormat,
+    pub size: u64,
+    pub created: DateTime<Utc>,
+}
+
+/// The limits of the uploads of a user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadLimits {
+    pub max_file_size: u64,
+    /// The storage that all uploads of the user may occupy, if it is limited
+    pub quota: Option<u64>,
+}
+
+/// The stored upload, if it is valid, and the report of its validation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadResponse {
+    pub upload: Option<Upload>,
+    pub report: ValidationReport,
+}
+
+/// Leading bytes of files that must never be stored, regardless of the declared format
+const FORBIDDEN_SIGNATURES: &[(&[u8], &str)] = &[
+    (b"MZ", "a Windows executable"),
+    (b"\x7fELF", "an ELF executable"),
+    (b"\xcf\xfa\xed\xfe", "a Mach-O executable"),
+    (b"#!", "a script"),
+];
+
+/// Check the name, size, extension and content of an upload before it is written to disk
+pub fn check_content(
+    name: &str,
+    format: UploadFormat,
+    content: &[u8],
+    max_size: u64,
+) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    if name.starts_with('.') || Path::new(name).file_name().and_then(|n| n.to_str()) != Some(name) {
+        report.push(
+            ValidationCheck::Name,
+            format!("`{}` is not a valid file name", name),
+        );
+    }
+
+    if content.is_empty() {
+        report.push(ValidationCheck::Size, "the file is empty");
+    } else if content.len() as u64 > max_size {
+        report.push(
+            ValidationCheck::Size,
+            format!("the file is larger than {} bytes", max_size),
+        );
+    }
+
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    if !extension.map_or(false, |extension| {
+        format.extensions().contains(&extension.as_str())
+    }) {
+        report.push(
+            ValidationCheck::Extension,
+            format!(
+                "the extension must be one of `{}` for {:?}",
+                format.extensions().join("`, `"),
+                format
+            ),
+        );
+    }
+
+    if let Some(problem) = sniff(format, content) {
+        report.push(ValidationCheck::Content, problem);
+    }
+
+    report
+}
+
+/// Compare the leading bytes of the `content` with the declared `format`
+fn sniff(format: UploadFormat, content: &[u8]) -> Option<String> {
+    if let Some((_, kind)) = FORBIDDEN_SIGNATURES
+        .iter()
+        .find(|(signature, _)| content.starts_with(signature))
+    {
+        return Some(format!("the content is {}", kind));
+    }
+
+    let text = || {
+        let sample = &content[..content.len().min(4096)];
+        match std::str::from_utf8(sample) {
+            Ok(text) => Some(text),
+            // the sample may end within a multi-byte character
+            Err(error) if error.error_len().is_none() => {
+                std::str::from_utf8(&sample[..error.valid_up_to()]).ok()
+            }
+            Err(_) => None,
+        }
+    };
+
+    match format {
+        UploadFormat::GeoTiff => {
+            if content.starts_with(b"II*\0")
+                || content.starts_with(b"MM\0*")
+                || content.starts_with(b"II+\0")
+                || content.starts_with(b"MM\0+")
+            {
+                None
+            } else {
+                Some("the content is not a TIFF file".to_string())
+            }
+        }
+        UploadFormat::GeoJson => match text() {
+            Some(text)
+                if text
+                    .trim_start_matches('\u{feff}')
+                    .trim_start()
+                    .starts_with('{') =>
+            {
+                None
+            }
+            _ => Some("the content is not a JSON object".to_string()),
+        },
+        UploadFormat::Csv => match text() {
+            Some(text) if !text.contains('\0') => None,
+            _ => Some("the content is not UTF-8 text".to_string()),
+        },
+    }
+}
+
+/// Open the file with GDAL or OGR, like a query would
+fn probe(path: &Path, format: UploadFormat) -> Option<String> {
+    if format.is_raster() {
+        match gdal::raster::Dataset::open(path) {
+            Ok(dataset) if dataset.count() > 0 => None,
+            Ok(_) => Some("the raster has no bands".to_string()),
+            Err(error) => Some(error.to_string()),
+        }
+    } else {
+        match gdal::vector::Dataset::open(path) {
+            Ok(dataset) if dataset.count() > 0 => None,
+            Ok(_) => Some("the file has no layers".to_string()),
+            Err(error) => Some(error.to_string()),
+        }
+    }
+}
+
+/// The directory of the uploads of a `user`
+pub fn user_directory(directory: &Path, user: UserId) -> PathBuf {
+    directory.join(user.to_string())
+}
+
+/// Storage of a user that is reserved for an upload that is being stored
+struct QuotaReservation {
+    user: UserId,
+    size: u64,
+}
+
+impl QuotaReservation {
+    /// Reserve `size` bytes for an upload of the `user`, if the stored uploads and the other
+    /// reservations of the `user` leave enough of the `quota`.
+    /// Otherwise, return the bytes that are already used.
+    fn reserve(
+        directory: &Path,
+        user: UserId,
+        size: u64,
+        quota: u64,
+    ) -> Result<std::result::Result<Self, u64>> {
+        let mut reservations = UPLOAD_RESERVATIONS
+            .lock()
+            .map_err(|_| error::Error::UploadReservationsLockFailed)?;
+
+        let reserved = reservations.get(&user).copied().unwrap_or_default();
+        let used = used_storage(directory, user)? + reserved;
+        if used + size > quota {
+            return Ok(Err(used));
+        }
+
+        reservations.insert(user, reserved + size);
+
+        Ok(Ok(Self { user, size }))
+    }
+}
+
+impl Drop for QuotaReservation {
+    fn drop(&mut self) {
+        // a poisoned lock cannot be repaired, so the reservation is kept
+        if let Ok(mut reservations) = UPLOAD_RESERVATIONS.lock() {
+            if let Some(reserved) = reservations.get_mut(&self.user) {
+                *reserved = reserved.saturating_sub(self.size);
+                if *reserved == 0 {
+                    reservations.remove(&self.user);
+                }
+            }
+        }
+    }
+}
+
+/// Validate an upload and store it in the `directory` of the uploads if it is valid and fits
+/// into the quota of the `user`.
+///
+/// The storage of the upload is reserved before it is written, so that simultaneous uploads
+/// cannot exceed the quota together. The file is opened with GDAL in a blocking task, so that
+/// a driver that panics on a malformed file only fails the validation. Drivers that crash the
+/// process, e.g., by a segmentation fault, are not isolated.
+pub async fn store_upload(
+    directory: PathBuf,
+    user: UserId,
+    name: String,
+    format: UploadFormat,
+    content: Vec<u8>,
+    limits: UploadLimits,
+) -> Result<UploadResponse> {
+    let mut report = check_content(&name, format, &content, limits.max_file_size);
+
+    let mut reservation = None;
+    if let Some(quota) = limits.quota {
+        let reservation_directory = directory.clone();
+        let size = content.len() as u64;
+        match tokio::task::spawn_blocking(move || {
+            QuotaReservation::reserve(&reservation_directory, user, size, quota)
+        })
+        .await
+        .context(error::TokioJoin)??
+        {
+            Ok(reserved) => reservation = Some(reserved),
+            Err(used) => report.push(
+                ValidationCheck::Quota,
+                format!(
+                    "the upload exceeds the storage quota of {} bytes, of which {} bytes are used",
+                    quota, used
+                ),
+            ),
+        }
+    }
+
+    if !report.is_valid() {
+        return Ok(UploadResponse {
+            upload: None,
+            report,
+        });
+    }
+
+    let upload = Upload {
+        id: UploadId::new(),
+        user,
+        name,
+        format,
+        size: content.len() as u64,
+        created: Utc::now(),
+    };
+    let upload_directory = user_directory(&directory, user).join(upload.id.to_string());
+    let path = upload_directory.join(&upload.name);
+
+    let write_path = path.clone();
+    tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(write_path.parent().expect("the upload has a directory"))?;
+        File::create(&write_path)?.write_all(&content)
+    })
+    .await
+    .context(error::TokioJoin)?
+    .context(error::IO)?;
+
+    let probe_path = path.clone();
+    let problem = tokio::task::spawn_blocking(move || probe(&probe_path, format))
+        .await
+        .unwrap_or_else(|_| Some("the file crashed its driver".to_string()));
+
+    if let Some(problem) = problem {
+        report.push(ValidationCheck::Open, problem);
+        tokio::task::spawn_blocking(move || std::fs::remove_dir_all(upload_directory))
+            .await
+            .context(error::TokioJoin)?
+            .context(error::IO)?;
+        return Ok(UploadResponse {
+            upload: None,
+            report,
+        });
+    }
+
+    let metadata = serde_json::to_vec(&upload).context(error::SerdeJson)?;
+    tokio::task::spawn_blocking(move || {
+        std::fs::write(upload_directory.with_extension("json"), metadata)
+    })
+    .await
+    .context(error::TokioJoin)?
+    .context(error::IO)?;
+
+    // the upload counts against the quota by its metadata from now on
+    drop(reservation);
+
+    Ok(UploadResponse {
+        upload: Some(upload),
+        report,
+    })
+}
+
+/// The uploads of a `user` in the `directory` of the uploads, sorted by their creation
+pub fn list_uploads(directory: &Path, user: UserId) -> Result<Vec<Upload>> {
+    let user_directory = user_directory(directory, user);
+    if !user_directory.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut uploads = Vec::new();
+    for entry in std::fs::read_dir(user_directory).context(error::IO)? {
+        let path = entry.context(error::IO)?.path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "json")
+        {
+            continue;
+        }
+
+        // unreadable metadata of interrupted uploads is left to the garbage collection
+        let file = File::open(&path).context(error::IO)?;
+        if let Ok(upload) = serde_json::from_reader(BufReader::new(file)) {
+            uploads.push(upload);
+        }
+    }
+
+    uploads.sort_by_key(|upload: &Upload| upload.created);
+
+    Ok(uploads)
+}
+
+/// The bytes that the uploads of a `user` occupy
+pub fn used_storage(directory: &Path, user: UserId) -> Result<u64> {
+    Ok(list_uploads(directory, user)?
+        .iter()
+        .map(|upload| upload.size)
+        .sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GEOJSON: &[u8] = br#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [1.0, 2.0] },
+                "properties": {}
+            }
+        ]
+    }"#;
+
+    fn limits(quota: Option<u64>) -> UploadLimits {
+        UploadLimits {
+            max_file_size: 1024,
+            quota,
+        }
+    }
+
+    #[test]
+    fn content_checks() {
+        assert!(check_content("points.geojson", UploadFormat::GeoJson, GEOJSON, 1024).is_valid());
+
+        let checks = |name, format, content: &[u8], max_size| {
+            check_content(name, format, content, max_size)
+                .problems
+                .into_iter()
+                .map(|problem| problem.check)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            checks("points.tif", UploadFormat::GeoJson, GEOJSON, 1024),
+            vec![ValidationCheck::Extension]
+        );
+        assert_eq!(
+            checks("points.geojson", UploadFormat::GeoJson, GEOJSON, 16),
+            vec![ValidationCheck::Size]
+        );
+        assert_eq!(
+            checks("../points.geojson", UploadFormat::GeoJson, GEOJSON, 1024),
+            vec![ValidationCheck::Name]
+        );
+        assert_eq!(
+            checks("points.csv", UploadFormat::Csv, b"\x7fELF\x02\x01", 1024),
+            vec![ValidationCheck::Content]
+        );
+        assert_eq!(
+            checks("raster.TIF", UploadFormat::GeoTiff, GEOJSON, 1024),
+            vec![ValidationCheck::Content]
+        );
+        assert_eq!(
+            checks("points.csv", UploadFormat::Csv, b"", 1024),
+            vec![ValidationCheck::Size]
+        );
+    }
+
+    #[tokio::test]
+    async fn store_valid_upload() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = UserId::new();
+
+        let response = store_upload(
+            dir.path().to_path_buf(),
+            user,
+            "points.geojson".to_string(),
+            UploadFormat::GeoJson,
+            GEOJSON.to_vec(),
+            limits(None),
+        )
+        .await
+        .unwrap();
+
+        assert!(response.report.is_valid());
+        let upload = response.upload.unwrap();
+        assert_eq!(upload.size, GEOJSON.len() as u64);
+        assert!(user_directory(dir.path(), user)
+            .join(upload.id.to_string())
+            .join("points.geojson")
+            .is_file());
+
+        assert_eq!(list_uploads(dir.path(), user).unwrap(), vec![upload]);
+        assert!(list_uploads(dir.path(), UserId::new()).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unreadable_upload_is_not_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = UserId::new();
+
+        // a TIFF header without any image file directory
+        let response = store_upload(
+            dir.path().to_path_buf(),
+            user,
+            "raster.tif".to_string(),
+            UploadFormat::GeoTiff,
+            b"II*\0\0\0\0\0".to_vec(),
+            limits(None),
+        )
+        .await
+        .unwrap();
+
+        assert!(response.upload.is_none());
+        assert_eq!(response.report.problems.len(), 1);
+        assert_eq!(response.report.problems[0].check, ValidationCheck::Open);
+        assert!(list_uploads(dir.path(), user).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn quota() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = UserId::new();
+        let quota = Some(GEOJSON.len() as u64 * 3 / 2);
+
+        let upload = || {
+            store_upload(
+                dir.path().to_path_buf(),
+                user,
+                "points.geojson".to_string(),
+                UploadFormat::GeoJson,
+                GEOJSON.to_vec(),
+                limits(quota),
+            )
+        };
+
+        assert!(upload().await.unwrap().upload.is_some());
+
+        let response = upload().await.unwrap();
+        assert!(response.upload.is_none());
+        assert_eq!(response.report.problems[0].check, ValidationCheck::Quota);
+
+        assert_eq!(
+            used_storage(dir.path(), user).unwrap(),
+            GEOJSON.len() as u64
+        );
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn simultaneous_uploads_share_the_quota() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = UserId::new();
+        let quota = Some(GEOJSON.len() as u64 * 3 / 2);
+
+        let upload = || {
+            store_upload(
+                dir.path().to_path_buf(),
+                user,
+                "points.geojson".to_string(),
+                UploadFormat::GeoJson,
+                GEOJSON.to_vec(),
+                limits(quota),
+            )
+        };
+
+        let (first, second) = futures::join!(upload(), upload());
+        let stored = vec![first.unwrap(), second.unwrap()]
+            .into_iter()
+            .filter(|response| response.upload.is_some())
+            .count();
+
+        assert_eq!(stored, 1);
+        assert_eq!(
+            used_storage(dir.path(), user).unwrap(),
+            GEOJSON.len() as u64
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use crate::datasets::SharedDatasetDefinitions;
+use crate::error;
+use crate::error::{Error, Result};
+use geoengine_operators::source::ReloadReport;
+use snafu::ResultExt;
+use std::time::Duration;
+
+/// Periodically check the dataset definitions for changes on disk and reload them if necessary
+pub async fn watch_dataset_definitions(definitions: SharedDatasetDefinitions, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        match reload_if_outdated(definitions.clone()).await {
+            Ok(Some(report)) => {
+                for failed in report.failed {
+                    eprintln!(
+                        "Keeping previous version of dataset definition `{}`: {}",
+                        failed.id, failed.reason
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(error) => eprintln!("Unable to reload dataset definitions: {}", error),
+        }
+    }
+}
+
+async fn reload_if_outdated(definitions: SharedDatasetDefinitions) -> Result<Option<ReloadReport>> {
+    tokio::task::spawn_blocking(move || {
+        let outdated = definitions
+            .read()
+            .map_err(|_| Error::DatasetDefinitionsLockFailed)?
+            .is_outdated();
+
+        if !outdated {
+            return Ok(None);
+        }
+
+        let report = definitions
+            .write()
+            .map_err(|_| Error::DatasetDefinitionsLockFailed)?
+            .reload();
+
+        Ok(Some(report))
+    })
+    .await
+    .context(error::TokioJoin)?
+}
+
+/// Reload all changed dataset definitions
+pub async fn reload_dataset_definitions(
+    definitions: SharedDatasetDefinitions,
+) -> Result<ReloadReport> {
+    tokio::task::spawn_blocking(move || {
+        Ok(definitions
+            .write()
+            .map_err(|_| Error::DatasetDefinitionsLockFailed)?
+            .reload())
+    })
+    .await
+    .context(error::TokioJoin)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::load_dataset_definitions;
+
+    #[tokio::test]
+    async fn reload() {
+        let report = reload_dataset_definitions(load_dataset_definitions().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(report.unchanged, vec!["test".to_string()]);
+        assert!(report.failed.is_empty());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::users::user::PasswordViolation;
+use crate::workflows::workflow::WorkflowId;
 use snafu::Snafu;
 use warp::reject::Reject;
 
@@ -11,6 +13,11 @@ pub enum Error {
     Operator {
         source: geoengine_operators::error::Error,
     },
+    #[snafu(display("Workflow `{}` failed", workflow_id))]
+    WorkflowOperator {
+        workflow_id: WorkflowId,
+        source: geoengine_operators::error::Error,
+    },
     HTTP {
         source: warp::http::Error,
     },
@@ -23,6 +30,14 @@ pub enum Error {
     IO {
         source: std::io::Error,
     },
+    #[cfg(feature = "postgres")]
+    Postgres {
+        source: r2d2_postgres::postgres::Error,
+    },
+    #[cfg(feature = "postgres")]
+    PostgresPool {
+        source: r2d2_postgres::r2d2::Error,
+    },
     TokioJoin {
         source: tokio::task::JoinError,
     },
@@ -39,14 +54,59 @@ pub enum Error {
 
     ServerStartup,
 
+    Config {
+        source: config::ConfigError,
+    },
+    ConfigLockFailed,
+    GdalDatasetPoolLockFailed,
+    QueryAdmissionLockFailed,
+    AuditLogLockFailed,
+    GlobalStatisticsLockFailed,
+    UploadReservationsLockFailed,
+    #[snafu(display("The audit log sink does not support queries"))]
+    AuditLogNotQueryable,
+    #[snafu(display("Invalid configuration:\n{}", problems.join("\n")))]
+    InvalidConfiguration {
+        problems: Vec<String>,
+    },
+    #[snafu(display(
+        "`{}` is not supported by the in-memory backend, enable `[postgres]` in the settings",
+        command
+    ))]
+    UnsupportedByBackend {
+        command: String,
+    },
+    #[snafu(display("The password of the new user is missing, set `{}`", variable))]
+    MissingPassword {
+        variable: String,
+    },
+
     #[snafu(display("Registration failed: {:?}", reason))]
     RegistrationFailed {
         reason: String,
     },
+    #[snafu(display(
+        "Invalid password: {}",
+        violations
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))]
+    InvalidPassword {
+        violations: Vec<PasswordViolation>,
+    },
     LoginFailed,
     LogoutFailed,
     SessionDoesNotExist,
+    #[snafu(display("The session expired"))]
+    SessionExpired,
+    UserDoesNotExist,
     InvalidSessionToken,
+    #[snafu(display("Invalid user settings: {}", reason))]
+    InvalidUserSettings {
+        reason: String,
+    },
 
     ProjectCreateFailed,
     ProjectListFailed,
@@ -54,12 +114,118 @@ pub enum Error {
     ProjectUpdateFailed,
     ProjectDeleteFailed,
     PermissionFailed,
+    #[snafu(display("Project import failed: {:?}", reason))]
+    ProjectImportFailed {
+        reason: String,
+    },
 
     InvalidNamespace,
 
+    #[snafu(display("The query was cancelled after exceeding its timeout of {:?}", timeout))]
+    QueryTimeout {
+        timeout: std::time::Duration,
+    },
+
+    #[snafu(display(
+        "Too many queries of this client, retry in {} seconds",
+        retry_after_seconds
+    ))]
+    TooManyQueries {
+        retry_after_seconds: u64,
+    },
+
+    #[snafu(display("Invalid vector style: {}", details))]
+    InvalidVectorStyle {
+        details: String,
+    },
+
+    #[snafu(display("Invalid raster style: {}", details))]
+    InvalidRasterStyle {
+        details: String,
+    },
+
+    #[snafu(display("The supersampling factor must be between 1 and {}", max))]
+    InvalidSupersampling {
+        max: u32,
+    },
+    #[snafu(display("The map resolution must be a positive number of dots per inch"))]
+    InvalidMapResolution,
+    #[snafu(display("Invalid background color `{}`, expected `0xRRGGBB`", value))]
+    InvalidBackgroundColor {
+        value: String,
+    },
+    #[snafu(display("The width and height of images must be at most {} pixels", max))]
+    ImageTooLarge {
+        max: u32,
+    },
+    #[snafu(display(
+        "Unknown map decoration `{}`, expected `attribution`, `scalebar` or `watermark`",
+        name
+    ))]
+    InvalidMapDecoration {
+        name: String,
+    },
+    NoGeometriesToRender,
+    #[snafu(display("Invalid animation: {}", details))]
+    InvalidAnimation {
+        details: String,
+    },
+    Image {
+        source: image::ImageError,
+    },
+
     InvalidWFSTypeNames,
 
     NoWorkflowForGivenId,
+    #[snafu(display("Access to the workflow `{}` is denied", workflow_id))]
+    WorkflowAccessDenied {
+        workflow_id: WorkflowId,
+    },
+    #[snafu(display("Share links are disabled, since no secret is configured"))]
+    ShareLinksDisabled,
+    #[snafu(display("The share link is invalid"))]
+    InvalidShareLink,
+    #[snafu(display("The share link expired at {}", expires))]
+    ShareLinkExpired {
+        expires: chrono::DateTime<chrono::Utc>,
+    },
+    #[snafu(display("Share links are valid for at most {} seconds", max))]
+    InvalidShareLinkValidity {
+        max: u64,
+    },
+    #[snafu(display(
+        "The workflow has the result type {} instead of {}. {}",
+        found,
+        expected,
+        hint
+    ))]
+    InvalidWorkflowResultType {
+        expected: String,
+        found: String,
+        hint: String,
+    },
+
+    DatasetDefinitionsLockFailed,
+    DatasetStatisticsLockFailed,
+    #[snafu(display("Only the owner of the dataset `{}` may delete it", id))]
+    DatasetDeletionDenied {
+        id: String,
+    },
+    #[snafu(display(
+        "Public workflows or the workflows or projects of other users use the dataset `{}`",
+        id
+    ))]
+    DatasetUsedByOthers {
+        id: String,
+    },
+    #[snafu(display("Uploads must not be larger than {} bytes", max))]
+    UploadTooLarge {
+        max: u64,
+    },
+
+    PinnedDatasetsLockFailed,
+    #[snafu(display("The extent of the workflow is unknown, so pinning it requires a `bbox`"))]
+    UnknownPinnedDatasetExtent,
 }
 
 impl Reject for Error {}
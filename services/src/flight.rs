@@ -0,0 +1,492 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
+};
+use futures::channel::mpsc;
+use futures::{FutureExt, SinkExt, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tonic::{Request, Response, Status, Streaming};
+
+use geoengine_datatypes::collections::FeatureCollection;
+use geoengine_datatypes::primitives::{BoundingBox2D, SpatialResolution, TimeInterval};
+use geoengine_operators::engine::{
+    InitializedOperatorBase, QueryContext, QueryRectangle, ResultDescriptor, TypedOperator,
+    TypedVectorQueryProcessor, VectorQueryProcessor,
+};
+
+use crate::error::{Error, Result};
+use crate::handlers::{vector_execution_context, with_query_timeout, Requester};
+use crate::users::session::SessionToken;
+use crate::users::userdb::UserDB;
+use crate::util::admission::query_admission;
+use crate::util::config;
+use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::resolver::WorkflowSnapshot;
+use crate::workflows::workflow::WorkflowId;
+
+type FlightStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send + Sync + 'static>>;
+
+/// The ticket of a `DoGet` request, i.e., the JSON serialization of the workflow and the
+/// query rectangle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightTicket {
+    pub workflow: WorkflowId,
+    pub bbox: BoundingBox2D,
+    pub time_interval: TimeInterval,
+}
+
+/// Serves the results of vector workflows as Arrow record batches via Arrow Flight.
+///
+/// Only `DoGet` is supported. Every collection of the query is sent as one record batch,
+/// preceded by the schema of the first collection.
+///
+/// Like the WMS and WFS, requests with a session token as `authorization` metadata may query
+/// the private workflows of its user, while requests without one are anonymous.
+pub struct VectorFlightService<T: WorkflowRegistry, U: UserDB> {
+    workflow_registry: Arc<RwLock<T>>,
+    user_db: Arc<RwLock<U>>,
+}
+
+impl<T: WorkflowRegistry, U: UserDB> VectorFlightService<T, U> {
+    pub fn new(workflow_registry: Arc<RwLock<T>>, user_db: Arc<RwLock<U>>) -> Self {
+        Self {
+            workflow_registry,
+            user_db,
+        }
+    }
+
+    /// The requester of a request, i.e., the session of the token in the `authorization` metadata,
+    /// if any. Requests with an invalid token are rejected.
+    async fn requester<R>(&self, request: &Request<R>) -> Result<Requester, Status> {
+        let token = match request.metadata().get("authorization") {
+            Some(token) => token
+                .to_str()
+                .ok()
+                .and_then(|token| SessionToken::from_str(token).ok())
+                .ok_or_else(|| Status::unauthenticated(Error::InvalidSessionToken.to_string()))?,
+            None => return Ok(Requester::Anonymous),
+        };
+
+        self.user_db
+            .read()
+            .await
+            .session(token)
+            .map(Requester::Session)
+            .map_err(|error| Status::unauthenticated(error.to_string()))
+    }
+
+    async fn query(
+        &self,
+        ticket: &FlightTicket,
+        requester: &Requester,
+        client: String,
+    ) -> Result<mpsc::Receiver<Result<FlightData, Status>>> {
+        let (workflow, referenced_workflows) = {
+            let registry = self.workflow_registry.read().await;
+            let workflow = requester.load_workflow(&*registry, &ticket.workflow)?;
+            let referenced_workflows =
+                WorkflowSnapshot::collect(&*registry, requester, &workflow.operator);
+            (workflow, referenced_workflows)
+        };
+
+        let operator = match workflow.operator {
+            TypedOperator::Vector(operator) => operator,
+            TypedOperator::Raster(_) => {
+                return Err(Error::InvalidWorkflowResultType {
+                    expected: "Vector".to_string(),
+                    found: "Raster".to_string(),
+                    hint: "Request raster workflows via the WMS endpoint".to_string(),
+                })
+            }
+            TypedOperator::Plot(_) => {
+                return Err(Error::InvalidWorkflowResultType {
+                    expected: "Vector".to_string(),
+                    found: "Plot".to_string(),
+                    hint: "Plots cannot be requested as features".to_string(),
+                })
+            }
+        };
+
+        let execution_context = vector_execution_context(requester, referenced_workflows)?;
+        let initialized = operator.initialize(&execution_context)?;
+
+        let query_rect = QueryRectangle {
+            bbox: ticket.bbox,
+            time_interval: ticket.time_interval,
+            spatial_resolution: SpatialResolution::zero_point_one(),
+        };
+        let query_config = config::get_config_element::<config::Query>()?;
+        let query_ctx = QueryContext {
+            chunk_byte_size: 1024 * 1024,
+            timeout: query_config.timeout(),
+            seed: query_config.seed,
+        };
+
+        let (sender, receiver) = mpsc::channel(1);
+
+        if !initialized.result_descriptor().has_data_for(&query_rect) {
+            // the query lies outside of the bounds of the result, so no batches are sent
+            return Ok(receiver);
+        }
+
+        let processor = initialized.query_processor()?;
+
+        // the query counts against the client's limit until all batches are sent
+        let permit = query_admission()?.admit(&client).await?;
+
+        tokio::spawn(async move {
+            // sending only fails if the client disconnected, which cancels the query
+            let _ = match processor {
+                TypedVectorQueryProcessor::Data(p) => {
+                    send_batches(p, query_rect, query_ctx, sender).await
+                }
+                TypedVectorQueryProcessor::MultiPoint(p) => {
+                    send_batches(p, query_rect, query_ctx, sender).await
+                }
+                TypedVectorQueryProcessor::MultiLineString(p) => {
+                    send_batches(p, query_rect, query_ctx, sender).await
+                }
+                TypedVectorQueryProcessor::MultiPolygon(p) => {
+                    send_batches(p, query_rect, query_ctx, sender).await
+                }
+            };
+
+            drop(permit);
+        });
+
+        Ok(receiver)
+    }
+}
+
+/// Send the collections of a query as `FlightData` until the query is finished, fails or the
+/// client disconnects
+async fn send_batches<G>(
+    processor: Box<dyn VectorQueryProcessor<VectorType = FeatureCollection<G>>>,
+    query_rect: QueryRectangle,
+    query_ctx: QueryContext,
+    mut sender: mpsc::Sender<Result<FlightData, Status>>,
+) -> Result<(), mpsc::SendError> {
+    let mut collections = processor.vector_query(query_rect, query_ctx);
+    let mut sent_schema = false;
+
+    let send = async {
+        while let Some(collection) = collections.next().await {
+            let batch = match collection {
+                Ok(collection) => collection.to_record_batch(),
+                Err(error) => return sender.send(Err(status(&Error::from(error)))).await,
+            };
+
+            if !sent_schema {
+                sender
+                    .send(Ok(FlightData::from(batch.schema().as_ref())))
+                    .await?;
+                sent_schema = true;
+            }
+
+            sender
+                .send(Ok(arrow_flight::utils::flight_data_from_arrow_batch(
+                    &batch,
+                )))
+                .await?;
+        }
+
+        Ok(())
+    };
+
+    match with_query_timeout(query_ctx, send.map(Ok)).await {
+        Ok(sent) => sent,
+        Err(error) => sender.send(Err(status(&error))).await,
+    }
+}
+
+/// Map an error to a gRPC status, rejected queries can be retried later
+fn status(error: &Error) -> Status {
+    match error {
+        Error::TooManyQueries { .. } => Status::resource_exhausted(error.to_string()),
+        Error::QueryTimeout { .. } => Status::deadline_exceeded(error.to_string()),
+        Error::NoWorkflowForGivenId => Status::not_found(error.to_string()),
+        Error::WorkflowAccessDenied { .. } => Status::permission_denied(error.to_string()),
+        _ => Status::invalid_argument(error.to_string()),
+    }
+}
+
+#[tonic::async_trait]
+impl<T, U> FlightService for VectorFlightService<T, U>
+where
+    T: WorkflowRegistry + 'static,
+    U: UserDB + 'static,
+{
+    type HandshakeStream = FlightStream<HandshakeResponse>;
+    type ListFlightsStream = FlightStream<FlightInfo>;
+    type DoGetStream = FlightStream<FlightData>;
+    type DoPutStream = FlightStream<PutResult>;
+    type DoActionStream = FlightStream<arrow_flight::Result>;
+    type ListActionsStream = FlightStream<ActionType>;
+    type DoExchangeStream = FlightStream<FlightData>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("Handshake is not supported"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        Err(Status::unimplemented("ListFlights is not supported"))
+    }
+
+    async fn get_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented("GetFlightInfo is not supported"))
+    }
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        Err(Status::unimplemented("GetSchema is not supported"))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        let client = request.remote_addr().map_or_else(
+            || "anonymous".to_string(),
+            |address| format!("address:{}", address.ip()),
+        );
+
+        let requester = self.requester(&request).await?;
+
+        let ticket: FlightTicket = serde_json::from_slice(&request.get_ref().ticket)
+            .map_err(|error| Status::invalid_argument(format!("Invalid ticket: {}", error)))?;
+
+        let batches = self
+            .query(&ticket, &requester, client)
+            .await
+            .map_err(|error| status(&error))?;
+
+        Ok(Response::new(Box::pin(batches) as Self::DoGetStream))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("DoPut is not supported"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("DoAction is not supported"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Err(Status::unimplemented("ListActions is not supported"))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("DoExchange is not supported"))
+    }
+}
+
+/// Serve the Arrow Flight endpoint until the server is shut down
+pub async fn serve_flight<T, U>(
+    bind_address: SocketAddr,
+    workflow_registry: Arc<RwLock<T>>,
+    user_db: Arc<RwLock<U>>,
+) -> Result<()>
+where
+    T: WorkflowRegistry + 'static,
+    U: UserDB + 'static,
+{
+    tonic::transport::Server::builder()
+        .add_service(FlightServiceServer::new(VectorFlightService::new(
+            workflow_registry,
+            user_db,
+        )))
+        .serve(bind_address)
+        .await
+        .map_err(|_| Error::ServerStartup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::users::hashmap_userdb::HashMapUserDB;
+    use crate::users::user::{UserCredentials, UserRegistration};
+    use crate::util::user_input::UserInput;
+    use crate::workflows::registry::HashMapRegistry;
+    use crate::workflows::workflow::Workflow;
+    use geoengine_datatypes::primitives::Coordinate2D;
+    use geoengine_operators::engine::VectorOperator;
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+
+    type Service = VectorFlightService<HashMapRegistry, HashMapUserDB>;
+
+    fn service() -> Service {
+        VectorFlightService::new(
+            Arc::new(RwLock::new(HashMapRegistry::default())),
+            Arc::new(RwLock::new(HashMapUserDB::default())),
+        )
+    }
+
+    fn workflow() -> Workflow {
+        Workflow {
+            operator: TypedOperator::Vector(
+                MockPointSource {
+                    params: MockPointSourceParams {
+                        points: vec![Coordinate2D::new(1., 1.), Coordinate2D::new(2., 2.)],
+                    },
+                }
+                .boxed(),
+            ),
+        }
+    }
+
+    fn ticket(workflow: WorkflowId) -> serde_json::Value {
+        serde_json::json!({
+            "workflow": workflow,
+            "bbox": BoundingBox2D::new((0., 0.).into(), (10., 10.).into()).unwrap(),
+            "time_interval": TimeInterval::default(),
+        })
+    }
+
+    async fn do_get_as(
+        service: &Service,
+        ticket: &serde_json::Value,
+        token: Option<&str>,
+    ) -> Result<Vec<Result<FlightData, Status>>, Status> {
+        let mut request = Request::new(Ticket {
+            ticket: serde_json::to_vec(ticket).unwrap(),
+        });
+        if let Some(token) = token {
+            request
+                .metadata_mut()
+                .insert("authorization", token.parse().unwrap());
+        }
+
+        let response = service.do_get(request).await?;
+
+        Ok(response.into_inner().collect().await)
+    }
+
+    async fn do_get(ticket: serde_json::Value) -> Result<Vec<Result<FlightData, Status>>, Status> {
+        let service = service();
+        let id = service
+            .workflow_registry
+            .write()
+            .await
+            .register(workflow())
+            .unwrap();
+
+        let mut ticket = ticket;
+        if ticket["workflow"].is_null() {
+            ticket["workflow"] = serde_json::to_value(&id).unwrap();
+        }
+
+        do_get_as(&service, &ticket, None).await
+    }
+
+    #[tokio::test]
+    async fn streams_schema_and_batches() {
+        let messages = do_get(serde_json::json!({
+            "bbox": BoundingBox2D::new((0., 0.).into(), (10., 10.).into()).unwrap(),
+            "time_interval": TimeInterval::default(),
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(messages.len(), 2);
+
+        let schema = messages[0].as_ref().unwrap();
+        assert!(schema.data_body.is_empty());
+        assert!(!schema.data_header.is_empty());
+
+        let batch = messages[1].as_ref().unwrap();
+        assert!(!batch.data_body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_tickets() {
+        assert_eq!(
+            do_get(serde_json::json!({ "bbox": "everywhere" }))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::InvalidArgument
+        );
+    }
+
+    #[tokio::test]
+    async fn private_workflows_require_a_session() {
+        let service = service();
+
+        let mut user_db = service.user_db.write().await;
+        let user = user_db
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".to_string(),
+                    password: "secret123".to_string(),
+                    real_name: "Foo Bar".to_string(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+        let session = user_db
+            .login(UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap();
+        drop(user_db);
+
+        let id = service
+            .workflow_registry
+            .write()
+            .await
+            .register_private(workflow(), user)
+            .unwrap();
+        let ticket = ticket(id);
+
+        assert_eq!(
+            do_get_as(&service, &ticket, None).await.unwrap_err().code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            do_get_as(&service, &ticket, Some("invalid"))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::Unauthenticated
+        );
+        assert_eq!(
+            do_get_as(&service, &ticket, Some(&session.token.to_string()))
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,418 @@
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::handlers::Requester;
+use crate::users::session::{Session, SessionToken};
+use crate::users::user::UserCredentials;
+use crate::users::userdb::UserDB;
+use crate::util::audit::{audit_log, AuditEvent, AuditEventKind};
+use crate::util::identifiers::Identifier;
+use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::workflow::{Workflow, WorkflowId};
+
+use self::proto::control_server::{Control, ControlServer};
+
+/// The messages and service definitions that are generated from `proto/control.proto`
+#[allow(clippy::pedantic)]
+pub mod proto {
+    tonic::include_proto!("geoengine.control");
+}
+
+impl From<WorkflowId> for proto::WorkflowId {
+    fn from(id: WorkflowId) -> Self {
+        Self { id: id.to_string() }
+    }
+}
+
+impl From<Session> for proto::Session {
+    fn from(session: Session) -> Self {
+        Self {
+            user: session.user.to_string(),
+            token: session.token.to_string(),
+            project: session
+                .project
+                .map(|project| project.to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl From<proto::UserCredentials> for UserCredentials {
+    fn from(credentials: proto::UserCredentials) -> Self {
+        Self {
+            email: credentials.email,
+            password: credentials.password,
+        }
+    }
+}
+
+/// The gRPC control API for programmatic clients, see `proto/control.proto`
+pub struct ControlService<W: WorkflowRegistry, U: UserDB> {
+    workflow_registry: Arc<RwLock<W>>,
+    user_db: Arc<RwLock<U>>,
+}
+
+impl<W: WorkflowRegistry, U: UserDB> ControlService<W, U> {
+    pub fn new(workflow_registry: Arc<RwLock<W>>, user_db: Arc<RwLock<U>>) -> Self {
+        Self {
+            workflow_registry,
+            user_db,
+        }
+    }
+
+    /// The session of the token in the `authorization` metadata, like `authenticate` for REST
+    async fn authenticate<T>(&self, request: &Request<T>) -> Result<Session, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|token| token.to_str().ok())
+            .and_then(|token| SessionToken::from_str(token).ok())
+            .ok_or_else(|| Status::unauthenticated(Error::InvalidSessionToken.to_string()))?;
+
+        self.user_db
+            .read()
+            .await
+            .session(token)
+            .map_err(|error| Status::unauthenticated(error.to_string()))
+    }
+
+    /// The session of a request with `authorization` metadata, otherwise an anonymous requester
+    async fn requester<T>(&self, request: &Request<T>) -> Result<Requester, Status> {
+        if request.metadata().contains_key("authorization") {
+            self.authenticate(request).await.map(Requester::Session)
+        } else {
+            Ok(Requester::Anonymous)
+        }
+    }
+}
+
+/// The client of a request, like `query_client` for REST
+fn client<T>(request: &Request<T>) -> String {
+    request.remote_addr().map_or_else(
+        || "anonymous".to_string(),
+        |address| format!("address:{}", address.ip()),
+    )
+}
+
+fn status(error: &Error) -> Status {
+    match error {
+        Error::NoWorkflowForGivenId => Status::not_found(error.to_string()),
+        Error::WorkflowAccessDenied { .. } => Status::permission_denied(error.to_string()),
+        Error::SerdeJson { .. } | Error::Uuid { .. } => Status::invalid_argument(error.to_string()),
+        _ => Status::internal(error.to_string()),
+    }
+}
+
+#[tonic::async_trait]
+impl<W, U> Control for ControlService<W, U>
+where
+    W: WorkflowRegistry + 'static,
+    U: UserDB + 'static,
+{
+    async fn register_workflow(
+        &self,
+        request: Request<proto::Workflow>,
+    ) -> Result<Response<proto::WorkflowId>, Status> {
+        let client = client(&request);
+        let workflow: Workflow = serde_json::from_str(&request.into_inner().json)
+            .map_err(|error| status(&Error::SerdeJson { source: error }))?;
+
+        let id = self
+            .workflow_registry
+            .write()
+            .await
+            .register(workflow)
+            .map_err(|error| status(&error))?;
+
+        audit_log()
+            .and_then(|log| {
+                log.record(
+                    AuditEvent::new(AuditEventKind::WorkflowRegistration, id.to_string())
+                        .client(client),
+                )
+            })
+            .map_err(|error| status(&error))?;
+
+        Ok(Response::new(id.into()))
+    }
+
+    async fn load_workflow(
+        &self,
+        request: Request<proto::WorkflowId>,
+    ) -> Result<Response<proto::Workflow>, Status> {
+        let requester = self.requester(&request).await?;
+        let id = Uuid::parse_str(&request.get_ref().id)
+            .map_err(|error| status(&Error::Uuid { source: error }))?;
+
+        let workflow = requester
+            .load_workflow(
+                &*self.workflow_registry.read().await,
+                &WorkflowId::from_uuid(id),
+            )
+            .map_err(|error| status(&error))?;
+
+        Ok(Response::new(proto::Workflow {
+            json: serde_json::to_string(&workflow)
+                .map_err(|error| status(&Error::SerdeJson { source: error }))?,
+        }))
+    }
+
+    async fn login(
+        &self,
+        request: Request<proto::UserCredentials>,
+    ) -> Result<Response<proto::Session>, Status> {
+        let client = client(&request);
+        let credentials = UserCredentials::from(request.into_inner());
+        let email = credentials.email.clone();
+
+        let session = self.user_db.write().await.login(credentials);
+
+        let event = match &session {
+            Ok(session) => AuditEvent::new(AuditEventKind::Login, email).user(session.user),
+            Err(_) => AuditEvent::new(AuditEventKind::FailedLogin, email),
+        };
+        audit_log()
+            .and_then(|log| log.record(event.client(client)))
+            .map_err(|error| status(&error))?;
+
+        session
+            .map(|session| Response::new(session.into()))
+            .map_err(|error| Status::unauthenticated(error.to_string()))
+    }
+
+    async fn logout(
+        &self,
+        request: Request<proto::Empty>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let session = self.authenticate(&request).await?;
+
+        self.user_db
+            .write()
+            .await
+            .logout(session.token)
+            .map_err(|error| Status::unauthenticated(error.to_string()))?;
+
+        audit_log()
+            .and_then(|log| {
+                log.record(AuditEvent::new(AuditEventKind::Logout, "").user(session.user))
+            })
+            .map_err(|error| status(&error))?;
+
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn get_session(
+        &self,
+        request: Request<proto::Empty>,
+    ) -> Result<Response<proto::Session>, Status> {
+        let session = self.authenticate(&request).await?;
+
+        Ok(Response::new(session.into()))
+    }
+}
+
+/// Serve the gRPC control API until the server is shut down
+pub async fn serve_grpc<W, U>(
+    bind_address: SocketAddr,
+    workflow_registry: Arc<RwLock<W>>,
+    user_db: Arc<RwLock<U>>,
+) -> Result<()>
+where
+    W: WorkflowRegistry + 'static,
+    U: UserDB + 'static,
+{
+    tonic::transport::Server::builder()
+        .add_service(ControlServer::new(ControlService::new(
+            workflow_registry,
+            user_db,
+        )))
+        .serve(bind_address)
+        .await
+        .map_err(|_| Error::ServerStartup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::users::hashmap_userdb::HashMapUserDB;
+    use crate::users::user::UserRegistration;
+    use crate::util::user_input::UserInput;
+    use crate::workflows::registry::HashMapRegistry;
+    use geoengine_operators::engine::VectorOperator;
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+
+    fn service() -> ControlService<HashMapRegistry, HashMapUserDB> {
+        ControlService::new(
+            Arc::new(RwLock::new(HashMapRegistry::default())),
+            Arc::new(RwLock::new(HashMapUserDB::default())),
+        )
+    }
+
+    #[tokio::test]
+    async fn register_and_load_workflow() {
+        let service = service();
+
+        let workflow = Workflow {
+            operator: MockPointSource {
+                params: MockPointSourceParams {
+                    points: vec![(0.0, 0.1).into(), (1.0, 1.1).into()],
+                },
+            }
+            .boxed()
+            .into(),
+        };
+        let json = serde_json::to_string(&workflow).unwrap();
+
+        let id = service
+            .register_workflow(Request::new(proto::Workflow { json: json.clone() }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let loaded = service
+            .load_workflow(Request::new(id))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(loaded.json, json);
+
+        let unknown = service
+            .load_workflow(Request::new(WorkflowId::new().into()))
+            .await
+            .unwrap_err();
+        assert_eq!(unknown.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn private_workflows_require_a_session() {
+        let service = service();
+
+        let user = service
+            .user_db
+            .write()
+            .await
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".to_string(),
+                    password: "secret123".to_string(),
+                    real_name: "Foo Bar".to_string(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+        let session = service
+            .login(Request::new(proto::UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let workflow = Workflow {
+            operator: MockPointSource {
+                params: MockPointSourceParams {
+                    points: vec![(0.0, 0.1).into()],
+                },
+            }
+            .boxed()
+            .into(),
+        };
+        let id: proto::WorkflowId = service
+            .workflow_registry
+            .write()
+            .await
+            .register_private(workflow, user)
+            .unwrap()
+            .into();
+
+        assert_eq!(
+            service
+                .load_workflow(Request::new(id.clone()))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+
+        let mut request = Request::new(id);
+        request
+            .metadata_mut()
+            .insert("authorization", session.token.parse().unwrap());
+        assert!(service.load_workflow(request).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn session_lifecycle() {
+        let service = service();
+
+        let user = UserRegistration {
+            email: "foo@bar.de".to_string(),
+            password: "secret123".to_string(),
+            real_name: "Foo Bar".to_string(),
+        }
+        .validated()
+        .unwrap();
+        service.user_db.write().await.register(user).unwrap();
+
+        let session = service
+            .login(Request::new(proto::UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let authenticated = |token: &str| {
+            let mut request = Request::new(proto::Empty {});
+            request
+                .metadata_mut()
+                .insert("authorization", token.parse().unwrap());
+            request
+        };
+
+        assert_eq!(
+            service
+                .get_session(authenticated(&session.token))
+                .await
+                .unwrap()
+                .into_inner(),
+            session
+        );
+
+        service.logout(authenticated(&session.token)).await.unwrap();
+
+        assert_eq!(
+            service
+                .get_session(authenticated(&session.token))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::Unauthenticated
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_login() {
+        assert_eq!(
+            service()
+                .login(Request::new(proto::UserCredentials {
+                    email: "foo@bar.de".to_string(),
+                    password: "wrong".to_string(),
+                }))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::Unauthenticated
+        );
+    }
+}
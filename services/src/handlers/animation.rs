@@ -0,0 +1,297 @@
+use std::sync::Arc;
+
+use image::gif::GifEncoder;
+use image::{Delay, Frame, ImageFormat};
+use serde::Deserialize;
+use snafu::ResultExt;
+use uuid::Uuid;
+use warp::{http::Response, Filter};
+
+use geoengine_datatypes::primitives::{BoundingBox2D, TimeInstance, TimeInterval};
+
+use crate::datasets::SharedDatasetDefinitions;
+use crate::error;
+use crate::error::Result;
+use crate::handlers::wms::render_map;
+use crate::handlers::{query_client, requester, Requester, DB};
+use crate::ogc::util::{parse_bbox, parse_time_instance};
+use crate::ogc::wms::request::{GetMap, GetMapFormat};
+use crate::users::userdb::UserDB;
+use crate::util::admission::query_admission;
+use crate::util::config;
+use crate::util::from_str;
+use crate::workflows::registry::WorkflowRegistry;
+
+/// An animation of a workflow over time, e.g.,
+/// `/animation/{workflow_id}?start=2014-01-01T00:00:00Z&end=2014-07-01T00:00:00Z&step=2592000&bbox=-90,-180,90,180&width=360&height=180`
+///
+/// Every frame is rendered like a `GetMap` request for the time interval `[t, t + step)`,
+/// starting at `start` until `end` is reached.
+#[derive(PartialEq, Debug, Deserialize)]
+pub struct AnimationRequest {
+    #[serde(deserialize_with = "parse_time_instance")]
+    pub start: TimeInstance,
+    #[serde(deserialize_with = "parse_time_instance")]
+    pub end: TimeInstance,
+    /// The length of a frame's time interval in seconds
+    #[serde(deserialize_with = "from_str")]
+    pub step: u32,
+    /// The bounding box in the axis order of `GetMap` requests
+    #[serde(deserialize_with = "parse_bbox")]
+    pub bbox: BoundingBox2D,
+    #[serde(deserialize_with = "from_str")]
+    pub width: u32,
+    #[serde(deserialize_with = "from_str")]
+    pub height: u32,
+    #[serde(default = "default_crs")]
+    pub crs: String,
+    /// The styles of the frames, e.g., `stretch:global` for comparable frames of a raster
+    #[serde(default)]
+    pub styles: String,
+    #[serde(default)]
+    pub format: AnimationFormat,
+}
+
+#[derive(PartialEq, Debug, Deserialize)]
+pub enum AnimationFormat {
+    #[serde(rename = "image/gif")]
+    ImageGif, // TODO: video formats
+}
+
+impl Default for AnimationFormat {
+    fn default() -> Self {
+        Self::ImageGif
+    }
+}
+
+fn default_crs() -> String {
+    "EPSG:4326".to_string()
+}
+
+pub fn animation_handler<T: WorkflowRegistry, U: UserDB>(
+    workflow_registry: DB<T>,
+    dataset_definitions: SharedDatasetDefinitions,
+    user_db: DB<U>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("animation" / Uuid))
+        .and(warp::query::<AnimationRequest>())
+        .and(query_client())
+        .and(requester(user_db))
+        .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and(warp::any().map(move || Arc::clone(&dataset_definitions)))
+        .and_then(animation)
+}
+
+// TODO: move into handler once async closures are available?
+async fn animation<T: WorkflowRegistry>(
+    workflow_id: Uuid,
+    request: AnimationRequest,
+    client: String,
+    requester: Requester,
+    workflow_registry: DB<T>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let animation_config = config::get_config_element::<config::Animation>()?;
+    let frames = frame_requests(&workflow_id, &request, animation_config.max_frames)?;
+
+    // the whole animation counts as one query against the client's limit
+    let _permit = query_admission()?.admit(&client).await?;
+
+    let mut pngs = Vec::with_capacity(frames.len());
+    for frame in &frames {
+        pngs.push(
+            render_map(
+                frame,
+                &requester,
+                &workflow_registry,
+                Arc::clone(&dataset_definitions),
+            )
+            .await?,
+        );
+    }
+
+    // encoding is CPU-bound, so it must not block the executor
+    let frame_duration = animation_config.frame_duration_milliseconds;
+    let gif = tokio::task::spawn_blocking(move || encode_gif(&pngs, frame_duration))
+        .await
+        .context(error::TokioJoin)??;
+
+    Ok(Response::builder()
+        .header("Content-Type", "image/gif")
+        .body(gif)
+        .context(error::HTTP)?)
+}
+
+/// The `GetMap` requests of the frames of an animation
+fn frame_requests(
+    workflow_id: &Uuid,
+    request: &AnimationRequest,
+    max_frames: usize,
+) -> Result<Vec<GetMap>> {
+    if request.step == 0 {
+        return Err(error::Error::InvalidAnimation {
+            details: "`step` must be greater than zero".to_string(),
+        });
+    }
+    if request.start >= request.end {
+        return Err(error::Error::InvalidAnimation {
+            details: "`start` must be before `end`".to_string(),
+        });
+    }
+
+    let step = i64::from(request.step) * 1000;
+    let mut frames = Vec::new();
+    let mut start = request.start.inner();
+
+    while start < request.end.inner() {
+        if frames.len() == max_frames {
+            return Err(error::Error::InvalidAnimation {
+                details: format!("an animation must not have more than {} frames", max_frames),
+            });
+        }
+
+        let end = start.saturating_add(step);
+
+        frames.push(GetMap {
+            version: "1.3.0".to_string(),
+            width: request.width,
+            height: request.height,
+            bbox: request.bbox,
+            format: GetMapFormat::ImagePng,
+            layers: workflow_id.to_string(),
+            crs: request.crs.clone(),
+            styles: request.styles.clone(),
+            time: Some(TimeInterval::new(start, end).context(error::DataType)?),
+            transparent: None,
+            bgcolor: None,
+            sld: None,
+            sld_body: None,
+            elevation: None,
+            exceptions: None,
+            decorations: None,
+            map_resolution: None,
+        });
+
+        start = end;
+    }
+
+    Ok(frames)
+}
+
+/// Encode PNG frames as an animated GIF that shows each frame for `frame_duration` milliseconds
+fn encode_gif(pngs: &[Vec<u8>], frame_duration: u32) -> Result<Vec<u8>> {
+    let mut gif = Vec::new();
+
+    {
+        let mut encoder = GifEncoder::new(&mut gif);
+
+        for png in pngs {
+            let image = image::load_from_memory_with_format(png, ImageFormat::Png)
+                .context(error::Image)?
+                .to_rgba();
+
+            encoder
+                .encode_frame(Frame::from_parts(
+                    image,
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(frame_duration, 1),
+                ))
+                .context(error::Image)?;
+        }
+    }
+
+    Ok(gif)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::load_dataset_definitions;
+    use crate::users::hashmap_userdb::HashMapUserDB;
+    use crate::workflows::registry::HashMapRegistry;
+    use crate::workflows::workflow::Workflow;
+    use geoengine_datatypes::primitives::Coordinate2D;
+    use geoengine_operators::engine::{TypedOperator, VectorOperator};
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+    use image::AnimationDecoder;
+    use tokio::sync::RwLock;
+
+    fn request(query: &str) -> AnimationRequest {
+        serde_urlencoded::from_str(query).unwrap()
+    }
+
+    #[test]
+    fn frames() {
+        let request = request("start=2014-01-01T00:00:00Z&end=2014-01-01T00:00:25Z&step=10&bbox=0,0,10,10&width=100&height=50");
+        assert_eq!(request.crs, "EPSG:4326");
+        assert_eq!(request.format, AnimationFormat::ImageGif);
+
+        let frames = frame_requests(&Uuid::new_v4(), &request, 10).unwrap();
+
+        let start = 1_388_534_400_000;
+        assert_eq!(
+            frames.iter().map(|frame| frame.time).collect::<Vec<_>>(),
+            vec![
+                Some(TimeInterval::new_unchecked(start, start + 10_000)),
+                Some(TimeInterval::new_unchecked(start + 10_000, start + 20_000)),
+                Some(TimeInterval::new_unchecked(start + 20_000, start + 30_000)),
+            ]
+        );
+        assert_eq!(frames[0].width, 100);
+        assert_eq!(frames[0].height, 50);
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let too_many = request("start=2014-01-01T00:00:00Z&end=2014-01-02T00:00:00Z&step=60&bbox=0,0,10,10&width=100&height=100");
+        assert!(frame_requests(&Uuid::new_v4(), &too_many, 100).is_err());
+
+        let no_step = request("start=2014-01-01T00:00:00Z&end=2014-01-02T00:00:00Z&step=0&bbox=0,0,10,10&width=100&height=100");
+        assert!(frame_requests(&Uuid::new_v4(), &no_step, 100).is_err());
+
+        let reversed = request("start=2014-01-02T00:00:00Z&end=2014-01-01T00:00:00Z&step=60&bbox=0,0,10,10&width=100&height=100");
+        assert!(frame_requests(&Uuid::new_v4(), &reversed, 100).is_err());
+    }
+
+    #[tokio::test]
+    async fn animated_gif() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+
+        let workflow = Workflow {
+            operator: TypedOperator::Vector(
+                MockPointSource {
+                    params: MockPointSourceParams {
+                        points: vec![Coordinate2D::new(5., 5.)],
+                    },
+                }
+                .boxed(),
+            ),
+        };
+
+        let id = workflow_registry.write().await.register(workflow).unwrap();
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&format!("/animation/{}?start=2014-01-01T00:00:00Z&end=2014-01-01T00:00:20Z&step=10&bbox=0,0,10,10&width=100&height=100&styles=fill:%23ff0000;point_radius:5", id.to_string()))
+            .reply(&animation_handler(
+                workflow_registry,
+                load_dataset_definitions().unwrap(),
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
+            .await;
+
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["Content-Type"], "image/gif");
+
+        let frames = image::gif::GifDecoder::new(res.body().as_ref())
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].buffer().dimensions(), (100, 100));
+    }
+}
@@ -0,0 +1,48 @@
+use crate::util::audit::{audit_log, AuditQuery};
+use crate::util::config;
+use warp::reply::Reply;
+use warp::Filter;
+
+/// Query the audit log with the `admin_token` of the `[audit]` configuration as `Authorization`
+pub fn audit_handler() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("audit"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::query::<AuditQuery>())
+        .and_then(query_audit_log)
+}
+
+// TODO: move into handler once async closures are available?
+async fn query_audit_log(
+    token: Option<String>,
+    query: AuditQuery,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let admin_token = config::get_config_element::<config::Audit>()?.admin_token;
+
+    // an empty token disables the endpoint
+    if admin_token.is_empty() || token.as_deref() != Some(admin_token.as_str()) {
+        return Ok(Box::new(
+            warp::http::StatusCode::UNAUTHORIZED.into_response(),
+        ));
+    }
+
+    let events = audit_log()?.query(&query)?;
+    Ok(Box::new(warp::reply::json(&events)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn disabled_without_admin_token() {
+        let res = warp::test::request()
+            .method("GET")
+            .path("/audit?kind=failedLogin&limit=10")
+            .header("Authorization", "")
+            .reply(&audit_handler())
+            .await;
+
+        assert_eq!(res.status(), 401);
+    }
+}
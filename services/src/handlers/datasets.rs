@@ -0,0 +1,484 @@
+use crate::datasets::dependencies::DatasetDependencies;
+use crate::datasets::upload::{store_upload, UploadFormat, UploadLimits};
+use crate::datasets::watcher;
+use crate::datasets::SharedDatasetDefinitions;
+use crate::error;
+use crate::error::Error;
+use crate::handlers::{authenticate, DB};
+use crate::projects::projectdb::ProjectDB;
+use crate::users::session::Session;
+use crate::users::userdb::UserDB;
+use crate::util::audit::{audit_log, AuditEvent, AuditEventKind};
+use crate::util::config;
+use crate::workflows::registry::WorkflowRegistry;
+use serde::Deserialize;
+use snafu::ResultExt;
+use std::sync::Arc;
+use warp::hyper::body::Bytes;
+use warp::reply::Reply;
+use warp::Filter;
+
+/// Reload the changed dataset definitions with the `admin_token` of the `[raster]` configuration
+/// as `Authorization`
+pub fn reload_dataset_definitions_handler(
+    dataset_definitions: SharedDatasetDefinitions,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("datasets" / "reload"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::any().map(move || Arc::clone(&dataset_definitions)))
+        .and_then(reload_dataset_definitions)
+}
+
+// TODO: move into handler once async closures are available?
+async fn reload_dataset_definitions(
+    token: Option<String>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let admin_token = config::get_config_element::<config::Raster>()?.admin_token;
+
+    // an empty token disables the endpoint
+    if admin_token.is_empty() || token.as_deref() != Some(admin_token.as_str()) {
+        return Ok(Box::new(
+            warp::http::StatusCode::UNAUTHORIZED.into_response(),
+        ));
+    }
+
+    let report = watcher::reload_dataset_definitions(dataset_definitions).await?;
+    audit_log()?.record(AuditEvent::new(AuditEventKind::DatasetReload, ""))?;
+    Ok(Box::new(warp::reply::json(&report)))
+}
+
+/// The name and declared format of an uploaded file
+#[derive(Debug, Deserialize)]
+pub struct UploadParameters {
+    pub name: String,
+    pub format: UploadFormat,
+}
+
+/// Upload a file, e.g., `/dataset/upload?name=ndvi.tif&format=geotiff`.
+/// The file is only stored if it passes all checks, otherwise the response lists its problems.
+pub fn upload_handler<T: UserDB>(
+    user_db: DB<T>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("dataset" / "upload"))
+        .and(authenticate(user_db))
+        .and(warp::query::<UploadParameters>())
+        .and(upload_size_limit())
+        .and(warp::body::bytes())
+        .and_then(upload)
+}
+
+/// Reject uploads whose announced size exceeds the limit before their body is read
+fn upload_size_limit() -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<u64>("content-length")
+        .and_then(|length: Option<u64>| async move {
+            let max = config::get_config_element::<config::Upload>()?.max_file_size_bytes;
+            if length.map_or(false, |length| length > max) {
+                return Err(warp::reject::custom(Error::UploadTooLarge { max }));
+            }
+            Ok(())
+        })
+        .untuple_one()
+}
+
+async fn upload(
+    session: Session,
+    parameters: UploadParameters,
+    content: Bytes,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let upload_config = config::get_config_element::<config::Upload>()?;
+
+    let response = store_upload(
+        upload_config.directory,
+        session.user,
+        parameters.name,
+        parameters.format,
+        content.to_vec(),
+        UploadLimits {
+            max_file_size: upload_config.max_file_size_bytes,
+            quota: upload_config.quota(),
+        },
+    )
+    .await?;
+
+    let status = match &response.upload {
+        Some(upload) => {
+            audit_log()?.record(
+                AuditEvent::new(AuditEventKind::DatasetUpload, upload.id.to_string())
+                    .user(session.user),
+            )?;
+            warp::http::StatusCode::OK
+        }
+        None => warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+    };
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        status,
+    ))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteDatasetParameters {
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Delete a dataset that the user owns and reclaim its storage, e.g., `DELETE /dataset/ndvi`.
+///
+/// If the user's registered workflows or projects use the dataset, it is only deleted with
+/// `?force=true`, which also unregisters these workflows and removes their layers from the
+/// projects. Otherwise the response is `409 Conflict` and lists them.
+/// Datasets that public workflows or the workflows or projects of other users use are not
+/// deleted at all.
+pub fn delete_dataset_handler<T: UserDB, P: ProjectDB, W: WorkflowRegistry>(
+    user_db: DB<T>,
+    project_db: DB<P>,
+    workflow_registry: DB<W>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::delete()
+        .and(warp::path!("dataset" / String))
+        .and(authenticate(user_db))
+        .and(warp::query::<DeleteDatasetParameters>())
+        .and(warp::any().map(move || Arc::clone(&project_db)))
+        .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and(warp::any().map(move || Arc::clone(&dataset_definitions)))
+        .and_then(delete_dataset)
+}
+
+async fn delete_dataset<P: ProjectDB, W: WorkflowRegistry>(
+    id: String,
+    session: Session,
+    parameters: DeleteDatasetParameters,
+    project_db: DB<P>,
+    workflow_registry: DB<W>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let owner = dataset_definitions
+        .read()
+        .map_err(|_| Error::DatasetDefinitionsLockFailed)?
+        .get(&id)
+        .map(|information| information.access.owner().map(ToString::to_string))
+        .ok_or_else(|| {
+            Error::from(geoengine_operators::error::Error::UnknownDataset { id: id.clone() })
+        })?;
+    if owner != Some(session.user.to_string()) {
+        return Err(Error::DatasetDeletionDenied { id }.into());
+    }
+
+    let mut workflow_registry = workflow_registry.write().await;
+    let mut project_db = project_db.write().await;
+
+    let dependencies =
+        DatasetDependencies::scan(&id, session.user, &*workflow_registry, &*project_db)?;
+    if dependencies.used_by_others {
+        return Err(Error::DatasetUsedByOthers { id }.into());
+    }
+    if !dependencies.is_empty() && !parameters.force {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&dependencies),
+            warp::http::StatusCode::CONFLICT,
+        ));
+    }
+
+    let removed_id = id.clone();
+    tokio::task::spawn_blocking(move || {
+        dataset_definitions
+            .write()
+            .map_err(|_| Error::DatasetDefinitionsLockFailed)?
+            .remove(&removed_id)
+            .map_err(Error::from)
+    })
+    .await
+    .context(error::TokioJoin)??;
+
+    for workflow in &dependencies.workflows {
+        workflow_registry.remove(workflow)?;
+    }
+    project_db.remove_layers(
+        session.user,
+        &dependencies.workflows.iter().copied().collect(),
+    )?;
+
+    audit_log()?.record(AuditEvent::new(AuditEventKind::DatasetDeletion, id).user(session.user))?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&dependencies),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::load_dataset_definitions;
+    use crate::datasets::upload::{UploadResponse, ValidationCheck};
+    use crate::handlers::handle_rejection;
+    use crate::projects::hashmap_projectdb::HashMapProjectDB;
+    use crate::projects::project::{
+        CreateProject, Layer, LayerInfo, RasterInfo, STRectangle, UpdateProject,
+    };
+    use crate::users::hashmap_userdb::HashMapUserDB;
+    use crate::users::user::{UserCredentials, UserRegistration};
+    use crate::util::user_input::UserInput;
+    use crate::workflows::registry::HashMapRegistry;
+    use crate::workflows::workflow::{Workflow, WorkflowId};
+    use geoengine_datatypes::operations::image::Colorizer;
+    use geoengine_datatypes::primitives::Coordinate2D;
+    use geoengine_operators::engine::{RasterOperator, TypedOperator, VectorOperator};
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+    use geoengine_operators::source::{
+        DatasetDefinitions, GdalSource, GdalSourceParameters, WorkflowSource,
+        WorkflowSourceParameters,
+    };
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn reload_disabled_without_admin_token() {
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+
+        // a session is not enough to reload the definitions
+        let session = login(&user_db, "foo@bar.de").await;
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/datasets/reload")
+            .header("Content-Length", "0")
+            .header("Authorization", session.token.to_string())
+            .reply(&reload_dataset_definitions_handler(
+                load_dataset_definitions().unwrap(),
+            ))
+            .await;
+
+        assert_eq!(res.status(), 401);
+    }
+
+    async fn login(user_db: &DB<HashMapUserDB>, email: &str) -> Session {
+        user_db
+            .write()
+            .await
+            .register(
+                UserRegistration {
+                    email: email.to_string(),
+                    password: "secret123".to_string(),
+                    real_name: "Foo Bar".to_string(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+
+        user_db
+            .write()
+            .await
+            .login(UserCredentials {
+                email: email.to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn invalid_upload() {
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+        let session = login(&user_db, "foo@bar.de").await;
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/dataset/upload?name=raster.tif&format=geotiff")
+            .header("Authorization", session.token.to_string())
+            .body("a,b\n1,2\n")
+            .reply(&upload_handler(user_db.clone()))
+            .await;
+
+        assert_eq!(res.status(), 422);
+
+        let response: UploadResponse = serde_json::from_slice(res.body()).unwrap();
+        assert!(response.upload.is_none());
+        assert_eq!(
+            response
+                .report
+                .problems
+                .iter()
+                .map(|problem| problem.check)
+                .collect::<Vec<_>>(),
+            vec![ValidationCheck::Content]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_dataset() {
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+        let session = login(&user_db, "foo@bar.de").await;
+        let other_session = login(&user_db, "bar@foo.de").await;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("dataset_defs")).unwrap();
+        std::fs::create_dir_all(dir.path().join("modis_ndvi")).unwrap();
+        let mut information: serde_json::Value = serde_json::from_str(include_str!(
+            "../../../operators/test-data/raster/dataset_defs/test.json"
+        ))
+        .unwrap();
+        information["access"] =
+            serde_json::json!({ "type": "Private", "owner": session.user.to_string() });
+        std::fs::write(
+            dir.path().join("dataset_defs/test.json"),
+            information.to_string(),
+        )
+        .unwrap();
+        let dataset_definitions = Arc::new(std::sync::RwLock::new(
+            DatasetDefinitions::load(dir.path()).0,
+        ));
+
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let reference_of = |workflow: WorkflowId| Workflow {
+            operator: TypedOperator::Raster(RasterOperator::boxed(WorkflowSource {
+                params: WorkflowSourceParameters {
+                    workflow: workflow.to_string(),
+                },
+            })),
+        };
+        let (source, reference, foreign, unrelated) = {
+            let mut registry = workflow_registry.write().await;
+            let source = registry
+                .register_private(
+                    Workflow {
+                        operator: TypedOperator::Raster(
+                            GdalSource {
+                                params: GdalSourceParameters {
+                                    dataset_id: "test".to_string(),
+                                    channel: None,
+                                    band: None,
+                                },
+                            }
+                            .boxed(),
+                        ),
+                    },
+                    session.user,
+                )
+                .unwrap();
+            let reference = registry
+                .register_private(reference_of(source), session.user)
+                .unwrap();
+            let foreign = registry
+                .register_private(reference_of(source), other_session.user)
+                .unwrap();
+            let unrelated = registry
+                .register(Workflow {
+                    operator: TypedOperator::Vector(
+                        MockPointSource {
+                            params: MockPointSourceParams {
+                                points: vec![Coordinate2D::new(1., 2.)],
+                            },
+                        }
+                        .boxed(),
+                    ),
+                })
+                .unwrap();
+            (source, reference, foreign, unrelated)
+        };
+
+        let project_db = Arc::new(RwLock::new(HashMapProjectDB::default()));
+        let project = {
+            let mut project_db = project_db.write().await;
+            let project = project_db.create(
+                session.user,
+                CreateProject {
+                    name: "Test".to_string(),
+                    description: "Foo".to_string(),
+                    view: STRectangle::new(0., 0., 1., 1., 0, 1).unwrap(),
+                    bounds: STRectangle::new(0., 0., 1., 1., 0, 1).unwrap(),
+                }
+                .validated()
+                .unwrap(),
+            );
+            project_db
+                .update(
+                    session.user,
+                    UpdateProject {
+                        id: project,
+                        name: None,
+                        description: None,
+                        layers: Some(vec![Some(Layer {
+                            workflow: reference,
+                            name: "L1".to_string(),
+                            info: LayerInfo::Raster(RasterInfo {
+                                colorizer: Colorizer::Rgba,
+                            }),
+                        })]),
+                        view: None,
+                        bounds: None,
+                    }
+                    .validated()
+                    .unwrap(),
+                )
+                .unwrap();
+            project
+        };
+
+        let handler = delete_dataset_handler(
+            user_db.clone(),
+            project_db.clone(),
+            workflow_registry.clone(),
+            dataset_definitions.clone(),
+        )
+        .recover(handle_rejection);
+        let delete = |path: &str, session: &Session| {
+            warp::test::request()
+                .method("DELETE")
+                .path(path)
+                .header("Authorization", session.token.to_string())
+        };
+
+        let res = delete("/dataset/test", &other_session)
+            .reply(&handler)
+            .await;
+        assert_eq!(res.status(), 403);
+
+        // the workflows of other users are neither listed nor removed, even if forced
+        let res = delete("/dataset/test?force=true", &session)
+            .reply(&handler)
+            .await;
+        assert_eq!(res.status(), 409);
+        assert!(!String::from_utf8_lossy(res.body()).contains(&foreign.to_string()));
+        assert!(dataset_definitions.read().unwrap().get("test").is_some());
+        workflow_registry.write().await.remove(&foreign).unwrap();
+
+        let res = delete("/dataset/test", &session).reply(&handler).await;
+        assert_eq!(res.status(), 409);
+
+        let mut workflows = vec![source, reference];
+        workflows.sort_by_key(ToString::to_string);
+        assert_eq!(
+            serde_json::from_slice::<DatasetDependencies>(res.body()).unwrap(),
+            DatasetDependencies {
+                workflows,
+                projects: vec![project],
+                used_by_others: false,
+            }
+        );
+        assert!(dataset_definitions.read().unwrap().get("test").is_some());
+
+        let res = delete("/dataset/test?force=true", &session)
+            .reply(&handler)
+            .await;
+        assert_eq!(res.status(), 200);
+
+        assert!(dataset_definitions.read().unwrap().get("test").is_none());
+        assert!(!dir.path().join("modis_ndvi").exists());
+        assert_eq!(
+            workflow_registry.read().await.list().unwrap(),
+            vec![unrelated]
+        );
+        assert!(project_db
+            .read()
+            .await
+            .load_latest(session.user, project)
+            .unwrap()
+            .layers
+            .is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+use crate::error::Result;
+use crate::util::config;
+use geoengine_operators::engine::OperatorRegistry;
+use serde::{Deserialize, Serialize};
+use warp::Filter;
+
+/// What this deployment of the backend offers, so that frontends can adapt to it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub version: String,
+    pub features: Features,
+    pub limits: Limits,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Features {
+    pub gdal_version: String,
+    /// The names of the operators that workflows may use
+    pub operators: Vec<String>,
+    pub ogc_endpoints: Vec<String>,
+    pub arrow_flight: bool,
+    pub grpc: bool,
+    pub r_scripts: bool,
+    pub share_links: bool,
+}
+
+/// The limits of requests, disabled limits are omitted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Limits {
+    /// The largest width and height of WMS images
+    pub max_image_size: u32,
+    pub max_supersampling: u32,
+    pub max_animation_frames: usize,
+    pub query_timeout_seconds: Option<u64>,
+    pub max_running_queries_per_client: usize,
+    pub max_upload_size_bytes: u64,
+    /// The storage that the uploads of each user may occupy
+    pub user_quota_bytes: Option<u64>,
+    /// How long uploads are kept
+    pub upload_ttl_seconds: Option<u64>,
+}
+
+impl ServerInfo {
+    /// Describe the backend by its configuration and the operators that are linked into it
+    pub fn collect() -> Result<Self> {
+        let wms = config::get_config_element::<config::Wms>()?;
+        let upload = config::get_config_element::<config::Upload>()?;
+
+        // operators like `WorkflowSource` are registered once per kind
+        let mut operators: Vec<String> = OperatorRegistry::collect()
+            .operators()
+            .iter()
+            .map(|operator| operator.name.to_string())
+            .collect();
+        operators.dedup();
+
+        Ok(Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            features: Features {
+                gdal_version: gdal::version::version_info("RELEASE_NAME"),
+                operators,
+                ogc_endpoints: vec!["wms".to_string(), "wfs".to_string()],
+                arrow_flight: config::get_config_element::<config::Flight>()?.enabled,
+                grpc: cfg!(feature = "grpc")
+                    && config::get_config_element::<config::Grpc>()?.enabled,
+                r_scripts: config::get_config_element::<config::RRuntime>()?
+                    .runtime()
+                    .is_some(),
+                share_links: !config::get_config_element::<config::ShareLinks>()?
+                    .secret
+                    .is_empty(),
+            },
+            limits: Limits {
+                max_image_size: wms.max_image_size,
+                max_supersampling: wms.max_supersampling,
+                max_animation_frames: config::get_config_element::<config::Animation>()?.max_frames,
+                query_timeout_seconds: config::get_config_element::<config::Query>()?
+                    .timeout()
+                    .map(|timeout| timeout.as_secs()),
+                max_running_queries_per_client:
+                    config::get_config_element::<config::QueryAdmission>()?.max_running_per_client,
+                max_upload_size_bytes: upload.max_file_size_bytes,
+                user_quota_bytes: upload.quota(),
+                upload_ttl_seconds: upload.ttl().map(|ttl| ttl.as_secs()),
+            },
+        })
+    }
+}
+
+/// Describe the version, features and limits of the backend
+pub fn info_handler() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get().and(warp::path!("info")).and_then(info)
+}
+
+// TODO: move into handler once async closures are available?
+async fn info() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&ServerInfo::collect()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn info() {
+        let res = warp::test::request()
+            .method("GET")
+            .path("/info")
+            .reply(&info_handler())
+            .await;
+
+        assert_eq!(res.status(), 200);
+
+        let info: ServerInfo = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.features.gdal_version.is_empty());
+        assert!(info.features.operators.contains(&"GdalSource".to_string()));
+        // the default settings
+        assert!(!info.features.share_links);
+        assert_eq!(info.limits.max_image_size, 4096);
+        assert_eq!(info.limits.user_quota_bytes, Some(1_073_741_824));
+        assert_eq!(info.limits.upload_ttl_seconds, None);
+    }
+}
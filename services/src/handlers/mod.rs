@@ -1,12 +1,28 @@
-use crate::error::Error;
+use crate::datasets::pinned_datasets;
+use crate::error::{Error, Result};
 use crate::users::session::{Session, SessionToken};
 use crate::users::userdb::UserDB;
+use crate::util::config;
+use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::resolver::WorkflowSnapshot;
+use crate::workflows::share_link::{ShareLink, ShareParameters};
+use crate::workflows::workflow::{Workflow, WorkflowId};
+use chrono::Utc;
+use geoengine_datatypes::error::ErrorChain;
+use geoengine_operators::engine::{ExecutionContext, Principal, QueryContext};
+use std::future::Future;
+use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use warp::Filter;
 use warp::{Rejection, Reply};
 
+pub mod animation;
+pub mod audit;
+pub mod datasets;
+pub mod info;
+pub mod pinned;
 pub mod projects;
 pub mod users;
 pub mod wfs;
@@ -21,17 +37,72 @@ type DB<T> = Arc<RwLock<T>>;
 ///
 /// Fails if the rejection is not custom
 ///
-pub async fn handle_rejection(error: Rejection) -> Result<impl Reply, Rejection> {
+pub async fn handle_rejection(error: Rejection) -> Result<Box<dyn Reply>, Rejection> {
     // TODO: handle/report serde deserialization error when e.g. a json attribute is missing/malformed
     error.find::<Error>().map_or(Err(warp::reject()), |err| {
-        let json = warp::reply::json(&err.to_string());
-        Ok(warp::reply::with_status(
+        let json = warp::reply::json(&ErrorChain(err).to_string());
+
+        if matches!(
+            err,
+            Error::WorkflowAccessDenied { .. }
+                | Error::DatasetDeletionDenied { .. }
+                | Error::InvalidShareLink
+                | Error::ShareLinkExpired { .. }
+                | Error::WorkflowOperator {
+                    source: geoengine_operators::error::Error::DatasetAccessDenied { .. },
+                    ..
+                }
+        ) {
+            return Ok(Box::new(warp::reply::with_status(
+                json,
+                warp::http::StatusCode::FORBIDDEN,
+            )));
+        }
+
+        if let Error::DatasetUsedByOthers { .. } = err {
+            return Ok(Box::new(warp::reply::with_status(
+                json,
+                warp::http::StatusCode::CONFLICT,
+            )));
+        }
+
+        if let Error::SessionExpired = err {
+            return Ok(Box::new(warp::reply::with_status(
+                json,
+                warp::http::StatusCode::UNAUTHORIZED,
+            )));
+        }
+
+        if let Error::InvalidPassword { violations } = err {
+            return Ok(Box::new(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "error": ErrorChain(err).to_string(),
+                    "violations": violations,
+                })),
+                warp::http::StatusCode::BAD_REQUEST,
+            )));
+        }
+
+        if let Error::TooManyQueries {
+            retry_after_seconds,
+        } = err
+        {
+            return Ok(Box::new(warp::reply::with_header(
+                warp::reply::with_status(json, warp::http::StatusCode::TOO_MANY_REQUESTS),
+                "Retry-After",
+                retry_after_seconds.to_string(),
+            )));
+        }
+
+        Ok(Box::new(warp::reply::with_status(
             json,
             warp::http::StatusCode::BAD_REQUEST,
-        ))
+        )))
     })
 }
 
+/// The session of a request with a session token in its `Authorization` header.
+/// Requests with expired sessions are rejected as unauthorized.
 pub fn authenticate<T: UserDB>(
     user_db: DB<T>,
 ) -> impl warp::Filter<Extract = (Session,), Error = warp::Rejection> + Clone {
@@ -41,7 +112,10 @@ pub fn authenticate<T: UserDB>(
     ) -> Result<Session, warp::Rejection> {
         let token = SessionToken::from_str(&token).map_err(|_| warp::reject())?;
         let db = user_db.read().await;
-        db.session(token).map_err(|_| warp::reject())
+        db.session(token).map_err(|error| match error {
+            Error::SessionExpired => warp::reject::custom(error),
+            _ => warp::reject(),
+        })
     }
 
     warp::any()
@@ -49,3 +123,265 @@ pub fn authenticate<T: UserDB>(
         .and(warp::header::<String>("authorization"))
         .and_then(do_authenticate)
 }
+
+/// The session of a request with a session token in its `Authorization` header or, for clients
+/// that cannot set headers, e.g., QGIS, in its `token` query parameter.
+/// Requests without a token are anonymous, while requests with an invalid token are rejected.
+pub fn optional_session<T: UserDB>(
+    user_db: DB<T>,
+) -> impl warp::Filter<Extract = (Option<Session>,), Error = warp::Rejection> + Clone {
+    async fn do_authenticate<T: UserDB>(
+        user_db: DB<T>,
+        header: Option<String>,
+        query_string: Option<String>,
+    ) -> Result<Option<Session>, warp::Rejection> {
+        let token = match header.or_else(|| query_string.as_deref().and_then(query_token)) {
+            Some(token) => SessionToken::from_str(&token)?,
+            None => return Ok(None),
+        };
+
+        let db = user_db.read().await;
+        let session = db.session(token).map_err(|error| match error {
+            Error::SessionExpired => error,
+            _ => Error::InvalidSessionToken,
+        })?;
+
+        Ok(Some(session))
+    }
+
+    warp::any()
+        .and(warp::any().map(move || Arc::clone(&user_db)))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(
+            warp::query::raw()
+                .map(Some)
+                .or(warp::any().map(|| None))
+                .unify(),
+        )
+        .and_then(do_authenticate)
+}
+
+/// Who queries a workflow: a user, an anonymous client or the holder of a share link
+#[derive(Debug, Clone)]
+pub enum Requester {
+    Session(Session),
+    Anonymous,
+    ShareLink(ShareParameters),
+}
+
+impl Requester {
+    /// Check whether the requester may query the workflow `id`.
+    /// Share links are verified against the workflow, since the link does not name it.
+    pub fn check_access<T>(&self, registry: &T, id: &WorkflowId) -> Result<()>
+    where
+        T: WorkflowRegistry + ?Sized,
+    {
+        match self {
+            Requester::Session(session) => registry.check_access(id, Some(session.user)),
+            Requester::Anonymous => registry.check_access(id, None),
+            Requester::ShareLink(parameters) => ShareLink::from_parameters(*id, parameters)?
+                .verify(
+                    &parameters.signature,
+                    &config::get_config_element::<config::ShareLinks>()?.secret,
+                    Utc::now(),
+                ),
+        }
+    }
+
+    /// Check whether the requester may query the workflow `id` that a queried workflow references.
+    /// Share links only grant access to the shared workflow, so its references have to be public.
+    pub fn check_reference_access<T>(&self, registry: &T, id: &WorkflowId) -> Result<()>
+    where
+        T: WorkflowRegistry + ?Sized,
+    {
+        match self {
+            Requester::ShareLink(_) => Requester::Anonymous.check_access(registry, id),
+            Requester::Session(_) | Requester::Anonymous => self.check_access(registry, id),
+        }
+    }
+
+    /// Load the workflow `id` if the requester may query it
+    pub fn load_workflow<T>(&self, registry: &T, id: &WorkflowId) -> Result<Workflow>
+    where
+        T: WorkflowRegistry + ?Sized,
+    {
+        self.check_access(registry, id)?;
+        registry.load(id)
+    }
+
+    /// On whose behalf the requester's workflows are executed.
+    /// Holders of share links are anonymous, so they only see public datasets.
+    pub fn principal(&self) -> Principal {
+        match self {
+            Requester::Session(session) => Principal::User(session.user.to_string()),
+            Requester::Anonymous | Requester::ShareLink(_) => Principal::Anonymous,
+        }
+    }
+}
+
+/// The requester of a workflow in a WMS, WFS or animation request, i.e., its session, if any,
+/// or the share link of its query string
+pub fn requester<T: UserDB>(
+    user_db: DB<T>,
+) -> impl warp::Filter<Extract = (Requester,), Error = warp::Rejection> + Clone {
+    optional_session(user_db)
+        .and(
+            warp::query::raw()
+                .map(|query_string: String| {
+                    serde_urlencoded::from_str::<ShareParameters>(&query_string).ok()
+                })
+                .or(warp::any().map(|| None))
+                .unify(),
+        )
+        .map(
+            |session: Option<Session>, share_parameters| match (session, share_parameters) {
+                (Some(session), _) => Requester::Session(session),
+                (None, Some(parameters)) => Requester::ShareLink(parameters),
+                (None, None) => Requester::Anonymous,
+            },
+        )
+}
+
+/// The value of the `token` parameter of a query string, ignoring the case of the key like OGC services
+fn query_token(query_string: &str) -> Option<String> {
+    serde_urlencoded::from_str::<Vec<(String, String)>>(query_string)
+        .ok()?
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("token"))
+        .map(|(_, value)| value)
+}
+
+/// Identifies the client of a query for the admission control,
+/// i.e., its session token or, for anonymous requests, its remote address
+pub fn query_client(
+) -> impl warp::Filter<Extract = (String,), Error = std::convert::Infallible> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::addr::remote())
+        .map(
+            |token: Option<String>, address: Option<SocketAddr>| match (token, address) {
+                (Some(token), _) => format!("session:{}", token),
+                (None, Some(address)) => format!("address:{}", address.ip()),
+                (None, None) => "anonymous".to_string(),
+            },
+        )
+}
+
+/// The context for executing vector workflows on behalf of the `requester`, as done by the WFS
+/// and the Arrow Flight service
+pub(crate) fn vector_execution_context(
+    requester: &Requester,
+    referenced_workflows: WorkflowSnapshot,
+) -> Result<ExecutionContext> {
+    Ok(ExecutionContext {
+        pinned_datasets: Some(pinned_datasets()?),
+        workflow_resolver: Some(Arc::new(referenced_workflows)),
+        principal: requester.principal(),
+        ..ExecutionContext::mock_empty()
+    })
+}
+
+/// Run a `query` until it finishes or exceeds the timeout of its `query_ctx`.
+/// A query that exceeds its timeout is dropped, which cancels its streams.
+pub(crate) async fn with_query_timeout<F, T>(
+    query_ctx: QueryContext,
+    query: F,
+) -> crate::error::Result<T>
+where
+    F: Future<Output = crate::error::Result<T>>,
+{
+    match query_ctx.timeout {
+        Some(timeout) => tokio::time::timeout(timeout, query)
+            .await
+            .map_err(|_| Error::QueryTimeout { timeout })?,
+        None => query.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::identifiers::Identifier;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn query_timeout() {
+        let query_ctx = |timeout| QueryContext {
+            chunk_byte_size: 1024,
+            timeout,
+            seed: 0,
+        };
+
+        let slow_query = async {
+            tokio::time::delay_for(Duration::from_millis(500)).await;
+            Ok(42)
+        };
+        assert!(matches!(
+            with_query_timeout(query_ctx(Some(Duration::from_millis(10))), slow_query).await,
+            Err(Error::QueryTimeout { .. })
+        ));
+
+        let fast_query = async { Ok(42) };
+        assert_eq!(
+            with_query_timeout(query_ctx(Some(Duration::from_secs(10))), fast_query)
+                .await
+                .unwrap(),
+            42
+        );
+        assert_eq!(
+            with_query_timeout(query_ctx(None), async { Ok(42) })
+                .await
+                .unwrap(),
+            42
+        );
+    }
+
+    #[test]
+    fn token_from_query_string() {
+        assert_eq!(
+            query_token("request=GetMap&TOKEN=abc&layers=x"),
+            Some("abc".to_string())
+        );
+        assert_eq!(query_token("request=GetMap"), None);
+        assert_eq!(query_token(""), None);
+    }
+
+    #[tokio::test]
+    async fn too_many_queries_are_retried_later() {
+        let response = handle_rejection(warp::reject::custom(Error::TooManyQueries {
+            retry_after_seconds: 2,
+        }))
+        .await
+        .unwrap()
+        .into_response();
+
+        assert_eq!(response.status(), 429);
+        assert_eq!(response.headers()["Retry-After"], "2");
+    }
+
+    #[tokio::test]
+    async fn rejections_report_the_error_chain() {
+        let filter = warp::any()
+            .and_then(|| async {
+                Err::<String, _>(warp::reject::custom(Error::WorkflowOperator {
+                    workflow_id: crate::workflows::workflow::WorkflowId::from_uuid(
+                        uuid::Uuid::nil(),
+                    ),
+                    source: geoengine_operators::error::Error::DataType {
+                        source: geoengine_datatypes::error::Error::TimeIntervalEndBeforeStart {
+                            start: 2,
+                            end: 1,
+                        },
+                    },
+                }))
+            })
+            .recover(handle_rejection);
+
+        let response = warp::test::request().reply(&filter).await;
+
+        assert_eq!(response.status(), 400);
+        assert_eq!(
+            serde_json::from_slice::<String>(response.body()).unwrap(),
+            "Workflow `00000000-0000-0000-0000-000000000000` failed → DataTypeError → Start `2` must be before end `1`"
+        );
+    }
+}
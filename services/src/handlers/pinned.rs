@@ -0,0 +1,182 @@
+use crate::datasets::pinned::{
+    pin_dataset, pinned_dataset_infos, unpin_dataset, PinnedDatasetDefinition,
+};
+use crate::datasets::pinned_datasets;
+use crate::handlers::{Requester, DB};
+use crate::util::audit::{audit_log, AuditEvent, AuditEventKind};
+use crate::util::config;
+use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::resolver::WorkflowSnapshot;
+use crate::workflows::workflow::WorkflowId;
+use geoengine_datatypes::primitives::{BoundingBox2D, TimeInterval};
+use serde::Deserialize;
+use std::sync::Arc;
+use warp::reply::Reply;
+use warp::Filter;
+
+/// Pins the features of a registered vector workflow
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinRequest {
+    pub workflow: WorkflowId,
+    /// The extent of the features to load, defaults to the extent of the workflow's result
+    #[serde(default)]
+    pub bbox: Option<BoundingBox2D>,
+    /// The time of the features to load, defaults to the time of the workflow's result
+    #[serde(default)]
+    pub time_interval: Option<TimeInterval>,
+}
+
+/// List the pinned datasets with the `admin_token` of the `[pinned_datasets]` configuration as
+/// `Authorization`
+pub fn list_pinned_datasets_handler(
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("pinned"))
+        .and(admin())
+        .and_then(list_pinned_datasets)
+}
+
+// TODO: move into handler once async closures are available?
+async fn list_pinned_datasets(is_admin: bool) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    if !is_admin {
+        return Ok(Box::new(
+            warp::http::StatusCode::UNAUTHORIZED.into_response(),
+        ));
+    }
+
+    let infos = pinned_dataset_infos(&pinned_datasets()?)?;
+    Ok(Box::new(warp::reply::json(&infos)))
+}
+
+/// Load the features of a registered vector workflow into memory and serve them as the
+/// `PinnedSource` of the given name, e.g., `PUT /pinned/countries`.
+/// The pinned dataset replaces a previous one of the same name.
+/// Every client may query pinned datasets, so only public workflows can be pinned.
+pub fn pin_dataset_handler<T: WorkflowRegistry>(
+    workflow_registry: DB<T>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::put()
+        .and(warp::path!("pinned" / String))
+        .and(admin())
+        .and(warp::body::json())
+        .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and_then(pin)
+}
+
+// TODO: move into handler once async closures are available?
+async fn pin<T: WorkflowRegistry>(
+    name: String,
+    is_admin: bool,
+    request: PinRequest,
+    workflow_registry: DB<T>,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    if !is_admin {
+        return Ok(Box::new(
+            warp::http::StatusCode::UNAUTHORIZED.into_response(),
+        ));
+    }
+
+    let (workflow, resolver) = {
+        let registry = workflow_registry.read().await;
+        let workflow = Requester::Anonymous.load_workflow(&*registry, &request.workflow)?;
+        let resolver =
+            WorkflowSnapshot::collect(&*registry, &Requester::Anonymous, &workflow.operator);
+        (workflow, resolver)
+    };
+
+    let definition = PinnedDatasetDefinition {
+        workflow,
+        bbox: request.bbox,
+        time_interval: request.time_interval,
+    };
+    let file = config::get_config_element::<config::PinnedDatasets>()?.file;
+    let info = pin_dataset(
+        &name,
+        definition,
+        Arc::new(resolver),
+        &pinned_datasets()?,
+        &file,
+    )
+    .await?;
+
+    audit_log()?.record(AuditEvent::new(AuditEventKind::DatasetPin, name))?;
+    Ok(Box::new(warp::reply::json(&info)))
+}
+
+/// Release the memory of a pinned dataset, e.g., `DELETE /pinned/countries`
+pub fn unpin_dataset_handler(
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::delete()
+        .and(warp::path!("pinned" / String))
+        .and(admin())
+        .and_then(unpin)
+}
+
+// TODO: move into handler once async closures are available?
+async fn unpin(name: String, is_admin: bool) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    if !is_admin {
+        return Ok(Box::new(
+            warp::http::StatusCode::UNAUTHORIZED.into_response(),
+        ));
+    }
+
+    let file = config::get_config_element::<config::PinnedDatasets>()?.file;
+    if !unpin_dataset(&name, &pinned_datasets()?, &file)? {
+        return Ok(Box::new(warp::http::StatusCode::NOT_FOUND.into_response()));
+    }
+
+    audit_log()?.record(AuditEvent::new(AuditEventKind::DatasetUnpin, name))?;
+    Ok(Box::new(warp::http::StatusCode::NO_CONTENT.into_response()))
+}
+
+/// Whether the `Authorization` header matches the `admin_token`, an empty token disables the
+/// endpoints
+fn admin() -> impl Filter<Extract = (bool,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization").and_then(|token: Option<String>| async move {
+        let admin_token = config::get_config_element::<config::PinnedDatasets>()?.admin_token;
+        Ok::<bool, warp::Rejection>(
+            !admin_token.is_empty() && token.as_deref() == Some(admin_token.as_str()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::identifiers::Identifier;
+    use crate::workflows::registry::HashMapRegistry;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn disabled_without_admin_token() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+
+        let res = warp::test::request()
+            .method("GET")
+            .path("/pinned")
+            .header("Authorization", "")
+            .reply(&list_pinned_datasets_handler())
+            .await;
+        assert_eq!(res.status(), 401);
+
+        let res = warp::test::request()
+            .method("PUT")
+            .path("/pinned/countries")
+            .header("Authorization", "")
+            .json(&serde_json::json!({
+                "workflow": WorkflowId::new(),
+            }))
+            .reply(&pin_dataset_handler(workflow_registry))
+            .await;
+        assert_eq!(res.status(), 401);
+
+        let res = warp::test::request()
+            .method("DELETE")
+            .path("/pinned/countries")
+            .header("Authorization", "")
+            .reply(&unpin_dataset_handler())
+            .await;
+        assert_eq!(res.status(), 401);
+    }
+}
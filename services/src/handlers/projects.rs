@@ -1,11 +1,14 @@
 use crate::handlers::{authenticate, DB};
+use crate::projects::bundle::{ImportProject, ProjectBundle};
 use crate::projects::project::{
     CreateProject, LoadVersion, ProjectId, ProjectListOptions, UpdateProject, UserProjectPermission,
 };
 use crate::projects::projectdb::ProjectDB;
 use crate::users::session::Session;
 use crate::users::userdb::UserDB;
+use crate::util::audit::{audit_log, AuditEvent, AuditEventKind};
 use crate::util::user_input::UserInput;
+use crate::workflows::registry::WorkflowRegistry;
 use std::sync::Arc;
 use uuid::Uuid;
 use warp::Filter;
@@ -171,10 +174,13 @@ async fn add_permission<T: ProjectDB>(
     permission: UserProjectPermission,
     project_db: DB<T>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    let details = format!("added {:?}", permission);
     project_db
         .write()
         .await
         .add_permission(session.user, permission)?;
+    audit_log()?
+        .record(AuditEvent::new(AuditEventKind::PermissionChange, details).user(session.user))?;
     Ok(warp::reply())
 }
 
@@ -196,10 +202,13 @@ async fn remove_permission<T: ProjectDB>(
     permission: UserProjectPermission,
     project_db: DB<T>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    let details = format!("removed {:?}", permission);
     project_db
         .write()
         .await
         .remove_permission(session.user, permission)?;
+    audit_log()?
+        .record(AuditEvent::new(AuditEventKind::PermissionChange, details).user(session.user))?;
     Ok(warp::reply())
 }
 
@@ -228,19 +237,80 @@ async fn list_permissions<T: ProjectDB>(
     Ok(warp::reply::json(&permissions))
 }
 
+pub fn export_project_handler<T: UserDB, R: ProjectDB, W: WorkflowRegistry>(
+    user_db: DB<T>,
+    project_db: DB<R>,
+    workflow_registry: DB<W>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("project" / "export"))
+        .and(authenticate(user_db))
+        .and(warp::body::json())
+        .and(warp::any().map(move || Arc::clone(&project_db)))
+        .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and_then(export_project)
+}
+
+// TODO: move into handler once async closures are available?
+async fn export_project<T: ProjectDB, W: WorkflowRegistry>(
+    session: Session,
+    project: ProjectId,
+    project_db: DB<T>,
+    workflow_registry: DB<W>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let project = project_db.read().await.load_latest(session.user, project)?;
+    let bundle = ProjectBundle::export(project, session.user, &*workflow_registry.read().await)?;
+    Ok(warp::reply::json(&bundle))
+}
+
+pub fn import_project_handler<T: UserDB, R: ProjectDB, W: WorkflowRegistry>(
+    user_db: DB<T>,
+    project_db: DB<R>,
+    workflow_registry: DB<W>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("project" / "import"))
+        .and(authenticate(user_db))
+        .and(warp::body::json())
+        .and(warp::any().map(move || Arc::clone(&project_db)))
+        .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and_then(import_project)
+}
+
+// TODO: move into handler once async closures are available?
+async fn import_project<T: ProjectDB, W: WorkflowRegistry>(
+    session: Session,
+    import: ImportProject,
+    project_db: DB<T>,
+    workflow_registry: DB<W>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let import = import.validated()?;
+    let id = ImportProject::import(
+        import,
+        session.user,
+        &mut *project_db.write().await,
+        &mut *workflow_registry.write().await,
+    )?;
+    Ok(warp::reply::json(&id))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::projects::hashmap_projectdb::HashMapProjectDB;
     use crate::projects::project::{
         Layer, LayerInfo, OrderBy, Project, ProjectFilter, ProjectId, ProjectListing,
-        ProjectPermission, ProjectVersion, RasterInfo, STRectangle, UpdateProject,
+        ProjectPermission, ProjectVersion, RasterInfo, STRectangle, UpdateProject, VectorInfo,
     };
     use crate::users::hashmap_userdb::HashMapUserDB;
     use crate::users::user::{UserCredentials, UserRegistration};
     use crate::util::identifiers::Identifier;
-    use crate::workflows::workflow::WorkflowId;
+    use crate::workflows::registry::HashMapRegistry;
+    use crate::workflows::workflow::{Workflow, WorkflowId};
     use geoengine_datatypes::operations::image::Colorizer;
+    use geoengine_datatypes::primitives::Coordinate2D;
+    use geoengine_operators::engine::{TypedOperator, VectorOperator};
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
     use tokio::sync::RwLock;
 
     #[tokio::test]
@@ -976,4 +1046,136 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 2);
     }
+
+    #[tokio::test]
+    async fn export_import() {
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+        let project_db = Arc::new(RwLock::new(HashMapProjectDB::default()));
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+
+        user_db
+            .write()
+            .await
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".to_string(),
+                    password: "secret123".to_string(),
+                    real_name: "Foo Bar".to_string(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+
+        let session = user_db
+            .write()
+            .await
+            .login(UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap();
+
+        let workflow = workflow_registry
+            .write()
+            .await
+            .register(Workflow {
+                operator: TypedOperator::Vector(
+                    MockPointSource {
+                        params: MockPointSourceParams {
+                            points: vec![Coordinate2D::new(1., 2.); 3],
+                        },
+                    }
+                    .boxed(),
+                ),
+            })
+            .unwrap();
+
+        let project = project_db.write().await.create(
+            session.user,
+            CreateProject {
+                name: "Test".to_string(),
+                description: "Foo".to_string(),
+                view: STRectangle::new(0., 0., 1., 1., 0, 1).unwrap(),
+                bounds: STRectangle::new(0., 0., 1., 1., 0, 1).unwrap(),
+            }
+            .validated()
+            .unwrap(),
+        );
+
+        project_db
+            .write()
+            .await
+            .update(
+                session.user,
+                UpdateProject {
+                    id: project,
+                    name: None,
+                    description: None,
+                    layers: Some(vec![Some(Layer {
+                        workflow,
+                        name: "L1".to_string(),
+                        info: LayerInfo::Vector(VectorInfo {}),
+                    })]),
+                    view: None,
+                    bounds: None,
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/project/export")
+            .header("Content-Length", "0")
+            .header("Authorization", session.token.to_string())
+            .json(&project)
+            .reply(&export_project_handler(
+                user_db.clone(),
+                project_db.clone(),
+                workflow_registry.clone(),
+            ))
+            .await;
+
+        assert_eq!(res.status(), 200);
+
+        let body: String = String::from_utf8(res.body().to_vec()).unwrap();
+        let bundle = serde_json::from_str::<ProjectBundle>(&body).unwrap();
+        assert_eq!(bundle.workflows.len(), 1);
+        assert_eq!(bundle.workflows[0].id, workflow);
+
+        let import = ImportProject {
+            bundle,
+            dataset_mapping: Default::default(),
+        };
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/project/import")
+            .header("Content-Length", "0")
+            .header("Authorization", session.token.to_string())
+            .json(&import)
+            .reply(&import_project_handler(
+                user_db.clone(),
+                project_db.clone(),
+                workflow_registry.clone(),
+            ))
+            .await;
+
+        assert_eq!(res.status(), 200);
+
+        let body: String = String::from_utf8(res.body().to_vec()).unwrap();
+        let imported = serde_json::from_str::<ProjectId>(&body).unwrap();
+        assert_ne!(imported, project);
+
+        let loaded = project_db
+            .read()
+            .await
+            .load_latest(session.user, imported)
+            .unwrap();
+        assert_eq!(loaded.name, "Test");
+        assert_eq!(loaded.layers.len(), 1);
+        assert_eq!(loaded.layers[0].workflow, workflow);
+    }
 }
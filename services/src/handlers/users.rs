@@ -1,9 +1,17 @@
+use crate::datasets::storage::StorageUsage;
+use crate::error;
 use crate::error::Result;
-use crate::handlers::{authenticate, DB};
+use crate::handlers::{authenticate, query_client, DB};
+use crate::projects::project::{ProjectId, STRectangle};
+use crate::projects::projectdb::ProjectDB;
 use crate::users::session::Session;
+use crate::users::settings::UserSettings;
 use crate::users::user::{UserCredentials, UserRegistration};
 use crate::users::userdb::UserDB;
+use crate::util::audit::{audit_log, AuditEvent, AuditEventKind};
+use crate::util::config;
 use crate::util::user_input::UserInput;
+use snafu::ResultExt;
 use std::sync::Arc;
 use warp::reply::Reply;
 use warp::Filter;
@@ -34,6 +42,7 @@ pub fn login_handler<T: UserDB>(
     warp::post()
         .and(warp::path!("user" / "login"))
         .and(warp::body::json())
+        .and(query_client())
         .and(warp::any().map(move || Arc::clone(&user_db.clone())))
         .and_then(login)
 }
@@ -41,12 +50,25 @@ pub fn login_handler<T: UserDB>(
 // TODO: move into handler once async closures are available?
 async fn login<T: UserDB>(
     user: UserCredentials,
+    client: String,
     user_db: DB<T>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    let email = user.email.clone();
     let mut db = user_db.write().await;
     match db.login(user) {
-        Ok(id) => Ok(warp::reply::json(&id).into_response()),
-        Err(_) => Ok(warp::http::StatusCode::UNAUTHORIZED.into_response()),
+        Ok(session) => {
+            audit_log()?.record(
+                AuditEvent::new(AuditEventKind::Login, email)
+                    .user(session.user)
+                    .client(client),
+            )?;
+            Ok(warp::reply::json(&session).into_response())
+        }
+        Err(_) => {
+            audit_log()?
+                .record(AuditEvent::new(AuditEventKind::FailedLogin, email).client(client))?;
+            Ok(warp::http::StatusCode::UNAUTHORIZED.into_response())
+        }
     }
 }
 
@@ -67,19 +89,173 @@ async fn logout<T: UserDB>(
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut db = user_db.write().await;
     match db.logout(session.token) {
-        Ok(_) => Ok(warp::reply().into_response()),
+        Ok(_) => {
+            audit_log()?.record(AuditEvent::new(AuditEventKind::Logout, "").user(session.user))?;
+            Ok(warp::reply().into_response())
+        }
         Err(_) => Ok(warp::http::StatusCode::UNAUTHORIZED.into_response()),
     }
 }
 
+pub fn session_handler<T: UserDB>(
+    user_db: DB<T>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("session"))
+        .and(authenticate(user_db))
+        .map(|session: Session| warp::reply::json(&session))
+}
+
+/// Extend the validity of the session by the session TTL
+pub fn refresh_session_handler<T: UserDB>(
+    user_db: DB<T>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("session" / "refresh"))
+        .and(authenticate(user_db.clone()))
+        .and(warp::any().map(move || Arc::clone(&user_db)))
+        .and_then(refresh_session)
+}
+
+// TODO: move into handler once async closures are available?
+async fn refresh_session<T: UserDB>(
+    session: Session,
+    user_db: DB<T>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let session = user_db.write().await.refresh_session(session.token)?;
+    Ok(warp::reply::json(&session))
+}
+
+pub fn session_project_handler<T: UserDB, R: ProjectDB>(
+    user_db: DB<T>,
+    project_db: DB<R>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("session" / "project"))
+        .and(authenticate(user_db.clone()))
+        .and(warp::body::json())
+        .and(warp::any().map(move || Arc::clone(&user_db)))
+        .and(warp::any().map(move || Arc::clone(&project_db)))
+        .and_then(session_project)
+}
+
+// TODO: move into handler once async closures are available?
+async fn session_project<T: UserDB, R: ProjectDB>(
+    session: Session,
+    project: ProjectId,
+    user_db: DB<T>,
+    project_db: DB<R>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // only projects that the user can read can be opened
+    project_db.read().await.load_latest(session.user, project)?;
+
+    user_db
+        .write()
+        .await
+        .set_session_project(&session, project)?;
+    Ok(warp::reply())
+}
+
+pub fn session_view_handler<T: UserDB>(
+    user_db: DB<T>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("session" / "view"))
+        .and(authenticate(user_db.clone()))
+        .and(warp::body::json())
+        .and(warp::any().map(move || Arc::clone(&user_db)))
+        .and_then(session_view)
+}
+
+// TODO: move into handler once async closures are available?
+async fn session_view<T: UserDB>(
+    session: Session,
+    view: STRectangle,
+    user_db: DB<T>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    user_db.write().await.set_session_view(&session, view)?;
+    Ok(warp::reply())
+}
+
+pub fn user_settings_handler<T: UserDB>(
+    user_db: DB<T>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("user" / "settings"))
+        .and(authenticate(user_db.clone()))
+        .and(warp::any().map(move || Arc::clone(&user_db)))
+        .and_then(user_settings)
+}
+
+// TODO: move into handler once async closures are available?
+async fn user_settings<T: UserDB>(
+    session: Session,
+    user_db: DB<T>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let settings = user_db.read().await.settings(session.user)?;
+    Ok(warp::reply::json(&settings))
+}
+
+pub fn update_user_settings_handler<T: UserDB>(
+    user_db: DB<T>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::put()
+        .and(warp::path!("user" / "settings"))
+        .and(authenticate(user_db.clone()))
+        .and(warp::body::json())
+        .and(warp::any().map(move || Arc::clone(&user_db)))
+        .and_then(update_user_settings)
+}
+
+// TODO: move into handler once async closures are available?
+async fn update_user_settings<T: UserDB>(
+    session: Session,
+    settings: UserSettings,
+    user_db: DB<T>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let settings = settings.validated()?;
+    user_db.write().await.set_settings(session.user, settings)?;
+    Ok(warp::reply())
+}
+
+/// The storage that the uploads of the user occupy and their quota
+pub fn user_storage_handler<T: UserDB>(
+    user_db: DB<T>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("user" / "storage"))
+        .and(authenticate(user_db))
+        .and_then(user_storage)
+}
+
+// TODO: move into handler once async closures are available?
+async fn user_storage(session: Session) -> Result<impl warp::Reply, warp::Rejection> {
+    let upload_config = config::get_config_element::<config::Upload>()?;
+    let usage = tokio::task::spawn_blocking(move || {
+        StorageUsage::of_user(
+            &upload_config.directory,
+            session.user,
+            upload_config.quota(),
+        )
+    })
+    .await
+    .context(error::TokioJoin)??;
+    Ok(warp::reply::json(&usage))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::handlers::handle_rejection;
+    use crate::projects::hashmap_projectdb::HashMapProjectDB;
+    use crate::projects::project::CreateProject;
     use crate::users::hashmap_userdb::HashMapUserDB;
     use crate::users::user::UserId;
     use crate::users::userdb::UserDB;
+    use crate::util::clock::MockClock;
+    use crate::util::identifiers::Identifier;
     use crate::util::user_input::Validated;
+    use chrono::{TimeZone, Utc};
     use tokio::sync::RwLock;
 
     #[tokio::test]
@@ -129,6 +305,33 @@ mod tests {
         assert_eq!(res.status(), 400);
     }
 
+    #[tokio::test]
+    async fn register_weak_password() {
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+
+        let user = UserRegistration {
+            email: "foo@bar.de".to_string(),
+            password: "secret".to_string(),
+            real_name: " Foo Bar".to_string(),
+        };
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/user/register")
+            .header("Content-Length", "0")
+            .json(&user)
+            .reply(&register_user_handler(user_db.clone()).recover(handle_rejection))
+            .await;
+
+        assert_eq!(res.status(), 400);
+
+        let body: serde_json::Value = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(
+            body["violations"],
+            serde_json::json!([{"type": "tooShort", "minLength": 8}])
+        );
+    }
+
     #[tokio::test]
     async fn login() {
         let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
@@ -267,4 +470,229 @@ mod tests {
         assert_eq!(res.status(), 404); // TODO: 400?
         assert_eq!(res.body(), "");
     }
+
+    #[tokio::test]
+    async fn session_project_and_view() {
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+        let project_db = Arc::new(RwLock::new(HashMapProjectDB::default()));
+
+        let user = Validated {
+            user_input: UserRegistration {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+                real_name: " Foo Bar".to_string(),
+            },
+        };
+        user_db.write().await.register(user).unwrap();
+
+        let session = user_db
+            .write()
+            .await
+            .login(UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap();
+
+        let project = project_db.write().await.create(
+            session.user,
+            CreateProject {
+                name: "Test".to_string(),
+                description: "Foo".to_string(),
+                view: STRectangle::new(0., 0., 1., 1., 0, 1).unwrap(),
+                bounds: STRectangle::new(0., 0., 1., 1., 0, 1).unwrap(),
+            }
+            .validated()
+            .unwrap(),
+        );
+        let view = STRectangle::new(-10., -20., 10., 20., 0, 100).unwrap();
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/session/project")
+            .header("Content-Length", "0")
+            .header("Authorization", session.token.to_string())
+            .json(&project)
+            .reply(&session_project_handler(
+                user_db.clone(),
+                project_db.clone(),
+            ))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/session/view")
+            .header("Content-Length", "0")
+            .header("Authorization", session.token.to_string())
+            .json(&view)
+            .reply(&session_view_handler(user_db.clone()))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path("/session")
+            .header("Authorization", session.token.to_string())
+            .reply(&session_handler(user_db.clone()))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let restored: Session = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(restored.token, session.token);
+        assert_eq!(restored.project, Some(project));
+        assert_eq!(restored.view, Some(view));
+
+        // projects without permission cannot be opened
+        let res = warp::test::request()
+            .method("POST")
+            .path("/session/project")
+            .header("Content-Length", "0")
+            .header("Authorization", session.token.to_string())
+            .json(&ProjectId::new())
+            .reply(
+                &session_project_handler(user_db.clone(), project_db.clone())
+                    .recover(handle_rejection),
+            )
+            .await;
+        assert_ne!(res.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn refresh_session() {
+        let clock = MockClock::new(Utc.ymd(2020, 1, 1).and_hms(0, 0, 0));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::with_session_ttl(
+            Some(chrono::Duration::hours(1)),
+            Arc::new(clock.clone()),
+        )));
+
+        let user = Validated {
+            user_input: UserRegistration {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+                real_name: " Foo Bar".to_string(),
+            },
+        };
+        user_db.write().await.register(user).unwrap();
+
+        let session = user_db
+            .write()
+            .await
+            .login(UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap();
+
+        clock.advance(chrono::Duration::minutes(30));
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/session/refresh")
+            .header("Authorization", session.token.to_string())
+            .reply(&refresh_session_handler(user_db.clone()))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let refreshed: Session = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(refreshed.token, session.token);
+        assert_eq!(
+            refreshed.valid_until,
+            Some(Utc.ymd(2020, 1, 1).and_hms(1, 30, 0))
+        );
+
+        clock.advance(chrono::Duration::hours(2));
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/session/refresh")
+            .header("Authorization", session.token.to_string())
+            .reply(&refresh_session_handler(user_db.clone()).recover(handle_rejection))
+            .await;
+        assert_eq!(res.status(), 401);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path("/session")
+            .header("Authorization", session.token.to_string())
+            .reply(&session_handler(user_db.clone()).recover(handle_rejection))
+            .await;
+        assert_eq!(res.status(), 401);
+    }
+
+    #[tokio::test]
+    async fn user_settings() {
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+
+        let user = Validated {
+            user_input: UserRegistration {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+                real_name: " Foo Bar".to_string(),
+            },
+        };
+        user_db.write().await.register(user).unwrap();
+
+        let session = user_db
+            .write()
+            .await
+            .login(UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap();
+
+        let settings = serde_json::json!({
+            "preferredCrs": "EPSG:4326",
+            "stylePresets": {
+                "red points": {
+                    "type": "vector",
+                    "style": {
+                        "fill_color": [255, 0, 0, 255],
+                        "stroke_color": [0, 0, 0, 255],
+                        "stroke_width": 1.0,
+                        "point_radius": 3.0
+                    }
+                }
+            }
+        });
+
+        let res = warp::test::request()
+            .method("PUT")
+            .path("/user/settings")
+            .header("Content-Length", "0")
+            .header("Authorization", session.token.to_string())
+            .json(&settings)
+            .reply(&update_user_settings_handler(user_db.clone()))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path("/user/settings")
+            .header("Authorization", session.token.to_string())
+            .reply(&user_settings_handler(user_db.clone()))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let body: serde_json::Value = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(body["preferredCrs"], "EPSG:4326");
+        assert_eq!(body["defaultColorizers"], serde_json::json!({}));
+        assert_eq!(body["stylePresets"], settings["stylePresets"]);
+
+        let res = warp::test::request()
+            .method("PUT")
+            .path("/user/settings")
+            .header("Content-Length", "0")
+            .header("Authorization", session.token.to_string())
+            .json(&serde_json::json!({
+                "defaultColorizers": {},
+                "stylePresets": {
+                    "": {"type": "vector", "style": settings["stylePresets"]["red points"]["style"]}
+                }
+            }))
+            .reply(&update_user_settings_handler(user_db.clone()).recover(handle_rejection))
+            .await;
+        assert_eq!(res.status(), 400);
+    }
 }
@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Instant;
 
 use snafu::ResultExt;
 use tokio::sync::RwLock;
@@ -8,29 +9,42 @@ use warp::{http::Response, Filter};
 
 use crate::error;
 use crate::error::Result;
+use crate::handlers::{
+    query_client, requester, vector_execution_context, with_query_timeout, Requester,
+};
+use crate::ogc::util::{query_time, AxisOrder};
 use crate::ogc::wfs::request::{GetCapabilities, GetFeature, TypeNames, WFSRequest};
+use crate::users::userdb::UserDB;
+use crate::util::admission::query_admission;
+use crate::util::clock::SystemClock;
+use crate::util::config;
 use crate::util::identifiers::Identifier;
 use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::resolver::WorkflowSnapshot;
 use crate::workflows::workflow::{Workflow, WorkflowId};
 use futures::StreamExt;
-use geoengine_datatypes::primitives::{FeatureData, MultiPoint, TimeInstance, TimeInterval};
+use geoengine_datatypes::primitives::{FeatureData, MultiPoint, TimeInterval};
 use geoengine_datatypes::{
     collections::{FeatureCollection, MultiPointCollection},
     primitives::SpatialResolution,
 };
 use geoengine_operators::engine::{
-    ExecutionContext, QueryContext, QueryRectangle, TypedVectorQueryProcessor, VectorQueryProcessor,
+    InitializedOperatorBase, QueryContext, QueryRectangle, ResultDescriptor, TypedOperator,
+    TypedVectorQueryProcessor, VectorQueryProcessor,
 };
 use serde_json::json;
 
 type WR<T> = Arc<RwLock<T>>;
 
-pub fn wfs_handler<T: WorkflowRegistry>(
+pub fn wfs_handler<T: WorkflowRegistry, U: UserDB>(
     workflow_registry: WR<T>,
+    user_db: WR<U>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::get()
         .and(warp::path!("wfs"))
         .and(warp::query::<WFSRequest>())
+        .and(query_client())
+        .and(requester(user_db))
         .and(warp::any().map(move || Arc::clone(&workflow_registry)))
         .and_then(wfs)
 }
@@ -38,13 +52,16 @@ pub fn wfs_handler<T: WorkflowRegistry>(
 // TODO: move into handler once async closures are available?
 async fn wfs<T: WorkflowRegistry>(
     request: WFSRequest,
+    client: String,
+    requester: Requester,
     workflow_registry: WR<T>,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
-    // TODO: authentication
     // TODO: more useful error output than "invalid query string"
     match request {
         WFSRequest::GetCapabilities(request) => get_capabilities(&request),
-        WFSRequest::GetFeature(request) => get_feature(&request, &workflow_registry).await,
+        WFSRequest::GetFeature(request) => {
+            get_feature(&request, &client, &requester, &workflow_registry).await
+        }
         _ => Ok(Box::new(
             warp::http::StatusCode::NOT_IMPLEMENTED.into_response(),
         )),
@@ -150,6 +167,8 @@ fn get_capabilities(_request: &GetCapabilities) -> Result<Box<dyn warp::Reply>,
 
 async fn get_feature<T: WorkflowRegistry>(
     request: &GetFeature,
+    client: &str,
+    requester: &Requester,
     workflow_registry: &WR<T>,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
     // TODO: validate request?
@@ -162,10 +181,20 @@ async fn get_feature<T: WorkflowRegistry>(
         return get_feature_mock(request);
     }
 
+    // the query counts against the client's limit until the response is built
+    let _permit = query_admission()?.admit(client).await?;
+
+    // only registered workflows have usage statistics
+    let mut workflow_id = None;
+
     let workflow: Workflow = match request.type_names.namespace.as_deref() {
-        Some("registry") => workflow_registry.read().await.load(&WorkflowId::from_uuid(
-            Uuid::parse_str(&request.type_names.feature_type).context(error::Uuid)?,
-        ))?,
+        Some("registry") => {
+            let id = WorkflowId::from_uuid(
+                Uuid::parse_str(&request.type_names.feature_type).context(error::Uuid)?,
+            );
+            workflow_id = Some(id);
+            requester.load_workflow(&*workflow_registry.read().await, &id)?
+        }
         Some("json") => {
             serde_json::from_str(&request.type_names.feature_type).context(error::SerdeJson)?
         }
@@ -177,48 +206,102 @@ async fn get_feature<T: WorkflowRegistry>(
         }
     };
 
-    let operator = workflow.operator.get_vector().context(error::Operator)?;
+    let referenced_workflows = WorkflowSnapshot::collect(
+        &*workflow_registry.read().await,
+        requester,
+        &workflow.operator,
+    );
+
+    let operator = match workflow.operator {
+        TypedOperator::Vector(operator) => operator,
+        TypedOperator::Raster(_) => {
+            return Err(error::Error::InvalidWorkflowResultType {
+                expected: "Vector".to_string(),
+                found: "Raster".to_string(),
+                hint: "Request raster workflows via the WMS endpoint".to_string(),
+            }
+            .into())
+        }
+        TypedOperator::Plot(_) => {
+            return Err(error::Error::InvalidWorkflowResultType {
+                expected: "Vector".to_string(),
+                found: "Plot".to_string(),
+                hint: "Plots cannot be requested as features".to_string(),
+            }
+            .into())
+        }
+    };
+
+    let start = Instant::now();
 
-    let execution_context = ExecutionContext::mock_empty();
+    let execution_context = vector_execution_context(requester, referenced_workflows)?;
     let initialized = operator
         .initialize(&execution_context)
-        .context(error::Operator)?;
-
-    let processor = initialized.query_processor().context(error::Operator)?;
+        .map_err(|source| operator_error(workflow_id, source))?;
+
+    let processor = initialized
+        .query_processor()
+        .map_err(|source| operator_error(workflow_id, source))?;
+
+    // WFS 1.0.0 and requests without `srsName` use lon/lat
+    let axis_order = match request.srs_name {
+        Some(srs_name) => AxisOrder::of_crs(
+            &srs_name.to_string(),
+            !request.version.starts_with("1.0"),
+            config::get_config_element::<config::Ogc>()?.lon_lat_axis_order,
+        ),
+        None => AxisOrder::EastNorth,
+    };
 
     let query_rect = QueryRectangle {
-        bbox: request.bbox,
-        time_interval: request.time.unwrap_or_else(|| {
-            let time = TimeInstance::from(chrono::offset::Utc::now());
-            TimeInterval::new_unchecked(time, time)
-        }),
+        bbox: axis_order.to_east_north(request.bbox),
+        time_interval: query_time(request.time, &SystemClock),
         spatial_resolution: SpatialResolution::zero_point_one(),
     };
+    let query_config = config::get_config_element::<config::Query>()?;
     let query_ctx = QueryContext {
         // TODO: use production config and test config sizes here
         chunk_byte_size: 1024,
+        timeout: query_config.timeout(),
+        seed: query_config.seed,
     };
 
-    // TODO: support geojson output for types other than multipoints
-    let json = match processor {
-        // TypedVectorQueryProcessor::Data(p) => {
-        //     vector_stream_to_geojson(p, query_rect, query_ctx).await
-        // }
-        TypedVectorQueryProcessor::MultiPoint(p) => {
-            point_stream_to_geojson(p, query_rect, query_ctx).await
-        }
-        // TypedVectorQueryProcessor::MultiLineString(p) => {
-        //     vector_stream_to_geojson(p, query_rect, query_ctx).await
-        // }
-        // TypedVectorQueryProcessor::MultiPolygon(p) => {
-        //     vector_stream_to_geojson(p, query_rect, query_ctx).await
-        // }
-        _ => {
-            return Ok(Box::new(
-                warp::http::StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-            ));
-        }
-    }?;
+    let json = if initialized.result_descriptor().has_data_for(&query_rect) {
+        // TODO: support geojson output for types other than multipoints
+        match processor {
+            // TypedVectorQueryProcessor::Data(p) => {
+            //     vector_stream_to_geojson(p, query_rect, query_ctx).await
+            // }
+            TypedVectorQueryProcessor::MultiPoint(p) => {
+                with_query_timeout(query_ctx, point_stream_to_geojson(p, query_rect, query_ctx))
+                    .await
+            }
+            // TypedVectorQueryProcessor::MultiLineString(p) => {
+            //     vector_stream_to_geojson(p, query_rect, query_ctx).await
+            // }
+            // TypedVectorQueryProcessor::MultiPolygon(p) => {
+            //     vector_stream_to_geojson(p, query_rect, query_ctx).await
+            // }
+            _ => {
+                return Ok(Box::new(
+                    warp::http::StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+                ));
+            }
+        }?
+    } else {
+        // the query lies outside of the bounds of the result, so the operators are not queried
+        json!({
+            "type": "FeatureCollection",
+            "features": []
+        })
+    };
+
+    if let Some(workflow_id) = workflow_id {
+        workflow_registry
+            .write()
+            .await
+            .record_usage(&workflow_id, start.elapsed())?;
+    }
 
     Ok(Box::new(
         Response::builder()
@@ -231,6 +314,20 @@ async fn get_feature<T: WorkflowRegistry>(
 // TODO: generify function to work with arbitrary FeatureCollection<T>.
 //       Currently the problem is the lifetime on the IntoGeometryOptionIterator trait bound
 //       that is required for calling to_geo_json on a feature collection
+/// Attach the id of registered workflows to the errors of their operators
+fn operator_error(
+    workflow_id: Option<WorkflowId>,
+    source: geoengine_operators::error::Error,
+) -> error::Error {
+    match workflow_id {
+        Some(workflow_id) => error::Error::WorkflowOperator {
+            workflow_id,
+            source,
+        },
+        None => error::Error::Operator { source },
+    }
+}
+
 async fn point_stream_to_geojson(
     processor: Box<dyn VectorQueryProcessor<VectorType = FeatureCollection<MultiPoint>>>,
     query_rect: QueryRectangle,
@@ -305,13 +402,15 @@ fn get_feature_mock(_request: &GetFeature) -> Result<Box<dyn warp::Reply>, warp:
 
 #[cfg(test)]
 mod tests {
-    use geoengine_operators::source::CsvSourceParameters;
+    use geoengine_operators::source::{CsvSourceParameters, GdalSource, GdalSourceParameters};
 
+    use crate::users::hashmap_userdb::HashMapUserDB;
     use crate::workflows::registry::HashMapRegistry;
 
     use super::*;
+    use crate::handlers::handle_rejection;
     use crate::workflows::workflow::Workflow;
-    use geoengine_operators::engine::TypedOperator;
+    use geoengine_operators::engine::RasterOperator;
     use geoengine_operators::source::csv::{
         CsvGeometrySpecification, CsvSource, CsvTimeSpecification,
     };
@@ -326,7 +425,10 @@ mod tests {
         let res = warp::test::request()
             .method("GET")
             .path("/wfs?request=GetFeature&service=WFS&version=2.0.0&typeNames=test&bbox=1,2,3,4")
-            .reply(&wfs_handler(workflow_registry))
+            .reply(&wfs_handler(
+                workflow_registry,
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
             .await;
         assert_eq!(res.status(), 200);
         let body: String = String::from_utf8(res.body().to_vec()).unwrap();
@@ -419,7 +521,10 @@ mod tests {
         let res = warp::test::request()
             .method("GET")
             .path("/wfs?request=GetCapabilities&service=WFS")
-            .reply(&wfs_handler(workflow_registry))
+            .reply(&wfs_handler(
+                workflow_registry,
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
             .await;
 
         assert_eq!(res.status(), 200);
@@ -472,7 +577,7 @@ x;y
         let res = warp::test::request()
             .method("GET")
             .path(&format!("/wfs?request=GetFeature&service=WFS&version=2.0.0&typeNames=registry:{}&bbox=-90,-180,90,180&crs=EPSG:4326", id.to_string()))
-            .reply(&wfs_handler(workflow_registry))
+            .reply(&wfs_handler(workflow_registry, Arc::new(RwLock::new(HashMapUserDB::default()))))
             .await;
         let body: String = String::from_utf8(res.body().to_vec()).unwrap();
         assert_eq!(
@@ -567,7 +672,10 @@ x;y
         let res = warp::test::request()
             .method("GET")
             .path(&url)
-            .reply(&wfs_handler(workflow_registry))
+            .reply(&wfs_handler(
+                workflow_registry,
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
             .await;
         let body: String = String::from_utf8(res.body().to_vec()).unwrap();
         assert_eq!(
@@ -616,4 +724,97 @@ x;y
         );
         assert_eq!(res.status(), 200);
     }
+
+    #[tokio::test]
+    async fn get_feature_lat_lon() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            temp_file,
+            "
+x;y
+0;1
+2;3
+4;5
+"
+        )
+        .unwrap();
+        temp_file.seek(SeekFrom::Start(0)).unwrap();
+
+        let workflow = Workflow {
+            operator: TypedOperator::Vector(Box::new(CsvSource {
+                params: CsvSourceParameters {
+                    file_path: temp_file.path().into(),
+                    field_separator: ';',
+                    geometry: CsvGeometrySpecification::XY {
+                        x: "x".into(),
+                        y: "y".into(),
+                    },
+                    time: CsvTimeSpecification::None,
+                },
+            })),
+        };
+
+        let json = serde_json::to_string(&workflow).unwrap();
+
+        // latitudes 2 to 6 and longitudes 0 to 2.5 only contain the point (2, 3)
+        let params = &[
+            ("request", "GetFeature"),
+            ("service", "WFS"),
+            ("version", "2.0.0"),
+            ("typeNames", &format!("json:{}", json)),
+            ("bbox", "2,0,6,2.5"),
+            ("srsName", "EPSG:4326"),
+        ];
+        let url = format!("/wfs?{}", &serde_urlencoded::to_string(params).unwrap());
+        let res = warp::test::request()
+            .method("GET")
+            .path(&url)
+            .reply(&wfs_handler(
+                Arc::new(RwLock::new(HashMapRegistry::default())),
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let body: serde_json::Value = serde_json::from_slice(res.body()).unwrap();
+        let features = body["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0]["geometry"]["coordinates"], json!([2.0, 3.0]));
+    }
+
+    #[tokio::test]
+    async fn get_feature_raster_workflow() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+
+        let workflow = Workflow {
+            operator: TypedOperator::Raster(
+                GdalSource {
+                    params: GdalSourceParameters {
+                        dataset_id: "test".to_owned(),
+                        channel: None,
+                        band: None,
+                    },
+                }
+                .boxed(),
+            ),
+        };
+
+        let id = workflow_registry
+            .write()
+            .await
+            .register(workflow.clone())
+            .unwrap();
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&format!("/wfs?request=GetFeature&service=WFS&version=2.0.0&typeNames=registry:{}&bbox=-90,-180,90,180&crs=EPSG:4326", id.to_string()))
+            .reply(&wfs_handler(workflow_registry, Arc::new(RwLock::new(HashMapUserDB::default()))).recover(handle_rejection))
+            .await;
+
+        assert_eq!(res.status(), 400);
+
+        let body: String = String::from_utf8(res.body().to_vec()).unwrap();
+        assert!(body.contains("Raster"));
+        assert!(body.contains("WMS"));
+    }
 }
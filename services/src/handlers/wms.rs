@@ -1,37 +1,65 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use snafu::ResultExt;
+use lazy_static::lazy_static;
+use snafu::{ensure, ResultExt};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 use warp::reply::Reply;
 use warp::{http::Response, Filter, Rejection};
 
 use geoengine_datatypes::{
-    operations::image::{Colorizer, ToPng},
+    operations::image::{
+        Canvas, Colorizer, DrawOnCanvas, MapDecorations, RgbaColor, ToPng, VectorStyle,
+    },
     primitives::SpatialResolution,
 };
 use geoengine_datatypes::{
     primitives::BoundingBox2D,
-    raster::{Blit, GeoTransform, Pixel, Raster2D},
+    raster::{Blit, GeoTransform, Pixel, Raster2D, TileStatistics},
 };
 
+use crate::datasets::{
+    dataset_statistics, gdal_dataset_pool, pinned_datasets, SharedDatasetDefinitions,
+};
 use crate::error;
 use crate::error::Result;
+use crate::handlers::{query_client, requester, with_query_timeout, Requester};
+use crate::ogc::util::{query_time, AxisOrder};
 use crate::ogc::wms::request::{GetCapabilities, GetLegendGraphic, GetMap, WMSRequest};
+use crate::users::userdb::UserDB;
+use crate::util::admission::query_admission;
+use crate::util::clock::SystemClock;
+use crate::util::config;
 use crate::util::identifiers::Identifier;
 use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::resolver::WorkflowSnapshot;
 use crate::workflows::workflow::WorkflowId;
 use futures::StreamExt;
-use geoengine_datatypes::primitives::{TimeInstance, TimeInterval};
 use geoengine_operators::call_on_generic_raster_processor;
 use geoengine_operators::engine::{
-    ExecutionContext, QueryContext, QueryRectangle, RasterQueryProcessor,
+    ExecutionContext, InitializedOperatorBase, QueryContext, QueryRectangle, RasterQueryProcessor,
+    RasterResultDescriptor, ResultDescriptor, TypedOperator, TypedVectorQueryProcessor,
+    VectorQueryProcessor,
 };
+use geoengine_operators::source::{select_band, statistics_dataset, RasterDatasetStatistics};
+
+/// `stretch:auto` with precomputed dataset statistics stretches between these quantiles, so
+/// that a few outliers do not darken or brighten the whole layer
+const AUTO_STRETCH_QUANTILES: (f64, f64) = (0.02, 0.98);
+
+lazy_static! {
+    static ref GLOBAL_STATISTICS: Mutex<HashMap<WorkflowId, TileStatistics>> =
+        Mutex::new(HashMap::new());
+}
 
 type WR<T> = Arc<RwLock<T>>;
 
-pub fn wms_handler<T: WorkflowRegistry>(
+pub fn wms_handler<T: WorkflowRegistry, U: UserDB>(
     workflow_registry: WR<T>,
+    dataset_definitions: SharedDatasetDefinitions,
+    user_db: WR<U>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::get()
         .and(warp::path!("wms"))
@@ -46,20 +74,34 @@ pub fn wms_handler<T: WorkflowRegistry>(
             }),
         )
         // .and(warp::query::<WMSRequest>())
+        .and(query_client())
+        .and(requester(user_db))
         .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and(warp::any().map(move || Arc::clone(&dataset_definitions)))
         .and_then(wms)
 }
 
 // TODO: move into handler once async closures are available?
 async fn wms<T: WorkflowRegistry>(
     request: WMSRequest,
+    client: String,
+    requester: Requester,
     workflow_registry: WR<T>,
+    dataset_definitions: SharedDatasetDefinitions,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
-    // TODO: authentication
     // TODO: more useful error output than "invalid query string"
     match request {
         WMSRequest::GetCapabilities(request) => get_capabilities(&request),
-        WMSRequest::GetMap(request) => get_map(&request, &workflow_registry).await,
+        WMSRequest::GetMap(request) => {
+            get_map(
+                &request,
+                &client,
+                &requester,
+                &workflow_registry,
+                dataset_definitions,
+            )
+            .await
+        }
         WMSRequest::GetLegendGraphic(request) => get_legend_graphic(&request, &workflow_registry),
         _ => Ok(Box::new(
             warp::http::StatusCode::NOT_IMPLEMENTED.into_response(),
@@ -71,6 +113,15 @@ fn get_capabilities(_request: &GetCapabilities) -> Result<Box<dyn warp::Reply>,
     // TODO: implement
     // TODO: inject correct url of the instance and return data for the default layer
     let wms_url = "http://localhost/wms".to_string();
+    let bbox = AxisOrder::of_crs(
+        "EPSG:4326",
+        true,
+        config::get_config_element::<config::Ogc>()?.lon_lat_axis_order,
+    )
+    .from_east_north(BoundingBox2D::new_unchecked(
+        (-180., -90.).into(),
+        (180., 90.).into(),
+    ));
     let mock = format!(
         r#"<WMS_Capabilities xmlns="http://www.opengis.net/wms" xmlns:sld="http://www.opengis.net/sld" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" version="1.3.0" xsi:schemaLocation="http://www.opengis.net/wms http://schemas.opengis.net/wms/1.3.0/capabilities_1_3_0.xsd http://www.opengis.net/sld http://schemas.opengis.net/sld/1.1.0/sld_capabilities.xsd">
     <Service>
@@ -116,11 +167,15 @@ fn get_capabilities(_request: &GetCapabilities) -> Result<Box<dyn warp::Reply>,
                 <southBoundLatitude>-90</southBoundLatitude>
                 <northBoundLatitude>90</northBoundLatitude>
             </EX_GeographicBoundingBox>
-            <BoundingBox CRS="EPSG:4326" minx="-90.0" miny="-180.0" maxx="90.0" maxy="180.0"/>
+            <BoundingBox CRS="EPSG:4326" minx="{min_x:.1}" miny="{min_y:.1}" maxx="{max_x:.1}" maxy="{max_y:.1}"/>
         </Layer>
     </Capability>
 </WMS_Capabilities>"#,
-        wms_url = wms_url
+        wms_url = wms_url,
+        min_x = bbox.lower_left().x,
+        min_y = bbox.lower_left().y,
+        max_x = bbox.upper_right().x,
+        max_y = bbox.upper_right().y,
     );
 
     Ok(Box::new(warp::reply::html(mock)))
@@ -128,65 +183,249 @@ fn get_capabilities(_request: &GetCapabilities) -> Result<Box<dyn warp::Reply>,
 
 async fn get_map<T: WorkflowRegistry>(
     request: &GetMap,
+    client: &str,
+    requester: &Requester,
     workflow_registry: &WR<T>,
+    dataset_definitions: SharedDatasetDefinitions,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
     // TODO: validate request?
     if request.layers == "mock_raster" {
         return get_map_mock(request);
     }
 
-    let workflow = workflow_registry.read().await.load(&WorkflowId::from_uuid(
-        Uuid::parse_str(&request.layers).context(error::Uuid)?,
-    ))?;
+    // the query counts against the client's limit until the image is rendered
+    let _permit = query_admission()?.admit(client).await?;
 
-    let operator = workflow.operator.get_raster().context(error::Operator)?;
+    let image_bytes =
+        render_map(request, requester, workflow_registry, dataset_definitions).await?;
 
-    let execution_context = ExecutionContext {
-        raster_data_root: "../operators/test-data/raster".into(), // ./ is the crate root when run as example from the multi crate root... doh
-    };
+    Ok(Box::new(
+        Response::builder()
+            .header("Content-Type", "image/png")
+            .body(image_bytes)
+            .context(error::HTTP)?,
+    ))
+}
 
-    let initialized = operator
-        .initialize(&execution_context)
-        .context(error::Operator)?;
+/// The axis order of the `bbox` of a `GetMap` request.
+/// Only WMS 1.3.0 follows the axis order of the CRS, which is lat/lon for EPSG:4326.
+fn request_axis_order(request: &GetMap) -> Result<AxisOrder> {
+    Ok(AxisOrder::of_crs(
+        &request.crs,
+        request.version.starts_with("1.3"),
+        config::get_config_element::<config::Ogc>()?.lon_lat_axis_order,
+    ))
+}
 
-    let processor = initialized.query_processor().context(error::Operator)?;
+/// Render the PNG image of a `GetMap` request and record the usage of its workflow.
+/// Private workflows are only rendered for the users of their access list or with a share link.
+///
+/// The caller is responsible for the admission of the query.
+pub(crate) async fn render_map<T: WorkflowRegistry>(
+    request: &GetMap,
+    requester: &Requester,
+    workflow_registry: &WR<T>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> Result<Vec<u8>> {
+    let max_image_size = config::get_config_element::<config::Wms>()?.max_image_size;
+    ensure!(
+        request.width <= max_image_size && request.height <= max_image_size,
+        error::ImageTooLarge {
+            max: max_image_size
+        }
+    );
 
-    let query_bbox = BoundingBox2D::new(
-        (request.bbox.lower_left().y, request.bbox.lower_left().x).into(),
-        (request.bbox.upper_right().y, request.bbox.upper_right().x).into(),
-    )
-    .context(error::DataType)?; // FIXME: handle WGS84 reverse order axes
+    let workflow_id = WorkflowId::from_uuid(Uuid::parse_str(&request.layers).context(error::Uuid)?);
+    let (workflow, referenced_workflows) = {
+        let registry = workflow_registry.read().await;
+        let workflow = requester.load_workflow(&*registry, &workflow_id)?;
+        let referenced_workflows =
+            WorkflowSnapshot::collect(&*registry, requester, &workflow.operator);
+        (workflow, referenced_workflows)
+    };
+    let start = Instant::now();
+
+    let execution_context = ExecutionContext {
+        raster_data_root: config::get_config_element::<config::Raster>()?.data_root,
+        dataset_definitions: Some(dataset_definitions),
+        gdal_dataset_pool: Some(gdal_dataset_pool()?),
+        dataset_statistics: Some(dataset_statistics()?),
+        pinned_datasets: Some(pinned_datasets()?),
+        r_runtime: config::get_config_element::<config::RRuntime>()?.runtime(),
+        workflow_resolver: Some(Arc::new(referenced_workflows)),
+        resolving_workflows: vec![],
+        principal: requester.principal(),
+    };
+
+    let query_bbox = request_axis_order(request)?.to_east_north(request.bbox);
+    let decorations = parse_decorations(request, query_bbox, &config::get_config_element()?)?;
+    let background = parse_background(request)?;
+    let dpi_scale = parse_dpi_scale(request)?;
+    let supersampling =
+        parse_supersampling(&request.styles, dpi_scale, &config::get_config_element()?)?;
     let x_query_resolution = query_bbox.size_x() / f64::from(request.width);
     let y_query_resolution = query_bbox.size_y() / f64::from(request.height);
 
     let query_rect = QueryRectangle {
         bbox: query_bbox,
-        time_interval: request.time.unwrap_or_else(|| {
-            let time = TimeInstance::from(chrono::offset::Utc::now());
-            TimeInterval::new_unchecked(time, time)
-        }),
+        time_interval: query_time(request.time, &SystemClock),
         spatial_resolution: SpatialResolution::new_unchecked(
             x_query_resolution,
             y_query_resolution,
         ),
     };
 
+    let query_config = config::get_config_element::<config::Query>()?;
     let query_ctx = QueryContext {
         // TODO: define meaningful query context
         chunk_byte_size: 1024,
+        timeout: query_config.timeout(),
+        seed: query_config.seed,
     };
 
-    let image_bytes = call_on_generic_raster_processor!(
-        processor,
-        p => raster_stream_to_png_bytes(p, query_rect, query_ctx, request).await
-    )?;
+    let operator = match parse_band(&request.styles) {
+        Some(band) => select_band(workflow.operator, band).context(error::Operator)?,
+        None => workflow.operator,
+    };
 
-    Ok(Box::new(
-        Response::builder()
-            .header("Content-Type", "image/png")
-            .body(image_bytes)
-            .context(error::HTTP)?,
-    ))
+    let image_bytes = match operator {
+        TypedOperator::Raster(operator) => {
+            let precomputed_statistics = statistics_dataset(operator.as_ref()).and_then(|id| {
+                execution_context
+                    .dataset_statistics
+                    .as_ref()?
+                    .read()
+                    .ok()?
+                    .get(&id)
+                    .cloned()
+            });
+
+            let initialized = operator
+                .initialize(&execution_context)
+                .context(error::WorkflowOperator { workflow_id })?;
+
+            let result_descriptor = initialized.result_descriptor();
+
+            if result_descriptor.has_data_for(&query_rect) {
+                let processor = initialized
+                    .query_processor()
+                    .context(error::WorkflowOperator { workflow_id })?;
+
+                // the raster is queried at the supersampled size and downsampled when rendering
+                let raster_query_rect = QueryRectangle {
+                    spatial_resolution: SpatialResolution::new_unchecked(
+                        x_query_resolution / f64::from(supersampling),
+                        y_query_resolution / f64::from(supersampling),
+                    ),
+                    ..query_rect
+                };
+
+                call_on_generic_raster_processor!(
+                    processor,
+                    p => with_query_timeout(
+                        query_ctx,
+                        raster_stream_to_png_bytes(
+                            p,
+                            raster_query_rect,
+                            query_ctx,
+                            request,
+                            supersampling,
+                            &workflow_id,
+                            &result_descriptor,
+                            precomputed_statistics.as_ref()
+                        )
+                    ).await
+                )?
+            } else {
+                // the query lies outside of the bounds of the result, so the operators are not queried
+                Canvas::new(request.width, request.height, query_bbox)
+                    .to_png()
+                    .context(error::DataType)?
+            }
+        }
+        TypedOperator::Vector(operator) => {
+            let style =
+                parse_vector_style(&request.styles)?.scaled(f64::from(supersampling) * dpi_scale);
+
+            let initialized = operator
+                .initialize(&execution_context)
+                .context(error::WorkflowOperator { workflow_id })?;
+
+            let canvas = Canvas::new(
+                request.width * supersampling,
+                request.height * supersampling,
+                query_bbox,
+            );
+
+            let canvas = if initialized.result_descriptor().has_data_for(&query_rect) {
+                match initialized
+                    .query_processor()
+                    .context(error::WorkflowOperator { workflow_id })?
+                {
+                    TypedVectorQueryProcessor::Data(_) => {
+                        return Err(error::Error::NoGeometriesToRender.into())
+                    }
+                    TypedVectorQueryProcessor::MultiPoint(p) => {
+                        with_query_timeout(
+                            query_ctx,
+                            vector_stream_to_canvas(p, query_rect, query_ctx, canvas, &style),
+                        )
+                        .await
+                    }
+                    TypedVectorQueryProcessor::MultiLineString(p) => {
+                        with_query_timeout(
+                            query_ctx,
+                            vector_stream_to_canvas(p, query_rect, query_ctx, canvas, &style),
+                        )
+                        .await
+                    }
+                    TypedVectorQueryProcessor::MultiPolygon(p) => {
+                        with_query_timeout(
+                            query_ctx,
+                            vector_stream_to_canvas(p, query_rect, query_ctx, canvas, &style),
+                        )
+                        .await
+                    }
+                }
+            } else {
+                // the query lies outside of the bounds of the result, so the canvas stays empty
+                Ok(canvas)
+            }?;
+
+            canvas
+                .downsampled(supersampling)
+                .to_png()
+                .context(error::DataType)?
+        }
+        TypedOperator::Plot(_) => {
+            return Err(error::Error::InvalidWorkflowResultType {
+                expected: "Raster or Vector".to_string(),
+                found: "Plot".to_string(),
+                hint: "Plots cannot be rendered as map layers".to_string(),
+            }
+            .into())
+        }
+    };
+
+    let image_bytes = if decorations.is_some() || background.is_some() {
+        let mut canvas = Canvas::from_png(&image_bytes, query_bbox).context(error::DataType)?;
+        if let Some(background) = background {
+            canvas.fill_background(background);
+        }
+        if let Some(decorations) = decorations {
+            canvas.decorate(&decorations);
+        }
+        canvas.to_png().context(error::DataType)?
+    } else {
+        image_bytes
+    };
+
+    workflow_registry
+        .write()
+        .await
+        .record_usage(&workflow_id, start.elapsed())?;
+
+    Ok(image_bytes)
 }
 
 async fn raster_stream_to_png_bytes<T>(
@@ -194,18 +433,28 @@ async fn raster_stream_to_png_bytes<T>(
     query_rect: QueryRectangle,
     query_ctx: QueryContext,
     request: &GetMap,
+    supersampling: u32,
+    workflow_id: &WorkflowId,
+    result_descriptor: &RasterResultDescriptor,
+    dataset_statistics: Option<&RasterDatasetStatistics>,
 ) -> Result<Vec<u8>>
 where
     T: Pixel,
 {
+    let style = parse_raster_style(&request.styles)?;
+
     let tile_stream = processor.raster_query(query_rect, query_ctx);
 
-    let x_query_resolution = query_rect.bbox.size_x() / f64::from(request.width);
-    let y_query_resolution = query_rect.bbox.size_y() / f64::from(request.height);
+    // the raster has `supersampling` times the size of the image
+    let width = request.width * supersampling;
+    let height = request.height * supersampling;
+    let x_query_resolution = query_rect.bbox.size_x() / f64::from(width);
+    let y_query_resolution = query_rect.bbox.size_y() / f64::from(height);
 
-    // build png
-    let dim = [request.height as usize, request.width as usize];
-    let data: Vec<T> = vec![T::zero(); dim[0] * dim[1]];
+    // build png, pixels without tiles or with no-data in all tiles stay no-data
+    let dim = [height as usize, width as usize];
+    let no_data_value: Option<T> = result_descriptor.no_data_value.map(T::from_);
+    let data: Vec<T> = vec![no_data_value.unwrap_or_else(T::zero); dim[0] * dim[1]];
     let query_geo_transform = GeoTransform::new(
         query_rect.bbox.upper_left(),
         x_query_resolution,
@@ -215,33 +464,438 @@ where
     let output_raster: Result<Raster2D<T>> = Raster2D::new(
         dim.into(),
         data,
-        None,
+        no_data_value,
         request.time.unwrap_or_default(),
         query_geo_transform,
     )
     .context(error::DataType);
 
-    let output_raster = tile_stream
-        .fold(output_raster, |raster2d, tile| {
-            let result: Result<Raster2D<T>> = match (raster2d, tile) {
-                (Ok(mut raster2d), Ok(tile)) => match raster2d.blit(tile.data) {
-                    Ok(_) => Ok(raster2d),
-                    Err(error) => Err(error.into()),
-                },
+    let output = output_raster.map(|raster2d| (raster2d, TileStatistics::empty()));
+
+    let (output_raster, statistics) = tile_stream
+        .fold(output, |output, tile| {
+            let result: Result<(Raster2D<T>, TileStatistics)> = match (output, tile) {
+                (Ok((mut raster2d, statistics)), Ok(tile)) => {
+                    // uses the statistics of the tile if they are already attached
+                    let statistics =
+                        if style == RasterStyle::AutoStretch && dataset_statistics.is_none() {
+                            statistics.merge(&tile.statistics())
+                        } else {
+                            statistics
+                        };
+
+                    match raster2d.blit(tile.data) {
+                        Ok(_) => Ok((raster2d, statistics)),
+                        Err(error) => Err(error.into()),
+                    }
+                }
                 (Err(error), _) => Err(error),
                 (_, Err(error)) => Err(error.into()),
             };
 
             match result {
-                Ok(updated_raster2d) => futures::future::ok(updated_raster2d),
+                Ok(updated_output) => futures::future::ok(updated_output),
                 Err(error) => futures::future::err(error),
             }
         })
         .await?;
 
-    let colorizer = Colorizer::rgba(); // TODO: create colorizer from request
+    let colorizer = match style {
+        RasterStyle::Rgba => Colorizer::rgba(),
+        RasterStyle::AutoStretch => match dataset_statistics {
+            Some(dataset_statistics) => {
+                auto_stretch_colorizer(&stretch_statistics(dataset_statistics))?
+            }
+            None => auto_stretch_colorizer(&statistics)?,
+        },
+        RasterStyle::GlobalStretch => auto_stretch_colorizer(
+            &global_statistics(
+                processor.as_ref(),
+                query_rect,
+                query_ctx,
+                request,
+                workflow_id,
+                result_descriptor,
+            )
+            .await?,
+        )?,
+    };
+
+    Ok(output_raster.to_png_supersampled(
+        request.width,
+        request.height,
+        &colorizer,
+        supersampling,
+    )?)
+}
+
+async fn vector_stream_to_canvas<C>(
+    processor: Box<dyn VectorQueryProcessor<VectorType = C>>,
+    query_rect: QueryRectangle,
+    query_ctx: QueryContext,
+    mut canvas: Canvas,
+    style: &VectorStyle,
+) -> Result<Canvas>
+where
+    C: DrawOnCanvas,
+{
+    let mut collection_stream = processor.vector_query(query_rect, query_ctx);
+
+    while let Some(collection) = collection_stream.next().await {
+        collection?.draw_on_canvas(&mut canvas, style);
+    }
+
+    Ok(canvas)
+}
 
-    Ok(output_raster.to_png(request.width, request.height, &colorizer)?)
+/// The styling of raster layers
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RasterStyle {
+    /// Interpret the pixel values as RGBA colors
+    Rgba,
+    /// Stretch a gray scale gradient between the minimum and maximum of the queried data,
+    /// i.e., per requested time step.
+    /// Datasets with precomputed statistics are stretched between their 2% and 98% quantiles
+    /// instead, so that tiles of the same dataset match.
+    AutoStretch,
+    /// Stretch a gray scale gradient between the minimum and maximum of all time steps of the
+    /// layer, so that the frames of an animation are comparable
+    GlobalStretch,
+}
+
+/// The statistics of a layer over its whole extent and all time steps, used for
+/// `stretch:global`.
+///
+/// They are computed at the pixel size of the first request and cached per workflow.
+/// If the bounds of the layer are unknown, the bounds of the first request are used instead.
+async fn global_statistics<T>(
+    processor: &dyn RasterQueryProcessor<RasterType = T>,
+    query_rect: QueryRectangle,
+    query_ctx: QueryContext,
+    request: &GetMap,
+    workflow_id: &WorkflowId,
+    result_descriptor: &RasterResultDescriptor,
+) -> Result<TileStatistics>
+where
+    T: Pixel,
+{
+    let cached = GLOBAL_STATISTICS
+        .lock()
+        .map_err(|_| error::Error::GlobalStatisticsLockFailed)?
+        .get(workflow_id)
+        .copied();
+    if let Some(statistics) = cached {
+        return Ok(statistics);
+    }
+
+    let bbox = result_descriptor.bbox.unwrap_or(query_rect.bbox);
+    let query = QueryRectangle {
+        bbox,
+        time_interval: result_descriptor
+            .time_interval
+            .unwrap_or(query_rect.time_interval),
+        spatial_resolution: SpatialResolution::new_unchecked(
+            bbox.size_x() / f64::from(request.width),
+            bbox.size_y() / f64::from(request.height),
+        ),
+    };
+
+    let statistics = processor
+        .raster_query(query, query_ctx)
+        .fold(Ok(TileStatistics::empty()), |statistics, tile| async move {
+            Ok::<_, error::Error>(statistics?.merge(&tile?.statistics()))
+        })
+        .await?;
+
+    GLOBAL_STATISTICS
+        .lock()
+        .map_err(|_| error::Error::GlobalStatisticsLockFailed)?
+        .insert(*workflow_id, statistics);
+
+    Ok(statistics)
+}
+
+/// Parse a raster style of the form `stretch:auto` or `stretch:global`, optionally with the
+/// name of the band to render, e.g., `band:nir;stretch:auto`.
+/// Named styles (e.g. `default`) result in the default style.
+fn parse_raster_style(styles: &str) -> Result<RasterStyle> {
+    let mut style = RasterStyle::Rgba;
+
+    if !styles.contains(':') {
+        return Ok(style);
+    }
+
+    for property in styles.split(';').filter(|property| !property.is_empty()) {
+        let mut key_value = property.splitn(2, ':');
+        let key = key_value.next().unwrap_or_default().trim();
+        let value = key_value.next().unwrap_or_default().trim();
+
+        match (key, value) {
+            ("stretch", "auto") => style = RasterStyle::AutoStretch,
+            ("stretch", "global") => style = RasterStyle::GlobalStretch,
+            ("stretch", "none") => style = RasterStyle::Rgba,
+            ("stretch", _) => {
+                return Err(error::Error::InvalidRasterStyle {
+                    details: format!("unknown stretch `{}`", value),
+                })
+            }
+            ("band", "") => {
+                return Err(error::Error::InvalidRasterStyle {
+                    details: "missing band name".to_string(),
+                })
+            }
+            // see `parse_supersampling` and `parse_band`
+            ("supersampling", _) | ("band", _) => {}
+            _ => {
+                return Err(error::Error::InvalidRasterStyle {
+                    details: format!("unknown property `{}`", key),
+                })
+            }
+        }
+    }
+
+    Ok(style)
+}
+
+/// The range of `stretch:auto` for a dataset with precomputed statistics
+fn stretch_statistics(dataset_statistics: &RasterDatasetStatistics) -> TileStatistics {
+    let (lower, upper) = AUTO_STRETCH_QUANTILES;
+
+    TileStatistics {
+        min: dataset_statistics.quantile(lower),
+        max: dataset_statistics.quantile(upper),
+        mean: dataset_statistics.mean,
+        valid_count: dataset_statistics.valid_count,
+    }
+}
+
+/// A black to white gradient between the minimum and maximum of the valid pixels.
+/// No-data and uncovered pixels outside of this range become transparent.
+fn auto_stretch_colorizer(statistics: &TileStatistics) -> Result<Colorizer> {
+    let (min, max) = if statistics.valid_count == 0 {
+        (0., 1.)
+    } else if statistics.min < statistics.max {
+        (statistics.min, statistics.max)
+    } else {
+        (statistics.min, statistics.min + 1.)
+    };
+
+    Colorizer::linear_gradient(
+        vec![
+            (min.into(), RgbaColor::black()).into(),
+            (max.into(), RgbaColor::white()).into(),
+        ],
+        RgbaColor::transparent(),
+        RgbaColor::transparent(),
+    )
+    .context(error::DataType)
+}
+
+/// Parse a vector style of the form `fill:#rrggbbaa;stroke:#rrggbb;stroke_width:1;point_radius:3`.
+/// Omitted properties keep their default value and named styles (e.g. `default`) result in the
+/// default style.
+fn parse_vector_style(styles: &str) -> Result<VectorStyle> {
+    let mut style = VectorStyle::default();
+
+    if !styles.contains(':') {
+        return Ok(style);
+    }
+
+    for property in styles.split(';').filter(|property| !property.is_empty()) {
+        let mut key_value = property.splitn(2, ':');
+        let key = key_value.next().unwrap_or_default().trim();
+        let value = key_value.next().unwrap_or_default().trim();
+
+        match key {
+            "fill" => style.fill_color = parse_color(value)?,
+            "stroke" => style.stroke_color = parse_color(value)?,
+            "stroke_width" => style.stroke_width = parse_pixels(key, value)?,
+            "point_radius" => style.point_radius = parse_pixels(key, value)?,
+            // see `parse_supersampling`
+            "supersampling" => {}
+            _ => {
+                return Err(error::Error::InvalidVectorStyle {
+                    details: format!("unknown property `{}`", key),
+                })
+            }
+        }
+    }
+
+    Ok(style)
+}
+
+/// Parse the `supersampling:n` property of raster and vector styles, which renders the image
+/// at `n` times its size and downscales it to smooth edges. Without the property, the image
+/// is rendered with the configured default factor, reduced by the `dpi_scale` because the pixels
+/// of high-DPI clients are already small.
+fn parse_supersampling(styles: &str, dpi_scale: f64, config: &config::Wms) -> Result<u32> {
+    let value = styles.split(';').find_map(|property| {
+        let mut key_value = property.splitn(2, ':');
+        if key_value.next().unwrap_or_default().trim() == "supersampling" {
+            Some(key_value.next().unwrap_or_default().trim())
+        } else {
+            None
+        }
+    });
+
+    match value.map(str::parse::<u32>) {
+        None => Ok((f64::from(config.supersampling) / dpi_scale).ceil().max(1.) as u32),
+        Some(Ok(factor)) if factor >= 1 && factor <= config.max_supersampling => Ok(factor),
+        Some(_) => Err(error::Error::InvalidSupersampling {
+            max: config.max_supersampling,
+        }),
+    }
+}
+
+/// Parse the `band:name` property of raster styles, which renders the band with the name
+/// instead of the channel of the workflow's sources
+fn parse_band(styles: &str) -> Option<&str> {
+    styles.split(';').find_map(|property| {
+        let mut key_value = property.splitn(2, ':');
+        if key_value.next().unwrap_or_default().trim() == "band" {
+            Some(key_value.next().unwrap_or_default().trim())
+        } else {
+            None
+        }
+    })
+}
+
+/// The resolution of the OGC standard pixel size of 0.28 mm in dots per inch
+const STANDARD_DPI: f64 = 25.4 / 0.28;
+
+/// Parse the `map_resolution` vendor parameter into the factor by which the client's pixels are
+/// smaller than standard pixels, or 1 if it is missing
+fn parse_dpi_scale(request: &GetMap) -> Result<f64> {
+    match request.map_resolution {
+        None => Ok(1.),
+        Some(dpi) if dpi.is_finite() && dpi > 0. => Ok(dpi / STANDARD_DPI),
+        Some(_) => Err(error::Error::InvalidMapResolution),
+    }
+}
+
+/// Parse the `decorations` vendor parameter into the overlays of the map.
+/// Requested texts that are not configured are omitted.
+fn parse_decorations(
+    request: &GetMap,
+    bbox: BoundingBox2D,
+    config: &config::Wms,
+) -> Result<Option<MapDecorations>> {
+    let names = match &request.decorations {
+        Some(names) => names,
+        None => return Ok(None),
+    };
+
+    let configured_text = |text: &str| {
+        if text.is_empty() {
+            None
+        } else {
+            Some(text.to_string())
+        }
+    };
+
+    let mut decorations = MapDecorations {
+        // the bitmap font only scales by whole factors
+        scale: parse_dpi_scale(request)?.round().max(1.).min(8.) as u32,
+        ..Default::default()
+    };
+    for name in names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        match name {
+            "attribution" => decorations.attribution = configured_text(&config.attribution),
+            "watermark" => decorations.watermark = configured_text(&config.watermark),
+            "scalebar" => {
+                // TODO: use the unit of the CRS once other CRS than EPSG:4326 are supported
+                decorations.scalebar_meters_per_unit = Some(if request.crs == "EPSG:4326" {
+                    const METERS_PER_DEGREE: f64 = 111_319.49;
+                    let center_latitude = (bbox.lower_left().y + bbox.upper_right().y) / 2.;
+                    METERS_PER_DEGREE * center_latitude.to_radians().cos()
+                } else {
+                    1.
+                });
+            }
+            _ => {
+                return Err(error::Error::InvalidMapDecoration {
+                    name: name.to_string(),
+                })
+            }
+        }
+    }
+
+    Ok(Some(decorations))
+}
+
+/// Parse the `transparent` and `bgcolor` parameters into the opaque color that fills no-data and
+/// uncovered pixels. The map stays transparent for `transparent=true` and if neither is given.
+fn parse_background(request: &GetMap) -> Result<Option<RgbaColor>> {
+    if request.transparent == Some(true) {
+        return Ok(None);
+    }
+
+    let value = match &request.bgcolor {
+        Some(value) => value,
+        None if request.transparent == Some(false) => return Ok(Some(RgbaColor::white())),
+        None => return Ok(None),
+    };
+
+    let invalid_color = || error::Error::InvalidBackgroundColor {
+        value: value.clone(),
+    };
+
+    let hex = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .ok_or_else(invalid_color)?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid_color());
+    }
+
+    let mut channels = [0_u8; 3];
+    for (channel, i) in channels.iter_mut().zip((0..hex.len()).step_by(2)) {
+        *channel = u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid_color())?;
+    }
+
+    Ok(Some(RgbaColor::new(
+        channels[0],
+        channels[1],
+        channels[2],
+        255,
+    )))
+}
+
+/// Parse a color of the form `#rrggbb` or `#rrggbbaa`
+fn parse_color(value: &str) -> Result<RgbaColor> {
+    let invalid_color = || error::Error::InvalidVectorStyle {
+        details: format!("invalid color `{}`", value),
+    };
+
+    let hex = value.strip_prefix('#').ok_or_else(invalid_color)?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(invalid_color());
+    }
+
+    let mut channels = [255_u8; 4];
+    for (channel, i) in channels.iter_mut().zip((0..hex.len()).step_by(2)) {
+        *channel = u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid_color())?;
+    }
+
+    Ok(RgbaColor::new(
+        channels[0],
+        channels[1],
+        channels[2],
+        channels[3],
+    ))
+}
+
+fn parse_pixels(key: &str, value: &str) -> Result<f64> {
+    match value.parse::<f64>() {
+        Ok(pixels) if pixels >= 0. => Ok(pixels),
+        _ => Err(error::Error::InvalidVectorStyle {
+            details: format!("`{}` must be a non-negative number", key),
+        }),
+    }
 }
 
 fn get_legend_graphic<T: WorkflowRegistry>(
@@ -286,15 +940,24 @@ fn get_map_mock(request: &GetMap) -> Result<Box<dyn warp::Reply>, warp::Rejectio
 mod tests {
     use std::path::PathBuf;
 
-    use geoengine_datatypes::primitives::{BoundingBox2D, TimeInterval};
-    use geoengine_operators::engine::{RasterOperator, TypedOperator};
+    use geoengine_datatypes::primitives::{BoundingBox2D, Coordinate2D, TimeInterval};
+    use geoengine_datatypes::raster::RasterDataType;
+    use geoengine_datatypes::spatial_reference::SpatialReference;
+    use geoengine_operators::engine::{RasterOperator, TypedOperator, VectorOperator};
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
     use geoengine_operators::source::{
         gdal_source::GdalSourceProcessor, GdalSource, GdalSourceParameters,
     };
 
+    use crate::handlers::handle_rejection;
+    use crate::users::hashmap_userdb::HashMapUserDB;
+    use crate::users::session::SessionToken;
+    use crate::users::user::{UserCredentials, UserRegistration};
+    use crate::util::user_input::UserInput;
     use crate::workflows::registry::HashMapRegistry;
 
     use super::*;
+    use crate::datasets::load_dataset_definitions;
     use crate::ogc::wms::request::GetMapFormat;
     use crate::workflows::workflow::Workflow;
     use xml::ParserConfig;
@@ -306,7 +969,11 @@ mod tests {
         let res = warp::test::request()
             .method("GET")
             .path("/wms?request=GetMap&service=WMS&version=1.3.0&layers=mock_raster&bbox=1,2,3,4&width=100&height=100&crs=foo&styles=ssss&format=image/png")
-            .reply(&wms_handler(workflow_registry))
+            .reply(&wms_handler(
+                workflow_registry,
+                load_dataset_definitions().unwrap(),
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
             .await;
         assert_eq!(res.status(), 200);
         assert_eq!(
@@ -322,7 +989,11 @@ mod tests {
         let res = warp::test::request()
             .method("GET")
             .path("/wms?request=GetCapabilities&service=WMS")
-            .reply(&wms_handler(workflow_registry))
+            .reply(&wms_handler(
+                workflow_registry,
+                load_dataset_definitions().unwrap(),
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
             .await;
         assert_eq!(res.status(), 200);
 
@@ -339,6 +1010,7 @@ mod tests {
         let gdal_params = GdalSourceParameters {
             dataset_id: "test".to_owned(),
             channel: None,
+            band: None,
         };
 
         let gdal_source = GdalSourceProcessor::<_, u8>::from_params_with_json_provider(
@@ -356,7 +1028,11 @@ mod tests {
                 time_interval: TimeInterval::default(),
                 spatial_resolution: SpatialResolution::zero_point_one(),
             },
-            QueryContext { chunk_byte_size: 0 },
+            QueryContext {
+                chunk_byte_size: 0,
+                timeout: None,
+                seed: 0,
+            },
             &GetMap {
                 version: "".to_string(),
                 width: 600,
@@ -373,7 +1049,20 @@ mod tests {
                 sld_body: None,
                 elevation: None,
                 exceptions: None,
+                decorations: None,
+                map_resolution: None,
+            },
+            1,
+            &WorkflowId::new(),
+            &RasterResultDescriptor {
+                data_type: RasterDataType::U8,
+                spatial_reference: SpatialReference::wgs84().into(),
+                bbox: None,
+                time_interval: None,
+                no_data_value: None,
+                bands: vec![],
             },
+            None,
         )
         .await
         .unwrap();
@@ -389,6 +1078,7 @@ mod tests {
         let gdal_params = GdalSourceParameters {
             dataset_id: "test".to_owned(),
             channel: None,
+            band: None,
         };
 
         let gdal_source = GdalSourceProcessor::<_, u8>::from_params_with_json_provider(
@@ -406,7 +1096,11 @@ mod tests {
                 time_interval: TimeInterval::default(),
                 spatial_resolution: SpatialResolution::new_unchecked(1.0, 1.0),
             },
-            QueryContext { chunk_byte_size: 0 },
+            QueryContext {
+                chunk_byte_size: 0,
+                timeout: None,
+                seed: 0,
+            },
             &GetMap {
                 version: "".to_string(),
                 width: 360,
@@ -423,7 +1117,20 @@ mod tests {
                 sld_body: None,
                 elevation: None,
                 exceptions: None,
+                decorations: None,
+                map_resolution: None,
             },
+            1,
+            &WorkflowId::new(),
+            &RasterResultDescriptor {
+                data_type: RasterDataType::U8,
+                spatial_reference: SpatialReference::wgs84().into(),
+                bbox: None,
+                time_interval: None,
+                no_data_value: None,
+                bands: vec![],
+            },
+            None,
         )
         .await
         .unwrap();
@@ -434,6 +1141,75 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn png_from_supersampled_stream() {
+        let gdal_params = GdalSourceParameters {
+            dataset_id: "test".to_owned(),
+            channel: None,
+            band: None,
+        };
+
+        let gdal_source = GdalSourceProcessor::<_, u8>::from_params_with_json_provider(
+            gdal_params,
+            PathBuf::from("../operators/test-data/raster").as_ref(),
+        )
+        .unwrap();
+
+        let query_bbox = BoundingBox2D::new((-180., -90.).into(), (180., 90.).into()).unwrap();
+
+        // the raster is queried at twice the resolution of the image
+        let image_bytes = raster_stream_to_png_bytes(
+            gdal_source.boxed(),
+            QueryRectangle {
+                bbox: query_bbox,
+                time_interval: TimeInterval::default(),
+                spatial_resolution: SpatialResolution::new_unchecked(0.5, 0.5),
+            },
+            QueryContext {
+                chunk_byte_size: 0,
+                timeout: None,
+                seed: 0,
+            },
+            &GetMap {
+                version: "".to_string(),
+                width: 360,
+                height: 180,
+                bbox: query_bbox,
+                format: GetMapFormat::ImagePng,
+                layers: "".to_string(),
+                crs: "".to_string(),
+                styles: "".to_string(),
+                time: None,
+                transparent: None,
+                bgcolor: None,
+                sld: None,
+                sld_body: None,
+                elevation: None,
+                exceptions: None,
+                decorations: None,
+                map_resolution: None,
+            },
+            2,
+            &WorkflowId::new(),
+            &RasterResultDescriptor {
+                data_type: RasterDataType::U8,
+                spatial_reference: SpatialReference::wgs84().into(),
+                bbox: None,
+                time_interval: None,
+                no_data_value: None,
+                bands: vec![],
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        let image = image::load_from_memory_with_format(&image_bytes, image::ImageFormat::Png)
+            .unwrap()
+            .to_rgba();
+        assert_eq!(image.dimensions(), (360, 180));
+    }
+
     #[tokio::test]
     async fn get_map() {
         let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
@@ -444,6 +1220,7 @@ mod tests {
                     params: GdalSourceParameters {
                         dataset_id: "test".to_owned(),
                         channel: None,
+                        band: None,
                     },
                 }
                 .boxed(),
@@ -458,8 +1235,12 @@ mod tests {
 
         let res = warp::test::request()
             .method("GET")
-            .path(&format!("/wms?request=GetMap&service=WMS&version=1.3.0&layers={}&bbox=20,-10,80,50&width=600&height=600&crs=foo&styles=ssss&format=image/png", id.to_string()))
-            .reply(&wms_handler(workflow_registry))
+            .path(&format!("/wms?request=GetMap&service=WMS&version=1.3.0&layers={}&bbox=20,-10,80,50&width=600&height=600&crs=EPSG:4326&styles=ssss&format=image/png", id.to_string()))
+            .reply(&wms_handler(
+                workflow_registry,
+                load_dataset_definitions().unwrap(),
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
             .await;
         assert_eq!(res.status(), 200);
         assert_eq!(
@@ -468,6 +1249,48 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn get_map_lon_lat() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+
+        let workflow = Workflow {
+            operator: TypedOperator::Raster(
+                GdalSource {
+                    params: GdalSourceParameters {
+                        dataset_id: "test".to_owned(),
+                        channel: None,
+                        band: None,
+                    },
+                }
+                .boxed(),
+            ),
+        };
+
+        let id = workflow_registry
+            .write()
+            .await
+            .register(workflow.clone())
+            .unwrap();
+
+        // WMS 1.1.1 and CRS:84 use lon/lat, so both requests show the same area as `get_map`
+        for (version, crs) in &[("1.1.1", "EPSG:4326"), ("1.3.0", "CRS:84")] {
+            let res = warp::test::request()
+                .method("GET")
+                .path(&format!("/wms?request=GetMap&service=WMS&version={}&layers={}&bbox=-10,20,50,80&width=600&height=600&crs={}&styles=ssss&format=image/png", version, id.to_string(), crs))
+                .reply(&wms_handler(
+                    workflow_registry.clone(),
+                    load_dataset_definitions().unwrap(),
+                    Arc::new(RwLock::new(HashMapUserDB::default())),
+                ))
+                .await;
+            assert_eq!(res.status(), 200);
+            assert_eq!(
+                include_bytes!("../../../services/test-data/wms/raster.png") as &[u8],
+                res.body().to_vec().as_slice()
+            );
+        }
+    }
+
     #[tokio::test]
     async fn get_map_uppercase() {
         let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
@@ -478,6 +1301,7 @@ mod tests {
                     params: GdalSourceParameters {
                         dataset_id: "test".to_owned(),
                         channel: None,
+                        band: None,
                     },
                 }
                 .boxed(),
@@ -492,8 +1316,12 @@ mod tests {
 
         let res = warp::test::request()
             .method("GET")
-            .path(&format!("/wms?SERVICE=WMS&VERSION=1.3.0&REQUEST=GetMap&FORMAT=image%2Fpng&TRANSPARENT=true&LAYERS={}&CRS=EPSG%3A3857&STYLES=&WIDTH=600&HEIGHT=600&BBOX=20,-10,80,50", id.to_string()))
-            .reply(&wms_handler(workflow_registry))
+            .path(&format!("/wms?SERVICE=WMS&VERSION=1.3.0&REQUEST=GetMap&FORMAT=image%2Fpng&TRANSPARENT=true&LAYERS={}&CRS=EPSG%3A4326&STYLES=&WIDTH=600&HEIGHT=600&BBOX=20,-10,80,50", id.to_string()))
+            .reply(&wms_handler(
+                workflow_registry,
+                load_dataset_definitions().unwrap(),
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
             .await;
 
         assert_eq!(res.status(), 200);
@@ -502,4 +1330,286 @@ mod tests {
             res.body().to_vec().as_slice()
         );
     }
+
+    #[tokio::test]
+    async fn get_map_vector() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+
+        let workflow = Workflow {
+            operator: TypedOperator::Vector(
+                MockPointSource {
+                    params: MockPointSourceParams {
+                        points: vec![Coordinate2D::new(5., 5.)],
+                    },
+                }
+                .boxed(),
+            ),
+        };
+
+        let id = workflow_registry
+            .write()
+            .await
+            .register(workflow.clone())
+            .unwrap();
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&format!("/wms?request=GetMap&service=WMS&version=1.3.0&layers={}&bbox=0,0,10,10&width=100&height=100&crs=foo&styles=fill:%23ff0000;point_radius:5&format=image/png", id.to_string()))
+            .reply(&wms_handler(
+                workflow_registry,
+                load_dataset_definitions().unwrap(),
+                Arc::new(RwLock::new(HashMapUserDB::default())),
+            ))
+            .await;
+
+        assert_eq!(res.status(), 200);
+
+        let image = image::load_from_memory_with_format(res.body(), image::ImageFormat::Png)
+            .unwrap()
+            .to_rgba();
+
+        assert_eq!(image.get_pixel(50, 50).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn get_map_private_workflow() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+
+        let user = user_db
+            .write()
+            .await
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".to_string(),
+                    password: "secret123".to_string(),
+                    real_name: "Foo Bar".to_string(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+        let session = user_db
+            .write()
+            .await
+            .login(UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap();
+
+        let workflow = Workflow {
+            operator: TypedOperator::Vector(
+                MockPointSource {
+                    params: MockPointSourceParams {
+                        points: vec![Coordinate2D::new(5., 5.)],
+                    },
+                }
+                .boxed(),
+            ),
+        };
+
+        let id = workflow_registry
+            .write()
+            .await
+            .register_private(workflow, user)
+            .unwrap();
+
+        let path = format!("/wms?request=GetMap&service=WMS&version=1.3.0&layers={}&bbox=0,0,10,10&width=100&height=100&crs=foo&styles=fill:%23ff0000;point_radius:5&format=image/png", id.to_string());
+        let handler = wms_handler(
+            workflow_registry,
+            load_dataset_definitions().unwrap(),
+            user_db,
+        )
+        .recover(handle_rejection);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&path)
+            .reply(&handler)
+            .await;
+        assert_eq!(res.status(), 403);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&format!("{}&token={}", path, session.token))
+            .reply(&handler)
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&format!("{}&token={}", path, SessionToken::default()))
+            .reply(&handler)
+            .await;
+        assert_eq!(res.status(), 400);
+    }
+
+    #[test]
+    fn vector_style() {
+        assert_eq!(parse_vector_style("ssss").unwrap(), VectorStyle::default());
+        assert_eq!(
+            parse_vector_style("fill:#00ff0080;stroke:#0000ff;stroke_width:2").unwrap(),
+            VectorStyle {
+                fill_color: RgbaColor::new(0, 255, 0, 128),
+                stroke_color: RgbaColor::new(0, 0, 255, 255),
+                stroke_width: 2.,
+                ..VectorStyle::default()
+            }
+        );
+        assert!(parse_vector_style("fill:red").is_err());
+        assert!(parse_vector_style("stroke_width:-1").is_err());
+        assert!(parse_vector_style("color:#000000").is_err());
+    }
+
+    #[test]
+    fn supersampling() {
+        let config = config::Wms {
+            supersampling: 1,
+            max_supersampling: 4,
+            max_image_size: 4096,
+            attribution: String::new(),
+            watermark: String::new(),
+        };
+
+        assert_eq!(parse_supersampling("default", 1., &config).unwrap(), 1);
+        assert_eq!(
+            parse_supersampling("stretch:auto;supersampling:2", 1., &config).unwrap(),
+            2
+        );
+        assert_eq!(
+            parse_raster_style("stretch:auto;supersampling:2").unwrap(),
+            RasterStyle::AutoStretch
+        );
+        assert_eq!(
+            parse_vector_style("supersampling:4").unwrap(),
+            VectorStyle::default()
+        );
+        assert!(parse_supersampling("supersampling:0", 1., &config).is_err());
+        assert!(parse_supersampling("supersampling:8", 1., &config).is_err());
+        assert!(parse_supersampling("supersampling:two", 1., &config).is_err());
+
+        let config = config::Wms {
+            supersampling: 4,
+            ..config
+        };
+        // high-DPI clients need less smoothing, unless they ask for it
+        assert_eq!(parse_supersampling("default", 2., &config).unwrap(), 2);
+        assert_eq!(parse_supersampling("default", 8., &config).unwrap(), 1);
+        assert_eq!(
+            parse_supersampling("supersampling:4", 2., &config).unwrap(),
+            4
+        );
+    }
+
+    #[test]
+    fn dpi_scale() {
+        let request = |parameters: &str| -> GetMap {
+            serde_urlencoded::from_str(&format!(
+                "version=1.3.0&layers=test&bbox=-10,0,10,20&width=2&height=2&crs=EPSG:4326&styles=&format=image/png{}",
+                parameters
+            ))
+            .unwrap()
+        };
+
+        assert!((parse_dpi_scale(&request("")).unwrap() - 1.).abs() < f64::EPSILON);
+        assert!((parse_dpi_scale(&request("&map_resolution=181.43")).unwrap() - 2.).abs() < 1e-3);
+        assert!(parse_dpi_scale(&request("&dpi=0")).is_err());
+
+        let decorations = parse_decorations(
+            &request("&decorations=scalebar&DPI=272"),
+            BoundingBox2D::new((0., -10.).into(), (20., 10.).into()).unwrap(),
+            &config::get_config_element().unwrap(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(decorations.scale, 3);
+    }
+
+    #[test]
+    fn decorations() {
+        let config = config::Wms {
+            supersampling: 1,
+            max_supersampling: 4,
+            max_image_size: 4096,
+            attribution: "(c) data providers".to_string(),
+            watermark: String::new(),
+        };
+        let bbox = BoundingBox2D::new((0., -10.).into(), (20., 10.).into()).unwrap();
+        let request = |decorations: &str| -> GetMap {
+            serde_urlencoded::from_str(&format!(
+                "version=1.3.0&layers=test&bbox=-10,0,10,20&width=2&height=2&crs=EPSG:4326&styles=&format=image/png{}",
+                decorations
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(
+            parse_decorations(&request(""), bbox, &config).unwrap(),
+            None
+        );
+
+        let decorations = parse_decorations(
+            &request("&decorations=attribution,scalebar,watermark"),
+            bbox,
+            &config,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            decorations.attribution,
+            Some("(c) data providers".to_string())
+        );
+        assert_eq!(decorations.watermark, None);
+        assert!((decorations.scalebar_meters_per_unit.unwrap() - 111_319.49).abs() < 1e-6);
+
+        assert!(parse_decorations(&request("&decorations=logo"), bbox, &config).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn raster_style() {
+        assert_eq!(parse_raster_style("ssss").unwrap(), RasterStyle::Rgba);
+        assert_eq!(
+            parse_raster_style("stretch:auto").unwrap(),
+            RasterStyle::AutoStretch
+        );
+        assert_eq!(
+            parse_raster_style("stretch:global").unwrap(),
+            RasterStyle::GlobalStretch
+        );
+        assert!(parse_raster_style("stretch:sometimes").is_err());
+        assert!(parse_raster_style("fill:#000000").is_err());
+        assert_eq!(
+            parse_raster_style("band:nir;stretch:auto").unwrap(),
+            RasterStyle::AutoStretch
+        );
+        assert!(parse_raster_style("band:").is_err());
+        assert_eq!(parse_band("band:nir;stretch:auto"), Some("nir"));
+        assert_eq!(parse_band("stretch:auto"), None);
+
+        let colorizer =
+            auto_stretch_colorizer(&TileStatistics::from_pixels(&[3_u8, 7, 5], None)).unwrap();
+        assert_eq!(colorizer.min_value(), 3.);
+        assert_eq!(colorizer.max_value(), 7.);
+
+        let colorizer = auto_stretch_colorizer(&TileStatistics::constant(4_u8, 3)).unwrap();
+        assert_eq!(colorizer.min_value(), 4.);
+        assert_eq!(colorizer.max_value(), 5.);
+
+        assert!(auto_stretch_colorizer(&TileStatistics::empty()).is_ok());
+
+        // precomputed statistics cut off the outliers
+        let colorizer = auto_stretch_colorizer(&stretch_statistics(&RasterDatasetStatistics {
+            min: 0.,
+            max: 100.,
+            mean: 50.,
+            valid_count: 100,
+            histogram: vec![1, 98, 1],
+        }))
+        .unwrap();
+        assert!(colorizer.min_value() > 33. && colorizer.min_value() < 34.);
+        assert!(colorizer.max_value() > 66. && colorizer.max_value() < 67.);
+    }
 }
@@ -3,60 +3,288 @@ use uuid::Uuid;
 use warp::reply::Reply;
 use warp::Filter;
 
-use crate::handlers::DB;
+use crate::datasets::{dataset_statistics, pinned_datasets, SharedDatasetDefinitions};
+use crate::error::Error;
+use crate::handlers::{authenticate, optional_session, query_client, Requester, DB};
+use crate::users::session::Session;
+use crate::users::userdb::UserDB;
+use crate::util::audit::{audit_log, AuditEvent, AuditEventKind};
+use crate::util::config;
 use crate::util::identifiers::Identifier;
+use crate::workflows::description::describe_workflow;
 use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::resolver::WorkflowSnapshot;
+use crate::workflows::share_link::ShareLink;
 use crate::workflows::workflow::{Workflow, WorkflowId};
+use chrono::Utc;
+use geoengine_operators::engine::{ExecutionContext, OperatorRegistry, WorkflowProblem};
+use geoengine_operators::source::referenced_workflows_of_json;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use warp::hyper::body::Bytes;
 
-// TODO: require authorized access
-pub fn register_workflow_handler<T: WorkflowRegistry>(
+/// Register a workflow, which is private to the user if the request has a session and public otherwise
+pub fn register_workflow_handler<T: WorkflowRegistry, U: UserDB>(
     workflow_registry: DB<T>,
+    user_db: DB<U>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::post()
         .and(warp::path!("workflow" / "register"))
         .and(warp::body::json())
+        .and(query_client())
+        .and(optional_session(user_db))
         .and(warp::any().map(move || Arc::clone(&workflow_registry)))
         .and_then(register_workflow)
 }
 
-pub fn load_workflow_handler<T: WorkflowRegistry>(
+pub fn load_workflow_handler<T: WorkflowRegistry, U: UserDB>(
     workflow_registry: DB<T>,
+    user_db: DB<U>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::get()
         .and(warp::path!("workflow" / Uuid))
+        .and(optional_session(user_db))
         .and(warp::any().map(move || Arc::clone(&workflow_registry)))
         .and_then(load_workflow)
 }
 
+/// Describe a workflow as a Markdown list of its operators and their parameters, e.g., for audits
+/// or sharing
+pub fn workflow_description_handler<T: WorkflowRegistry, U: UserDB>(
+    workflow_registry: DB<T>,
+    user_db: DB<U>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("workflow" / Uuid / "description"))
+        .and(optional_session(user_db))
+        .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and(warp::any().map(move || Arc::clone(&dataset_definitions)))
+        .and_then(workflow_description)
+}
+
+/// Check a workflow without registering it, e.g., for highlighting faulty operator parameters in
+/// an editor. The response lists all problems with JSON pointers into the submitted document.
+pub fn validate_workflow_handler<T: WorkflowRegistry, U: UserDB>(
+    workflow_registry: DB<T>,
+    user_db: DB<U>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("workflow" / "validate"))
+        .and(warp::body::bytes())
+        .and(optional_session(user_db))
+        .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and(warp::any().map(move || Arc::clone(&dataset_definitions)))
+        .and_then(validate_workflow)
+}
+
+/// The outcome of validating a workflow
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkflowValidation {
+    pub valid: bool,
+    pub problems: Vec<WorkflowProblem>,
+}
+
+/// The style and validity of a new share link of a workflow
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateShareLink {
+    #[serde(default)]
+    pub style: String,
+    pub valid_seconds: u64,
+}
+
+/// Create a signed link that grants WMS and WFS access to a workflow without a login, e.g., for
+/// embedding maps into external websites
+pub fn share_workflow_handler<T: WorkflowRegistry, U: UserDB>(
+    workflow_registry: DB<T>,
+    user_db: DB<U>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("workflow" / Uuid / "share"))
+        .and(authenticate(user_db))
+        .and(warp::body::json())
+        .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and_then(share_workflow)
+}
+
+/// The usage statistics of all registered workflows with the `admin_token` of the
+/// `[workflow_statistics]` configuration as `Authorization`
+pub fn workflow_statistics_handler<T: WorkflowRegistry>(
+    workflow_registry: DB<T>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("workflows" / "statistics"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::any().map(move || Arc::clone(&workflow_registry)))
+        .and_then(workflow_statistics)
+}
+
 // TODO: move into handler once async closures are available?
 async fn register_workflow<T: WorkflowRegistry>(
     workflow: Workflow,
+    client: String,
+    session: Option<Session>,
     workflow_registry: DB<T>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut wr = workflow_registry.write().await;
-    let id = wr.register(workflow)?;
+    let id = match session {
+        Some(session) => wr.register_private(workflow, session.user)?,
+        None => wr.register(workflow)?,
+    };
+    audit_log()?.record(
+        AuditEvent::new(AuditEventKind::WorkflowRegistration, id.to_string()).client(client),
+    )?;
     Ok(warp::reply::json(&id))
 }
 
+async fn validate_workflow<T: WorkflowRegistry>(
+    body: Bytes,
+    session: Option<Session>,
+    workflow_registry: DB<T>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let json = String::from_utf8_lossy(&body);
+
+    let references = serde_json::from_str(&json)
+        .map(|value| referenced_workflows_of_json(&value))
+        .unwrap_or_default();
+    let requester = session.map_or(Requester::Anonymous, Requester::Session);
+    let referenced_workflows = WorkflowSnapshot::collect_references(
+        &*workflow_registry.read().await,
+        &requester,
+        references,
+    );
+
+    let execution_context = ExecutionContext {
+        raster_data_root: config::get_config_element::<config::Raster>()?.data_root,
+        dataset_definitions: Some(dataset_definitions),
+        gdal_dataset_pool: None,
+        dataset_statistics: Some(dataset_statistics()?),
+        pinned_datasets: Some(pinned_datasets()?),
+        r_runtime: config::get_config_element::<config::RRuntime>()?.runtime(),
+        workflow_resolver: Some(Arc::new(referenced_workflows)),
+        resolving_workflows: vec![],
+        principal: requester.principal(),
+    };
+
+    let problems = OperatorRegistry::collect().validate(&json, &execution_context);
+
+    Ok(warp::reply::json(&WorkflowValidation {
+        valid: problems.is_empty(),
+        problems,
+    }))
+}
+
 async fn load_workflow<T: WorkflowRegistry>(
     id: Uuid,
+    session: Option<Session>,
+    workflow_registry: DB<T>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let wr = workflow_registry.read().await;
+    let id = WorkflowId::from_uuid(id);
+    wr.check_access(&id, session.map(|session| session.user))?;
+    Ok(warp::reply::json(&wr.load(&id)?).into_response())
+}
+
+async fn workflow_description<T: WorkflowRegistry>(
+    id: Uuid,
+    session: Option<Session>,
+    workflow_registry: DB<T>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let wr = workflow_registry.read().await;
+    let id = WorkflowId::from_uuid(id);
+    wr.check_access(&id, session.map(|session| session.user))?;
+    let workflow = wr.load(&id)?;
+
+    let description = {
+        let definitions = dataset_definitions
+            .read()
+            .map_err(|_| Error::DatasetDefinitionsLockFailed)?;
+        describe_workflow(&id, &workflow, &definitions)
+    };
+
+    Ok(warp::reply::with_header(
+        description,
+        "Content-Type",
+        "text/markdown; charset=utf-8",
+    ))
+}
+
+async fn share_workflow<T: WorkflowRegistry>(
+    id: Uuid,
+    session: Session,
+    request: CreateShareLink,
     workflow_registry: DB<T>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    let share_config = config::get_config_element::<config::ShareLinks>()?;
+    let invalid_validity = || Error::InvalidShareLinkValidity {
+        max: share_config.max_valid_seconds,
+    };
+    if request.valid_seconds == 0 || request.valid_seconds > share_config.max_valid_seconds {
+        return Err(invalid_validity().into());
+    }
+    let expires = chrono::Duration::from_std(Duration::from_secs(request.valid_seconds))
+        .ok()
+        .and_then(|validity| Utc::now().checked_add_signed(validity))
+        .ok_or_else(invalid_validity)?;
+
+    let id = WorkflowId::from_uuid(id);
+    workflow_registry
+        .read()
+        .await
+        .check_access(&id, Some(session.user))?;
+
+    let link = ShareLink {
+        workflow: id,
+        style: request.style,
+        expires,
+    }
+    .sign(&share_config.secret)?;
+
+    audit_log()?.record(
+        AuditEvent::new(AuditEventKind::ShareLinkCreation, id.to_string()).user(session.user),
+    )?;
+
+    Ok(warp::reply::json(&link))
+}
+
+async fn workflow_statistics<T: WorkflowRegistry>(
+    token: Option<String>,
+    workflow_registry: DB<T>,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let admin_token = config::get_config_element::<config::WorkflowStatistics>()?.admin_token;
+
+    // an empty token disables the endpoint
+    if admin_token.is_empty() || token.as_deref() != Some(admin_token.as_str()) {
+        return Ok(Box::new(
+            warp::http::StatusCode::UNAUTHORIZED.into_response(),
+        ));
+    }
+
     let wr = workflow_registry.read().await;
-    Ok(warp::reply::json(&wr.load(&WorkflowId::from_uuid(id))?).into_response())
+    Ok(Box::new(warp::reply::json(&wr.statistics()?)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handlers::handle_rejection;
+    use crate::users::hashmap_userdb::HashMapUserDB;
+    use crate::users::user::{UserCredentials, UserRegistration};
+    use crate::util::user_input::UserInput;
     use crate::workflows::registry::{HashMapRegistry, WorkflowRegistry};
     use geoengine_operators::engine::VectorOperator;
     use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+    use geoengine_operators::source::DatasetDefinitions;
     use tokio::sync::RwLock;
 
     #[tokio::test]
     async fn register() {
         let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
 
         let workflow = Workflow {
             operator: MockPointSource {
@@ -74,7 +302,10 @@ mod tests {
             .path("/workflow/register")
             .header("Content-Length", "0")
             .json(&workflow)
-            .reply(&register_workflow_handler(workflow_registry.clone()))
+            .reply(&register_workflow_handler(
+                workflow_registry.clone(),
+                user_db.clone(),
+            ))
             .await;
 
         assert_eq!(res.status(), 200);
@@ -83,9 +314,73 @@ mod tests {
         let _id: WorkflowId = serde_json::from_str(&body).unwrap();
     }
 
+    #[tokio::test]
+    async fn validate() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+        let dir = tempfile::tempdir().unwrap();
+        let dataset_definitions = Arc::new(std::sync::RwLock::new(
+            DatasetDefinitions::load(dir.path()).0,
+        ));
+
+        let validate = |body: &str| {
+            warp::test::request()
+                .method("POST")
+                .path("/workflow/validate")
+                .header("Content-Length", "0")
+                .body(body.to_string())
+                .reply(&validate_workflow_handler(
+                    workflow_registry.clone(),
+                    user_db.clone(),
+                    dataset_definitions.clone(),
+                ))
+        };
+
+        let workflow = Workflow {
+            operator: MockPointSource {
+                params: MockPointSourceParams {
+                    points: vec![(0.0, 0.1).into()],
+                },
+            }
+            .boxed()
+            .into(),
+        };
+        let res = validate(&serde_json::to_string(&workflow).unwrap()).await;
+        assert_eq!(res.status(), 200);
+        let validation: WorkflowValidation = serde_json::from_slice(res.body()).unwrap();
+        assert!(validation.valid);
+        assert!(validation.problems.is_empty());
+
+        let res = validate(
+            &serde_json::json!({
+                "type": "Raster",
+                "operator": {
+                    "type": "GdalSource",
+                    "params": {
+                        "dataset_id": "unknown",
+                        "channel": null
+                    }
+                }
+            })
+            .to_string(),
+        )
+        .await;
+        assert_eq!(res.status(), 200);
+        let validation: WorkflowValidation = serde_json::from_slice(res.body()).unwrap();
+        assert!(!validation.valid);
+        assert_eq!(validation.problems.len(), 1);
+        assert_eq!(validation.problems[0].pointer, "/operator");
+
+        let res = validate("not a workflow").await;
+        let validation: WorkflowValidation = serde_json::from_slice(res.body()).unwrap();
+        assert!(!validation.valid);
+        assert_eq!(validation.problems[0].pointer, "");
+    }
+
     #[tokio::test]
     async fn load() {
         let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
 
         let workflow = Workflow {
             operator: MockPointSource {
@@ -106,23 +401,275 @@ mod tests {
         let res = warp::test::request()
             .method("GET")
             .path(&format!("/workflow/{}", id.to_string()))
-            .reply(&load_workflow_handler(workflow_registry.clone()))
+            .reply(&load_workflow_handler(
+                workflow_registry.clone(),
+                user_db.clone(),
+            ))
             .await;
 
         assert_eq!(res.status(), 200);
         assert_eq!(res.body(), &serde_json::to_string(&workflow).unwrap());
     }
 
+    #[tokio::test]
+    async fn description() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+        let dir = tempfile::tempdir().unwrap();
+        let dataset_definitions = Arc::new(std::sync::RwLock::new(
+            DatasetDefinitions::load(dir.path()).0,
+        ));
+
+        let id = workflow_registry
+            .write()
+            .await
+            .register(Workflow {
+                operator: MockPointSource {
+                    params: MockPointSourceParams {
+                        points: vec![(0.0, 0.1).into()],
+                    },
+                }
+                .boxed()
+                .into(),
+            })
+            .unwrap();
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&format!("/workflow/{}/description", id.to_string()))
+            .reply(&workflow_description_handler(
+                workflow_registry,
+                user_db,
+                dataset_definitions,
+            ))
+            .await;
+
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.headers().get("Content-Type").unwrap(),
+            "text/markdown; charset=utf-8"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(res.body()),
+            format!(
+                "# Vector workflow `{}`\n\n- **MockPointSource** (vector): points [1 item]\n",
+                id
+            )
+        );
+    }
+
     #[tokio::test]
     async fn load_not_exist() {
         let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
 
         let res = warp::test::request()
             .method("GET")
             .path("/workflow/1")
-            .reply(&load_workflow_handler(workflow_registry.clone()))
+            .reply(&load_workflow_handler(
+                workflow_registry.clone(),
+                user_db.clone(),
+            ))
             .await;
 
         assert_eq!(res.status(), 404);
     }
+
+    #[tokio::test]
+    async fn private_workflow() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+
+        user_db
+            .write()
+            .await
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".to_string(),
+                    password: "secret123".to_string(),
+                    real_name: "Foo Bar".to_string(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+
+        let session = user_db
+            .write()
+            .await
+            .login(UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap();
+
+        let workflow = Workflow {
+            operator: MockPointSource {
+                params: MockPointSourceParams {
+                    points: vec![(0.0, 0.1).into()],
+                },
+            }
+            .boxed()
+            .into(),
+        };
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/workflow/register")
+            .header("Content-Length", "0")
+            .header("Authorization", session.token.to_string())
+            .json(&workflow)
+            .reply(&register_workflow_handler(
+                workflow_registry.clone(),
+                user_db.clone(),
+            ))
+            .await;
+        assert_eq!(res.status(), 200);
+        let id: WorkflowId = serde_json::from_slice(res.body()).unwrap();
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&format!("/workflow/{}", id.to_string()))
+            .reply(
+                &load_workflow_handler(workflow_registry.clone(), user_db.clone())
+                    .recover(handle_rejection),
+            )
+            .await;
+        assert_eq!(res.status(), 403);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&format!(
+                "/workflow/{}?token={}",
+                id.to_string(),
+                session.token
+            ))
+            .reply(&load_workflow_handler(
+                workflow_registry.clone(),
+                user_db.clone(),
+            ))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path(&format!("/workflow/{}", id.to_string()))
+            .header("Authorization", session.token.to_string())
+            .reply(&load_workflow_handler(workflow_registry, user_db))
+            .await;
+        assert_eq!(res.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn share_without_secret() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+
+        user_db
+            .write()
+            .await
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".to_string(),
+                    password: "secret123".to_string(),
+                    real_name: "Foo Bar".to_string(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+
+        let session = user_db
+            .write()
+            .await
+            .login(UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap();
+
+        let id = workflow_registry
+            .write()
+            .await
+            .register_private(
+                Workflow {
+                    operator: MockPointSource {
+                        params: MockPointSourceParams {
+                            points: vec![(0.0, 0.1).into()],
+                        },
+                    }
+                    .boxed()
+                    .into(),
+                },
+                session.user,
+            )
+            .unwrap();
+
+        let share = |valid_seconds: u64| {
+            warp::test::request()
+                .method("POST")
+                .path(&format!("/workflow/{}/share", id.to_string()))
+                .header("Content-Length", "0")
+                .header("Authorization", session.token.to_string())
+                .json(&serde_json::json!({
+                    "style": "stretch:auto",
+                    "validSeconds": valid_seconds,
+                }))
+        };
+        let handler = share_workflow_handler(workflow_registry, user_db).recover(handle_rejection);
+
+        let res = share(0).reply(&handler).await;
+        assert_eq!(res.status(), 400);
+        assert!(String::from_utf8_lossy(res.body()).contains("at most"));
+
+        // the default settings have no secret
+        let res = share(3600).reply(&handler).await;
+        assert_eq!(res.status(), 400);
+        assert!(String::from_utf8_lossy(res.body()).contains("disabled"));
+    }
+
+    #[tokio::test]
+    async fn statistics_disabled_without_admin_token() {
+        let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+        let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
+
+        user_db
+            .write()
+            .await
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".to_string(),
+                    password: "secret123".to_string(),
+                    real_name: "Foo Bar".to_string(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+
+        // a session is not enough to read the statistics
+        let session = user_db
+            .write()
+            .await
+            .login(UserCredentials {
+                email: "foo@bar.de".to_string(),
+                password: "secret123".to_string(),
+            })
+            .unwrap();
+
+        let res = warp::test::request()
+            .method("GET")
+            .path("/workflows/statistics")
+            .header("Authorization", session.token.to_string())
+            .reply(&workflow_statistics_handler(workflow_registry.clone()))
+            .await;
+        assert_eq!(res.status(), 401);
+
+        let res = warp::test::request()
+            .method("GET")
+            .path("/workflows/statistics")
+            .reply(&workflow_statistics_handler(workflow_registry))
+            .await;
+        assert_eq!(res.status(), 401);
+    }
 }
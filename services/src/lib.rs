@@ -21,7 +21,12 @@
 
 #[macro_use]
 pub mod util;
+pub mod cli;
+pub mod datasets;
 pub mod error;
+pub mod flight;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod handlers;
 pub mod ogc;
 pub mod projects;
@@ -1,3 +1,3 @@
-mod util;
+pub(crate) mod util;
 pub mod wfs;
 pub mod wms;
@@ -1,6 +1,29 @@
-use geoengine_datatypes::primitives::{BoundingBox2D, Coordinate2D, TimeInterval};
+use crate::util::clock::Clock;
+use geoengine_datatypes::primitives::{BoundingBox2D, Coordinate2D, TimeInstance, TimeInterval};
 use serde::de::Error;
 use serde::Deserialize;
+use snafu::Snafu;
+
+/// The reasons for rejecting a parameter of an OGC request
+#[derive(Debug, Snafu, PartialEq)]
+pub enum ParseError {
+    #[snafu(display("Invalid bbox `{}`: expected four comma separated numbers", value))]
+    BboxFormat { value: String },
+    #[snafu(display("Invalid bbox `{}`: the coordinates must be finite", value))]
+    BboxNotFinite { value: String },
+    #[snafu(display(
+        "Invalid bbox `{}`: the lower left corner must not exceed the upper right corner",
+        value
+    ))]
+    BboxOrder { value: String },
+    #[snafu(display(
+        "Invalid time `{}`: expected an RFC 3339 instant or an interval `start/end`",
+        value
+    ))]
+    TimeFormat { value: String },
+    #[snafu(display("Invalid time `{}`: the start must not be after the end", value))]
+    TimeOrder { value: String },
+}
 
 /// Parse bbox, format is: "x1,y1,x2,y2"
 pub fn parse_bbox<'de, D>(deserializer: D) -> Result<BoundingBox2D, D::Error>
@@ -9,13 +32,30 @@ where
 {
     let s = String::deserialize(deserializer)?;
 
+    bbox_from_str(&s).map_err(D::Error::custom)
+}
+
+/// Parse a bbox "x1,y1,x2,y2" with finite coordinates and the lower left corner first.
+/// The axis order is kept as is, i.e., swapped corners are rejected instead of reordered.
+pub fn bbox_from_str(s: &str) -> Result<BoundingBox2D, ParseError> {
     let split: Vec<Result<f64, std::num::ParseFloatError>> = s.split(',').map(str::parse).collect();
 
     if let [Ok(x1), Ok(y1), Ok(x2), Ok(y2)] = *split.as_slice() {
-        BoundingBox2D::new(Coordinate2D::new(x1, y1), Coordinate2D::new(x2, y2))
-            .map_err(D::Error::custom)
+        if ![x1, y1, x2, y2].iter().all(|value| value.is_finite()) {
+            return Err(ParseError::BboxNotFinite {
+                value: s.to_string(),
+            });
+        }
+
+        BoundingBox2D::new(Coordinate2D::new(x1, y1), Coordinate2D::new(x2, y2)).map_err(|_| {
+            ParseError::BboxOrder {
+                value: s.to_string(),
+            }
+        })
     } else {
-        Err(D::Error::custom("Invalid bbox"))
+        Err(ParseError::BboxFormat {
+            value: s.to_string(),
+        })
     }
 }
 
@@ -23,28 +63,351 @@ where
 /// time is specified in ISO8601, it can either be an instant (single datetime) or an interval
 /// An interval is separated by "/". "Either the start value or the end value can be omitted to
 /// indicate no restriction on time in that direction."
+/// "current" is the time of the query, like an omitted time, see `query_time`
 /// sources: - <http://docs.geoserver.org/2.8.x/en/user/services/wms/time.html#wms-time>
 ///          - <http://www.ogcnetwork.net/node/178>
 pub fn parse_time<'de, D>(deserializer: D) -> Result<Option<TimeInterval>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    // TODO: support relative time intervals
-
     let s = String::deserialize(deserializer)?;
 
+    if s.eq_ignore_ascii_case("current") {
+        return Ok(None);
+    }
+
+    time_from_str(&s).map(Some).map_err(D::Error::custom)
+}
+
+/// The time interval of a query for the requested `time`, or the current instant of the `clock`
+/// if the request has no time
+pub fn query_time(time: Option<TimeInterval>, clock: &dyn Clock) -> TimeInterval {
+    time.unwrap_or_else(|| {
+        let now = TimeInstance::from(clock.now());
+        TimeInterval::new_unchecked(now, now)
+    })
+}
+
+/// Parse an RFC 3339 instant or an interval "start/end" of RFC 3339 datetimes
+pub fn time_from_str(s: &str) -> Result<TimeInterval, ParseError> {
+    // TODO: support relative time intervals and omitted starts or ends
+
     let split: Vec<_> = s
         .split('/')
         .map(|s| chrono::DateTime::parse_from_rfc3339(s))
         .collect();
 
-    match *split.as_slice() {
-        [Ok(time)] => TimeInterval::new(time.timestamp(), time.timestamp())
-            .map(Some)
-            .map_err(D::Error::custom),
-        [Ok(start), Ok(end)] => TimeInterval::new(start.timestamp(), end.timestamp())
-            .map(Some)
-            .map_err(D::Error::custom),
-        _ => Err(D::Error::custom("Invalid time")),
+    let interval = match *split.as_slice() {
+        [Ok(time)] => TimeInterval::new(time.timestamp(), time.timestamp()),
+        [Ok(start), Ok(end)] => TimeInterval::new(start.timestamp(), end.timestamp()),
+        _ => {
+            return Err(ParseError::TimeFormat {
+                value: s.to_string(),
+            })
+        }
+    };
+
+    interval.map_err(|_| ParseError::TimeOrder {
+        value: s.to_string(),
+    })
+}
+
+/// Parse a single datetime in RFC 3339
+pub fn parse_time_instance<'de, D>(deserializer: D) -> Result<TimeInstance, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+
+    chrono::DateTime::parse_from_rfc3339(&s)
+        .map(|time| TimeInstance::from_millis(time.timestamp_millis()))
+        .map_err(D::Error::custom)
+}
+
+/// The order of the coordinate axes in the bbox of an OGC request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxisOrder {
+    /// x is the longitude or easting, y is the latitude or northing
+    EastNorth,
+    /// x is the latitude, y is the longitude, as defined by EPSG:4326
+    NorthEast,
+}
+
+impl AxisOrder {
+    /// The axis order of `crs` in a request of a service version that follows the axis order of
+    /// the CRS, i.e., WMS 1.3.0 or WFS 1.1.0 and later. Older versions always use east/north.
+    ///
+    /// `lon_lat` forces the legacy east/north order for clients that ignore the CRS definition.
+    pub fn of_crs(crs: &str, crs_axis_order: bool, lon_lat: bool) -> Self {
+        if crs_axis_order && !lon_lat && is_epsg_4326(crs) {
+            AxisOrder::NorthEast
+        } else {
+            AxisOrder::EastNorth
+        }
+    }
+
+    /// Convert a bbox given in this axis order to one with east/north coordinates
+    pub fn to_east_north(self, bbox: BoundingBox2D) -> BoundingBox2D {
+        match self {
+            AxisOrder::EastNorth => bbox,
+            AxisOrder::NorthEast => swap_axes(bbox),
+        }
+    }
+
+    /// Convert a bbox with east/north coordinates to one in this axis order
+    pub fn from_east_north(self, bbox: BoundingBox2D) -> BoundingBox2D {
+        // swapping is its own inverse
+        self.to_east_north(bbox)
+    }
+}
+
+fn swap_axes(bbox: BoundingBox2D) -> BoundingBox2D {
+    // the corners stay ordered since both axes are swapped
+    BoundingBox2D::new_unchecked(
+        Coordinate2D::new(bbox.lower_left().y, bbox.lower_left().x),
+        Coordinate2D::new(bbox.upper_right().y, bbox.upper_right().x),
+    )
+}
+
+/// Whether `crs` denotes EPSG:4326 in one of the notations of OGC requests.
+/// `CRS:84` is the same datum with lon/lat axes and thus not considered.
+fn is_epsg_4326(crs: &str) -> bool {
+    let crs = crs.trim().to_ascii_uppercase();
+
+    crs == "EPSG:4326"
+        || crs == "URN:OGC:DEF:CRS:EPSG::4326"
+        || crs == "HTTP://WWW.OPENGIS.NET/DEF/CRS/EPSG/0/4326"
+        || crs == "HTTP://WWW.OPENGIS.NET/GML/SRS/EPSG.XML#4326"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ogc::wfs::request::WFSRequest;
+    use crate::ogc::wms::request::WMSRequest;
+    use crate::util::clock::MockClock;
+    use chrono::{TimeZone, Utc};
+    use proptest::prelude::*;
+
+    fn coordinate() -> impl Strategy<Value = f64> {
+        -1.0e9..1.0e9
+    }
+
+    fn rfc3339(seconds: i64) -> String {
+        Utc.timestamp(seconds, 0).to_rfc3339()
+    }
+
+    #[test]
+    fn bbox_errors() {
+        assert!(matches!(
+            bbox_from_str("1,2,3"),
+            Err(ParseError::BboxFormat { .. })
+        ));
+        assert!(matches!(
+            bbox_from_str("1,2,3,4,5"),
+            Err(ParseError::BboxFormat { .. })
+        ));
+        assert!(matches!(
+            bbox_from_str("1;2;3;4"),
+            Err(ParseError::BboxFormat { .. })
+        ));
+        assert!(matches!(
+            bbox_from_str("1,2,inf,4"),
+            Err(ParseError::BboxNotFinite { .. })
+        ));
+        assert!(matches!(
+            bbox_from_str("NaN,2,3,4"),
+            Err(ParseError::BboxNotFinite { .. })
+        ));
+        assert!(matches!(
+            bbox_from_str("3,2,1,4"),
+            Err(ParseError::BboxOrder { .. })
+        ));
+    }
+
+    #[test]
+    fn time_errors() {
+        assert!(matches!(
+            time_from_str("yesterday"),
+            Err(ParseError::TimeFormat { .. })
+        ));
+        assert!(matches!(
+            time_from_str("2014-01-01T00:00:00Z/"),
+            Err(ParseError::TimeFormat { .. })
+        ));
+        assert!(matches!(
+            time_from_str("2014-01-01T00:00:00Z/2014-01-02T00:00:00Z/2014-01-03T00:00:00Z"),
+            Err(ParseError::TimeFormat { .. })
+        ));
+        assert!(matches!(
+            time_from_str("2014-01-02T00:00:00Z/2014-01-01T00:00:00Z"),
+            Err(ParseError::TimeOrder { .. })
+        ));
+    }
+
+    #[test]
+    fn current_time() {
+        let clock = MockClock::new(Utc.ymd(2020, 1, 1).and_hms(12, 0, 0));
+        let now = TimeInstance::from(clock.now());
+
+        let request = |time: &str| -> WMSRequest {
+            serde_urlencoded::from_str(&format!(
+                "request=GetMap&service=WMS&version=1.3.0&layers=test&crs=EPSG:4326&styles=\
+                 &format=image/png&bbox=1,2,3,4&width=2&height=2&time={}",
+                time
+            ))
+            .unwrap()
+        };
+
+        match request("current") {
+            WMSRequest::GetMap(get_map) => assert_eq!(
+                query_time(get_map.time, &clock),
+                TimeInterval::new_unchecked(now, now)
+            ),
+            _ => panic!("expected a GetMap request"),
+        }
+
+        match request("2014-01-01T00:00:00Z") {
+            WMSRequest::GetMap(get_map) => assert_eq!(
+                query_time(get_map.time, &clock),
+                time_from_str("2014-01-01T00:00:00Z").unwrap()
+            ),
+            _ => panic!("expected a GetMap request"),
+        }
+    }
+
+    #[test]
+    fn axis_order() {
+        assert_eq!(
+            AxisOrder::of_crs("EPSG:4326", true, false),
+            AxisOrder::NorthEast
+        );
+        assert_eq!(
+            AxisOrder::of_crs("urn:ogc:def:crs:EPSG::4326", true, false),
+            AxisOrder::NorthEast
+        );
+        assert_eq!(
+            AxisOrder::of_crs("EPSG:4326", false, false),
+            AxisOrder::EastNorth
+        );
+        assert_eq!(
+            AxisOrder::of_crs("EPSG:4326", true, true),
+            AxisOrder::EastNorth
+        );
+        assert_eq!(
+            AxisOrder::of_crs("CRS:84", true, false),
+            AxisOrder::EastNorth
+        );
+        assert_eq!(
+            AxisOrder::of_crs("EPSG:3857", true, false),
+            AxisOrder::EastNorth
+        );
+    }
+
+    #[test]
+    fn swap_bbox_axes() {
+        let lat_lon = bbox_from_str("20,-10,80,50").unwrap();
+        let lon_lat = bbox_from_str("-10,20,50,80").unwrap();
+
+        assert_eq!(AxisOrder::NorthEast.to_east_north(lat_lon), lon_lat);
+        assert_eq!(AxisOrder::NorthEast.from_east_north(lon_lat), lat_lon);
+        assert_eq!(AxisOrder::EastNorth.to_east_north(lon_lat), lon_lat);
+    }
+
+    proptest! {
+        #[test]
+        fn bbox_never_panics(s in ".*") {
+            let _ = bbox_from_str(&s);
+        }
+
+        #[test]
+        fn time_never_panics(s in ".*") {
+            let _ = time_from_str(&s);
+        }
+
+        #[test]
+        fn ordered_bbox_roundtrips(
+            x1 in coordinate(), y1 in coordinate(), x2 in coordinate(), y2 in coordinate()
+        ) {
+            let (x_min, x_max) = if x1 <= x2 { (x1, x2) } else { (x2, x1) };
+            let (y_min, y_max) = if y1 <= y2 { (y1, y2) } else { (y2, y1) };
+
+            let bbox = bbox_from_str(&format!("{},{},{},{}", x_min, y_min, x_max, y_max)).unwrap();
+
+            prop_assert_eq!(bbox.lower_left(), Coordinate2D::new(x_min, y_min));
+            prop_assert_eq!(bbox.upper_right(), Coordinate2D::new(x_max, y_max));
+        }
+
+        #[test]
+        fn swapped_bbox_is_rejected(
+            x in coordinate(), y in coordinate(), dx in 1.0..1.0e6, dy in 0.0..1.0e6
+        ) {
+            let s = format!("{},{},{},{}", x + dx, y, x, y + dy);
+
+            prop_assert_eq!(bbox_from_str(&s), Err(ParseError::BboxOrder { value: s.clone() }));
+        }
+
+        #[test]
+        fn ordered_intervals_are_accepted(
+            start in -1_000_000_000_i64..4_000_000_000, length in 0_i64..1_000_000_000
+        ) {
+            let instant = time_from_str(&rfc3339(start)).unwrap();
+            prop_assert_eq!(instant.start(), instant.end());
+
+            let interval =
+                time_from_str(&format!("{}/{}", rfc3339(start), rfc3339(start + length))).unwrap();
+            prop_assert!(interval.start() <= interval.end());
+
+            if length > 0 {
+                prop_assert!(matches!(
+                    time_from_str(&format!("{}/{}", rfc3339(start + length), rfc3339(start))),
+                    Err(ParseError::TimeOrder { .. })
+                ));
+            }
+        }
+
+        #[test]
+        fn get_map_rejects_malformed_parameters(
+            bbox in ".*", time in ".*", width in ".*", height in ".*"
+        ) {
+            let query = serde_urlencoded::to_string(&[
+                ("request", "GetMap"),
+                ("service", "WMS"),
+                ("version", "1.3.0"),
+                ("layers", "test"),
+                ("crs", "EPSG:4326"),
+                ("styles", ""),
+                ("format", "image/png"),
+                ("bbox", &bbox),
+                ("time", &time),
+                ("width", &width),
+                ("height", &height),
+            ])
+            .unwrap();
+
+            let parsed = serde_urlencoded::from_str::<WMSRequest>(&query);
+
+            let time_is_malformed =
+                time_from_str(&time).is_err() && !time.eq_ignore_ascii_case("current");
+            if bbox_from_str(&bbox).is_err() || time_is_malformed {
+                prop_assert!(parsed.is_err());
+            }
+        }
+
+        #[test]
+        fn get_feature_rejects_malformed_bbox(type_names in ".*", bbox in ".*") {
+            let query = serde_urlencoded::to_string(&[
+                ("request", "GetFeature"),
+                ("service", "WFS"),
+                ("version", "2.0.0"),
+                ("typeNames", &type_names),
+                ("bbox", &bbox),
+            ])
+            .unwrap();
+
+            let parsed = serde_urlencoded::from_str::<WFSRequest>(&query);
+
+            prop_assert_eq!(parsed.is_ok(), bbox_from_str(&bbox).is_ok());
+        }
     }
 }
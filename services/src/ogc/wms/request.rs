@@ -69,7 +69,17 @@ pub struct GetMap {
     pub elevation: Option<String>,
     #[serde(alias = "EXCEPTIONS")]
     pub exceptions: Option<String>, // TODO: parse Option<GetMapExceptionFormat>
-                                    // TODO: DIM_<name>
+    // TODO: DIM_<name>
+    /// Vendor parameter: a comma separated list of the overlays `attribution`, `scalebar` and
+    /// `watermark` that are drawn onto the map
+    #[serde(alias = "DECORATIONS")]
+    pub decorations: Option<String>,
+    /// Vendor parameter: the resolution of the client's display or print in dots per inch, which
+    /// enlarges texts and strokes accordingly
+    #[serde(alias = "MAP_RESOLUTION", alias = "dpi", alias = "DPI")]
+    #[serde(default)]
+    #[serde(deserialize_with = "from_str_option")]
+    pub map_resolution: Option<f64>,
 }
 
 #[derive(PartialEq, Debug, Deserialize, Serialize)]
@@ -116,7 +126,7 @@ mod tests {
 
     #[test]
     fn deserialize_get_map() {
-        let query = "request=GetMap&service=WMS&version=1.3.0&layers=test&bbox=1,2,3,4&width=2&height=2&crs=foo&styles=ssss&format=image/png&time=2000-01-01T00:00:00.0Z/2000-01-02T00:00:00.0Z&transparent=true&bgcolor=#000000&sld=sld_spec&sld_body=sld_body&elevation=elevation&exceptions=exceptions";
+        let query = "request=GetMap&service=WMS&version=1.3.0&layers=test&bbox=1,2,3,4&width=2&height=2&crs=foo&styles=ssss&format=image/png&time=2000-01-01T00:00:00.0Z/2000-01-02T00:00:00.0Z&transparent=true&bgcolor=#000000&sld=sld_spec&sld_body=sld_body&elevation=elevation&exceptions=exceptions&decorations=scalebar&DPI=180";
         let parsed: WMSRequest = serde_urlencoded::from_str(query).unwrap();
 
         let request = WMSRequest::GetMap(GetMap {
@@ -135,6 +145,8 @@ mod tests {
             height: 2,
             format: GetMapFormat::ImagePng,
             exceptions: Some("exceptions".into()),
+            decorations: Some("scalebar".into()),
+            map_resolution: Some(180.),
         });
 
         assert_eq!(parsed, request);
@@ -161,6 +173,8 @@ mod tests {
             height: 2,
             format: GetMapFormat::ImagePng,
             exceptions: None,
+            decorations: None,
+            map_resolution: None,
         });
 
         assert_eq!(parsed, request);
@@ -0,0 +1,260 @@
+use crate::error;
+use crate::error::{Error, Result};
+use crate::projects::project::{CreateProject, Layer, Project, ProjectId, UpdateProject};
+use crate::projects::projectdb::ProjectDB;
+use crate::users::user::UserId;
+use crate::util::user_input::{UserInput, Validated};
+use crate::workflows::registry::WorkflowRegistry;
+use crate::workflows::workflow::{Workflow, WorkflowId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use snafu::{ensure, ResultExt};
+use std::collections::HashMap;
+
+/// A self-contained export of a project that includes all workflows its layers reference
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProjectBundle {
+    pub project: Project,
+    pub workflows: Vec<BundledWorkflow>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundledWorkflow {
+    pub id: WorkflowId,
+    pub workflow: Workflow,
+}
+
+impl ProjectBundle {
+    /// Bundle the `project` with the definitions of all workflows referenced by its layers.
+    /// The export fails if the exporting `user` may not query one of these workflows.
+    pub fn export<W: WorkflowRegistry>(
+        project: Project,
+        user: UserId,
+        workflow_registry: &W,
+    ) -> Result<Self> {
+        let mut workflows: Vec<BundledWorkflow> = Vec::with_capacity(project.layers.len());
+
+        for layer in &project.layers {
+            if workflows.iter().any(|w| w.id == layer.workflow) {
+                continue;
+            }
+
+            workflow_registry.check_access(&layer.workflow, Some(user))?;
+            workflows.push(BundledWorkflow {
+                id: layer.workflow,
+                workflow: workflow_registry.load(&layer.workflow)?,
+            });
+        }
+
+        Ok(Self { project, workflows })
+    }
+}
+
+/// A `ProjectBundle` to import together with a mapping from the dataset ids of the exporting
+/// instance to the dataset ids of this instance
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportProject {
+    pub bundle: ProjectBundle,
+    #[serde(default)]
+    pub dataset_mapping: HashMap<String, String>,
+}
+
+impl UserInput for ImportProject {
+    fn validate(&self) -> Result<(), Error> {
+        for layer in &self.bundle.project.layers {
+            ensure!(
+                self.bundle.workflows.iter().any(|w| w.id == layer.workflow),
+                error::ProjectImportFailed {
+                    reason: format!("Workflow of layer `{}` is missing in bundle", layer.name)
+                }
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl ImportProject {
+    /// Register the bundled workflows and create a new project for the `user` from the bundle.
+    /// Dataset references are remapped according to the `dataset_mapping`.
+    pub fn import<P: ProjectDB, W: WorkflowRegistry>(
+        import: Validated<Self>,
+        user: UserId,
+        project_db: &mut P,
+        workflow_registry: &mut W,
+    ) -> Result<ProjectId> {
+        let ImportProject {
+            bundle,
+            dataset_mapping,
+        } = import.user_input;
+
+        let mut workflow_ids: HashMap<WorkflowId, WorkflowId> = HashMap::new();
+        for bundled in bundle.workflows {
+            let workflow = remap_dataset_ids(&bundled.workflow, &dataset_mapping)?;
+            workflow_ids.insert(bundled.id, workflow_registry.register(workflow)?);
+        }
+
+        let project = bundle.project;
+
+        let create = CreateProject {
+            name: project.name,
+            description: project.description,
+            view: project.view,
+            bounds: project.bounds,
+        }
+        .validated()?;
+
+        let layers: Vec<Option<Layer>> = project
+            .layers
+            .into_iter()
+            .map(|layer| {
+                Some(Layer {
+                    workflow: workflow_ids[&layer.workflow],
+                    ..layer
+                })
+            })
+            .collect();
+
+        let id = project_db.create(user, create);
+
+        if !layers.is_empty() {
+            let update = UpdateProject {
+                id,
+                name: None,
+                description: None,
+                layers: Some(layers),
+                view: None,
+                bounds: None,
+            }
+            .validated()?;
+
+            project_db.update(user, update)?;
+        }
+
+        Ok(id)
+    }
+}
+
+/// Replace all `dataset_id` parameters inside the `workflow` that have an entry in the `mapping`
+fn remap_dataset_ids(workflow: &Workflow, mapping: &HashMap<String, String>) -> Result<Workflow> {
+    if mapping.is_empty() {
+        return Ok(workflow.clone());
+    }
+
+    let mut value = serde_json::to_value(workflow).context(error::SerdeJson)?;
+    replace_dataset_ids(&mut value, mapping);
+    serde_json::from_value(value).context(error::SerdeJson)
+}
+
+fn replace_dataset_ids(value: &mut Value, mapping: &HashMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(dataset_id) if key == "dataset_id" => {
+                        if let Some(new_id) = mapping.get(dataset_id) {
+                            *dataset_id = new_id.clone();
+                        }
+                    }
+                    _ => replace_dataset_ids(value, mapping),
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                replace_dataset_ids(value, mapping);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projects::project::{LayerInfo, STRectangle, VectorInfo};
+    use crate::util::identifiers::Identifier;
+    use crate::workflows::registry::HashMapRegistry;
+    use geoengine_operators::engine::{RasterOperator, TypedOperator, VectorOperator};
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+    use geoengine_operators::source::{GdalSource, GdalSourceParameters};
+
+    #[test]
+    fn export_checks_access() {
+        let mut registry = HashMapRegistry::default();
+        let (owner, other) = (UserId::new(), UserId::new());
+
+        let workflow = registry
+            .register_private(
+                Workflow {
+                    operator: MockPointSource {
+                        params: MockPointSourceParams {
+                            points: vec![(1., 2.).into()],
+                        },
+                    }
+                    .boxed()
+                    .into(),
+                },
+                owner,
+            )
+            .unwrap();
+
+        let mut project = Project::from_create_project(
+            CreateProject {
+                name: "Test".to_string(),
+                description: "Foo".to_string(),
+                view: STRectangle::new(0., 0., 1., 1., 0, 1).unwrap(),
+                bounds: STRectangle::new(0., 0., 1., 1., 0, 1).unwrap(),
+            },
+            owner,
+        );
+        project.layers.push(Layer {
+            workflow,
+            name: "L1".to_string(),
+            info: LayerInfo::Vector(VectorInfo {}),
+        });
+
+        let bundle = ProjectBundle::export(project.clone(), owner, &registry).unwrap();
+        assert_eq!(bundle.workflows.len(), 1);
+
+        assert!(matches!(
+            ProjectBundle::export(project, other, &registry),
+            Err(Error::WorkflowAccessDenied { .. })
+        ));
+    }
+
+    #[test]
+    fn remap() {
+        let workflow = Workflow {
+            operator: TypedOperator::Raster(
+                GdalSource {
+                    params: GdalSourceParameters {
+                        dataset_id: "modis_ndvi".to_owned(),
+                        channel: None,
+                        band: None,
+                    },
+                }
+                .boxed(),
+            ),
+        };
+
+        let mut mapping = HashMap::new();
+        mapping.insert("modis_ndvi".to_owned(), "ndvi".to_owned());
+
+        let remapped = remap_dataset_ids(&workflow, &mapping).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&remapped).unwrap(),
+            serde_json::json!({
+                "type": "Raster",
+                "operator": {
+                    "type": "GdalSource",
+                    "params": {
+                        "dataset_id": "ndvi",
+                        "channel": null
+                    }
+                }
+            })
+        );
+    }
+}
@@ -7,8 +7,9 @@ use crate::projects::project::{
 use crate::projects::projectdb::ProjectDB;
 use crate::users::user::UserId;
 use crate::util::user_input::Validated;
+use crate::workflows::workflow::WorkflowId;
 use snafu::ensure;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Default)]
 pub struct HashMapProjectDB {
@@ -519,6 +520,47 @@ impl ProjectDB for HashMapProjectDB {
                 Ok(())
             })
     }
+
+    fn referencing_projects(&self, workflows: &HashSet<WorkflowId>) -> Vec<ProjectId> {
+        self.projects
+            .iter()
+            .filter(|(_, versions)| {
+                versions.last().map_or(false, |project| {
+                    project
+                        .layers
+                        .iter()
+                        .any(|layer| workflows.contains(&layer.workflow))
+                })
+            })
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    fn is_owner(&self, user: UserId, project: ProjectId) -> bool {
+        self.permissions.iter().any(|p| {
+            p.project == project && p.user == user && p.permission == ProjectPermission::Owner
+        })
+    }
+
+    fn remove_layers(&mut self, user: UserId, workflows: &HashSet<WorkflowId>) -> Result<()> {
+        for id in self.referencing_projects(workflows) {
+            if !self.is_owner(user, id) {
+                continue;
+            }
+
+            let versions = self
+                .projects
+                .get_mut(&id)
+                .ok_or(error::Error::ProjectUpdateFailed)?;
+            let project = versions
+                .last()
+                .ok_or(error::Error::ProjectUpdateFailed)?
+                .without_layers_of(workflows, user);
+            versions.push(project);
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -1,3 +1,4 @@
+pub mod bundle;
 pub mod hashmap_projectdb;
 pub mod project;
 pub mod projectdb;
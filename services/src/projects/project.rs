@@ -2,6 +2,7 @@ use crate::error;
 use crate::error::Error;
 use crate::error::Result;
 use crate::users::user::UserId;
+use crate::util::config;
 use crate::util::identifiers::Identifier;
 use crate::util::user_input::UserInput;
 use crate::workflows::workflow::WorkflowId;
@@ -13,6 +14,7 @@ use geoengine_datatypes::primitives::{
 use serde::{Deserialize, Serialize};
 use snafu::{ensure, ResultExt};
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use uuid::Uuid;
 
 identifier!(ProjectId);
@@ -42,6 +44,16 @@ impl Project {
         }
     }
 
+    /// A new version of the project by the `user` without the layers of the `workflows`
+    pub fn without_layers_of(&self, workflows: &HashSet<WorkflowId>, user: UserId) -> Project {
+        let mut project = self.clone();
+        project.version = ProjectVersion::new(user);
+        project
+            .layers
+            .retain(|layer| !workflows.contains(&layer.workflow));
+        project
+    }
+
     pub fn update_project(&self, update: UpdateProject, user: UserId) -> Project {
         let mut project = self.clone();
         project.version = ProjectVersion::new(user);
@@ -247,7 +259,7 @@ pub struct ProjectListOptions {
 impl UserInput for ProjectListOptions {
     fn validate(&self) -> Result<(), Error> {
         ensure!(
-            self.limit <= 20, // TODO: configuration
+            self.limit <= config::get_config_element::<config::ProjectService>()?.list_limit,
             error::ProjectListFailed
         );
 
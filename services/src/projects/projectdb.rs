@@ -5,6 +5,8 @@ use crate::projects::project::{
 };
 use crate::users::user::UserId;
 use crate::util::user_input::Validated;
+use crate::workflows::workflow::WorkflowId;
+use std::collections::HashSet;
 
 /// Storage of user projects
 pub trait ProjectDB: Send + Sync {
@@ -45,4 +47,14 @@ pub trait ProjectDB: Send + Sync {
 
     /// Remove a `permission` if the `user` is owner of the target project
     fn remove_permission(&mut self, user: UserId, permission: UserProjectPermission) -> Result<()>;
+
+    /// The projects of all users whose latest version has a layer of one of the `workflows`
+    fn referencing_projects(&self, workflows: &HashSet<WorkflowId>) -> Vec<ProjectId>;
+
+    /// Whether the `user` is an owner of the `project`
+    fn is_owner(&self, user: UserId, project: ProjectId) -> bool;
+
+    /// Remove the layers of the `workflows` from the projects that the `user` owns with a new
+    /// version, e.g., because the workflows were unregistered
+    fn remove_layers(&mut self, user: UserId, workflows: &HashSet<WorkflowId>) -> Result<()>;
 }
@@ -3,15 +3,26 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use warp::{Filter, Rejection};
 
+use crate::datasets;
+use crate::datasets::pinned;
+use crate::datasets::statistics;
+use crate::datasets::storage;
+use crate::datasets::watcher;
+use crate::datasets::SharedDatasetDefinitions;
 use crate::error;
 use crate::error::{Error, Result};
 use crate::handlers;
 use crate::handlers::handle_rejection;
 use crate::projects::hashmap_projectdb::HashMapProjectDB;
+use crate::projects::projectdb::ProjectDB;
 use crate::users::hashmap_userdb::HashMapUserDB;
-use crate::workflows::registry::HashMapRegistry;
+use crate::users::userdb::UserDB;
+use crate::util::clock::SystemClock;
+use crate::util::config;
+use crate::workflows::registry::{HashMapRegistry, WorkflowRegistry};
 use snafu::ResultExt;
 use std::path::PathBuf;
+use std::time::Duration;
 use tokio::signal;
 use tokio::sync::oneshot::{Receiver, Sender};
 use warp::fs::File;
@@ -20,18 +31,178 @@ pub async fn start_server(
     shutdown_rx: Option<Receiver<()>>,
     static_files_dir: Option<PathBuf>,
 ) -> Result<()> {
-    let user_db = Arc::new(RwLock::new(HashMapUserDB::default()));
-    let workflow_registry = Arc::new(RwLock::new(HashMapRegistry::default()));
+    let session_ttl = config::get_config_element::<config::Session>()?.ttl();
+
+    #[cfg(feature = "postgres")]
+    {
+        let postgres = config::get_config_element::<config::Postgres>()?;
+        if postgres.enabled {
+            let pool = crate::util::postgres::connect(&postgres)?;
+            return serve(
+                crate::users::postgres_userdb::PostgresUserDB::with_session_ttl(
+                    pool.clone(),
+                    session_ttl,
+                    Arc::new(SystemClock),
+                ),
+                crate::workflows::postgres_registry::PostgresWorkflowRegistry::new(pool),
+                shutdown_rx,
+                static_files_dir,
+            )
+            .await;
+        }
+    }
+
+    serve(
+        HashMapUserDB::with_session_ttl(session_ttl, Arc::new(SystemClock)),
+        HashMapRegistry::default(),
+        shutdown_rx,
+        static_files_dir,
+    )
+    .await
+}
+
+/// Serve the API and the configured background tasks with the given backends
+async fn serve<U, W>(
+    user_db: U,
+    workflow_registry: W,
+    shutdown_rx: Option<Receiver<()>>,
+    static_files_dir: Option<PathBuf>,
+) -> Result<()>
+where
+    U: UserDB + 'static,
+    W: WorkflowRegistry + 'static,
+{
+    let user_db = Arc::new(RwLock::new(user_db));
+    let workflow_registry = Arc::new(RwLock::new(workflow_registry));
     let project_db = Arc::new(RwLock::new(HashMapProjectDB::default()));
+    let dataset_definitions = datasets::load_dataset_definitions()?;
+
+    let reload_interval =
+        config::get_config_element::<config::Raster>()?.definition_reload_interval_seconds;
+    if reload_interval > 0 {
+        tokio::task::spawn(watcher::watch_dataset_definitions(
+            dataset_definitions.clone(),
+            Duration::from_secs(reload_interval),
+        ));
+    }
+
+    let statistics_settings = config::get_config_element::<config::DatasetStatistics>()?;
+    if statistics_settings.interval_seconds > 0 {
+        tokio::task::spawn(statistics::compute_statistics_periodically(
+            dataset_definitions.clone(),
+            datasets::dataset_statistics()?,
+            statistics_settings.sample_size,
+            statistics_settings.bins,
+            Duration::from_secs(statistics_settings.interval_seconds),
+        ));
+    }
 
+    tokio::task::spawn(pinned::pin_stored_datasets(
+        workflow_registry.clone(),
+        datasets::pinned_datasets()?,
+        config::get_config_element::<config::PinnedDatasets>()?.file,
+    ));
+
+    let upload = config::get_config_element::<config::Upload>()?;
+    if upload.garbage_collection_interval_seconds > 0 {
+        tokio::task::spawn(storage::collect_garbage_periodically(
+            upload.directory.clone(),
+            Duration::from_secs(upload.garbage_collection_interval_seconds),
+            upload.ttl(),
+            Duration::from_secs(upload.orphan_ttl_seconds),
+        ));
+    }
+
+    let flight = config::get_config_element::<config::Flight>()?;
+    if flight.enabled {
+        tokio::task::spawn(crate::flight::serve_flight(
+            flight.bind_address,
+            workflow_registry.clone(),
+            user_db.clone(),
+        ));
+    }
+
+    #[cfg(feature = "grpc")]
+    {
+        let grpc = config::get_config_element::<config::Grpc>()?;
+        if grpc.enabled {
+            tokio::task::spawn(crate::grpc::serve_grpc(
+                grpc.bind_address,
+                workflow_registry.clone(),
+                user_db.clone(),
+            ));
+        }
+    }
+
+    let handler = api_filter(user_db, project_db, workflow_registry, dataset_definitions)
+        .or(serve_static_directory(static_files_dir))
+        .recover(handle_rejection);
+
+    let bind_address = config::get_config_element::<config::Web>()?.bind_address;
+
+    let task = if let Some(receiver) = shutdown_rx {
+        let (_, server) = warp::serve(handler).bind_with_graceful_shutdown(bind_address, async {
+            receiver.await.ok();
+        });
+        tokio::task::spawn(server)
+    } else {
+        let server = warp::serve(handler).bind(bind_address);
+        tokio::task::spawn(server)
+    };
+
+    task.await.context(error::TokioJoin)
+}
+
+/// All handlers of the HTTP API, without static files and rejection handling
+pub fn api_filter<U, P, W>(
+    user_db: Arc<RwLock<U>>,
+    project_db: Arc<RwLock<P>>,
+    workflow_registry: Arc<RwLock<W>>,
+    dataset_definitions: SharedDatasetDefinitions,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone
+where
+    U: UserDB + 'static,
+    P: ProjectDB + 'static,
+    W: WorkflowRegistry + 'static,
+{
     // TODO: hierarchical filters workflow -> (register, load), user -> (register, login, ...)
-    let handler = handlers::workflows::register_workflow_handler(workflow_registry.clone())
+    handlers::workflows::register_workflow_handler(workflow_registry.clone(), user_db.clone())
+        .or(handlers::workflows::validate_workflow_handler(
+            workflow_registry.clone(),
+            user_db.clone(),
+            dataset_definitions.clone(),
+        ))
+        .or(handlers::workflows::workflow_description_handler(
+            workflow_registry.clone(),
+            user_db.clone(),
+            dataset_definitions.clone(),
+        ))
         .or(handlers::workflows::load_workflow_handler(
             workflow_registry.clone(),
+            user_db.clone(),
+        ))
+        .or(handlers::workflows::share_workflow_handler(
+            workflow_registry.clone(),
+            user_db.clone(),
+        ))
+        .or(handlers::workflows::workflow_statistics_handler(
+            workflow_registry.clone(),
         ))
         .or(handlers::users::register_user_handler(user_db.clone()))
         .or(handlers::users::login_handler(user_db.clone()))
         .or(handlers::users::logout_handler(user_db.clone()))
+        .or(handlers::users::session_handler(user_db.clone()))
+        .or(handlers::users::refresh_session_handler(user_db.clone()))
+        .or(handlers::users::session_project_handler(
+            user_db.clone(),
+            project_db.clone(),
+        ))
+        .or(handlers::users::session_view_handler(user_db.clone()))
+        .or(handlers::users::user_settings_handler(user_db.clone()))
+        .or(handlers::users::update_user_settings_handler(
+            user_db.clone(),
+        ))
+        .or(handlers::users::user_storage_handler(user_db.clone()))
         .or(handlers::projects::create_project_handler(
             user_db.clone(),
             project_db.clone(),
@@ -40,6 +211,10 @@ pub async fn start_server(
             user_db.clone(),
             project_db.clone(),
         ))
+        .or(handlers::projects::load_project_handler(
+            user_db.clone(),
+            project_db.clone(),
+        ))
         .or(handlers::projects::update_project_handler(
             user_db.clone(),
             project_db.clone(),
@@ -64,23 +239,45 @@ pub async fn start_server(
             user_db.clone(),
             project_db.clone(),
         ))
-        .or(handlers::wms::wms_handler(workflow_registry.clone()))
-        .or(handlers::wfs::wfs_handler(workflow_registry.clone()))
-        .or(serve_static_directory(static_files_dir))
-        .recover(handle_rejection);
-
-    let task = if let Some(receiver) = shutdown_rx {
-        let (_, server) =
-            warp::serve(handler).bind_with_graceful_shutdown(([127, 0, 0, 1], 3030), async {
-                receiver.await.ok();
-            });
-        tokio::task::spawn(server)
-    } else {
-        let server = warp::serve(handler).bind(([127, 0, 0, 1], 3030));
-        tokio::task::spawn(server)
-    };
-
-    task.await.context(error::TokioJoin)
+        .or(handlers::projects::export_project_handler(
+            user_db.clone(),
+            project_db.clone(),
+            workflow_registry.clone(),
+        ))
+        .or(handlers::projects::import_project_handler(
+            user_db.clone(),
+            project_db.clone(),
+            workflow_registry.clone(),
+        ))
+        .or(handlers::datasets::reload_dataset_definitions_handler(
+            dataset_definitions.clone(),
+        ))
+        .or(handlers::datasets::upload_handler(user_db.clone()))
+        .or(handlers::datasets::delete_dataset_handler(
+            user_db.clone(),
+            project_db.clone(),
+            workflow_registry.clone(),
+            dataset_definitions.clone(),
+        ))
+        .or(handlers::wms::wms_handler(
+            workflow_registry.clone(),
+            dataset_definitions.clone(),
+            user_db.clone(),
+        ))
+        .or(handlers::wfs::wfs_handler(
+            workflow_registry.clone(),
+            user_db.clone(),
+        ))
+        .or(handlers::animation::animation_handler(
+            workflow_registry.clone(),
+            dataset_definitions.clone(),
+            user_db.clone(),
+        ))
+        .or(handlers::pinned::list_pinned_datasets_handler())
+        .or(handlers::pinned::pin_dataset_handler(workflow_registry))
+        .or(handlers::pinned::unpin_dataset_handler())
+        .or(handlers::audit::audit_handler())
+        .or(handlers::info::info_handler())
 }
 
 fn serve_static_directory(
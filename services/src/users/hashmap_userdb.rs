@@ -1,19 +1,46 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use chrono::{DateTime, Duration, Utc};
 use pwhash::bcrypt;
 use snafu::ensure;
 
 use crate::error;
 use crate::error::Result;
+use crate::projects::project::{ProjectId, STRectangle};
 use crate::users::session::{Session, SessionToken};
+use crate::users::settings::UserSettings;
 use crate::users::user::{User, UserCredentials, UserId, UserRegistration};
 use crate::users::userdb::UserDB;
+use crate::util::clock::{Clock, SystemClock};
 use crate::util::user_input::Validated;
 
-#[derive(Default)]
 pub struct HashMapUserDB {
     users: HashMap<String, User>,
     sessions: HashMap<SessionToken, Session>,
+    settings: HashMap<UserId, UserSettings>,
+    session_ttl: Option<Duration>,
+    clock: Arc<dyn Clock>,
+}
+
+impl HashMapUserDB {
+    /// A user database whose sessions expire after the `session_ttl`, as measured by the `clock`
+    pub fn with_session_ttl(session_ttl: Option<Duration>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            users: HashMap::new(),
+            sessions: HashMap::new(),
+            settings: HashMap::new(),
+            session_ttl,
+            clock,
+        }
+    }
+}
+
+/// A user database whose sessions are valid until the logout
+impl Default for HashMapUserDB {
+    fn default() -> Self {
+        Self::with_session_ttl(None, Arc::new(SystemClock))
+    }
 }
 
 impl UserDB for HashMapUserDB {
@@ -83,7 +110,11 @@ impl UserDB for HashMapUserDB {
     fn login(&mut self, user_credentials: UserCredentials) -> Result<Session> {
         match self.users.get(&user_credentials.email) {
             Some(user) if bcrypt::verify(user_credentials.password, &user.password_hash) => {
-                let session = Session::new(user);
+                let now = self.clock.now();
+                let session = Session::new(user, self.valid_until(now));
+
+                // forget the sessions that expired in the meantime
+                self.sessions.retain(|_, session| !session.is_expired(now));
                 self.sessions.insert(session.token.clone(), session.clone());
                 Ok(session)
             }
@@ -158,8 +189,132 @@ impl UserDB for HashMapUserDB {
     /// ```
     fn session(&self, token: SessionToken) -> Result<Session> {
         match self.sessions.get(&token) {
+            Some(session) if session.is_expired(self.clock.now()) => {
+                Err(error::Error::SessionExpired)
+            }
             Some(session) => Ok(session.clone()),
             None => Err(error::Error::SessionDoesNotExist),
         }
     }
+
+    fn refresh_session(&mut self, token: SessionToken) -> Result<Session> {
+        let now = self.clock.now();
+        let valid_until = self.valid_until(now);
+
+        match self.sessions.get_mut(&token) {
+            Some(session) if session.is_expired(now) => Err(error::Error::SessionExpired),
+            Some(session) => {
+                session.valid_until = valid_until;
+                Ok(session.clone())
+            }
+            None => Err(error::Error::SessionDoesNotExist),
+        }
+    }
+
+    fn set_session_project(&mut self, session: &Session, project: ProjectId) -> Result<()> {
+        match self.sessions.get_mut(&session.token) {
+            Some(session) => {
+                session.project = Some(project);
+                Ok(())
+            }
+            None => Err(error::Error::SessionDoesNotExist),
+        }
+    }
+
+    fn set_session_view(&mut self, session: &Session, view: STRectangle) -> Result<()> {
+        match self.sessions.get_mut(&session.token) {
+            Some(session) => {
+                session.view = Some(view);
+                Ok(())
+            }
+            None => Err(error::Error::SessionDoesNotExist),
+        }
+    }
+
+    fn settings(&self, user: UserId) -> Result<UserSettings> {
+        ensure!(self.user_exists(user), error::UserDoesNotExist);
+
+        Ok(self.settings.get(&user).cloned().unwrap_or_default())
+    }
+
+    fn set_settings(&mut self, user: UserId, settings: Validated<UserSettings>) -> Result<()> {
+        ensure!(self.user_exists(user), error::UserDoesNotExist);
+
+        self.settings.insert(user, settings.user_input);
+        Ok(())
+    }
+}
+
+impl HashMapUserDB {
+    fn user_exists(&self, user: UserId) -> bool {
+        self.users.values().any(|existing| existing.id == user)
+    }
+
+    /// The end of a session that starts or is refreshed `now`
+    fn valid_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.session_ttl.map(|ttl| now + ttl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::MockClock;
+    use crate::util::user_input::UserInput;
+    use chrono::TimeZone;
+
+    #[test]
+    fn session_expiration() {
+        let clock = MockClock::new(Utc.ymd(2020, 1, 1).and_hms(0, 0, 0));
+        let mut user_db =
+            HashMapUserDB::with_session_ttl(Some(Duration::hours(1)), Arc::new(clock.clone()));
+
+        user_db
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".into(),
+                    password: "secret123".into(),
+                    real_name: "Foo Bar".into(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+        let session = user_db
+            .login(UserCredentials {
+                email: "foo@bar.de".into(),
+                password: "secret123".into(),
+            })
+            .unwrap();
+        assert_eq!(
+            session.valid_until,
+            Some(Utc.ymd(2020, 1, 1).and_hms(1, 0, 0))
+        );
+
+        clock.advance(Duration::minutes(45));
+        assert!(user_db.session(session.token.clone()).is_ok());
+
+        let refreshed = user_db.refresh_session(session.token.clone()).unwrap();
+        assert_eq!(
+            refreshed.valid_until,
+            Some(Utc.ymd(2020, 1, 1).and_hms(1, 45, 0))
+        );
+
+        clock.advance(Duration::minutes(45));
+        assert!(user_db.session(session.token.clone()).is_ok());
+
+        clock.advance(Duration::minutes(15));
+        assert!(matches!(
+            user_db.session(session.token.clone()),
+            Err(error::Error::SessionExpired)
+        ));
+        assert!(matches!(
+            user_db.refresh_session(session.token.clone()),
+            Err(error::Error::SessionExpired)
+        ));
+        assert!(matches!(
+            user_db.refresh_session(SessionToken::default()),
+            Err(error::Error::SessionDoesNotExist)
+        ));
+    }
 }
@@ -1,4 +1,7 @@
 pub mod hashmap_userdb;
+#[cfg(feature = "postgres")]
+pub mod postgres_userdb;
 pub mod session;
+pub mod settings;
 pub mod user;
 pub mod userdb;
@@ -0,0 +1,400 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use postgres_types::Json;
+use pwhash::bcrypt;
+use r2d2_postgres::postgres::Row;
+use snafu::{ensure, ResultExt};
+use uuid::Uuid;
+
+use crate::error;
+use crate::error::Result;
+use crate::projects::project::{ProjectId, STRectangle};
+use crate::users::session::{Session, SessionToken};
+use crate::users::settings::UserSettings;
+use crate::users::user::{User, UserCredentials, UserId, UserRegistration};
+use crate::users::userdb::UserDB;
+use crate::util::clock::{Clock, SystemClock};
+use crate::util::identifiers::Identifier;
+use crate::util::postgres::{blocking, PostgresPool};
+use crate::util::user_input::Validated;
+
+/// A `UserDB` that keeps users, sessions and settings in Postgres, so that they survive restarts
+pub struct PostgresUserDB {
+    pool: PostgresPool,
+    session_ttl: Option<Duration>,
+    clock: Arc<dyn Clock>,
+}
+
+impl PostgresUserDB {
+    /// A user database in the tables of a pool that was created by `util::postgres::connect`,
+    /// whose sessions are valid until the logout
+    pub fn new(pool: PostgresPool) -> Self {
+        Self::with_session_ttl(pool, None, Arc::new(SystemClock))
+    }
+
+    /// A user database whose sessions expire after the `session_ttl`, as measured by the `clock`
+    pub fn with_session_ttl(
+        pool: PostgresPool,
+        session_ttl: Option<Duration>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            pool,
+            session_ttl,
+            clock,
+        }
+    }
+
+    /// The end of a session that starts or is refreshed now
+    fn valid_until(&self) -> Option<DateTime<Utc>> {
+        self.session_ttl.map(|ttl| self.clock.now() + ttl)
+    }
+
+    fn user_exists(&self, user: UserId) -> Result<bool> {
+        let mut connection = self.pool.get().context(error::PostgresPool)?;
+        let row = connection
+            .query_opt("SELECT 1 FROM users WHERE id = $1", &[&user.uuid()])
+            .context(error::Postgres)?;
+        Ok(row.is_some())
+    }
+}
+
+impl UserDB for PostgresUserDB {
+    fn register(&mut self, user_registration: Validated<UserRegistration>) -> Result<UserId> {
+        let user = User::from(user_registration.user_input);
+
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let inserted = connection
+                .execute(
+                    "INSERT INTO users (id, email, password_hash, real_name, active)
+                     VALUES ($1, $2, $3, $4, $5)
+                     ON CONFLICT (email) DO NOTHING",
+                    &[
+                        &user.id.uuid(),
+                        &user.email,
+                        &user.password_hash,
+                        &user.real_name,
+                        &user.active,
+                    ],
+                )
+                .context(error::Postgres)?;
+
+            ensure!(
+                inserted == 1,
+                error::RegistrationFailed {
+                    reason: "E-mail already exists "
+                }
+            );
+
+            Ok(user.id)
+        })
+    }
+
+    fn login(&mut self, user_credentials: UserCredentials) -> Result<Session> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let row = connection
+                .query_opt(
+                    "SELECT id, password_hash FROM users WHERE email = $1",
+                    &[&user_credentials.email],
+                )
+                .context(error::Postgres)?;
+
+            match row {
+                Some(row) if bcrypt::verify(user_credentials.password, row.get(1)) => {
+                    let session = Session {
+                        user: UserId::from_uuid(row.get(0)),
+                        token: SessionToken::default(),
+                        project: None,
+                        view: None,
+                        valid_until: self.valid_until(),
+                    };
+
+                    connection
+                        .execute(
+                            "INSERT INTO sessions (token, user_id, valid_until) VALUES ($1, $2, $3)",
+                            &[
+                                &session.token.uuid(),
+                                &session.user.uuid(),
+                                &session.valid_until,
+                            ],
+                        )
+                        .context(error::Postgres)?;
+
+                    Ok(session)
+                }
+                _ => Err(error::Error::LoginFailed),
+            }
+        })
+    }
+
+    fn logout(&mut self, token: SessionToken) -> Result<()> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let deleted = connection
+                .execute("DELETE FROM sessions WHERE token = $1", &[&token.uuid()])
+                .context(error::Postgres)?;
+
+            ensure!(deleted == 1, error::LogoutFailed);
+            Ok(())
+        })
+    }
+
+    fn session(&self, token: SessionToken) -> Result<Session> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let row = connection
+                .query_opt(
+                    "SELECT user_id, project, view, valid_until FROM sessions WHERE token = $1",
+                    &[&token.uuid()],
+                )
+                .context(error::Postgres)?
+                .ok_or(error::Error::SessionDoesNotExist)?;
+
+            let session = session_from_row(token, &row);
+            ensure!(!session.is_expired(self.clock.now()), error::SessionExpired);
+
+            Ok(session)
+        })
+    }
+
+    fn refresh_session(&mut self, token: SessionToken) -> Result<Session> {
+        let mut session = self.session(token)?;
+        session.valid_until = self.valid_until();
+
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let updated = connection
+                .execute(
+                    "UPDATE sessions SET valid_until = $2 WHERE token = $1",
+                    &[&session.token.uuid(), &session.valid_until],
+                )
+                .context(error::Postgres)?;
+
+            ensure!(updated == 1, error::SessionDoesNotExist);
+            Ok(session)
+        })
+    }
+
+    fn set_session_project(&mut self, session: &Session, project: ProjectId) -> Result<()> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let updated = connection
+                .execute(
+                    "UPDATE sessions SET project = $2 WHERE token = $1",
+                    &[&session.token.uuid(), &project.uuid()],
+                )
+                .context(error::Postgres)?;
+
+            ensure!(updated == 1, error::SessionDoesNotExist);
+            Ok(())
+        })
+    }
+
+    fn set_session_view(&mut self, session: &Session, view: STRectangle) -> Result<()> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let updated = connection
+                .execute(
+                    "UPDATE sessions SET view = $2 WHERE token = $1",
+                    &[&session.token.uuid(), &Json(view)],
+                )
+                .context(error::Postgres)?;
+
+            ensure!(updated == 1, error::SessionDoesNotExist);
+            Ok(())
+        })
+    }
+
+    fn settings(&self, user: UserId) -> Result<UserSettings> {
+        blocking(|| {
+            ensure!(self.user_exists(user)?, error::UserDoesNotExist);
+
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let row = connection
+                .query_opt(
+                    "SELECT settings FROM user_settings WHERE user_id = $1",
+                    &[&user.uuid()],
+                )
+                .context(error::Postgres)?;
+
+            Ok(row
+                .map(|row| row.get::<_, Json<UserSettings>>(0).0)
+                .unwrap_or_default())
+        })
+    }
+
+    fn set_settings(&mut self, user: UserId, settings: Validated<UserSettings>) -> Result<()> {
+        blocking(|| {
+            ensure!(self.user_exists(user)?, error::UserDoesNotExist);
+
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            connection
+                .execute(
+                    "INSERT INTO user_settings (user_id, settings) VALUES ($1, $2)
+                     ON CONFLICT (user_id) DO UPDATE SET settings = EXCLUDED.settings",
+                    &[&user.uuid(), &Json(settings.user_input)],
+                )
+                .context(error::Postgres)?;
+
+            Ok(())
+        })
+    }
+}
+
+fn session_from_row(token: SessionToken, row: &Row) -> Session {
+    Session {
+        user: UserId::from_uuid(row.get(0)),
+        token,
+        project: row.get::<_, Option<Uuid>>(1).map(ProjectId::from_uuid),
+        view: row
+            .get::<_, Option<Json<STRectangle>>>(2)
+            .map(|view| view.0),
+        valid_until: row.get(3),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::MockClock;
+    use crate::util::postgres::TestSchema;
+    use crate::util::user_input::UserInput;
+    use chrono::TimeZone;
+
+    #[tokio::test(threaded_scheduler)]
+    #[ignore]
+    async fn users_and_sessions() {
+        let schema = TestSchema::new();
+        let mut user_db = PostgresUserDB::new(schema.pool.clone());
+
+        let registration = || {
+            UserRegistration {
+                email: "foo@bar.de".into(),
+                password: "secret123".into(),
+                real_name: "Foo Bar".into(),
+            }
+            .validated()
+            .unwrap()
+        };
+        let user = user_db.register(registration()).unwrap();
+        assert!(user_db.register(registration()).is_err());
+
+        assert!(user_db
+            .login(UserCredentials {
+                email: "foo@bar.de".into(),
+                password: "wrong".into(),
+            })
+            .is_err());
+        let session = user_db
+            .login(UserCredentials {
+                email: "foo@bar.de".into(),
+                password: "secret123".into(),
+            })
+            .unwrap();
+        assert_eq!(session.user, user);
+
+        let project = ProjectId::new();
+        let view = STRectangle::new(0., 0., 1., 1., 0, 1).unwrap();
+        user_db.set_session_project(&session, project).unwrap();
+        user_db.set_session_view(&session, view.clone()).unwrap();
+
+        // a new database on the same tables, e.g., after a restart
+        let mut user_db = PostgresUserDB::new(schema.pool.clone());
+        let loaded = user_db.session(session.token.clone()).unwrap();
+        assert_eq!(loaded.user, user);
+        assert_eq!(loaded.project, Some(project));
+        assert_eq!(loaded.view, Some(view));
+
+        user_db.logout(session.token.clone()).unwrap();
+        assert!(user_db.session(session.token.clone()).is_err());
+        assert!(user_db.logout(session.token).is_err());
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    #[ignore]
+    async fn session_expiration() {
+        let schema = TestSchema::new();
+        let clock = MockClock::new(Utc.ymd(2020, 1, 1).and_hms(0, 0, 0));
+        let mut user_db = PostgresUserDB::with_session_ttl(
+            schema.pool.clone(),
+            Some(Duration::hours(1)),
+            Arc::new(clock.clone()),
+        );
+
+        user_db
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".into(),
+                    password: "secret123".into(),
+                    real_name: "Foo Bar".into(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+        let session = user_db
+            .login(UserCredentials {
+                email: "foo@bar.de".into(),
+                password: "secret123".into(),
+            })
+            .unwrap();
+
+        clock.advance(Duration::minutes(45));
+        let refreshed = user_db.refresh_session(session.token.clone()).unwrap();
+        assert_eq!(
+            refreshed.valid_until,
+            Some(Utc.ymd(2020, 1, 1).and_hms(1, 45, 0))
+        );
+        assert_eq!(
+            user_db.session(session.token.clone()).unwrap().valid_until,
+            refreshed.valid_until
+        );
+
+        clock.advance(Duration::hours(1));
+        assert!(matches!(
+            user_db.session(session.token.clone()),
+            Err(error::Error::SessionExpired)
+        ));
+        assert!(user_db.refresh_session(session.token).is_err());
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    #[ignore]
+    async fn settings() {
+        let schema = TestSchema::new();
+        let mut user_db = PostgresUserDB::new(schema.pool.clone());
+
+        let user = user_db
+            .register(
+                UserRegistration {
+                    email: "foo@bar.de".into(),
+                    password: "secret123".into(),
+                    real_name: "Foo Bar".into(),
+                }
+                .validated()
+                .unwrap(),
+            )
+            .unwrap();
+
+        assert!(user_db
+            .settings(user)
+            .unwrap()
+            .default_colorizers
+            .is_empty());
+        assert!(user_db.settings(UserId::new()).is_err());
+
+        let mut settings = UserSettings::default();
+        settings.preferred_crs = Some("EPSG:4326".parse().unwrap());
+        user_db
+            .set_settings(user, settings.clone().validated().unwrap())
+            .unwrap();
+
+        assert_eq!(
+            user_db.settings(user).unwrap().preferred_crs,
+            settings.preferred_crs
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use core::fmt;
 use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error;
 use crate::error::Result;
+use crate::projects::project::{ProjectId, STRectangle};
 use crate::users::user::{User, UserId};
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Hash)]
@@ -23,6 +25,16 @@ impl FromStr for SessionToken {
     }
 }
 
+impl SessionToken {
+    pub fn from_uuid(token: Uuid) -> Self {
+        Self { token }
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.token
+    }
+}
+
 impl Default for SessionToken {
     fn default() -> Self {
         Self {
@@ -37,17 +49,33 @@ impl fmt::Display for SessionToken {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Hash)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct Session {
     pub user: UserId,
     pub token: SessionToken,
+    /// The project that is currently open in the frontend
+    pub project: Option<ProjectId>,
+    /// The last view of the map, i.e., its bounding box and time
+    pub view: Option<STRectangle>,
+    /// When the session expires unless it is refreshed, `None` if it is valid until the logout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
 }
 
 impl Session {
-    pub fn new(user: &User) -> Session {
+    pub fn new(user: &User, valid_until: Option<DateTime<Utc>>) -> Session {
         Self {
             user: user.id,
             token: SessionToken::default(),
+            project: None,
+            view: None,
+            valid_until,
         }
     }
+
+    /// Whether the session is no longer valid at `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.valid_until
+            .map_or(false, |valid_until| valid_until <= now)
+    }
 }
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use geoengine_datatypes::operations::image::{Colorizer, VectorStyle};
+use geoengine_datatypes::spatial_reference::SpatialReference;
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+use crate::error;
+use crate::error::{Error, Result};
+use crate::util::user_input::UserInput;
+
+/// The maximum number of default colorizers and style presets, respectively
+const MAX_ENTRIES: usize = 100;
+/// The maximum length of the names of default colorizers and style presets
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Styling choices of a user that persist across sessions and devices
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UserSettings {
+    /// The CRS for new projects
+    pub preferred_crs: Option<SpatialReference>,
+    /// Colorizers for new raster layers, e.g., by dataset or data type
+    pub default_colorizers: HashMap<String, Colorizer>,
+    /// Saved styles that can be applied to layers
+    pub style_presets: HashMap<String, StylePreset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum StylePreset {
+    Raster { colorizer: Colorizer },
+    Vector { style: VectorStyle },
+}
+
+impl UserInput for UserSettings {
+    fn validate(&self) -> Result<(), Error> {
+        validate_names("defaultColorizers", self.default_colorizers.keys())?;
+        validate_names("stylePresets", self.style_presets.keys())?;
+
+        Ok(())
+    }
+}
+
+fn validate_names<'n>(field: &str, names: impl ExactSizeIterator<Item = &'n String>) -> Result<()> {
+    ensure!(
+        names.len() <= MAX_ENTRIES,
+        error::InvalidUserSettings {
+            reason: format!("`{}` must have at most {} entries", field, MAX_ENTRIES)
+        }
+    );
+
+    for name in names {
+        ensure!(
+            !name.is_empty() && name.chars().count() <= MAX_NAME_LENGTH,
+            error::InvalidUserSettings {
+                reason: format!(
+                    "the names of `{}` must have between 1 and {} characters",
+                    field, MAX_NAME_LENGTH
+                )
+            }
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let mut settings = UserSettings::default();
+        assert!(settings.validate().is_ok());
+
+        settings.style_presets.insert(
+            "points".to_string(),
+            StylePreset::Vector {
+                style: VectorStyle::default(),
+            },
+        );
+        assert!(settings.validate().is_ok());
+
+        settings.style_presets.insert(
+            String::new(),
+            StylePreset::Vector {
+                style: VectorStyle::default(),
+            },
+        );
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn deserialize_partial_settings() {
+        let settings: UserSettings =
+            serde_json::from_str(r#"{"preferredCrs": "EPSG:4326"}"#).unwrap();
+
+        assert_eq!(settings.preferred_crs, Some(SpatialReference::wgs84()));
+        assert!(settings.default_colorizers.is_empty());
+        assert!(settings.style_presets.is_empty());
+    }
+}
@@ -4,8 +4,10 @@ use snafu::ensure;
 
 use crate::error;
 use crate::error::{Error, Result};
+use crate::util::config;
 use crate::util::identifiers::Identifier;
 use crate::util::user_input::UserInput;
+use std::fmt;
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Hash)]
 pub struct UserRegistration {
@@ -24,12 +26,11 @@ impl UserInput for UserRegistration {
             }
         );
 
-        ensure!(
-            self.password.len() >= 8,
-            error::RegistrationFailed {
-                reason: "Password must have at least 8 characters"
-            }
+        let violations = password_violations(
+            &self.password,
+            &config::get_config_element::<config::PasswordPolicy>()?,
         );
+        ensure!(violations.is_empty(), error::InvalidPassword { violations });
 
         ensure!(
             !self.real_name.is_empty(),
@@ -42,6 +43,75 @@ impl UserInput for UserRegistration {
     }
 }
 
+/// A rule of the password policy that a password breaks
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PasswordViolation {
+    #[serde(rename_all = "camelCase")]
+    TooShort {
+        min_length: usize,
+    },
+    #[serde(rename_all = "camelCase")]
+    TooLong {
+        max_bytes: usize,
+    },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSpecial,
+}
+
+impl fmt::Display for PasswordViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort { min_length } => {
+                write!(f, "must have at least {} characters", min_length)
+            }
+            Self::TooLong { max_bytes } => write!(f, "must have at most {} bytes", max_bytes),
+            Self::MissingLowercase => write!(f, "must contain a lowercase letter"),
+            Self::MissingUppercase => write!(f, "must contain an uppercase letter"),
+            Self::MissingDigit => write!(f, "must contain a digit"),
+            Self::MissingSpecial => {
+                write!(f, "must contain a character that is no letter or digit")
+            }
+        }
+    }
+}
+
+/// The rules of the `policy` that the `password` breaks, empty if it is acceptable
+pub fn password_violations(
+    password: &str,
+    policy: &config::PasswordPolicy,
+) -> Vec<PasswordViolation> {
+    let mut violations = Vec::new();
+    let contains = |class: fn(char) -> bool| password.chars().any(class);
+
+    if password.chars().count() < policy.min_length {
+        violations.push(PasswordViolation::TooShort {
+            min_length: policy.min_length,
+        });
+    }
+    if password.len() > config::PasswordPolicy::MAX_BYTES {
+        violations.push(PasswordViolation::TooLong {
+            max_bytes: config::PasswordPolicy::MAX_BYTES,
+        });
+    }
+    if policy.require_lowercase && !contains(char::is_lowercase) {
+        violations.push(PasswordViolation::MissingLowercase);
+    }
+    if policy.require_uppercase && !contains(char::is_uppercase) {
+        violations.push(PasswordViolation::MissingUppercase);
+    }
+    if policy.require_digit && !contains(|c| c.is_ascii_digit()) {
+        violations.push(PasswordViolation::MissingDigit);
+    }
+    if policy.require_special && !contains(|c| !c.is_alphanumeric()) {
+        violations.push(PasswordViolation::MissingSpecial);
+    }
+
+    violations
+}
+
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Hash)]
 pub struct UserCredentials {
     pub email: String,
@@ -70,3 +140,45 @@ impl From<UserRegistration> for User {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn password_policy() {
+        let policy = config::PasswordPolicy {
+            min_length: 8,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_special: true,
+        };
+
+        assert!(password_violations("Secret#123", &policy).is_empty());
+        assert_eq!(
+            password_violations("secret", &policy),
+            vec![
+                PasswordViolation::TooShort { min_length: 8 },
+                PasswordViolation::MissingUppercase,
+                PasswordViolation::MissingDigit,
+                PasswordViolation::MissingSpecial,
+            ]
+        );
+        // the length is counted in characters, but limited in bytes
+        assert!(password_violations("Ä#1äöüßé", &policy).is_empty());
+        assert_eq!(
+            password_violations(&"Aa1#".repeat(19), &policy),
+            vec![PasswordViolation::TooLong { max_bytes: 72 }]
+        );
+
+        let lenient = config::PasswordPolicy {
+            min_length: 0,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_special: false,
+        };
+        assert!(password_violations("", &lenient).is_empty());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::error::Result;
+use crate::projects::project::{ProjectId, STRectangle};
 use crate::users::session::{Session, SessionToken};
+use crate::users::settings::UserSettings;
 use crate::users::user::{UserCredentials, UserId, UserRegistration};
 use crate::util::user_input::Validated;
 
@@ -32,7 +34,47 @@ pub trait UserDB: Send + Sync {
     ///
     /// # Errors
     ///
-    /// This call fails if the token is invalid.
+    /// This call fails if the token is invalid or the session expired.
     ///
     fn session(&self, token: SessionToken) -> Result<Session>;
+
+    /// Extends the validity of a session by the session TTL, starting now
+    ///
+    /// # Errors
+    ///
+    /// This call fails if the token is invalid or the session expired.
+    ///
+    fn refresh_session(&mut self, token: SessionToken) -> Result<Session>;
+
+    /// Sets the project that is open in the `session`
+    ///
+    /// # Errors
+    ///
+    /// This call fails if the session is invalid.
+    ///
+    fn set_session_project(&mut self, session: &Session, project: ProjectId) -> Result<()>;
+
+    /// Sets the last map view of the `session`
+    ///
+    /// # Errors
+    ///
+    /// This call fails if the session is invalid.
+    ///
+    fn set_session_view(&mut self, session: &Session, view: STRectangle) -> Result<()>;
+
+    /// Loads the settings of the `user`, which are the defaults if they were never set
+    ///
+    /// # Errors
+    ///
+    /// This call fails if the user does not exist.
+    ///
+    fn settings(&self, user: UserId) -> Result<UserSettings>;
+
+    /// Replaces the settings of the `user`
+    ///
+    /// # Errors
+    ///
+    /// This call fails if the user does not exist.
+    ///
+    fn set_settings(&mut self, user: UserId, settings: Validated<UserSettings>) -> Result<()>;
 }
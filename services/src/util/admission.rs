@@ -0,0 +1,250 @@
+use crate::error::{Error, Result};
+use crate::util::config;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+lazy_static! {
+    static ref QUERY_ADMISSION: Mutex<Option<Arc<QueryAdmission>>> = Mutex::new(None);
+}
+
+/// The admission control for queries that is shared by all handlers.
+/// It is created from the configuration on first use.
+pub fn query_admission() -> Result<Arc<QueryAdmission>> {
+    let mut admission = QUERY_ADMISSION
+        .lock()
+        .map_err(|_| Error::QueryAdmissionLockFailed)?;
+
+    if let Some(admission) = admission.as_ref() {
+        return Ok(admission.clone());
+    }
+
+    let settings = config::get_config_element::<config::QueryAdmission>()?;
+    let created = Arc::new(QueryAdmission::new(
+        settings.max_running_per_client,
+        settings.max_queued_per_client,
+        settings.retry_after_seconds,
+    ));
+    *admission = Some(created.clone());
+
+    Ok(created)
+}
+
+/// Bounds the number of simultaneously running queries per client.
+///
+/// A client is a user session or, for anonymous requests, a remote address.
+/// Queries beyond `max_running_per_client` wait in a queue of at most `max_queued_per_client`
+/// queries; further queries are rejected, so that one client cannot starve all others.
+#[derive(Debug)]
+pub struct QueryAdmission {
+    max_running_per_client: usize,
+    max_queued_per_client: usize,
+    retry_after_seconds: u64,
+    clients: Arc<Mutex<Clients>>,
+}
+
+type Clients = HashMap<String, Arc<ClientQueries>>;
+
+#[derive(Debug)]
+struct ClientQueries {
+    counts: Mutex<QueryCounts>,
+    finished: Notify,
+}
+
+#[derive(Debug, Default)]
+struct QueryCounts {
+    running: usize,
+    queued: usize,
+}
+
+impl QueryAdmission {
+    pub fn new(
+        max_running_per_client: usize,
+        max_queued_per_client: usize,
+        retry_after_seconds: u64,
+    ) -> Self {
+        Self {
+            max_running_per_client,
+            max_queued_per_client,
+            retry_after_seconds,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Wait until `client` may run another query.
+    /// The query counts as running until the returned permit is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::TooManyQueries` if the queue of the client is full
+    ///
+    pub async fn admit(&self, client: &str) -> Result<QueryPermit> {
+        let queries = self.client_queries(client)?;
+        let mut queued: Option<QueuedQuery> = None;
+
+        loop {
+            {
+                let mut counts = queries
+                    .counts
+                    .lock()
+                    .map_err(|_| Error::QueryAdmissionLockFailed)?;
+
+                if counts.running < self.max_running_per_client {
+                    counts.running += 1;
+                    drop(counts);
+                    // leave the queue
+                    drop(queued);
+
+                    return Ok(QueryPermit {
+                        clients: self.clients.clone(),
+                        client: client.to_string(),
+                        queries: Some(queries),
+                    });
+                }
+
+                if queued.is_none() {
+                    if counts.queued >= self.max_queued_per_client {
+                        return Err(Error::TooManyQueries {
+                            retry_after_seconds: self.retry_after_seconds,
+                        });
+                    }
+
+                    counts.queued += 1;
+                    queued = Some(QueuedQuery {
+                        queries: queries.clone(),
+                    });
+                }
+            }
+
+            queries.finished.notified().await;
+        }
+    }
+
+    /// The number of running and queued queries of `client`
+    pub fn queries(&self, client: &str) -> (usize, usize) {
+        self.clients
+            .lock()
+            .ok()
+            .and_then(|clients| {
+                let counts = clients.get(client)?.counts.lock().ok()?;
+                Some((counts.running, counts.queued))
+            })
+            .unwrap_or_default()
+    }
+
+    fn client_queries(&self, client: &str) -> Result<Arc<ClientQueries>> {
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|_| Error::QueryAdmissionLockFailed)?;
+
+        Ok(clients
+            .entry(client.to_string())
+            .or_insert_with(|| {
+                Arc::new(ClientQueries {
+                    counts: Mutex::default(),
+                    finished: Notify::new(),
+                })
+            })
+            .clone())
+    }
+}
+
+/// Marks a query as running until it is dropped
+#[derive(Debug)]
+pub struct QueryPermit {
+    clients: Arc<Mutex<Clients>>,
+    client: String,
+    queries: Option<Arc<ClientQueries>>,
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        if let Some(queries) = self.queries.take() {
+            if let Ok(mut counts) = queries.counts.lock() {
+                counts.running -= 1;
+            }
+            queries.finished.notify();
+        }
+
+        // forget clients without running or queued queries
+        if let Ok(mut clients) = self.clients.lock() {
+            let idle = clients
+                .get(&self.client)
+                .map_or(false, |queries| Arc::strong_count(queries) == 1);
+
+            if idle {
+                clients.remove(&self.client);
+            }
+        }
+    }
+}
+
+/// Leaves the queue if a waiting query is cancelled
+struct QueuedQuery {
+    queries: Arc<ClientQueries>,
+}
+
+impl Drop for QueuedQuery {
+    fn drop(&mut self) {
+        if let Ok(mut counts) = self.queries.counts.lock() {
+            counts.queued -= 1;
+        }
+        // pass on a notification that this query might have consumed
+        self.queries.finished.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn queues_and_rejects_queries() {
+        let admission = Arc::new(QueryAdmission::new(1, 1, 3));
+
+        let running = admission.admit("a").await.unwrap();
+        assert_eq!(admission.queries("a"), (1, 0));
+
+        let queued = {
+            let admission = admission.clone();
+            tokio::spawn(async move { admission.admit("a").await.map(|_| ()) })
+        };
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+        assert_eq!(admission.queries("a"), (1, 1));
+
+        assert!(matches!(
+            admission.admit("a").await,
+            Err(Error::TooManyQueries {
+                retry_after_seconds: 3
+            })
+        ));
+
+        // other clients are not affected
+        let other = admission.admit("b").await.unwrap();
+        drop(other);
+        assert_eq!(admission.queries("b"), (0, 0));
+
+        drop(running);
+        queued.await.unwrap().unwrap();
+        assert_eq!(admission.queries("a"), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn cancelled_queries_leave_the_queue() {
+        let admission = Arc::new(QueryAdmission::new(1, 1, 1));
+
+        let running = admission.admit("a").await.unwrap();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), admission.admit("a"))
+                .await
+                .is_err()
+        );
+        assert_eq!(admission.queries("a"), (1, 0));
+
+        drop(running);
+        assert!(admission.admit("a").await.is_ok());
+    }
+}
@@ -0,0 +1,325 @@
+use crate::error::{self, Error, Result};
+use crate::users::user::UserId;
+use crate::util::config;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+lazy_static! {
+    static ref AUDIT_LOG: Mutex<Option<Arc<AuditLog>>> = Mutex::new(None);
+}
+
+/// The audit log that is shared by all handlers.
+/// It is created from the configuration on first use.
+pub fn audit_log() -> Result<Arc<AuditLog>> {
+    let mut audit_log = AUDIT_LOG.lock().map_err(|_| Error::AuditLogLockFailed)?;
+
+    if let Some(audit_log) = audit_log.as_ref() {
+        return Ok(audit_log.clone());
+    }
+
+    let settings = config::get_config_element::<config::Audit>()?;
+    let sink: Box<dyn AuditSink> = match settings.sink {
+        config::AuditSinkType::Memory => Box::new(MemoryAuditSink::new(settings.memory_capacity)),
+        config::AuditSinkType::File => Box::new(FileAuditSink::new(settings.file)),
+        config::AuditSinkType::Stdout => Box::new(StdoutAuditSink),
+    };
+    let created = Arc::new(AuditLog::new(sink));
+    *audit_log = Some(created.clone());
+
+    Ok(created)
+}
+
+/// A security-relevant event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    pub time: DateTime<Utc>,
+    pub kind: AuditEventKind,
+    /// The user that caused the event, if known
+    pub user: Option<UserId>,
+    /// The session or remote address of the request, see `query_client`
+    pub client: Option<String>,
+    pub details: String,
+}
+
+impl AuditEvent {
+    pub fn new(kind: AuditEventKind, details: impl Into<String>) -> Self {
+        Self {
+            time: Utc::now(),
+            kind,
+            user: None,
+            client: None,
+            details: details.into(),
+        }
+    }
+
+    pub fn user(mut self, user: UserId) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    pub fn client(mut self, client: impl Into<String>) -> Self {
+        self.client = Some(client.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditEventKind {
+    Login,
+    FailedLogin,
+    Logout,
+    WorkflowRegistration,
+    ShareLinkCreation,
+    DatasetUpload,
+    DatasetDeletion,
+    DatasetReload,
+    DatasetPin,
+    DatasetUnpin,
+    PermissionChange,
+}
+
+/// Selects events of the audit log, the most recent first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditQuery {
+    pub kind: Option<AuditEventKind>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, event: &AuditEvent) -> bool {
+        self.kind.map_or(true, |kind| kind == event.kind)
+            && self.since.map_or(true, |since| event.time >= since)
+    }
+
+    /// The matching events of `events` (oldest first), the most recent first
+    fn select<'e>(
+        &self,
+        events: impl DoubleEndedIterator<Item = &'e AuditEvent>,
+    ) -> Vec<AuditEvent> {
+        events
+            .rev()
+            .filter(|event| self.matches(event))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Where the events of the audit log are stored
+pub trait AuditSink: Send + Sync + std::fmt::Debug {
+    fn record(&self, event: &AuditEvent) -> Result<()>;
+
+    /// The recorded events that match the `query`
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::AuditLogNotQueryable` if the sink only writes events
+    ///
+    fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>>;
+}
+
+/// Records events and forwards them to its sink
+#[derive(Debug)]
+pub struct AuditLog {
+    sink: Box<dyn AuditSink>,
+}
+
+impl AuditLog {
+    pub fn new(sink: Box<dyn AuditSink>) -> Self {
+        Self { sink }
+    }
+
+    pub fn record(&self, event: AuditEvent) -> Result<()> {
+        self.sink.record(&event)
+    }
+
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        self.sink.query(query)
+    }
+}
+
+/// Keeps the latest `capacity` events in memory
+#[derive(Debug)]
+pub struct MemoryAuditSink {
+    capacity: usize,
+    events: Mutex<VecDeque<AuditEvent>>,
+}
+
+impl MemoryAuditSink {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+}
+
+impl AuditSink for MemoryAuditSink {
+    fn record(&self, event: &AuditEvent) -> Result<()> {
+        let mut events = self.events.lock().map_err(|_| Error::AuditLogLockFailed)?;
+        if events.len() >= self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event.clone());
+        Ok(())
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        let events = self.events.lock().map_err(|_| Error::AuditLogLockFailed)?;
+        Ok(query.select(events.iter()))
+    }
+}
+
+/// Appends the events as JSON lines to a file
+#[derive(Debug)]
+pub struct FileAuditSink {
+    path: PathBuf,
+    /// serializes the writes of concurrent requests
+    lock: Mutex<()>,
+}
+
+impl FileAuditSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&self, event: &AuditEvent) -> Result<()> {
+        let _lock = self.lock.lock().map_err(|_| Error::AuditLogLockFailed)?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context(error::IO)?;
+        let line = serde_json::to_string(event).context(error::SerdeJson)?;
+        writeln!(file, "{}", line).context(error::IO)
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        let _lock = self.lock.lock().map_err(|_| Error::AuditLogLockFailed)?;
+
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => return Err(Error::IO { source: error }),
+        };
+
+        let mut events: Vec<AuditEvent> = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.context(error::IO)?;
+            if !line.trim().is_empty() {
+                events.push(serde_json::from_str(&line).context(error::SerdeJson)?);
+            }
+        }
+
+        Ok(query.select(events.iter()))
+    }
+}
+
+/// Prints the events as JSON lines to the standard output, e.g., for a log collector
+#[derive(Debug)]
+pub struct StdoutAuditSink;
+
+impl AuditSink for StdoutAuditSink {
+    fn record(&self, event: &AuditEvent) -> Result<()> {
+        let line = serde_json::to_string(event).context(error::SerdeJson)?;
+        println!("{}", line);
+        Ok(())
+    }
+
+    fn query(&self, _query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        Err(Error::AuditLogNotQueryable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::identifiers::Identifier;
+
+    fn events() -> Vec<AuditEvent> {
+        vec![
+            AuditEvent::new(AuditEventKind::FailedLogin, "foo@bar.de").client("address:127.0.0.1"),
+            AuditEvent::new(AuditEventKind::Login, "foo@bar.de").user(UserId::new()),
+            AuditEvent::new(AuditEventKind::FailedLogin, "bar@foo.de"),
+        ]
+    }
+
+    #[test]
+    fn memory_sink() {
+        let log = AuditLog::new(Box::new(MemoryAuditSink::new(2)));
+        let events = events();
+        for event in &events {
+            log.record(event.clone()).unwrap();
+        }
+
+        // the oldest event was dropped
+        assert_eq!(
+            log.query(&AuditQuery::default()).unwrap(),
+            vec![events[2].clone(), events[1].clone()]
+        );
+        assert_eq!(
+            log.query(&AuditQuery {
+                kind: Some(AuditEventKind::FailedLogin),
+                ..Default::default()
+            })
+            .unwrap(),
+            vec![events[2].clone()]
+        );
+        assert_eq!(
+            log.query(&AuditQuery {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap()
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn file_sink() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(Box::new(FileAuditSink::new(
+            directory.path().join("audit.log"),
+        )));
+        assert!(log.query(&AuditQuery::default()).unwrap().is_empty());
+
+        let events = events();
+        for event in &events {
+            log.record(event.clone()).unwrap();
+        }
+
+        assert_eq!(
+            log.query(&AuditQuery {
+                kind: Some(AuditEventKind::FailedLogin),
+                ..Default::default()
+            })
+            .unwrap(),
+            vec![events[2].clone(), events[0].clone()]
+        );
+    }
+
+    #[test]
+    fn stdout_sink_is_not_queryable() {
+        let log = AuditLog::new(Box::new(StdoutAuditSink));
+
+        assert!(log.record(events().remove(0)).is_ok());
+        assert!(log.query(&AuditQuery::default()).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Duration, Utc};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+/// The source of the current time for time-dependent behavior, e.g., timestamps of usage
+/// statistics or the default time of OGC requests.
+///
+/// Components take a clock instead of reading the system time, so that tests can control time
+/// with a `MockClock` instead of sleeping.
+pub trait Clock: Send + Sync + Debug {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system time
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that stands still until it is set or advanced, for tests.
+/// Clones share their time.
+///
+/// # Examples
+///
+/// ```rust
+/// use chrono::{Duration, TimeZone, Utc};
+/// use geoengine_services::util::clock::{Clock, MockClock};
+///
+/// let clock = MockClock::new(Utc.ymd(2020, 1, 1).and_hms(0, 0, 0));
+/// clock.advance(Duration::hours(1));
+///
+/// assert_eq!(clock.now(), Utc.ymd(2020, 1, 1).and_hms(1, 0, 0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self
+            .now
+            .lock()
+            .expect("mock clock lock must not be poisoned") = now;
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self
+            .now
+            .lock()
+            .expect("mock clock lock must not be poisoned");
+        *now = *now + duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self
+            .now
+            .lock()
+            .expect("mock clock lock must not be poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn mock_clock() {
+        let start = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let clock = MockClock::new(start);
+        let shared: Arc<dyn Clock> = Arc::new(clock.clone());
+
+        assert_eq!(shared.now(), start);
+
+        clock.advance(Duration::minutes(90));
+        assert_eq!(shared.now(), Utc.ymd(2020, 1, 1).and_hms(1, 30, 0));
+
+        clock.set(start);
+        assert_eq!(shared.now(), start);
+    }
+}
@@ -0,0 +1,760 @@
+use crate::error;
+use crate::error::{Error, Result};
+use config::{Config, Environment, File, FileFormat};
+use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+lazy_static! {
+    static ref SETTINGS: RwLock<Config> = RwLock::new(default_settings());
+}
+
+fn default_settings() -> Config {
+    let mut settings = Config::default();
+    settings
+        .merge(File::from_str(
+            include_str!("../../Settings-default.toml"),
+            FileFormat::Toml,
+        ))
+        .expect("the default settings must be valid");
+    settings
+}
+
+/// The prefix of environment variables that override settings
+const ENVIRONMENT_PREFIX: &str = "GEOENGINE";
+
+/// Load the settings in layers, where each layer overrides the ones before:
+///
+/// 1. the default settings
+/// 2. the `Settings-<profile>.toml` of the `profile` or of the `GEOENGINE_PROFILE` variable, if any
+/// 3. the settings file at `path` or an optional `Settings.toml` in the working directory
+/// 4. environment variables of the form `GEOENGINE_<SECTION>__<KEY>`,
+///    e.g., `GEOENGINE_WEB__BIND_ADDRESS=0.0.0.0:3030`
+///
+/// Values of environment variables are parsed into the type of the setting when it is read.
+pub fn load_settings(profile: Option<&str>, path: Option<&Path>) -> Result<()> {
+    let profile = profile
+        .map(ToString::to_string)
+        .or_else(|| std::env::var(format!("{}_PROFILE", ENVIRONMENT_PREFIX)).ok());
+
+    let settings_file = match path {
+        Some(path) => File::from(path).required(true),
+        None => File::with_name("Settings").required(false),
+    };
+
+    let settings = layered_settings(
+        profile.map(|profile| File::with_name(&format!("Settings-{}", profile)).required(true)),
+        settings_file,
+        Environment::with_prefix(ENVIRONMENT_PREFIX).separator("__"),
+    )?;
+
+    *SETTINGS.write().map_err(|_| Error::ConfigLockFailed)? = settings;
+
+    Ok(())
+}
+
+fn layered_settings<P, S>(
+    profile_file: Option<File<P>>,
+    settings_file: File<S>,
+    environment: Environment,
+) -> Result<Config>
+where
+    File<P>: config::Source + Send + Sync + 'static,
+    File<S>: config::Source + Send + Sync + 'static,
+{
+    let mut settings = default_settings();
+
+    if let Some(profile_file) = profile_file {
+        settings.merge(profile_file).context(error::Config)?;
+    }
+    settings.merge(settings_file).context(error::Config)?;
+    settings.merge(environment).context(error::Config)?;
+
+    Ok(settings)
+}
+
+pub fn get_config<T>(key: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    SETTINGS
+        .read()
+        .map_err(|_| Error::ConfigLockFailed)?
+        .get::<T>(key)
+        .context(error::Config)
+}
+
+/// A section of the configuration
+pub trait ConfigElement: DeserializeOwned + Serialize {
+    const KEY: &'static str;
+
+    /// Check the values of the section and return a description of every invalid value
+    fn problems(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+pub fn get_config_element<T>() -> Result<T>
+where
+    T: ConfigElement,
+{
+    get_config(T::KEY)
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Web {
+    pub bind_address: SocketAddr,
+}
+
+impl ConfigElement for Web {
+    const KEY: &'static str = "web";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProjectService {
+    pub list_limit: usize,
+}
+
+impl ConfigElement for ProjectService {
+    const KEY: &'static str = "project_service";
+
+    fn problems(&self) -> Vec<String> {
+        if self.list_limit == 0 {
+            vec!["`list_limit` must be greater than zero".to_string()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Session {
+    pub ttl_seconds: u64,
+}
+
+impl Session {
+    /// The longest TTL, which keeps the expiration dates representable
+    const MAX_TTL_SECONDS: u64 = 100 * 365 * 24 * 60 * 60;
+
+    /// How long sessions are valid after their login or last refresh, if they expire
+    pub fn ttl(&self) -> Option<chrono::Duration> {
+        if self.ttl_seconds == 0 {
+            None
+        } else {
+            Some(chrono::Duration::seconds(
+                self.ttl_seconds.min(Self::MAX_TTL_SECONDS) as i64,
+            ))
+        }
+    }
+}
+
+impl ConfigElement for Session {
+    const KEY: &'static str = "session";
+
+    fn problems(&self) -> Vec<String> {
+        if self.ttl_seconds > Self::MAX_TTL_SECONDS {
+            vec![format!(
+                "`ttl_seconds` must be at most {}",
+                Self::MAX_TTL_SECONDS
+            )]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+}
+
+impl PasswordPolicy {
+    /// bcrypt ignores everything after the first 72 bytes of a password
+    pub const MAX_BYTES: usize = 72;
+}
+
+impl ConfigElement for PasswordPolicy {
+    const KEY: &'static str = "password_policy";
+
+    fn problems(&self) -> Vec<String> {
+        if self.min_length > Self::MAX_BYTES {
+            vec![format!(
+                "`min_length` must be at most {}, because longer passwords are rejected",
+                Self::MAX_BYTES
+            )]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Query {
+    pub timeout_seconds: u64,
+    pub seed: u64,
+}
+
+impl Query {
+    /// The query timeout, if it is enabled
+    pub fn timeout(&self) -> Option<Duration> {
+        if self.timeout_seconds == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(self.timeout_seconds))
+        }
+    }
+}
+
+impl ConfigElement for Query {
+    const KEY: &'static str = "query";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct QueryAdmission {
+    pub max_running_per_client: usize,
+    pub max_queued_per_client: usize,
+    pub retry_after_seconds: u64,
+}
+
+impl ConfigElement for QueryAdmission {
+    const KEY: &'static str = "query_admission";
+
+    fn problems(&self) -> Vec<String> {
+        if self.max_running_per_client == 0 {
+            vec!["`max_running_per_client` must be greater than zero".to_string()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Raster {
+    pub data_root: PathBuf,
+    pub definition_reload_interval_seconds: u64,
+    /// not reported when validating the configuration
+    #[serde(skip_serializing)]
+    pub admin_token: String,
+}
+
+impl ConfigElement for Raster {
+    const KEY: &'static str = "raster";
+
+    fn problems(&self) -> Vec<String> {
+        if self.data_root.is_dir() {
+            vec![]
+        } else {
+            vec![format!(
+                "`data_root` directory `{}` does not exist",
+                self.data_root.display()
+            )]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DatasetStatistics {
+    pub directory: PathBuf,
+    pub sample_size: usize,
+    pub bins: usize,
+    pub interval_seconds: u64,
+}
+
+impl ConfigElement for DatasetStatistics {
+    const KEY: &'static str = "dataset_statistics";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if self.sample_size == 0 {
+            problems.push("`sample_size` must be greater than zero".to_string());
+        }
+        if self.bins == 0 {
+            problems.push("`bins` must be greater than zero".to_string());
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PinnedDatasets {
+    pub file: PathBuf,
+    pub max_bytes: usize,
+    /// not reported when validating the configuration
+    #[serde(skip_serializing)]
+    pub admin_token: String,
+}
+
+impl ConfigElement for PinnedDatasets {
+    const KEY: &'static str = "pinned_datasets";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Upload {
+    pub directory: PathBuf,
+    pub max_file_size_bytes: u64,
+    pub user_quota_bytes: u64,
+    pub ttl_seconds: u64,
+    pub orphan_ttl_seconds: u64,
+    pub garbage_collection_interval_seconds: u64,
+}
+
+impl Upload {
+    /// The storage that the uploads of a user may occupy, if it is limited
+    pub fn quota(&self) -> Option<u64> {
+        if self.user_quota_bytes == 0 {
+            None
+        } else {
+            Some(self.user_quota_bytes)
+        }
+    }
+
+    /// How long uploads are kept, if they expire
+    pub fn ttl(&self) -> Option<Duration> {
+        if self.ttl_seconds == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(self.ttl_seconds))
+        }
+    }
+}
+
+impl ConfigElement for Upload {
+    const KEY: &'static str = "upload";
+
+    fn problems(&self) -> Vec<String> {
+        if self.max_file_size_bytes == 0 {
+            vec!["`max_file_size_bytes` must be greater than zero".to_string()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GdalDatasetPool {
+    pub max_files: usize,
+    pub handles_per_file: usize,
+}
+
+impl ConfigElement for GdalDatasetPool {
+    const KEY: &'static str = "gdal_dataset_pool";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RemoteSources {
+    pub max_retries: u32,
+    pub initial_backoff_milliseconds: u64,
+    pub max_backoff_milliseconds: u64,
+    pub failure_threshold: u32,
+    pub cool_down_seconds: u64,
+}
+
+impl ConfigElement for RemoteSources {
+    const KEY: &'static str = "remote_sources";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if self.failure_threshold == 0 {
+            problems.push("`failure_threshold` must be greater than zero".to_string());
+        }
+        if self.initial_backoff_milliseconds > self.max_backoff_milliseconds {
+            problems.push(
+                "`initial_backoff_milliseconds` must not exceed `max_backoff_milliseconds`"
+                    .to_string(),
+            );
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RRuntime {
+    pub executable: PathBuf,
+    pub timeout_seconds: u64,
+}
+
+impl RRuntime {
+    /// The runtime for `RScript` operators, if an executable is configured
+    pub fn runtime(&self) -> Option<Arc<geoengine_operators::util::r_runtime::RRuntime>> {
+        if self.executable.as_os_str().is_empty() {
+            return None;
+        }
+
+        Some(Arc::new(
+            geoengine_operators::util::r_runtime::RRuntime::new(
+                self.executable.clone(),
+                Duration::from_secs(self.timeout_seconds),
+            ),
+        ))
+    }
+}
+
+impl ConfigElement for RRuntime {
+    const KEY: &'static str = "r_runtime";
+
+    fn problems(&self) -> Vec<String> {
+        if self.timeout_seconds == 0 {
+            vec!["`timeout_seconds` must be greater than zero".to_string()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ShareLinks {
+    /// the key of the share link signatures, empty disables share links;
+    /// not reported when validating the configuration
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub max_valid_seconds: u64,
+}
+
+impl ConfigElement for ShareLinks {
+    const KEY: &'static str = "share_links";
+
+    fn problems(&self) -> Vec<String> {
+        if self.max_valid_seconds == 0 {
+            vec!["`max_valid_seconds` must be greater than zero".to_string()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Ogc {
+    /// interpret and write EPSG:4326 coordinates as lon/lat regardless of the service version
+    pub lon_lat_axis_order: bool,
+}
+
+impl ConfigElement for Ogc {
+    const KEY: &'static str = "ogc";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Wms {
+    pub supersampling: u32,
+    pub max_supersampling: u32,
+    /// the largest width and height of `GetMap` images
+    pub max_image_size: u32,
+    /// the credits for the `attribution` decoration, empty omits it
+    pub attribution: String,
+    /// the text of the `watermark` decoration, empty omits it
+    pub watermark: String,
+}
+
+impl ConfigElement for Wms {
+    const KEY: &'static str = "wms";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.max_supersampling == 0 {
+            problems.push("`max_supersampling` must be greater than zero".to_string());
+        }
+        if self.max_image_size == 0 {
+            problems.push("`max_image_size` must be greater than zero".to_string());
+        }
+        if self.supersampling == 0 || self.supersampling > self.max_supersampling {
+            problems
+                .push("`supersampling` must be between one and `max_supersampling`".to_string());
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Flight {
+    pub enabled: bool,
+    pub bind_address: SocketAddr,
+}
+
+impl ConfigElement for Flight {
+    const KEY: &'static str = "flight";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Grpc {
+    pub enabled: bool,
+    pub bind_address: SocketAddr,
+}
+
+impl ConfigElement for Grpc {
+    const KEY: &'static str = "grpc";
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Postgres {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub database: String,
+    pub user: String,
+    /// not reported when validating the configuration
+    #[serde(skip_serializing)]
+    pub password: String,
+    pub schema: String,
+}
+
+impl ConfigElement for Postgres {
+    const KEY: &'static str = "postgres";
+
+    fn problems(&self) -> Vec<String> {
+        // the schema is part of the connection options and of statements, so it is not quoted
+        let is_identifier = self
+            .schema
+            .chars()
+            .next()
+            .map_or(false, |first| first.is_ascii_alphabetic() || first == '_')
+            && self
+                .schema
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if is_identifier {
+            vec![]
+        } else {
+            vec!["`schema` must consist of letters, digits and underscores".to_string()]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Animation {
+    pub max_frames: usize,
+    pub frame_duration_milliseconds: u32,
+}
+
+impl ConfigElement for Animation {
+    const KEY: &'static str = "animation";
+
+    fn problems(&self) -> Vec<String> {
+        if self.max_frames == 0 {
+            vec!["`max_frames` must be greater than zero".to_string()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSinkType {
+    Memory,
+    File,
+    Stdout,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Audit {
+    pub sink: AuditSinkType,
+    pub memory_capacity: usize,
+    pub file: PathBuf,
+    /// not reported when validating the configuration
+    #[serde(skip_serializing)]
+    pub admin_token: String,
+}
+
+impl ConfigElement for Audit {
+    const KEY: &'static str = "audit";
+
+    fn problems(&self) -> Vec<String> {
+        if self.sink == AuditSinkType::Memory && self.memory_capacity == 0 {
+            vec!["`memory_capacity` must be greater than zero for the `memory` sink".to_string()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WorkflowStatistics {
+    /// not reported when validating the configuration
+    #[serde(skip_serializing)]
+    pub admin_token: String,
+}
+
+impl ConfigElement for WorkflowStatistics {
+    const KEY: &'static str = "workflow_statistics";
+}
+
+/// Validate all configuration sections and return a report of the effective configuration.
+///
+/// # Errors
+///
+/// Fails with a list of all problems if any section is missing, malformed or contains invalid values
+///
+pub fn validate() -> Result<String> {
+    let mut problems = Vec::new();
+    let mut report = String::new();
+
+    check_element::<Web>(&mut problems, &mut report);
+    check_element::<ProjectService>(&mut problems, &mut report);
+    check_element::<Session>(&mut problems, &mut report);
+    check_element::<PasswordPolicy>(&mut problems, &mut report);
+    check_element::<Query>(&mut problems, &mut report);
+    check_element::<QueryAdmission>(&mut problems, &mut report);
+    check_element::<Raster>(&mut problems, &mut report);
+    check_element::<DatasetStatistics>(&mut problems, &mut report);
+    check_element::<PinnedDatasets>(&mut problems, &mut report);
+    check_element::<Upload>(&mut problems, &mut report);
+    check_element::<GdalDatasetPool>(&mut problems, &mut report);
+    check_element::<RemoteSources>(&mut problems, &mut report);
+    check_element::<RRuntime>(&mut problems, &mut report);
+    check_element::<ShareLinks>(&mut problems, &mut report);
+    check_element::<Ogc>(&mut problems, &mut report);
+    check_element::<Wms>(&mut problems, &mut report);
+    check_element::<Animation>(&mut problems, &mut report);
+    check_element::<Flight>(&mut problems, &mut report);
+    check_element::<Grpc>(&mut problems, &mut report);
+    check_element::<Postgres>(&mut problems, &mut report);
+    check_element::<Audit>(&mut problems, &mut report);
+    check_element::<WorkflowStatistics>(&mut problems, &mut report);
+
+    if problems.is_empty() {
+        Ok(report)
+    } else {
+        Err(Error::InvalidConfiguration { problems })
+    }
+}
+
+fn check_element<T: ConfigElement>(problems: &mut Vec<String>, report: &mut String) {
+    let element = match get_config_element::<T>() {
+        Ok(element) => element,
+        Err(error) => {
+            problems.push(format!("[{}]: {}", T::KEY, error));
+            return;
+        }
+    };
+
+    problems.extend(
+        element
+            .problems()
+            .into_iter()
+            .map(|problem| format!("[{}]: {}", T::KEY, problem)),
+    );
+
+    report.push_str(&format!("[{}]\n", T::KEY));
+    if let Ok(serde_json::Value::Object(values)) = serde_json::to_value(&element) {
+        for (key, value) in values {
+            report.push_str(&format!("{} = {}\n", key, value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings_are_valid() {
+        let report = validate().unwrap();
+
+        assert!(report.contains("[web]\nbind_address = \"127.0.0.1:3030\"\n"));
+        assert!(report.contains("[project_service]\nlist_limit = 20\n"));
+        assert!(report.contains("[gdal_dataset_pool]\n"));
+        assert_eq!(
+            get_config_element::<Query>().unwrap().timeout(),
+            Some(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn layered_overrides() {
+        std::env::set_var("GEOENGINE_TEST_QUERY__TIMEOUT_SECONDS", "5");
+        std::env::set_var(
+            "GEOENGINE_TEST_QUERY_ADMISSION__MAX_RUNNING_PER_CLIENT",
+            "2",
+        );
+
+        let settings = layered_settings(
+            Some(File::from_str(
+                "[query]\ntimeout_seconds = 1\n[project_service]\nlist_limit = 3\n",
+                FileFormat::Toml,
+            )),
+            File::from_str("[project_service]\nlist_limit = 4\n", FileFormat::Toml),
+            Environment::with_prefix("GEOENGINE_TEST").separator("__"),
+        )
+        .unwrap();
+
+        let query: Query = settings.get(Query::KEY).unwrap();
+        assert_eq!(query.timeout_seconds, 5);
+
+        let admission: QueryAdmission = settings.get(QueryAdmission::KEY).unwrap();
+        assert_eq!(admission.max_running_per_client, 2);
+        assert_eq!(admission.max_queued_per_client, 16);
+
+        let project_service: ProjectService = settings.get(ProjectService::KEY).unwrap();
+        assert_eq!(project_service.list_limit, 4);
+    }
+
+    #[test]
+    fn problems() {
+        assert_eq!(ProjectService { list_limit: 0 }.problems().len(), 1);
+        assert_eq!(
+            Session {
+                ttl_seconds: u64::MAX
+            }
+            .problems()
+            .len(),
+            1
+        );
+        assert_eq!(
+            Raster {
+                data_root: "does/not/exist".into(),
+                definition_reload_interval_seconds: 0,
+                admin_token: String::new(),
+            }
+            .problems()
+            .len(),
+            1
+        );
+        assert_eq!(
+            RemoteSources {
+                max_retries: 3,
+                initial_backoff_milliseconds: 1000,
+                max_backoff_milliseconds: 100,
+                failure_threshold: 0,
+                cool_down_seconds: 30,
+            }
+            .problems()
+            .len(),
+            2
+        );
+        assert_eq!(
+            Wms {
+                supersampling: 8,
+                max_supersampling: 4,
+                max_image_size: 4096,
+                attribution: String::new(),
+                watermark: String::new(),
+            }
+            .problems()
+            .len(),
+            1
+        );
+        assert_eq!(
+            Postgres {
+                enabled: true,
+                host: "localhost".to_string(),
+                port: 5432,
+                database: "geoengine".to_string(),
+                user: "geoengine".to_string(),
+                password: "geoengine".to_string(),
+                schema: "public; DROP TABLE users".to_string(),
+            }
+            .problems()
+            .len(),
+            1
+        );
+    }
+}
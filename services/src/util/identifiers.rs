@@ -4,6 +4,9 @@ pub trait Identifier {
 
     /// Create identifier from given `id`
     fn from_uuid(id: uuid::Uuid) -> Self;
+
+    /// The underlying `Uuid`, e.g., for storing the identifier in a database
+    fn uuid(&self) -> uuid::Uuid;
 }
 
 #[macro_export]
@@ -24,6 +27,10 @@ macro_rules! identifier {
                     id: uuid::Uuid::new_v4(),
                 }
             }
+
+            fn uuid(&self) -> uuid::Uuid {
+                self.id
+            }
         }
 
         impl std::fmt::Display for $id_name {
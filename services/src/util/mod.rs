@@ -1,7 +1,14 @@
 use serde::de::Error;
 
+pub mod admission;
+pub mod audit;
+pub mod clock;
+pub mod config;
 #[macro_use]
 pub mod identifiers;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod test_harness;
 pub mod user_input;
 
 /// Serde deserializer <https://docs.rs/serde_qs/0.6.0/serde_qs/index.html#flatten-workaround>
@@ -0,0 +1,203 @@
+use crate::error;
+use crate::error::Result;
+use crate::util::config;
+use crate::util::config::ConfigElement;
+use r2d2_postgres::postgres::{Config, NoTls};
+use r2d2_postgres::r2d2::Pool;
+use r2d2_postgres::PostgresConnectionManager;
+use snafu::{ensure, ResultExt};
+
+pub type PostgresPool = Pool<PostgresConnectionManager<NoTls>>;
+
+/// The changes of the schema in the order of their application.
+/// Every migration is applied exactly once, so existing migrations must never be changed.
+const MIGRATIONS: &[&str] = &[
+    r#"
+    CREATE TABLE users (
+        id UUID PRIMARY KEY,
+        email TEXT NOT NULL UNIQUE,
+        password_hash TEXT NOT NULL,
+        real_name TEXT NOT NULL,
+        active BOOLEAN NOT NULL
+    );
+
+    CREATE TABLE sessions (
+        token UUID PRIMARY KEY,
+        user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        project UUID,
+        view JSONB
+    );
+
+    CREATE TABLE user_settings (
+        user_id UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+        settings JSONB NOT NULL
+    );
+
+    CREATE TABLE workflows (
+        id UUID PRIMARY KEY,
+        workflow JSONB NOT NULL,
+        public BOOLEAN NOT NULL
+    );
+
+    CREATE TABLE workflow_owners (
+        workflow_id UUID NOT NULL REFERENCES workflows (id) ON DELETE CASCADE,
+        user_id UUID NOT NULL,
+        PRIMARY KEY (workflow_id, user_id)
+    );
+
+    CREATE TABLE workflow_usage (
+        workflow_id UUID PRIMARY KEY REFERENCES workflows (id) ON DELETE CASCADE,
+        access_count BIGINT NOT NULL,
+        last_used TIMESTAMP WITH TIME ZONE NOT NULL,
+        total_execution_time_ms DOUBLE PRECISION NOT NULL
+    );
+"#,
+    r#"
+    ALTER TABLE sessions ADD COLUMN valid_until TIMESTAMP WITH TIME ZONE;
+"#,
+];
+
+/// Connect to the configured database and bring the tables of its schema up to date
+pub fn connect(config: &config::Postgres) -> Result<PostgresPool> {
+    // the schema is inserted into statements, so it must be a plain identifier
+    let problems = config.problems();
+    ensure!(
+        problems.is_empty(),
+        error::InvalidConfiguration { problems }
+    );
+
+    let mut postgres_config = Config::new();
+    postgres_config
+        .host(&config.host)
+        .port(config.port)
+        .dbname(&config.database)
+        .user(&config.user)
+        .password(&config.password)
+        .options(&format!("-c search_path={}", config.schema));
+
+    blocking(|| {
+        let pool = Pool::new(PostgresConnectionManager::new(postgres_config, NoTls))
+            .context(error::PostgresPool)?;
+
+        pool.get()
+            .context(error::PostgresPool)?
+            .batch_execute(&format!("CREATE SCHEMA IF NOT EXISTS {}", config.schema))
+            .context(error::Postgres)?;
+        migrate(&pool)?;
+
+        Ok(pool)
+    })
+}
+
+/// Apply the migrations that the database lacks
+fn migrate(pool: &PostgresPool) -> Result<()> {
+    let mut connection = pool.get().context(error::PostgresPool)?;
+    let mut transaction = connection.transaction().context(error::Postgres)?;
+
+    // concurrently starting servers wait for the first one to finish the migration
+    transaction
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL);
+             LOCK TABLE schema_version;",
+        )
+        .context(error::Postgres)?;
+
+    let applied: i32 = transaction
+        .query_one("SELECT COALESCE(MAX(version), 0) FROM schema_version", &[])
+        .context(error::Postgres)?
+        .get(0);
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(applied as usize) {
+        transaction
+            .batch_execute(migration)
+            .context(error::Postgres)?;
+        transaction
+            .execute(
+                "INSERT INTO schema_version (version) VALUES ($1)",
+                &[&(index as i32 + 1)],
+            )
+            .context(error::Postgres)?;
+    }
+
+    transaction.commit().context(error::Postgres)
+}
+
+/// Run a blocking database operation without stalling the other tasks of the async runtime
+pub fn blocking<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    tokio::task::block_in_place(f)
+}
+
+/// A fresh schema in the configured database for tests, which is dropped afterwards.
+///
+/// The tests that use it require a running Postgres and are ignored by default,
+/// run them with `cargo test --features postgres -- --ignored`.
+/// Database operations block in place, so they require the threaded scheduler of tokio.
+#[cfg(test)]
+pub(crate) struct TestSchema {
+    pub pool: PostgresPool,
+    schema: String,
+}
+
+#[cfg(test)]
+impl TestSchema {
+    pub fn new() -> Self {
+        let mut config = config::get_config_element::<config::Postgres>().unwrap();
+        config.schema = format!("test_{}", uuid::Uuid::new_v4().to_simple());
+
+        Self {
+            pool: connect(&config).unwrap(),
+            schema: config.schema,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestSchema {
+    fn drop(&mut self) {
+        if let Ok(mut connection) = self.pool.get() {
+            let _ = connection.batch_execute(&format!("DROP SCHEMA {} CASCADE", self.schema));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_schemas_that_are_no_identifiers() {
+        let mut config = config::get_config_element::<config::Postgres>().unwrap();
+        config.schema = "public; DROP TABLE users".to_string();
+
+        assert!(matches!(
+            connect(&config),
+            Err(error::Error::InvalidConfiguration { .. })
+        ));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    #[ignore]
+    async fn migrations_are_applied_once() {
+        let schema = TestSchema::new();
+
+        migrate(&schema.pool).unwrap();
+
+        let mut connection = schema.pool.get().unwrap();
+        let versions: Vec<i32> = connection
+            .query("SELECT version FROM schema_version ORDER BY version", &[])
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(versions, (1..=MIGRATIONS.len() as i32).collect::<Vec<_>>());
+
+        let users: i64 = connection
+            .query_one("SELECT COUNT(*) FROM users", &[])
+            .unwrap()
+            .get(0);
+        assert_eq!(users, 0);
+    }
+}
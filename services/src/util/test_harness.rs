@@ -0,0 +1,254 @@
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+use warp::hyper::body::Bytes;
+use warp::test::RequestBuilder;
+use warp::Filter;
+
+use crate::datasets::{load_dataset_definitions, SharedDatasetDefinitions};
+use crate::error::Result;
+use crate::handlers::handle_rejection;
+use crate::projects::hashmap_projectdb::HashMapProjectDB;
+use crate::server::api_filter;
+use crate::users::hashmap_userdb::HashMapUserDB;
+use crate::users::session::Session;
+use crate::users::user::{UserCredentials, UserRegistration};
+use crate::users::userdb::UserDB;
+use crate::util::user_input::UserInput;
+use crate::workflows::registry::{HashMapRegistry, WorkflowRegistry};
+use crate::workflows::workflow::{Workflow, WorkflowId};
+
+/// The complete HTTP API with in-memory backends for scenario tests that span several
+/// endpoints, e.g., registering a workflow, rendering it and exporting a project.
+///
+/// Requests are answered in-process without binding a port. The datasets are the definitions
+/// of the configured raster data root.
+///
+/// # Examples
+///
+/// ```rust
+/// use geoengine_services::util::test_harness::TestServer;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let server = TestServer::new().unwrap();
+/// let session = server.seed_user("foo@bar.de", "secret123").await.unwrap();
+///
+/// let res = server
+///     .reply(server.authenticated(&session).method("GET").path("/session"))
+///     .await;
+///
+/// assert_eq!(res.status(), 200);
+/// # }
+/// ```
+pub struct TestServer {
+    pub user_db: Arc<RwLock<HashMapUserDB>>,
+    pub project_db: Arc<RwLock<HashMapProjectDB>>,
+    pub workflow_registry: Arc<RwLock<HashMapRegistry>>,
+    pub dataset_definitions: SharedDatasetDefinitions,
+}
+
+impl TestServer {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            user_db: Default::default(),
+            project_db: Default::default(),
+            workflow_registry: Default::default(),
+            dataset_definitions: load_dataset_definitions()?,
+        })
+    }
+
+    /// Register a user with the real name `Test User` and return a session of it
+    pub async fn seed_user(&self, email: &str, password: &str) -> Result<Session> {
+        let user = UserRegistration {
+            email: email.to_string(),
+            password: password.to_string(),
+            real_name: "Test User".to_string(),
+        }
+        .validated()?;
+
+        let mut user_db = self.user_db.write().await;
+        user_db.register(user)?;
+        user_db.login(UserCredentials {
+            email: email.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    pub async fn seed_workflow(&self, workflow: Workflow) -> Result<WorkflowId> {
+        self.workflow_registry.write().await.register(workflow)
+    }
+
+    /// A request that is authenticated with the token of `session`
+    pub fn authenticated(&self, session: &Session) -> RequestBuilder {
+        warp::test::request().header("Authorization", session.token.to_string())
+    }
+
+    /// Answer the request with all handlers of the API, including the rejection handling
+    pub async fn reply(&self, request: RequestBuilder) -> warp::http::Response<Bytes> {
+        let filter = api_filter(
+            self.user_db.clone(),
+            self.project_db.clone(),
+            self.workflow_registry.clone(),
+            self.dataset_definitions.clone(),
+        )
+        .recover(handle_rejection);
+
+        request.reply(&filter).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projects::bundle::ProjectBundle;
+    use crate::projects::project::{
+        CreateProject, OrderBy, Project, ProjectFilter, ProjectId, ProjectListOptions,
+        ProjectListing, ProjectPermission, STRectangle, UpdateProject,
+    };
+    use geoengine_datatypes::primitives::Coordinate2D;
+    use geoengine_operators::engine::{TypedOperator, VectorOperator};
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+
+    #[tokio::test]
+    async fn register_render_and_export() {
+        let server = TestServer::new().unwrap();
+        let session = server.seed_user("foo@bar.de", "secret123").await.unwrap();
+
+        let workflow = Workflow {
+            operator: TypedOperator::Vector(
+                MockPointSource {
+                    params: MockPointSourceParams {
+                        points: vec![Coordinate2D::new(5., 5.)],
+                    },
+                }
+                .boxed(),
+            ),
+        };
+
+        let res = server
+            .reply(
+                warp::test::request()
+                    .method("POST")
+                    .path("/workflow/register")
+                    .json(&workflow),
+            )
+            .await;
+        assert_eq!(res.status(), 200);
+        let workflow_id: WorkflowId = serde_json::from_slice(res.body()).unwrap();
+
+        let res = server
+            .reply(warp::test::request().method("GET").path(&format!(
+                "/wms?request=GetMap&service=WMS&version=1.3.0&layers={}&bbox=0,0,10,10&width=10&height=10&crs=EPSG:4326&styles=&format=image/png",
+                workflow_id
+            )))
+            .await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["Content-Type"], "image/png");
+
+        let res = server
+            .reply(
+                server
+                    .authenticated(&session)
+                    .method("POST")
+                    .path("/project/create")
+                    .json(&CreateProject {
+                        name: "Test".to_string(),
+                        description: "Foo".to_string(),
+                        view: STRectangle::new(0., 0., 10., 10., 0, 1).unwrap(),
+                        bounds: STRectangle::new(0., 0., 10., 10., 0, 1).unwrap(),
+                    }),
+            )
+            .await;
+        assert_eq!(res.status(), 200);
+        let project_id: ProjectId = serde_json::from_slice(res.body()).unwrap();
+
+        let res = server
+            .reply(
+                server
+                    .authenticated(&session)
+                    .method("POST")
+                    .path("/project/export")
+                    .json(&project_id),
+            )
+            .await;
+        assert_eq!(res.status(), 200);
+        let _bundle: ProjectBundle = serde_json::from_slice(res.body()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn project_lifecycle() {
+        let server = TestServer::new().unwrap();
+        let session = server.seed_user("foo@bar.de", "secret123").await.unwrap();
+        let request = |path: &str| server.authenticated(&session).method("POST").path(path);
+
+        let res = server
+            .reply(request("/project/create").json(&CreateProject {
+                name: "Test".to_string(),
+                description: "Foo".to_string(),
+                view: STRectangle::new(0., 0., 10., 10., 0, 1).unwrap(),
+                bounds: STRectangle::new(0., 0., 10., 10., 0, 1).unwrap(),
+            }))
+            .await;
+        assert_eq!(res.status(), 200);
+        let project_id: ProjectId = serde_json::from_slice(res.body()).unwrap();
+
+        let res = server
+            .reply(request("/project/list").json(&ProjectListOptions {
+                permissions: vec![ProjectPermission::Owner],
+                filter: ProjectFilter::None,
+                order: OrderBy::NameAsc,
+                offset: 0,
+                limit: 10,
+            }))
+            .await;
+        assert_eq!(res.status(), 200);
+        let listing: Vec<ProjectListing> = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(listing.len(), 1);
+        assert_eq!(listing[0].id, project_id);
+
+        let res = server
+            .reply(request("/project/update").json(&UpdateProject {
+                id: project_id,
+                name: Some("Renamed".to_string()),
+                description: None,
+                layers: None,
+                view: Some(STRectangle::new(1., 1., 2., 2., 0, 1).unwrap()),
+                bounds: None,
+            }))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let res = server
+            .reply(request("/project/load").json(&project_id))
+            .await;
+        assert_eq!(res.status(), 200);
+        let project: Project = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(project.name, "Renamed");
+        assert_eq!(
+            project.view,
+            STRectangle::new(1., 1., 2., 2., 0, 1).unwrap()
+        );
+
+        let res = server
+            .reply(request("/project/delete").json(&project_id))
+            .await;
+        assert_eq!(res.status(), 200);
+
+        let res = server
+            .reply(request("/project/load").json(&project_id))
+            .await;
+        assert_ne!(res.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn rejects_unauthenticated_requests() {
+        let server = TestServer::new().unwrap();
+
+        let res = server
+            .reply(warp::test::request().method("GET").path("/session"))
+            .await;
+
+        assert_ne!(res.status(), 200);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::workflows::workflow::{Workflow, WorkflowId};
+use geoengine_operators::source::DatasetDefinitions;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Parameter values are shortened to at most this many characters
+const MAX_VALUE_LENGTH: usize = 60;
+
+/// Render a workflow as a Markdown list of its operators, where sources are nested below the
+/// operators that consume them, e.g.,
+///
+/// ```text
+/// # Raster workflow `…`
+///
+/// - **Expression** (raster): expression `A+B`, output_type `F32`
+///   - **GdalSource** (raster): channel `1`, dataset_id `ndvi` (U8 raster dataset)
+/// ```
+///
+/// Scalar parameters are listed with their values, lists and objects are only summarized.
+pub fn describe_workflow(
+    id: &WorkflowId,
+    workflow: &Workflow,
+    dataset_definitions: &DatasetDefinitions,
+) -> String {
+    let value = serde_json::to_value(workflow).unwrap_or(Value::Null);
+    let kind = value
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or("Unknown");
+
+    let mut description = format!("# {} workflow `{}`\n\n", kind, id);
+    if let Some(operator) = value.get("operator") {
+        describe_operator(
+            operator,
+            &kind.to_lowercase(),
+            0,
+            dataset_definitions,
+            &mut description,
+        );
+    }
+    description
+}
+
+fn describe_operator(
+    operator: &Value,
+    kind: &str,
+    depth: usize,
+    dataset_definitions: &DatasetDefinitions,
+    description: &mut String,
+) {
+    let name = operator
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or("Unknown");
+
+    let parameters: Vec<String> = operator
+        .get("params")
+        .and_then(Value::as_object)
+        .map(|params| {
+            params
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(parameter, value)| {
+                    let mut text = format!("{} {}", parameter, describe_value(value));
+                    if name == "GdalSource" && parameter == "dataset_id" {
+                        text.push_str(&describe_dataset(value, dataset_definitions));
+                    }
+                    text
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let _ = write!(
+        description,
+        "{}- **{}** ({})",
+        "  ".repeat(depth),
+        name,
+        kind
+    );
+    if !parameters.is_empty() {
+        let _ = write!(description, ": {}", parameters.join(", "));
+    }
+    description.push('\n');
+
+    for (field, source_kind) in &[("raster_sources", "raster"), ("vector_sources", "vector")] {
+        if let Some(Value::Array(sources)) = operator.get(*field) {
+            for source in sources {
+                describe_operator(
+                    source,
+                    source_kind,
+                    depth + 1,
+                    dataset_definitions,
+                    description,
+                );
+            }
+        }
+    }
+}
+
+fn describe_value(value: &Value) -> String {
+    let text = match value {
+        Value::String(string) => string.clone(),
+        Value::Array(values) if values.len() == 1 => return "[1 item]".to_string(),
+        Value::Array(values) => return format!("[{} items]", values.len()),
+        Value::Object(_) => return "{…}".to_string(),
+        _ => value.to_string(),
+    };
+
+    if text.chars().count() > MAX_VALUE_LENGTH {
+        let shortened: String = text.chars().take(MAX_VALUE_LENGTH).collect();
+        format!("`{}…`", shortened)
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+fn describe_dataset(id: &Value, dataset_definitions: &DatasetDefinitions) -> String {
+    match id.as_str().and_then(|id| dataset_definitions.get(id)) {
+        Some(information) => format!(" ({:?} raster dataset)", information.data_type),
+        None => " (unknown dataset)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::identifiers::Identifier;
+    use geoengine_operators::engine::{RasterOperator, TypedOperator, VectorOperator};
+    use geoengine_operators::mock::{
+        MockPointSource, MockPointSourceParams, MockRasterPointJoinOperator,
+        MockRasterPointJoinParams,
+    };
+    use geoengine_operators::source::{GdalSource, GdalSourceParameters};
+
+    #[test]
+    fn describe() {
+        let dataset_definitions =
+            DatasetDefinitions::load("../operators/test-data/raster".as_ref()).0;
+
+        let workflow = Workflow {
+            operator: TypedOperator::Vector(
+                MockRasterPointJoinOperator {
+                    params: MockRasterPointJoinParams {
+                        feature_name: "ndvi".to_string(),
+                    },
+                    raster_sources: vec![
+                        GdalSource {
+                            params: GdalSourceParameters {
+                                dataset_id: "test".to_string(),
+                                channel: None,
+                                band: None,
+                            },
+                        }
+                        .boxed(),
+                        GdalSource {
+                            params: GdalSourceParameters {
+                                dataset_id: "missing".to_string(),
+                                channel: Some(2),
+                                band: None,
+                            },
+                        }
+                        .boxed(),
+                    ],
+                    vector_sources: vec![MockPointSource {
+                        params: MockPointSourceParams {
+                            points: vec![(0.0, 0.1).into(), (1.0, 1.1).into()],
+                        },
+                    }
+                    .boxed()],
+                }
+                .boxed(),
+            ),
+        };
+        let id = WorkflowId::new();
+
+        assert_eq!(
+            describe_workflow(&id, &workflow, &dataset_definitions),
+            format!(
+                "# Vector workflow `{}`\n\
+                 \n\
+                 - **MockRasterPointJoinOperator** (vector): feature_name `ndvi`\n  \
+                 - **GdalSource** (raster): dataset_id `test` (U8 raster dataset)\n  \
+                 - **GdalSource** (raster): channel `2`, dataset_id `missing` (unknown dataset)\n  \
+                 - **MockPointSource** (vector): points [2 items]\n",
+                id
+            )
+        );
+    }
+}
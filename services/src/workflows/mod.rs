@@ -1,2 +1,7 @@
+pub mod description;
+#[cfg(feature = "postgres")]
+pub mod postgres_registry;
 pub mod registry;
+pub mod resolver;
+pub mod share_link;
 pub mod workflow;
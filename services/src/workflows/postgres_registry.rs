@@ -0,0 +1,321 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use postgres_types::Json;
+use snafu::{ensure, ResultExt};
+use uuid::Uuid;
+
+use super::registry::{WorkflowRegistry, WorkflowStatistics};
+use super::workflow::{Workflow, WorkflowId};
+use crate::error;
+use crate::error::Result;
+use crate::users::user::UserId;
+use crate::util::clock::{Clock, SystemClock};
+use crate::util::identifiers::Identifier;
+use crate::util::postgres::{blocking, PostgresPool};
+
+/// A `WorkflowRegistry` that keeps workflows, their access and their usage in Postgres, so that
+/// they survive restarts
+pub struct PostgresWorkflowRegistry {
+    pool: PostgresPool,
+    clock: Arc<dyn Clock>,
+}
+
+impl PostgresWorkflowRegistry {
+    /// A registry in the tables of a pool that was created by `util::postgres::connect`
+    pub fn new(pool: PostgresPool) -> Self {
+        Self::with_clock(pool, Arc::new(SystemClock))
+    }
+
+    /// A registry that timestamps the usage of workflows with the `clock`
+    pub fn with_clock(pool: PostgresPool, clock: Arc<dyn Clock>) -> Self {
+        Self { pool, clock }
+    }
+}
+
+impl WorkflowRegistry for PostgresWorkflowRegistry {
+    fn register(&mut self, workflow: Workflow) -> Result<WorkflowId> {
+        let id = WorkflowId::from_hash(&workflow);
+
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            connection
+                .execute(
+                    "INSERT INTO workflows (id, workflow, public) VALUES ($1, $2, TRUE)
+                     ON CONFLICT (id) DO NOTHING",
+                    &[&id.uuid(), &Json(&workflow)],
+                )
+                .context(error::Postgres)?;
+
+            Ok(id)
+        })
+    }
+
+    fn register_private(&mut self, workflow: Workflow, owner: UserId) -> Result<WorkflowId> {
+        let id = WorkflowId::from_private_hash(&workflow, owner);
+
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let mut transaction = connection.transaction().context(error::Postgres)?;
+
+            transaction
+                .execute(
+                    "INSERT INTO workflows (id, workflow, public) VALUES ($1, $2, FALSE)
+                     ON CONFLICT (id) DO NOTHING",
+                    &[&id.uuid(), &Json(&workflow)],
+                )
+                .context(error::Postgres)?;
+            transaction
+                .execute(
+                    "INSERT INTO workflow_owners (workflow_id, user_id) VALUES ($1, $2)
+                     ON CONFLICT DO NOTHING",
+                    &[&id.uuid(), &owner.uuid()],
+                )
+                .context(error::Postgres)?;
+
+            transaction.commit().context(error::Postgres)?;
+            Ok(id)
+        })
+    }
+
+    fn owner(&self, id: &WorkflowId) -> Result<Option<UserId>> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let row = connection
+                .query_opt(
+                    "SELECT (SELECT user_id FROM workflow_owners WHERE workflow_id = $1 LIMIT 1)
+                     FROM workflows WHERE id = $1",
+                    &[&id.uuid()],
+                )
+                .context(error::Postgres)?
+                .ok_or(error::Error::NoWorkflowForGivenId)?;
+
+            Ok(row.get::<_, Option<Uuid>>(0).map(UserId::from_uuid))
+        })
+    }
+
+    fn check_access(&self, id: &WorkflowId, user: Option<UserId>) -> Result<()> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let row = connection
+                .query_opt(
+                    "SELECT public, EXISTS (
+                         SELECT 1 FROM workflow_owners WHERE workflow_id = $1 AND user_id = $2
+                     )
+                     FROM workflows WHERE id = $1",
+                    &[&id.uuid(), &user.map(|user| user.uuid())],
+                )
+                .context(error::Postgres)?
+                .ok_or(error::Error::NoWorkflowForGivenId)?;
+
+            let (public, owner): (bool, bool) = (row.get(0), row.get(1));
+            ensure!(
+                public || owner,
+                error::WorkflowAccessDenied { workflow_id: *id }
+            );
+            Ok(())
+        })
+    }
+
+    fn load(&self, id: &WorkflowId) -> Result<Workflow> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let row = connection
+                .query_opt(
+                    "SELECT workflow FROM workflows WHERE id = $1",
+                    &[&id.uuid()],
+                )
+                .context(error::Postgres)?
+                .ok_or(error::Error::NoWorkflowForGivenId)?;
+
+            Ok(row.get::<_, Json<Workflow>>(0).0)
+        })
+    }
+
+    fn record_usage(&mut self, id: &WorkflowId, execution_time: Duration) -> Result<()> {
+        let now = self.clock.now();
+
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let recorded = connection
+                .execute(
+                    "INSERT INTO workflow_usage
+                         (workflow_id, access_count, last_used, total_execution_time_ms)
+                     SELECT id, 1, $2, $3 FROM workflows WHERE id = $1
+                     ON CONFLICT (workflow_id) DO UPDATE SET
+                         access_count = workflow_usage.access_count + 1,
+                         last_used = EXCLUDED.last_used,
+                         total_execution_time_ms =
+                             workflow_usage.total_execution_time_ms
+                             + EXCLUDED.total_execution_time_ms",
+                    &[&id.uuid(), &now, &(execution_time.as_secs_f64() * 1000.)],
+                )
+                .context(error::Postgres)?;
+
+            ensure!(recorded == 1, error::NoWorkflowForGivenId);
+            Ok(())
+        })
+    }
+
+    fn statistics(&self) -> Result<Vec<WorkflowStatistics>> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let rows = connection
+                .query(
+                    "SELECT id, access_count, last_used, total_execution_time_ms
+                     FROM workflows LEFT JOIN workflow_usage ON id = workflow_id
+                     ORDER BY access_count DESC NULLS LAST, last_used DESC NULLS LAST",
+                    &[],
+                )
+                .context(error::Postgres)?;
+
+            Ok(rows
+                .iter()
+                .map(|row| {
+                    let access_count: Option<i64> = row.get(1);
+                    let total_execution_time_ms: Option<f64> = row.get(3);
+
+                    WorkflowStatistics {
+                        workflow: WorkflowId::from_uuid(row.get(0)),
+                        access_count: access_count.unwrap_or_default() as u64,
+                        last_used: row.get::<_, Option<DateTime<Utc>>>(2),
+                        average_execution_time_ms: access_count.and_then(|count| {
+                            total_execution_time_ms.map(|total| total / count as f64)
+                        }),
+                    }
+                })
+                .collect())
+        })
+    }
+
+    fn list(&self) -> Result<Vec<WorkflowId>> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            let rows = connection
+                .query("SELECT id FROM workflows", &[])
+                .context(error::Postgres)?;
+
+            Ok(rows
+                .iter()
+                .map(|row| WorkflowId::from_uuid(row.get::<_, Uuid>(0)))
+                .collect())
+        })
+    }
+
+    fn remove(&mut self, id: &WorkflowId) -> Result<()> {
+        blocking(|| {
+            let mut connection = self.pool.get().context(error::PostgresPool)?;
+            // the access and the usage are removed by cascading
+            let removed = connection
+                .execute("DELETE FROM workflows WHERE id = $1", &[&id.uuid()])
+                .context(error::Postgres)?;
+
+            ensure!(removed == 1, error::NoWorkflowForGivenId);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::MockClock;
+    use crate::util::postgres::TestSchema;
+    use chrono::TimeZone;
+    use geoengine_operators::engine::VectorOperator;
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+
+    fn workflow(x: f64) -> Workflow {
+        Workflow {
+            operator: MockPointSource {
+                params: MockPointSourceParams {
+                    points: vec![(x, 0.).into()],
+                },
+            }
+            .boxed()
+            .into(),
+        }
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    #[ignore]
+    async fn access() {
+        let schema = TestSchema::new();
+        let mut registry = PostgresWorkflowRegistry::new(schema.pool.clone());
+        let (owner, other) = (UserId::new(), UserId::new());
+
+        let public = registry.register(workflow(0.)).unwrap();
+        let private = registry.register_private(workflow(1.), owner).unwrap();
+
+        assert!(registry.check_access(&public, None).is_ok());
+        assert!(registry.check_access(&private, Some(owner)).is_ok());
+        assert!(registry.check_access(&private, Some(other)).is_err());
+        assert!(registry.check_access(&private, None).is_err());
+
+        assert_eq!(registry.owner(&private).unwrap(), Some(owner));
+        assert_eq!(registry.owner(&public).unwrap(), None);
+
+        // registering the same workflow does not grant access to the owner's id
+        let others = registry.register_private(workflow(1.), other).unwrap();
+        assert_ne!(others, private);
+        assert!(registry.check_access(&private, Some(other)).is_err());
+        let republished = registry.register(workflow(1.)).unwrap();
+        assert_ne!(republished, private);
+        assert!(registry.check_access(&republished, None).is_ok());
+        assert!(registry.check_access(&private, None).is_err());
+
+        // a new registry on the same tables, e.g., after a restart
+        let mut registry = PostgresWorkflowRegistry::new(schema.pool.clone());
+        assert_eq!(
+            serde_json::to_string(&registry.load(&public).unwrap()).unwrap(),
+            serde_json::to_string(&workflow(0.)).unwrap()
+        );
+
+        registry.remove(&public).unwrap();
+        assert!(registry.load(&public).is_err());
+        assert!(registry.check_access(&public, None).is_err());
+        assert!(registry.remove(&public).is_err());
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    #[ignore]
+    async fn statistics() {
+        let schema = TestSchema::new();
+        let clock = MockClock::new(Utc.ymd(2020, 1, 1).and_hms(0, 0, 0));
+        let mut registry =
+            PostgresWorkflowRegistry::with_clock(schema.pool.clone(), Arc::new(clock.clone()));
+
+        let unused = registry.register(workflow(0.)).unwrap();
+        let used = registry.register(workflow(1.)).unwrap();
+
+        registry
+            .record_usage(&used, Duration::from_millis(100))
+            .unwrap();
+        clock.advance(chrono::Duration::minutes(1));
+        registry
+            .record_usage(&used, Duration::from_millis(300))
+            .unwrap();
+        assert!(registry
+            .record_usage(&WorkflowId::new(), Duration::from_millis(1))
+            .is_err());
+
+        assert_eq!(
+            registry.statistics().unwrap(),
+            vec![
+                WorkflowStatistics {
+                    workflow: used,
+                    access_count: 2,
+                    last_used: Some(Utc.ymd(2020, 1, 1).and_hms(0, 1, 0)),
+                    average_execution_time_ms: Some(200.),
+                },
+                WorkflowStatistics {
+                    workflow: unused,
+                    access_count: 0,
+                    last_used: None,
+                    average_execution_time_ms: None,
+                },
+            ]
+        );
+    }
+}
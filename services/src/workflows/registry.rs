@@ -1,30 +1,313 @@
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 use super::workflow::{Workflow, WorkflowId};
 use crate::error;
 use crate::error::Result;
+use crate::users::user::UserId;
+use crate::util::clock::{Clock, SystemClock};
 
 pub trait WorkflowRegistry: Send + Sync {
+    /// Register a `workflow` that every client may query
     fn register(&mut self, workflow: Workflow) -> Result<WorkflowId>;
     fn load(&self, id: &WorkflowId) -> Result<Workflow>;
+
+    /// Register a `workflow` that only the `owner` may query.
+    /// Its id is derived from the owner, so other users who register the same workflow, publicly
+    /// or privately, get a different id.
+    fn register_private(&mut self, workflow: Workflow, owner: UserId) -> Result<WorkflowId>;
+
+    /// The owner of the private workflow `id`, `None` if it is public
+    fn owner(&self, id: &WorkflowId) -> Result<Option<UserId>>;
+
+    /// Check whether the `user`, or an anonymous client if there is none, may query the workflow `id`
+    fn check_access(&self, id: &WorkflowId, user: Option<UserId>) -> Result<()>;
+
+    /// Record a successful query of the workflow `id` that took `execution_time`
+    fn record_usage(&mut self, id: &WorkflowId, execution_time: Duration) -> Result<()>;
+
+    /// The usage statistics of all registered workflows, including unused ones
+    fn statistics(&self) -> Result<Vec<WorkflowStatistics>>;
+
+    /// The ids of all registered workflows
+    fn list(&self) -> Result<Vec<WorkflowId>>;
+
+    /// Unregister the workflow `id` together with its access and usage
+    fn remove(&mut self, id: &WorkflowId) -> Result<()>;
+}
+
+/// How often and how recently a workflow was queried
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStatistics {
+    pub workflow: WorkflowId,
+    pub access_count: u64,
+    pub last_used: Option<DateTime<Utc>>,
+    pub average_execution_time_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct WorkflowUsage {
+    access_count: u64,
+    last_used: DateTime<Utc>,
+    total_execution_time: Duration,
+}
+
+/// Who may query a registered workflow
+#[derive(Debug, Clone, Copy)]
+enum WorkflowAccess {
+    Public,
+    Private(UserId),
 }
 
-#[derive(Default)]
 pub struct HashMapRegistry {
     map: HashMap<WorkflowId, Workflow>,
+    usage: HashMap<WorkflowId, WorkflowUsage>,
+    access: HashMap<WorkflowId, WorkflowAccess>,
+    clock: Arc<dyn Clock>,
+}
+
+impl HashMapRegistry {
+    /// A registry that timestamps the usage of workflows with the `clock`
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            map: HashMap::new(),
+            usage: HashMap::new(),
+            access: HashMap::new(),
+            clock,
+        }
+    }
+}
+
+impl Default for HashMapRegistry {
+    fn default() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
 }
 
 impl WorkflowRegistry for HashMapRegistry {
     fn register(&mut self, workflow: Workflow) -> Result<WorkflowId> {
         let id = WorkflowId::from_hash(&workflow);
         self.map.insert(id, workflow);
+        self.access.insert(id, WorkflowAccess::Public);
+        Ok(id)
+    }
+
+    fn register_private(&mut self, workflow: Workflow, owner: UserId) -> Result<WorkflowId> {
+        let id = WorkflowId::from_private_hash(&workflow, owner);
+        self.map.insert(id, workflow);
+        self.access.insert(id, WorkflowAccess::Private(owner));
         Ok(id)
     }
 
+    fn owner(&self, id: &WorkflowId) -> Result<Option<UserId>> {
+        match self.access.get(id) {
+            Some(WorkflowAccess::Public) => Ok(None),
+            Some(WorkflowAccess::Private(owner)) => Ok(Some(*owner)),
+            None => Err(error::Error::NoWorkflowForGivenId),
+        }
+    }
+
+    fn check_access(&self, id: &WorkflowId, user: Option<UserId>) -> Result<()> {
+        match (self.access.get(id), user) {
+            (None, _) => Err(error::Error::NoWorkflowForGivenId),
+            (Some(WorkflowAccess::Public), _) => Ok(()),
+            (Some(WorkflowAccess::Private(owner)), Some(user)) if *owner == user => Ok(()),
+            _ => Err(error::Error::WorkflowAccessDenied { workflow_id: *id }),
+        }
+    }
+
     fn load(&self, id: &WorkflowId) -> Result<Workflow> {
         self.map
             .get(&id)
             .cloned()
             .ok_or(error::Error::NoWorkflowForGivenId)
     }
+
+    fn record_usage(&mut self, id: &WorkflowId, execution_time: Duration) -> Result<()> {
+        if !self.map.contains_key(id) {
+            return Err(error::Error::NoWorkflowForGivenId);
+        }
+
+        let now = self.clock.now();
+        let usage = self.usage.entry(*id).or_insert(WorkflowUsage {
+            access_count: 0,
+            last_used: now,
+            total_execution_time: Duration::default(),
+        });
+        usage.access_count += 1;
+        usage.last_used = now;
+        usage.total_execution_time += execution_time;
+
+        Ok(())
+    }
+
+    fn statistics(&self) -> Result<Vec<WorkflowStatistics>> {
+        let mut statistics: Vec<WorkflowStatistics> = self
+            .map
+            .keys()
+            .map(|id| match self.usage.get(id) {
+                Some(usage) => WorkflowStatistics {
+                    workflow: *id,
+                    access_count: usage.access_count,
+                    last_used: Some(usage.last_used),
+                    average_execution_time_ms: Some(
+                        usage.total_execution_time.as_secs_f64() * 1000.
+                            / usage.access_count as f64,
+                    ),
+                },
+                None => WorkflowStatistics {
+                    workflow: *id,
+                    access_count: 0,
+                    last_used: None,
+                    average_execution_time_ms: None,
+                },
+            })
+            .collect();
+
+        // the most used workflows first
+        statistics.sort_by(|a, b| {
+            b.access_count
+                .cmp(&a.access_count)
+                .then_with(|| b.last_used.cmp(&a.last_used))
+        });
+
+        Ok(statistics)
+    }
+
+    fn list(&self) -> Result<Vec<WorkflowId>> {
+        Ok(self.map.keys().copied().collect())
+    }
+
+    fn remove(&mut self, id: &WorkflowId) -> Result<()> {
+        self.map
+            .remove(id)
+            .ok_or(error::Error::NoWorkflowForGivenId)?;
+        self.access.remove(id);
+        self.usage.remove(id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::MockClock;
+    use crate::util::identifiers::Identifier;
+    use chrono::TimeZone;
+    use geoengine_operators::engine::VectorOperator;
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+
+    fn workflow(x: f64) -> Workflow {
+        Workflow {
+            operator: MockPointSource {
+                params: MockPointSourceParams {
+                    points: vec![(x, 0.).into()],
+                },
+            }
+            .boxed()
+            .into(),
+        }
+    }
+
+    #[test]
+    fn access() {
+        let mut registry = HashMapRegistry::default();
+        let owner = UserId::new();
+        let other = UserId::new();
+
+        let private = registry.register_private(workflow(1.), owner).unwrap();
+        assert!(registry.check_access(&private, Some(owner)).is_ok());
+        assert!(registry.check_access(&private, Some(other)).is_err());
+        assert!(registry.check_access(&private, None).is_err());
+        assert_eq!(registry.owner(&private).unwrap(), Some(owner));
+
+        // registering the same workflow does not grant access to the owner's id
+        let others = registry.register_private(workflow(1.), other).unwrap();
+        assert_ne!(others, private);
+        assert!(registry.check_access(&others, Some(other)).is_ok());
+        assert!(registry.check_access(&private, Some(other)).is_err());
+
+        let public = registry.register(workflow(1.)).unwrap();
+        assert_ne!(public, private);
+        assert!(registry.check_access(&public, None).is_ok());
+        assert!(registry.check_access(&public, Some(other)).is_ok());
+        assert_eq!(registry.owner(&public).unwrap(), None);
+        assert!(registry.check_access(&private, None).is_err());
+
+        assert!(matches!(
+            registry.check_access(&WorkflowId::new(), None),
+            Err(error::Error::NoWorkflowForGivenId)
+        ));
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn statistics() {
+        let mut registry = HashMapRegistry::default();
+        let used = registry.register(workflow(1.)).unwrap();
+        let unused = registry.register(workflow(2.)).unwrap();
+
+        registry
+            .record_usage(&used, Duration::from_millis(10))
+            .unwrap();
+        registry
+            .record_usage(&used, Duration::from_millis(30))
+            .unwrap();
+        assert!(registry
+            .record_usage(&WorkflowId::new(), Duration::from_millis(10))
+            .is_err());
+
+        let statistics = registry.statistics().unwrap();
+        assert_eq!(statistics.len(), 2);
+
+        assert_eq!(statistics[0].workflow, used);
+        assert_eq!(statistics[0].access_count, 2);
+        assert!(statistics[0].last_used.is_some());
+        assert_eq!(statistics[0].average_execution_time_ms, Some(20.));
+
+        assert_eq!(
+            statistics[1],
+            WorkflowStatistics {
+                workflow: unused,
+                access_count: 0,
+                last_used: None,
+                average_execution_time_ms: None,
+            }
+        );
+    }
+
+    #[test]
+    fn statistics_of_equally_used_workflows() {
+        let clock = MockClock::new(Utc.ymd(2020, 1, 1).and_hms(0, 0, 0));
+        let mut registry = HashMapRegistry::with_clock(Arc::new(clock.clone()));
+
+        let earlier = registry.register(workflow(1.)).unwrap();
+        let later = registry.register(workflow(2.)).unwrap();
+
+        registry
+            .record_usage(&earlier, Duration::from_millis(10))
+            .unwrap();
+        clock.advance(chrono::Duration::minutes(5));
+        registry
+            .record_usage(&later, Duration::from_millis(10))
+            .unwrap();
+
+        // the most recently used first
+        let statistics = registry.statistics().unwrap();
+        assert_eq!(statistics[0].workflow, later);
+        assert_eq!(
+            statistics[0].last_used,
+            Some(Utc.ymd(2020, 1, 1).and_hms(0, 5, 0))
+        );
+        assert_eq!(statistics[1].workflow, earlier);
+        assert_eq!(
+            statistics[1].last_used,
+            Some(Utc.ymd(2020, 1, 1).and_hms(0, 0, 0))
+        );
+    }
 }
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use geoengine_operators::engine::{TypedOperator, WorkflowResolver};
+use geoengine_operators::source::referenced_workflows;
+use uuid::Uuid;
+
+use super::registry::WorkflowRegistry;
+use super::workflow::WorkflowId;
+use crate::handlers::Requester;
+use crate::util::identifiers::Identifier;
+
+/// The workflows that an operator references through `WorkflowSource` operators, directly or
+/// indirectly, as they were registered when the snapshot was taken.
+///
+/// Registries are locked asynchronously, so the references are collected before the operator is
+/// initialized instead of looking them up during initialization.
+#[derive(Debug, Default)]
+pub struct WorkflowSnapshot {
+    workflows: HashMap<String, TypedOperator>,
+}
+
+impl WorkflowSnapshot {
+    /// Collect the workflows that `operator` references from the `registry`.
+    /// Unknown references and references that the `requester` may not query are left out and fail
+    /// when the operator is initialized.
+    pub fn collect<T>(registry: &T, requester: &Requester, operator: &TypedOperator) -> Self
+    where
+        T: WorkflowRegistry + ?Sized,
+    {
+        Self::collect_references(registry, requester, referenced_workflows(operator))
+    }
+
+    /// Collect the workflows with the ids `references` and the workflows they reference
+    pub fn collect_references<T>(
+        registry: &T,
+        requester: &Requester,
+        references: Vec<String>,
+    ) -> Self
+    where
+        T: WorkflowRegistry + ?Sized,
+    {
+        let mut workflows = HashMap::new();
+        let mut pending = references;
+
+        while let Some(id) = pending.pop() {
+            if workflows.contains_key(&id) {
+                continue;
+            }
+
+            let workflow = match Uuid::parse_str(&id)
+                .ok()
+                .map(WorkflowId::from_uuid)
+                .filter(|id| requester.check_reference_access(registry, id).is_ok())
+                .and_then(|id| registry.load(&id).ok())
+            {
+                Some(workflow) => workflow,
+                None => continue,
+            };
+
+            pending.extend(referenced_workflows(&workflow.operator));
+            workflows.insert(id, workflow.operator);
+        }
+
+        Self { workflows }
+    }
+}
+
+impl WorkflowResolver for WorkflowSnapshot {
+    fn resolve(&self, id: &str) -> geoengine_operators::util::Result<TypedOperator> {
+        self.workflows.get(id).cloned().ok_or_else(|| {
+            geoengine_operators::error::Error::UnresolvableWorkflow {
+                workflow: id.to_string(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::users::session::{Session, SessionToken};
+    use crate::users::user::UserId;
+    use crate::workflows::registry::HashMapRegistry;
+    use crate::workflows::workflow::Workflow;
+    use geoengine_datatypes::primitives::Coordinate2D;
+    use geoengine_operators::engine::VectorOperator;
+    use geoengine_operators::mock::{MockPointSource, MockPointSourceParams};
+    use geoengine_operators::source::{WorkflowSource, WorkflowSourceParameters};
+
+    fn reference(id: &WorkflowId) -> TypedOperator {
+        TypedOperator::Vector(VectorOperator::boxed(WorkflowSource {
+            params: WorkflowSourceParameters {
+                workflow: id.to_string(),
+            },
+        }))
+    }
+
+    #[test]
+    fn collects_transitive_references() {
+        let mut registry = HashMapRegistry::default();
+
+        let points = registry
+            .register(Workflow {
+                operator: TypedOperator::Vector(
+                    MockPointSource {
+                        params: MockPointSourceParams {
+                            points: vec![Coordinate2D::new(1., 2.)],
+                        },
+                    }
+                    .boxed(),
+                ),
+            })
+            .unwrap();
+        let alias = registry
+            .register(Workflow {
+                operator: reference(&points),
+            })
+            .unwrap();
+
+        let snapshot =
+            WorkflowSnapshot::collect(&registry, &Requester::Anonymous, &reference(&alias));
+
+        assert!(snapshot.resolve(&alias.to_string()).is_ok());
+        assert!(snapshot.resolve(&points.to_string()).is_ok());
+        assert!(snapshot.resolve(&WorkflowId::new().to_string()).is_err());
+    }
+
+    #[test]
+    fn leaves_out_inaccessible_references() {
+        let mut registry = HashMapRegistry::default();
+        let owner = UserId::new();
+
+        let points = registry
+            .register_private(
+                Workflow {
+                    operator: TypedOperator::Vector(
+                        MockPointSource {
+                            params: MockPointSourceParams {
+                                points: vec![Coordinate2D::new(1., 2.)],
+                            },
+                        }
+                        .boxed(),
+                    ),
+                },
+                owner,
+            )
+            .unwrap();
+        let alias = registry
+            .register(Workflow {
+                operator: reference(&points),
+            })
+            .unwrap();
+
+        // the public alias does not grant access to the private workflow it references
+        let snapshot =
+            WorkflowSnapshot::collect(&registry, &Requester::Anonymous, &reference(&alias));
+        assert!(snapshot.resolve(&alias.to_string()).is_ok());
+        assert!(snapshot.resolve(&points.to_string()).is_err());
+
+        let snapshot = WorkflowSnapshot::collect(
+            &registry,
+            &Requester::Session(Session {
+                user: owner,
+                token: SessionToken::default(),
+                project: None,
+                view: None,
+                valid_until: None,
+            }),
+            &reference(&alias),
+        );
+        assert!(snapshot.resolve(&points.to_string()).is_ok());
+    }
+}
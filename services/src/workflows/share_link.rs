@@ -0,0 +1,198 @@
+use chrono::{DateTime, TimeZone, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::error;
+use crate::error::Result;
+use crate::util::from_str;
+use crate::workflows::workflow::WorkflowId;
+
+/// A link that grants read-only WMS and WFS access to a workflow with a style until it expires,
+/// without a session. The link is signed with the server's secret, so it cannot be altered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShareLink {
+    pub workflow: WorkflowId,
+    pub style: String,
+    pub expires: DateTime<Utc>,
+}
+
+/// The parameters of a share link in the query string of a WMS or WFS request
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ShareParameters {
+    #[serde(alias = "STYLES")]
+    #[serde(default)]
+    pub styles: String,
+    /// The expiry in seconds since the Unix epoch
+    #[serde(alias = "EXPIRES")]
+    #[serde(deserialize_with = "from_str")]
+    pub expires: i64,
+    #[serde(alias = "SIGNATURE")]
+    pub signature: String,
+}
+
+/// A signed share link as returned to its creator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedShareLink {
+    pub workflow: WorkflowId,
+    pub expires: DateTime<Utc>,
+    /// The parameters to append to WMS and WFS requests of the workflow
+    pub query: String,
+}
+
+impl ShareLink {
+    /// The share link of the `workflow` that the `parameters` of a request describe
+    pub fn from_parameters(workflow: WorkflowId, parameters: &ShareParameters) -> Result<Self> {
+        Ok(Self {
+            workflow,
+            style: parameters.styles.clone(),
+            expires: Utc
+                .timestamp_opt(parameters.expires, 0)
+                .single()
+                .ok_or(error::Error::InvalidShareLink)?,
+        })
+    }
+
+    /// The hex encoded HMAC-SHA256 of the link's workflow, style and expiry
+    pub fn signature(&self, secret: &str) -> Result<String> {
+        if secret.is_empty() {
+            return Err(error::Error::ShareLinksDisabled);
+        }
+
+        let mut mac =
+            Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(self.message().as_bytes());
+
+        Ok(format!("{:x}", mac.finalize().into_bytes()))
+    }
+
+    /// Sign the link and return the parameters that requests have to include
+    pub fn sign(&self, secret: &str) -> Result<SignedShareLink> {
+        let query = serde_urlencoded::to_string(&[
+            ("styles", self.style.clone()),
+            ("expires", self.expires.timestamp().to_string()),
+            ("signature", self.signature(secret)?),
+        ])
+        .map_err(|_| error::Error::InvalidShareLink)?;
+
+        Ok(SignedShareLink {
+            workflow: self.workflow,
+            expires: self.expires,
+            query,
+        })
+    }
+
+    /// Check that the `signature` belongs to the link and that the link has not expired
+    pub fn verify(&self, signature: &str, secret: &str, now: DateTime<Utc>) -> Result<()> {
+        let expected = self.signature(secret)?;
+
+        if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            return Err(error::Error::InvalidShareLink);
+        }
+
+        if self.expires <= now {
+            return Err(error::Error::ShareLinkExpired {
+                expires: self.expires,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn message(&self) -> String {
+        // the style is last, so that it cannot contain a separator that shifts the other fields
+        format!(
+            "{}\n{}\n{}",
+            self.workflow,
+            self.expires.timestamp(),
+            self.style
+        )
+    }
+}
+
+/// Compare the bytes in time that only depends on their lengths, so that the comparison of
+/// signatures does not reveal the length of the matching prefix
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::identifiers::Identifier;
+
+    fn link() -> ShareLink {
+        ShareLink {
+            workflow: WorkflowId::new(),
+            style: "stretch:auto".to_string(),
+            expires: Utc.timestamp(1_600_000_000, 0),
+        }
+    }
+
+    #[test]
+    fn verify() {
+        let link = link();
+        let signature = link.signature("secret").unwrap();
+        let before = Utc.timestamp(1_599_999_999, 0);
+
+        assert!(link.verify(&signature, "secret", before).is_ok());
+        assert!(matches!(
+            link.verify(&signature, "other secret", before),
+            Err(error::Error::InvalidShareLink)
+        ));
+        assert!(matches!(
+            link.verify(&signature, "secret", link.expires),
+            Err(error::Error::ShareLinkExpired { .. })
+        ));
+
+        let restyled = ShareLink {
+            style: "default".to_string(),
+            ..link.clone()
+        };
+        assert!(restyled.verify(&signature, "secret", before).is_err());
+
+        let extended = ShareLink {
+            expires: Utc.timestamp(1_700_000_000, 0),
+            ..link
+        };
+        assert!(extended.verify(&signature, "secret", before).is_err());
+    }
+
+    #[test]
+    fn out_of_range_expiry() {
+        let parameters = ShareParameters {
+            styles: String::new(),
+            expires: i64::MAX,
+            signature: String::new(),
+        };
+
+        assert!(ShareLink::from_parameters(WorkflowId::new(), &parameters).is_err());
+    }
+
+    #[test]
+    fn disabled() {
+        assert!(matches!(
+            link().signature(""),
+            Err(error::Error::ShareLinksDisabled)
+        ));
+    }
+
+    #[test]
+    fn parameters_roundtrip() {
+        let link = link();
+        let signed = link.sign("secret").unwrap();
+
+        let parameters: ShareParameters = serde_urlencoded::from_str(&signed.query).unwrap();
+        let parsed = ShareLink::from_parameters(link.workflow, &parameters).unwrap();
+
+        assert_eq!(parsed, link);
+        assert!(parsed
+            .verify(
+                &parameters.signature,
+                "secret",
+                Utc.timestamp(1_500_000_000, 0)
+            )
+            .is_ok());
+    }
+}
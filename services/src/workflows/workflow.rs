@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::users::user::UserId;
+use crate::util::identifiers::Identifier;
 use geoengine_operators::engine::TypedOperator;
 
 identifier!(WorkflowId);
@@ -14,6 +16,17 @@ impl WorkflowId {
             ),
         }
     }
+
+    /// The id of a `workflow` that is private to the `owner`, which differs from the ids of the
+    /// same workflow of other users and of the public one
+    pub fn from_private_hash(workflow: &Workflow, owner: UserId) -> Self {
+        Self {
+            id: Uuid::new_v5(
+                &owner.uuid(),
+                serde_json::to_string(workflow).unwrap().as_bytes(),
+            ),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]